@@ -0,0 +1,221 @@
+//! `codex proto --json-rpc`: the protocol as newline-delimited JSON-RPC 2.0
+//! over stdio, the stable surface for editor integrations.
+//!
+//! Requests:
+//!
+//! - `submit`: params are an `Op`, e.g. `{"type": "user_input", "items":
+//!   [{"type": "text", "text": "..."}]}`.
+//! - `interrupt`: stops the running task; optional params
+//!   `{"replace_with": "..."}` start a new one right away.
+//! - `approve`: answers an approval request event, with the event's `id`:
+//!   `{"kind": "exec" | "patch", "id": "...", "decision": "approved" |
+//!   "approved_for_session" | "denied" | "abort"}`, or `{"kind":
+//!   "turn_diff", "id": "...", "approved": ["path", ...]}`.
+//!
+//! Each returns `{"id": "<submission id>"}`, the id of the events the
+//! submission leads to. Every event is sent as an `event` notification whose
+//! params are the event. Notifications from the client are carried out
+//! without a reply.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_core::Codex;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCRequest;
+use mcp_types::JSONRPCResponse;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::sync::Notify;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Params of `approve`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Approval {
+    Exec {
+        id: String,
+        decision: ReviewDecision,
+    },
+    Patch {
+        id: String,
+        decision: ReviewDecision,
+    },
+    TurnDiff {
+        id: String,
+        approved: Vec<PathBuf>,
+    },
+}
+
+impl From<Approval> for Op {
+    fn from(approval: Approval) -> Self {
+        match approval {
+            Approval::Exec { id, decision } => Op::ExecApproval { id, decision },
+            Approval::Patch { id, decision } => Op::PatchApproval { id, decision },
+            Approval::TurnDiff { id, approved } => Op::TurnDiffApproval { id, approved },
+        }
+    }
+}
+
+/// Params of `interrupt`.
+#[derive(Debug, Default, Deserialize)]
+struct Interrupt {
+    #[serde(default)]
+    replace_with: Option<String>,
+}
+
+/// Serve requests from stdin and stream the events of `codex` to stdout
+/// until stdin closes and the session ends, or `ctrl_c` fires.
+pub(crate) async fn run_main(codex: Arc<Codex>, ctrl_c: Arc<Notify>) {
+    let requests = {
+        let codex = codex.clone();
+        let ctrl_c = ctrl_c.clone();
+        async move {
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+            loop {
+                let line = tokio::select! {
+                    _ = ctrl_c.notified() => break,
+                    line = lines.next_line() => line,
+                };
+                let Ok(Some(line)) = line else {
+                    info!("stdin closed");
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<JSONRPCMessage>(&line) {
+                    Ok(message) => {
+                        if let Some(reply) = handle_message(&codex, message).await {
+                            write_message(&reply);
+                        }
+                    }
+                    Err(e) => error!("invalid JSON-RPC message: {e}"),
+                }
+            }
+        }
+    };
+
+    let events = async move {
+        loop {
+            let event = tokio::select! {
+                _ = ctrl_c.notified() => break,
+                event = codex.next_event() => event,
+            };
+            match event {
+                Ok(event) => match serde_json::to_value(&event) {
+                    Ok(params) => {
+                        write_message(&JSONRPCMessage::Notification(JSONRPCNotification {
+                            jsonrpc: JSONRPC_VERSION.to_string(),
+                            method: "event".to_string(),
+                            params: Some(params),
+                        }))
+                    }
+                    Err(e) => error!("failed to serialize event: {e}"),
+                },
+                Err(e) => {
+                    error!("{e:#}");
+                    break;
+                }
+            }
+        }
+    };
+
+    tokio::join!(requests, events);
+}
+
+/// Carry out `message`, returning the reply to a request.
+async fn handle_message(codex: &Codex, message: JSONRPCMessage) -> Option<JSONRPCMessage> {
+    match message {
+        JSONRPCMessage::Request(JSONRPCRequest {
+            id, method, params, ..
+        }) => Some(match call(codex, &method, params).await {
+            Ok(result) => JSONRPCMessage::Response(JSONRPCResponse {
+                id,
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                result,
+            }),
+            Err(error) => JSONRPCMessage::Error(JSONRPCError {
+                error,
+                id,
+                jsonrpc: JSONRPC_VERSION.to_string(),
+            }),
+        }),
+        JSONRPCMessage::Notification(JSONRPCNotification { method, params, .. }) => {
+            if let Err(e) = call(codex, &method, params).await {
+                warn!("notification `{method}` failed: {}", e.message);
+            }
+            None
+        }
+        _ => {
+            warn!("ignoring a JSON-RPC message that is not a request or notification");
+            None
+        }
+    }
+}
+
+async fn call(
+    codex: &Codex,
+    method: &str,
+    params: Option<Value>,
+) -> Result<Value, JSONRPCErrorError> {
+    let op = match method {
+        "submit" => parse_params(params)?,
+        "interrupt" => {
+            let Interrupt { replace_with } = match params {
+                Some(params) => parse_params(Some(params))?,
+                None => Interrupt::default(),
+            };
+            Op::Interrupt { replace_with }
+        }
+        "approve" => parse_params::<Approval>(params)?.into(),
+        _ => {
+            return Err(rpc_error(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{method}`"),
+            ));
+        }
+    };
+    let id = codex
+        .submit(op)
+        .await
+        .map_err(|e| rpc_error(INTERNAL_ERROR, e.to_string()))?;
+    Ok(json!({ "id": id }))
+}
+
+fn parse_params<T: DeserializeOwned>(params: Option<Value>) -> Result<T, JSONRPCErrorError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|e| rpc_error(INVALID_PARAMS, format!("invalid params: {e}")))
+}
+
+fn rpc_error(code: i64, message: String) -> JSONRPCErrorError {
+    JSONRPCErrorError {
+        code,
+        data: None,
+        message,
+    }
+}
+
+#[allow(clippy::print_stdout)]
+fn write_message(message: &JSONRPCMessage) {
+    match serde_json::to_string(message) {
+        Ok(line) => println!("{line}"),
+        Err(e) => error!("failed to serialize JSON-RPC message: {e}"),
+    }
+}
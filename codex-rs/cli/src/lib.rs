@@ -1,6 +1,8 @@
 pub mod debug_sandbox;
 mod exit_status;
+mod json_rpc;
 pub mod proto;
+pub mod serve;
 
 use clap::Parser;
 use codex_common::CliConfigOverrides;
@@ -1,14 +1,30 @@
+use clap::CommandFactory;
 use clap::Parser;
+use clap_complete::Shell;
+use clap_complete::engine::ArgValueCompleter;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::CompleteEnv;
+use clap_complete::env::Shells;
 use codex_cli::LandlockCommand;
 use codex_cli::SeatbeltCommand;
 use codex_cli::proto;
+use codex_cli::serve;
+use codex_cli::serve::ServeCli;
 use codex_common::CliConfigOverrides;
 use codex_exec::Cli as ExecCli;
+use codex_exec::TestFix;
 use codex_tui::Cli as TuiCli;
+use codex_tui::ReplayTarget;
+use codex_tui::ResumeTarget;
+use std::ffi::OsStr;
 use std::path::PathBuf;
 
 use crate::proto::ProtoCli;
 
+/// Environment variable through which the completion script asks `codex`
+/// for candidates; the default of [`CompleteEnv`].
+const COMPLETE_VAR: &str = "COMPLETE";
+
 /// Codex CLI
 ///
 /// If no subcommand is specified, options will be forwarded to the interactive CLI.
@@ -36,6 +52,19 @@ enum Subcommand {
     #[clap(visible_alias = "e")]
     Exec(ExecCli),
 
+    /// Run a custom prompt from `~/.codex/prompts` non-interactively.
+    Run(RunArgs),
+
+    /// Run the tests and have Codex fix the failures, non-interactively,
+    /// until they pass.
+    TestFix(TestFixArgs),
+
+    /// Continue an earlier session; without an id, pick one from a list.
+    Resume(ResumeArgs),
+
+    /// Play back a recorded session in the TUI, without running anything.
+    Replay(ReplayArgs),
+
     /// Experimental: run Codex as an MCP server.
     Mcp,
 
@@ -43,14 +72,159 @@ enum Subcommand {
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
 
+    /// Serve the protocol over WebSocket, one session per connection.
+    Serve(ServeCli),
+
     /// GitHub Copilot authentication
     #[clap(visible_alias = "c")]
     Copilot(CopilotArgs),
 
+    /// Sign in with your ChatGPT account instead of setting OPENAI_API_KEY.
+    Login,
+
+    /// Move saved credentials between machines.
+    Auth(AuthArgs),
+
+    /// Inspect recorded sessions.
+    Sessions(SessionsArgs),
+
+    /// Review the commands and file writes recorded in the audit log.
+    Audit(AuditArgs),
+
+    /// Delete the sessions and audit logs older than the `[retention]`
+    /// table allows.
+    Gc(GcArgs),
+
+    /// List or revoke the sandbox grants remembered for this workspace.
+    Permissions(PermissionsArgs),
+
+    /// List, edit or forget the facts remembered about this project.
+    Memory(MemoryArgs),
+
+    /// Detect the project's build and test commands and write a starter
+    /// `AGENTS.md` and `.codex/config.toml`.
+    Init(InitArgs),
+
+    /// Propose additions to the project's `AGENTS.md` from what a finished
+    /// session learned.
+    Distill(DistillArgs),
+
+    /// Explain a file or a top-level definition, printing Markdown.
+    Explain(ExplainArgs),
+
+    /// Check the config, provider, credentials, sandbox and git, with hints
+    /// on fixing what fails.
+    Doctor,
+
+    /// Inspect the configured model providers.
+    Providers(ProvidersArgs),
+
+    /// Run a suite of tasks with one or more models and compare the results.
+    Bench(BenchArgs),
+
+    /// Print a shell completion script.
+    Completion(CompletionArgs),
+
     /// Internal debugging commands.
     Debug(DebugArgs),
 }
 
+#[derive(Debug, Parser)]
+struct RunArgs {
+    /// Name of the prompt (the file name without `.md`).
+    name: String,
+
+    /// Arguments substituted for `$ARGUMENTS`; the first one also names the
+    /// file substituted for `$FILE`.
+    #[arg(trailing_var_arg = true)]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+struct TestFixArgs {
+    /// Test command, run with the shell. Defaults to `test` in the
+    /// `[checks]` table.
+    #[arg(long = "test-command", value_name = "COMMAND")]
+    test_command: Option<String>,
+
+    /// Fix attempts before giving up.
+    #[arg(long = "max-iterations", default_value_t = 5)]
+    max_iterations: usize,
+
+    /// Options of `codex exec`; its prompt, if any, is passed on with each
+    /// failure.
+    #[clap(flatten)]
+    exec: ExecCli,
+}
+
+#[derive(Debug, Parser)]
+struct BenchArgs {
+    /// Directory of task files (`*.toml`), each with a `prompt`, a `check`
+    /// command and an optional `fixture` directory.
+    #[arg(long, value_name = "DIR")]
+    suite: PathBuf,
+
+    /// Model to run the suite with, as `model` or `model@provider`; repeat
+    /// to compare several. Defaults to the configured model.
+    #[arg(long = "model", short = 'm', value_name = "MODEL")]
+    models: Vec<codex_core::bench::BenchTarget>,
+}
+
+#[derive(Debug, Parser)]
+struct CompletionArgs {
+    /// Shell to complete for. Source the output from its startup file, e.g.
+    /// `source <(codex completion bash)` in `~/.bashrc`.
+    shell: Shell,
+}
+
+#[derive(Debug, Parser)]
+struct InitArgs {
+    /// Accept the detected commands and write the files without asking.
+    #[arg(long, short = 'y', default_value_t = false)]
+    yes: bool,
+
+    /// Overwrite `AGENTS.md` and `.codex/config.toml` if they exist.
+    #[arg(long, default_value_t = false)]
+    force: bool,
+}
+
+#[derive(Debug, Parser)]
+struct DistillArgs {
+    /// Session id (or a unique prefix of it).
+    #[arg(add = ArgValueCompleter::new(complete_session_ids))]
+    id: String,
+
+    /// Write the proposed additions without asking.
+    #[arg(long, short = 'y', default_value_t = false)]
+    yes: bool,
+}
+
+#[derive(Debug, Parser)]
+struct ExplainArgs {
+    /// Path of a file, relative to the working directory, or the name of a
+    /// function, type or class defined in the project.
+    target: String,
+}
+
+#[derive(Debug, Parser)]
+struct ResumeArgs {
+    /// Session id (or a unique prefix of it).
+    #[arg(add = ArgValueCompleter::new(complete_session_ids))]
+    id: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+struct ReplayArgs {
+    /// Session id (or a unique prefix of it).
+    #[arg(add = ArgValueCompleter::new(complete_session_ids))]
+    id: String,
+
+    /// Playback speed; 2 plays twice as fast. Change it with +/- while
+    /// playing.
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+}
+
 #[derive(Debug, Parser)]
 struct CopilotArgs {
     #[command(subcommand)]
@@ -60,11 +234,234 @@ struct CopilotArgs {
 #[derive(Debug, clap::Subcommand)]
 enum CopilotCommand {
     /// Authenticate with GitHub Copilot using device flow
-    Auth,
+    Auth(CopilotAuthArgs),
     /// Check current Copilot token status
     Status,
 }
 
+#[derive(Debug, Parser)]
+struct CopilotAuthArgs {
+    /// Sign in with a GitHub personal access token read from stdin, or from
+    /// GITHUB_TOKEN when stdin is a terminal, instead of the device flow.
+    #[arg(long)]
+    with_token: bool,
+}
+
+#[derive(Debug, Parser)]
+struct AuthArgs {
+    #[command(subcommand)]
+    cmd: AuthCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AuthCommand {
+    /// Write the credentials saved by `codex login` and `codex copilot auth`
+    /// to a passphrase-encrypted file.
+    Export(AuthFileArgs),
+    /// Save the credentials from a file written by `codex auth export`,
+    /// replacing the saved ones.
+    Import(AuthFileArgs),
+}
+
+#[derive(Debug, Parser)]
+struct AuthFileArgs {
+    /// The encrypted credentials file. The passphrase is read from
+    /// `CODEX_AUTH_PASSPHRASE`, or asked for.
+    #[arg(long, short = 'f')]
+    file: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+struct SessionsArgs {
+    #[command(subcommand)]
+    cmd: SessionsCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum SessionsCommand {
+    /// List recent sessions with their titles, newest first.
+    List(ListSessionsArgs),
+
+    /// List the sessions whose messages contain all the given words.
+    Search(SearchSessionsArgs),
+
+    /// Export a session transcript as Markdown, HTML or JSON.
+    Export(ExportArgs),
+
+    /// Write a session to a single file, with likely secrets redacted, that
+    /// others can open with `codex sessions view`.
+    Share(ShareArgs),
+
+    /// Print a shared session bundle.
+    View(ViewArgs),
+}
+
+#[derive(Debug, Parser)]
+struct ListSessionsArgs {
+    #[clap(flatten)]
+    filter: SessionFilterArgs,
+}
+
+#[derive(Debug, Parser)]
+struct SearchSessionsArgs {
+    /// Words to look for, in any case and order.
+    #[arg(required = true)]
+    words: Vec<String>,
+
+    #[clap(flatten)]
+    filter: SessionFilterArgs,
+}
+
+#[derive(Debug, Parser)]
+struct SessionFilterArgs {
+    /// Maximum number of sessions to list.
+    #[arg(long, short = 'n', default_value_t = 20)]
+    limit: usize,
+
+    /// Only sessions run in this directory or below it.
+    #[arg(long)]
+    cwd: Option<PathBuf>,
+
+    /// Only sessions active since this date (YYYY-MM-DD, UTC).
+    #[arg(long, value_parser = codex_core::session_store::parse_date)]
+    since: Option<std::time::SystemTime>,
+}
+
+#[derive(Debug, Parser)]
+struct ExportArgs {
+    /// Session id (or a unique prefix of it), as shown when the session starts.
+    #[arg(add = ArgValueCompleter::new(complete_session_ids))]
+    id: String,
+
+    /// Output format. Defaults to the extension of `--output`, else `md`.
+    #[arg(long, short = 'f', value_parser = ["md", "html", "json"])]
+    format: Option<String>,
+
+    /// Write the transcript to this file instead of stdout.
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct ShareArgs {
+    /// Session id (or a unique prefix of it), as shown when the session starts.
+    #[arg(add = ArgValueCompleter::new(complete_session_ids))]
+    id: String,
+
+    /// Where to write the bundle. Defaults to `<id>.codex` in the current
+    /// directory.
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct ViewArgs {
+    /// Bundle written by `codex sessions share`.
+    bundle: PathBuf,
+
+    /// Output format.
+    #[arg(long, short = 'f', value_parser = ["md", "html", "json"], default_value = "md")]
+    format: String,
+}
+
+#[derive(Debug, Parser)]
+struct ProvidersArgs {
+    #[command(subcommand)]
+    cmd: ProvidersCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ProvidersCommand {
+    /// Ping each provider and report whether it is reachable, whether it
+    /// accepts its key, its median latency and the rate-limit headroom left.
+    Status(ProvidersStatusArgs),
+}
+
+#[derive(Debug, Parser)]
+struct ProvidersStatusArgs {
+    /// Providers to check, by their key in `model_providers`. Defaults to
+    /// all of them.
+    ids: Vec<String>,
+
+    /// Requests to send to each provider.
+    #[arg(long, default_value_t = codex_core::provider_status::DEFAULT_SAMPLES)]
+    samples: usize,
+}
+
+#[derive(Debug, Parser)]
+struct AuditArgs {
+    #[command(subcommand)]
+    cmd: AuditCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AuditCommand {
+    /// Print the audit log of a session.
+    Show(AuditShowArgs),
+}
+
+#[derive(Debug, Parser)]
+struct GcArgs {
+    /// List what would be deleted without deleting it.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Parser)]
+struct AuditShowArgs {
+    /// Session id (or a unique prefix of it).
+    #[arg(long, add = ArgValueCompleter::new(complete_session_ids))]
+    session: String,
+
+    /// Print the raw JSON records instead of one summary line per record.
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+#[derive(Debug, Parser)]
+struct PermissionsArgs {
+    #[command(subcommand)]
+    cmd: PermissionsCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum PermissionsCommand {
+    /// Print the sandbox grants remembered for the workspace of the current
+    /// directory, numbered.
+    List,
+
+    /// Forget a remembered sandbox grant.
+    Revoke {
+        /// Number of the grant, as printed by `codex permissions list`.
+        number: usize,
+    },
+}
+
+#[derive(Debug, Parser)]
+struct MemoryArgs {
+    #[command(subcommand)]
+    cmd: MemoryCommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum MemoryCommand {
+    /// Print the memories of the project of the current directory, with the
+    /// first line of each.
+    List,
+
+    /// Open a memory in `$VISUAL` / `$EDITOR`, creating it if needed.
+    Edit {
+        /// Name of the memory, as printed by `codex memory list`.
+        name: String,
+    },
+
+    /// Delete a memory.
+    Forget {
+        /// Name of the memory, as printed by `codex memory list`.
+        name: String,
+    },
+}
+
 #[derive(Debug, Parser)]
 struct DebugArgs {
     #[command(subcommand)]
@@ -84,6 +481,10 @@ enum DebugCommand {
 struct ReplProto {}
 
 fn main() -> anyhow::Result<()> {
+    // Answers the completion requests made by the script that `codex
+    // completion` prints, then exits.
+    CompleteEnv::with_factory(command_with_completers).complete();
+
     codex_linux_sandbox::run_with_sandbox(|codex_linux_sandbox_exe| async move {
         cli_main(codex_linux_sandbox_exe).await?;
         Ok(())
@@ -103,6 +504,38 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut exec_cli.config_overrides, cli.config_overrides);
             codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Run(run_args)) => {
+            let mut exec_cli = custom_prompt_exec_cli(run_args)?;
+            prepend_config_flags(&mut exec_cli.config_overrides, cli.config_overrides);
+            codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
+        }
+        Some(Subcommand::TestFix(test_fix_args)) => {
+            let mut exec_cli = test_fix_args.exec;
+            exec_cli.test_fix = Some(TestFix {
+                command: test_fix_args.test_command,
+                max_iterations: test_fix_args.max_iterations,
+            });
+            prepend_config_flags(&mut exec_cli.config_overrides, cli.config_overrides);
+            codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
+        }
+        Some(Subcommand::Resume(resume_args)) => {
+            let mut tui_cli = cli.interactive;
+            prepend_config_flags(&mut tui_cli.config_overrides, cli.config_overrides);
+            tui_cli.resume = Some(match resume_args.id {
+                Some(id) => ResumeTarget::Session(id),
+                None => ResumeTarget::Picker,
+            });
+            codex_tui::run_main(tui_cli, codex_linux_sandbox_exe)?;
+        }
+        Some(Subcommand::Replay(replay_args)) => {
+            let mut tui_cli = cli.interactive;
+            prepend_config_flags(&mut tui_cli.config_overrides, cli.config_overrides);
+            tui_cli.replay = Some(ReplayTarget {
+                id: replay_args.id,
+                speed: replay_args.speed,
+            });
+            codex_tui::run_main(tui_cli, codex_linux_sandbox_exe)?;
+        }
         Some(Subcommand::Mcp) => {
             codex_mcp_server::run_main(codex_linux_sandbox_exe).await?;
         }
@@ -110,9 +543,56 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut proto_cli.config_overrides, cli.config_overrides);
             proto::run_main(proto_cli).await?;
         }
+        Some(Subcommand::Serve(mut serve_cli)) => {
+            prepend_config_flags(&mut serve_cli.config_overrides, cli.config_overrides);
+            serve::run_main(serve_cli, codex_linux_sandbox_exe).await?;
+        }
         Some(Subcommand::Copilot(copilot_args)) => {
             run_copilot_command(copilot_args, cli.config_overrides).await?;
         }
+        Some(Subcommand::Login) => run_login(cli.config_overrides).await?,
+        Some(Subcommand::Auth(auth_args)) => transfer_credentials(auth_args)?,
+        Some(Subcommand::Sessions(sessions_args)) => match sessions_args.cmd {
+            SessionsCommand::List(list_args) => {
+                list_sessions(list_args.filter, None, cli.config_overrides)?;
+            }
+            SessionsCommand::Search(search_args) => {
+                let words = search_args.words.join(" ");
+                list_sessions(search_args.filter, Some(words), cli.config_overrides)?;
+            }
+            SessionsCommand::Export(export_args) => export_session(export_args)?,
+            SessionsCommand::Share(share_args) => share_session(share_args)?,
+            SessionsCommand::View(view_args) => view_session_bundle(view_args)?,
+        },
+        Some(Subcommand::Audit(audit_args)) => match audit_args.cmd {
+            AuditCommand::Show(show_args) => show_audit_log(show_args)?,
+        },
+        Some(Subcommand::Gc(gc_args)) => collect_garbage(gc_args, cli.config_overrides)?,
+        Some(Subcommand::Permissions(permissions_args)) => {
+            manage_permissions(permissions_args.cmd)?;
+        }
+        Some(Subcommand::Memory(memory_args)) => manage_memory(memory_args.cmd)?,
+        Some(Subcommand::Init(init_args)) => init_project(init_args)?,
+        Some(Subcommand::Distill(distill_args)) => {
+            distill_session(distill_args, cli.config_overrides).await?;
+        }
+        Some(Subcommand::Explain(explain_args)) => {
+            explain(explain_args, cli.config_overrides).await?;
+        }
+        Some(Subcommand::Doctor) => {
+            run_doctor(cli.config_overrides, codex_linux_sandbox_exe).await?;
+        }
+        Some(Subcommand::Providers(providers_args)) => match providers_args.cmd {
+            ProvidersCommand::Status(status_args) => {
+                providers_status(status_args, cli.config_overrides).await?;
+            }
+        },
+        Some(Subcommand::Bench(bench_args)) => {
+            run_bench(bench_args, cli.config_overrides).await?;
+        }
+        Some(Subcommand::Completion(completion_args)) => {
+            print_completion_script(completion_args.shell)?;
+        }
         Some(Subcommand::Debug(debug_args)) => match debug_args.cmd {
             DebugCommand::Seatbelt(mut seatbelt_cli) => {
                 prepend_config_flags(&mut seatbelt_cli.config_overrides, cli.config_overrides);
@@ -136,19 +616,635 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
     Ok(())
 }
 
-async fn run_copilot_command(args: CopilotArgs, config_overrides: codex_common::CliConfigOverrides) -> anyhow::Result<()> {
+async fn run_copilot_command(
+    args: CopilotArgs,
+    config_overrides: codex_common::CliConfigOverrides,
+) -> anyhow::Result<()> {
     match args.cmd {
-        CopilotCommand::Auth => {
+        CopilotCommand::Auth(CopilotAuthArgs { with_token: true }) => {
+            codex_core::copilot::run_copilot_token_auth_command(config_overrides).await?;
+        }
+        CopilotCommand::Auth(CopilotAuthArgs { with_token: false }) => {
             codex_core::copilot::run_copilot_auth_command(config_overrides).await?;
         }
         CopilotCommand::Status => {
             codex_core::copilot::run_copilot_status_command().await?;
         }
     }
-    
+
+    Ok(())
+}
+
+#[allow(clippy::print_stdout, clippy::print_stderr)]
+async fn run_login(config_overrides: CliConfigOverrides) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::protocol::EventMsg;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())?;
+    let (tx_event, rx_event) = async_channel::unbounded();
+    let login =
+        tokio::spawn(async move { codex_core::chatgpt_auth::run_login(&config, tx_event).await });
+
+    while let Ok(event) = rx_event.recv().await {
+        match event.msg {
+            EventMsg::ChatGptAuthStarted(started) => {
+                println!(
+                    "Sign in with your ChatGPT account in the browser. If it did not open, visit:"
+                );
+                println!("\n  {}\n", started.auth_url);
+                println!("Waiting for you to finish signing in...");
+            }
+            EventMsg::ChatGptAuthComplete(complete) if complete.success => {
+                println!("{}", complete.message);
+            }
+            EventMsg::ChatGptAuthComplete(complete) => {
+                eprintln!("{}", complete.message);
+                std::process::exit(1);
+            }
+            _ => {}
+        }
+    }
+    login.await??;
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn transfer_credentials(args: AuthArgs) -> anyhow::Result<()> {
+    use codex_core::credential_bundle;
+    use codex_core::credential_bundle::BundleContents;
+
+    let describe = |contents: BundleContents| {
+        let mut names = Vec::new();
+        if contents.chatgpt {
+            names.push("ChatGPT login");
+        }
+        if contents.copilot {
+            names.push("Copilot token");
+        }
+        names.join(" and ")
+    };
+    let codex_home = codex_core::config::find_codex_home()?;
+    match args.cmd {
+        AuthCommand::Export(AuthFileArgs { file }) => {
+            let passphrase = passphrase(true)?;
+            let (bundle, contents) = credential_bundle::export(&codex_home, &passphrase)?;
+            std::fs::write(&file, bundle)?;
+            println!("Exported the {} to {}", describe(contents), file.display());
+        }
+        AuthCommand::Import(AuthFileArgs { file }) => {
+            let bundle = std::fs::read(&file)?;
+            let passphrase = passphrase(false)?;
+            let contents = credential_bundle::import(&codex_home, &bundle, &passphrase)?;
+            println!(
+                "Imported the {} from {}",
+                describe(contents),
+                file.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The passphrase from `CODEX_AUTH_PASSPHRASE`, else asked for on the
+/// terminal (twice when `confirm` is set).
+fn passphrase(confirm: bool) -> anyhow::Result<String> {
+    if let Ok(passphrase) = std::env::var("CODEX_AUTH_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("Passphrase: ")?;
+    if confirm && rpassword::prompt_password("Repeat the passphrase: ")? != passphrase {
+        anyhow::bail!("the passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+#[allow(clippy::print_stdout)]
+fn list_sessions(
+    filter: SessionFilterArgs,
+    text: Option<String>,
+    config_overrides: CliConfigOverrides,
+) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::session_store::SessionQuery;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())?;
+    let cwd = match filter.cwd {
+        Some(cwd) => Some(std::path::absolute(cwd)?),
+        None => None,
+    };
+    let query = SessionQuery {
+        cwd,
+        since: filter.since,
+        text,
+        limit: filter.limit,
+    };
+    for session in codex_core::session_store::open(&config)?.query(&query)? {
+        // Ids are unique well before 8 characters, and any prefix works as
+        // an argument to `codex sessions export` and `codex resume`.
+        let id = session.id.get(..8).unwrap_or(&session.id);
+        let description = session
+            .title
+            .or_else(|| {
+                let prompt = session.first_prompt?;
+                prompt.lines().next().map(str::to_string)
+            })
+            .unwrap_or_default();
+        let cwd = session
+            .cwd
+            .map(|cwd| cwd.display().to_string())
+            .unwrap_or_default();
+        println!("{id}  {description:<60}  {cwd}");
+    }
+    Ok(())
+}
+
+fn export_session(args: ExportArgs) -> anyhow::Result<()> {
+    use codex_core::session_export::ExportFormat;
+    use codex_core::session_export::Transcript;
+
+    let format = match (&args.format, &args.output) {
+        (Some(format), _) => format.parse().map_err(anyhow::Error::msg)?,
+        (None, Some(output)) => ExportFormat::from_path(output).unwrap_or(ExportFormat::Markdown),
+        (None, None) => ExportFormat::Markdown,
+    };
+    let codex_home = codex_core::config::find_codex_home()?;
+    let path = codex_core::rollout::find_rollout(&codex_home, &args.id)?;
+    let document = Transcript::load(&path)?.render(format);
+    match args.output {
+        Some(output) => std::fs::write(output, document)?,
+        #[allow(clippy::print_stdout)]
+        None => print!("{document}"),
+    }
     Ok(())
 }
 
+#[allow(clippy::print_stderr)]
+fn share_session(args: ShareArgs) -> anyhow::Result<()> {
+    use codex_core::session_export::BUNDLE_EXTENSION;
+    use codex_core::session_export::Bundle;
+    use codex_core::session_export::Transcript;
+
+    let codex_home = codex_core::config::find_codex_home()?;
+    let path = codex_core::rollout::find_rollout(&codex_home, &args.id)?;
+    let transcript = Transcript::load(&path)?;
+    let output = args
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{BUNDLE_EXTENSION}", transcript.id)));
+    let bundle = Bundle::new(&transcript);
+    bundle.write(&output)?;
+
+    eprintln!("Wrote {}", output.display());
+    if !bundle.redacted.is_empty() {
+        let redacted: Vec<String> = bundle
+            .redacted
+            .iter()
+            .map(|secret| format!("{} {}", secret.count, secret.kind))
+            .collect();
+        eprintln!("Redacted {}", redacted.join(", "));
+    }
+    eprintln!(
+        "Redaction only catches well-known credential formats; review the bundle before sharing it."
+    );
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn view_session_bundle(args: ViewArgs) -> anyhow::Result<()> {
+    use codex_core::session_export::Bundle;
+
+    let format = args.format.parse().map_err(anyhow::Error::msg)?;
+    let bundle = Bundle::read(&args.bundle)?;
+    print!("{}", bundle.transcript.render(format));
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn show_audit_log(args: AuditShowArgs) -> anyhow::Result<()> {
+    let codex_home = codex_core::config::find_codex_home()?;
+    let path = codex_core::audit::find_audit_log(&codex_home, &args.session)?;
+    for record in codex_core::audit::read_audit_log(&path)? {
+        if args.json {
+            println!("{}", serde_json::to_string(&record)?);
+        } else {
+            println!("{record}");
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn collect_garbage(args: GcArgs, config_overrides: CliConfigOverrides) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())?;
+    if !config.retention.is_enabled() {
+        println!(
+            "No retention policy: set `sessions_days` or `audit_days` in the `[retention]` table of config.toml."
+        );
+        return Ok(());
+    }
+    let report =
+        codex_core::retention::purge(&config.codex_home, &config.retention, None, args.dry_run)?;
+    if args.dry_run {
+        for path in report.sessions.iter().chain(&report.audit_logs) {
+            println!("{}", path.display());
+        }
+    }
+    println!(
+        "{} {} sessions and {} audit logs ({} KiB).",
+        if args.dry_run {
+            "Would delete"
+        } else {
+            "Deleted"
+        },
+        report.sessions.len(),
+        report.audit_logs.len(),
+        report.bytes.div_ceil(1024)
+    );
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn manage_permissions(cmd: PermissionsCommand) -> anyhow::Result<()> {
+    use codex_core::project_permissions;
+
+    let codex_home = codex_core::config::find_codex_home()?;
+    let workspace = project_permissions::workspace_root(&std::env::current_dir()?);
+    match cmd {
+        PermissionsCommand::List => {
+            let grants = project_permissions::load_grants(&codex_home, &workspace)?;
+            if grants.is_empty() {
+                println!("No sandbox grants remembered for {}.", workspace.display());
+            }
+            for (i, access) in grants.iter().enumerate() {
+                println!("{:>3}  {access}", i + 1);
+            }
+        }
+        PermissionsCommand::Revoke { number } => {
+            let index = number
+                .checked_sub(1)
+                .ok_or_else(|| anyhow::anyhow!("grants are numbered from 1"))?;
+            let access = project_permissions::revoke_grant(&codex_home, &workspace, index)?;
+            println!("Revoked {access} for {}.", workspace.display());
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn manage_memory(cmd: MemoryCommand) -> anyhow::Result<()> {
+    use codex_core::project_init::project_root;
+    use codex_core::project_memory;
+
+    let root = project_root(&std::env::current_dir()?);
+    match cmd {
+        MemoryCommand::List => {
+            let memories = project_memory::list(&root)?;
+            if memories.is_empty() {
+                println!("No memories for {}.", root.display());
+            }
+            for memory in memories {
+                let summary = memory.content.lines().next().unwrap_or_default();
+                println!("{:<24}  {summary}", memory.name);
+            }
+        }
+        MemoryCommand::Edit { name } => {
+            let path = project_memory::memory_path(&root, &name)?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            codex_tui::edit_file(&path)?;
+        }
+        MemoryCommand::Forget { name } => {
+            project_memory::forget(&root, &name)?;
+            println!("Forgot {name}.");
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+async fn distill_session(
+    args: DistillArgs,
+    config_overrides: CliConfigOverrides,
+) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())?;
+    let path = codex_core::rollout::find_rollout(&config.codex_home, &args.id)?;
+    println!(
+        "Asking {} what the session adds to AGENTS.md...",
+        config.model
+    );
+    let Some(proposal) = codex_core::distill::propose(&config, &path).await? else {
+        println!("Nothing to add.");
+        return Ok(());
+    };
+    println!("\n{}", proposal.diff());
+    let confirmed = args.yes
+        || matches!(
+            prompt(&format!("Write {}? [y/N] ", proposal.path.display()))?.as_str(),
+            "y" | "Y"
+        );
+    if confirmed {
+        proposal.apply()?;
+        println!("Wrote {}.", proposal.path.display());
+    }
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+async fn explain(args: ExplainArgs, config_overrides: CliConfigOverrides) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())?;
+    let explanation = codex_core::explain::explain(&config, &args.target).await?;
+    println!("{}", explanation.trim_end());
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+async fn run_bench(args: BenchArgs, config_overrides: CliConfigOverrides) -> anyhow::Result<()> {
+    use codex_core::bench;
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let tasks = bench::load_suite(&args.suite)?;
+    let targets = if args.models.is_empty() {
+        let config =
+            Config::load_with_cli_overrides(cli_kv_overrides.clone(), ConfigOverrides::default())?;
+        vec![bench::BenchTarget {
+            model: config.model,
+            provider: Some(config.model_provider_id),
+        }]
+    } else {
+        args.models
+    };
+
+    let mut results = Vec::new();
+    for target in &targets {
+        for task in &tasks {
+            println!("Running {} with {target}...", task.name);
+            let result = bench::run_task(task, target, cli_kv_overrides.clone()).await;
+            println!(
+                "  {} in {:.1}s",
+                if result.passed { "passed" } else { "failed" },
+                result.duration.as_secs_f64()
+            );
+            results.push(result);
+        }
+    }
+    println!("\n{}", bench::render_report(&results));
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+fn init_project(args: InitArgs) -> anyhow::Result<()> {
+    use codex_core::config::PROJECT_CONFIG_FILE;
+    use codex_core::project_init;
+
+    let root = project_init::project_root(&std::env::current_dir()?);
+    let mut detection = project_init::detect(&root);
+    println!("Project root: {}", root.display());
+    if detection.stacks.is_empty() {
+        println!("No known build system found.");
+    }
+    for stack in &detection.stacks {
+        match stack.language {
+            Some(language) => println!("Detected {language} ({})", stack.build_system),
+            None => println!("Detected {}", stack.build_system),
+        }
+    }
+
+    if !args.yes {
+        println!(
+            "\nConfirm each check command: Enter keeps it, `-` drops it, anything else replaces it."
+        );
+        let checks = &mut detection.checks;
+        for (kind, command) in [
+            ("format", &mut checks.format),
+            ("build", &mut checks.build),
+            ("lint", &mut checks.lint),
+            ("test", &mut checks.test),
+        ] {
+            let answer = prompt(&format!(
+                "{kind} [{}]: ",
+                command.as_deref().unwrap_or("none")
+            ))?;
+            match answer.as_str() {
+                "" => {}
+                "-" => *command = None,
+                answer => *command = Some(answer.to_string()),
+            }
+        }
+    }
+
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Project".to_string());
+    let files = [
+        (
+            root.join("AGENTS.md"),
+            project_init::agents_md(&name, &detection),
+        ),
+        (
+            root.join(PROJECT_CONFIG_FILE),
+            project_init::project_config(&detection.checks),
+        ),
+    ];
+    for (path, contents) in files {
+        if path.exists() && !args.force {
+            println!(
+                "Skipped {}: it exists (use --force to overwrite).",
+                path.display()
+            );
+            continue;
+        }
+        let confirmed = args.yes
+            || matches!(
+                prompt(&format!("Write {}? [Y/n] ", path.display()))?.as_str(),
+                "" | "y" | "Y"
+            );
+        if !confirmed {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Print `question` and read one line of the answer from stdin.
+#[allow(clippy::print_stdout)]
+fn prompt(question: &str) -> anyhow::Result<String> {
+    use std::io::Write;
+
+    print!("{question}");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+#[allow(clippy::print_stdout)]
+async fn run_doctor(
+    config_overrides: CliConfigOverrides,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::doctor::Status;
+
+    let config = config_overrides
+        .parse_overrides()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+        .and_then(|cli_kv_overrides| {
+            Config::load_with_cli_overrides(
+                cli_kv_overrides,
+                ConfigOverrides {
+                    codex_linux_sandbox_exe: codex_linux_sandbox_exe.clone(),
+                    ..Default::default()
+                },
+            )
+        });
+    let checks = codex_core::doctor::run_checks(config, codex_linux_sandbox_exe.as_deref()).await;
+    for check in &checks {
+        println!("{check}");
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    if failed > 0 {
+        println!("\n{failed} check(s) failed.");
+        std::process::exit(1);
+    }
+    println!("\nAll checks passed.");
+    Ok(())
+}
+
+#[allow(clippy::print_stdout)]
+async fn providers_status(
+    args: ProvidersStatusArgs,
+    config_overrides: CliConfigOverrides,
+) -> anyhow::Result<()> {
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::provider_status;
+
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())?;
+    let statuses = provider_status::check_providers(&config, &args.ids, args.samples).await?;
+    print!("{}", provider_status::render_report(&statuses));
+    Ok(())
+}
+
+/// The CLI definition with completers for the values clap cannot know
+/// statically: profile names and session ids.
+fn command_with_completers() -> clap::Command {
+    let profile = |arg: clap::Arg| arg.add(ArgValueCompleter::new(complete_profiles));
+    MultitoolCli::command()
+        .mut_arg("config_profile", profile)
+        .mut_subcommand("exec", |exec| exec.mut_arg("config_profile", profile))
+}
+
+/// Print the script that registers completions for `shell`. It calls back
+/// into `codex` for every completion, so profile names and session ids are
+/// current.
+fn print_completion_script(shell: Shell) -> anyhow::Result<()> {
+    let shells = Shells::builtins();
+    let Some(completer) = shells.completer(&shell.to_string()) else {
+        anyhow::bail!("completion is not supported for {shell}");
+    };
+    let mut stdout = std::io::stdout().lock();
+    completer.write_registration(COMPLETE_VAR, "codex", "codex", "codex", &mut stdout)?;
+    Ok(())
+}
+
+fn complete_profiles(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(codex_home) = codex_core::config::find_codex_home() else {
+        return Vec::new();
+    };
+    let current = current.to_string_lossy();
+    codex_core::config::profile_names(&codex_home)
+        .into_iter()
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Ids of recent sessions, described by their title or first prompt.
+fn complete_session_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    const MAX_SESSIONS: usize = 50;
+    let Ok(codex_home) = codex_core::config::find_codex_home() else {
+        return Vec::new();
+    };
+    let current = current.to_string_lossy();
+    let sessions =
+        codex_core::rollout::list_sessions(&codex_home, MAX_SESSIONS).unwrap_or_default();
+    sessions
+        .into_iter()
+        .filter(|session| session.id.starts_with(current.as_ref()))
+        .map(|session| {
+            let help = match session.title {
+                Some(title) => title,
+                None => {
+                    let prompt = session.first_prompt.unwrap_or_default();
+                    prompt.lines().next().unwrap_or_default().to_string()
+                }
+            };
+            CompletionCandidate::new(session.id).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// Expand the named custom prompt and build the `exec` invocation that sends
+/// it to the model.
+fn custom_prompt_exec_cli(args: RunArgs) -> anyhow::Result<ExecCli> {
+    let codex_home = codex_core::config::find_codex_home()?;
+    let dir = codex_core::custom_prompts::prompts_dir(&codex_home);
+    let Some(prompt) = codex_core::custom_prompts::find_prompt(&dir, &args.name) else {
+        anyhow::bail!("no prompt named `{}` in {}", args.name, dir.display());
+    };
+    let cwd = std::env::current_dir()?;
+    let text = prompt.expand(&args.args.join(" "), &cwd)?;
+    // `--` keeps a prompt that starts with '-' from being parsed as a flag.
+    Ok(ExecCli::try_parse_from([
+        "codex-exec",
+        "--",
+        text.as_str(),
+    ])?)
+}
+
 /// Prepend root-level overrides so they have lower precedence than
 /// CLI-specific ones specified after the subcommand (if any).
 fn prepend_config_flags(
@@ -15,11 +15,26 @@ use tracing::info;
 
 #[derive(Debug, Parser)]
 pub struct ProtoCli {
+    /// Speak newline-delimited JSON-RPC 2.0: `submit`, `interrupt` and
+    /// `approve` requests, and an `event` notification for each event.
+    #[arg(long = "json-rpc", default_value_t = false)]
+    pub json_rpc: bool,
+
+    /// Print the JSON Schema of the submissions and events, with the
+    /// protocol version, and exit.
+    #[arg(long = "schema", default_value_t = false)]
+    pub schema: bool,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 }
 
 pub async fn run_main(opts: ProtoCli) -> anyhow::Result<()> {
+    if opts.schema {
+        let schema = codex_core::protocol::json_schema()?;
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
     if std::io::stdin().is_terminal() {
         anyhow::bail!("Protocol mode expects stdin to be a pipe, not a terminal");
     }
@@ -28,7 +43,11 @@ pub async fn run_main(opts: ProtoCli) -> anyhow::Result<()> {
         .with_writer(std::io::stderr)
         .init();
 
-    let ProtoCli { config_overrides } = opts;
+    let ProtoCli {
+        json_rpc,
+        schema: _,
+        config_overrides,
+    } = opts;
     let overrides_vec = config_overrides
         .parse_overrides()
         .map_err(anyhow::Error::msg)?;
@@ -37,6 +56,10 @@ pub async fn run_main(opts: ProtoCli) -> anyhow::Result<()> {
     let ctrl_c = notify_on_sigint();
     let (codex, _init_id) = Codex::spawn(config, ctrl_c.clone()).await?;
     let codex = Arc::new(codex);
+    if json_rpc {
+        crate::json_rpc::run_main(codex, ctrl_c).await;
+        return Ok(());
+    }
 
     // Task that reads JSON lines from stdin and forwards to Submission Queue
     let sq_fut = {
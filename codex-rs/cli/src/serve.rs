@@ -0,0 +1,243 @@
+//! `codex serve`: the submission/event protocol of `codex proto`, over
+//! WebSocket, so that IDE extensions and web front-ends can drive Codex from
+//! another process or machine.
+//!
+//! Every connection gets its own session. Clients send each [`Submission`]
+//! as a JSON text message and receive each [`Event`] the same way; closing
+//! the connection ends the session.
+//!
+//! Connections must present the server's token, as `Authorization: Bearer
+//! <token>` or, for browsers, which cannot set headers on a WebSocket, as a
+//! `token` query parameter. The token is `CODEX_SERVE_TOKEN` when set and a
+//! random one, printed at startup, otherwise.
+//!
+//! [`Event`]: codex_core::protocol::Event
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::Codex;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::protocol::Submission;
+use futures::SinkExt;
+use futures::StreamExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::Callback;
+use tokio_tungstenite::tungstenite::handshake::server::ErrorResponse;
+use tokio_tungstenite::tungstenite::handshake::server::Request;
+use tokio_tungstenite::tungstenite::handshake::server::Response;
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tracing::info;
+use tracing::warn;
+
+/// Environment variable holding the token clients must present.
+const TOKEN_ENV_VAR: &str = "CODEX_SERVE_TOKEN";
+
+#[derive(Debug, Parser)]
+pub struct ServeCli {
+    /// Port to listen on.
+    #[arg(long, default_value_t = 4500)]
+    pub port: u16,
+
+    /// Address to listen on. Anything other than a loopback address exposes
+    /// the sessions, and the commands they run, to the network.
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+}
+
+#[allow(clippy::print_stderr)]
+pub async fn run_main(
+    opts: ServeCli,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let ServeCli {
+        port,
+        host,
+        config_overrides,
+    } = opts;
+    let overrides_vec = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(
+        overrides_vec,
+        ConfigOverrides {
+            codex_linux_sandbox_exe,
+            ..Default::default()
+        },
+    )?;
+
+    let (token, generated) = match std::env::var(TOKEN_ENV_VAR) {
+        Ok(token) if !token.is_empty() => (token, false),
+        _ => (uuid::Uuid::new_v4().simple().to_string(), true),
+    };
+    let listener = TcpListener::bind((host.as_str(), port)).await?;
+    let addr = listener.local_addr()?;
+    // A token the user chose is theirs to share; it stays out of the logs.
+    if generated {
+        eprintln!("Listening on ws://{addr}/?token={token}");
+    } else {
+        eprintln!("Listening on ws://{addr}/ (token from {TOKEN_ENV_VAR})");
+    }
+    let token = Arc::new(token);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            accepted = listener.accept() => accepted?,
+        };
+        let config = config.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, peer, config, &token).await {
+                warn!("connection from {peer} ended: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Run a session for the client on `stream` until it disconnects.
+async fn serve_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    config: Config,
+    token: &str,
+) -> anyhow::Result<()> {
+    let websocket = tokio_tungstenite::accept_hdr_async(stream, Authorize(token)).await?;
+    info!("session started for {peer}");
+    let (mut outgoing, mut incoming) = websocket.split();
+    // Interrupts arrive as `Op::Interrupt` submissions, not signals.
+    let (codex, _init_id) = Codex::spawn(config, Arc::new(Notify::new())).await?;
+    let codex = Arc::new(codex);
+
+    // Submissions and events flow independently, as in `codex proto`, so
+    // that a full event queue never holds up a submission or vice versa.
+    let submissions = {
+        let codex = codex.clone();
+        async move {
+            while let Some(message) = incoming.next().await {
+                let text = match message? {
+                    Message::Text(text) => text,
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+                match serde_json::from_str::<Submission>(&text) {
+                    Ok(submission) => codex.submit_with_id(submission).await?,
+                    Err(e) => warn!("invalid submission from {peer}: {e}"),
+                }
+            }
+            anyhow::Ok(())
+        }
+    };
+    let events = async move {
+        loop {
+            let event = codex.next_event().await?;
+            let text = serde_json::to_string(&event)?;
+            outgoing.send(Message::text(text)).await?;
+        }
+    };
+
+    let result = tokio::select! {
+        result = submissions => result,
+        result = events => result,
+    };
+    info!("session for {peer} closed");
+    result
+}
+
+/// Lets the handshake through when it carries the token.
+struct Authorize<'a>(&'a str);
+
+impl Callback for Authorize<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        let Authorize(token) = self;
+        let bearer = request
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let query = request.uri().query().unwrap_or_default();
+        let from_query = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="));
+        if bearer
+            .into_iter()
+            .chain(from_query)
+            .any(|candidate| constant_time_eq(candidate.as_bytes(), token.as_bytes()))
+        {
+            return Ok(response);
+        }
+
+        warn!("rejected a connection without a valid token");
+        let mut rejection = ErrorResponse::new(Some("missing or invalid token".to_string()));
+        *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(rejection)
+    }
+}
+
+/// Compare without returning early, so that timing does not reveal how much
+/// of a guessed token is right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn status(uri: &str, authorization: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        match Authorize(TOKEN).on_request(&request.body(()).unwrap(), Response::new(())) {
+            Ok(response) => response.status(),
+            Err(rejection) => rejection.status(),
+        }
+    }
+
+    #[test]
+    fn accepts_the_token_in_the_header_or_the_query() {
+        assert_eq!(status("/", Some("Bearer 0123456789abcdef")), StatusCode::OK);
+        assert_eq!(
+            status("/?session=1&token=0123456789abcdef", None),
+            StatusCode::OK
+        );
+    }
+
+    #[test]
+    fn rejects_a_missing_or_wrong_token() {
+        assert_eq!(status("/", None), StatusCode::UNAUTHORIZED);
+        assert_eq!(status("/?token=", None), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status("/?token=0123456789abcdeX", None),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/", Some("Bearer 0123456789abcde")),
+            StatusCode::UNAUTHORIZED
+        );
+        // The header needs the `Bearer` scheme.
+        assert_eq!(
+            status("/", Some("0123456789abcdef")),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
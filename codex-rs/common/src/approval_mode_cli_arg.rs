@@ -49,6 +49,10 @@ pub enum SandboxPermission {
     /// Is allowed to read all files on disk.
     DiskFullReadAccess,
 
+    /// Without full read access, is allowed to read a specific folder in
+    /// place of the current working directory.
+    DiskReadFolder(PathBuf),
+
     /// Is allowed to write to the operating system\'s temp dir that
     /// is restricted to the user the agent is running as.
     DiskWritePlatformUserTempFolder,
@@ -92,7 +96,7 @@ pub struct SandboxPermissionOption {
     ///       -s disk-write-platform-global-temp-folder
     /// ```
     ///
-    /// Note disk-write-folder takes a value:
+    /// Note disk-write-folder and disk-read-folder take a value:
     ///
     /// ```shell
     ///     -s disk-write-folder=$HOME/.pyenv/shims
@@ -108,17 +112,31 @@ pub struct SandboxPermissionOption {
     pub permissions: Option<Vec<SandboxPermission>>,
 }
 
+/// Builds a folder permission from the folder's path.
+type FolderPermission = fn(PathBuf) -> SandboxPermission;
+
 /// Custom value-parser so we can keep the CLI surface small *and*
-/// still handle the parameterised `disk-write-folder` case.
+/// still handle the parameterised `disk-write-folder` and `disk-read-folder`
+/// cases.
 fn parse_sandbox_permission(raw: &str) -> std::io::Result<SandboxPermission> {
     let base_path = std::env::current_dir()?;
     use SandboxPermission::*;
 
-    if let Some(path) = raw.strip_prefix("disk-write-folder=") {
+    let folder_permissions: [(&str, FolderPermission); 2] = [
+        ("disk-write-folder", DiskWriteFolder),
+        ("disk-read-folder", DiskReadFolder),
+    ];
+    for (name, permission) in folder_permissions {
+        let Some(path) = raw
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+        else {
+            continue;
+        };
         return if path.is_empty() {
             Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
-                "--sandbox-permission disk-write-folder=<PATH> requires a non-empty PATH",
+                format!("--sandbox-permission {name}=<PATH> requires a non-empty PATH"),
             ))
         } else {
             let path = PathBuf::from(path);
@@ -127,7 +145,7 @@ fn parse_sandbox_permission(raw: &str) -> std::io::Result<SandboxPermission> {
             } else {
                 base_path.join(path)
             };
-            Ok(DiskWriteFolder(path))
+            Ok(permission(path))
         };
     }
 
@@ -13,6 +13,12 @@ pub use approval_mode_cli_arg::SandboxPermission;
 #[cfg(feature = "cli")]
 pub use approval_mode_cli_arg::SandboxPermissionOption;
 
+#[cfg(feature = "cli")]
+mod session_mode_cli_arg;
+
+#[cfg(feature = "cli")]
+pub use session_mode_cli_arg::SessionModeCliArg;
+
 #[cfg(feature = "cli")]
 mod config_override;
 
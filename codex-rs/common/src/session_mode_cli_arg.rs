@@ -0,0 +1,16 @@
+//! Standard type to use with the `--mode` CLI option.
+//! Available when the `cli` feature is enabled for the crate.
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum SessionModeCliArg {
+    /// Let the agent edit files and run commands as the approval and sandbox
+    /// policies allow.
+    Code,
+
+    /// Read-only codebase Q&A: the agent can read and search files but not
+    /// edit them or run other commands.
+    Ask,
+}
@@ -0,0 +1,295 @@
+//! Guards on the files mentioned in a message (`@path`) before their
+//! contents are sent to the model.
+//!
+//! Binary files are replaced with a short note. Files larger than
+//! `attachments.max_file_bytes` are sent as their first and last lines, each
+//! part headed by the line numbers it covers so that the model can read the
+//! middle with a command. The files of one message share
+//! `attachments.max_turn_bytes`; once it is spent, further files are
+//! replaced with a note as well.
+
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+use crate::config_types::Attachments;
+
+/// A file is binary when its first bytes contain a NUL or are not UTF-8.
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Below this, what is left of the message's budget is not worth a window.
+const MIN_WINDOW_BYTES: usize = 1024;
+
+/// A mentioned file, ready to be sent to the model.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Attachment {
+    /// The file in `<file>` tags, or a note saying why it was left out.
+    pub(crate) text: String,
+    /// What was done to the file, for the user, when it is not sent whole.
+    pub(crate) notice: Option<String>,
+}
+
+/// What is left of the byte budget of the files mentioned in one message.
+pub(crate) struct AttachmentBudget {
+    max_file_bytes: usize,
+    max_turn_bytes: usize,
+    remaining: usize,
+}
+
+impl AttachmentBudget {
+    pub(crate) fn new(limits: &Attachments) -> Self {
+        Self {
+            max_file_bytes: limits.max_file_bytes,
+            max_turn_bytes: limits.max_turn_bytes,
+            remaining: limits.max_turn_bytes,
+        }
+    }
+
+    /// Read the file at `path`, within the budget. `label` names it in
+    /// notices.
+    pub(crate) fn attach(&mut self, path: &Path, label: &str) -> std::io::Result<Attachment> {
+        let mut file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len()).unwrap_or(usize::MAX);
+        let limit = self.max_file_bytes.min(self.remaining);
+
+        let mut head = Vec::new();
+        (&mut file)
+            .take(len.min(limit.max(SNIFF_BYTES)) as u64)
+            .read_to_end(&mut head)?;
+        if is_binary(&head) {
+            return Ok(Attachment {
+                text: note(
+                    path,
+                    "This looks like a binary file, so its contents were not attached.",
+                ),
+                notice: Some(format!(
+                    "{label} looks like a binary file and was not attached"
+                )),
+            });
+        }
+
+        if len <= limit {
+            let contents = String::from_utf8_lossy(&head);
+            self.remaining = self.remaining.saturating_sub(contents.len());
+            return Ok(Attachment {
+                text: wrap(path, &contents),
+                notice: None,
+            });
+        }
+        if limit < MIN_WINDOW_BYTES {
+            return Ok(Attachment {
+                text: note(
+                    path,
+                    "The files of this message already fill the attachment budget, so this one was not attached.",
+                ),
+                notice: Some(format!(
+                    "{label} was not attached: the files of this message reached the {} limit",
+                    format_size(self.max_turn_bytes)
+                )),
+            });
+        }
+
+        head.truncate(limit / 2);
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start((len - limit / 2) as u64))?;
+        file.read_to_end(&mut tail)?;
+        file.rewind()?;
+        let total_lines = count_lines(&mut file)?;
+
+        let contents = window(&head, &tail, total_lines, len);
+        self.remaining = self.remaining.saturating_sub(contents.len());
+        Ok(Attachment {
+            text: wrap(path, &contents),
+            notice: Some(format!(
+                "{label} is {}; only its first and last lines were attached",
+                format_size(len)
+            )),
+        })
+    }
+}
+
+/// Whether the file at `path` looks binary, judging by its first bytes.
+pub(crate) fn is_binary_file(path: &Path) -> std::io::Result<bool> {
+    let mut head = Vec::new();
+    File::open(path)?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    Ok(is_binary(&head))
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    if sample.contains(&0) {
+        return true;
+    }
+    // A multi-byte character cut off by the end of the sample is fine.
+    matches!(std::str::from_utf8(sample), Err(e) if e.error_len().is_some())
+}
+
+/// The complete lines at the start of `head` and the end of `tail`, the
+/// first and last `limit / 2` bytes of a file of `len` bytes and
+/// `total_lines` lines.
+fn window(head: &[u8], tail: &[u8], total_lines: usize, len: usize) -> String {
+    let head = match head.iter().rposition(|&b| b == b'\n') {
+        Some(end) => &head[..=end],
+        None => &[][..],
+    };
+    // The last line of the file may lack its newline; skip the partial line
+    // the tail starts with.
+    let tail = match tail[..tail.len().saturating_sub(1)]
+        .iter()
+        .position(|&b| b == b'\n')
+    {
+        Some(start) => &tail[start + 1..],
+        None => &[][..],
+    };
+    let head_lines = head.iter().filter(|&&b| b == b'\n').count();
+    let tail_lines = tail.iter().filter(|&&b| b == b'\n').count()
+        + usize::from(!tail.is_empty() && !tail.ends_with(b"\n"));
+    let tail_start = total_lines - tail_lines + 1;
+
+    if head_lines == 0 && tail_lines == 0 {
+        return format!("[the lines of this {len}-byte file are too long to show in part]\n");
+    }
+    let mut text = String::new();
+    if head_lines > 0 {
+        text.push_str(&format!("[lines 1-{head_lines} of {total_lines}]\n"));
+        text.push_str(&String::from_utf8_lossy(head));
+    }
+    let (first, last) = (head_lines + 1, tail_start - 1);
+    text.push_str(&format!(
+        "[lines {first}-{last} omitted; read them with e.g. `sed -n '{first},{last}p'`]\n"
+    ));
+    if tail_lines > 0 {
+        text.push_str(&format!(
+            "[lines {tail_start}-{total_lines} of {total_lines}]\n"
+        ));
+        text.push_str(&String::from_utf8_lossy(tail));
+    }
+    text
+}
+
+fn count_lines(file: &mut File) -> std::io::Result<usize> {
+    let mut buf = vec![0; 64 * 1024];
+    let mut lines = 0;
+    let mut last = b'\n';
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        lines += buf[..n].iter().filter(|&&b| b == b'\n').count();
+        last = buf[n - 1];
+    }
+    Ok(lines + usize::from(last != b'\n'))
+}
+
+fn wrap(path: &Path, contents: &str) -> String {
+    let mut text = format!("<file path=\"{}\">\n{contents}", path.display());
+    if !contents.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str("</file>");
+    text
+}
+
+fn note(path: &Path, note: &str) -> String {
+    format!("<file path=\"{}\">\n[{note}]\n</file>", path.display())
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{} KB", bytes.div_ceil(1024))
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn budget(max_file_bytes: usize, max_turn_bytes: usize) -> AttachmentBudget {
+        AttachmentBudget::new(&Attachments {
+            max_file_bytes,
+            max_turn_bytes,
+        })
+    }
+
+    #[test]
+    fn small_files_are_attached_whole() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("small.txt");
+        std::fs::write(&path, "hello").unwrap();
+        assert_eq!(
+            budget(1024, 4096).attach(&path, "small.txt").unwrap(),
+            Attachment {
+                text: format!("<file path=\"{}\">\nhello\n</file>", path.display()),
+                notice: None,
+            }
+        );
+    }
+
+    #[test]
+    fn binary_files_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("blob.bin");
+        std::fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+        let attachment = budget(1024, 4096).attach(&path, "blob.bin").unwrap();
+        assert!(
+            attachment.text.contains("binary file"),
+            "{}",
+            attachment.text
+        );
+        assert_eq!(
+            attachment.notice.as_deref(),
+            Some("blob.bin looks like a binary file and was not attached")
+        );
+    }
+
+    #[test]
+    fn large_files_are_windowed_with_line_numbers() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("big.log");
+        let contents: String = (1..=1000).map(|i| format!("line {i:04}\n")).collect();
+        std::fs::write(&path, &contents).unwrap();
+
+        // Each line is 10 bytes, so each half of the 2 KB window holds 102.
+        let attachment = budget(2048, 4096).attach(&path, "big.log").unwrap();
+        let text = attachment.text;
+        assert!(
+            text.contains("[lines 1-102 of 1000]\nline 0001\n"),
+            "{text}"
+        );
+        assert!(text.contains("line 0102\n[lines 103-898 omitted"), "{text}");
+        assert!(
+            text.contains("[lines 899-1000 of 1000]\nline 0899\n"),
+            "{text}"
+        );
+        assert!(text.ends_with("line 1000\n</file>"), "{text}");
+        assert_eq!(
+            attachment.notice.as_deref(),
+            Some("big.log is 10 KB; only its first and last lines were attached")
+        );
+    }
+
+    #[test]
+    fn files_share_the_message_budget() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "a".repeat(3000)).unwrap();
+
+        let mut budget = budget(4096, 4000);
+        assert_eq!(budget.attach(&path, "a.txt").unwrap().notice, None);
+        let second = budget.attach(&path, "a.txt").unwrap();
+        assert!(second.text.contains("attachment budget"), "{}", second.text);
+        assert!(second.notice.is_some());
+    }
+}
@@ -0,0 +1,414 @@
+//! Append-only audit log of the commands Codex runs and the files it writes.
+//!
+//! Every session appends to `~/.codex/audit/<session_id>.jsonl`, one JSON
+//! record per line. A record holds what was done (command argv, cwd and exit
+//! code, or the files a patch wrote) and the approval decision that allowed
+//! it. Unlike the rollout, the log is kept when response storage is disabled
+//! and is never rewritten.
+//!
+//! With `encrypt_sessions`, each record is sealed as described in
+//! [`crate::encryption`], in `<session_id>.jsonl.enc`.
+
+use std::fmt;
+use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use time::OffsetDateTime;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::encryption::DecryptingReader;
+use crate::encryption::ENCRYPTED_SUFFIX;
+use crate::encryption::session_key;
+use crate::protocol::SandboxAccess;
+
+/// Directory inside `~/.codex` that holds the audit logs.
+const AUDIT_SUBDIR: &str = "audit";
+
+/// What allowed an action to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditApproval {
+    /// Allowed by the approval and sandbox policies without asking.
+    Auto,
+    /// The user approved this call.
+    User,
+    /// The user approved the same command earlier in the session.
+    Session,
+    /// Covered by the user's switch to write mode.
+    WriteMode,
+    /// Written right away, to be reviewed with the rest of the turn's changes
+    /// (`review_edits = "per-turn"`).
+    TurnReview,
+    /// Not run at all: the session is a dry run.
+    DryRun,
+    /// Not run at all: the policy file refuses it.
+    Policy,
+}
+
+impl fmt::Display for AuditApproval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditApproval::Auto => "auto",
+            AuditApproval::User => "user",
+            AuditApproval::Session => "session",
+            AuditApproval::WriteMode => "write-mode",
+            AuditApproval::TurnReview => "turn-review",
+            AuditApproval::DryRun => "dry-run",
+            AuditApproval::Policy => "policy",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditAction {
+    Exec {
+        command: Vec<String>,
+        cwd: PathBuf,
+        sandboxed: bool,
+        /// `None` when the command could not be run to completion, e.g. it
+        /// timed out or failed to start.
+        exit_code: Option<i32>,
+    },
+    Patch {
+        files: Vec<PathBuf>,
+        success: bool,
+    },
+    /// Files restored to their previous content because the user rejected
+    /// the changes made to them.
+    Revert {
+        files: Vec<PathBuf>,
+    },
+    /// A sandboxed command that failed because the sandbox denied it
+    /// something (`[linux_sandbox] log_violations`).
+    SandboxViolation {
+        command: Vec<String>,
+        cwd: PathBuf,
+        /// `None` when the command's output does not name the path or host.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access: Option<SandboxAccess>,
+        /// The end of what the command printed to stderr.
+        stderr: String,
+    },
+    /// A tool call a rule of the policy file refused.
+    PolicyViolation {
+        tool: String,
+        rule: String,
+        /// What the rule matched, e.g. ``host `example.com` ``.
+        matched: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_id: Option<String>,
+    pub approval: AuditApproval,
+    #[serde(flatten)]
+    pub action: AuditAction,
+}
+
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}] ", self.timestamp, self.approval)?;
+        match &self.action {
+            AuditAction::Exec {
+                command,
+                cwd,
+                sandboxed,
+                exit_code,
+            } => {
+                let exit = match (self.approval, exit_code) {
+                    (AuditApproval::DryRun, _) => "not executed".to_string(),
+                    (_, Some(code)) => format!("exit {code}"),
+                    (_, None) => "no exit code".to_string(),
+                };
+                let sandbox = if *sandboxed {
+                    "sandboxed"
+                } else {
+                    "unsandboxed"
+                };
+                write!(
+                    f,
+                    "exec ({exit}, {sandbox}) in {}: {}",
+                    cwd.display(),
+                    shlex::try_join(command.iter().map(String::as_str))
+                        .unwrap_or_else(|_| command.join(" "))
+                )
+            }
+            AuditAction::Patch { files, success } => {
+                let status = if *success { "applied" } else { "failed" };
+                write!(f, "patch ({status}): {}", join_paths(files))
+            }
+            AuditAction::Revert { files } => write!(f, "revert: {}", join_paths(files)),
+            AuditAction::SandboxViolation {
+                command,
+                cwd,
+                access,
+                ..
+            } => {
+                let denied = match access {
+                    Some(access) => access.to_string(),
+                    None => "unknown access".to_string(),
+                };
+                write!(
+                    f,
+                    "sandbox denied {denied} in {}: {}",
+                    cwd.display(),
+                    shlex::try_join(command.iter().map(String::as_str))
+                        .unwrap_or_else(|_| command.join(" "))
+                )
+            }
+            AuditAction::PolicyViolation {
+                tool,
+                rule,
+                matched,
+            } => write!(f, "{tool} refused by {rule}: {matched}"),
+        }
+    }
+}
+
+fn join_paths(files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writer for one session's audit log.
+#[derive(Debug, Clone)]
+pub(crate) struct AuditLog {
+    path: PathBuf,
+    encrypted: bool,
+}
+
+impl AuditLog {
+    pub(crate) fn new(codex_home: &Path, session_id: Uuid, encrypted: bool) -> Self {
+        let suffix = if encrypted { ENCRYPTED_SUFFIX } else { "" };
+        Self {
+            path: audit_dir(codex_home).join(format!("{session_id}.jsonl{suffix}")),
+            encrypted,
+        }
+    }
+
+    /// Append a record. Failures are logged rather than returned so that they
+    /// never fail the action being audited.
+    pub(crate) async fn record(
+        &self,
+        call_id: Option<&str>,
+        approval: AuditApproval,
+        action: AuditAction,
+    ) {
+        let record = AuditRecord {
+            timestamp: now(),
+            call_id: call_id.map(str::to_string),
+            approval,
+            action,
+        };
+        if let Err(e) = self.append(&record).await {
+            tracing::warn!("failed to write audit log {}: {e}", self.path.display());
+        }
+    }
+
+    async fn append(&self, record: &AuditRecord) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let bytes = if self.encrypted {
+            session_key()?.seal(line.as_bytes())?
+        } else {
+            line.into_bytes()
+        };
+        let mut options = tokio::fs::OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        // One write per record on an O_APPEND descriptor, so concurrent
+        // writers cannot interleave within a line.
+        let mut file = options.open(&self.path).await?;
+        file.write_all(&bytes).await?;
+        file.flush().await
+    }
+}
+
+fn now() -> String {
+    let format: &[FormatItem] =
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z");
+    OffsetDateTime::now_utc()
+        .format(format)
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+pub(crate) fn audit_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join(AUDIT_SUBDIR)
+}
+
+/// Locate the audit log of the session whose id is `id` or starts with it.
+pub fn find_audit_log(codex_home: &Path, id: &str) -> std::io::Result<PathBuf> {
+    let id = id.trim().to_ascii_lowercase();
+    let not_found = || {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no audit log for session `{id}`"),
+        )
+    };
+    let entries = match fs::read_dir(audit_dir(codex_home)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => return Err(e),
+    };
+    let mut matches = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(session_id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.strip_suffix(ENCRYPTED_SUFFIX).unwrap_or(name))
+            .and_then(|name| name.strip_suffix(".jsonl"))
+        else {
+            continue;
+        };
+        if session_id == id {
+            return Ok(path);
+        }
+        if !id.is_empty() && session_id.starts_with(&id) {
+            matches.push(path);
+        }
+    }
+    match matches.len() {
+        0 => Err(not_found()),
+        1 => Ok(matches.remove(0)),
+        n => Err(std::io::Error::other(format!(
+            "session id `{id}` is ambiguous ({n} audit logs match)"
+        ))),
+    }
+}
+
+/// Read every record of an audit log, oldest first.
+pub fn read_audit_log(path: &Path) -> std::io::Result<Vec<AuditRecord>> {
+    let mut reader: Box<dyn Read> = Box::new(fs::File::open(path)?);
+    if path
+        .to_str()
+        .is_some_and(|path| path.ends_with(ENCRYPTED_SUFFIX))
+    {
+        reader = Box::new(DecryptingReader::new(reader, session_key()?));
+    }
+    let reader = BufReader::new(reader);
+    let mut records = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}:{}: {e}", path.display(), idx + 1),
+            )
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn records_round_trip_and_are_found_by_prefix() {
+        let codex_home = TempDir::new().unwrap();
+        let session_id = Uuid::new_v4();
+        let log = AuditLog::new(codex_home.path(), session_id, false);
+        let exec = AuditAction::Exec {
+            command: vec!["cargo".to_string(), "test".to_string()],
+            cwd: PathBuf::from("/repo"),
+            sandboxed: true,
+            exit_code: Some(0),
+        };
+        let patch = AuditAction::Patch {
+            files: vec![PathBuf::from("/repo/src/lib.rs")],
+            success: true,
+        };
+        log.record(Some("call-1"), AuditApproval::Auto, exec.clone())
+            .await;
+        log.record(Some("call-2"), AuditApproval::User, patch.clone())
+            .await;
+
+        let prefix = &session_id.to_string()[..8];
+        let path = find_audit_log(codex_home.path(), prefix).unwrap();
+        let records = read_audit_log(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].call_id.as_deref(), Some("call-1"));
+        assert_eq!(records[0].action, exec);
+        assert_eq!(records[1].approval, AuditApproval::User);
+        assert_eq!(records[1].action, patch);
+
+        assert!(find_audit_log(codex_home.path(), "not-a-session").is_err());
+    }
+
+    #[test]
+    fn renders_one_line_per_record() {
+        let record = AuditRecord {
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            call_id: Some("call-1".to_string()),
+            approval: AuditApproval::Session,
+            action: AuditAction::Exec {
+                command: vec!["echo".to_string(), "hello world".to_string()],
+                cwd: PathBuf::from("/repo"),
+                sandboxed: false,
+                exit_code: Some(1),
+            },
+        };
+        assert_eq!(
+            record.to_string(),
+            "2025-01-01T00:00:00.000Z [session] exec (exit 1, unsandboxed) in /repo: echo 'hello world'"
+        );
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            serde_json::json!({
+                "timestamp": "2025-01-01T00:00:00.000Z",
+                "call_id": "call-1",
+                "approval": "session",
+                "type": "exec",
+                "command": ["echo", "hello world"],
+                "cwd": "/repo",
+                "sandboxed": false,
+                "exit_code": 1,
+            })
+        );
+
+        let violation = AuditRecord {
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            call_id: Some("call-2".to_string()),
+            approval: AuditApproval::Auto,
+            action: AuditAction::SandboxViolation {
+                command: vec!["touch".to_string(), "/opt/x".to_string()],
+                cwd: PathBuf::from("/repo"),
+                access: Some(SandboxAccess::Write {
+                    path: PathBuf::from("/opt"),
+                }),
+                stderr: "touch: cannot touch '/opt/x': Permission denied".to_string(),
+            },
+        };
+        assert_eq!(
+            violation.to_string(),
+            "2025-01-01T00:00:00.000Z [auto] sandbox denied write access to /opt in /repo: touch /opt/x"
+        );
+    }
+}
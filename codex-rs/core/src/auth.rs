@@ -0,0 +1,396 @@
+//! Credentials for model providers.
+//!
+//! [`AuthManager`] is the one place that decides which token a request to a
+//! provider carries. It owns the token stores of the sign-in flows and falls
+//! back to the provider's `env_key`:
+//!
+//! - GitHub Copilot: the token saved by `codex copilot auth`, exchanged for a
+//!   new one when it expires, else `COPILOT_TOKEN`.
+//! - OpenAI: `OPENAI_API_KEY`, else the API key saved by `codex login`,
+//!   refreshed when the tokens are older than [`CHATGPT_REFRESH_INTERVAL`].
+//! - Every other provider: its `env_key`, if it has one.
+//!
+//! The model client asks a [`CredentialProvider`] for every request, so
+//! tokens never pass through the process environment. Copilot tokens also
+//! name the proxy they were issued for (e.g. an enterprise's), which chat
+//! requests are sent to instead of the provider's `base_url`.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use tokio::sync::Mutex;
+
+use crate::ModelProviderInfo;
+use crate::chatgpt_auth;
+use crate::chatgpt_auth::ChatGptTokens;
+use crate::copilot;
+use crate::copilot_token_store::COPILOT_TOKEN_FILE;
+use crate::copilot_token_store::CopilotToken;
+use crate::error::CodexErr;
+use crate::error::EnvVarError;
+use crate::error::Result;
+use crate::token_store::TokenFile;
+
+pub(crate) const COPILOT_PROVIDER_NAME: &str = "GitHub Copilot";
+const OPENAI_ENV_KEY: &str = "OPENAI_API_KEY";
+/// Tokens from `codex login` are refreshed once they are this old.
+pub const CHATGPT_REFRESH_INTERVAL: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+/// Copilot tokens are refreshed this long before they expire, so that a
+/// request does not carry a token that expires in flight.
+const COPILOT_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// A token for a provider and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub token: String,
+    pub source: CredentialSource,
+    /// Endpoint to send the token to instead of the provider's `base_url`:
+    /// the proxy a Copilot token was issued for.
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialSource {
+    /// The provider's `env_key`.
+    EnvVar(String),
+    /// The token saved by `codex copilot auth`.
+    CopilotDeviceFlow,
+    /// The API key saved by `codex login`.
+    ChatGptLogin,
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialSource::EnvVar(var) => write!(f, "`{var}`"),
+            CredentialSource::CopilotDeviceFlow => {
+                write!(f, "the token saved by `codex copilot auth`")
+            }
+            CredentialSource::ChatGptLogin => write!(f, "the API key saved by `codex login`"),
+        }
+    }
+}
+
+/// Supplies the credentials for requests to a provider.
+pub trait CredentialProvider: Send + Sync {
+    /// See [`AuthManager::get_credentials`].
+    fn credentials<'a>(
+        &'a self,
+        provider: &'a ModelProviderInfo,
+    ) -> BoxFuture<'a, Result<Option<Credentials>>>;
+}
+
+pub struct AuthManager {
+    client: reqwest::Client,
+    copilot: TokenFile,
+    chatgpt: TokenFile,
+    copilot_token_url: String,
+    chatgpt_issuer: String,
+    /// Held while refreshing, so that concurrent requests refresh only once.
+    /// The lock on the token file does the same across processes.
+    refresh_lock: Mutex<()>,
+}
+
+impl AuthManager {
+    /// Manage the token stores in `codex_home`, refreshing tokens with
+    /// `client`.
+    pub fn new(codex_home: &Path, client: reqwest::Client) -> Self {
+        Self {
+            client,
+            copilot: TokenFile::new(codex_home, COPILOT_TOKEN_FILE),
+            chatgpt: TokenFile::new(codex_home, chatgpt_auth::AUTH_FILE),
+            copilot_token_url: copilot::COPILOT_CHAT_AUTH_URL.to_string(),
+            chatgpt_issuer: chatgpt_auth::ISSUER.to_string(),
+            refresh_lock: Mutex::new(()),
+        }
+    }
+
+    /// The credentials to send to `provider`, refreshing saved tokens that
+    /// expired. `Ok(None)` when the provider needs none; an
+    /// [`CodexErr::EnvVar`] error when it needs some and none are available.
+    pub async fn get_credentials(
+        &self,
+        provider: &ModelProviderInfo,
+    ) -> Result<Option<Credentials>> {
+        let Some(env_key) = &provider.env_key else {
+            return Ok(None);
+        };
+        if provider.name == COPILOT_PROVIDER_NAME {
+            if let Some(credentials) = self.copilot_credentials().await {
+                return Ok(Some(credentials));
+            }
+        }
+        if let Some(mut credentials) = env_credentials(env_key) {
+            if provider.name == COPILOT_PROVIDER_NAME {
+                credentials.base_url =
+                    CopilotToken::from_raw_token(&credentials.token).proxy_base_url();
+            }
+            return Ok(Some(credentials));
+        }
+        if env_key == OPENAI_ENV_KEY {
+            if let Some(credentials) = self.chatgpt_credentials().await {
+                return Ok(Some(credentials));
+            }
+        }
+        Err(CodexErr::EnvVar(EnvVarError {
+            var: env_key.clone(),
+            instructions: provider.env_key_instructions.clone(),
+        }))
+    }
+
+    async fn copilot_credentials(&self) -> Option<Credentials> {
+        let _guard = self.refresh_lock.lock().await;
+        let token_file = match self.copilot.lock_for_refresh().await {
+            Ok(token_file) => token_file?,
+            Err(e) => {
+                tracing::warn!("could not read the Copilot token: {e}");
+                return None;
+            }
+        };
+        let token = match token_file.load::<CopilotToken>().await {
+            Ok(token) => token?,
+            Err(e) => {
+                tracing::warn!("could not read the Copilot token: {e}");
+                return None;
+            }
+        };
+        let expires_soon = token.expires_at.is_some_and(|expires_at| {
+            expires_at <= chatgpt_auth::now() + COPILOT_EXPIRY_MARGIN.as_secs()
+        });
+        if !expires_soon {
+            return Some(copilot_credentials(token));
+        }
+
+        let github_token = token.github_token.as_deref()?;
+        match copilot::refresh_copilot_token(&self.client, &self.copilot_token_url, github_token)
+            .await
+        {
+            Ok(token) => {
+                if let Err(e) = token_file.save(&token) {
+                    tracing::warn!("could not save the refreshed Copilot token: {e}");
+                }
+                Some(copilot_credentials(token))
+            }
+            Err(e) => {
+                tracing::warn!("could not refresh the Copilot token: {e}");
+                None
+            }
+        }
+    }
+
+    async fn chatgpt_credentials(&self) -> Option<Credentials> {
+        let _guard = self.refresh_lock.lock().await;
+        let token_file = match self.chatgpt.lock_for_refresh().await {
+            Ok(token_file) => token_file?,
+            Err(e) => {
+                tracing::warn!("could not read the `codex login` tokens: {e}");
+                return None;
+            }
+        };
+        let mut tokens = match token_file.load::<ChatGptTokens>().await {
+            Ok(tokens) => tokens?,
+            Err(e) => {
+                tracing::warn!("could not read the `codex login` tokens: {e}");
+                return None;
+            }
+        };
+        let age = chatgpt_auth::now().saturating_sub(tokens.last_refresh);
+        if age >= CHATGPT_REFRESH_INTERVAL.as_secs() {
+            match chatgpt_auth::refresh_tokens(&self.client, &self.chatgpt_issuer, &tokens).await {
+                Ok(refreshed) => {
+                    if let Err(e) = token_file.save(&refreshed) {
+                        tracing::warn!("could not save the refreshed `codex login` tokens: {e}");
+                    }
+                    tokens = refreshed;
+                }
+                // The API key may well still work; try it.
+                Err(e) => tracing::warn!("could not refresh the `codex login` tokens: {e}"),
+            }
+        }
+        Some(Credentials {
+            token: tokens.api_key?,
+            source: CredentialSource::ChatGptLogin,
+            base_url: None,
+        })
+    }
+}
+
+impl CredentialProvider for AuthManager {
+    fn credentials<'a>(
+        &'a self,
+        provider: &'a ModelProviderInfo,
+    ) -> BoxFuture<'a, Result<Option<Credentials>>> {
+        Box::pin(self.get_credentials(provider))
+    }
+}
+
+fn copilot_credentials(token: CopilotToken) -> Credentials {
+    Credentials {
+        base_url: token.proxy_base_url(),
+        token: token.token,
+        source: CredentialSource::CopilotDeviceFlow,
+    }
+}
+
+fn env_credentials(env_key: &str) -> Option<Credentials> {
+    let token = std::env::var(env_key).ok()?;
+    (!token.trim().is_empty()).then(|| Credentials {
+        token,
+        source: CredentialSource::EnvVar(env_key.to_string()),
+        base_url: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::WireApi;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_string_contains;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    fn manager(codex_home: &TempDir, server: &MockServer) -> AuthManager {
+        let mut manager = AuthManager::new(codex_home.path(), reqwest::Client::new());
+        manager.copilot_token_url = format!("{}/copilot_internal/v2/token", server.uri());
+        manager.chatgpt_issuer = server.uri();
+        manager
+    }
+
+    #[tokio::test]
+    async fn reports_the_missing_env_var() {
+        let codex_home = TempDir::new().unwrap();
+        let manager = AuthManager::new(codex_home.path(), reqwest::Client::new());
+        let mut provider = ModelProviderInfo {
+            name: "Example".into(),
+            base_url: "https://example.com/v1".into(),
+            env_key: None,
+            env_key_instructions: Some("get a key".into()),
+            wire_api: WireApi::Chat,
+            supports_images: false,
+            supports_structured_output: false,
+            rate_limit: None,
+        };
+        assert_eq!(manager.get_credentials(&provider).await.unwrap(), None);
+
+        provider.env_key = Some("CODEX_AUTH_TEST_UNSET_KEY".into());
+        let Err(CodexErr::EnvVar(e)) = manager.get_credentials(&provider).await else {
+            panic!("expected an EnvVar error");
+        };
+        assert_eq!(e.var, "CODEX_AUTH_TEST_UNSET_KEY");
+        assert_eq!(e.instructions.as_deref(), Some("get a key"));
+    }
+
+    #[tokio::test]
+    async fn refreshes_an_expired_copilot_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/copilot_internal/v2/token"))
+            .and(header("authorization", "Bearer gho_github"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "token": "tid=new;exp=99999999999;proxy-ep=proxy.example.com",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let codex_home = TempDir::new().unwrap();
+        let manager = manager(&codex_home, &server);
+        let mut expired = CopilotToken::from_raw_token("tid=old;exp=1");
+        expired.github_token = Some("gho_github".to_string());
+        manager.copilot.save(&expired).unwrap();
+
+        let credentials = manager.copilot_credentials().await.unwrap();
+        assert_eq!(
+            credentials.token,
+            "tid=new;exp=99999999999;proxy-ep=proxy.example.com"
+        );
+        assert_eq!(
+            credentials.base_url.as_deref(),
+            Some("https://proxy.example.com")
+        );
+        // Saved: the second call does not refresh again.
+        manager.copilot_credentials().await.unwrap();
+        let saved = manager.copilot.load::<CopilotToken>().unwrap().unwrap();
+        assert_eq!(saved.github_token.as_deref(), Some("gho_github"));
+    }
+
+    #[test]
+    fn copilot_proxy_must_be_a_plain_host() {
+        let proxy = |endpoint: &str| {
+            CopilotToken::from_raw_token(&format!("tid=1;proxy-ep={endpoint}")).proxy_base_url()
+        };
+        assert_eq!(
+            proxy("proxy.enterprise.githubcopilot.com").as_deref(),
+            Some("https://proxy.enterprise.githubcopilot.com")
+        );
+        assert_eq!(
+            proxy("https://proxy.example.com:8443/").as_deref(),
+            Some("https://proxy.example.com:8443")
+        );
+        // Never downgraded to plain HTTP, and never an IP address, user info
+        // or path that the certificate check would not cover.
+        assert_eq!(proxy("http://proxy.example.com"), None);
+        assert_eq!(proxy("10.0.0.1"), None);
+        assert_eq!(proxy("user@proxy.example.com"), None);
+        assert_eq!(proxy("proxy.example.com/evil"), None);
+    }
+
+    #[tokio::test]
+    async fn refreshes_stale_chatgpt_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=refresh-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id_token": "id-2",
+                "refresh_token": "refresh-2",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_string_contains("subject_token=id-2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "access_token": "sk-2" })),
+            )
+            .mount(&server)
+            .await;
+        let codex_home = TempDir::new().unwrap();
+        let manager = manager(&codex_home, &server);
+        manager
+            .chatgpt
+            .save(&ChatGptTokens {
+                id_token: "id-1".to_string(),
+                access_token: "access-1".to_string(),
+                refresh_token: "refresh-1".to_string(),
+                api_key: Some("sk-1".to_string()),
+                last_refresh: 0,
+            })
+            .unwrap();
+
+        assert_eq!(
+            manager.chatgpt_credentials().await,
+            Some(Credentials {
+                token: "sk-2".to_string(),
+                source: CredentialSource::ChatGptLogin,
+                base_url: None,
+            })
+        );
+        let saved = manager.chatgpt.load::<ChatGptTokens>().unwrap().unwrap();
+        assert_eq!(saved.refresh_token, "refresh-2");
+        assert_eq!(saved.access_token, "access-1");
+        // Fresh now: no second refresh.
+        manager.chatgpt_credentials().await.unwrap();
+    }
+}
@@ -0,0 +1,377 @@
+//! `codex bench`: run a suite of tasks headlessly with several models and
+//! compare how often, how quickly and how cheaply each gets them done.
+//!
+//! A suite is a directory of task files, one `*.toml` per task:
+//!
+//! ```toml
+//! prompt = "Add a --verbose flag that prints each file as it is processed."
+//! fixture = "fixtures/wc"  # copied into a fresh workspace; relative to the suite
+//! check = "cargo test"     # the task succeeded if this exits with 0
+//! timeout_secs = 600       # the default
+//! ```
+//!
+//! Each task runs in its own copy of its fixture, with commands and edits
+//! approved automatically inside the workspace-write sandbox.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use serde::Deserialize;
+use toml::Value as TomlValue;
+use uuid::Uuid;
+
+use crate::codex_wrapper::init_codex;
+use crate::config::Config;
+use crate::config::ConfigOverrides;
+use crate::openai_model_info::get_model_info;
+use crate::protocol::AskForApproval;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::TokenUsage;
+use crate::test_fix::run_tests;
+
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+/// One task of a suite.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BenchTask {
+    /// The file name without `.toml`.
+    #[serde(skip)]
+    pub name: String,
+    pub prompt: String,
+    /// Directory the workspace starts as a copy of; an empty workspace when
+    /// unset.
+    pub fixture: Option<PathBuf>,
+    /// Shell command deciding whether the task succeeded.
+    pub check: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// The tasks in the suite at `dir`, by name, with fixtures resolved against
+/// it.
+pub fn load_suite(dir: &Path) -> anyhow::Result<Vec<BenchTask>> {
+    let mut tasks = Vec::new();
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "toml") {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        let mut task: BenchTask = toml::from_str(&contents)
+            .with_context(|| format!("invalid task {}", path.display()))?;
+        task.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        task.fixture = task.fixture.map(|fixture| dir.join(fixture));
+        tasks.push(task);
+    }
+    if tasks.is_empty() {
+        anyhow::bail!("no task files (*.toml) in {}", dir.display());
+    }
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tasks)
+}
+
+/// A model a suite is run with, written `model` or `model@provider`, where
+/// `provider` is a key of `model_providers`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BenchTarget {
+    pub model: String,
+    pub provider: Option<String>,
+}
+
+impl FromStr for BenchTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (model, provider) = match s.rsplit_once('@') {
+            Some((model, provider)) => (model, Some(provider.to_string())),
+            None => (s, None),
+        };
+        if model.is_empty() || provider.as_deref() == Some("") {
+            return Err(format!("expected `model` or `model@provider`, got `{s}`"));
+        }
+        Ok(Self {
+            model: model.to_string(),
+            provider,
+        })
+    }
+}
+
+impl fmt::Display for BenchTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.provider {
+            Some(provider) => write!(f, "{}@{provider}", self.model),
+            None => f.write_str(&self.model),
+        }
+    }
+}
+
+/// How one task went with one target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskResult {
+    pub task: String,
+    pub target: BenchTarget,
+    pub passed: bool,
+    pub usage: TokenUsage,
+    /// At list prices; `None` for models without known prices.
+    pub cost: Option<f64>,
+    /// From the prompt to the end of the check.
+    pub duration: Duration,
+    /// Why the task did not get as far as its check.
+    pub error: Option<String>,
+}
+
+/// Run `task` with `target` in a fresh copy of its fixture. `cli_overrides`
+/// are the `-c` overrides applied to every run.
+pub async fn run_task(
+    task: &BenchTask,
+    target: &BenchTarget,
+    cli_overrides: Vec<(String, TomlValue)>,
+) -> TaskResult {
+    let start = Instant::now();
+    let mut usage = TokenUsage::default();
+    let workspace = std::env::temp_dir().join(format!("codex-bench-{}", Uuid::new_v4()));
+    let outcome = async {
+        match &task.fixture {
+            Some(fixture) => copy_dir(fixture, &workspace)
+                .with_context(|| format!("failed to copy {}", fixture.display()))?,
+            None => std::fs::create_dir_all(&workspace)?,
+        }
+        let config = Config::load_with_cli_overrides(
+            cli_overrides,
+            ConfigOverrides {
+                model: Some(target.model.clone()),
+                model_provider: target.provider.clone(),
+                cwd: Some(workspace.clone()),
+                approval_policy: Some(AskForApproval::Never),
+                sandbox_policy: Some(SandboxPolicy::new_full_auto_policy()),
+                ..Default::default()
+            },
+        )?;
+        let timeout = Duration::from_secs(task.timeout_secs);
+        tokio::time::timeout(
+            timeout,
+            run_prompt(config.clone(), &task.prompt, &mut usage),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out after {}s", task.timeout_secs))??;
+        let check = run_tests(&task.check, &config)
+            .await
+            .with_context(|| format!("failed to run `{}`", task.check))?;
+        anyhow::Ok(check.passed)
+    }
+    .await;
+    if let Err(e) = std::fs::remove_dir_all(&workspace) {
+        tracing::warn!("failed to remove {}: {e}", workspace.display());
+    }
+
+    TaskResult {
+        task: task.name.clone(),
+        target: target.clone(),
+        passed: matches!(outcome, Ok(true)),
+        cost: get_model_info(&target.model).map(|info| info.estimate_cost(&usage)),
+        usage,
+        duration: start.elapsed(),
+        error: outcome.err().map(|e| format!("{e:#}")),
+    }
+}
+
+/// Run `prompt` to completion, adding up the tokens it takes into `usage`.
+async fn run_prompt(config: Config, prompt: &str, usage: &mut TokenUsage) -> anyhow::Result<()> {
+    let (codex, _, _) = init_codex(config).await?;
+    let task_id = codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: prompt.to_string(),
+            }],
+            response_format: None,
+        })
+        .await?;
+    loop {
+        let event = codex.next_event().await?;
+        match event.msg {
+            EventMsg::TokenCount(count) => usage.add_assign(&count),
+            EventMsg::TaskComplete(_) => return Ok(()),
+            EventMsg::Error(error) if event.id == task_id => anyhow::bail!(error.message),
+            // Keep every edit, as a user would who approves them all.
+            EventMsg::TurnDiffApprovalRequest(request) => {
+                codex
+                    .submit(Op::TurnDiffApproval {
+                        id: event.id,
+                        approved: request.changes.into_keys().collect(),
+                    })
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// A table of every result, then the success rate, tokens, cost and time of
+/// each target.
+pub fn render_report(results: &[TaskResult]) -> String {
+    let mut report = format!(
+        "{:<24} {:<28} {:<6} {:>10} {:>9} {:>8}\n",
+        "task", "model", "result", "tokens", "cost", "time"
+    );
+    for result in results {
+        let verdict = if result.passed { "pass" } else { "fail" };
+        report.push_str(&format!(
+            "{:<24} {:<28} {verdict:<6} {:>10} {:>9} {:>7.1}s\n",
+            result.task,
+            result.target.to_string(),
+            result.usage.total_tokens,
+            format_cost(result.cost),
+            result.duration.as_secs_f64(),
+        ));
+        if let Some(error) = &result.error {
+            report.push_str(&format!("  error: {error}\n"));
+        }
+    }
+
+    let mut by_target: BTreeMap<&BenchTarget, Vec<&TaskResult>> = BTreeMap::new();
+    for result in results {
+        by_target.entry(&result.target).or_default().push(result);
+    }
+    report.push('\n');
+    for (target, results) in by_target {
+        let passed = results.iter().filter(|result| result.passed).count();
+        let tokens: u64 = results.iter().map(|result| result.usage.total_tokens).sum();
+        let cost = results
+            .iter()
+            .map(|result| result.cost)
+            .sum::<Option<f64>>();
+        let time: Duration = results.iter().map(|result| result.duration).sum();
+        report.push_str(&format!(
+            "{target}: {passed}/{} passed ({:.0}%), {tokens} tokens, {}, {:.1}s\n",
+            results.len(),
+            100.0 * passed as f64 / results.len() as f64,
+            format_cost(cost),
+            time.as_secs_f64(),
+        ));
+    }
+    report
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map_or_else(|| "n/a".to_string(), |cost| format!("${cost:.4}"))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(
+            "o3".parse::<BenchTarget>().unwrap(),
+            BenchTarget {
+                model: "o3".to_string(),
+                provider: None,
+            }
+        );
+        let local: BenchTarget = "qwen2.5-coder:7b@ollama".parse().unwrap();
+        assert_eq!(local.model, "qwen2.5-coder:7b");
+        assert_eq!(local.provider.as_deref(), Some("ollama"));
+        assert_eq!(local.to_string(), "qwen2.5-coder:7b@ollama");
+        assert!("o3@".parse::<BenchTarget>().is_err());
+    }
+
+    #[test]
+    fn loads_task_files() {
+        let suite = TempDir::new().unwrap();
+        std::fs::write(
+            suite.path().join("b-flag.toml"),
+            "prompt = \"Add a flag.\"\nfixture = \"fixtures/wc\"\ncheck = \"cargo test\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            suite.path().join("a-readme.toml"),
+            "prompt = \"Write a README.\"\ncheck = \"test -f README.md\"\ntimeout_secs = 60\n",
+        )
+        .unwrap();
+        std::fs::write(suite.path().join("notes.md"), "not a task").unwrap();
+
+        let tasks = load_suite(suite.path()).unwrap();
+        assert_eq!(
+            tasks,
+            vec![
+                BenchTask {
+                    name: "a-readme".to_string(),
+                    prompt: "Write a README.".to_string(),
+                    fixture: None,
+                    check: "test -f README.md".to_string(),
+                    timeout_secs: 60,
+                },
+                BenchTask {
+                    name: "b-flag".to_string(),
+                    prompt: "Add a flag.".to_string(),
+                    fixture: Some(suite.path().join("fixtures/wc")),
+                    check: "cargo test".to_string(),
+                    timeout_secs: 600,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn summarizes_each_target() {
+        let result = |task: &str, model: &str, passed: bool| TaskResult {
+            task: task.to_string(),
+            target: model.parse().unwrap(),
+            passed,
+            usage: TokenUsage {
+                total_tokens: 1000,
+                ..Default::default()
+            },
+            cost: get_model_info(model).map(|_| 0.01),
+            duration: Duration::from_secs(2),
+            error: None,
+        };
+        let report = render_report(&[
+            result("a", "o3", true),
+            result("b", "o3", false),
+            result("a", "local@ollama", true),
+        ]);
+        assert!(
+            report.contains("\nlocal@ollama: 1/1 passed (100%), 1000 tokens, n/a, 2.0s\n"),
+            "{report}"
+        );
+        assert!(
+            report.contains("\no3: 1/2 passed (50%), 2000 tokens, $0.0200, 4.0s\n"),
+            "{report}"
+        );
+    }
+}
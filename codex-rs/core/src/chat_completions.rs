@@ -14,26 +14,38 @@ use tokio::sync::mpsc;
 use tokio::time::timeout;
 use tracing::debug;
 use tracing::trace;
+use tracing::warn;
 
 use crate::ModelProviderInfo;
+use crate::auth::CredentialProvider;
 use crate::client_common::Prompt;
+use crate::client_common::Reasoning;
 use crate::client_common::ResponseEvent;
 use crate::client_common::ResponseStream;
+use crate::config_types::SamplingParams;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::flags::OPENAI_REQUEST_MAX_RETRIES;
 use crate::flags::OPENAI_STREAM_IDLE_TIMEOUT_MS;
 use crate::models::ContentItem;
+use crate::models::ReasoningItemReasoningSummary;
 use crate::models::ResponseItem;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::rate_limit::estimate_tokens;
+use crate::rate_limit::limiter_for;
+use crate::structured_output::SCHEMA_NAME;
 use crate::util::backoff;
 
 /// Implementation for the classic Chat Completions API.
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
     model: &str,
+    reasoning: Option<&Reasoning>,
+    sampling: &SamplingParams,
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
+    credential_provider: &dyn CredentialProvider,
 ) -> Result<ResponseStream> {
     // Build messages array
     let mut messages = Vec::<serde_json::Value>::new();
@@ -44,17 +56,7 @@ pub(crate) async fn stream_chat_completions(
     for item in &prompt.input {
         match item {
             ResponseItem::Message { role, content } => {
-                let mut text = String::new();
-                for c in content {
-                    match c {
-                        ContentItem::InputText { text: t }
-                        | ContentItem::OutputText { text: t } => {
-                            text.push_str(t);
-                        }
-                        _ => {}
-                    }
-                }
-                messages.push(json!({"role": role, "content": text}));
+                messages.push(json!({"role": role, "content": chat_message_content(content)}));
             }
             ResponseItem::FunctionCall {
                 name,
@@ -106,63 +108,84 @@ pub(crate) async fn stream_chat_completions(
         }
     }
 
+    let has_images = prompt.input.iter().any(|item| {
+        matches!(item, ResponseItem::Message { content, .. }
+            if content.iter().any(|c| matches!(c, ContentItem::InputImage { .. })))
+    });
+
     let tools_json = create_tools_json_for_chat_completions_api(prompt, model)?;
-    let payload = json!({
+    let mut payload = json!({
         "model": model,
         "messages": messages,
         "stream": true,
+        "stream_options": {"include_usage": true},
         "tools": tools_json,
     });
+    if let Some(format) = prompt.response_format_for(provider) {
+        payload["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": { "name": SCHEMA_NAME, "schema": format.schema },
+        });
+    }
+    // Chat Completions takes the effort only; summaries are a Responses API
+    // feature.
+    if let Some(reasoning) = reasoning {
+        payload["reasoning_effort"] = json!(reasoning.effort);
+    }
+    if let (Some(payload), serde_json::Value::Object(sampling)) =
+        (payload.as_object_mut(), json!(sampling))
+    {
+        payload.extend(sampling);
+    }
 
-    let base_url = provider.base_url.trim_end_matches('/');
-    let url = format!("{}/chat/completions", base_url);
+    let credentials = credential_provider.credentials(provider).await?;
+    let default_url = format!(
+        "{}/chat/completions",
+        provider.base_url.trim_end_matches('/')
+    );
+    let mut url = match credentials.as_ref().and_then(|c| c.base_url.as_deref()) {
+        Some(base_url) => format!("{base_url}/chat/completions"),
+        None => default_url.clone(),
+    };
 
     debug!(url, "POST (chat)");
-    
-    // Special debug logging for Copilot
-    if provider.name == "GitHub Copilot" {
-        println!("🔧 [Copilot Debug] Request URL: {}", url);
-        println!("🔧 [Copilot Debug] Model: {}", model);
-        println!("🔧 [Copilot Debug] Tools count: {}", tools_json.len());
-        println!("🔧 [Copilot Debug] Tools: {}", serde_json::to_string_pretty(&tools_json).unwrap_or_default());
-        println!("🔧 [Copilot Debug] Messages count: {}", messages.len());
-        for (i, msg) in messages.iter().enumerate() {
-            println!("🔧 [Copilot Debug] Message {}: {}", i, serde_json::to_string(msg).unwrap_or_default());
-        }
-    }
-    
+
     trace!(
-        "request payload: {}",
-    debug!(
         "POST to {url}: {}",
         serde_json::to_string_pretty(&payload).unwrap_or_default()
     );
 
-    let api_key = provider.api_key()?;
+    let limiter = limiter_for(provider);
+    let tokens = estimate_tokens(prompt);
     let mut attempt = 0;
     loop {
         attempt += 1;
 
         let mut req_builder = client.post(&url);
-        if let Some(api_key) = &api_key {
-            req_builder = req_builder.bearer_auth(api_key.clone());
+        if let Some(credentials) = &credentials {
+            req_builder = req_builder.bearer_auth(&credentials.token);
         }
-        
+
         // Add Copilot-specific headers if this is a Copilot provider
         if provider.name == "GitHub Copilot" {
-            println!("🔧 [Copilot Debug] Adding Copilot headers");
             req_builder = req_builder
-                .header("Editor-Version", format!("Codex/{}", env!("CARGO_PKG_VERSION")))
+                .header(
+                    "Editor-Version",
+                    format!("Codex/{}", env!("CARGO_PKG_VERSION")),
+                )
                 .header("Copilot-Integration-Id", "vscode-chat")
-                .header("Copilot-Vision-Request", "false");
+                .header("Copilot-Vision-Request", has_images.to_string());
         }
-        
+
         let res = req_builder
             .header(reqwest::header::ACCEPT, "text/event-stream")
             .json(&payload)
             .send()
             .await;
 
+        if let Ok(resp) = &res {
+            limiter.observe(resp.headers());
+        }
         match res {
             Ok(resp) if resp.status().is_success() => {
                 let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(16);
@@ -190,17 +213,61 @@ pub(crate) async fn stream_chat_completions(
                 let delay = retry_after_secs
                     .map(|s| Duration::from_millis(s * 1_000))
                     .unwrap_or_else(|| backoff(attempt));
-                tokio::time::sleep(delay).await;
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    limiter.pause(delay);
+                }
+                // Retries count against the rate limit like any request.
+                tokio::time::sleep(delay.max(limiter.reserve(tokens))).await;
+            }
+            // The token's proxy may not be reachable from this network; the
+            // provider's own endpoint takes the token too.
+            Err(e) if url != default_url => {
+                warn!("cannot reach {url}, falling back to {default_url}: {e}");
+                url = default_url.clone();
             }
             Err(e) => {
                 if attempt > *OPENAI_REQUEST_MAX_RETRIES {
                     return Err(e.into());
                 }
                 let delay = backoff(attempt);
-                tokio::time::sleep(delay).await;
+                tokio::time::sleep(delay.max(limiter.reserve(tokens))).await;
+            }
+        }
+    }
+}
+
+/// Converts message content into the Chat Completions `content` field. Plain
+/// text messages are sent as a single string; messages that carry images use
+/// the multimodal array form with `text` and `image_url` parts.
+fn chat_message_content(content: &[ContentItem]) -> serde_json::Value {
+    let has_images = content
+        .iter()
+        .any(|c| matches!(c, ContentItem::InputImage { .. }));
+    if !has_images {
+        let mut text = String::new();
+        for c in content {
+            match c {
+                ContentItem::InputText { text: t } | ContentItem::OutputText { text: t } => {
+                    text.push_str(t);
+                }
+                ContentItem::InputImage { .. } => {}
             }
         }
+        return json!(text);
     }
+
+    let parts: Vec<serde_json::Value> = content
+        .iter()
+        .map(|c| match c {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                json!({"type": "text", "text": text})
+            }
+            ContentItem::InputImage { image_url } => {
+                json!({"type": "image_url", "image_url": {"url": image_url}})
+            }
+        })
+        .collect();
+    json!(parts)
 }
 
 /// Lightweight SSE processor for the Chat Completions streaming format. The
@@ -229,6 +296,16 @@ where
 
     let mut fn_call_state = FunctionCallState::default();
 
+    // With `stream_options.include_usage` the usage totals arrive in a final
+    // chunk (with an empty `choices` array) after the `finish_reason` chunk,
+    // so we keep reading until `[DONE]` or the end of the stream.
+    let mut token_usage: Option<TokenUsage> = None;
+
+    // Providers that stream their reasoning (e.g. DeepSeek) send it as
+    // `reasoning_content` before the answer. It is forwarded as deltas and
+    // then as a `Reasoning` item once the answer starts.
+    let mut reasoning = String::new();
+
     loop {
         let sse = match timeout(idle_timeout, stream.next()).await {
             Ok(Some(Ok(ev))) => ev,
@@ -241,6 +318,7 @@ where
                 let _ = tx_event
                     .send(Ok(ResponseEvent::Completed {
                         response_id: String::new(),
+                        token_usage,
                     }))
                     .await;
                 return;
@@ -258,6 +336,7 @@ where
             let _ = tx_event
                 .send(Ok(ResponseEvent::Completed {
                     response_id: String::new(),
+                    token_usage,
                 }))
                 .await;
             return;
@@ -270,15 +349,50 @@ where
         };
         trace!("chat_completions received SSE chunk: {chunk:?}");
 
+        if let Some(usage) = chunk.get("usage").filter(|u| !u.is_null()) {
+            token_usage = parse_chat_usage(usage);
+        }
+
         let choice_opt = chunk.get("choices").and_then(|c| c.get(0));
 
         if let Some(choice) = choice_opt {
+            if let Some(delta) = choice
+                .get("delta")
+                .and_then(|d| d.get("reasoning_content"))
+                .and_then(|c| c.as_str())
+                .filter(|c| !c.is_empty())
+            {
+                reasoning.push_str(delta);
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::ReasoningSummaryDelta(delta.to_string())))
+                    .await;
+            }
+
+            let answer_started = choice.get("finish_reason").is_some_and(|r| !r.is_null())
+                || choice.get("delta").is_some_and(|d| {
+                    d.get("content").is_some_and(|c| !c.is_null())
+                        || d.get("tool_calls").is_some_and(|t| !t.is_null())
+                });
+            if answer_started && !reasoning.is_empty() {
+                let item = ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                        text: std::mem::take(&mut reasoning),
+                    }],
+                };
+                let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+            }
+
             // Handle assistant content tokens.
             if let Some(content) = choice
                 .get("delta")
                 .and_then(|d| d.get("content"))
                 .and_then(|c| c.as_str())
             {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::OutputTextDelta(content.to_string())))
+                    .await;
+
                 let item = ResponseItem::Message {
                     role: "assistant".to_string(),
                     content: vec![ContentItem::OutputText {
@@ -339,28 +453,44 @@ where
                     _ => {}
                 }
 
-                // Emit Completed regardless of reason so the agent can advance.
-                let _ = tx_event
-                    .send(Ok(ResponseEvent::Completed {
-                        response_id: String::new(),
-                    }))
-                    .await;
-
-                // Prepare for potential next turn (should not happen in same stream).
-                // fn_call_state = FunctionCallState::default();
-
-                return; // End processing for this SSE stream.
+                // Completed is emitted once the stream ends (see above) so
+                // that a trailing usage chunk is not lost.
+                fn_call_state = FunctionCallState::default();
             }
         }
     }
 }
 
+/// Maps the Chat Completions `usage` object onto [`TokenUsage`].
+fn parse_chat_usage(usage: &serde_json::Value) -> Option<TokenUsage> {
+    let input_tokens = usage.get("prompt_tokens")?.as_u64()?;
+    let output_tokens = usage.get("completion_tokens")?.as_u64()?;
+    let total_tokens = usage
+        .get("total_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(input_tokens + output_tokens);
+    Some(TokenUsage {
+        input_tokens,
+        cached_input_tokens: usage
+            .get("prompt_tokens_details")
+            .and_then(|d| d.get("cached_tokens"))
+            .and_then(|v| v.as_u64()),
+        output_tokens,
+        reasoning_output_tokens: usage
+            .get("completion_tokens_details")
+            .and_then(|d| d.get("reasoning_tokens"))
+            .and_then(|v| v.as_u64()),
+        total_tokens,
+    })
+}
+
 /// Optional client-side aggregation helper
 ///
 /// Stream adapter that merges the incremental `OutputItemDone` chunks coming from
 /// [`process_chat_sse`] into a *running* assistant message, **suppressing the
-/// per-token deltas**.  The stream stays silent while the model is thinking
-/// and only emits two events per turn:
+/// per-token items**.  Apart from the `OutputTextDelta` and
+/// `ReasoningSummaryDelta` events, which are passed through for live display,
+/// and non-message items such as tool calls, it emits two events per turn:
 ///
 ///   1. `ResponseEvent::OutputItemDone` with the *complete* assistant message
 ///      (fully concatenated).
@@ -420,7 +550,18 @@ where
                     // Not an assistant message – forward immediately.
                     return Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone(item))));
                 }
-                Poll::Ready(Some(Ok(ResponseEvent::Completed { response_id }))) => {
+                // Deltas are forwarded untouched so front-ends can render the
+                // message as it streams.
+                Poll::Ready(Some(Ok(
+                    event @ (ResponseEvent::OutputTextDelta(_)
+                    | ResponseEvent::ReasoningSummaryDelta(_)),
+                ))) => {
+                    return Poll::Ready(Some(Ok(event)));
+                }
+                Poll::Ready(Some(Ok(ResponseEvent::Completed {
+                    response_id,
+                    token_usage,
+                }))) => {
                     if !this.cumulative.is_empty() {
                         let aggregated_item = crate::models::ResponseItem::Message {
                             role: "assistant".to_string(),
@@ -430,7 +571,10 @@ where
                         };
 
                         // Buffer Completed so it is returned *after* the aggregated message.
-                        this.pending_completed = Some(ResponseEvent::Completed { response_id });
+                        this.pending_completed = Some(ResponseEvent::Completed {
+                            response_id,
+                            token_usage,
+                        });
 
                         return Poll::Ready(Some(Ok(ResponseEvent::OutputItemDone(
                             aggregated_item,
@@ -438,7 +582,10 @@ where
                     }
 
                     // Nothing aggregated – forward Completed directly.
-                    return Poll::Ready(Some(Ok(ResponseEvent::Completed { response_id })));
+                    return Poll::Ready(Some(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage,
+                    })));
                 } // No other `Ok` variants exist at the moment, continue polling.
             }
         }
@@ -452,6 +599,7 @@ pub(crate) trait AggregateStreamExt: Stream<Item = Result<ResponseEvent>> + Size
     /// `ResponseEvent` sequence for a typical text turn looks like:
     ///
     /// ```ignore
+    ///     OutputTextDelta(<chunk>)   // zero or more
     ///     OutputItemDone(<full message>)
     ///     Completed { .. }
     /// ```
@@ -476,3 +624,80 @@ pub(crate) trait AggregateStreamExt: Stream<Item = Result<ResponseEvent>> + Size
 }
 
 impl<T> AggregateStreamExt for T where T: Stream<Item = Result<ResponseEvent>> + Sized {}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn text_only_content_is_a_plain_string() {
+        let content = vec![ContentItem::InputText {
+            text: "hello".to_string(),
+        }];
+        assert_eq!(chat_message_content(&content), json!("hello"));
+    }
+
+    #[test]
+    fn image_content_uses_multimodal_parts() {
+        let content = vec![
+            ContentItem::InputText {
+                text: "fix this".to_string(),
+            },
+            ContentItem::InputImage {
+                image_url: "data:image/png;base64,AAAA".to_string(),
+            },
+        ];
+        assert_eq!(
+            chat_message_content(&content),
+            json!([
+                {"type": "text", "text": "fix this"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_reasoning_precedes_the_answer() {
+        let chunks = [
+            json!({"choices": [{"delta": {"role": "assistant", "content": null, "reasoning_content": "Check "}}]}),
+            json!({"choices": [{"delta": {"content": null, "reasoning_content": "the tests."}}]}),
+            json!({"choices": [{"delta": {"content": "Done.", "reasoning_content": null}}]}),
+            json!({"choices": [{"delta": {}, "finish_reason": "stop"}]}),
+        ];
+        let body: String = chunks
+            .iter()
+            .map(|chunk| format!("data: {chunk}\n\n"))
+            .chain(["data: [DONE]\n\n".to_string()])
+            .collect();
+        let (tx_event, rx_event) = mpsc::channel(16);
+        let stream = futures::stream::iter([Ok(Bytes::from(body))]);
+        process_chat_sse(stream, tx_event).await;
+
+        let events: Vec<String> = ResponseStream { rx_event }
+            .aggregate()
+            .map(|event| match event.unwrap() {
+                ResponseEvent::ReasoningSummaryDelta(delta) => format!("reasoning delta {delta}"),
+                ResponseEvent::OutputTextDelta(delta) => format!("delta {delta}"),
+                ResponseEvent::OutputItemDone(ResponseItem::Reasoning { summary, .. }) => {
+                    let ReasoningItemReasoningSummary::SummaryText { text } = &summary[0];
+                    format!("reasoning {text}")
+                }
+                ResponseEvent::OutputItemDone(item) => format!("item {item:?}"),
+                ResponseEvent::Completed { .. } => "completed".to_string(),
+            })
+            .collect()
+            .await;
+        assert_eq!(
+            events,
+            vec![
+                "reasoning delta Check ",
+                "reasoning delta the tests.",
+                "reasoning Check the tests.",
+                "delta Done.",
+                r#"item Message { role: "assistant", content: [OutputText { text: "Done." }] }"#,
+                "completed",
+            ]
+        );
+    }
+}
@@ -0,0 +1,492 @@
+//! Sign-in with a ChatGPT account: the OAuth authorization code flow with
+//! PKCE, the browser counterpart of the Copilot device flow in
+//! [`crate::copilot`].
+//!
+//! Codex listens on `localhost:1455`, opens the OpenAI sign-in page in the
+//! browser, and receives the authorization code on `/auth/callback`. The code
+//! is exchanged for tokens, and the id token for an OpenAI API key, which are
+//! saved to `CODEX_HOME/auth.json`. The OpenAI provider falls back to that key
+//! when `OPENAI_API_KEY` is not set. Progress is reported as
+//! `ChatGptAuthStarted` and `ChatGptAuthComplete` events.
+
+use std::time::Duration;
+
+use async_channel::Sender;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::protocol::ChatGptAuthCompleteEvent;
+use crate::protocol::ChatGptAuthStartedEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::token_store::TokenFile;
+
+pub(crate) const ISSUER: &str = "https://auth.openai.com";
+const CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
+/// The redirect URI registered for the client uses this port.
+const CALLBACK_PORT: u16 = 1455;
+const CALLBACK_PATH: &str = "/auth/callback";
+pub(crate) const AUTH_FILE: &str = "auth.json";
+/// How long to wait for the user to finish signing in in the browser.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Tokens saved by `codex login`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatGptTokens {
+    pub id_token: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// OpenAI API key minted from the id token; `None` when the account has
+    /// no API organization to mint it in.
+    pub api_key: Option<String>,
+    /// Unix time at which the tokens were obtained.
+    pub last_refresh: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+    access_token: String,
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct ApiKeyResponse {
+    access_token: String,
+}
+
+struct Pkce {
+    verifier: String,
+    challenge: String,
+}
+
+impl Pkce {
+    fn new() -> Self {
+        Self::from_verifier(URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>()))
+    }
+
+    fn from_verifier(verifier: String) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+/// Run the sign-in and save the tokens, reporting progress on `tx_event`
+/// with `sub_id`. Errors are reported in the `ChatGptAuthComplete` event;
+/// only a failure to send it is returned.
+pub async fn handle_chatgpt_auth(
+    client: reqwest::Client,
+    tx_event: Sender<Event>,
+    sub_id: String,
+) -> Result<(), CodexErr> {
+    let result = match TcpListener::bind(("127.0.0.1", CALLBACK_PORT)).await {
+        Ok(listener) => sign_in(&client, ISSUER, listener, &tx_event, &sub_id).await,
+        Err(e) => Err(format!(
+            "Could not listen on localhost:{CALLBACK_PORT} for the sign-in callback: {e}"
+        )),
+    };
+    let (success, message) = match result {
+        Ok(tokens) => match save_chatgpt_tokens(&tokens) {
+            Ok(()) if tokens.api_key.is_some() => (
+                true,
+                "Signed in to ChatGPT; Codex will use your OpenAI API key".to_string(),
+            ),
+            Ok(()) => (
+                true,
+                "Signed in to ChatGPT, but the account has no API organization to create an \
+                 API key in; set OPENAI_API_KEY instead"
+                    .to_string(),
+            ),
+            Err(e) => (false, format!("Could not save the tokens: {e}")),
+        },
+        Err(message) => (false, message),
+    };
+    let event = Event {
+        id: sub_id,
+        msg: EventMsg::ChatGptAuthComplete(ChatGptAuthCompleteEvent { success, message }),
+    };
+    tx_event
+        .send(event)
+        .await
+        .map_err(|_| CodexErr::InternalAgentDied)
+}
+
+/// Run the sign-in outside a session, as `codex login` does.
+pub async fn run_login(config: &Config, tx_event: Sender<Event>) -> Result<(), CodexErr> {
+    let client = crate::http_client::create_client(config)?;
+    handle_chatgpt_auth(client, tx_event, String::new()).await
+}
+
+pub fn load_chatgpt_tokens() -> anyhow::Result<Option<ChatGptTokens>> {
+    TokenFile::in_codex_home(AUTH_FILE)?.load()
+}
+
+fn save_chatgpt_tokens(tokens: &ChatGptTokens) -> anyhow::Result<()> {
+    TokenFile::in_codex_home(AUTH_FILE)?.save(tokens)
+}
+
+async fn sign_in(
+    client: &reqwest::Client,
+    issuer: &str,
+    listener: TcpListener,
+    tx_event: &Sender<Event>,
+    sub_id: &str,
+) -> Result<ChatGptTokens, String> {
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let redirect_uri = format!("http://localhost:{port}{CALLBACK_PATH}");
+    let pkce = Pkce::new();
+    let state = URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+    let auth_url = authorize_url(issuer, &redirect_uri, &pkce, &state);
+
+    let event = Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::ChatGptAuthStarted(ChatGptAuthStartedEvent {
+            auth_url: auth_url.clone(),
+        }),
+    };
+    tx_event.send(event).await.ok();
+    let _ = crate::copilot::open_browser(&auth_url);
+
+    let code = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_callback(&listener, &state))
+        .await
+        .map_err(|_| "Sign-in timed out".to_string())??;
+    exchange_code(client, issuer, &code, &redirect_uri, &pkce)
+        .await
+        .map_err(|e| format!("Could not exchange the authorization code: {e}"))
+}
+
+fn authorize_url(issuer: &str, redirect_uri: &str, pkce: &Pkce, state: &str) -> String {
+    let params = [
+        ("response_type", "code"),
+        ("client_id", CLIENT_ID),
+        ("redirect_uri", redirect_uri),
+        ("scope", "openid profile email offline_access"),
+        ("code_challenge", pkce.challenge.as_str()),
+        ("code_challenge_method", "S256"),
+        ("id_token_add_organizations", "true"),
+        ("state", state),
+    ];
+    Url::parse_with_params(&format!("{issuer}/oauth/authorize"), params)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// Serve the callback until a request for it arrives, and return its
+/// authorization code.
+async fn wait_for_callback(listener: &TcpListener, state: &str) -> Result<String, String> {
+    loop {
+        let (mut stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
+        let Some(request_line) = read_request_line(&mut stream).await else {
+            continue;
+        };
+        match parse_callback(&request_line, state) {
+            None => respond(&mut stream, "404 Not Found", "Not found").await,
+            Some(Ok(code)) => {
+                respond(
+                    &mut stream,
+                    "200 OK",
+                    "Signed in to Codex. You can close this tab.",
+                )
+                .await;
+                return Ok(code);
+            }
+            Some(Err(message)) => {
+                respond(&mut stream, "400 Bad Request", &message).await;
+                return Err(message);
+            }
+        }
+    }
+}
+
+async fn read_request_line(stream: &mut TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(2).any(|w| w == b"\r\n") && request.len() < 8192 {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    request.lines().next().map(str::to_string)
+}
+
+/// `None` when the request is not for the callback (e.g. a favicon), else
+/// the authorization code or why the sign-in failed.
+fn parse_callback(request_line: &str, state: &str) -> Option<Result<String, String>> {
+    let mut parts = request_line.split_whitespace();
+    let (Some("GET"), Some(target)) = (parts.next(), parts.next()) else {
+        return None;
+    };
+    let url = Url::parse(&format!("http://localhost{target}")).ok()?;
+    if url.path() != CALLBACK_PATH {
+        return None;
+    }
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    if param("state").as_deref() != Some(state) {
+        return Some(Err(
+            "The sign-in callback did not match this login attempt".to_string()
+        ));
+    }
+    if let Some(error) = param("error") {
+        let description = param("error_description").unwrap_or_default();
+        return Some(Err(format!("Sign-in failed: {error} {description}")
+            .trim_end()
+            .to_string()));
+    }
+    Some(param("code").ok_or_else(|| "The sign-in callback carried no code".to_string()))
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = format!("<!doctype html><title>Codex</title><p>{message}</p>");
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+async fn exchange_code(
+    client: &reqwest::Client,
+    issuer: &str,
+    code: &str,
+    redirect_uri: &str,
+    pkce: &Pkce,
+) -> Result<ChatGptTokens, CodexErr> {
+    let token_url = format!("{issuer}/oauth/token");
+    let response = client
+        .post(&token_url)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", CLIENT_ID),
+            ("code_verifier", pkce.verifier.as_str()),
+        ])
+        .send()
+        .await?;
+    let tokens: TokenResponse = json_or_status(response).await?;
+    let api_key = mint_api_key(client, &token_url, &tokens.id_token).await?;
+    Ok(ChatGptTokens {
+        id_token: tokens.id_token,
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        api_key,
+        last_refresh: now(),
+    })
+}
+
+/// Trade the refresh token for new tokens and mint a new API key from them.
+pub(crate) async fn refresh_tokens(
+    client: &reqwest::Client,
+    issuer: &str,
+    tokens: &ChatGptTokens,
+) -> Result<ChatGptTokens, CodexErr> {
+    #[derive(Deserialize)]
+    struct RefreshResponse {
+        id_token: Option<String>,
+        access_token: Option<String>,
+        refresh_token: Option<String>,
+    }
+
+    let token_url = format!("{issuer}/oauth/token");
+    let response = client
+        .post(&token_url)
+        .form(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", CLIENT_ID),
+            ("refresh_token", tokens.refresh_token.as_str()),
+            ("scope", "openid profile email"),
+        ])
+        .send()
+        .await?;
+    let refreshed: RefreshResponse = json_or_status(response).await?;
+    let id_token = refreshed
+        .id_token
+        .unwrap_or_else(|| tokens.id_token.clone());
+    let api_key = mint_api_key(client, &token_url, &id_token)
+        .await?
+        .or_else(|| tokens.api_key.clone());
+    Ok(ChatGptTokens {
+        id_token,
+        access_token: refreshed
+            .access_token
+            .unwrap_or_else(|| tokens.access_token.clone()),
+        refresh_token: refreshed
+            .refresh_token
+            .unwrap_or_else(|| tokens.refresh_token.clone()),
+        api_key,
+        last_refresh: now(),
+    })
+}
+
+/// Mint an API key from the id token, so requests go to the regular OpenAI
+/// API. `None` when the account has no API organization.
+async fn mint_api_key(
+    client: &reqwest::Client,
+    token_url: &str,
+    id_token: &str,
+) -> Result<Option<String>, CodexErr> {
+    let response = client
+        .post(token_url)
+        .form(&[
+            (
+                "grant_type",
+                "urn:ietf:params:oauth:grant-type:token-exchange",
+            ),
+            ("client_id", CLIENT_ID),
+            ("requested_token", "openai-api-key"),
+            ("subject_token", id_token),
+            (
+                "subject_token_type",
+                "urn:ietf:params:oauth:token-type:id_token",
+            ),
+        ])
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        tracing::warn!("could not create an API key: {}", response.status());
+        return Ok(None);
+    }
+    Ok(Some(response.json::<ApiKeyResponse>().await?.access_token))
+}
+
+async fn json_or_status<T: serde::de::DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T, CodexErr> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CodexErr::UnexpectedStatus(
+            status,
+            response.text().await.unwrap_or_default(),
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_string_contains;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    #[test]
+    fn pkce_challenge_is_the_sha256_of_the_verifier() {
+        // The example from RFC 7636, appendix B.
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string());
+        assert_eq!(
+            pkce.challenge,
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn parses_the_callback_request() {
+        let line = |target: &str| format!("GET {target} HTTP/1.1");
+        assert_eq!(
+            parse_callback(&line("/auth/callback?code=abc%2B1&state=s1"), "s1"),
+            Some(Ok("abc+1".to_string()))
+        );
+        assert_eq!(parse_callback(&line("/favicon.ico"), "s1"), None);
+        assert!(matches!(
+            parse_callback(&line("/auth/callback?code=abc&state=other"), "s1"),
+            Some(Err(_))
+        ));
+        assert_eq!(
+            parse_callback(&line("/auth/callback?error=access_denied&state=s1"), "s1"),
+            Some(Err("Sign-in failed: access_denied".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn signs_in_through_the_local_callback() {
+        let issuer = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_string_contains("grant_type=authorization_code"))
+            .and(body_string_contains("code=the-code"))
+            .and(body_string_contains("code_verifier="))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id_token": "id",
+                "access_token": "access",
+                "refresh_token": "refresh",
+            })))
+            .mount(&issuer)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_string_contains("requested_token=openai-api-key"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "access_token": "sk-minted" })),
+            )
+            .mount(&issuer)
+            .await;
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let client = reqwest::Client::new();
+        let issuer_uri = issuer.uri();
+        let login =
+            tokio::spawn(
+                async move { sign_in(&client, &issuer_uri, listener, &tx_event, "sub").await },
+            );
+
+        let EventMsg::ChatGptAuthStarted(started) = rx_event.recv().await.unwrap().msg else {
+            panic!("expected ChatGptAuthStarted");
+        };
+        let auth_url = Url::parse(&started.auth_url).unwrap();
+        let param = |name: &str| {
+            auth_url
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .unwrap()
+        };
+        // What the browser does after the user signs in.
+        let callback = format!(
+            "{}?code=the-code&state={}",
+            param("redirect_uri"),
+            param("state")
+        );
+        let response = reqwest::get(callback).await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        let tokens = login.await.unwrap().unwrap();
+        assert_eq!(tokens.refresh_token, "refresh");
+        assert_eq!(tokens.api_key.as_deref(), Some("sk-minted"));
+    }
+}
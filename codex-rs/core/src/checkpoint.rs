@@ -0,0 +1,142 @@
+//! Checkpoints of a session, which `Op::Rewind` takes the conversation and
+//! the workspace back to.
+//!
+//! A checkpoint is taken at the start of every task and on `Op::Checkpoint`.
+//! It holds the conversation as it was then and, like the staging area of
+//! turn reviews, the content each file had before the agent first patched it
+//! after the checkpoint. Rewinding restores the files staged by the later
+//! checkpoints as well, newest first. Changes made by shell commands are not
+//! tracked.
+
+use std::path::PathBuf;
+
+use codex_apply_patch::ApplyPatchAction;
+
+use crate::conversation_history::ConversationHistory;
+use crate::models::ResponseItem;
+use crate::turn_review::StagedEdits;
+
+/// What the session carries into its next model request.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConversationSnapshot {
+    pub transcript: Option<ConversationHistory>,
+    pub previous_response_id: Option<String>,
+    pub compacted_summary: Option<ResponseItem>,
+    pub interrupted_turn: Vec<ResponseItem>,
+    pub resumed_history: Vec<ResponseItem>,
+}
+
+#[derive(Debug, Clone)]
+struct Checkpoint {
+    conversation: ConversationSnapshot,
+    edits: StagedEdits,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Checkpoints {
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl Checkpoints {
+    /// Take a checkpoint of `conversation` and of the workspace as it is now;
+    /// returns its number.
+    pub(crate) fn create(&mut self, conversation: ConversationSnapshot) -> usize {
+        self.checkpoints.push(Checkpoint {
+            conversation,
+            edits: StagedEdits::default(),
+        });
+        self.checkpoints.len()
+    }
+
+    /// Remember the content of every file `action` is about to touch, for
+    /// rewinding to the latest checkpoint. Call before the patch is applied.
+    pub(crate) fn stage(&mut self, action: &ApplyPatchAction) {
+        if let Some(latest) = self.checkpoints.last_mut() {
+            latest.edits.stage(action);
+        }
+    }
+
+    /// Restore the files patched since checkpoint `number` and drop the
+    /// checkpoints after it. Returns the conversation to go back to and the
+    /// restored files.
+    pub(crate) fn rewind(
+        &mut self,
+        number: usize,
+    ) -> Result<(ConversationSnapshot, Vec<PathBuf>), String> {
+        if number == 0 || number > self.checkpoints.len() {
+            return Err(format!(
+                "no checkpoint {number}; the session has {}",
+                self.checkpoints.len()
+            ));
+        }
+        let mut restored = Vec::new();
+        let later = self.checkpoints.split_off(number);
+        let target = &mut self.checkpoints[number - 1];
+        let edits = later
+            .into_iter()
+            .rev()
+            .map(|checkpoint| checkpoint.edits)
+            .chain([std::mem::take(&mut target.edits)]);
+        for edits in edits {
+            let reverted = edits
+                .revert_rejected(&[])
+                .map_err(|e| format!("failed to restore the files: {e}"))?;
+            restored.extend(reverted);
+        }
+        restored.sort();
+        restored.dedup();
+        Ok((target.conversation.clone(), restored))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Stage a patch of `path` the way the session does before applying it.
+    fn stage(checkpoints: &mut Checkpoints, path: &Path) {
+        checkpoints.stage(&ApplyPatchAction::new_add_for_test(path, String::new()));
+    }
+
+    fn snapshot(previous_response_id: &str) -> ConversationSnapshot {
+        ConversationSnapshot {
+            previous_response_id: Some(previous_response_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rewinding_restores_files_patched_after_the_checkpoint() {
+        let dir = TempDir::new().unwrap();
+        let lib = dir.path().join("lib.rs");
+        let added = dir.path().join("added.rs");
+        std::fs::write(&lib, "v1\n").unwrap();
+
+        let mut checkpoints = Checkpoints::default();
+        assert_eq!(checkpoints.create(snapshot("resp_1")), 1);
+        stage(&mut checkpoints, &lib);
+        std::fs::write(&lib, "v2\n").unwrap();
+
+        assert_eq!(checkpoints.create(snapshot("resp_2")), 2);
+        stage(&mut checkpoints, &lib);
+        stage(&mut checkpoints, &added);
+        std::fs::write(&lib, "v3\n").unwrap();
+        std::fs::write(&added, "new\n").unwrap();
+
+        let (conversation, restored) = checkpoints.rewind(1).unwrap();
+        assert_eq!(conversation.previous_response_id.as_deref(), Some("resp_1"));
+        assert_eq!(restored, vec![added.clone(), lib.clone()]);
+        assert_eq!(std::fs::read_to_string(&lib).unwrap(), "v1\n");
+        assert!(!added.exists());
+
+        assert_eq!(
+            checkpoints.rewind(2).unwrap_err(),
+            "no checkpoint 2; the session has 1"
+        );
+        assert_eq!(checkpoints.rewind(1).unwrap().1, Vec::<PathBuf>::new());
+    }
+}
@@ -1,5 +1,6 @@
 use std::io::BufRead;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::Bytes;
@@ -16,6 +17,7 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::auth::CredentialProvider;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -25,16 +27,23 @@ use crate::client_common::ResponsesApiRequest;
 use crate::client_common::create_reasoning_param_for_request;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::SamplingParams;
 use crate::error::CodexErr;
-use crate::error::EnvVarError;
 use crate::error::Result;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
 use crate::flags::OPENAI_REQUEST_MAX_RETRIES;
 use crate::flags::OPENAI_STREAM_IDLE_TIMEOUT_MS;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::WireApi;
+use crate::models::ContentItem;
 use crate::models::ResponseItem;
 use crate::openai_tools::create_tools_json_for_responses_api;
+use crate::protocol::TokenUsage;
+use crate::rate_limit::BYTES_PER_TOKEN;
+use crate::rate_limit::estimate_tokens;
+use crate::rate_limit::limiter_for;
+use crate::replay;
+use crate::replay::is_replaying;
 use crate::util::backoff;
 
 #[derive(Clone)]
@@ -44,6 +53,8 @@ pub struct ModelClient {
     provider: ModelProviderInfo,
     effort: ReasoningEffortConfig,
     summary: ReasoningSummaryConfig,
+    sampling: SamplingParams,
+    credentials: Arc<dyn CredentialProvider>,
 }
 
 impl ModelClient {
@@ -52,27 +63,170 @@ impl ModelClient {
         provider: ModelProviderInfo,
         effort: ReasoningEffortConfig,
         summary: ReasoningSummaryConfig,
+        sampling: SamplingParams,
+        client: reqwest::Client,
+        credentials: Arc<dyn CredentialProvider>,
     ) -> Self {
         Self {
             model: model.to_string(),
-            client: reqwest::Client::new(),
+            client,
             provider,
             effort,
             summary,
+            sampling,
+            credentials,
         }
     }
 
+    pub fn provider(&self) -> &ModelProviderInfo {
+        &self.provider
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn effort(&self) -> ReasoningEffortConfig {
+        self.effort
+    }
+
+    pub fn summary(&self) -> ReasoningSummaryConfig {
+        self.summary
+    }
+
+    pub(crate) fn sampling(&self) -> &SamplingParams {
+        &self.sampling
+    }
+
+    /// A client for the same provider that queries `model` instead.
+    pub(crate) fn with_model(
+        &self,
+        model: String,
+        effort: ReasoningEffortConfig,
+        summary: ReasoningSummaryConfig,
+    ) -> Self {
+        Self {
+            model,
+            effort,
+            summary,
+            ..self.clone()
+        }
+    }
+
+    /// Reserve a slot for `prompt` with the provider's rate limiter and return
+    /// how long to wait before calling [`Self::stream`].
+    pub(crate) fn reserve_rate_limit(&self, prompt: &Prompt) -> Duration {
+        if is_replaying() {
+            return Duration::ZERO;
+        }
+        limiter_for(&self.provider).reserve(estimate_tokens(prompt))
+    }
+
     /// Dispatches to either the Responses or Chat implementation depending on
     /// the provider config.  Public callers always invoke `stream()` – the
     /// specialised helpers are private to avoid accidental misuse.
+    ///
+    /// Under `CODEX_REPLAY` the response comes from a recording instead, and
+    /// under `CODEX_RECORD` it is recorded on its way to the caller.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        if let Some(replay) = replay::replay()? {
+            return replay.next_response();
+        }
+        let response = self.stream_from_provider(prompt).await;
+        match replay::recorder()? {
+            Some(recorder) => recorder.record(&self.model, prompt, response),
+            None => response,
+        }
+    }
+
+    /// Send `prompt` and return the text of the assistant messages in the
+    /// response, for requests made outside of a conversation.
+    pub(crate) async fn complete(&self, prompt: &Prompt) -> Result<String> {
+        let mut stream = self.stream(prompt).await?;
+        let mut text = String::new();
+        while let Some(event) = stream.next().await {
+            match event? {
+                ResponseEvent::OutputItemDone(ResponseItem::Message { content, .. }) => {
+                    for item in content {
+                        if let ContentItem::OutputText { text: delta } = item {
+                            text.push_str(&delta);
+                        }
+                    }
+                }
+                ResponseEvent::Completed { .. } => break,
+                _ => {}
+            }
+        }
+        Ok(text)
+    }
+
+    /// Embed each of `inputs` with the client's model through the provider's
+    /// OpenAI-compatible `/embeddings` endpoint.
+    pub(crate) async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        #[derive(Serialize)]
+        struct EmbeddingsRequest<'a> {
+            model: &'a str,
+            input: &'a [String],
+        }
+        #[derive(Deserialize)]
+        struct EmbeddingsResponse {
+            data: Vec<Embedding>,
+        }
+        #[derive(Deserialize)]
+        struct Embedding {
+            index: usize,
+            embedding: Vec<f32>,
+        }
+
+        let bytes: usize = inputs.iter().map(String::len).sum();
+        let delay = limiter_for(&self.provider).reserve((bytes / BYTES_PER_TOKEN) as u64);
+        tokio::time::sleep(delay).await;
+
+        let url = format!(
+            "{}/embeddings",
+            self.provider.base_url.trim_end_matches('/')
+        );
+        let mut req_builder = self.client.post(&url);
+        if let Some(credentials) = self.credentials.credentials(&self.provider).await? {
+            req_builder = req_builder.bearer_auth(credentials.token);
+        }
+        let res = req_builder
+            .json(&EmbeddingsRequest {
+                model: &self.model,
+                input: inputs,
+            })
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(CodexErr::UnexpectedStatus(status, body));
+        }
+        let mut data = res.json::<EmbeddingsResponse>().await?.data;
+        data.sort_by_key(|embedding| embedding.index);
+        Ok(data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
+
+    async fn stream_from_provider(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
             WireApi::Chat => {
                 // Create the raw streaming connection first.
-                let response_stream =
-                    stream_chat_completions(prompt, &self.model, &self.client, &self.provider)
-                        .await?;
+                let reasoning =
+                    create_reasoning_param_for_request(&self.model, self.effort, self.summary);
+                let response_stream = stream_chat_completions(
+                    prompt,
+                    &self.model,
+                    reasoning.as_ref(),
+                    &self.sampling,
+                    &self.client,
+                    &self.provider,
+                    self.credentials.as_ref(),
+                )
+                .await?;
 
                 // Wrap it with the aggregation adapter so callers see *only*
                 // the final assistant message per turn (matching the
@@ -115,11 +269,14 @@ impl ModelClient {
             input: &prompt.input,
             tools: &tools_json,
             tool_choice: "auto",
-            parallel_tool_calls: false,
+            parallel_tool_calls: prompt.parallel_tool_calls,
             reasoning,
             previous_response_id: prompt.prev_id.clone(),
             store: prompt.store,
             stream: true,
+            text: prompt.response_format_for(&self.provider).map(Into::into),
+            temperature: self.sampling.temperature,
+            top_p: self.sampling.top_p,
         };
 
         let base_url = self.provider.base_url.clone();
@@ -127,25 +284,26 @@ impl ModelClient {
         let url = format!("{}/responses", base_url);
         trace!("POST to {url}: {}", serde_json::to_string(&payload)?);
 
+        let limiter = limiter_for(&self.provider);
+        let tokens = estimate_tokens(prompt);
         let mut attempt = 0;
         loop {
             attempt += 1;
 
-            let api_key = self.provider.api_key()?.ok_or_else(|| {
-                CodexErr::EnvVar(EnvVarError {
-                    var: self.provider.env_key.clone().unwrap_or_default(),
-                    instructions: None,
-                })
-            })?;
-            let res = self
-                .client
-                .post(&url)
-                .bearer_auth(api_key)
+            let credentials = self.credentials.credentials(&self.provider).await?;
+            let mut req_builder = self.client.post(&url);
+            if let Some(credentials) = credentials {
+                req_builder = req_builder.bearer_auth(credentials.token);
+            }
+            let res = req_builder
                 .header("OpenAI-Beta", "responses=experimental")
                 .header(reqwest::header::ACCEPT, "text/event-stream")
                 .json(&payload)
                 .send()
                 .await;
+            if let Ok(resp) = &res {
+                limiter.observe(resp.headers());
+            }
             match res {
                 Ok(resp) if resp.status().is_success() => {
                     let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(16);
@@ -185,14 +343,18 @@ impl ModelClient {
                     let delay = retry_after_secs
                         .map(|s| Duration::from_millis(s * 1_000))
                         .unwrap_or_else(|| backoff(attempt));
-                    tokio::time::sleep(delay).await;
+                    if status == StatusCode::TOO_MANY_REQUESTS {
+                        limiter.pause(delay);
+                    }
+                    // Retries count against the rate limit like any request.
+                    tokio::time::sleep(delay.max(limiter.reserve(tokens))).await;
                 }
                 Err(e) => {
                     if attempt > *OPENAI_REQUEST_MAX_RETRIES {
                         return Err(e.into());
                     }
                     let delay = backoff(attempt);
-                    tokio::time::sleep(delay).await;
+                    tokio::time::sleep(delay.max(limiter.reserve(tokens))).await;
                 }
             }
         }
@@ -205,11 +367,44 @@ struct SseEvent {
     kind: String,
     response: Option<Value>,
     item: Option<Value>,
+    delta: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ResponseCompleted {
     id: String,
+    usage: Option<ResponseCompletedUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseCompletedUsage {
+    input_tokens: u64,
+    input_tokens_details: Option<ResponseCompletedInputTokensDetails>,
+    output_tokens: u64,
+    output_tokens_details: Option<ResponseCompletedOutputTokensDetails>,
+    total_tokens: u64,
+}
+
+impl From<ResponseCompletedUsage> for TokenUsage {
+    fn from(val: ResponseCompletedUsage) -> Self {
+        TokenUsage {
+            input_tokens: val.input_tokens,
+            cached_input_tokens: val.input_tokens_details.map(|d| d.cached_tokens),
+            output_tokens: val.output_tokens,
+            reasoning_output_tokens: val.output_tokens_details.map(|d| d.reasoning_tokens),
+            total_tokens: val.total_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseCompletedInputTokensDetails {
+    cached_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseCompletedOutputTokensDetails {
+    reasoning_tokens: u64,
 }
 
 async fn process_sse<S>(stream: S, tx_event: mpsc::Sender<Result<ResponseEvent>>)
@@ -222,6 +417,7 @@ where
     let idle_timeout = *OPENAI_STREAM_IDLE_TIMEOUT_MS;
     // The response id returned from the "complete" message.
     let mut response_id = None;
+    let mut token_usage = None;
 
     loop {
         let sse = match timeout(idle_timeout, stream.next()).await {
@@ -235,7 +431,10 @@ where
             Ok(None) => {
                 match response_id {
                     Some(response_id) => {
-                        let event = ResponseEvent::Completed { response_id };
+                        let event = ResponseEvent::Completed {
+                            response_id,
+                            token_usage,
+                        };
                         let _ = tx_event.send(Ok(event)).await;
                     }
                     None => {
@@ -302,6 +501,7 @@ where
                     match serde_json::from_value::<ResponseCompleted>(resp_val) {
                         Ok(r) => {
                             response_id = Some(r.id);
+                            token_usage = r.usage.map(Into::into);
                         }
                         Err(e) => {
                             debug!("failed to parse ResponseCompleted: {e}");
@@ -310,15 +510,29 @@ where
                     };
                 };
             }
+            "response.output_text.delta" => {
+                if let Some(delta) = event.delta {
+                    let event = ResponseEvent::OutputTextDelta(delta);
+                    if tx_event.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            "response.reasoning_summary_text.delta" => {
+                if let Some(delta) = event.delta {
+                    let event = ResponseEvent::ReasoningSummaryDelta(delta);
+                    if tx_event.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
             "response.content_part.done"
             | "response.created"
             | "response.function_call_arguments.delta"
             | "response.in_progress"
             | "response.output_item.added"
-            | "response.output_text.delta"
             | "response.output_text.done"
             | "response.reasoning_summary_part.added"
-            | "response.reasoning_summary_text.delta"
             | "response.reasoning_summary_text.done" => {
                 // Currently, we ignore these events, but we handle them
                 // separately to skip the logging message in the `other` case.
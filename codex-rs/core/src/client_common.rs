@@ -1,7 +1,14 @@
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::SamplingParams;
 use crate::error::Result;
+use crate::model_provider_info::ModelProviderInfo;
+use crate::model_provider_info::WireApi;
 use crate::models::ResponseItem;
+use crate::protocol::ResponseFormat;
+use crate::protocol::TokenUsage;
+use crate::structured_output::SCHEMA_NAME;
+use crate::structured_output::instructions;
 use codex_apply_patch::APPLY_PATCH_TOOL_INSTRUCTIONS;
 use futures::Stream;
 use serde::Serialize;
@@ -16,6 +23,9 @@ use tokio::sync::mpsc;
 /// with this content.
 const BASE_INSTRUCTIONS: &str = include_str!("../prompt.md");
 
+/// Appended to the instructions while the session is in ask mode.
+const ASK_MODE_INSTRUCTIONS: &str = include_str!("../prompt_for_ask_mode.md");
+
 /// API request payload for a single model turn.
 #[derive(Default, Debug, Clone)]
 pub struct Prompt {
@@ -33,6 +43,29 @@ pub struct Prompt {
     /// the "fully qualified" tool name (i.e., prefixed with the server name),
     /// which should be reported to the model in place of Tool::name.
     pub extra_tools: HashMap<String, mcp_types::Tool>,
+
+    /// Whether to offer the `spawn_agents` tool.
+    pub allow_sub_agents: bool,
+
+    /// Whether to offer the `semantic_search` tool.
+    pub semantic_search: bool,
+
+    /// Whether the session is in ask mode, where the agent is read-only.
+    pub ask_mode: bool,
+
+    /// Whether the model may ask for several tool calls in one response.
+    pub parallel_tool_calls: bool,
+
+    /// What the model is told about the shell its commands run in, when it
+    /// is not a POSIX shell.
+    pub shell_instructions: Option<&'static str>,
+
+    /// Whether to offer the `remember` tool, which saves a memory of the
+    /// project.
+    pub remember: bool,
+
+    /// Schema the agent's final message must match, if any.
+    pub response_format: Option<ResponseFormat>,
 }
 
 impl Prompt {
@@ -41,17 +74,41 @@ impl Prompt {
         if let Some(ref user) = self.user_instructions {
             sections.push(user);
         }
-        if model.starts_with("gpt-4.1") {
+        sections.extend(self.shell_instructions);
+        if self.ask_mode {
+            sections.push(ASK_MODE_INSTRUCTIONS);
+        } else if model.starts_with("gpt-4.1") {
             sections.push(APPLY_PATCH_TOOL_INSTRUCTIONS);
         }
+        let structured_output = self.response_format.as_ref().map(instructions);
+        sections.extend(structured_output.as_deref());
         Cow::Owned(sections.join("\n"))
     }
+
+    /// The schema to pass to `provider`, when it enforces schemas itself.
+    pub(crate) fn response_format_for(
+        &self,
+        provider: &ModelProviderInfo,
+    ) -> Option<&ResponseFormat> {
+        self.response_format
+            .as_ref()
+            .filter(|_| provider.supports_structured_output)
+    }
 }
 
 #[derive(Debug)]
 pub enum ResponseEvent {
     OutputItemDone(ResponseItem),
-    Completed { response_id: String },
+    /// Fragment of assistant text; the full message is delivered later as an
+    /// `OutputItemDone`.
+    OutputTextDelta(String),
+    /// Fragment of the reasoning summary; the full summary is delivered later
+    /// as an `OutputItemDone` with a `Reasoning` item.
+    ReasoningSummaryDelta(String),
+    Completed {
+        response_id: String,
+        token_usage: Option<TokenUsage>,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -124,6 +181,39 @@ pub(crate) struct ResponsesApiRequest<'a> {
     /// true when using the Responses API.
     pub(crate) store: bool,
     pub(crate) stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) text: Option<TextControls<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) top_p: Option<f32>,
+}
+
+/// The `text` parameter of the Responses API, which carries the schema for
+/// structured output.
+#[derive(Debug, Serialize)]
+pub(crate) struct TextControls<'a> {
+    pub(crate) format: TextFormat<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum TextFormat<'a> {
+    JsonSchema {
+        name: &'static str,
+        schema: &'a serde_json::Value,
+    },
+}
+
+impl<'a> From<&'a ResponseFormat> for TextControls<'a> {
+    fn from(format: &'a ResponseFormat) -> Self {
+        TextControls {
+            format: TextFormat::JsonSchema {
+                name: SCHEMA_NAME,
+                schema: &format.schema,
+            },
+        }
+    }
 }
 
 pub(crate) fn create_reasoning_param_for_request(
@@ -144,6 +234,37 @@ pub(crate) fn create_reasoning_param_for_request(
     }
 }
 
+/// Check that `provider` accepts the sampling parameters for `model`, so that
+/// a misconfiguration fails when the session is configured rather than on
+/// every request.
+pub(crate) fn check_sampling_params(
+    sampling: &SamplingParams,
+    model: &str,
+    provider: &ModelProviderInfo,
+    effort: ReasoningEffortConfig,
+) -> std::result::Result<(), String> {
+    if sampling.seed.is_some() && provider.wire_api == WireApi::Responses {
+        return Err(format!(
+            "`seed` is not supported by the Responses API, which {} uses",
+            provider.name
+        ));
+    }
+    let reasoning = Option::<OpenAiReasoningEffort>::from(effort).is_some()
+        && model_supports_reasoning_summaries(model);
+    let fixed = [
+        ("temperature", sampling.temperature.is_some()),
+        ("top_p", sampling.top_p.is_some()),
+    ]
+    .into_iter()
+    .find_map(|(name, set)| set.then_some(name));
+    match fixed {
+        Some(name) if reasoning => Err(format!(
+            "`{name}` is not supported by reasoning models such as {model}; set `model_reasoning_effort = \"none\"` if {model} does not reason"
+        )),
+        _ => Ok(()),
+    }
+}
+
 pub fn model_supports_reasoning_summaries(model: &str) -> bool {
     // Currently, we hardcode this rule to decide whether enable reasoning.
     // We expect reasoning to apply only to OpenAI models, but we do not want
@@ -171,3 +292,43 @@ impl Stream for ResponseStream {
         self.rx_event.poll_recv(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::model_provider_info::built_in_model_providers;
+
+    #[test]
+    fn sampling_params_are_checked_against_the_provider_and_model() {
+        let providers = built_in_model_providers();
+        let openai = &providers["openai"];
+        let chat = ModelProviderInfo {
+            wire_api: WireApi::Chat,
+            ..openai.clone()
+        };
+        let seeded = SamplingParams {
+            temperature: Some(0.0),
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_sampling_params(&seeded, "gpt-4.1", &chat, ReasoningEffortConfig::Medium),
+            Ok(())
+        );
+        assert_eq!(
+            check_sampling_params(&seeded, "gpt-4.1", openai, ReasoningEffortConfig::Medium),
+            Err("`seed` is not supported by the Responses API, which OpenAI uses".to_string())
+        );
+        assert!(
+            check_sampling_params(&seeded, "o3", &chat, ReasoningEffortConfig::High)
+                .unwrap_err()
+                .starts_with("`temperature` is not supported by reasoning models such as o3")
+        );
+        assert_eq!(
+            check_sampling_params(&seeded, "o3", &chat, ReasoningEffortConfig::None),
+            Ok(())
+        );
+    }
+}
@@ -9,6 +9,7 @@ use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
 use async_channel::Receiver;
@@ -21,25 +22,46 @@ use codex_apply_patch::maybe_parse_apply_patch_verified;
 use codex_apply_patch::print_summary;
 use futures::prelude::*;
 use mcp_types::CallToolResult;
+use mcp_types::CallToolResultContent;
 use serde::Serialize;
 use serde_json;
 use tokio::sync::Notify;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::task::AbortHandle;
+use tracing::Instrument;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
+use tracing::info_span;
 use tracing::trace;
 use tracing::warn;
 use uuid::Uuid;
 
 use crate::WireApi;
+use crate::attachments::AttachmentBudget;
+use crate::audit::AuditAction;
+use crate::audit::AuditApproval;
+use crate::audit::AuditLog;
+use crate::auth::AuthManager;
+use crate::auth::COPILOT_PROVIDER_NAME;
+use crate::checkpoint::Checkpoints;
+use crate::checkpoint::ConversationSnapshot;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::client_common::check_sampling_params;
+use crate::command_risk::assess_command_risk;
 use crate::config::Config;
+use crate::config_types::ApplyMode;
+use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
+use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::ReviewEdits;
+use crate::config_types::SamplingParams;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
+use crate::copilot_plan::CopilotPlan;
+use crate::copilot_token_store::CopilotToken;
 // TODO: Import full copilot integration when ready
 
 use crate::error::CodexErr;
@@ -51,6 +73,12 @@ use crate::exec::SandboxType;
 use crate::exec::process_exec_tool_call;
 use crate::exec_env::create_env;
 use crate::flags::OPENAI_STREAM_MAX_RETRIES;
+use crate::git_context::git_context;
+use crate::hooks::HookToolCall;
+use crate::hooks::run_post_tool_call_hooks;
+use crate::hooks::run_pre_tool_call_hooks;
+use crate::http_client::is_local_url;
+use crate::is_safe_command::is_known_safe_command;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_connection_manager::try_parse_fully_qualified_tool_name;
 use crate::mcp_tool_call::handle_mcp_tool_call;
@@ -61,34 +89,103 @@ use crate::models::ReasoningItemReasoningSummary;
 use crate::models::ResponseInputItem;
 use crate::models::ResponseItem;
 use crate::models::ShellToolCallParams;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::builtin_tool_parameters;
+use crate::plan_tool::UPDATE_PLAN_TOOL_NAME;
+use crate::plan_tool::parse_update_plan;
+use crate::policy::PolicyCall;
 use crate::project_doc::get_user_instructions;
+use crate::project_init::project_root;
+use crate::project_memory;
+use crate::project_memory::REMEMBER_TOOL_NAME;
+use crate::project_memory::RememberArgs;
+use crate::project_permissions;
+use crate::protocol::AgentMessageDeltaEvent;
 use crate::protocol::AgentMessageEvent;
+use crate::protocol::AgentReasoningDeltaEvent;
 use crate::protocol::AgentReasoningEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::CheckpointCreatedEvent;
+use crate::protocol::CopilotPlanDetectedEvent;
+use crate::protocol::DryRunExecEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
+use crate::protocol::EventKind;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecApprovalRequestEvent;
 use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
+use crate::protocol::HandshakeEvent;
 use crate::protocol::InputItem;
 use crate::protocol::Op;
+use crate::protocol::PROTOCOL_VERSION;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
+use crate::protocol::PatchProposedEvent;
+use crate::protocol::PlanItem;
+use crate::protocol::RateLimitedEvent;
+use crate::protocol::RedactedSecret;
+use crate::protocol::ResponseFormat;
 use crate::protocol::ReviewDecision;
+use crate::protocol::RewoundEvent;
+use crate::protocol::SandboxAccess;
+use crate::protocol::SandboxEscalationRequestEvent;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SecretsRedactedEvent;
 use crate::protocol::SessionConfiguredEvent;
+use crate::protocol::SessionMode;
 use crate::protocol::Submission;
 use crate::protocol::TaskCompleteEvent;
+use crate::protocol::TaskStats;
+use crate::protocol::ToolCallRepairEvent;
+use crate::protocol::TurnContextOverriddenEvent;
+use crate::protocol::TurnDiffApprovalRequestEvent;
+use crate::read_cache::FileRead;
+use crate::read_cache::READ_FILE_CHANGES_TOOL_NAME;
+use crate::read_cache::ReadCache;
+use crate::read_cache::ReadFileChangesArgs;
+use crate::redaction::redact_secrets;
+use crate::replay::is_replaying;
 use crate::rollout::RolloutRecorder;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
+use crate::safety::assess_command_safety_read_only;
 use crate::safety::assess_patch_safety;
+use crate::safety::get_platform_sandbox;
+use crate::safety::is_write_patch_constrained_to_writable_paths;
+use crate::sandbox_escalation::denied_access;
+use crate::scratchpad::SCRATCHPAD_REMINDER;
+use crate::scratchpad::SCRATCHPAD_TOOL_NAME;
+use crate::scratchpad::Scratchpad;
+use crate::scratchpad::ScratchpadArgs;
+use crate::scratchpad::ScratchpadOutcome;
+use crate::semantic_search::SEMANTIC_SEARCH_TOOL_NAME;
+use crate::semantic_search::SemanticIndex;
+use crate::semantic_search::SemanticSearchArgs;
+use crate::session_title::generate_title;
+use crate::shell::Shell;
+use crate::structured_output::MAX_REASKS;
+use crate::structured_output::check_final_message;
+use crate::structured_output::reask_message;
+use crate::sub_agents::ParentSession;
+use crate::sub_agents::SPAWN_AGENTS_TOOL_NAME;
+use crate::sub_agents::SpawnAgentsArgs;
+use crate::sub_agents::run_sub_agents;
+use crate::tool_arguments::MAX_TOOL_CALL_REPAIRS;
+use crate::tool_arguments::check_arguments;
+use crate::tool_arguments::repair_message;
+use crate::turn_review::StagedEdits;
+use crate::turn_review::rejection_note;
+use crate::untrusted_content::injection_signs;
+use crate::untrusted_content::untrusted_source;
+use crate::untrusted_content::wrap as wrap_untrusted;
 use crate::user_notification::UserNotification;
 use crate::util::backoff;
+use crate::working_dir::can_run_in;
+use crate::working_dir::cd_target;
 
 /// The high-level interface to the Codex system.
 /// It operates as a queue pair where you send submissions and receive events.
@@ -96,8 +193,10 @@ pub struct Codex {
     next_id: AtomicU64,
     tx_sub: Sender<Submission>,
     rx_event: Receiver<Event>,
+    /// Kinds of event `next_event` delivers, set by `Op::Subscribe`; empty
+    /// for all of them.
+    subscription: Mutex<Vec<EventKind>>,
     // TODO: Add copilot client for AI assistance
-
 }
 
 impl Codex {
@@ -108,34 +207,28 @@ impl Codex {
         let (tx_sub, rx_sub) = async_channel::bounded(64);
         let (tx_event, rx_event) = async_channel::bounded(64);
 
-        let instructions = get_user_instructions(&config).await;
-        let configure_session = Op::ConfigureSession {
-            provider: config.model_provider.clone(),
-            model: config.model.clone(),
-            model_reasoning_effort: config.model_reasoning_effort,
-            model_reasoning_summary: config.model_reasoning_summary,
-            instructions,
-            approval_policy: config.approval_policy,
-            sandbox_policy: config.sandbox_policy.clone(),
-            disable_response_storage: config.disable_response_storage,
-            notify: config.notify.clone(),
-            cwd: config.cwd.clone(),
-        };
+        let configure_session = configure_session_op(&config).await;
+        let http_client = crate::http_client::create_client(&config)?;
 
         let config = Arc::new(config);
-        tokio::spawn(submission_loop(config, rx_sub, tx_event, ctrl_c));
+        tokio::spawn(submission_loop(
+            config,
+            http_client,
+            rx_sub,
+            tx_event,
+            ctrl_c,
+        ));
         let codex = Codex {
             next_id: AtomicU64::new(0),
             tx_sub,
             rx_event,
-
+            subscription: Mutex::new(Vec::new()),
         };
         let init_id = codex.submit(configure_session).await?;
 
         Ok((codex, init_id))
     }
 
-
     /// Submit the `op` wrapped in a `Submission` with a unique ID.
     pub async fn submit(&self, op: Op) -> CodexResult<String> {
         let id = self
@@ -150,6 +243,12 @@ impl Codex {
     /// Use sparingly: prefer `submit()` so Codex is responsible for generating
     /// unique IDs for each submission.
     pub async fn submit_with_id(&self, sub: Submission) -> CodexResult<()> {
+        // The subscription filters what this end of the queue receives, so
+        // the session never sees it.
+        if let Op::Subscribe { kinds } = sub.op {
+            *self.subscription.lock().unwrap() = kinds;
+            return Ok(());
+        }
         self.tx_sub
             .send(sub)
             .await
@@ -158,21 +257,69 @@ impl Codex {
     }
 
     pub async fn next_event(&self) -> CodexResult<Event> {
-        let event = self
-            .rx_event
-            .recv()
-            .await
-            .map_err(|_| CodexErr::InternalAgentDied)?;
-        Ok(event)
+        loop {
+            let event = self
+                .rx_event
+                .recv()
+                .await
+                .map_err(|_| CodexErr::InternalAgentDied)?;
+            if self.is_subscribed(&event.msg) {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn is_subscribed(&self, msg: &EventMsg) -> bool {
+        let subscription = self.subscription.lock().unwrap();
+        subscription.is_empty()
+            || matches!(msg, EventMsg::Error(_))
+            || subscription.contains(&msg.kind())
     }
 }
 
+/// Build the `ConfigureSession` op that starts the session for `config`.
+async fn configure_session_op(config: &Config) -> Op {
+    let instructions = get_user_instructions(config).await;
+    Op::ConfigureSession {
+        provider: config.model_provider.clone(),
+        model: config.model.clone(),
+        model_reasoning_effort: config.model_reasoning_effort,
+        model_reasoning_summary: config.model_reasoning_summary,
+        sampling: config.sampling,
+        instructions,
+        approval_policy: config.approval_policy,
+        sandbox_policy: config.sandbox_policy.clone(),
+        disable_response_storage: config.disable_response_storage,
+        notify: config.notify.clone(),
+        cwd: config.cwd.clone(),
+        mode: config.mode,
+        dry_run: config.dry_run,
+    }
+}
+
+/// Prompt sent to the model to produce the summary for `Op::Compact`.
+const SUMMARIZATION_PROMPT: &str = include_str!("../prompt_for_compact_command.md");
+
+/// Prefix for the user message that carries a compacted conversation into
+/// the next turn.
+const COMPACTED_SUMMARY_PREFIX: &str =
+    "Summary of the conversation so far (earlier messages were compacted):";
+
+/// Lines of stderr kept in the audit record of a sandbox violation.
+const VIOLATION_STDERR_LINES: usize = 20;
 
 /// Context for an initialized model agent
 ///
 /// A session has at most 1 running task at a time, and can be interrupted by user input.
 pub(crate) struct Session {
-    client: ModelClient,
+    /// Replaced by `Op::OverrideTurnContext`; each model request uses the
+    /// client current when it is sent.
+    client: Mutex<ModelClient>,
+    /// Client for `router.small_model`, which summarizes the conversation
+    /// for `Op::Compact` and titles the session.
+    small_client: Option<ModelClient>,
+    /// Backs the `semantic_search` tool when it is enabled.
+    semantic_index: Option<SemanticIndex>,
     tx_event: Sender<Event>,
     ctrl_c: Arc<Notify>,
 
@@ -181,8 +328,19 @@ pub(crate) struct Session {
     /// instead of `std::env::current_dir()`.
     cwd: PathBuf,
     instructions: Option<String>,
-    approval_policy: AskForApproval,
+    /// Changed by `Op::OverrideTurnContext`; read through `approval_policy()`,
+    /// which Ask mode overrides.
+    approval_policy: Mutex<AskForApproval>,
     sandbox_policy: SandboxPolicy,
+    /// Sandbox policy of write mode when the escalation ladder is enabled:
+    /// the session starts under `sandbox_policy` and switches to this one
+    /// once the user approves the model's first edit.
+    write_mode_policy: Option<SandboxPolicy>,
+    /// Changed by `Op::OverrideTurnContext`.
+    mode: Mutex<SessionMode>,
+    /// Show shell commands instead of running them. Changed by
+    /// `Op::OverrideTurnContext`.
+    dry_run: Mutex<bool>,
     shell_environment_policy: ShellEnvironmentPolicy,
     writable_roots: Mutex<Vec<PathBuf>>,
 
@@ -198,14 +356,67 @@ pub(crate) struct Session {
     rollout: Mutex<Option<crate::rollout::RolloutRecorder>>,
     state: Mutex<State>,
     codex_linux_sandbox_exe: Option<PathBuf>,
+
+    /// Configuration the session was started with; sub-agents derive theirs
+    /// from it.
+    config: Arc<Config>,
+
+    /// Where executed commands and file writes are recorded, unless
+    /// `audit_log = false`.
+    audit: Option<AuditLog>,
 }
 
 impl Session {
+    fn client(&self) -> ModelClient {
+        self.client.lock().unwrap().clone()
+    }
+
+    /// The client for a model request of a task of `kind`. Compaction goes to
+    /// the small model, unless the conversation does not fit its context
+    /// window.
+    fn client_for(&self, kind: TaskKind) -> ModelClient {
+        if let Some(small_client) = self
+            .small_client
+            .as_ref()
+            .filter(|_| kind == TaskKind::Compact)
+        {
+            let context_tokens = self.state.lock().unwrap().context_tokens;
+            if get_model_info(small_client.model())
+                .is_none_or(|info| context_tokens <= info.context_window)
+            {
+                return small_client.clone();
+            }
+        }
+        self.client()
+    }
+
     fn resolve_path(&self, path: Option<String>) -> PathBuf {
         path.as_ref()
             .map(PathBuf::from)
             .map_or_else(|| self.cwd.clone(), |p| self.cwd.join(p))
     }
+
+    /// The directory commands without a `workdir` run in.
+    fn working_dir(&self) -> PathBuf {
+        self.state
+            .lock()
+            .unwrap()
+            .working_dir
+            .clone()
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(|| self.cwd.clone())
+    }
+
+    /// Keep the directory the leading `cd` of a command that ran moved to,
+    /// if the sandbox lets commands read it.
+    fn follow_cd(&self, params: &ExecParams) {
+        let Some(dir) = cd_target(&params.command, &params.cwd) else {
+            return;
+        };
+        if can_run_in(&dir, &self.sandbox_policy(), &self.cwd) {
+            self.state.lock().unwrap().working_dir = Some(dir);
+        }
+    }
 }
 
 /// Mutable state of the agent
@@ -217,6 +428,83 @@ struct State {
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
     pending_input: Vec<ResponseInputItem>,
     zdr_transcript: Option<ConversationHistory>,
+    /// Summary produced by `Op::Compact` that has to be sent ahead of the
+    /// next user input when there is no local transcript to hold it.
+    compacted_summary: Option<ResponseItem>,
+    /// Output of the turn in flight, so that an interrupted turn is not lost.
+    partial_turn: PartialTurn,
+    /// Output of an interrupted turn, sent ahead of the next user input.
+    interrupted_turn: Vec<ResponseItem>,
+    /// Conversation restored from a resumed session's rollout, sent ahead of
+    /// the next user input when there is no local transcript to hold it.
+    resumed_history: Vec<ResponseItem>,
+    /// Number of `spawn_agents` calls so far, used to namespace sub-agents.
+    sub_agent_calls: u64,
+    /// Latest plan published through `update_plan`.
+    plan: Vec<PlanItem>,
+    /// Whether the user switched the session to write mode.
+    write_mode: bool,
+    /// Files edited since the last turn review (`review_edits = "per-turn"`).
+    staged_edits: StagedEdits,
+    pending_turn_reviews: HashMap<String, oneshot::Sender<Vec<PathBuf>>>,
+    /// Tells the model which of its changes the user rejected; sent ahead of
+    /// the next user input.
+    review_note: Option<ResponseItem>,
+    /// Input tokens of the latest model request: roughly how much context a
+    /// model taking over the conversation has to hold.
+    context_tokens: u64,
+    /// Whether the session still needs a title, which is generated from its
+    /// first prompt.
+    untitled: bool,
+    /// Whether the recent Git changes (`[git_context]`) were sent already.
+    git_context_sent: bool,
+    /// Whether the project's memories (`[memory]`) were sent already.
+    memories_sent: bool,
+    /// Model time, requests, tokens and cost of the running task so far.
+    task_stats: TaskStats,
+    /// Plan of the Copilot token, which limits the models that can be used.
+    copilot_plan: Option<CopilotPlan>,
+    /// Tool calls in a row the model was asked to correct the arguments of.
+    tool_call_repairs: u32,
+    /// Why the rest of the task needs the user's confirmation for every
+    /// action: untrusted content looked like it tried to instruct the agent.
+    untrusted_instructions: Option<String>,
+    /// Access the user granted sandboxed commands for the rest of the
+    /// session after one was denied it.
+    sandbox_grants: Vec<SandboxAccess>,
+    /// Files the model read through the shell, to answer an identical read
+    /// of an unchanged file without sending the content again.
+    read_cache: ReadCache,
+    /// Where the leading `cd` of an earlier command led: commands without a
+    /// `workdir` run there instead of in `cwd`.
+    working_dir: Option<PathBuf>,
+    /// Notes the model keeps through the `scratchpad` tool.
+    scratchpad: Scratchpad,
+    /// Points `Op::Rewind` can take the conversation and the workspace back
+    /// to.
+    checkpoints: Checkpoints,
+}
+
+/// What the model has produced so far in the current turn.
+#[derive(Default)]
+struct PartialTurn {
+    /// Streamed text of an assistant message that has not completed yet.
+    message: String,
+    /// Completed messages and tool calls paired with their outputs.
+    items: Vec<ResponseItem>,
+}
+
+impl PartialTurn {
+    fn into_items(self) -> Vec<ResponseItem> {
+        let mut items = self.items;
+        if !self.message.is_empty() {
+            items.push(ResponseItem::Message {
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text: self.message }],
+            });
+        }
+        items
+    }
 }
 
 impl Session {
@@ -253,22 +541,95 @@ impl Session {
         reason: Option<String>,
     ) -> oneshot::Receiver<ReviewDecision> {
         let (tx_approve, rx_approve) = oneshot::channel();
+        self.maybe_notify(UserNotification::ApprovalRequested {
+            turn_id: sub_id.clone(),
+            command: Some(command.clone()),
+            changed_files: None,
+            reason: reason.clone(),
+        });
         let event = Event {
             id: sub_id.clone(),
             msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+                risk: assess_command_risk(&command, &cwd, &self.cwd),
                 command,
                 cwd,
                 reason,
             }),
         };
+        // Register the request before sending it: the answer can arrive
+        // before `send` returns.
+        {
+            let mut state = self.state.lock().unwrap();
+            state.pending_approvals.insert(sub_id, tx_approve);
+        }
         let _ = self.tx_event.send(event).await;
+        rx_approve
+    }
+
+    /// Ask the user to grant `access`, which a sandboxed command was denied,
+    /// for the rest of the session or, approved for the session, for the
+    /// workspace.
+    pub async fn request_sandbox_escalation(
+        &self,
+        sub_id: String,
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        access: SandboxAccess,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        self.maybe_notify(UserNotification::ApprovalRequested {
+            turn_id: sub_id.clone(),
+            command: Some(command.clone()),
+            changed_files: None,
+            reason: Some(format!("the sandbox denied {access}")),
+        });
+        let event = Event {
+            id: sub_id.clone(),
+            msg: EventMsg::SandboxEscalationRequest(SandboxEscalationRequestEvent {
+                call_id,
+                command,
+                cwd,
+                access,
+            }),
+        };
         {
             let mut state = self.state.lock().unwrap();
             state.pending_approvals.insert(sub_id, tx_approve);
         }
+        let _ = self.tx_event.send(event).await;
         rx_approve
     }
 
+    /// Let sandboxed commands have `access` for the rest of the session.
+    fn grant_sandbox_access(&self, access: SandboxAccess) {
+        if let SandboxAccess::Write { path } = &access {
+            self.writable_roots.lock().unwrap().push(path.clone());
+        }
+        let mut state = self.state.lock().unwrap();
+        if !state.sandbox_grants.contains(&access) {
+            state.sandbox_grants.push(access);
+        }
+    }
+
+    /// Remember `access` for the workspace of the session so that later
+    /// sessions have it from the start. Returns for how long it was granted,
+    /// which is just this session if it could not be saved.
+    fn remember_sandbox_access(&self, access: &SandboxAccess) -> &'static str {
+        let workspace = project_permissions::workspace_root(&self.cwd);
+        match project_permissions::remember_grant(
+            &self.config.codex_home,
+            &workspace,
+            access.clone(),
+        ) {
+            Ok(()) => "this workspace",
+            Err(e) => {
+                tracing::warn!("failed to remember sandbox grant: {e:#}");
+                "this session"
+            }
+        }
+    }
+
     pub async fn request_patch_approval(
         &self,
         sub_id: String,
@@ -277,6 +638,14 @@ impl Session {
         grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
         let (tx_approve, rx_approve) = oneshot::channel();
+        let mut changed_files: Vec<PathBuf> = action.changes().keys().cloned().collect();
+        changed_files.sort();
+        self.maybe_notify(UserNotification::ApprovalRequested {
+            turn_id: sub_id.clone(),
+            command: None,
+            changed_files: Some(changed_files),
+            reason: reason.clone(),
+        });
         let event = Event {
             id: sub_id.clone(),
             msg: EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
@@ -285,11 +654,11 @@ impl Session {
                 grant_root,
             }),
         };
-        let _ = self.tx_event.send(event).await;
         {
             let mut state = self.state.lock().unwrap();
             state.pending_approvals.insert(sub_id, tx_approve);
         }
+        let _ = self.tx_event.send(event).await;
         rx_approve
     }
 
@@ -300,6 +669,95 @@ impl Session {
         }
     }
 
+    pub async fn request_turn_diff_approval(
+        &self,
+        sub_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    ) -> oneshot::Receiver<Vec<PathBuf>> {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let mut changed_files: Vec<PathBuf> = changes.keys().cloned().collect();
+        changed_files.sort();
+        self.maybe_notify(UserNotification::ApprovalRequested {
+            turn_id: sub_id.clone(),
+            command: None,
+            changed_files: Some(changed_files),
+            reason: None,
+        });
+        let event = Event {
+            id: sub_id.clone(),
+            msg: EventMsg::TurnDiffApprovalRequest(TurnDiffApprovalRequestEvent { changes }),
+        };
+        {
+            let mut state = self.state.lock().unwrap();
+            state.pending_turn_reviews.insert(sub_id, tx_approve);
+        }
+        let _ = self.tx_event.send(event).await;
+        rx_approve
+    }
+
+    pub fn notify_turn_diff_approval(&self, sub_id: &str, approved: Vec<PathBuf>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(tx_approve) = state.pending_turn_reviews.remove(sub_id) {
+            tx_approve.send(approved).ok();
+        }
+    }
+
+    fn mode(&self) -> SessionMode {
+        *self.mode.lock().unwrap()
+    }
+
+    fn dry_run(&self) -> bool {
+        *self.dry_run.lock().unwrap()
+    }
+
+    /// Ask mode never escalates to the user, whatever the configured policy.
+    fn approval_policy(&self) -> AskForApproval {
+        match self.mode() {
+            SessionMode::Code => *self.approval_policy.lock().unwrap(),
+            SessionMode::Ask => AskForApproval::Never,
+        }
+    }
+
+    /// Sandbox policy for the next command: that of write mode once the
+    /// session switched to it, and read-only in Ask mode.
+    fn sandbox_policy(&self) -> SandboxPolicy {
+        if self.mode() == SessionMode::Ask {
+            return SandboxPolicy::new_read_only_policy();
+        }
+        let state = self.state.lock().unwrap();
+        let policy = match &self.write_mode_policy {
+            Some(policy) if state.write_mode => policy.clone(),
+            _ => self.sandbox_policy.clone(),
+        };
+        state.sandbox_grants.iter().fold(policy, |policy, access| {
+            policy.with_permission(access.permission())
+        })
+    }
+
+    /// Why the agent's actions need the user's confirmation for the rest of
+    /// the task, if untrusted content looked like it tried to instruct it.
+    fn untrusted_instructions(&self) -> Option<String> {
+        self.state.lock().unwrap().untrusted_instructions.clone()
+    }
+
+    /// Whether edits wait for the user to switch the session to write mode.
+    fn awaiting_write_mode(&self) -> bool {
+        self.write_mode_policy.is_some()
+            && self.mode() == SessionMode::Code
+            && !self.state.lock().unwrap().write_mode
+    }
+
+    /// Approval policy for patches. Write mode is a standing approval for
+    /// edits inside the writable roots.
+    fn patch_approval_policy(&self) -> AskForApproval {
+        match self.approval_policy() {
+            AskForApproval::UnlessAllowListed if self.write_mode_policy.is_some() => {
+                AskForApproval::AutoEdit
+            }
+            policy => policy,
+        }
+    }
+
     pub fn add_approved_command(&self, cmd: Vec<String>) {
         let mut state = self.state.lock().unwrap();
         state.approved_commands.insert(cmd);
@@ -333,6 +791,17 @@ impl Session {
         }
     }
 
+    /// Append the stats of a finished task to the rollout, for totals across
+    /// sessions.
+    async fn record_task_stats(&self, sub_id: &str, stats: &TaskStats) {
+        let recorder = self.rollout.lock().unwrap().clone();
+        if let Some(rec) = recorder {
+            if let Err(e) = rec.record_task_stats(sub_id, stats).await {
+                warn!("failed to record task stats: {e}");
+            }
+        }
+    }
+
     async fn notify_exec_command_begin(&self, sub_id: &str, call_id: &str, params: &ExecParams) {
         let event = Event {
             id: sub_id.to_string(),
@@ -368,6 +837,18 @@ impl Session {
         let _ = self.tx_event.send(event).await;
     }
 
+    /// Append a record to the session's audit log, if it keeps one.
+    async fn record_audit(
+        &self,
+        call_id: Option<&str>,
+        approval: AuditApproval,
+        action: AuditAction,
+    ) {
+        if let Some(audit) = &self.audit {
+            audit.record(call_id, approval, action).await;
+        }
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
@@ -392,6 +873,12 @@ impl Session {
         }
     }
 
+    /// Whether the user submitted input that has not yet been sent to the
+    /// model.
+    fn has_pending_input(&self) -> bool {
+        !self.state.lock().unwrap().pending_input.is_empty()
+    }
+
     pub fn get_pending_input(&self) -> Vec<ResponseInputItem> {
         let mut state = self.state.lock().unwrap();
         if state.pending_input.is_empty() {
@@ -415,10 +902,352 @@ impl Session {
             .await
     }
 
+    /// Replace the conversation context with `summary`. With a local
+    /// transcript the summary becomes its only entry; otherwise the server-side
+    /// chain is dropped and the summary is sent with the next turn.
+    fn replace_history_with_summary(&self, summary: &str) {
+        let mut state = self.state.lock().unwrap();
+        let mut text = format!("{COMPACTED_SUMMARY_PREFIX}\n\n{summary}");
+        if !state.scratchpad.is_empty() {
+            text.push_str(&format!("\n\n{SCRATCHPAD_REMINDER}"));
+        }
+        let item = ResponseItem::Message {
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText { text }],
+        };
+        state.previous_response_id = None;
+        state.context_tokens = 0;
+        match state.zdr_transcript.as_mut() {
+            Some(transcript) => {
+                let mut compacted = ConversationHistory::new();
+                compacted.record_items(std::slice::from_ref(&item));
+                *transcript = compacted;
+            }
+            None => state.compacted_summary = Some(item),
+        }
+        // The outputs of earlier reads are gone from the history.
+        state.read_cache.clear();
+    }
+
+    /// Switch to the settings that are set, unless the managed config locks
+    /// one of them, the model is unsuitable or the conversation no longer
+    /// fits its context window.
+    fn override_turn_context(
+        &self,
+        model: Option<String>,
+        effort: Option<ReasoningEffortConfig>,
+        summary: Option<ReasoningSummaryConfig>,
+        approval_policy: Option<AskForApproval>,
+        mode: Option<SessionMode>,
+        dry_run: Option<bool>,
+    ) -> Result<TurnContextOverriddenEvent, String> {
+        let overridden = [
+            ("model", model.is_some()),
+            ("model_reasoning_effort", effort.is_some()),
+            ("model_reasoning_summary", summary.is_some()),
+            ("approval_policy", approval_policy.is_some()),
+            ("mode", mode.is_some()),
+            ("dry_run", dry_run.is_some()),
+        ];
+        if let Some((key, _)) = overridden
+            .iter()
+            .find(|(key, overridden)| *overridden && self.config.locks(key))
+        {
+            return Err(format!("{key} is locked by the managed configuration"));
+        }
+
+        let mut client = self.client.lock().unwrap();
+        let model = model.unwrap_or_else(|| client.model().to_string());
+        let effort = effort.unwrap_or(client.effort());
+        let summary = summary.unwrap_or(client.summary());
+        check_sampling_params(client.sampling(), &model, client.provider(), effort)?;
+        if let Some(message) = self.copilot_plan_error(&model) {
+            return Err(message);
+        }
+
+        let context_window = get_model_info(&model).map(|info| info.context_window);
+        let context_tokens = self.state.lock().unwrap().context_tokens;
+        if let Some(context_window) = context_window.filter(|window| context_tokens > *window) {
+            return Err(format!(
+                "the conversation uses {context_tokens} tokens, more than the {context_window} token context window of {model}; run /compact first"
+            ));
+        }
+
+        info!("Overriding turn context: model={model}; effort={effort}; summary={summary}");
+        *client = client.with_model(model.clone(), effort, summary);
+        drop(client);
+        if let Some(approval_policy) = approval_policy {
+            *self.approval_policy.lock().unwrap() = approval_policy;
+        }
+        if let Some(mode) = mode {
+            *self.mode.lock().unwrap() = mode;
+        }
+        if let Some(dry_run) = dry_run {
+            *self.dry_run.lock().unwrap() = dry_run;
+        }
+        Ok(TurnContextOverriddenEvent {
+            model,
+            effort,
+            summary,
+            context_window,
+            approval_policy: *self.approval_policy.lock().unwrap(),
+            mode: self.mode(),
+            dry_run: self.dry_run(),
+        })
+    }
+
+    /// Why `model` cannot be used with the session's Copilot plan, if it
+    /// cannot.
+    fn copilot_plan_error(&self, model: &str) -> Option<String> {
+        let plan = self.state.lock().unwrap().copilot_plan?;
+        plan.check_model(model)
+    }
+
+    /// Tell the plan from the Copilot token's SKU, so that models the plan
+    /// does not include are refused before they fail with a 403.
+    async fn detect_copilot_plan(&self, sub_id: &str, auth: &AuthManager) {
+        let provider = self.client.lock().unwrap().provider().clone();
+        let Ok(Some(credentials)) = auth.get_credentials(&provider).await else {
+            return;
+        };
+        let Some(sku) = CopilotToken::from_raw_token(&credentials.token).sku else {
+            return;
+        };
+        let Some(plan) = CopilotPlan::from_sku(&sku) else {
+            info!("unknown Copilot SKU `{sku}`; not limiting models");
+            return;
+        };
+        self.state.lock().unwrap().copilot_plan = Some(plan);
+        let models = plan
+            .models()
+            .unwrap_or_default()
+            .iter()
+            .map(|model| model.to_string())
+            .collect();
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::CopilotPlanDetected(CopilotPlanDetectedEvent { plan, sku, models }),
+        })
+        .await;
+        let model = self.client.lock().unwrap().model().to_string();
+        if let Some(message) = self.copilot_plan_error(&model) {
+            self.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            })
+            .await;
+        }
+    }
+
+    /// Title the session after `input`, its first prompt, in the background.
+    fn title_from_first_prompt(&self, input: &[InputItem]) {
+        if !std::mem::take(&mut self.state.lock().unwrap().untitled) {
+            return;
+        }
+        let Some(rollout) = self.rollout.lock().unwrap().clone() else {
+            return;
+        };
+        let prompt = input
+            .iter()
+            .filter_map(|item| match item {
+                InputItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let client = self.small_client.clone();
+        tokio::spawn(async move {
+            if let Some(title) = generate_title(client.as_ref(), &prompt).await {
+                if let Err(e) = rollout.record_title(&title).await {
+                    warn!("failed to record the session title: {e}");
+                }
+            }
+        });
+    }
+
+    fn take_compacted_summary(&self) -> Option<ResponseItem> {
+        self.state.lock().unwrap().compacted_summary.take()
+    }
+
+    fn take_review_note(&self) -> Option<ResponseItem> {
+        self.state.lock().unwrap().review_note.take()
+    }
+
+    /// The recent Git changes, for the first task of the session that asks.
+    async fn take_git_context(&self, sub_id: &str) -> Option<ResponseItem> {
+        let settings = &self.config.git_context;
+        if !settings.enabled
+            || std::mem::replace(&mut self.state.lock().unwrap().git_context_sent, true)
+        {
+            return None;
+        }
+        let mut text = git_context(&self.cwd, settings).await?;
+        let redacted = self
+            .config
+            .redact_secrets
+            .then(|| redact_secrets(&text))
+            .flatten();
+        if let Some(redacted) = redacted {
+            notify_secrets_redacted(self, sub_id, "git diff".to_string(), redacted.secrets).await;
+            text = redacted.text;
+        }
+        self.notify_background_event(sub_id, "sent recent Git changes to the model")
+            .await;
+        Some(ResponseItem::Message {
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText { text }],
+        })
+    }
+
+    /// The project's memories most relevant to `input`, for the first task
+    /// of the session.
+    async fn take_memories(&self, sub_id: &str, input: &[InputItem]) -> Option<ResponseItem> {
+        let settings = &self.config.memory;
+        if !settings.enabled
+            || std::mem::replace(&mut self.state.lock().unwrap().memories_sent, true)
+        {
+            return None;
+        }
+        let memories = match project_memory::list(&project_root(&self.cwd)) {
+            Ok(memories) => memories,
+            Err(e) => {
+                warn!("failed to read the project's memories: {e:#}");
+                return None;
+            }
+        };
+        let prompt = input
+            .iter()
+            .filter_map(|item| match item {
+                InputItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let relevant = project_memory::relevant(&memories, &prompt, settings.max_bytes);
+        if relevant.is_empty() {
+            return None;
+        }
+        let names: Vec<&str> = relevant.iter().map(|memory| memory.name.as_str()).collect();
+        self.notify_background_event(sub_id, format!("recalled memories: {}", names.join(", ")))
+            .await;
+        Some(ResponseItem::Message {
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: project_memory::render(&relevant),
+            }],
+        })
+    }
+
+    /// Take a checkpoint of the conversation and the workspace.
+    async fn checkpoint(&self, sub_id: &str, automatic: bool) {
+        let number = {
+            let mut state = self.state.lock().unwrap();
+            let conversation = state.conversation_snapshot();
+            state.checkpoints.create(conversation)
+        };
+        let recorder = self.rollout.lock().unwrap().clone();
+        if let Some(recorder) = recorder {
+            if let Err(e) = recorder.record_checkpoint(number).await {
+                warn!("failed to record checkpoint {number}: {e}");
+            }
+        }
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::CheckpointCreated(CheckpointCreatedEvent { number, automatic }),
+        })
+        .await;
+    }
+
+    /// Take the conversation and the files the agent patched back to
+    /// checkpoint `number`.
+    async fn rewind(&self, sub_id: &str, number: usize) {
+        let result = {
+            let mut state = self.state.lock().unwrap();
+            if state.current_task.is_some() {
+                Err("cannot rewind while a task is running; interrupt it first".to_string())
+            } else {
+                match state.checkpoints.rewind(number) {
+                    Ok((conversation, restored)) => {
+                        state.restore_conversation(conversation);
+                        Ok(restored)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        let msg = match result {
+            Ok(restored) => {
+                if !restored.is_empty() {
+                    self.record_audit(
+                        None,
+                        AuditApproval::User,
+                        AuditAction::Revert {
+                            files: restored.clone(),
+                        },
+                    )
+                    .await;
+                }
+                let recorder = self.rollout.lock().unwrap().clone();
+                if let Some(recorder) = recorder {
+                    if let Err(e) = recorder.record_rewind(number).await {
+                        warn!("failed to record the rewind to checkpoint {number}: {e}");
+                    }
+                }
+                EventMsg::Rewound(RewoundEvent {
+                    checkpoint: number,
+                    restored,
+                })
+            }
+            Err(message) => EventMsg::Error(ErrorEvent { message }),
+        };
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg,
+        })
+        .await;
+    }
+
+    fn take_interrupted_turn(&self) -> Vec<ResponseItem> {
+        std::mem::take(&mut self.state.lock().unwrap().interrupted_turn)
+    }
+
+    fn take_resumed_history(&self) -> Vec<ResponseItem> {
+        std::mem::take(&mut self.state.lock().unwrap().resumed_history)
+    }
+
+    fn reset_partial_turn(&self) {
+        self.state.lock().unwrap().partial_turn = PartialTurn::default();
+    }
+
+    /// Abort the current task like [`Session::abort`], but keep what the
+    /// model produced in the interrupted turn, and any input queued for it,
+    /// so the next task builds on it. Tool calls that had not finished are
+    /// dropped, so the history never holds a call without its output.
+    ///
+    /// Returns the event announcing the interruption instead of sending it,
+    /// so the caller can order it before whatever it does next.
+    pub fn interrupt(&self) -> Option<Event> {
+        info!("Interrupting current task");
+        let mut state = self.state.lock().unwrap();
+        let task = state.current_task.take()?;
+        state.pending_approvals.clear();
+        state.pending_turn_reviews.clear();
+        let partial = std::mem::take(&mut state.partial_turn).into_items();
+        let queued = std::mem::take(&mut state.pending_input)
+            .into_iter()
+            .map(ResponseItem::from);
+        state
+            .interrupted_turn
+            .extend(partial.into_iter().chain(queued));
+        drop(state);
+        task.abort_with_event()
+    }
+
     pub fn abort(&self) {
         info!("Aborting existing session");
         let mut state = self.state.lock().unwrap();
         state.pending_approvals.clear();
+        state.pending_turn_reviews.clear();
         state.pending_input.clear();
         if let Some(task) = state.current_task.take() {
             task.abort();
@@ -471,9 +1300,43 @@ impl State {
             } else {
                 None
             },
+            compacted_summary: self.compacted_summary.clone(),
+            interrupted_turn: self.interrupted_turn.clone(),
+            resumed_history: self.resumed_history.clone(),
+            plan: self.plan.clone(),
+            write_mode: self.write_mode,
+            staged_edits: self.staged_edits.clone(),
+            review_note: self.review_note.clone(),
+            context_tokens: self.context_tokens,
+            untitled: self.untitled,
+            git_context_sent: self.git_context_sent,
+            memories_sent: self.memories_sent,
+            scratchpad: self.scratchpad.clone(),
+            checkpoints: self.checkpoints.clone(),
             ..Default::default()
         }
     }
+
+    fn conversation_snapshot(&self) -> ConversationSnapshot {
+        ConversationSnapshot {
+            transcript: self.zdr_transcript.clone(),
+            previous_response_id: self.previous_response_id.clone(),
+            compacted_summary: self.compacted_summary.clone(),
+            interrupted_turn: self.interrupted_turn.clone(),
+            resumed_history: self.resumed_history.clone(),
+        }
+    }
+
+    fn restore_conversation(&mut self, conversation: ConversationSnapshot) {
+        self.zdr_transcript = conversation.transcript;
+        self.previous_response_id = conversation.previous_response_id;
+        self.compacted_summary = conversation.compacted_summary;
+        self.interrupted_turn = conversation.interrupted_turn;
+        self.resumed_history = conversation.resumed_history;
+        self.context_tokens = 0;
+        // The outputs of later reads are gone from the history.
+        self.read_cache.clear();
+    }
 }
 
 /// A series of Turns in response to user input.
@@ -483,44 +1346,108 @@ pub(crate) struct AgentTask {
     handle: AbortHandle,
 }
 
+/// What a task does once the model finishes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TaskKind {
+    /// Regular user turn.
+    Regular,
+    /// `Op::Compact`: the final assistant message replaces the history.
+    Compact,
+}
+
 impl AgentTask {
-    fn spawn(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) -> Self {
-        let handle =
-            tokio::spawn(run_task(Arc::clone(&sess), sub_id.clone(), input)).abort_handle();
-        Self {
-            sess,
-            sub_id,
-            handle,
-        }
+    fn spawn(
+        sess: Arc<Session>,
+        sub_id: String,
+        input: Vec<InputItem>,
+        response_format: Option<ResponseFormat>,
+    ) -> Self {
+        Self::spawn_kind(sess, sub_id, input, TaskKind::Regular, response_format)
+    }
+
+    fn compact(sess: Arc<Session>, sub_id: String) -> Self {
+        let input = vec![InputItem::Text {
+            text: SUMMARIZATION_PROMPT.to_string(),
+        }];
+        Self::spawn_kind(sess, sub_id, input, TaskKind::Compact, None)
+    }
+
+    fn spawn_kind(
+        sess: Arc<Session>,
+        sub_id: String,
+        input: Vec<InputItem>,
+        kind: TaskKind,
+        response_format: Option<ResponseFormat>,
+    ) -> Self {
+        let span = info_span!("task", sub_id = %sub_id, compact = kind == TaskKind::Compact);
+        let task = run_task(
+            Arc::clone(&sess),
+            sub_id.clone(),
+            input,
+            kind,
+            response_format,
+        );
+        let handle = tokio::spawn(task.instrument(span)).abort_handle();
+        Self {
+            sess,
+            sub_id,
+            handle,
+        }
     }
 
     fn abort(self) {
-        if !self.handle.is_finished() {
-            self.handle.abort();
-            let event = Event {
-                id: self.sub_id,
-                msg: EventMsg::Error(ErrorEvent {
-                    message: "Turn interrupted".to_string(),
-                }),
-            };
-            let tx_event = self.sess.tx_event.clone();
+        let tx_event = self.sess.tx_event.clone();
+        if let Some(event) = self.abort_with_event() {
             tokio::spawn(async move {
                 tx_event.send(event).await.ok();
             });
         }
     }
+
+    /// Abort the task, returning the event that tells the client about it if
+    /// the task was still running.
+    fn abort_with_event(self) -> Option<Event> {
+        if self.handle.is_finished() {
+            return None;
+        }
+        self.handle.abort();
+        Some(Event {
+            id: self.sub_id,
+            msg: EventMsg::Error(ErrorEvent {
+                message: "Turn interrupted".to_string(),
+            }),
+        })
+    }
 }
 
 async fn submission_loop(
     config: Arc<Config>,
+    http_client: reqwest::Client,
     rx_sub: Receiver<Submission>,
     tx_event: Sender<Event>,
     ctrl_c: Arc<Notify>,
 ) {
-    // Generate a unique ID for the lifetime of this Codex session.
-    let session_id = Uuid::new_v4();
+    // A resumed session keeps its ID and appends to its existing rollout;
+    // otherwise generate a unique ID for the lifetime of this Codex session.
+    let mut resumed = config.resume_from.as_deref().and_then(|path| {
+        match RolloutRecorder::read_for_resume(path) {
+            Ok(resumed) => Some(resumed),
+            Err(e) => {
+                error!("failed to resume session from {}: {e}", path.display());
+                None
+            }
+        }
+    });
+    let session_id = resumed
+        .as_ref()
+        .map_or_else(Uuid::new_v4, |resumed| resumed.session_id);
 
     let mut sess: Option<Arc<Session>> = None;
+    // Cancels the Copilot sign-in in progress, if any, on interrupt.
+    let mut copilot_auth: Option<Arc<Notify>> = None;
+    // Shared by the model clients of every session, so that a refreshed token
+    // is reused.
+    let auth = Arc::new(AuthManager::new(&config.codex_home, http_client.clone()));
     // shorthand - send an event when there is no active session
     let send_no_session_event = |sub_id: String| async {
         let event = Event {
@@ -541,8 +1468,11 @@ async fn submission_loop(
                 Err(_) => break,
             },
             _ = interrupted => {
-                if let Some(sess) = sess.as_ref(){
-                    sess.abort();
+                if let Some(cancel) = copilot_auth.take() {
+                    cancel.notify_one();
+                }
+                if let Some(event) = sess.as_ref().and_then(|sess| sess.interrupt()) {
+                    tx_event.send(event).await.ok();
                 }
                 continue;
             },
@@ -550,7 +1480,10 @@ async fn submission_loop(
 
         debug!(?sub, "Submission");
         match sub.op {
-            Op::Interrupt => {
+            Op::Interrupt { replace_with } => {
+                if let Some(cancel) = copilot_auth.take() {
+                    cancel.notify_one();
+                }
                 let sess = match sess.as_ref() {
                     Some(sess) => sess,
                     None => {
@@ -558,23 +1491,53 @@ async fn submission_loop(
                         continue;
                     }
                 };
-                sess.abort();
+                if let Some(event) = sess.interrupt() {
+                    tx_event.send(event).await.ok();
+                }
+                if let Some(text) = replace_with {
+                    let items = vec![InputItem::Text { text }];
+                    sess.title_from_first_prompt(&items);
+                    let task = AgentTask::spawn(Arc::clone(sess), sub.id, items, None);
+                    sess.set_task(task);
+                }
             }
             Op::ConfigureSession {
                 provider,
                 model,
                 model_reasoning_effort,
                 model_reasoning_summary,
+                sampling,
                 instructions,
                 approval_policy,
                 sandbox_policy,
                 disable_response_storage,
                 notify,
                 cwd,
+                mode,
+                dry_run,
             } => {
-                info!("Configuring session: model={model}; provider={provider:?}");
-                if !cwd.is_absolute() {
-                    let message = format!("cwd is not absolute: {cwd:?}");
+                info!(
+                    "Configuring session: model={model}; provider={provider:?}; mode={mode:?}; dry_run={dry_run}"
+                );
+                // A replayed session never contacts the provider.
+                let offline_error = (config.offline
+                    && !is_local_url(&provider.base_url)
+                    && !is_replaying())
+                .then(|| {
+                    format!(
+                        "offline mode only allows local model providers, but {} is at {}",
+                        provider.name, provider.base_url
+                    )
+                });
+                let sampling_error = std::iter::once(&model)
+                    .chain(&config.router.small_model)
+                    .find_map(|model| {
+                        check_sampling_params(&sampling, model, &provider, model_reasoning_effort)
+                            .err()
+                    });
+                if let Some(message) = offline_error.or(sampling_error).or_else(|| {
+                    (!cwd.is_absolute()).then(|| format!("cwd is not absolute: {cwd:?}"))
+                }) {
                     error!(message);
                     let event = Event {
                         id: sub.id,
@@ -591,14 +1554,40 @@ async fn submission_loop(
                     provider.clone(),
                     model_reasoning_effort,
                     model_reasoning_summary,
+                    sampling,
+                    http_client.clone(),
+                    auth.clone(),
                 );
+                let small_client = config.router.small_model.clone().map(|small_model| {
+                    client.with_model(small_model, model_reasoning_effort, model_reasoning_summary)
+                });
+                let semantic_index = config.semantic_search.enabled.then(|| {
+                    let search = &config.semantic_search;
+                    let provider = search
+                        .provider
+                        .as_ref()
+                        .and_then(|key| config.model_providers.get(key))
+                        .unwrap_or(&provider);
+                    let embedding_client = ModelClient::new(
+                        search.model.clone(),
+                        provider.clone(),
+                        model_reasoning_effort,
+                        model_reasoning_summary,
+                        SamplingParams::default(),
+                        http_client.clone(),
+                        auth.clone(),
+                    );
+                    SemanticIndex::new(&cwd, embedding_client, config.offline)
+                });
 
                 // abort any current running session and clone its state
                 let retain_zdr_transcript =
                     record_conversation_history(disable_response_storage, provider.wire_api);
-                let state = match sess.take() {
+                let mut previous_rollout = None;
+                let mut state = match sess.take() {
                     Some(sess) => {
                         sess.abort();
+                        previous_rollout = sess.rollout.lock().unwrap().clone();
                         sess.state
                             .lock()
                             .unwrap()
@@ -613,8 +1602,44 @@ async fn submission_loop(
                         ..Default::default()
                     },
                 };
+                let resumed = resumed.take();
+                if let Some(resumed) = &resumed {
+                    match state.zdr_transcript.as_mut() {
+                        Some(transcript) => transcript.record_items(resumed.items.iter()),
+                        None => state.resumed_history = resumed.items.clone(),
+                    }
+                    if let Some(scratchpad) = &resumed.scratchpad {
+                        state.scratchpad = Scratchpad::new(scratchpad.clone());
+                    }
+                }
+
+                let mut writable_roots = get_writable_roots(&cwd);
+                // Grants the user asked to remember for this workspace. Ask
+                // mode is read-only whatever was granted: see `sandbox_policy()`.
+                let workspace = project_permissions::workspace_root(&cwd);
+                let grants = project_permissions::load_grants(&config.codex_home, &workspace)
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("failed to load remembered sandbox grants: {e:#}");
+                        Vec::new()
+                    });
+                for access in grants {
+                    match &access {
+                        _ if config.forbids(&access.permission()) => continue,
+                        SandboxAccess::Network { .. } if config.offline => continue,
+                        SandboxAccess::Write { path } => writable_roots.push(path.clone()),
+                        SandboxAccess::Network { .. } => {}
+                    }
+                    if !state.sandbox_grants.contains(&access) {
+                        state.sandbox_grants.push(access);
+                    }
+                }
+                let writable_roots = Mutex::new(writable_roots);
 
-                let writable_roots = Mutex::new(get_writable_roots(&cwd));
+                let sandbox_policy = if config.offline {
+                    sandbox_policy.without_network_access()
+                } else {
+                    sandbox_policy
+                };
 
                 // Error messages to dispatch after SessionConfigured is sent.
                 let mut mcp_connection_errors = Vec::<Event>::new();
@@ -646,26 +1671,59 @@ async fn submission_loop(
                 }
 
                 // Attempt to create a RolloutRecorder *before* moving the
-                // `instructions` value into the Session struct.
-                // TODO: if ConfigureSession is sent twice, we will create an
-                // overlapping rollout file. Consider passing RolloutRecorder
-                // from above.
-                let rollout_recorder =
-                    match RolloutRecorder::new(&config, session_id, instructions.clone()).await {
-                        Ok(r) => Some(r),
-                        Err(e) => {
-                            tracing::warn!("failed to initialise rollout recorder: {e}");
-                            None
+                // `instructions` value into the Session struct. A session
+                // that is reconfigured keeps writing to the same rollout.
+                let first_configure = previous_rollout.is_none();
+                let new_session = first_configure && resumed.is_none();
+                let rollout_recorder = match (previous_rollout, &resumed) {
+                    (Some(recorder), _) => Ok(Some(recorder)),
+                    (None, Some(resumed)) => RolloutRecorder::resume(&resumed.path).map(Some),
+                    (None, None) if !config.record_rollout => Ok(None),
+                    (None, None) => RolloutRecorder::new(&config, session_id, instructions.clone())
+                        .await
+                        .map(Some),
+                };
+                let rollout_recorder = match rollout_recorder {
+                    Ok(r) => r,
+                    Err(e) => {
+                        tracing::warn!("failed to initialise rollout recorder: {e}");
+                        None
+                    }
+                };
+                if new_session && rollout_recorder.is_some() {
+                    state.untitled = true;
+                }
+                // Enforce the retention policy once per session, sparing the
+                // rollout being resumed however old it is.
+                if first_configure && config.retention.is_enabled() {
+                    let codex_home = config.codex_home.clone();
+                    let retention = config.retention;
+                    let keep = resumed.as_ref().map(|resumed| resumed.path.clone());
+                    tokio::task::spawn_blocking(move || {
+                        let purged = crate::retention::purge(
+                            &codex_home,
+                            &retention,
+                            keep.as_deref(),
+                            false,
+                        );
+                        if let Err(e) = purged {
+                            tracing::warn!("failed to purge old sessions: {e}");
                         }
-                    };
+                    });
+                }
 
                 sess = Some(Arc::new(Session {
-                    client,
+                    client: Mutex::new(client),
+                    small_client,
+                    semantic_index,
                     tx_event: tx_event.clone(),
                     ctrl_c: Arc::clone(&ctrl_c),
                     instructions,
-                    approval_policy,
+                    approval_policy: Mutex::new(approval_policy),
                     sandbox_policy,
+                    write_mode_policy: config.escalation.clone(),
+                    mode: Mutex::new(mode),
+                    dry_run: Mutex::new(dry_run),
                     shell_environment_policy: config.shell_environment_policy.clone(),
                     cwd,
                     writable_roots,
@@ -674,6 +1732,10 @@ async fn submission_loop(
                     state: Mutex::new(state),
                     rollout: Mutex::new(rollout_recorder),
                     codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
+                    config: Arc::clone(&config),
+                    audit: config.audit_log.then(|| {
+                        AuditLog::new(&config.codex_home, session_id, config.encrypt_sessions)
+                    }),
                 }));
 
                 // Gather history metadata for SessionConfiguredEvent.
@@ -696,13 +1758,72 @@ async fn submission_loop(
                         error!("failed to send event: {e:?}");
                     }
                 }
+                if let Some(sess) = sess.as_ref() {
+                    if provider.name == COPILOT_PROVIDER_NAME && !is_replaying() {
+                        sess.detect_copilot_plan(&sub.id, &auth).await;
+                    }
+                }
+            }
+            Op::OverrideTurnContext {
+                model,
+                effort,
+                summary,
+                approval_policy,
+                mode,
+                dry_run,
+            } => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                let msg = match sess.override_turn_context(
+                    model,
+                    effort,
+                    summary,
+                    approval_policy,
+                    mode,
+                    dry_run,
+                ) {
+                    Ok(event) => EventMsg::TurnContextOverridden(event),
+                    Err(message) => {
+                        error!(message);
+                        EventMsg::Error(ErrorEvent { message })
+                    }
+                };
+                tx_event.send(Event { id: sub.id, msg }).await.ok();
+            }
+            Op::CopilotAuth if config.offline => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: "Copilot sign-in needs network access, which offline mode forbids"
+                            .to_string(),
+                    }),
+                };
+                tx_event.send(event).await.ok();
             }
             Op::CopilotAuth => {
                 let tx_event = tx_event.clone();
                 let sub_id = sub.id.clone();
-                
+                let http_client = http_client.clone();
+                let cancel = Arc::new(Notify::new());
+                // A new sign-in supersedes one still waiting for the user.
+                if let Some(previous) = copilot_auth.replace(Arc::clone(&cancel)) {
+                    previous.notify_one();
+                }
+
                 tokio::spawn(async move {
-                    if let Err(e) = crate::copilot::handle_copilot_auth(tx_event.clone(), sub_id.clone()).await {
+                    if let Err(e) = crate::copilot::handle_copilot_auth(
+                        http_client,
+                        tx_event.clone(),
+                        sub_id.clone(),
+                        cancel,
+                    )
+                    .await
+                    {
                         let event = Event {
                             id: sub_id,
                             msg: EventMsg::Error(ErrorEvent {
@@ -713,7 +1834,43 @@ async fn submission_loop(
                     }
                 });
             }
-            Op::UserInput { items } => {
+            Op::ChatGptAuth if config.offline => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: "ChatGPT sign-in needs network access, which offline mode forbids"
+                            .to_string(),
+                    }),
+                };
+                tx_event.send(event).await.ok();
+            }
+            Op::ChatGptAuth => {
+                let tx_event = tx_event.clone();
+                let sub_id = sub.id.clone();
+                let http_client = http_client.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) = crate::chatgpt_auth::handle_chatgpt_auth(
+                        http_client,
+                        tx_event.clone(),
+                        sub_id.clone(),
+                    )
+                    .await
+                    {
+                        let event = Event {
+                            id: sub_id,
+                            msg: EventMsg::Error(ErrorEvent {
+                                message: format!("ChatGPT sign-in failed: {e}"),
+                            }),
+                        };
+                        tx_event.send(event).await.ok();
+                    }
+                });
+            }
+            Op::UserInput {
+                items,
+                response_format,
+            } => {
                 let sess = match sess.as_ref() {
                     Some(sess) => sess,
                     None => {
@@ -722,13 +1879,86 @@ async fn submission_loop(
                     }
                 };
 
+                let client = sess.client();
+                let provider = client.provider();
+                if !provider.supports_images && items.iter().any(InputItem::is_image) {
+                    let message =
+                        CodexErr::ImageInputUnsupported(provider.name.clone()).to_string();
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    };
+                    tx_event.send(event).await.ok();
+                    continue;
+                }
+
+                sess.title_from_first_prompt(&items);
+                let items = attach_file_mentions(sess, &sub.id, items).await;
+
                 // attempt to inject input into current task
                 if let Err(items) = sess.inject_input(items) {
                     // no current task, spawn a new one
-                    let task = AgentTask::spawn(Arc::clone(sess), sub.id, items);
+                    let task = AgentTask::spawn(Arc::clone(sess), sub.id, items, response_format);
                     sess.set_task(task);
                 }
             }
+            Op::Compact => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+
+                let task = AgentTask::compact(Arc::clone(sess), sub.id);
+                sess.set_task(task);
+            }
+            Op::Checkpoint => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                sess.checkpoint(&sub.id, false).await;
+            }
+            Op::Rewind { checkpoint } => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                sess.rewind(&sub.id, checkpoint).await;
+            }
+            Op::Subscribe { .. } => {
+                // Applied by `Codex::submit_with_id`.
+            }
+            Op::Handshake { protocol_version } => {
+                let msg = if protocol_version == PROTOCOL_VERSION {
+                    EventMsg::Handshake(HandshakeEvent { protocol_version })
+                } else {
+                    EventMsg::Error(ErrorEvent {
+                        message: format!(
+                            "protocol version {protocol_version} is not supported; \
+                             this Codex speaks version {PROTOCOL_VERSION}"
+                        ),
+                    })
+                };
+                tx_event.send(Event { id: sub.id, msg }).await.ok();
+            }
+            Op::Unknown => {
+                let msg = EventMsg::Error(ErrorEvent {
+                    message: format!(
+                        "unsupported submission; this Codex speaks protocol version \
+                         {PROTOCOL_VERSION}"
+                    ),
+                });
+                tx_event.send(Event { id: sub.id, msg }).await.ok();
+            }
             Op::ExecApproval { id, decision } => {
                 let sess = match sess.as_ref() {
                     Some(sess) => sess,
@@ -759,6 +1989,16 @@ async fn submission_loop(
                     other => sess.notify_approval(&id, other),
                 }
             }
+            Op::TurnDiffApproval { id, approved } => {
+                let sess = match sess.as_ref() {
+                    Some(sess) => sess,
+                    None => {
+                        send_no_session_event(sub.id).await;
+                        continue;
+                    }
+                };
+                sess.notify_turn_diff_approval(&id, approved);
+            }
             Op::AddToHistory { text } => {
                 let id = session_id;
                 let config = config.clone();
@@ -817,10 +2057,25 @@ async fn submission_loop(
 ///   back to the model in the next turn.
 /// - If the model sends only an assistant message, we record it in the
 ///   conversation history and consider the task complete.
-async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
+async fn run_task(
+    sess: Arc<Session>,
+    sub_id: String,
+    input: Vec<InputItem>,
+    kind: TaskKind,
+    response_format: Option<ResponseFormat>,
+) {
     if input.is_empty() {
         return;
     }
+    let model = sess.client.lock().unwrap().model().to_string();
+    if let Some(message) = sess.copilot_plan_error(&model) {
+        sess.send_event(Event {
+            id: sub_id,
+            msg: EventMsg::Error(ErrorEvent { message }),
+        })
+        .await;
+        return;
+    }
     let event = Event {
         id: sub_id.clone(),
         msg: EventMsg::TaskStarted,
@@ -828,17 +2083,60 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
     if sess.tx_event.send(event).await.is_err() {
         return;
     }
+    let started = Instant::now();
+    {
+        let mut state = sess.state.lock().unwrap();
+        state.task_stats = TaskStats::default();
+        state.tool_call_repairs = 0;
+        state.untrusted_instructions = None;
+    }
 
+    if kind == TaskKind::Regular {
+        sess.checkpoint(&sub_id, true).await;
+    }
+    let memories = if kind == TaskKind::Regular {
+        sess.take_memories(&sub_id, &input).await
+    } else {
+        None
+    };
     let initial_input_for_turn = ResponseInputItem::from(input);
-    sess.record_conversation_items(&[initial_input_for_turn.clone().into()])
-        .await;
+    let mut items_to_record: Vec<ResponseItem> = Vec::new();
+    let compacted_summary = sess.take_compacted_summary();
+    let review_note = sess.take_review_note();
+    let git_context = if kind == TaskKind::Regular {
+        sess.take_git_context(&sub_id).await
+    } else {
+        None
+    };
+    let interrupted_turn = sess.take_interrupted_turn();
+    let resumed_history = sess.take_resumed_history();
+    items_to_record.extend(compacted_summary.clone());
+    items_to_record.extend(review_note.clone());
+    items_to_record.extend(git_context.clone());
+    items_to_record.extend(memories.clone());
+    items_to_record.extend(interrupted_turn.iter().cloned());
+    items_to_record.push(initial_input_for_turn.clone().into());
+    sess.record_conversation_items(&items_to_record).await;
 
     let mut input_for_next_turn: Vec<ResponseInputItem> = vec![initial_input_for_turn];
     let last_agent_message: Option<String>;
+    // Times the model was asked to fix a final message that did not match
+    // `response_format`.
+    let mut reasks = 0;
+    // The resumed history is already in the rollout, so it is only sent, not
+    // recorded again.
+    let mut carried_context: Vec<ResponseItem> = resumed_history
+        .into_iter()
+        .chain(compacted_summary)
+        .chain(review_note)
+        .chain(git_context)
+        .chain(memories)
+        .chain(interrupted_turn)
+        .collect();
     loop {
-        let mut net_new_turn_input = input_for_next_turn
+        let mut net_new_turn_input = carried_context
             .drain(..)
-            .map(ResponseItem::from)
+            .chain(input_for_next_turn.drain(..).map(ResponseItem::from))
             .collect::<Vec<_>>();
 
         // Note that pending_input would be something like a message the user
@@ -894,72 +2192,24 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
                 })
             })
             .collect();
-        match run_turn(&sess, sub_id.clone(), turn_input).await {
+        let turn_span = info_span!("turn", model = %sess.client_for(kind).model());
+        match run_turn(
+            &sess,
+            sub_id.clone(),
+            turn_input,
+            kind,
+            response_format.clone(),
+        )
+        .instrument(turn_span)
+        .await
+        {
             Ok(turn_output) => {
                 let mut items_to_record_in_conversation_history = Vec::<ResponseItem>::new();
                 let mut responses = Vec::<ResponseInputItem>::new();
                 for processed_response_item in turn_output {
                     let ProcessedResponseItem { item, response } = processed_response_item;
-                    match (&item, &response) {
-                        (ResponseItem::Message { role, .. }, None) if role == "assistant" => {
-                            // If the model returned a message, we need to record it.
-                            items_to_record_in_conversation_history.push(item);
-                        }
-                        (
-                            ResponseItem::LocalShellCall { .. },
-                            Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::FunctionCallOutput {
-                                    call_id: call_id.clone(),
-                                    output: output.clone(),
-                                },
-                            );
-                        }
-                        (
-                            ResponseItem::FunctionCall { .. },
-                            Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::FunctionCallOutput {
-                                    call_id: call_id.clone(),
-                                    output: output.clone(),
-                                },
-                            );
-                        }
-                        (
-                            ResponseItem::FunctionCall { .. },
-                            Some(ResponseInputItem::McpToolCallOutput { call_id, result }),
-                        ) => {
-                            items_to_record_in_conversation_history.push(item);
-                            let (content, success): (String, Option<bool>) = match result {
-                                Ok(CallToolResult { content, is_error }) => {
-                                    match serde_json::to_string(content) {
-                                        Ok(content) => (content, *is_error),
-                                        Err(e) => {
-                                            warn!("Failed to serialize MCP tool call output: {e}");
-                                            (e.to_string(), Some(true))
-                                        }
-                                    }
-                                }
-                                Err(e) => (e.clone(), Some(true)),
-                            };
-                            items_to_record_in_conversation_history.push(
-                                ResponseItem::FunctionCallOutput {
-                                    call_id: call_id.clone(),
-                                    output: FunctionCallOutputPayload { content, success },
-                                },
-                            );
-                        }
-                        (ResponseItem::Reasoning { .. }, None) => {
-                            // Omit from conversation history.
-                        }
-                        _ => {
-                            warn!("Unexpected response item: {item:?} with response: {response:?}");
-                        }
-                    };
+                    items_to_record_in_conversation_history
+                        .extend(history_items(&item, response.as_ref()));
                     if let Some(response) = response {
                         responses.push(response);
                     }
@@ -971,11 +2221,55 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
                         .await;
                 }
 
+                // Messages queued by the user while this turn was running
+                // are answered before the task ends.
+                if responses.is_empty() && kind == TaskKind::Regular && sess.has_pending_input() {
+                    debug!("Turn completed with queued user input");
+                    continue;
+                }
+
                 if responses.is_empty() {
                     debug!("Turn completed");
-                    last_agent_message = get_last_assistant_message_from_turn(
+                    let final_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
+                    if let Some(format) = &response_format {
+                        if let Err(violation) =
+                            check_final_message(format, final_message.as_deref())
+                        {
+                            if reasks == MAX_REASKS {
+                                let message = format!(
+                                    "final message does not match the response schema: {violation}"
+                                );
+                                error!(message);
+                                let event = Event {
+                                    id: sub_id.clone(),
+                                    msg: EventMsg::Error(ErrorEvent { message }),
+                                };
+                                sess.tx_event.send(event).await.ok();
+                                return;
+                            }
+                            reasks += 1;
+                            sess.notify_background_event(
+                                &sub_id,
+                                format!(
+                                    "final message does not match the response schema: {violation}; asking again ({reasks}/{MAX_REASKS})"
+                                ),
+                            )
+                            .await;
+                            let reask = ResponseInputItem::Message {
+                                role: "user".to_string(),
+                                content: vec![ContentItem::InputText {
+                                    text: reask_message(&violation),
+                                }],
+                            };
+                            sess.record_conversation_items(&[reask.clone().into()])
+                                .await;
+                            input_for_next_turn = vec![reask];
+                            continue;
+                        }
+                    }
+                    last_agent_message = final_message;
                     sess.maybe_notify(UserNotification::AgentTurnComplete {
                         turn_id: sub_id.clone(),
                         input_messages: turn_input_messages,
@@ -988,6 +2282,10 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
             }
             Err(e) => {
                 info!("Turn error: {e:#}");
+                sess.maybe_notify(UserNotification::Error {
+                    turn_id: sub_id.clone(),
+                    message: e.to_string(),
+                });
                 let event = Event {
                     id: sub_id.clone(),
                     msg: EventMsg::Error(ErrorEvent {
@@ -999,10 +2297,37 @@ async fn run_task(sess: Arc<Session>, sub_id: String, input: Vec<InputItem>) {
             }
         }
     }
+    if kind == TaskKind::Compact {
+        match &last_agent_message {
+            Some(summary) => {
+                sess.replace_history_with_summary(summary);
+                sess.notify_background_event(&sub_id, "Conversation compacted.")
+                    .await;
+            }
+            None => {
+                sess.notify_background_event(
+                    &sub_id,
+                    "Compaction produced no summary; the conversation was left unchanged.",
+                )
+                .await;
+            }
+        }
+    }
+    if kind == TaskKind::Regular {
+        review_turn_edits(&sess, &sub_id).await;
+    }
     sess.remove_task(&sub_id);
+    let stats = TaskStats {
+        duration_ms: started.elapsed().as_millis() as u64,
+        ..std::mem::take(&mut sess.state.lock().unwrap().task_stats)
+    };
+    sess.record_task_stats(&sub_id, &stats).await;
     let event = Event {
         id: sub_id,
-        msg: EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
+        msg: EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message,
+            stats: Some(stats),
+        }),
     };
     sess.tx_event.send(event).await.ok();
 }
@@ -1011,6 +2336,8 @@ async fn run_turn(
     sess: &Session,
     sub_id: String,
     input: Vec<ResponseItem>,
+    kind: TaskKind,
+    response_format: Option<ResponseFormat>,
 ) -> CodexResult<Vec<ProcessedResponseItem>> {
     // Decide whether to use server-side storage (previous_response_id) or disable it
     let (prev_id, store) = {
@@ -1026,22 +2353,44 @@ async fn run_turn(
         (prev_id, store)
     };
 
-    let extra_tools = sess.mcp_connection_manager.list_all_tools();
-    let prompt = Prompt {
+    let ask_mode = sess.mode() == SessionMode::Ask;
+    // MCP tools may have side effects we know nothing about.
+    let extra_tools = if ask_mode {
+        HashMap::new()
+    } else {
+        sess.mcp_connection_manager.list_all_tools()
+    };
+    let mut prompt = Prompt {
         input,
         prev_id,
         user_instructions: sess.instructions.clone(),
         store,
         extra_tools,
+        allow_sub_agents: sess.config.max_sub_agents > 0,
+        semantic_search: sess.semantic_index.is_some(),
+        ask_mode,
+        parallel_tool_calls: sess.config.max_parallel_tool_calls > 1,
+        shell_instructions: sess.config.shell.instructions(),
+        remember: sess.config.memory.enabled && !ask_mode,
+        response_format,
     };
 
     let mut retries = 0;
     loop {
-        match try_run_turn(sess, &sub_id, &prompt).await {
+        match try_run_turn(sess, &sub_id, &prompt, kind).await {
             Ok(output) => return Ok(output),
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
+            Err(e @ CodexErr::ReplayExhausted(_)) => return Err(e),
             Err(e) => {
+                // Items handled before the stream broke off are kept: their
+                // tool calls have run and their messages were shown. A retry
+                // continues from them.
+                let handled = std::mem::take(&mut sess.state.lock().unwrap().partial_turn.items);
+                if !handled.is_empty() {
+                    sess.record_conversation_items(&handled).await;
+                    prompt.input.extend(handled);
+                }
                 if retries < *OPENAI_STREAM_MAX_RETRIES {
                     retries += 1;
                     let delay = backoff(retries);
@@ -1080,49 +2429,299 @@ struct ProcessedResponseItem {
     response: Option<ResponseInputItem>,
 }
 
+/// Items to record in the conversation history for a processed model output
+/// item: assistant messages, and tool calls together with their outputs.
+fn history_items(item: &ResponseItem, response: Option<&ResponseInputItem>) -> Vec<ResponseItem> {
+    let mut items = Vec::new();
+    match (item, response) {
+        (ResponseItem::Message { role, .. }, None) if role == "assistant" => {
+            items.push(item.clone());
+        }
+        (
+            ResponseItem::LocalShellCall { .. },
+            Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
+        ) => {
+            items.push(item.clone());
+            items.push(ResponseItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: output.clone(),
+            });
+        }
+        (
+            ResponseItem::FunctionCall { .. },
+            Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
+        ) => {
+            items.push(item.clone());
+            items.push(ResponseItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: output.clone(),
+            });
+        }
+        (
+            ResponseItem::FunctionCall { .. },
+            Some(ResponseInputItem::McpToolCallOutput { call_id, result }),
+        ) => {
+            items.push(item.clone());
+            let (content, success): (String, Option<bool>) = match result {
+                Ok(CallToolResult { content, is_error }) => match serde_json::to_string(content) {
+                    Ok(content) => (content, *is_error),
+                    Err(e) => {
+                        warn!("Failed to serialize MCP tool call output: {e}");
+                        (e.to_string(), Some(true))
+                    }
+                },
+                Err(e) => (e.clone(), Some(true)),
+            };
+            items.push(ResponseItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: FunctionCallOutputPayload { content, success },
+            });
+        }
+        (ResponseItem::Reasoning { .. }, None) => {
+            // Omit from conversation history.
+        }
+        _ => {
+            warn!("Unexpected response item: {item:?} with response: {response:?}");
+        }
+    }
+    items
+}
+
 async fn try_run_turn(
     sess: &Session,
     sub_id: &str,
     prompt: &Prompt,
+    kind: TaskKind,
 ) -> CodexResult<Vec<ProcessedResponseItem>> {
-    let mut stream = sess.client.clone().stream(prompt).await?;
-
-    // Buffer all the incoming messages from the stream first, then execute them.
-    // If we execute a function call in the middle of handling the stream, it can time out.
-    let mut input = Vec::new();
-    while let Some(event) = stream.next().await {
-        input.push(event?);
+    sess.reset_partial_turn();
+    let client = sess.client_for(kind);
+    let wait = client.reserve_rate_limit(prompt);
+    if !wait.is_zero() {
+        sess.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::RateLimited(RateLimitedEvent {
+                provider: client.provider().name.clone(),
+                wait_ms: wait.as_millis() as u64,
+            }),
+        })
+        .await;
+        tokio::time::sleep(wait).await;
     }
-
-    let mut output = Vec::new();
-    for event in input {
-        match event {
-            ResponseEvent::OutputItemDone(item) => {
-                let response = handle_response_item(sess, sub_id, item.clone()).await?;
-                output.push(ProcessedResponseItem { item, response });
+    // Covers the request and its streamed response.
+    let request_span = info_span!(
+        "model_request",
+        model = %client.model(),
+        input_tokens = tracing::field::Empty,
+        cached_input_tokens = tracing::field::Empty,
+        output_tokens = tracing::field::Empty,
+        reasoning_output_tokens = tracing::field::Empty,
+        total_tokens = tracing::field::Empty,
+    );
+    let request_started = Instant::now();
+    let mut stream = client.stream(prompt).await?;
+
+    // Output items are handled as they arrive while the rest of the response
+    // keeps streaming, so the stream is not left idle (and timed out) while a
+    // tool call runs. Text deltas only feed the live display.
+    let (tx_item, rx_item) = mpsc::unbounded_channel();
+    let read_response = async move {
+        let mut completed = None;
+        while let Some(event) = stream.next().await {
+            match event? {
+                ResponseEvent::OutputItemDone(item) => {
+                    // The receiver is only gone when handling an item failed,
+                    // and that error is what the turn returns.
+                    tx_item.send(item).ok();
+                }
+                ResponseEvent::OutputTextDelta(delta) => {
+                    sess.state
+                        .lock()
+                        .unwrap()
+                        .partial_turn
+                        .message
+                        .push_str(&delta);
+                    sess.tx_event
+                        .send(Event {
+                            id: sub_id.to_string(),
+                            msg: EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }),
+                        })
+                        .await
+                        .ok();
+                }
+                ResponseEvent::ReasoningSummaryDelta(delta) => {
+                    sess.tx_event
+                        .send(Event {
+                            id: sub_id.to_string(),
+                            msg: EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }),
+                        })
+                        .await
+                        .ok();
+                }
+                ResponseEvent::Completed {
+                    response_id,
+                    token_usage,
+                } => {
+                    completed = Some((response_id, token_usage));
+                    break;
+                }
             }
-            ResponseEvent::Completed { response_id } => {
-                let mut state = sess.state.lock().unwrap();
-                state.previous_response_id = Some(response_id);
-                break;
+        }
+        {
+            let mut state = sess.state.lock().unwrap();
+            state.task_stats.model_ms += request_started.elapsed().as_millis() as u64;
+            state.task_stats.requests += 1;
+        }
+        if let Some((_, Some(usage))) = &completed {
+            request_span.record("input_tokens", usage.input_tokens);
+            request_span.record("cached_input_tokens", usage.cached_input_tokens);
+            request_span.record("output_tokens", usage.output_tokens);
+            request_span.record("reasoning_output_tokens", usage.reasoning_output_tokens);
+            request_span.record("total_tokens", usage.total_tokens);
+        }
+        Ok::<_, CodexErr>(completed)
+    };
+    // When the stream breaks off, the items received so far are still
+    // handled before the error is returned.
+    let (completed, output) =
+        tokio::join!(read_response, handle_response_items(sess, sub_id, rx_item));
+    let output = output?;
+    let completed = completed?;
+    if let Some((response_id, token_usage)) = completed {
+        {
+            let mut state = sess.state.lock().unwrap();
+            state.previous_response_id = Some(response_id);
+            if let Some(token_usage) = &token_usage {
+                state.context_tokens = token_usage.input_tokens;
+                state.task_stats.usage.add_assign(token_usage);
+                state.task_stats.add_cost(
+                    get_model_info(client.model()).map(|info| info.estimate_cost(token_usage)),
+                );
             }
         }
+        if let Some(token_usage) = token_usage {
+            sess.tx_event
+                .send(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::TokenCount(token_usage),
+                })
+                .await
+                .ok();
+        }
     }
+    sess.reset_partial_turn();
     Ok(output)
 }
 
-async fn handle_response_item(
+/// Handle the output items of a response as they arrive, in the order the
+/// model produced them. A run of consecutive tool calls that only read runs
+/// concurrently, up to `max_parallel_tool_calls` at a time, each call
+/// starting as soon as it arrives; their outputs still go back to the model
+/// in the order the calls were made. Any other item waits for the calls
+/// before it to finish.
+async fn handle_response_items(
     sess: &Session,
     sub_id: &str,
-    item: ResponseItem,
-) -> CodexResult<Option<ResponseInputItem>> {
-    debug!(?item, "Output item");
-    let output = match item {
-        ResponseItem::Message { content, .. } => {
-            for item in content {
-                if let ContentItem::OutputText { text } = item {
-                    let event = Event {
-                        id: sub_id.to_string(),
+    mut items: mpsc::UnboundedReceiver<ResponseItem>,
+) -> CodexResult<Vec<ProcessedResponseItem>> {
+    let limit = sess.config.max_parallel_tool_calls;
+    let handle = |item: ResponseItem| async move {
+        let response = handle_response_item(sess, sub_id, item.clone()).await;
+        (item, response)
+    };
+    let mut output = Vec::new();
+    let mut next = items.recv().await;
+    while let Some(item) = next.take() {
+        if limit <= 1 || !is_read_only_tool_call(&item) {
+            let (item, response) = handle(item).await;
+            output.push(processed_response_item(sess, item, response?));
+            next = items.recv().await;
+            continue;
+        }
+
+        let mut running = stream::FuturesOrdered::new();
+        running.push_back(handle(item));
+        let mut receiving = true;
+        while !running.is_empty() {
+            tokio::select! {
+                Some((item, response)) = running.next() => {
+                    output.push(processed_response_item(sess, item, response?));
+                }
+                received = items.recv(), if receiving && running.len() < limit => {
+                    match received {
+                        Some(item) if is_read_only_tool_call(&item) => {
+                            running.push_back(handle(item));
+                        }
+                        // The end of the run: handled once the calls finish.
+                        received => {
+                            next = received;
+                            receiving = false;
+                        }
+                    }
+                }
+            }
+        }
+        if receiving {
+            next = items.recv().await;
+        }
+    }
+    Ok(output)
+}
+
+/// Record a handled item in the partial turn, so that it survives an
+/// interruption or a stream error.
+fn processed_response_item(
+    sess: &Session,
+    item: ResponseItem,
+    response: Option<ResponseInputItem>,
+) -> ProcessedResponseItem {
+    let mut state = sess.state.lock().unwrap();
+    if matches!(item, ResponseItem::Message { .. }) {
+        // The streamed text is now part of the completed message.
+        state.partial_turn.message.clear();
+    }
+    let items = history_items(&item, response.as_ref());
+    state.partial_turn.items.extend(items);
+    ProcessedResponseItem { item, response }
+}
+
+/// Whether `item` is a tool call that cannot change anything, so it may run
+/// alongside others: a command known to be safe, which also never waits for
+/// an approval, a semantic search or a re-read of a file's changes.
+fn is_read_only_tool_call(item: &ResponseItem) -> bool {
+    let command = match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            SEMANTIC_SEARCH_TOOL_NAME | READ_FILE_CHANGES_TOOL_NAME => return true,
+            "container.exec" | "shell" => {
+                match serde_json::from_str::<ShellToolCallParams>(arguments) {
+                    Ok(params) => params.command,
+                    Err(_) => return false,
+                }
+            }
+            _ => return false,
+        },
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => action.command.clone(),
+        _ => return false,
+    };
+    is_known_safe_command(&command)
+}
+
+async fn handle_response_item(
+    sess: &Session,
+    sub_id: &str,
+    item: ResponseItem,
+) -> CodexResult<Option<ResponseInputItem>> {
+    debug!(?item, "Output item");
+    let output = match item {
+        ResponseItem::Message { content, .. } => {
+            for item in content {
+                if let ContentItem::OutputText { text } = item {
+                    let event = Event {
+                        id: sub_id.to_string(),
                         msg: EventMsg::AgentMessage(AgentMessageEvent { message: text }),
                     };
                     sess.tx_event.send(event).await.ok();
@@ -1149,7 +2748,12 @@ async fn handle_response_item(
             call_id,
         } => {
             tracing::info!("FunctionCall: {arguments}");
-            Some(handle_function_call(sess, sub_id.to_string(), name, arguments, call_id).await)
+            if let Some(output) =
+                check_tool_arguments(sess, sub_id, &name, &arguments, &call_id).await
+            {
+                return Ok(Some(output));
+            }
+            Some(run_tool_call(sess, sub_id, name, arguments, call_id).await)
         }
         ResponseItem::LocalShellCall {
             id,
@@ -1159,11 +2763,13 @@ async fn handle_response_item(
         } => {
             let LocalShellAction::Exec(action) = action;
             tracing::info!("LocalShellCall: {action:?}");
-            let params = ShellToolCallParams {
-                command: action.command,
-                workdir: action.working_directory,
-                timeout_ms: action.timeout_ms,
-            };
+            // Handled as a `shell` function call so that hooks see it too.
+            let arguments = serde_json::json!({
+                "command": action.command,
+                "workdir": action.working_directory,
+                "timeout": action.timeout_ms,
+            })
+            .to_string();
             let effective_call_id = match (call_id, id) {
                 (Some(call_id), _) => call_id,
                 (None, Some(id)) => id,
@@ -1179,12 +2785,12 @@ async fn handle_response_item(
                 }
             };
 
-            let exec_params = to_exec_params(params, sess);
             Some(
-                handle_container_exec_with_params(
-                    exec_params,
+                run_tool_call(
                     sess,
-                    sub_id.to_string(),
+                    sub_id,
+                    "shell".to_string(),
+                    arguments,
                     effective_call_id,
                 )
                 .await,
@@ -1199,6 +2805,191 @@ async fn handle_response_item(
     Ok(output)
 }
 
+/// Check the arguments of a call against the tool's parameters. When they do
+/// not match, returns the output that asks the model to correct them instead
+/// of running the call, unless the model has already been asked
+/// [`MAX_TOOL_CALL_REPAIRS`] times in a row.
+async fn check_tool_arguments(
+    sess: &Session,
+    sub_id: &str,
+    name: &str,
+    arguments: &str,
+    call_id: &str,
+) -> Option<ResponseInputItem> {
+    let parameters = match sess.mcp_connection_manager.tool(name) {
+        Some(tool) => serde_json::to_value(&tool.input_schema).ok(),
+        None => builtin_tool_parameters(name),
+    }?;
+    let violation = match check_arguments(&parameters, arguments) {
+        Ok(()) => {
+            sess.state.lock().unwrap().tool_call_repairs = 0;
+            return None;
+        }
+        Err(violation) => violation,
+    };
+    let attempt = {
+        let mut state = sess.state.lock().unwrap();
+        if state.tool_call_repairs == MAX_TOOL_CALL_REPAIRS {
+            // Leave it to the tool to report what is wrong.
+            return None;
+        }
+        state.tool_call_repairs += 1;
+        state.tool_call_repairs
+    };
+    warn!("invalid arguments for {name}: {violation}");
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::ToolCallRepair(ToolCallRepairEvent {
+            call_id: call_id.to_string(),
+            tool: display_tool_name(name),
+            violation: violation.clone(),
+            attempt,
+            max_attempts: MAX_TOOL_CALL_REPAIRS,
+        }),
+    })
+    .await;
+    Some(ResponseInputItem::FunctionCallOutput {
+        call_id: call_id.to_string(),
+        output: FunctionCallOutputPayload {
+            content: repair_message(name, &violation),
+            success: Some(false),
+        },
+    })
+}
+
+/// Run a tool call the model asked for and prepare its output for the model.
+async fn run_tool_call(
+    sess: &Session,
+    sub_id: &str,
+    name: String,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let tool = display_tool_name(&name);
+    let untrusted = untrusted_output_source(sess, &name, &arguments);
+    let span = info_span!(
+        "tool_call",
+        tool = %tool,
+        call_id = %call_id,
+        success = tracing::field::Empty,
+    );
+    let output = handle_function_call(sess, sub_id.to_string(), name, arguments, call_id)
+        .instrument(span.clone())
+        .await;
+    if let (_, Some(success)) = tool_call_outcome(&output) {
+        span.record("success", success);
+    }
+    let output = redact_tool_output(sess, sub_id, &format!("{tool} output"), output).await;
+    match untrusted {
+        Some(source) => mark_untrusted_output(sess, sub_id, &source, output).await,
+        None => output,
+    }
+}
+
+/// What produced the output of a call when `[untrusted_content]` says not to
+/// trust it.
+fn untrusted_output_source(sess: &Session, name: &str, arguments: &str) -> Option<String> {
+    let exec_params = match name {
+        "container.exec" | "shell" => serde_json::from_str::<ShellToolCallParams>(arguments)
+            .ok()
+            .map(|params| to_exec_params(params, sess)),
+        _ => None,
+    };
+    untrusted_source(
+        &sess.config.untrusted_content,
+        &display_tool_name(name),
+        exec_params
+            .as_ref()
+            .map(|params| (params.command.as_slice(), params.cwd.as_path())),
+    )
+}
+
+/// Wrap untrusted output from `source` in provenance markers. When it looks
+/// like it tries to instruct the agent, warn the user: the agent's actions
+/// need their confirmation for the rest of the task.
+async fn mark_untrusted_output(
+    sess: &Session,
+    sub_id: &str,
+    source: &str,
+    mut output: ResponseInputItem,
+) -> ResponseInputItem {
+    let mut texts: Vec<&mut String> = match &mut output {
+        ResponseInputItem::FunctionCallOutput { output, .. } => vec![&mut output.content],
+        ResponseInputItem::McpToolCallOutput {
+            result: Ok(result), ..
+        } => result
+            .content
+            .iter_mut()
+            .filter_map(|content| match content {
+                CallToolResultContent::TextContent(content) => Some(&mut content.text),
+                _ => None,
+            })
+            .collect(),
+        ResponseInputItem::McpToolCallOutput { result: Err(_), .. }
+        | ResponseInputItem::Message { .. } => Vec::new(),
+    };
+    let mut signs = Vec::new();
+    for text in &texts {
+        for sign in injection_signs(text) {
+            if !signs.contains(&sign) {
+                signs.push(sign);
+            }
+        }
+    }
+    for text in &mut texts {
+        **text = wrap_untrusted(source, text, &signs);
+    }
+    if !signs.is_empty() {
+        let reason = format!(
+            "the output of `{source}` looks like it tries to instruct the agent ({})",
+            signs.join("; ")
+        );
+        sess.state.lock().unwrap().untrusted_instructions = Some(reason.clone());
+        sess.notify_background_event(
+            sub_id,
+            format!(
+                "Possible prompt injection: {reason}. Commands, edits and tool calls need your confirmation for the rest of this task."
+            ),
+        )
+        .await;
+    }
+    output
+}
+
+/// Have the user confirm an action the approval policy would allow, because
+/// of `reason`: untrusted content looked like it tried to instruct the agent.
+/// Without a user to ask, the action is refused.
+async fn confirm_untrusted_action(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    command: Vec<String>,
+    reason: String,
+) -> Result<(), ResponseInputItem> {
+    let refuse = |content: String| ResponseInputItem::FunctionCallOutput {
+        call_id: call_id.to_string(),
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    };
+    if sess.approval_policy() == AskForApproval::Never {
+        return Err(refuse(format!(
+            "not run without the user's confirmation: {reason}"
+        )));
+    }
+    let rx_approve = sess
+        .request_command_approval(sub_id.to_string(), command, sess.cwd.clone(), Some(reason))
+        .await;
+    match rx_approve.await.unwrap_or_default() {
+        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => Ok(()),
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            Err(refuse("rejected by user".to_string()))
+        }
+    }
+}
+
+/// Run a tool call, surrounded by the `[hooks]` the user configured.
 async fn handle_function_call(
     sess: &Session,
     sub_id: String,
@@ -1206,6 +2997,296 @@ async fn handle_function_call(
     arguments: String,
     call_id: String,
 ) -> ResponseInputItem {
+    let hooks = &sess.config.hooks;
+    if hooks.pre_tool_call.is_empty() && hooks.post_tool_call.is_empty() {
+        return dispatch_function_call(sess, sub_id, name, arguments, call_id).await;
+    }
+
+    let arguments_json = serde_json::from_str(&arguments)
+        .unwrap_or_else(|_| serde_json::Value::String(arguments.clone()));
+    let files = patch_files(sess, &name, &arguments_json);
+    let tool = if files.is_empty() {
+        display_tool_name(&name)
+    } else {
+        "apply_patch".to_string()
+    };
+    let mut hook_call = HookToolCall {
+        tool: &tool,
+        call_id: &call_id,
+        cwd: &sess.cwd,
+        arguments: arguments_json,
+        files: &files,
+    };
+    let arguments = match run_pre_tool_call_hooks(&hooks.pre_tool_call, hook_call.clone()).await {
+        Ok(arguments) => {
+            let serialized = match &arguments {
+                serde_json::Value::String(raw) => raw.clone(),
+                other => other.to_string(),
+            };
+            hook_call.arguments = arguments;
+            serialized
+        }
+        Err(reason) => {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: reason,
+                    success: Some(false),
+                },
+            };
+        }
+    };
+
+    let output = dispatch_function_call(sess, sub_id, name, arguments, call_id.clone()).await;
+    let (content, success) = tool_call_outcome(&output);
+    run_post_tool_call_hooks(&hooks.post_tool_call, &hook_call, &content, success).await;
+    output
+}
+
+/// Output of a tool call as text, and whether the call succeeded if known.
+fn tool_call_outcome(output: &ResponseInputItem) -> (String, Option<bool>) {
+    match output {
+        ResponseInputItem::FunctionCallOutput { output, .. } => {
+            (output.content.clone(), output.success)
+        }
+        ResponseInputItem::McpToolCallOutput {
+            result: Ok(result), ..
+        } => (
+            serde_json::to_string(result).unwrap_or_default(),
+            Some(result.is_error != Some(true)),
+        ),
+        ResponseInputItem::McpToolCallOutput { result: Err(e), .. } => (e.clone(), Some(false)),
+        ResponseInputItem::Message { .. } => (String::new(), None),
+    }
+}
+
+/// Name of a tool as shown to users: `<server>/<tool>` for MCP tools.
+fn display_tool_name(name: &str) -> String {
+    match try_parse_fully_qualified_tool_name(name) {
+        Some((server, tool_name)) => format!("{server}/{tool_name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Replace likely secrets in what a tool call returns to the model.
+async fn redact_tool_output(
+    sess: &Session,
+    sub_id: &str,
+    source: &str,
+    mut output: ResponseInputItem,
+) -> ResponseInputItem {
+    if !sess.config.redact_secrets {
+        return output;
+    }
+    let mut secrets = Vec::new();
+    let mut redact = |text: &mut String| {
+        let Some(redacted) = redact_secrets(text) else {
+            return;
+        };
+        *text = redacted.text;
+        for secret in redacted.secrets {
+            match secrets
+                .iter_mut()
+                .find(|seen: &&mut RedactedSecret| seen.kind == secret.kind)
+            {
+                Some(seen) => seen.count += secret.count,
+                None => secrets.push(secret),
+            }
+        }
+    };
+    match &mut output {
+        ResponseInputItem::FunctionCallOutput { output, .. } => redact(&mut output.content),
+        ResponseInputItem::McpToolCallOutput {
+            result: Ok(result), ..
+        } => {
+            for content in &mut result.content {
+                if let CallToolResultContent::TextContent(content) = content {
+                    redact(&mut content.text);
+                }
+            }
+        }
+        ResponseInputItem::McpToolCallOutput { result: Err(e), .. } => redact(e),
+        ResponseInputItem::Message { .. } => {}
+    }
+    notify_secrets_redacted(sess, sub_id, source.to_string(), secrets).await;
+    output
+}
+
+/// Read the files the user mentioned, within the `[attachments]` limits and
+/// replacing likely secrets, so that their contents reach the model as text.
+async fn attach_file_mentions(
+    sess: &Session,
+    sub_id: &str,
+    input: Vec<InputItem>,
+) -> Vec<InputItem> {
+    let mut budget = AttachmentBudget::new(&sess.config.attachments);
+    let mut items = Vec::with_capacity(input.len());
+    for item in input {
+        let InputItem::LocalFile { path } = &item else {
+            items.push(item);
+            continue;
+        };
+        let source = path
+            .strip_prefix(&sess.cwd)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let attachment = match budget.attach(path, &source) {
+            Ok(attachment) => attachment,
+            Err(e) => {
+                warn!(
+                    "Skipping file {} – could not read file: {e}",
+                    path.display()
+                );
+                sess.notify_background_event(sub_id, format!("{source} could not be read: {e}"))
+                    .await;
+                continue;
+            }
+        };
+        if let Some(notice) = attachment.notice {
+            sess.notify_background_event(sub_id, notice).await;
+        }
+        let redacted = if sess.config.redact_secrets {
+            redact_secrets(&attachment.text)
+        } else {
+            None
+        };
+        let text = match redacted {
+            Some(redacted) => {
+                notify_secrets_redacted(sess, sub_id, source, redacted.secrets).await;
+                redacted.text
+            }
+            None => attachment.text,
+        };
+        items.push(InputItem::Text { text });
+    }
+    items
+}
+
+async fn notify_secrets_redacted(
+    sess: &Session,
+    sub_id: &str,
+    source: String,
+    secrets: Vec<RedactedSecret>,
+) {
+    if secrets.is_empty() {
+        return;
+    }
+    let event = Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::SecretsRedacted(SecretsRedactedEvent { source, secrets }),
+    };
+    sess.send_event(event).await;
+}
+
+/// Files a `shell` call running `apply_patch` would touch, so hooks can
+/// match on them; empty for every other call.
+fn patch_files(sess: &Session, name: &str, arguments: &serde_json::Value) -> Vec<PathBuf> {
+    if !matches!(name, "container.exec" | "shell") {
+        return Vec::new();
+    }
+    let Ok(params) = serde_json::from_value::<ShellToolCallParams>(arguments.clone()) else {
+        return Vec::new();
+    };
+    let params = to_exec_params(params, sess);
+    let MaybeApplyPatchVerified::Body(action) =
+        maybe_parse_apply_patch_verified(&params.command, &params.cwd)
+    else {
+        return Vec::new();
+    };
+    action_paths(&action)
+}
+
+/// Every path `action` writes, sorted: the moved-from and moved-to paths of
+/// a rename are both listed.
+fn action_paths(action: &ApplyPatchAction) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = action
+        .changes()
+        .iter()
+        .flat_map(|(path, change)| match change {
+            ApplyPatchFileChange::Update {
+                move_path: Some(dest),
+                ..
+            } => vec![path.clone(), dest.clone()],
+            _ => vec![path.clone()],
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Refuse the call if the policy file denies it, telling the model which
+/// rule refused it and recording the refusal in the audit log.
+async fn check_policy(
+    sess: &Session,
+    name: &str,
+    arguments: &str,
+    call_id: &str,
+) -> Result<(), ResponseInputItem> {
+    if sess.config.policy.rules.is_empty() {
+        return Ok(());
+    }
+    let call = policy_call(sess, name, arguments);
+    let Err(refusal) = sess.config.policy.check(&call, &sess.cwd) else {
+        return Ok(());
+    };
+    let reason = refusal
+        .reason
+        .as_ref()
+        .map(|reason| format!(": {reason}"))
+        .unwrap_or_default();
+    let content = format!(
+        "refused by policy rule `{}` ({}){reason}",
+        refusal.rule, refusal.matched
+    );
+    sess.record_audit(
+        Some(call_id),
+        AuditApproval::Policy,
+        AuditAction::PolicyViolation {
+            tool: call.tool,
+            rule: refusal.rule,
+            matched: refusal.matched,
+        },
+    )
+    .await;
+    Err(ResponseInputItem::FunctionCallOutput {
+        call_id: call_id.to_string(),
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    })
+}
+
+/// What the policy file sees of a call to `name`: like hooks, a `shell`
+/// call running `apply_patch` is seen as an `apply_patch` call.
+fn policy_call(sess: &Session, name: &str, arguments: &str) -> PolicyCall {
+    let shell_params = match name {
+        "container.exec" | "shell" => serde_json::from_str::<ShellToolCallParams>(arguments).ok(),
+        _ => None,
+    };
+    if let Some(params) = shell_params {
+        let params = to_exec_params(params, sess);
+        return match maybe_parse_apply_patch_verified(&params.command, &params.cwd) {
+            MaybeApplyPatchVerified::Body(action) => PolicyCall::patch(action_paths(&action)),
+            _ => PolicyCall::command(&params.command, &params.cwd),
+        };
+    }
+    let arguments = serde_json::from_str(arguments)
+        .unwrap_or_else(|_| serde_json::Value::String(arguments.to_string()));
+    PolicyCall::tool(display_tool_name(name), &arguments)
+}
+
+async fn dispatch_function_call(
+    sess: &Session,
+    sub_id: String,
+    name: String,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    if let Err(output) = check_policy(sess, &name, &arguments, &call_id).await {
+        return output;
+    }
     match name.as_str() {
         "container.exec" | "shell" => {
             let params = match parse_container_exec_arguments(arguments, sess, &call_id) {
@@ -1216,9 +3297,32 @@ async fn handle_function_call(
             };
             handle_container_exec_with_params(params, sess, sub_id, call_id).await
         }
+        UPDATE_PLAN_TOOL_NAME => handle_update_plan(sess, &sub_id, call_id, &arguments).await,
+        SPAWN_AGENTS_TOOL_NAME => handle_spawn_agents(sess, &sub_id, call_id, arguments).await,
+        SEMANTIC_SEARCH_TOOL_NAME => handle_semantic_search(sess, call_id, &arguments).await,
+        READ_FILE_CHANGES_TOOL_NAME => handle_read_file_changes(sess, call_id, &arguments),
+        SCRATCHPAD_TOOL_NAME => handle_scratchpad(sess, call_id, &arguments).await,
+        REMEMBER_TOOL_NAME => handle_remember(sess, sub_id, call_id, &arguments).await,
         _ => {
             match try_parse_fully_qualified_tool_name(&name) {
+                Some(_) if sess.mode() == SessionMode::Ask => {
+                    ResponseInputItem::FunctionCallOutput {
+                        call_id,
+                        output: FunctionCallOutputPayload {
+                            content: format!("{name} is not available in ask mode"),
+                            success: Some(false),
+                        },
+                    }
+                }
                 Some((server, tool_name)) => {
+                    if let Some(reason) = sess.untrusted_instructions() {
+                        let command = vec![display_tool_name(&name), arguments.clone()];
+                        if let Err(output) =
+                            confirm_untrusted_action(sess, &sub_id, &call_id, command, reason).await
+                        {
+                            return output;
+                        }
+                    }
                     // TODO(mbolin): Determine appropriate timeout for tool call.
                     let timeout = None;
                     handle_mcp_tool_call(
@@ -1241,15 +3345,234 @@ async fn handle_function_call(
     }
 }
 
+async fn handle_update_plan(
+    sess: &Session,
+    sub_id: &str,
+    call_id: String,
+    arguments: &str,
+) -> ResponseInputItem {
+    let (content, success) = match parse_update_plan(arguments) {
+        Ok(update) => {
+            sess.state.lock().unwrap().plan = update.plan.clone();
+            let event = Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::PlanUpdated(update),
+            };
+            sess.tx_event.send(event).await.ok();
+            ("Plan updated".to_string(), true)
+        }
+        Err(e) => (e, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+async fn handle_spawn_agents(
+    sess: &Session,
+    sub_id: &str,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let result = match serde_json::from_str::<SpawnAgentsArgs>(&arguments) {
+        Ok(args) => {
+            let call_seq = {
+                let mut state = sess.state.lock().unwrap();
+                state.sub_agent_calls += 1;
+                state.sub_agent_calls
+            };
+            let sandbox_policy = sess.sandbox_policy();
+            let client = sess.client();
+            let parent = ParentSession {
+                config: &sess.config,
+                model: client.model(),
+                cwd: &sess.cwd,
+                sandbox_policy: &sandbox_policy,
+                approval_policy: sess.approval_policy(),
+                mode: sess.mode(),
+            };
+            // Sub-agent events arrive through a plain callback, so queue them
+            // and let a task forward them in order.
+            let (tx_forward, mut rx_forward) = tokio::sync::mpsc::unbounded_channel();
+            let tx_event = sess.tx_event.clone();
+            let sub_id = sub_id.to_string();
+            let forwarder = tokio::spawn(async move {
+                while let Some(event) = rx_forward.recv().await {
+                    let event = Event {
+                        id: sub_id.clone(),
+                        msg: EventMsg::SubAgent(event),
+                    };
+                    if tx_event.send(event).await.is_err() {
+                        break;
+                    }
+                }
+            });
+            let result = run_sub_agents(parent, call_seq, args, move |event| {
+                tx_forward.send(event).ok();
+            })
+            .await;
+            // `run_sub_agents` consumed the sender, so the forwarder stops
+            // once the queued events have been sent.
+            forwarder.await.ok();
+            result
+        }
+        Err(e) => Err(format!("failed to parse function arguments: {e}")),
+    };
+    let (content, success) = match result {
+        Ok(report) => (report, true),
+        Err(e) => (e, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+async fn handle_semantic_search(
+    sess: &Session,
+    call_id: String,
+    arguments: &str,
+) -> ResponseInputItem {
+    let result = match (
+        &sess.semantic_index,
+        serde_json::from_str::<SemanticSearchArgs>(arguments),
+    ) {
+        (None, _) => Err(format!("{SEMANTIC_SEARCH_TOOL_NAME} is not enabled")),
+        (Some(_), Err(e)) => Err(format!("failed to parse function arguments: {e}")),
+        (Some(index), Ok(args)) => index.search(args).await,
+    };
+    let (content, success) = match result {
+        Ok(results) => (results, true),
+        Err(e) => (e, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+fn handle_read_file_changes(sess: &Session, call_id: String, arguments: &str) -> ResponseInputItem {
+    let result = match serde_json::from_str::<ReadFileChangesArgs>(arguments) {
+        Ok(args) => {
+            let path = sess.resolve_path(Some(args.path));
+            sess.state
+                .lock()
+                .unwrap()
+                .read_cache
+                .changes_since_last_read(&path)
+        }
+        Err(e) => Err(format!("failed to parse function arguments: {e}")),
+    };
+    let (content, success) = match result {
+        Ok(changes) => (changes, true),
+        Err(e) => (e, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+async fn handle_scratchpad(sess: &Session, call_id: String, arguments: &str) -> ResponseInputItem {
+    let result = serde_json::from_str::<ScratchpadArgs>(arguments)
+        .map_err(|e| format!("failed to parse function arguments: {e}"))
+        .and_then(|args| {
+            let mut state = sess.state.lock().unwrap();
+            let outcome = state.scratchpad.apply(args)?;
+            Ok((outcome, state.scratchpad.content().to_string()))
+        });
+    let (content, success) = match result {
+        Ok((ScratchpadOutcome::Read(content), _)) => (content, true),
+        Ok((ScratchpadOutcome::Changed(message), scratchpad)) => {
+            let recorder = sess.rollout.lock().unwrap().clone();
+            if let Some(recorder) = recorder {
+                if let Err(e) = recorder.record_scratchpad(&scratchpad).await {
+                    warn!("failed to record the scratchpad: {e}");
+                }
+            }
+            (message, true)
+        }
+        Err(e) => (e, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+/// Save a memory as an `apply_patch` call, which dry runs, approvals, the
+/// writable roots and the audit log apply to as they do to the model's
+/// other edits.
+async fn handle_remember(
+    sess: &Session,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> ResponseInputItem {
+    let patch = match serde_json::from_str::<RememberArgs>(arguments) {
+        Ok(_) if !sess.config.memory.enabled || sess.mode() == SessionMode::Ask => {
+            Err(format!("{REMEMBER_TOOL_NAME} is not enabled"))
+        }
+        Ok(args) => project_memory::save_patch(&project_root(&sess.cwd), &args.name, &args.content)
+            .map_err(|e| format!("{e:#}")),
+        Err(e) => Err(format!("failed to parse function arguments: {e}")),
+    };
+    match patch {
+        Ok(patch) => {
+            let params = ExecParams {
+                command: vec!["apply_patch".to_string(), patch],
+                cwd: sess.cwd.clone(),
+                timeout_ms: None,
+                env: create_env(&sess.shell_environment_policy),
+            };
+            handle_container_exec_with_params(params, sess, sub_id, call_id).await
+        }
+        Err(content) => ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        },
+    }
+}
+
 fn to_exec_params(params: ShellToolCallParams, sess: &Session) -> ExecParams {
     ExecParams {
         command: params.command,
-        cwd: sess.resolve_path(params.workdir.clone()),
+        cwd: params.workdir.clone().map_or_else(
+            || sess.working_dir(),
+            |workdir| sess.resolve_path(Some(workdir)),
+        ),
         timeout_ms: params.timeout_ms,
         env: create_env(&sess.shell_environment_policy),
     }
 }
 
+/// `params` with the command as it runs in `shell`.
+fn for_shell(params: &ExecParams, shell: Shell) -> ExecParams {
+    ExecParams {
+        command: shell.translate(&params.command),
+        ..params.clone()
+    }
+}
+
 fn parse_container_exec_arguments(
     arguments: String,
     sess: &Session,
@@ -1278,8 +3601,24 @@ async fn handle_container_exec_with_params(
     sub_id: String,
     call_id: String,
 ) -> ResponseInputItem {
+    if sess.dry_run() {
+        return dry_run_exec(params, sess, sub_id, call_id).await;
+    }
+
     // check if this was a patch, and apply it if so
     match maybe_parse_apply_patch_verified(&params.command, &params.cwd) {
+        MaybeApplyPatchVerified::Body(_) if sess.mode() == SessionMode::Ask => {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: "editing files is not allowed in ask mode".to_string(),
+                    success: Some(false),
+                },
+            };
+        }
+        MaybeApplyPatchVerified::Body(changes) if sess.config.apply_mode == ApplyMode::Propose => {
+            return propose_patch(sess, sub_id, call_id, &changes).await;
+        }
         MaybeApplyPatchVerified::Body(changes) => {
             return apply_patch(sess, sub_id, call_id, changes).await;
         }
@@ -1302,24 +3641,49 @@ async fn handle_container_exec_with_params(
     }
 
     // safety checks
-    let safety = {
-        let state = sess.state.lock().unwrap();
-        assess_command_safety(
-            &params.command,
-            sess.approval_policy,
-            &sess.sandbox_policy,
-            &state.approved_commands,
-        )
+    let (safety, approved_for_session) = match sess.mode() {
+        SessionMode::Code => {
+            let sandbox_policy = sess.sandbox_policy();
+            let state = sess.state.lock().unwrap();
+            let safety = assess_command_safety(
+                &params.command,
+                sess.approval_policy(),
+                &sandbox_policy,
+                &state.approved_commands,
+            );
+            (safety, state.approved_commands.contains(&params.command))
+        }
+        SessionMode::Ask => (assess_command_safety_read_only(&params.command), false),
+    };
+    // Once untrusted content looked like it tried to instruct the agent,
+    // commands other than plain reads need the user's confirmation.
+    let untrusted_instructions = if is_known_safe_command(&params.command) {
+        None
+    } else {
+        sess.untrusted_instructions()
     };
-    let sandbox_type = match safety {
-        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+    let (sandbox_type, approval) = match safety {
+        SafetyCheck::AutoApprove { sandbox_type } if untrusted_instructions.is_some() => {
+            let reason = untrusted_instructions.unwrap_or_default();
+            if let Err(output) =
+                confirm_untrusted_action(sess, &sub_id, &call_id, params.command.clone(), reason)
+                    .await
+            {
+                return output;
+            }
+            (sandbox_type, AuditApproval::User)
+        }
+        SafetyCheck::AutoApprove { sandbox_type } if approved_for_session => {
+            (sandbox_type, AuditApproval::Session)
+        }
+        SafetyCheck::AutoApprove { sandbox_type } => (sandbox_type, AuditApproval::Auto),
         SafetyCheck::AskUser => {
             let rx_approve = sess
                 .request_command_approval(
                     sub_id.clone(),
                     params.command.clone(),
                     params.cwd.clone(),
-                    None,
+                    untrusted_instructions,
                 )
                 .await;
             match rx_approve.await.unwrap_or_default() {
@@ -1341,7 +3705,7 @@ async fn handle_container_exec_with_params(
             // explicit approval. Often, we end up in this case because
             // the command cannot be run in a sandbox, such as
             // installing a new dependency that requires network access.
-            SandboxType::None
+            (SandboxType::None, AuditApproval::User)
         }
         SafetyCheck::Reject { reason } => {
             return ResponseInputItem::FunctionCallOutput {
@@ -1353,18 +3717,91 @@ async fn handle_container_exec_with_params(
             };
         }
     };
+    // Whatever was approved, the sandbox stays on when the managed config
+    // forbids the access an unsandboxed command has.
+    let sandbox_type = match (sandbox_type, get_platform_sandbox()) {
+        (SandboxType::None, platform_sandbox) if !sess.config.allows_unsandboxed_commands() => {
+            let Some(platform_sandbox) = platform_sandbox else {
+                return ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: crate::models::FunctionCallOutputPayload {
+                        content: "exec command rejected: the managed config forbids running \
+                                  commands outside the sandbox, and there is no sandbox on \
+                                  this platform"
+                            .to_string(),
+                        success: None,
+                    },
+                };
+            };
+            platform_sandbox
+        }
+        (sandbox_type, _) => sandbox_type,
+    };
+
+    // A read of a file the model already read, and which has not changed
+    // since, is answered with a pointer to the earlier output.
+    let file_read = FileRead::parse(&params.command, &params.cwd)
+        .and_then(|read| read.stamp().map(|stamp| (read, stamp)));
+    if let Some((read, stamp)) = &file_read {
+        let earlier = sess
+            .state
+            .lock()
+            .unwrap()
+            .read_cache
+            .unchanged_since(read, *stamp)
+            .map(str::to_string);
+        if let Some(earlier) = earlier {
+            let content = format!(
+                "unchanged since last read: {} has not been modified since the output of call {earlier}",
+                read.path().display()
+            );
+            sess.notify_exec_command_begin(&sub_id, &call_id, &params)
+                .await;
+            sess.notify_exec_command_end(&sub_id, &call_id, &content, "", 0)
+                .await;
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(true),
+                },
+            };
+        }
+    }
 
     sess.notify_exec_command_begin(&sub_id, &call_id, &params)
         .await;
 
     let output_result = process_exec_tool_call(
-        params.clone(),
+        for_shell(&params, sess.config.shell),
         sandbox_type,
         sess.ctrl_c.clone(),
-        &sess.sandbox_policy,
+        &sess.sandbox_policy(),
         &sess.codex_linux_sandbox_exe,
+        &sess.config.linux_sandbox,
+        &sess.config.macos_seatbelt,
+    )
+    .await;
+
+    let exit_code = match &output_result {
+        Ok(output) => Some(output.exit_code),
+        Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, _, _))) => Some(*exit_code),
+        Err(_) => None,
+    };
+    sess.record_audit(
+        Some(&call_id),
+        approval,
+        AuditAction::Exec {
+            command: params.command.clone(),
+            cwd: params.cwd.clone(),
+            sandboxed: sandbox_type != SandboxType::None,
+            exit_code,
+        },
     )
     .await;
+    if let Err(CodexErr::Sandbox(error)) = &output_result {
+        log_sandbox_violation(sess, &call_id, approval, &params, sandbox_type, error).await;
+    }
 
     match output_result {
         Ok(output) => {
@@ -1377,13 +3814,24 @@ async fn handle_container_exec_with_params(
 
             sess.notify_exec_command_end(&sub_id, &call_id, &stdout, &stderr, exit_code)
                 .await;
+            sess.follow_cd(&params);
 
             let is_success = exit_code == 0;
             let content = format_exec_output(
-                if is_success { &stdout } else { &stderr },
+                &sess
+                    .config
+                    .shell
+                    .normalize_output(if is_success { &stdout } else { &stderr }),
                 exit_code,
                 duration,
             );
+            if let Some((read, stamp)) = file_read.filter(|_| is_success) {
+                sess.state
+                    .lock()
+                    .unwrap()
+                    .read_cache
+                    .record(read, stamp, call_id.clone(), &stdout);
+            }
 
             ResponseInputItem::FunctionCallOutput {
                 call_id,
@@ -1409,6 +3857,48 @@ async fn handle_container_exec_with_params(
     }
 }
 
+/// Report a command instead of running it, patches included, and tell the
+/// model it was not executed.
+async fn dry_run_exec(
+    params: ExecParams,
+    sess: &Session,
+    sub_id: String,
+    call_id: String,
+) -> ResponseInputItem {
+    info!(
+        "dry-run, not executing: {:?} in {}",
+        params.command,
+        params.cwd.display()
+    );
+    sess.send_event(Event {
+        id: sub_id,
+        msg: EventMsg::DryRunExec(DryRunExecEvent {
+            call_id: call_id.clone(),
+            command: params.command.clone(),
+            cwd: params.cwd.clone(),
+        }),
+    })
+    .await;
+    sess.record_audit(
+        Some(&call_id),
+        AuditApproval::DryRun,
+        AuditAction::Exec {
+            command: params.command,
+            cwd: params.cwd,
+            sandboxed: false,
+            exit_code: None,
+        },
+    )
+    .await;
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content: "dry-run: not executed".to_string(),
+            success: None,
+        },
+    }
+}
+
 async fn handle_sanbox_error(
     error: SandboxErr,
     sandbox_type: SandboxType,
@@ -1418,7 +3908,7 @@ async fn handle_sanbox_error(
     call_id: String,
 ) -> ResponseInputItem {
     // Early out if the user never wants to be asked for approval; just return to the model immediately
-    if sess.approval_policy == AskForApproval::Never {
+    if sess.approval_policy() == AskForApproval::Never {
         return ResponseInputItem::FunctionCallOutput {
             call_id,
             output: FunctionCallOutputPayload {
@@ -1431,10 +3921,63 @@ async fn handle_sanbox_error(
         };
     }
 
-    // Ask the user to retry without sandbox
     sess.notify_background_event(&sub_id, format!("Execution failed: {error}"))
         .await;
 
+    // When the failure names the path or host the sandbox denied, offer to
+    // grant just that and retry in the sandbox.
+    if let Some(access) = escalation_for(&error, &params, sess) {
+        let rx_approve = sess
+            .request_sandbox_escalation(
+                sub_id.clone(),
+                call_id.clone(),
+                params.command.clone(),
+                params.cwd.clone(),
+                access.clone(),
+            )
+            .await;
+        return match rx_approve.await.unwrap_or_default() {
+            decision @ (ReviewDecision::Approved | ReviewDecision::ApprovedForSession) => {
+                let scope = if decision == ReviewDecision::ApprovedForSession {
+                    sess.remember_sandbox_access(&access)
+                } else {
+                    "this session"
+                };
+                sess.notify_background_event(
+                    &sub_id,
+                    format!("granted {access} for {scope}; retrying command in the sandbox"),
+                )
+                .await;
+                sess.grant_sandbox_access(access);
+                retry_exec(params, sandbox_type, sess, &sub_id, call_id).await
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: FunctionCallOutputPayload {
+                        content: format!(
+                            "the sandbox denied {access}, and the user did not grant it"
+                        ),
+                        success: None,
+                    },
+                }
+            }
+        };
+    }
+
+    if !sess.config.allows_unsandboxed_commands() {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: format!(
+                    "failed in sandbox {sandbox_type:?} with execution error: {error}; the managed config forbids retrying without the sandbox"
+                ),
+                success: Some(false),
+            },
+        };
+    }
+
+    // Otherwise ask the user to retry without sandbox
     let rx_approve = sess
         .request_command_approval(
             sub_id.clone(),
@@ -1455,73 +3998,172 @@ async fn handle_sanbox_error(
             sess.notify_background_event(&sub_id, "retrying command without sandbox")
                 .await;
 
-            // Emit a fresh Begin event so progress bars reset.
-            let retry_call_id = format!("{call_id}-retry");
-            sess.notify_exec_command_begin(&sub_id, &retry_call_id, &params)
-                .await;
-
             // This is an escalated retry; the policy will not be
             // examined and the sandbox has been set to `None`.
-            let retry_output_result = process_exec_tool_call(
-                params,
-                SandboxType::None,
-                sess.ctrl_c.clone(),
-                &sess.sandbox_policy,
-                &sess.codex_linux_sandbox_exe,
-            )
-            .await;
+            retry_exec(params, SandboxType::None, sess, &sub_id, call_id).await
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            // Fall through to original failure handling.
+            ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: "exec command rejected by user".to_string(),
+                    success: None,
+                },
+            }
+        }
+    }
+}
 
-            match retry_output_result {
-                Ok(retry_output) => {
-                    let ExecToolCallOutput {
-                        exit_code,
-                        stdout,
-                        stderr,
-                        duration,
-                    } = retry_output;
+/// The access to ask the user for after `error`: the path or host the
+/// command's output says it was denied, unless the sandbox already allows
+/// it (and so did not cause the failure), or offline mode or the managed
+/// config forbids it.
+fn escalation_for(
+    error: &SandboxErr,
+    params: &ExecParams,
+    sess: &Session,
+) -> Option<SandboxAccess> {
+    let SandboxErr::Denied(_, _, stderr) = error else {
+        return None;
+    };
+    let access = denied_access(stderr, &params.cwd)?;
+    let policy = sess.sandbox_policy();
+    let already_allowed = match &access {
+        SandboxAccess::Write { path } => {
+            policy.has_full_disk_write_access()
+                || policy
+                    .get_writable_roots_with_cwd(&sess.cwd)
+                    .iter()
+                    .any(|root| path.starts_with(root))
+        }
+        SandboxAccess::Network { .. } => policy.has_full_network_access() || sess.config.offline,
+    };
+    let forbidden = sess.config.forbids(&access.permission());
+    (!already_allowed && !forbidden).then_some(access)
+}
 
-                    sess.notify_exec_command_end(
-                        &sub_id,
-                        &retry_call_id,
-                        &stdout,
-                        &stderr,
-                        exit_code,
-                    )
-                    .await;
+/// With `[linux_sandbox] log_violations`, record in the audit log that the
+/// Linux sandbox denied a command something, and what when its output says.
+async fn log_sandbox_violation(
+    sess: &Session,
+    call_id: &str,
+    approval: AuditApproval,
+    params: &ExecParams,
+    sandbox_type: SandboxType,
+    error: &SandboxErr,
+) {
+    if !sess.config.linux_sandbox.log_violations || sandbox_type != SandboxType::LinuxSeccomp {
+        return;
+    }
+    let SandboxErr::Denied(_, _, stderr) = error else {
+        return;
+    };
+    let mut tail: Vec<&str> = stderr.lines().rev().take(VIOLATION_STDERR_LINES).collect();
+    tail.reverse();
+    sess.record_audit(
+        Some(call_id),
+        approval,
+        AuditAction::SandboxViolation {
+            command: params.command.clone(),
+            cwd: params.cwd.clone(),
+            access: denied_access(stderr, &params.cwd),
+            stderr: tail.join("\n"),
+        },
+    )
+    .await;
+}
 
-                    let is_success = exit_code == 0;
-                    let content = format_exec_output(
-                        if is_success { &stdout } else { &stderr },
-                        exit_code,
-                        duration,
-                    );
+/// Run a command again after the user approved it, reporting it as a new
+/// call so that progress in the UI starts over.
+async fn retry_exec(
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sess: &Session,
+    sub_id: &str,
+    call_id: String,
+) -> ResponseInputItem {
+    // Emit a fresh Begin event so progress bars reset.
+    let retry_call_id = format!("{call_id}-retry");
+    sess.notify_exec_command_begin(sub_id, &retry_call_id, &params)
+        .await;
 
-                    ResponseInputItem::FunctionCallOutput {
-                        call_id,
-                        output: FunctionCallOutputPayload {
-                            content,
-                            success: Some(is_success),
-                        },
-                    }
-                }
-                Err(e) => {
-                    // Handle retry failure
-                    ResponseInputItem::FunctionCallOutput {
-                        call_id,
-                        output: FunctionCallOutputPayload {
-                            content: format!("retry failed: {e}"),
-                            success: None,
-                        },
-                    }
-                }
+    let retry_output_result = process_exec_tool_call(
+        for_shell(&params, sess.config.shell),
+        sandbox_type,
+        sess.ctrl_c.clone(),
+        &sess.sandbox_policy(),
+        &sess.codex_linux_sandbox_exe,
+        &sess.config.linux_sandbox,
+        &sess.config.macos_seatbelt,
+    )
+    .await;
+    if retry_output_result.is_ok() {
+        sess.follow_cd(&params);
+    }
+    if let Err(CodexErr::Sandbox(error)) = &retry_output_result {
+        log_sandbox_violation(
+            sess,
+            &retry_call_id,
+            AuditApproval::User,
+            &params,
+            sandbox_type,
+            error,
+        )
+        .await;
+    }
+    sess.record_audit(
+        Some(&retry_call_id),
+        AuditApproval::User,
+        AuditAction::Exec {
+            command: params.command,
+            cwd: params.cwd,
+            sandboxed: sandbox_type != SandboxType::None,
+            exit_code: match &retry_output_result {
+                Ok(output) => Some(output.exit_code),
+                Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, _, _))) => Some(*exit_code),
+                Err(_) => None,
+            },
+        },
+    )
+    .await;
+
+    match retry_output_result {
+        Ok(retry_output) => {
+            let ExecToolCallOutput {
+                exit_code,
+                stdout,
+                stderr,
+                duration,
+            } = retry_output;
+
+            sess.notify_exec_command_end(sub_id, &retry_call_id, &stdout, &stderr, exit_code)
+                .await;
+
+            let is_success = exit_code == 0;
+            let content = format_exec_output(
+                &sess
+                    .config
+                    .shell
+                    .normalize_output(if is_success { &stdout } else { &stderr }),
+                exit_code,
+                duration,
+            );
+
+            ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(is_success),
+                },
             }
         }
-        ReviewDecision::Denied | ReviewDecision::Abort => {
-            // Fall through to original failure handling.
+        Err(e) => {
+            // Handle retry failure
             ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
-                    content: "exec command rejected by user".to_string(),
+                    content: format!("retry failed: {e}"),
                     success: None,
                 },
             }
@@ -1529,6 +4171,157 @@ async fn handle_sanbox_error(
     }
 }
 
+/// With `review_edits = "per-turn"`, present the net changes of the task as
+/// one diff and restore the files the user rejects.
+async fn review_turn_edits(sess: &Session, sub_id: &str) {
+    let changes = {
+        let state = sess.state.lock().unwrap();
+        if state.staged_edits.is_empty() {
+            return;
+        }
+        state.staged_edits.changes()
+    };
+    let approved = if changes.is_empty() || sess.approval_policy() == AskForApproval::Never {
+        changes.into_keys().collect()
+    } else {
+        let rx = sess
+            .request_turn_diff_approval(sub_id.to_string(), changes)
+            .await;
+        // A dropped request leaves the edits staged for the next review.
+        let Ok(approved) = rx.await else {
+            return;
+        };
+        approved
+    };
+
+    let staged = std::mem::take(&mut sess.state.lock().unwrap().staged_edits);
+    match staged.revert_rejected(&approved) {
+        Ok(reverted) if reverted.is_empty() => {}
+        Ok(reverted) => {
+            sess.record_audit(
+                None,
+                AuditApproval::User,
+                AuditAction::Revert {
+                    files: reverted.clone(),
+                },
+            )
+            .await;
+            let note = rejection_note(&sess.cwd, &reverted);
+            sess.notify_background_event(
+                sub_id,
+                format!("reverted {} rejected file(s)", reverted.len()),
+            )
+            .await;
+            sess.state.lock().unwrap().review_note = Some(ResponseItem::Message {
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText { text: note }],
+            });
+        }
+        Err(e) => {
+            sess.notify_background_event(sub_id, format!("failed to revert rejected edits: {e}"))
+                .await;
+        }
+    }
+}
+
+/// Ask the user once whether the session may switch to write mode.
+async fn switch_to_write_mode(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    action: &ApplyPatchAction,
+) -> Result<(), ResponseInputItem> {
+    let rejected = |content: &str| ResponseInputItem::FunctionCallOutput {
+        call_id: call_id.to_string(),
+        output: FunctionCallOutputPayload {
+            content: content.to_string(),
+            success: Some(false),
+        },
+    };
+    if sess.approval_policy() == AskForApproval::Never {
+        return Err(rejected(
+            "patch rejected: the session is read-only and switching to write mode needs the \
+             user's approval",
+        ));
+    }
+    let reason = "Codex wants to start editing files. Switch to write mode for the rest of the \
+                  session? Later edits inside the workspace will not ask again."
+        .to_string();
+    let rx = sess
+        .request_patch_approval(sub_id.to_string(), action, Some(reason), None)
+        .await;
+    match rx.await.unwrap_or_default() {
+        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+            sess.state.lock().unwrap().write_mode = true;
+            sess.notify_background_event(sub_id, "switched to write mode for this session")
+                .await;
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => Err(rejected(
+            "patch rejected: the user kept the session read-only",
+        )),
+    }
+}
+
+/// With `apply_mode = "propose"`: hand the patch to the front-end instead of
+/// writing it. The editor applies it outside any sandbox, so it gets the
+/// checks of a patch Codex writes itself: a patch that writes outside the
+/// writable roots is only proposed once the user confirms it.
+async fn propose_patch(
+    sess: &Session,
+    sub_id: String,
+    call_id: String,
+    action: &ApplyPatchAction,
+) -> ResponseInputItem {
+    let rejected = |content: String| ResponseInputItem::FunctionCallOutput {
+        call_id: call_id.clone(),
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    };
+    let writable_roots = sess.writable_roots.lock().unwrap().clone();
+    let safety = assess_patch_safety(
+        action,
+        sess.patch_approval_policy(),
+        &writable_roots,
+        &sess.cwd,
+    );
+    if let SafetyCheck::Reject { reason } = safety {
+        return rejected(format!("patch rejected: {reason}"));
+    }
+    if !is_write_patch_constrained_to_writable_paths(action, &writable_roots, &sess.cwd) {
+        let reason = "the patch writes outside the writable roots".to_string();
+        let rx = sess
+            .request_patch_approval(sub_id.clone(), action, Some(reason), None)
+            .await;
+        if !matches!(
+            rx.await.unwrap_or_default(),
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession
+        ) {
+            return rejected("patch rejected by user".to_string());
+        }
+    }
+
+    let _ = sess
+        .tx_event
+        .send(Event {
+            id: sub_id,
+            msg: EventMsg::PatchProposed(PatchProposedEvent {
+                call_id: call_id.clone(),
+                changes: convert_apply_patch_to_protocol(action),
+            }),
+        })
+        .await;
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content: "Patch proposed: the user's editor applies it, so the files on disk may not show it yet. Do not apply it again.".to_string(),
+            success: Some(true),
+        },
+    }
+}
+
 async fn apply_patch(
     sess: &Session,
     sub_id: String,
@@ -1540,21 +4333,64 @@ async fn apply_patch(
         guard.clone()
     };
 
-    let auto_approved = match assess_patch_safety(
-        &action,
-        sess.approval_policy,
-        &writable_roots_snapshot,
-        &sess.cwd,
-    ) {
-        SafetyCheck::AutoApprove { .. } => true,
-        SafetyCheck::AskUser => {
+    let mut approval = if sess.write_mode_policy.is_some() {
+        AuditApproval::WriteMode
+    } else {
+        AuditApproval::Auto
+    };
+    // Once untrusted content looked like it tried to instruct the agent, the
+    // user confirms each patch before it is applied.
+    let (safety, untrusted_instructions) = if sess.awaiting_write_mode() {
+        // The first edit climbs the escalation ladder: the user's approval
+        // covers this patch and the rest of the session.
+        if let Err(output) = switch_to_write_mode(sess, &sub_id, &call_id, &action).await {
+            return output;
+        }
+        let safety = SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+        };
+        (safety, None)
+    } else {
+        let safety = assess_patch_safety(
+            &action,
+            sess.patch_approval_policy(),
+            &writable_roots_snapshot,
+            &sess.cwd,
+        );
+        (safety, sess.untrusted_instructions())
+    };
+    let review_per_turn = sess.config.review_edits == ReviewEdits::PerTurn;
+    let auto_approved = match safety {
+        SafetyCheck::AutoApprove { .. } if untrusted_instructions.is_none() => true,
+        // The edit is reviewed with the rest of the turn's changes.
+        SafetyCheck::AskUser if review_per_turn && untrusted_instructions.is_none() => {
+            approval = AuditApproval::TurnReview;
+            true
+        }
+        SafetyCheck::AutoApprove { .. } | SafetyCheck::AskUser => {
+            if let (Some(reason), AskForApproval::Never) =
+                (&untrusted_instructions, sess.approval_policy())
+            {
+                return ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: FunctionCallOutputPayload {
+                        content: format!(
+                            "patch not applied without the user's confirmation: {reason}"
+                        ),
+                        success: Some(false),
+                    },
+                };
+            }
             // Compute a readable summary of path changes to include in the
             // approval request so the user can make an informed decision.
             let rx_approve = sess
-                .request_patch_approval(sub_id.clone(), &action, None, None)
+                .request_patch_approval(sub_id.clone(), &action, untrusted_instructions, None)
                 .await;
             match rx_approve.await.unwrap_or_default() {
-                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => false,
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+                    approval = AuditApproval::User;
+                    false
+                }
                 ReviewDecision::Denied | ReviewDecision::Abort => {
                     return ResponseInputItem::FunctionCallOutput {
                         call_id,
@@ -1607,6 +4443,15 @@ async fn apply_patch(
 
         // user approved, extend writable roots for this session
         sess.writable_roots.lock().unwrap().push(root);
+        approval = AuditApproval::User;
+    }
+
+    {
+        let mut state = sess.state.lock().unwrap();
+        if review_per_turn {
+            state.staged_edits.stage(&action);
+        }
+        state.checkpoints.stage(&action);
     }
 
     let _ = sess
@@ -1684,6 +4529,7 @@ async fn apply_patch(
                 ) {
                     // Extend writable roots.
                     sess.writable_roots.lock().unwrap().push(root);
+                    approval = AuditApproval::User;
                     stdout.clear();
                     stderr.clear();
                     result = apply_changes_from_apply_patch_and_report(
@@ -1698,6 +4544,15 @@ async fn apply_patch(
 
     // Emit PatchApplyEnd event.
     let success_flag = result.is_ok();
+    sess.record_audit(
+        Some(&call_id),
+        approval,
+        AuditAction::Patch {
+            files: action_paths(&action),
+            success: success_flag,
+        },
+    )
+    .await;
     let _ = sess
         .tx_event
         .send(Event {
@@ -1,18 +1,43 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::ApplyMode;
+use crate::config_types::Attachments;
+use crate::config_types::GitContext;
 use crate::config_types::History;
+use crate::config_types::Hooks;
+use crate::config_types::LinuxSandbox;
+use crate::config_types::MacosSeatbelt;
 use crate::config_types::McpServerConfig;
+use crate::config_types::Memory;
+use crate::config_types::Notifications;
+use crate::config_types::OtelConfig;
+use crate::config_types::ProjectChecks;
 use crate::config_types::ReasoningEffort;
 use crate::config_types::ReasoningSummary;
+use crate::config_types::Retention;
+use crate::config_types::ReviewEdits;
+use crate::config_types::Router;
+use crate::config_types::SamplingParams;
+use crate::config_types::SemanticSearch;
+use crate::config_types::SessionStoreKind;
+use crate::config_types::ShellEnvironment;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
+use crate::config_types::UntrustedContent;
 use crate::config_types::UriBasedFileOpener;
 use crate::flags::OPENAI_DEFAULT_MODEL;
+use crate::http_client::is_local_url;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
+use crate::policy;
+use crate::policy::POLICY_FILE;
+use crate::policy::Policy;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPermission;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SessionMode;
+use crate::seatbelt_policy::validate_snippet;
+use crate::shell::Shell;
 use dirs::home_dir;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -25,6 +50,16 @@ use toml::Value as TomlValue;
 /// the context window.
 pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
+/// Default for the number of sub-agents a single `spawn_agents` call may start.
+pub(crate) const DEFAULT_MAX_SUB_AGENTS: usize = 4;
+
+/// Default for the number of read-only tool calls from one response that run
+/// at once.
+pub(crate) const DEFAULT_MAX_PARALLEL_TOOL_CALLS: usize = 4;
+
+/// Default size of the repository map included in the instructions.
+pub(crate) const DEFAULT_REPO_MAP_MAX_TOKENS: usize = 2048;
+
 /// Application configuration loaded from disk and merged with overrides.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -42,8 +77,26 @@ pub struct Config {
 
     pub sandbox_policy: SandboxPolicy,
 
+    /// What the agent may do in the session. `Ask` keeps it read-only
+    /// whatever the approval and sandbox policies say.
+    pub mode: SessionMode,
+
+    /// Show and log the shell commands the agent proposes without running
+    /// them; the model is told they were not executed.
+    pub dry_run: bool,
+
+    /// Subdirectory of `cwd` the session concentrates on (`--focus`): the
+    /// file index and the repository map only cover it and, on Linux,
+    /// sandboxed commands can only read it, the system directories and
+    /// `[linux_sandbox] read_only_paths`.
+    pub focus: Option<PathBuf>,
+
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// Shell the model's commands run in; on Windows, their common Unix
+    /// commands are translated for it.
+    pub shell: Shell,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -59,7 +112,8 @@ pub struct Config {
 
     /// Optional external notifier command. When set, Codex will spawn this
     /// program after each completed *turn* (i.e. when the agent finishes
-    /// processing a user submission). The value must be the full command
+    /// processing a user submission), when it needs approval, and when a turn
+    /// fails with an error. The value must be the full command
     /// broken into argv tokens **without** the trailing JSON argument - Codex
     /// appends one extra argument containing a JSON payload describing the
     /// event.
@@ -93,6 +147,117 @@ pub struct Config {
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
+    /// Approximate number of tokens the repository map in the instructions
+    /// may take up. `0` leaves the map out.
+    pub repo_map_max_tokens: usize,
+
+    /// Maximum number of sub-agents the model may run at once through the
+    /// `spawn_agents` tool. `0` does not offer the tool at all.
+    pub max_sub_agents: usize,
+
+    /// Maximum number of tool calls from a single model response that run at
+    /// once. Only calls that just read (known-safe commands and semantic
+    /// searches) run concurrently; `1` runs every call in turn.
+    pub max_parallel_tool_calls: usize,
+
+    /// Sandbox policy the session switches to, after a single approval, when
+    /// the model proposes its first edit. Until then commands run under
+    /// `sandbox_policy`. `None` when the escalation ladder is disabled.
+    pub escalation: Option<SandboxPolicy>,
+
+    /// Whether file edits are approved one patch at a time or reviewed as one
+    /// diff when the task ends.
+    pub review_edits: ReviewEdits,
+
+    /// Whether the agent's patches are written or only proposed to the
+    /// front-end.
+    pub apply_mode: ApplyMode,
+
+    /// Scripts run before and after tool calls.
+    pub hooks: Hooks,
+
+    /// Rules checked before every tool call, read from `policy_file`.
+    pub policy: Policy,
+
+    /// Tools and commands whose output could try to instruct the agent.
+    pub untrusted_content: UntrustedContent,
+
+    /// Commands the model is asked to run to verify its changes.
+    pub checks: ProjectChecks,
+
+    /// Whether `checks.test` was set by the project config. A checked-out
+    /// repository chose it, so Codex does not run it on its own without the
+    /// user's confirmation. Never read from the config file.
+    pub test_command_from_project: bool,
+
+    /// Which model serves which requests.
+    pub router: Router,
+
+    /// Embedding-based search of the workspace offered to the model.
+    pub semantic_search: SemanticSearch,
+
+    /// Size limits on the files mentioned in a message.
+    pub attachments: Attachments,
+
+    /// Recent Git changes sent ahead of the first prompt of a session.
+    pub git_context: GitContext,
+
+    /// Facts about the project remembered across sessions.
+    pub memory: Memory,
+
+    /// Replace likely secrets in tool output and mentioned files before they
+    /// are sent to the model.
+    pub redact_secrets: bool,
+
+    /// Customization of the Linux sandbox.
+    pub linux_sandbox: LinuxSandbox,
+
+    /// Rules added to the macOS sandbox profile.
+    pub macos_seatbelt: MacosSeatbelt,
+
+    /// Where to export OpenTelemetry spans, if anywhere.
+    pub otel: Option<OtelConfig>,
+
+    /// Record executed commands and file writes in `~/.codex/audit/`.
+    pub audit_log: bool,
+
+    /// Write new session rollouts zstd-compressed.
+    pub compress_sessions: bool,
+
+    /// Encrypt new session rollouts and audit logs with the key in the OS
+    /// keychain.
+    pub encrypt_sessions: bool,
+
+    /// Backend that `codex sessions` queries.
+    pub session_store: SessionStoreKind,
+
+    /// How long session rollouts and audit logs are kept.
+    pub retention: Retention,
+
+    /// Proxy for plain HTTP requests.
+    pub http_proxy: Option<String>,
+
+    /// Proxy for HTTPS requests.
+    pub https_proxy: Option<String>,
+
+    /// PEM bundles trusted in addition to the system's root certificates.
+    pub extra_ca_certs: Vec<PathBuf>,
+
+    /// Forbid network access: only local providers can be used and commands
+    /// never get network access.
+    pub offline: bool,
+
+    /// Sandbox permissions the managed config forbids: removed from the
+    /// sandbox policy and never granted during a session.
+    pub forbidden_sandbox_permissions: Vec<SandboxPermission>,
+
+    /// Top-level keys the managed config locks, which sessions refuse to
+    /// change too.
+    pub locked: Vec<String>,
+
+    /// HTTP endpoint headless runs POST every event to, as JSON.
+    pub event_sink: Option<String>,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -107,6 +272,9 @@ pub struct Config {
     /// Collection of settings that are specific to the TUI.
     pub tui: Tui,
 
+    /// Desktop notification settings.
+    pub notifications: Notifications,
+
     /// Path to the `codex-linux-sandbox` executable. This must be set if
     /// [`crate::exec::SandboxType::LinuxSeccomp`] is used. Note that this
     /// cannot be set in the config file: it must be set in code via
@@ -115,13 +283,27 @@ pub struct Config {
     /// When this program is invoked, arg0 will be set to `codex-linux-sandbox`.
     pub codex_linux_sandbox_exe: Option<PathBuf>,
 
+    /// Rollout of an earlier session whose conversation this session
+    /// continues. Like `codex_linux_sandbox_exe`, this is never read from the
+    /// config file; `codex resume` sets it.
+    pub resume_from: Option<PathBuf>,
+
+    /// Whether the session writes a rollout, which lists it for
+    /// `codex resume`. Never read from the config file; sub-agents turn it
+    /// off.
+    pub record_rollout: bool,
+
     /// If not "none", the value to use for `reasoning.effort` when making a
-    /// request using the Responses API.
+    /// request using the Responses API, or `reasoning_effort` with Chat
+    /// Completions, for models that support reasoning.
     pub model_reasoning_effort: ReasoningEffort,
 
     /// If not "none", the value to use for `reasoning.summary` when making a
     /// request using the Responses API.
     pub model_reasoning_summary: ReasoningSummary,
+
+    /// Temperature, top_p and seed for model requests.
+    pub sampling: SamplingParams,
 }
 
 impl Config {
@@ -129,8 +311,11 @@ impl Config {
     /// **in between** the values parsed from `config.toml` and the
     /// strongly-typed overrides specified via [`ConfigOverrides`].
     ///
-    /// The precedence order is therefore: `config.toml` < `-c` overrides <
-    /// `ConfigOverrides`.
+    /// A project's `.codex/config.toml` sits between `config.toml` and the
+    /// `-c` overrides, and the managed config above it, so the precedence
+    /// order is: `config.toml` < project config < managed config < `-c`
+    /// overrides < `ConfigOverrides`. Keys the managed config locks win over
+    /// all of them.
     pub fn load_with_cli_overrides(
         cli_overrides: Vec<(String, TomlValue)>,
         overrides: ConfigOverrides,
@@ -140,13 +325,32 @@ impl Config {
         // `Config` instance.
         let codex_home = find_codex_home()?;
 
-        // Step 1: parse `config.toml` into a generic JSON value.
+        // Step 1: parse `config.toml` into a generic JSON value, and lay the
+        // project's config over it.
         let mut root_value = load_config_as_toml(&codex_home)?;
+        let cwd = match &overrides.cwd {
+            Some(cwd) => std::env::current_dir()?.join(cwd),
+            None => std::env::current_dir()?,
+        };
+        let project_value = load_project_config_as_toml(&cwd, &codex_home, home_dir().as_deref())?;
+        let project_test_command = project_value
+            .as_ref()
+            .and_then(|value| value.get("checks")?.get("test")?.as_str())
+            .map(str::to_string);
+        if let Some(project_value) = project_value {
+            merge_toml(&mut root_value, project_value);
+        }
+        let managed_value = load_managed_config_as_toml()?;
+        if let Some(managed_value) = &managed_value {
+            merge_toml(&mut root_value, managed_value.clone());
+        }
 
-        // Step 2: apply the `-c` overrides.
+        // Step 2: apply the `-c` overrides, then the keys the managed config
+        // locks over them.
         for (path, value) in cli_overrides.into_iter() {
             apply_toml_override(&mut root_value, &path, value);
         }
+        lock_managed_keys(&mut root_value, managed_value.as_ref());
 
         // Step 3: deserialize into `ConfigToml` so that Serde can enforce the
         // correct types.
@@ -156,10 +360,29 @@ impl Config {
         })?;
 
         // Step 4: merge with the strongly-typed overrides.
-        Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)
+        let mut config = Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)?;
+        config.test_command_from_project =
+            project_test_command.is_some() && config.checks.test == project_test_command;
+        Ok(config)
     }
 }
 
+/// Names of the profiles defined in `CODEX_HOME/config.toml`, sorted. Meant
+/// for shell completion, so a config that cannot be read has none.
+pub fn profile_names(codex_home: &Path) -> Vec<String> {
+    let mut names = match load_config_as_toml(codex_home) {
+        Ok(TomlValue::Table(mut table)) => match table.remove("profiles") {
+            Some(TomlValue::Table(profiles)) => {
+                profiles.into_iter().map(|(name, _)| name).collect()
+            }
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
 /// Read `CODEX_HOME/config.toml` and return it as a generic TOML value. Returns
 /// an empty TOML table when the file does not exist.
 fn load_config_as_toml(codex_home: &Path) -> std::io::Result<TomlValue> {
@@ -183,6 +406,211 @@ fn load_config_as_toml(codex_home: &Path) -> std::io::Result<TomlValue> {
     }
 }
 
+/// Name of the project config file, relative to the project root.
+pub const PROJECT_CONFIG_FILE: &str = ".codex/config.toml";
+
+/// Keys a project config may set. A checked-out repository is not trusted,
+/// so it cannot choose where requests go or which programs Codex runs on its
+/// own (`model_providers`, `mcp_servers`, `hooks`, `notify`), nor loosen the
+/// approval policy or the sandbox.
+const PROJECT_CONFIG_KEYS: &[&str] = &[
+    "attachments",
+    "checks",
+    "git_context",
+    "model",
+    "model_reasoning_effort",
+    "model_reasoning_summary",
+    "router",
+    "temperature",
+    "top_p",
+    "seed",
+    "project_doc_max_bytes",
+    "repo_map_max_tokens",
+    "review_edits",
+];
+
+/// Read the `.codex/config.toml` of the project containing `cwd`: the first
+/// one found walking up from `cwd`, stopping at the Git root. Outside a Git
+/// repository the walk stops at `home`, and outside both only `cwd` is
+/// searched: anyone can leave a `.codex/config.toml` in `/tmp` or `/`. Keys
+/// outside [`PROJECT_CONFIG_KEYS`] are dropped with a warning.
+fn load_project_config_as_toml(
+    cwd: &Path,
+    codex_home: &Path,
+    home: Option<&Path>,
+) -> std::io::Result<Option<TomlValue>> {
+    let user_config = codex_home.join("config.toml").canonicalize().ok();
+    let last = cwd
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .or(home.filter(|home| cwd.starts_with(home)))
+        .unwrap_or(cwd);
+    for dir in cwd.ancestors() {
+        let path = dir.join(PROJECT_CONFIG_FILE);
+        // With `cwd` at or below `$HOME`, `~/.codex/config.toml` is found
+        // here too; it is loaded already.
+        if path.is_file() && path.canonicalize().ok() != user_config {
+            let contents = std::fs::read_to_string(&path)?;
+            let mut value = toml::from_str::<TomlValue>(&contents).map_err(|e| {
+                tracing::error!("Failed to parse {}: {e}", path.display());
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {e}", path.display()),
+                )
+            })?;
+            if let TomlValue::Table(table) = &mut value {
+                table.retain(|key, _| {
+                    let allowed = PROJECT_CONFIG_KEYS.contains(&key);
+                    if !allowed {
+                        tracing::warn!(
+                            "ignoring `{key}` in {}: only user config can set it",
+                            path.display()
+                        );
+                    }
+                    allowed
+                });
+            }
+            return Ok(Some(value));
+        }
+        if dir == last {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+/// Where administrators deploy the managed config: settings that override
+/// the user's, and in its `[managed]` table, the keys users cannot change
+/// and the sandbox permissions they cannot have.
+pub const MANAGED_CONFIG_PATH: &str = "/etc/codex/managed_config.toml";
+
+/// The macOS managed preferences (deployed with a configuration profile)
+/// that take precedence over [`MANAGED_CONFIG_PATH`], and the key under which
+/// they hold the managed config, as TOML.
+#[cfg(target_os = "macos")]
+const MANAGED_PREFERENCES_PATH: &str = "/Library/Managed Preferences/com.openai.codex.plist";
+#[cfg(target_os = "macos")]
+const MANAGED_PREFERENCES_KEY: &str = "managed_config_toml";
+
+/// Read the managed config, if the machine has one. Unlike `config.toml`, a
+/// managed config that cannot be read is an error: Codex does not run
+/// without the guardrails it sets.
+fn load_managed_config_as_toml() -> std::io::Result<Option<TomlValue>> {
+    #[cfg(target_os = "macos")]
+    if let Some(contents) = read_managed_preferences()? {
+        return parse_managed_config(&contents, MANAGED_PREFERENCES_PATH).map(Some);
+    }
+    match std::fs::read_to_string(MANAGED_CONFIG_PATH) {
+        Ok(contents) => parse_managed_config(&contents, MANAGED_CONFIG_PATH).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => {
+            tracing::error!("Failed to read {MANAGED_CONFIG_PATH}: {e}");
+            Err(e)
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn read_managed_preferences() -> std::io::Result<Option<String>> {
+    if !Path::new(MANAGED_PREFERENCES_PATH).exists() {
+        return Ok(None);
+    }
+    let preferences = plist::Value::from_file(MANAGED_PREFERENCES_PATH).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{MANAGED_PREFERENCES_PATH}: {e}"),
+        )
+    })?;
+    Ok(preferences
+        .as_dictionary()
+        .and_then(|preferences| preferences.get(MANAGED_PREFERENCES_KEY))
+        .and_then(|value| value.as_string())
+        .map(str::to_string))
+}
+
+fn parse_managed_config(contents: &str, source: &str) -> std::io::Result<TomlValue> {
+    toml::from_str::<TomlValue>(contents).map_err(|e| {
+        tracing::error!("Failed to parse {source}: {e}");
+        std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{source}: {e}"))
+    })
+}
+
+/// Give the keys `managed` locks its values back, whatever profiles and `-c`
+/// overrides set, and drop them from every profile. The `[managed]` table
+/// itself is only ever taken from the managed config.
+fn lock_managed_keys(root: &mut TomlValue, managed: Option<&TomlValue>) {
+    let TomlValue::Table(root) = root else {
+        return;
+    };
+    let managed = match managed {
+        Some(TomlValue::Table(managed)) => managed,
+        _ => {
+            root.remove("managed");
+            return;
+        }
+    };
+    let locked: Vec<&str> = managed
+        .get("managed")
+        .and_then(|table| table.get("locked"))
+        .and_then(TomlValue::as_array)
+        .map(|keys| keys.iter().filter_map(TomlValue::as_str).collect())
+        .unwrap_or_default();
+    for key in locked.into_iter().chain(["managed"]) {
+        match managed.get(key) {
+            Some(value) => root.insert(key.to_string(), value.clone()),
+            None => root.remove(key),
+        };
+        if let Some(TomlValue::Table(profiles)) = root.get_mut("profiles") {
+            for (_, profile) in profiles.iter_mut() {
+                if let TomlValue::Table(profile) = profile {
+                    profile.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// Whether `forbidden` covers `permission`. A forbidden `disk-write-folder`
+/// covers the folders below it too.
+fn is_forbidden(forbidden: &[SandboxPermission], permission: &SandboxPermission) -> bool {
+    forbidden
+        .iter()
+        .any(|forbidden| match (forbidden, permission) {
+            (
+                SandboxPermission::DiskWriteFolder { folder: root },
+                SandboxPermission::DiskWriteFolder { folder },
+            ) => folder.starts_with(root),
+            _ => forbidden == permission,
+        })
+}
+
+/// `value` unless the managed config locks `key`, which command-line flags
+/// cannot change either.
+fn unless_locked<T>(locked: &[String], key: &str, value: Option<T>) -> Option<T> {
+    if value.is_some() && locked.iter().any(|locked| locked == key) {
+        tracing::warn!("ignoring the override of `{key}`: the managed config locks it");
+        return None;
+    }
+    value
+}
+
+/// Merge `overlay` into `base`, recursing into tables present in both.
+fn merge_toml(base: &mut TomlValue, overlay: TomlValue) {
+    match (base, overlay) {
+        (TomlValue::Table(base), TomlValue::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
 /// Apply a single dotted-path override onto a TOML value.
 fn apply_toml_override(root: &mut TomlValue, path: &str, value: TomlValue) {
     use toml::value::Table;
@@ -238,9 +666,24 @@ pub struct ConfigToml {
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
+    /// Default session mode, `code` or `ask`.
+    pub mode: Option<SessionMode>,
+
+    /// Defaults to `false`.
+    pub dry_run: Option<bool>,
+
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// `login` to start the environment of the agent's commands from the
+    /// user's login shell instead of the one Codex was started with.
+    pub shell_environment: Option<ShellEnvironment>,
+
+    /// Shell to run commands in on Windows, `powershell`, `cmd` or
+    /// `git-bash`, when the one Codex was started from is not the one to
+    /// use. Ignored elsewhere.
+    pub windows_shell: Option<Shell>,
+
     // The `default` attribute ensures that the field is treated as `None` when
     // the key is omitted from the TOML. Without it, Serde treats the field as
     // required because we supply a custom deserializer.
@@ -270,6 +713,108 @@ pub struct ConfigToml {
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
+    /// Approximate token budget of the repository map; `0` disables it.
+    pub repo_map_max_tokens: Option<usize>,
+
+    /// Maximum number of sub-agents the model may run at once.
+    pub max_sub_agents: Option<usize>,
+
+    /// Maximum number of read-only tool calls that run at once.
+    pub max_parallel_tool_calls: Option<usize>,
+
+    /// Start read-only and switch to write mode once, on the first edit.
+    pub escalation: Option<EscalationToml>,
+
+    /// `per-patch` (default) or `per-turn`.
+    pub review_edits: Option<ReviewEdits>,
+
+    /// `write` (default) or `propose`.
+    pub apply_mode: Option<ApplyMode>,
+
+    /// `[hooks]` table of scripts run around tool calls.
+    #[serde(default)]
+    pub hooks: Hooks,
+
+    /// Policy file, relative to `CODEX_HOME`. Defaults to `policy.toml`.
+    pub policy_file: Option<PathBuf>,
+
+    /// `[untrusted_content]` table: tools and commands whose output is
+    /// marked as untrusted and checked for prompt injection.
+    #[serde(default)]
+    pub untrusted_content: UntrustedContent,
+
+    /// `[checks]` table of build, test, lint and format commands.
+    #[serde(default)]
+    pub checks: ProjectChecks,
+
+    /// `[router]` table: a cheaper model for summarization requests.
+    #[serde(default)]
+    pub router: Router,
+
+    /// `[semantic_search]` table: the embedding model behind the
+    /// `semantic_search` tool.
+    #[serde(default)]
+    pub semantic_search: SemanticSearch,
+
+    /// `[attachments]` table: size limits on the files mentioned in a
+    /// message.
+    #[serde(default)]
+    pub attachments: Attachments,
+
+    /// `[git_context]` table: recent Git changes sent with the first prompt.
+    #[serde(default)]
+    pub git_context: GitContext,
+
+    /// `[memory]` table: facts about the project remembered across
+    /// sessions.
+    #[serde(default)]
+    pub memory: Memory,
+
+    /// Defaults to `true`.
+    pub redact_secrets: Option<bool>,
+
+    /// `[linux_sandbox]` table: extra read-only paths, blocked system calls
+    /// and logging of sandbox violations.
+    #[serde(default)]
+    pub linux_sandbox: LinuxSandbox,
+
+    /// `[macos_seatbelt]` table: rules appended to the Seatbelt profile.
+    #[serde(default)]
+    pub macos_seatbelt: MacosSeatbelt,
+
+    /// Defaults to `true`.
+    pub audit_log: Option<bool>,
+
+    /// Defaults to `true`.
+    pub compress_sessions: Option<bool>,
+
+    /// Defaults to `false`.
+    pub encrypt_sessions: Option<bool>,
+
+    /// Defaults to `jsonl`.
+    pub session_store: Option<SessionStoreKind>,
+
+    /// `[retention]` table: how long session rollouts and audit logs are
+    /// kept.
+    #[serde(default)]
+    pub retention: Retention,
+
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+
+    /// Relative paths are resolved against `CODEX_HOME`.
+    #[serde(default)]
+    pub extra_ca_certs: Vec<PathBuf>,
+
+    /// Defaults to `false`.
+    pub offline: Option<bool>,
+
+    /// URL that headless runs POST every event to, as JSON.
+    pub event_sink: Option<String>,
+
+    /// `[otel]` table: export spans to an OTLP endpoint.
+    pub otel: Option<OtelConfig>,
+
     /// Profile to use from the `profiles` map.
     pub profile: Option<String>,
 
@@ -288,12 +833,63 @@ pub struct ConfigToml {
     /// Collection of settings that are specific to the TUI.
     pub tui: Option<Tui>,
 
+    /// Desktop notification settings.
+    pub notifications: Option<Notifications>,
+
     /// When set to `true`, `AgentReasoning` events will be hidden from the
     /// UI/output. Defaults to `false`.
     pub hide_agent_reasoning: Option<bool>,
 
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
+
+    /// Sampling temperature, between 0 and 2.
+    pub temperature: Option<f32>,
+
+    /// Nucleus sampling probability mass, between 0 and 1.
+    pub top_p: Option<f32>,
+
+    /// Seed for repeatable sampling. Only Chat Completions providers take it.
+    pub seed: Option<u64>,
+
+    /// `[managed]` table. Only read from the managed config, see
+    /// [`MANAGED_CONFIG_PATH`].
+    #[serde(default)]
+    pub managed: ManagedToml,
+}
+
+/// The `[managed]` table of the managed config.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ManagedToml {
+    /// Top-level keys users cannot change: the managed config's value (or
+    /// the default) wins over profiles, `-c` and command-line flags.
+    #[serde(default)]
+    pub locked: Vec<String>,
+
+    /// Permissions left out of the sandbox policy, whatever the user
+    /// configures, and never granted during a session.
+    #[serde(default, deserialize_with = "deserialize_sandbox_permissions")]
+    pub forbidden_sandbox_permissions: Option<Vec<SandboxPermission>>,
+}
+
+/// The `[escalation]` table.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct EscalationToml {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Permissions granted in write mode. Defaults to those of `--full-auto`.
+    #[serde(default, deserialize_with = "deserialize_sandbox_permissions")]
+    pub write_permissions: Option<Vec<SandboxPermission>>,
+}
+
+impl EscalationToml {
+    fn write_mode_policy(self) -> Option<SandboxPolicy> {
+        self.enabled.then(|| match self.write_permissions {
+            Some(permissions) => SandboxPolicy::from(permissions),
+            None => SandboxPolicy::new_full_auto_policy(),
+        })
+    }
 }
 
 fn deserialize_sandbox_permissions<'de, D>(
@@ -332,6 +928,14 @@ pub struct ConfigOverrides {
     pub model_provider: Option<String>,
     pub config_profile: Option<String>,
     pub codex_linux_sandbox_exe: Option<PathBuf>,
+    pub mode: Option<SessionMode>,
+    pub dry_run: Option<bool>,
+    pub focus: Option<PathBuf>,
+    pub offline: Option<bool>,
+    pub temperature: Option<f32>,
+    pub seed: Option<u64>,
+    pub event_sink: Option<String>,
+    pub apply_mode: Option<ApplyMode>,
 }
 
 impl Config {
@@ -353,8 +957,37 @@ impl Config {
             model_provider,
             config_profile: config_profile_key,
             codex_linux_sandbox_exe,
+            mode,
+            dry_run,
+            focus,
+            offline,
+            temperature,
+            seed,
+            event_sink,
+            apply_mode,
         } = overrides;
 
+        let ManagedToml {
+            locked,
+            forbidden_sandbox_permissions,
+        } = cfg.managed;
+        let model = unless_locked(&locked, "model", model);
+        let approval_policy = unless_locked(&locked, "approval_policy", approval_policy);
+        let sandbox_policy = unless_locked(&locked, "sandbox_permissions", sandbox_policy);
+        let model_provider = unless_locked(&locked, "model_provider", model_provider);
+        let config_profile_key = unless_locked(&locked, "profile", config_profile_key);
+        let mode = unless_locked(&locked, "mode", mode);
+        let dry_run = unless_locked(&locked, "dry_run", dry_run);
+        let offline = unless_locked(&locked, "offline", offline);
+        let temperature = unless_locked(&locked, "temperature", temperature);
+        let seed = unless_locked(&locked, "seed", seed);
+        let event_sink = unless_locked(&locked, "event_sink", event_sink);
+        let apply_mode = unless_locked(&locked, "apply_mode", apply_mode);
+        let forbidden_sandbox_permissions = forbidden_sandbox_permissions.unwrap_or_default();
+        let forbidden = |permission: &SandboxPermission| {
+            is_forbidden(&forbidden_sandbox_permissions, permission)
+        };
+
         let config_profile = match config_profile_key.or(cfg.profile) {
             Some(key) => cfg
                 .profiles
@@ -369,6 +1002,37 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
+        let sampling = SamplingParams {
+            temperature: temperature
+                .or(config_profile.temperature)
+                .or(cfg.temperature),
+            top_p: config_profile.top_p.or(cfg.top_p),
+            seed: seed.or(config_profile.seed).or(cfg.seed),
+        };
+        let out_of_range = [
+            ("temperature", sampling.temperature, 2.0),
+            ("top_p", sampling.top_p, 1.0),
+        ]
+        .into_iter()
+        .find(|(_, value, max)| value.is_some_and(|v| !(0.0..=*max).contains(&v)));
+        if let Some((name, Some(value), max)) = out_of_range {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("`{name}` must be between 0 and {max}, got {value}"),
+            ));
+        }
+
+        for (index, snippet) in cfg.macos_seatbelt.extra_policy.iter().enumerate() {
+            if let Err(reason) = validate_snippet(snippet) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "`macos_seatbelt.extra_policy[{index}]` is not a valid Seatbelt rule: {reason}"
+                    ),
+                ));
+            }
+        }
+
         let sandbox_policy = match sandbox_policy {
             Some(sandbox_policy) => sandbox_policy,
             None => {
@@ -383,6 +1047,13 @@ impl Config {
                 }
             }
         };
+        let offline = offline.or(cfg.offline).unwrap_or(false);
+        let sandbox_policy = if offline {
+            sandbox_policy.without_network_access()
+        } else {
+            sandbox_policy
+        };
+        let sandbox_policy = sandbox_policy.without_permissions(forbidden);
 
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
@@ -403,8 +1074,33 @@ impl Config {
                 )
             })?
             .clone();
+        if offline && !is_local_url(&model_provider.base_url) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "offline mode only allows local model providers, but `{model_provider_id}` \
+                     is at {}",
+                    model_provider.base_url
+                ),
+            ));
+        }
 
-        let shell_environment_policy = cfg.shell_environment_policy.into();
+        if let Some(provider) = cfg
+            .semantic_search
+            .provider
+            .as_ref()
+            .filter(|provider| !model_providers.contains_key(*provider))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("semantic_search.provider `{provider}` not found"),
+            ));
+        }
+
+        let shell_environment_policy = ShellEnvironmentPolicy {
+            source: cfg.shell_environment.unwrap_or_default(),
+            ..cfg.shell_environment_policy.into()
+        };
 
         let resolved_cwd = {
             use std::env;
@@ -425,8 +1121,38 @@ impl Config {
             }
         };
 
+        let focus = focus
+            .map(|focus| resolve_focus(&resolved_cwd, &focus))
+            .transpose()?;
+        // Landlock can confine reads to the focus. Seatbelt would need every
+        // path programs read to start listed as well, so on macOS sandboxed
+        // commands keep reading the whole disk.
+        let focus_reads = |policy: SandboxPolicy| match &focus {
+            Some(focus) if cfg!(target_os = "linux") => policy.with_reads_limited_to(focus),
+            _ => policy,
+        };
+        let sandbox_policy = focus_reads(sandbox_policy);
+        let escalation = cfg
+            .escalation
+            .and_then(EscalationToml::write_mode_policy)
+            .map(|policy| {
+                if offline {
+                    policy.without_network_access()
+                } else {
+                    policy
+                }
+            })
+            .map(|policy| policy.without_permissions(forbidden))
+            .map(focus_reads);
+
         let history = cfg.history.unwrap_or_default();
 
+        let policy_file = codex_home.join(
+            cfg.policy_file
+                .unwrap_or_else(|| PathBuf::from(POLICY_FILE)),
+        );
+        let policy = policy::load(&policy_file)?;
+
         let config = Self {
             model: model
                 .or(config_profile.model)
@@ -440,7 +1166,17 @@ impl Config {
                 .or(cfg.approval_policy)
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
+            mode: mode
+                .or(config_profile.mode)
+                .or(cfg.mode)
+                .unwrap_or_default(),
+            dry_run: dry_run.or(cfg.dry_run).unwrap_or(false),
+            focus,
             shell_environment_policy,
+            shell: match cfg.windows_shell {
+                Some(shell) if cfg!(windows) => shell,
+                _ => Shell::detect(),
+            },
             disable_response_storage: config_profile
                 .disable_response_storage
                 .or(cfg.disable_response_storage)
@@ -450,19 +1186,88 @@ impl Config {
             mcp_servers: cfg.mcp_servers,
             model_providers,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
+            repo_map_max_tokens: cfg
+                .repo_map_max_tokens
+                .unwrap_or(DEFAULT_REPO_MAP_MAX_TOKENS),
+            max_sub_agents: cfg.max_sub_agents.unwrap_or(DEFAULT_MAX_SUB_AGENTS),
+            max_parallel_tool_calls: cfg
+                .max_parallel_tool_calls
+                .unwrap_or(DEFAULT_MAX_PARALLEL_TOOL_CALLS)
+                .max(1),
+            escalation,
+            review_edits: cfg.review_edits.unwrap_or_default(),
+            apply_mode: apply_mode.or(cfg.apply_mode).unwrap_or_default(),
+            hooks: cfg.hooks,
+            policy,
+            untrusted_content: cfg.untrusted_content,
+            checks: cfg.checks,
+            router: cfg.router,
+            semantic_search: cfg.semantic_search,
+            attachments: cfg.attachments,
+            git_context: cfg.git_context,
+            memory: cfg.memory,
+            redact_secrets: cfg.redact_secrets.unwrap_or(true),
+            linux_sandbox: cfg.linux_sandbox,
+            macos_seatbelt: cfg.macos_seatbelt,
+            audit_log: cfg.audit_log.unwrap_or(true),
+            compress_sessions: cfg.compress_sessions.unwrap_or(true),
+            encrypt_sessions: cfg.encrypt_sessions.unwrap_or(false),
+            session_store: cfg.session_store.unwrap_or_default(),
+            retention: cfg.retention,
+            http_proxy: cfg.http_proxy,
+            https_proxy: cfg.https_proxy,
+            extra_ca_certs: cfg
+                .extra_ca_certs
+                .into_iter()
+                .map(|path| codex_home.join(path))
+                .collect(),
+            offline,
+            forbidden_sandbox_permissions,
+            locked,
+            event_sink: event_sink.or(cfg.event_sink),
+            otel: cfg.otel,
             codex_home,
             history,
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             tui: cfg.tui.unwrap_or_default(),
+            notifications: cfg.notifications.unwrap_or_default(),
             codex_linux_sandbox_exe,
+            resume_from: None,
+            record_rollout: true,
+            test_command_from_project: false,
 
             hide_agent_reasoning: cfg.hide_agent_reasoning.unwrap_or(false),
-            model_reasoning_effort: cfg.model_reasoning_effort.unwrap_or_default(),
-            model_reasoning_summary: cfg.model_reasoning_summary.unwrap_or_default(),
+            model_reasoning_effort: config_profile
+                .model_reasoning_effort
+                .or(cfg.model_reasoning_effort)
+                .unwrap_or_default(),
+            model_reasoning_summary: config_profile
+                .model_reasoning_summary
+                .or(cfg.model_reasoning_summary)
+                .unwrap_or_default(),
+            sampling,
         };
         Ok(config)
     }
 
+    /// Whether the managed config locks the top-level `key`.
+    pub fn locks(&self, key: &str) -> bool {
+        self.locked.iter().any(|locked| locked == key)
+    }
+
+    /// Whether the managed config forbids granting `permission`.
+    pub fn forbids(&self, permission: &SandboxPermission) -> bool {
+        is_forbidden(&self.forbidden_sandbox_permissions, permission)
+    }
+
+    /// Whether commands may run outside the sandbox, which gives them full
+    /// disk write and network access: not when the managed config forbids
+    /// either.
+    pub fn allows_unsandboxed_commands(&self) -> bool {
+        !self.forbids(&SandboxPermission::DiskFullWriteAccess)
+            && !self.forbids(&SandboxPermission::NetworkFullAccess)
+    }
+
     fn load_instructions(codex_dir: Option<&Path>) -> Option<String> {
         let mut p = match codex_dir {
             Some(p) => p.to_path_buf(),
@@ -481,6 +1286,29 @@ impl Config {
     }
 }
 
+/// `focus` resolved against `cwd`, which it must be an existing directory
+/// under.
+fn resolve_focus(cwd: &Path, focus: &Path) -> std::io::Result<PathBuf> {
+    let invalid = |reason: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("--focus {}: {reason}", focus.display()),
+        )
+    };
+    let resolved = cwd
+        .join(focus)
+        .canonicalize()
+        .map_err(|e| invalid(&e.to_string()))?;
+    if !resolved.is_dir() {
+        return Err(invalid("not a directory"));
+    }
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+    if !resolved.starts_with(&cwd) {
+        return Err(invalid(&format!("not inside {}", cwd.display())));
+    }
+    Ok(resolved)
+}
+
 fn default_model() -> String {
     OPENAI_DEFAULT_MODEL.to_string()
 }
@@ -493,7 +1321,7 @@ fn default_model() -> String {
 ///   function will Err if the path does not exist.
 /// - If `CODEX_HOME` is not set, this function does not verify that the
 ///   directory exists.
-fn find_codex_home() -> std::io::Result<PathBuf> {
+pub fn find_codex_home() -> std::io::Result<PathBuf> {
     // Honor the `CODEX_HOME` environment variable when it is set to allow users
     // (and tests) to override the default location.
     if let Ok(val) = std::env::var("CODEX_HOME") {
@@ -520,17 +1348,30 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
     Ok(p)
 }
 
+/// Builds a folder permission from the folder's path.
+type FolderPermission = fn(PathBuf) -> SandboxPermission;
+
 pub fn parse_sandbox_permission_with_base_path(
     raw: &str,
     base_path: PathBuf,
 ) -> std::io::Result<SandboxPermission> {
     use SandboxPermission::*;
 
-    if let Some(path) = raw.strip_prefix("disk-write-folder=") {
+    let folder_permissions: [(&str, FolderPermission); 2] = [
+        ("disk-write-folder", |folder| DiskWriteFolder { folder }),
+        ("disk-read-folder", |folder| DiskReadFolder { folder }),
+    ];
+    for (name, permission) in folder_permissions {
+        let Some(path) = raw
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+        else {
+            continue;
+        };
         return if path.is_empty() {
             Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
-                "--sandbox-permission disk-write-folder=<PATH> requires a non-empty PATH",
+                format!("--sandbox-permission {name}=<PATH> requires a non-empty PATH"),
             ))
         } else {
             use path_absolutize::*;
@@ -542,9 +1383,7 @@ pub fn parse_sandbox_permission_with_base_path(
                 file.absolutize()
             }
             .map(|path| path.into_owned())?;
-            Ok(DiskWriteFolder {
-                folder: absolute_path,
-            })
+            Ok(permission(absolute_path))
         };
     }
 
@@ -567,6 +1406,7 @@ pub fn parse_sandbox_permission_with_base_path(
 #[cfg(test)]
 mod tests {
     #![allow(clippy::expect_used, clippy::unwrap_used)]
+    use crate::config_types::HistoryDedupe;
     use crate::config_types::HistoryPersistence;
 
     use super::*;
@@ -613,19 +1453,37 @@ mod tests {
     fn test_toml_parsing() {
         let history_with_persistence = r#"
 [history]
-persistence = "save-all"
+persistence = "local"
 "#;
         let history_with_persistence_cfg: ConfigToml =
             toml::from_str::<ConfigToml>(history_with_persistence)
                 .expect("TOML deserialization should succeed");
         assert_eq!(
             Some(History {
-                persistence: HistoryPersistence::SaveAll,
+                persistence: HistoryPersistence::Local,
+                dedupe: HistoryDedupe::Consecutive,
                 max_bytes: None,
             }),
             history_with_persistence_cfg.history
         );
 
+        // `save-all` is the former name of `local`.
+        let history_save_all = r#"
+[history]
+persistence = "save-all"
+dedupe = "none"
+"#;
+        let history_save_all_cfg: ConfigToml = toml::from_str::<ConfigToml>(history_save_all)
+            .expect("TOML deserialization should succeed");
+        assert_eq!(
+            Some(History {
+                persistence: HistoryPersistence::Local,
+                dedupe: HistoryDedupe::None,
+                max_bytes: None,
+            }),
+            history_save_all_cfg.history
+        );
+
         let history_no_persistence = r#"
 [history]
 persistence = "none"
@@ -637,6 +1495,7 @@ persistence = "none"
         assert_eq!(
             Some(History {
                 persistence: HistoryPersistence::None,
+                dedupe: HistoryDedupe::Consecutive,
                 max_bytes: None,
             }),
             history_no_persistence_cfg.history
@@ -659,6 +1518,36 @@ persistence = "none"
         assert!(msg.contains("not-a-real-permission"));
     }
 
+    #[test]
+    fn test_escalation_write_mode_policy() {
+        let parse = |toml_source: &str| {
+            toml::from_str::<ConfigToml>(toml_source)
+                .unwrap()
+                .escalation
+                .and_then(EscalationToml::write_mode_policy)
+        };
+
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("[escalation]\nenabled = false"), None);
+        assert_eq!(
+            parse("[escalation]\nenabled = true"),
+            Some(SandboxPolicy::new_full_auto_policy())
+        );
+        assert_eq!(
+            parse(
+                r#"
+[escalation]
+enabled = true
+write_permissions = ["disk-full-read-access", "disk-write-cwd"]
+"#
+            ),
+            Some(SandboxPolicy::from(vec![
+                SandboxPermission::DiskFullReadAccess,
+                SandboxPermission::DiskWriteCwd,
+            ]))
+        );
+    }
+
     struct PrecedenceTestFixture {
         cwd: TempDir,
         codex_home: TempDir,
@@ -699,10 +1588,14 @@ wire_api = "chat"
 model = "o3"
 model_provider = "openai"
 approval_policy = "never"
+model_reasoning_effort = "high"
+model_reasoning_summary = "detailed"
 
 [profiles.gpt3]
 model = "gpt-3.5-turbo"
 model_provider = "openai-chat-completions"
+temperature = 0.2
+seed = 42
 
 [profiles.zdr]
 model = "o3"
@@ -729,6 +1622,9 @@ disable_response_storage = true
             env_key: Some("OPENAI_API_KEY".to_string()),
             wire_api: crate::WireApi::Chat,
             env_key_instructions: None,
+            supports_images: true,
+            supports_structured_output: true,
+            rate_limit: None,
         };
         let model_provider_map = {
             let mut model_provider_map = built_in_model_providers();
@@ -787,7 +1683,11 @@ disable_response_storage = true
                 model_provider: fixture.openai_provider.clone(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
+                mode: SessionMode::Code,
+                dry_run: false,
+                focus: None,
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
+                shell: Shell::Posix,
                 disable_response_storage: false,
                 instructions: None,
                 notify: None,
@@ -795,14 +1695,50 @@ disable_response_storage = true
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+                repo_map_max_tokens: DEFAULT_REPO_MAP_MAX_TOKENS,
+                max_sub_agents: DEFAULT_MAX_SUB_AGENTS,
+                max_parallel_tool_calls: DEFAULT_MAX_PARALLEL_TOOL_CALLS,
+                escalation: None,
+                review_edits: ReviewEdits::PerPatch,
+                apply_mode: ApplyMode::Write,
+                hooks: Hooks::default(),
+                policy: Policy::default(),
+                untrusted_content: UntrustedContent::default(),
+                router: Router::default(),
+                semantic_search: SemanticSearch::default(),
+                attachments: Attachments::default(),
+                git_context: GitContext::default(),
+                memory: Memory::default(),
+                checks: ProjectChecks::default(),
+                redact_secrets: true,
+                linux_sandbox: LinuxSandbox::default(),
+                macos_seatbelt: MacosSeatbelt::default(),
+                otel: None,
+                audit_log: true,
+                compress_sessions: true,
+                encrypt_sessions: false,
+                session_store: SessionStoreKind::Jsonl,
+                retention: Retention::default(),
+                http_proxy: None,
+                https_proxy: None,
+                extra_ca_certs: Vec::new(),
+                offline: false,
+                forbidden_sandbox_permissions: Vec::new(),
+                locked: Vec::new(),
+                event_sink: None,
                 codex_home: fixture.codex_home(),
                 history: History::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 tui: Tui::default(),
+                notifications: Notifications::default(),
                 codex_linux_sandbox_exe: None,
+                resume_from: None,
+                record_rollout: true,
+                test_command_from_project: false,
                 hide_agent_reasoning: false,
-                model_reasoning_effort: ReasoningEffort::default(),
-                model_reasoning_summary: ReasoningSummary::default(),
+                model_reasoning_effort: ReasoningEffort::High,
+                model_reasoning_summary: ReasoningSummary::Detailed,
+                sampling: SamplingParams::default(),
             },
             o3_profile_config
         );
@@ -829,7 +1765,11 @@ disable_response_storage = true
             model_provider: fixture.openai_chat_completions_provider.clone(),
             approval_policy: AskForApproval::UnlessAllowListed,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            mode: SessionMode::Code,
+            dry_run: false,
+            focus: None,
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            shell: Shell::Posix,
             disable_response_storage: false,
             instructions: None,
             notify: None,
@@ -837,14 +1777,54 @@ disable_response_storage = true
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            repo_map_max_tokens: DEFAULT_REPO_MAP_MAX_TOKENS,
+            max_sub_agents: DEFAULT_MAX_SUB_AGENTS,
+            max_parallel_tool_calls: DEFAULT_MAX_PARALLEL_TOOL_CALLS,
+            escalation: None,
+            review_edits: ReviewEdits::PerPatch,
+            apply_mode: ApplyMode::Write,
+            hooks: Hooks::default(),
+            policy: Policy::default(),
+            untrusted_content: UntrustedContent::default(),
+            router: Router::default(),
+            semantic_search: SemanticSearch::default(),
+            attachments: Attachments::default(),
+            git_context: GitContext::default(),
+            memory: Memory::default(),
+            checks: ProjectChecks::default(),
+            redact_secrets: true,
+            linux_sandbox: LinuxSandbox::default(),
+            macos_seatbelt: MacosSeatbelt::default(),
+            otel: None,
+            audit_log: true,
+            compress_sessions: true,
+            encrypt_sessions: false,
+            session_store: SessionStoreKind::Jsonl,
+            retention: Retention::default(),
+            http_proxy: None,
+            https_proxy: None,
+            extra_ca_certs: Vec::new(),
+            offline: false,
+            forbidden_sandbox_permissions: Vec::new(),
+            locked: Vec::new(),
+            event_sink: None,
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             tui: Tui::default(),
+            notifications: Notifications::default(),
             codex_linux_sandbox_exe: None,
+            resume_from: None,
+            record_rollout: true,
+            test_command_from_project: false,
             hide_agent_reasoning: false,
             model_reasoning_effort: ReasoningEffort::default(),
             model_reasoning_summary: ReasoningSummary::default(),
+            sampling: SamplingParams {
+                temperature: Some(0.2),
+                top_p: None,
+                seed: Some(42),
+            },
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -886,7 +1866,11 @@ disable_response_storage = true
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            mode: SessionMode::Code,
+            dry_run: false,
+            focus: None,
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            shell: Shell::Posix,
             disable_response_storage: true,
             instructions: None,
             notify: None,
@@ -894,18 +1878,254 @@ disable_response_storage = true
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            repo_map_max_tokens: DEFAULT_REPO_MAP_MAX_TOKENS,
+            max_sub_agents: DEFAULT_MAX_SUB_AGENTS,
+            max_parallel_tool_calls: DEFAULT_MAX_PARALLEL_TOOL_CALLS,
+            escalation: None,
+            review_edits: ReviewEdits::PerPatch,
+            apply_mode: ApplyMode::Write,
+            hooks: Hooks::default(),
+            policy: Policy::default(),
+            untrusted_content: UntrustedContent::default(),
+            router: Router::default(),
+            semantic_search: SemanticSearch::default(),
+            attachments: Attachments::default(),
+            git_context: GitContext::default(),
+            memory: Memory::default(),
+            checks: ProjectChecks::default(),
+            redact_secrets: true,
+            linux_sandbox: LinuxSandbox::default(),
+            macos_seatbelt: MacosSeatbelt::default(),
+            otel: None,
+            audit_log: true,
+            compress_sessions: true,
+            encrypt_sessions: false,
+            session_store: SessionStoreKind::Jsonl,
+            retention: Retention::default(),
+            http_proxy: None,
+            https_proxy: None,
+            extra_ca_certs: Vec::new(),
+            offline: false,
+            forbidden_sandbox_permissions: Vec::new(),
+            locked: Vec::new(),
+            event_sink: None,
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             tui: Tui::default(),
+            notifications: Notifications::default(),
             codex_linux_sandbox_exe: None,
+            resume_from: None,
+            record_rollout: true,
+            test_command_from_project: false,
             hide_agent_reasoning: false,
             model_reasoning_effort: ReasoningEffort::default(),
             model_reasoning_summary: ReasoningSummary::default(),
+            sampling: SamplingParams::default(),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
 
         Ok(())
     }
+
+    #[test]
+    fn sampling_flags_override_the_profile_and_are_range_checked() -> std::io::Result<()> {
+        let fixture = create_test_fixture()?;
+
+        let config = Config::load_from_base_config_with_overrides(
+            fixture.cfg.clone(),
+            ConfigOverrides {
+                cwd: Some(fixture.cwd()),
+                temperature: Some(0.0),
+                ..Default::default()
+            },
+            fixture.codex_home(),
+        )?;
+        assert_eq!(
+            config.sampling,
+            SamplingParams {
+                temperature: Some(0.0),
+                top_p: None,
+                seed: Some(42),
+            }
+        );
+
+        let err = Config::load_from_base_config_with_overrides(
+            fixture.cfg.clone(),
+            ConfigOverrides {
+                cwd: Some(fixture.cwd()),
+                temperature: Some(2.5),
+                ..Default::default()
+            },
+            fixture.codex_home(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "`temperature` must be between 0 and 2, got 2.5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn project_config_is_layered_over_the_user_config() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let mut root = toml::from_str::<TomlValue>(
+            r#"
+model = "o3"
+approval_policy = "unless-allow-listed"
+
+[checks]
+test = "make test"
+lint = "make lint"
+"#,
+        )
+        .unwrap();
+
+        let repo = TempDir::new()?;
+        std::fs::create_dir(repo.path().join(".git"))?;
+        std::fs::create_dir_all(repo.path().join(".codex"))?;
+        std::fs::write(
+            repo.path().join(PROJECT_CONFIG_FILE),
+            r#"
+model = "gpt-4.1"
+approval_policy = "never"
+notify = ["curl", "https://example.com"]
+
+[checks]
+test = "cargo test"
+"#,
+        )?;
+        let nested = repo.path().join("src/bin");
+        std::fs::create_dir_all(&nested)?;
+
+        let project = load_project_config_as_toml(&nested, codex_home.path(), None)?.unwrap();
+        merge_toml(&mut root, project);
+        let cfg: ConfigToml = root.try_into().unwrap();
+
+        assert_eq!(cfg.model.as_deref(), Some("gpt-4.1"));
+        // A repository cannot loosen approvals or run programs of its own.
+        assert_eq!(cfg.approval_policy, Some(AskForApproval::UnlessAllowListed));
+        assert_eq!(cfg.notify, None);
+        assert_eq!(
+            cfg.checks,
+            ProjectChecks {
+                test: Some("cargo test".to_string()),
+                lint: Some("make lint".to_string()),
+                ..Default::default()
+            }
+        );
+
+        // The search stops at the Git root.
+        std::fs::remove_dir(repo.path().join(".git"))?;
+        std::fs::create_dir(nested.join(".git"))?;
+        assert_eq!(
+            load_project_config_as_toml(&nested, codex_home.path(), None)?,
+            None
+        );
+
+        // Outside a Git repository, it stops at `$HOME`, and outside that
+        // it only looks in `cwd`.
+        std::fs::remove_dir(nested.join(".git"))?;
+        assert!(
+            load_project_config_as_toml(&nested, codex_home.path(), Some(repo.path()))?.is_some()
+        );
+        assert_eq!(
+            load_project_config_as_toml(&nested, codex_home.path(), Some(&nested))?,
+            None
+        );
+        assert_eq!(
+            load_project_config_as_toml(&nested, codex_home.path(), None)?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn the_managed_config_locks_keys_and_forbids_permissions() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let mut root = toml::from_str::<TomlValue>(
+            r#"
+model = "o3"
+approval_policy = "never"
+sandbox_permissions = ["disk-full-read-access", "disk-full-write-access"]
+profile = "yolo"
+
+[profiles.yolo]
+approval_policy = "never"
+model = "gpt-4.1"
+
+[managed]
+locked = []
+"#,
+        )
+        .unwrap();
+        let managed = parse_managed_config(
+            r#"
+approval_policy = "on-failure"
+model = "o4-mini"
+
+[managed]
+locked = ["approval_policy", "sandbox_permissions"]
+forbidden_sandbox_permissions = ["disk-full-write-access", "disk-write-folder=/etc"]
+"#,
+            MANAGED_CONFIG_PATH,
+        )?;
+
+        merge_toml(&mut root, managed.clone());
+        apply_toml_override(&mut root, "approval_policy", "never".into());
+        apply_toml_override(&mut root, "model", "codex-mini-latest".into());
+        lock_managed_keys(&mut root, Some(&managed));
+        let cfg: ConfigToml = root.try_into().unwrap();
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                approval_policy: Some(AskForApproval::Never),
+                sandbox_policy: Some(SandboxPolicy::new_full_auto_policy()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+
+        // Locked: neither `-c`, the profile nor the flags change it.
+        assert_eq!(config.approval_policy, AskForApproval::OnFailure);
+        // Locked but unset in the managed config: the default applies.
+        assert_eq!(config.sandbox_policy, SandboxPolicy::new_read_only_policy());
+        // Not locked: the managed value is a default that `-c` and profiles
+        // can change.
+        assert_eq!(config.model, "gpt-4.1");
+        assert!(config.forbids(&SandboxPermission::DiskFullWriteAccess));
+        assert!(config.forbids(&SandboxPermission::DiskWriteFolder {
+            folder: PathBuf::from("/etc/ssh"),
+        }));
+        assert!(!config.forbids(&SandboxPermission::DiskWriteFolder {
+            folder: PathBuf::from("/tmp"),
+        }));
+
+        // Forbidden permissions are dropped from whatever policy is asked for.
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+sandbox_permissions = ["disk-full-read-access", "disk-full-write-access"]
+
+[managed]
+forbidden_sandbox_permissions = ["disk-full-write-access"]
+"#,
+        )
+        .unwrap();
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(config.sandbox_policy, SandboxPolicy::new_read_only_policy());
+        assert!(!config.allows_unsandboxed_commands());
+
+        // Users cannot write a `[managed]` table of their own.
+        let mut root = toml::from_str::<TomlValue>("[managed]\nlocked = [\"model\"]").unwrap();
+        lock_managed_keys(&mut root, None);
+        assert_eq!(root, TomlValue::Table(Default::default()));
+        Ok(())
+    }
 }
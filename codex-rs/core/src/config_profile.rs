@@ -1,6 +1,9 @@
 use serde::Deserialize;
 
+use crate::config_types::ReasoningEffort;
+use crate::config_types::ReasoningSummary;
 use crate::protocol::AskForApproval;
+use crate::protocol::SessionMode;
 
 /// Collection of common configuration options that a user can define as a unit
 /// in `config.toml`.
@@ -12,4 +15,10 @@ pub struct ConfigProfile {
     pub model_provider: Option<String>,
     pub approval_policy: Option<AskForApproval>,
     pub disable_response_storage: Option<bool>,
+    pub mode: Option<SessionMode>,
+    pub model_reasoning_effort: Option<ReasoningEffort>,
+    pub model_reasoning_summary: Option<ReasoningSummary>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub seed: Option<u64>,
 }
@@ -4,12 +4,17 @@
 // definitions that do not contain business logic.
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use strum_macros::Display;
+use strum_macros::EnumString;
 use wildmatch::WildMatchPattern;
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::protocol::MAX_FILE_MENTION_BYTES;
+
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
     pub command: String,
@@ -54,10 +59,14 @@ impl UriBasedFileOpener {
 
 /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
 pub struct History {
-    /// If true, history entries will not be written to disk.
+    /// Whether prompts are written to disk at all.
     pub persistence: HistoryPersistence,
 
+    /// Which prompts are left out as duplicates.
+    pub dedupe: HistoryDedupe,
+
     /// If set, the maximum size of the history file in bytes.
     /// TODO(mbolin): Not currently honored.
     pub max_bytes: Option<usize>,
@@ -66,15 +75,28 @@ pub struct History {
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum HistoryPersistence {
-    /// Save all history entries to disk.
+    /// Save history entries to the local history file.
     #[default]
-    SaveAll,
+    #[serde(alias = "save-all")]
+    Local,
     /// Do not write history to disk.
     None,
 }
 
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HistoryDedupe {
+    /// Skip a prompt that is identical to the last entry in the file, so
+    /// re-running a prompt does not take up more than one step of recall.
+    #[default]
+    Consecutive,
+    /// Record every prompt.
+    None,
+}
+
 /// Collection of settings that are specific to the TUI.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
 pub struct Tui {
     /// By default, mouse capture is enabled in the TUI so that it is possible
     /// to scroll the conversation history with a mouse. This comes at the cost
@@ -88,6 +110,379 @@ pub struct Tui {
     /// `space` still work. This allows the user to select text in the TUI
     /// using the mouse without needing to hold down a modifier key.
     pub disable_mouse_capture: bool,
+
+    /// Name of the syntax highlighting theme used for fenced code blocks in
+    /// agent output (e.g. `"base16-ocean.dark"` or `"InspiredGitHub"`). Set
+    /// to `"none"` to disable highlighting.
+    pub code_theme: Option<String>,
+
+    /// Key chords for TUI actions, e.g. `submit = "ctrl+enter"`.
+    pub keybindings: Keybindings,
+
+    /// Color theme: `"auto"` (the default; picks dark or light from the
+    /// terminal background), `"dark"`, `"light"`, `"high-contrast"`, or the
+    /// name of a table in `themes`.
+    pub theme: Option<String>,
+
+    /// User-defined themes, keyed by name.
+    pub themes: HashMap<String, ThemeColors>,
+}
+
+/// A user-defined color table. Colors are names (`"magenta"`,
+/// `"light-blue"`), ANSI indices (`"208"`) or hex (`"#b58900"`); unset
+/// colors come from the `base` theme.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ThemeColors {
+    /// Built-in theme to start from: `"dark"` (default), `"light"` or
+    /// `"high-contrast"`.
+    pub base: Option<String>,
+    /// Agent output labels and titles.
+    pub agent: Option<String>,
+    /// User message labels.
+    pub user: Option<String>,
+    /// Added lines, successful results.
+    pub success: Option<String>,
+    /// Removed lines, errors.
+    pub error: Option<String>,
+    /// Modified files, warnings.
+    pub warning: Option<String>,
+    /// Secondary highlights such as diff hunk headers.
+    pub info: Option<String>,
+    /// Items in completion popups.
+    pub accent: Option<String>,
+    /// Background of the selected row in lists.
+    pub selection: Option<String>,
+    /// Border and scrollbar of the focused pane.
+    pub focus: Option<String>,
+    /// De-emphasized chrome such as scrollbar tracks.
+    pub muted: Option<String>,
+    /// Emphasized foreground text.
+    pub text: Option<String>,
+    /// Background of search matches.
+    pub search_match: Option<String>,
+}
+
+/// Key chords such as `"ctrl+c"`, `"enter"` or `"pageup"`, one per action.
+/// Unset actions keep their default binding.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Keybindings {
+    /// Send the composed message. Defaults to `enter`.
+    pub submit: Option<String>,
+
+    /// Interrupt the running task. Defaults to `ctrl+c`.
+    pub interrupt: Option<String>,
+
+    /// Scroll the conversation up a page. Defaults to `pageup`.
+    pub scroll_up: Option<String>,
+
+    /// Scroll the conversation down a page. Defaults to `pagedown`.
+    pub scroll_down: Option<String>,
+
+    /// Show or hide the diff viewer. Unbound by default (use `/diff`).
+    pub toggle_diff: Option<String>,
+
+    /// Approve a command or patch once. Defaults to `y`.
+    pub approve: Option<String>,
+
+    /// Reject a command or patch. Defaults to `n`.
+    pub reject: Option<String>,
+}
+
+/// Desktop notifications raised by the TUI while the terminal is not
+/// focused.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Notifications {
+    /// Master switch; notifications are off unless this is `true`.
+    pub enabled: bool,
+
+    /// Notify when the agent is waiting for approval.
+    pub approval_requests: bool,
+
+    /// Notify when a task finishes.
+    pub task_complete: bool,
+
+    /// Only notify about finished tasks that ran for at least this many
+    /// seconds, so quick replies do not pop up a notification.
+    pub min_task_duration_secs: u64,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            approval_requests: true,
+            task_complete: true,
+            min_task_duration_secs: 10,
+        }
+    }
+}
+
+/// User scripts run around tool calls, from the `[hooks]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Hooks {
+    /// Run before a tool call; can block it or rewrite its arguments.
+    pub pre_tool_call: Vec<HookConfig>,
+
+    /// Run after a tool call with its output; cannot change the outcome.
+    pub post_tool_call: Vec<HookConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HookConfig {
+    /// Program and arguments. The call is written to stdin as JSON.
+    pub command: Vec<String>,
+
+    /// Tool names (glob patterns allowed) the hook applies to. Empty means
+    /// every tool.
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// Kill the hook after this many milliseconds.
+    pub timeout_ms: Option<u64>,
+}
+
+/// Model routing, from the `[router]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Router {
+    /// Cheaper model, from the same provider, for requests that only
+    /// summarize, such as `/compact`. Unset sends every request to `model`.
+    pub small_model: Option<String>,
+}
+
+/// The `semantic_search` tool, from the `[semantic_search]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SemanticSearch {
+    pub enabled: bool,
+    /// Embedding model the workspace and the queries are embedded with.
+    pub model: String,
+    /// Key in `model_providers` of the provider serving `model`. Unset uses
+    /// the session's provider.
+    pub provider: Option<String>,
+}
+
+impl Default for SemanticSearch {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: "text-embedding-3-small".to_string(),
+            provider: None,
+        }
+    }
+}
+
+/// Limits on the files attached to a message with `@path`, from the
+/// `[attachments]` table.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Attachments {
+    /// Larger files are sent as their first and last lines.
+    pub max_file_bytes: usize,
+    /// Shared by all the files attached to one message; files past it are
+    /// not sent.
+    pub max_turn_bytes: usize,
+}
+
+impl Default for Attachments {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: MAX_FILE_MENTION_BYTES,
+            max_turn_bytes: 4 * MAX_FILE_MENTION_BYTES,
+        }
+    }
+}
+
+/// Recent Git changes sent ahead of the first prompt of a session, from the
+/// `[git_context]` table.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct GitContext {
+    pub enabled: bool,
+    /// Changes are taken since `HEAD~commits`; `0` takes the uncommitted
+    /// ones only.
+    pub commits: usize,
+    /// Longer diffs are cut; `0` sends the list of changed files only.
+    pub max_bytes: usize,
+}
+
+impl Default for GitContext {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            commits: 1,
+            max_bytes: 16 * 1024,
+        }
+    }
+}
+
+/// Facts about the project remembered across sessions, from the `[memory]`
+/// table.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Memory {
+    pub enabled: bool,
+    /// Most bytes of memories sent ahead of the first prompt; the least
+    /// relevant ones are left out.
+    pub max_bytes: usize,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 4 * 1024,
+        }
+    }
+}
+
+/// How long recorded sessions are kept, from the `[retention]` table. Files
+/// last written more than that many days ago are deleted when a session
+/// starts and by `codex gc`; unset keeps them forever.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Retention {
+    /// Session rollouts in `~/.codex/sessions`.
+    pub sessions_days: Option<u32>,
+    /// Audit logs in `~/.codex/audit`.
+    pub audit_days: Option<u32>,
+}
+
+impl Retention {
+    pub fn is_enabled(&self) -> bool {
+        self.sessions_days.is_some() || self.audit_days.is_some()
+    }
+}
+
+/// Tool output from outside the workspace, such as web pages and issue
+/// bodies, which could try to instruct the agent. From the
+/// `[untrusted_content]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct UntrustedContent {
+    pub enabled: bool,
+    /// Tool names (glob patterns allowed) whose output is untrusted, as
+    /// hooks see them, e.g. `<server>/<tool>` for MCP tools.
+    pub tools: Vec<String>,
+    /// Commands (glob patterns allowed) whose output is untrusted. Each
+    /// command of a `bash -lc` script is matched on its own.
+    pub commands: Vec<String>,
+}
+
+impl Default for UntrustedContent {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tools: vec!["*/fetch*".to_string()],
+            commands: [
+                "curl *",
+                "wget *",
+                "gh issue view*",
+                "gh pr view*",
+                "gh api *",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+/// Customization of the Linux sandbox (Landlock and seccomp), from the
+/// `[linux_sandbox]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct LinuxSandbox {
+    /// Paths sandboxed commands may read when the sandbox permissions do not
+    /// include `disk-full-read-access`, besides the working directory, the
+    /// writable roots and the system directories programs need to run.
+    pub read_only_paths: Vec<PathBuf>,
+    /// System calls that fail with `EPERM` in the sandbox, besides the
+    /// network calls blocked whenever network access is not granted.
+    pub blocked_syscalls: Vec<SyscallClass>,
+    /// Record every sandboxed command that fails because it was denied
+    /// something in the audit log, with the access it was denied when it
+    /// can be told.
+    pub log_violations: bool,
+}
+
+/// Additions to the macOS Seatbelt profile, from the `[macos_seatbelt]`
+/// table.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct MacosSeatbelt {
+    /// Seatbelt (sbpl) `allow`/`deny` rules appended to the generated
+    /// profile, e.g. to let commands write to a toolchain directory without
+    /// granting `disk-full-write-access`. Checked when the config is loaded.
+    pub extra_policy: Vec<String>,
+}
+
+/// Groups of related system calls that `[linux_sandbox]` can block.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum SyscallClass {
+    /// `mount`, `umount2` and `pivot_root`.
+    Mount,
+    /// Loading and unloading kernel modules.
+    KernelModules,
+    /// The kernel keyring: `add_key`, `keyctl` and `request_key`.
+    Keyring,
+    /// Creating or joining namespaces: `unshare` and `setns`.
+    Namespaces,
+    /// `bpf` and `perf_event_open`.
+    Bpf,
+    /// `reboot`, `kexec_load`, `swapon` and `swapoff`.
+    Reboot,
+}
+
+/// Commands that verify a change to the project, from the `[checks]` table.
+/// They are listed in the instructions so the model runs them before it
+/// finishes; `codex init` fills them in from what it detects.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ProjectChecks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl ProjectChecks {
+    /// The configured commands, by kind, in the order they should run.
+    pub fn commands(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("format", &self.format),
+            ("build", &self.build),
+            ("lint", &self.lint),
+            ("test", &self.test),
+        ]
+        .into_iter()
+        .filter_map(|(kind, command)| command.as_deref().map(|command| (kind, command)))
+        .collect()
+    }
+}
+
+/// OpenTelemetry export, from the `[otel]` table. Only honored by builds with
+/// the `otel` feature.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct OtelConfig {
+    /// OTLP/HTTP traces endpoint, e.g. `http://localhost:4318/v1/traces`.
+    pub endpoint: String,
+
+    /// Extra HTTP headers sent with every export, e.g. for authentication.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// `service.name` of the exported spans. Defaults to `codex`.
+    pub service_name: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
@@ -105,6 +500,21 @@ pub enum ShellEnvironmentPolicyInherit {
     None,
 }
 
+/// Where the environment of the agent's commands starts from, before
+/// `shell_environment_policy` is applied.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShellEnvironment {
+    /// The environment Codex was started with.
+    #[default]
+    Process,
+
+    /// The environment of the user's login shell once it has sourced its
+    /// startup files, so that tools set up there (nvm, pyenv, rustup, ...)
+    /// are on the `PATH`.
+    Login,
+}
+
 /// Policy for building the `env` when spawning a process via either the
 /// `shell` or `local_shell` tool.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
@@ -125,7 +535,8 @@ pub struct ShellEnvironmentPolicyToml {
 pub type EnvironmentVariablePattern = WildMatchPattern<'*', '?'>;
 
 /// Deriving the `env` based on this policy works as follows:
-/// 1. Create an initial map based on the `inherit` policy.
+/// 1. Create an initial map from the `source` environment based on the
+///    `inherit` policy.
 /// 2. If `ignore_default_excludes` is false, filter the map using the default
 ///    exclude pattern(s), which are: `"*KEY*"` and `"*TOKEN*"`.
 /// 3. If `exclude` is not empty, filter the map using the provided patterns.
@@ -133,6 +544,9 @@ pub type EnvironmentVariablePattern = WildMatchPattern<'*', '?'>;
 /// 5. If non-empty, filter the map using the `include_only` patterns.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct ShellEnvironmentPolicy {
+    /// Environment `inherit` takes variables from.
+    pub source: ShellEnvironment,
+
     /// Starting point when building the environment.
     pub inherit: ShellEnvironmentPolicyInherit,
 
@@ -169,6 +583,7 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
             .collect();
 
         Self {
+            source: ShellEnvironment::default(),
             inherit,
             ignore_default_excludes,
             exclude,
@@ -178,8 +593,51 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
     }
 }
 
+/// When the user reviews the agent's file edits.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewEdits {
+    /// Each `apply_patch` call is approved on its own, as the approval policy
+    /// requires.
+    #[default]
+    PerPatch,
+
+    /// Edits are applied without asking but staged, and the changes of the
+    /// whole task are reviewed as one diff when it ends.
+    PerTurn,
+}
+
+/// What happens to the agent's `apply_patch` calls.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApplyMode {
+    /// Codex writes the patch, once approved.
+    #[default]
+    Write,
+
+    /// Codex never writes the patch: it is sent to the front-end as a
+    /// `PatchProposed` event, e.g. for an editor plugin to apply.
+    Propose,
+}
+
+/// Which backend `codex sessions` looks recorded sessions up in. Rollouts are
+/// written to `~/.codex/sessions` either way.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionStoreKind {
+    /// Read the rollouts, through a cache of their summaries.
+    #[default]
+    Jsonl,
+
+    /// Keep the summaries and the text of the messages in a SQLite database
+    /// next to the rollouts, for queries over thousands of sessions.
+    Sqlite,
+}
+
 /// See https://platform.openai.com/docs/guides/reasoning?api-mode=responses#get-started-with-reasoning
-#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Display)]
+#[derive(
+    Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Display, JsonSchema,
+)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum ReasoningEffort {
@@ -194,7 +652,9 @@ pub enum ReasoningEffort {
 /// A summary of the reasoning performed by the model. This can be useful for
 /// debugging and understanding the model's reasoning process.
 /// See https://platform.openai.com/docs/guides/reasoning?api-mode=responses#reasoning-summaries
-#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Display)]
+#[derive(
+    Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Display, JsonSchema,
+)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum ReasoningSummary {
@@ -205,3 +665,21 @@ pub enum ReasoningSummary {
     /// Option to disable reasoning summaries.
     None,
 }
+
+/// Sampling parameters sent with each model request. Unset values are left to
+/// the provider's defaults.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, JsonSchema)]
+pub struct SamplingParams {
+    /// Between 0 and 2; lower values make the output more deterministic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    /// Between 0 and 1; only tokens within this probability mass are sampled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    /// Makes sampling repeatable on a best-effort basis, for providers that
+    /// support it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
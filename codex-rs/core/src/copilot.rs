@@ -1,5 +1,5 @@
 use crate::error::CodexErr;
-use crate::protocol::{Event, EventMsg, CopilotAuthStartedEvent, CopilotAuthCompleteEvent, Op};
+use crate::protocol::{Event, EventMsg, CopilotAuthStartedEvent, CopilotAuthProgressEvent, CopilotAuthCompleteEvent, Op};
 use crate::{Codex, config::Config};
 use crate::copilot_token_store::{CopilotToken, CopilotTokenStore};
 use async_channel::Sender;
@@ -11,12 +11,55 @@ use anyhow;
 
 const GITHUB_DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
 const GITHUB_DEVICE_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
-const COPILOT_CHAT_AUTH_URL: &str = "https://api.github.com/copilot_internal/v2/token";
+pub(crate) const COPILOT_CHAT_AUTH_URL: &str = "https://api.github.com/copilot_internal/v2/token";
 const GITHUB_CLIENT_ID: &str = "Iv1.b507a08c87ecfe98";
 
-pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Result<(), CodexErr> {
+/// Used when GitHub's device code response leaves out `expires_in` or
+/// `interval`; these are the values GitHub documents.
+const DEFAULT_EXPIRES_IN_SECS: u64 = 900;
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+/// The polling interval after a `slow_down` answer: the one GitHub sends
+/// with it, or else the current one plus 5 seconds as RFC 8628 asks.
+fn slowed_down_interval(token_response: &Value, interval: u64) -> u64 {
+    token_response["interval"]
+        .as_u64()
+        .filter(|&new| new > interval)
+        .unwrap_or(interval + 5)
+}
+
+/// Run the GitHub device flow, reporting on `tx_event`. Notifying `cancel`
+/// backs out of it at any point, ending with an unsuccessful
+/// `CopilotAuthComplete` whose message is "cancelled".
+pub async fn handle_copilot_auth(
+    client: reqwest::Client,
+    tx_event: Sender<Event>,
+    sub_id: String,
+    cancel: Arc<Notify>,
+) -> Result<(), CodexErr> {
+    tokio::select! {
+        result = device_flow(client, tx_event.clone(), sub_id.clone()) => result,
+        _ = cancel.notified() => {
+            tracing::debug!("Copilot authentication cancelled");
+            let event = Event {
+                id: sub_id,
+                msg: EventMsg::CopilotAuthComplete(CopilotAuthCompleteEvent {
+                    success: false,
+                    message: "cancelled".to_string(),
+                }),
+            };
+            tx_event.send(event).await.ok();
+            Ok(())
+        }
+    }
+}
+
+async fn device_flow(
+    client: reqwest::Client,
+    tx_event: Sender<Event>,
+    sub_id: String,
+) -> Result<(), CodexErr> {
     // Step 1: Request device code
-    let client = reqwest::Client::new();
     let device_request = [
         ("client_id", GITHUB_CLIENT_ID),
         ("scope", "copilot"),
@@ -40,7 +83,8 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
     let user_code = device_response["user_code"].as_str().unwrap();
     let verification_uri = device_response["verification_uri"].as_str().unwrap();
     let device_code = device_response["device_code"].as_str().unwrap();
-    let interval = device_response["interval"].as_u64().unwrap_or(5);
+    let mut interval = device_response["interval"].as_u64().unwrap_or(DEFAULT_INTERVAL_SECS);
+    let expires_in = device_response["expires_in"].as_u64().unwrap_or(DEFAULT_EXPIRES_IN_SECS);
 
     // Step 2: Send auth started event
     let auth_started_event = Event {
@@ -48,6 +92,7 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
         msg: EventMsg::CopilotAuthStarted(CopilotAuthStartedEvent {
             verification_uri: verification_uri.to_string(),
             user_code: user_code.to_string(),
+            expires_in_secs: Some(expires_in),
         }),
     };
     tx_event.send(auth_started_event).await.ok();
@@ -56,11 +101,11 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
     let _ = open_browser(verification_uri);
 
     // Step 3: Poll for token
-    let mut interval_timer = tokio::time::interval(std::time::Duration::from_secs(interval));
-    let expires_at = std::time::Instant::now() + std::time::Duration::from_secs(300); // 5 minutes
+    let expires_at = std::time::Instant::now() + std::time::Duration::from_secs(expires_in);
 
     loop {
-        if std::time::Instant::now() > expires_at {
+        let remaining = expires_at.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
             let event = Event {
                 id: sub_id.clone(),
                 msg: EventMsg::CopilotAuthComplete(CopilotAuthCompleteEvent {
@@ -72,7 +117,16 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
             return Ok(());
         }
 
-        interval_timer.tick().await;
+        let event = Event {
+            id: sub_id.clone(),
+            msg: EventMsg::CopilotAuthProgress(CopilotAuthProgressEvent {
+                seconds_remaining: remaining.as_secs(),
+                interval_secs: interval,
+            }),
+        };
+        tx_event.send(event).await.ok();
+
+        tokio::time::sleep(std::time::Duration::from_secs(interval).min(remaining)).await;
 
         let token_request = [
             ("client_id", GITHUB_CLIENT_ID),
@@ -87,16 +141,15 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
             .send()
             .await?;
 
-        println!("Polling GitHub for token... (status: {})", response.status());
+        tracing::debug!("Polling GitHub for token... (status: {})", response.status());
 
         if response.status().is_success() {
             let token_response: Value = response.json().await?;
-            println!("Token response: {:?}", token_response);
             
             if let Some(access_token) = token_response["access_token"].as_str() {
-                println!("✅ Got GitHub access token, exchanging for Copilot token...");
+                tracing::debug!("✅ Got GitHub access token, exchanging for Copilot token...");
                 
-                println!("✅ Got GitHub access token, attempting Copilot authentication...");
+                tracing::debug!("✅ Got GitHub access token, attempting Copilot authentication...");
                 
                 // Try multiple Copilot API endpoints as the internal one might not work
                 let copilot_endpoints = [
@@ -109,7 +162,7 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
                 let mut copilot_token_found = false;
 
                 for (endpoint, endpoint_name) in copilot_endpoints.iter() {
-                    println!("🔍 Trying {} endpoint: {}", endpoint_name, endpoint);
+                    tracing::debug!("🔍 Trying {} endpoint: {}", endpoint_name, endpoint);
                     
                     let copilot_response = client
                         .get(*endpoint)
@@ -121,34 +174,26 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
                         .await?;
 
                     let status = copilot_response.status();
-                    println!("   Status: {}", status);
+                    tracing::debug!("   Status: {}", status);
 
                     if status.is_success() {
                         let auth_response: Value = copilot_response.json().await?;
-                        println!("   Response: {:?}", auth_response);
                         
                         // Try different possible token field names
                         let token_fields = ["token", "access_token", "chat_token", "copilot_token"];
                         for field in token_fields.iter() {
                             if let Some(copilot_token) = auth_response[field].as_str() {
-                                println!("✅ Found Copilot token in field '{}' from {} endpoint", field, endpoint_name);
+                                tracing::debug!("✅ Found Copilot token in field '{}' from {} endpoint", field, endpoint_name);
                                 
                                 // Save token persistently
-                                match save_copilot_token(copilot_token) {
+                                match save_copilot_token(copilot_token, Some(access_token)) {
                                     Ok(_) => {
-                                        println!("💾 Token saved to ~/.codex/copilot_token.json");
-                                        // Also set for this session
-                                        unsafe {
-                                            std::env::set_var("COPILOT_TOKEN", copilot_token);
-                                        }
+                                        tracing::debug!("💾 Token saved to ~/.codex/copilot_token.json");
                                     }
                                     Err(e) => {
-                                        println!("⚠️  Warning: Could not save token persistently: {}", e);
-                                        println!("🔑 To use this token in your shell, run:");
-                                        println!("export COPILOT_TOKEN='{}'", copilot_token);
+                                        tracing::warn!("⚠️  Warning: Could not save token persistently: {}", e);
                                     }
                                 }
-                                println!("");
                         
                                 let event = Event {
                                     id: sub_id.clone(),
@@ -167,37 +212,30 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
                             return Ok(());
                         } else {
                             last_error = format!("No token field found in {} response", endpoint_name);
-                            println!("   ⚠️ {}", last_error);
+                            tracing::debug!("   ⚠️ {}", last_error);
                         }
                     } else if status.as_u16() == 404 {
                         last_error = format!("{} endpoint not found", endpoint_name);
-                        println!("   ⚠️ {}", last_error);
+                        tracing::debug!("   ⚠️ {}", last_error);
                     } else {
                         let error_text = copilot_response.text().await.unwrap_or_default();
                         last_error = format!("{} failed: {} - {}", endpoint_name, status, error_text);
-                        println!("   ❌ {}", last_error);
+                        tracing::debug!("   ❌ {}", last_error);
                     }
                 }
 
                 // If we get here, none of the Copilot endpoints worked
                 // For now, just use the GitHub token directly as a fallback
-                println!("⚠️ No Copilot-specific endpoints worked, using GitHub token as fallback");
+                tracing::debug!("⚠️ No Copilot-specific endpoints worked, using GitHub token as fallback");
                 // Save fallback token persistently
-                match save_copilot_token(access_token) {
+                match save_copilot_token(access_token, None) {
                     Ok(_) => {
-                        println!("💾 GitHub token saved to ~/.codex/copilot_token.json (fallback)");
-                        // Also set for this session
-                        unsafe {
-                            std::env::set_var("COPILOT_TOKEN", access_token);
-                        }
+                        tracing::debug!("💾 GitHub token saved to ~/.codex/copilot_token.json (fallback)");
                     }
                     Err(e) => {
-                        println!("⚠️  Warning: Could not save token persistently: {}", e);
-                        println!("🔑 To use this token in your shell, run:");
-                        println!("export COPILOT_TOKEN='{}'", access_token);
+                        tracing::warn!("⚠️  Warning: Could not save token persistently: {}", e);
                     }
                 }
-                println!("");
                 
                 let event = Event {
                     id: sub_id.clone(),
@@ -209,15 +247,15 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
                 tx_event.send(event).await.ok();
                 return Ok(());
             } else if let Some(error) = token_response["error"].as_str() {
-                println!("GitHub OAuth error: {}", error);
+                tracing::debug!("GitHub OAuth error: {}", error);
                 match error {
                     "authorization_pending" => {
-                        println!("⏳ Still waiting for user authorization...");
+                        tracing::debug!("⏳ Still waiting for user authorization...");
                         continue;
                     },
                     "slow_down" => {
-                        println!("⏳ Rate limited, slowing down polling...");
-                        tokio::time::sleep(std::time::Duration::from_secs(interval + 5)).await;
+                        interval = slowed_down_interval(&token_response, interval);
+                        tracing::debug!("⏳ Rate limited, polling every {}s", interval);
                         continue;
                     }
                     "access_denied" => {
@@ -255,7 +293,7 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
                     }
                 }
             } else {
-                println!("❌ Unexpected response format: {:?}", token_response);
+                tracing::debug!("❌ Unexpected response format: {:?}", token_response);
                 let event = Event {
                     id: sub_id.clone(),
                     msg: EventMsg::CopilotAuthComplete(CopilotAuthCompleteEvent {
@@ -269,11 +307,11 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
         } else {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            println!("❌ Token request failed with status: {} - {}", status, error_text);
+            tracing::debug!("❌ Token request failed with status: {} - {}", status, error_text);
             
             // Don't fail immediately on HTTP errors, GitHub might be temporarily down
             if status.as_u16() >= 500 {
-                println!("⏳ Server error, retrying...");
+                tracing::debug!("⏳ Server error, retrying...");
                 continue;
             } else {
                 let event = Event {
@@ -290,7 +328,16 @@ pub async fn handle_copilot_auth(tx_event: Sender<Event>, sub_id: String) -> Res
     }
 }
 
-fn open_browser(url: &str) -> Result<(), std::io::Error> {
+/// Run the device flow outside a session, e.g. from the TUI's first-run
+/// setup. Progress arrives on `tx_event` as `CopilotAuthStarted`,
+/// `CopilotAuthProgress` and `CopilotAuthComplete` events; notifying
+/// `cancel` abandons the flow.
+pub async fn run_device_flow(config: &Config, tx_event: Sender<Event>, cancel: Arc<Notify>) -> Result<(), CodexErr> {
+    let client = crate::http_client::create_client(config)?;
+    handle_copilot_auth(client, tx_event, String::new(), cancel).await
+}
+
+pub(crate) fn open_browser(url: &str) -> Result<(), std::io::Error> {
     #[cfg(target_os = "macos")]
     {
         Command::new("open").arg(url).spawn()?;
@@ -306,26 +353,55 @@ fn open_browser(url: &str) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-fn save_copilot_token(token: &str) -> anyhow::Result<()> {
+fn save_copilot_token(token: &str, github_token: Option<&str>) -> anyhow::Result<()> {
     let store = CopilotTokenStore::new()?;
-    let copilot_token = CopilotToken::from_raw_token(token);
+    let mut copilot_token = CopilotToken::from_raw_token(token);
+    copilot_token.github_token = github_token.map(str::to_string);
     store.save_token(&copilot_token)?;
     Ok(())
 }
 
+/// Exchange the GitHub OAuth token from the device flow for a new Copilot
+/// token at `token_url`.
+pub(crate) async fn refresh_copilot_token(
+    client: &reqwest::Client,
+    token_url: &str,
+    github_token: &str,
+) -> Result<CopilotToken, CodexErr> {
+    let response = client
+        .get(token_url)
+        .bearer_auth(github_token)
+        .header("Accept", "application/json")
+        .header("User-Agent", "Codex-CLI")
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CodexErr::UnexpectedStatus(
+            status,
+            response.text().await.unwrap_or_default(),
+        ));
+    }
+    let body: Value = response.json().await?;
+    let token = body["token"].as_str().ok_or_else(|| {
+        CodexErr::UnexpectedStatus(status, "no token in the Copilot token response".to_string())
+    })?;
+    let mut copilot_token = CopilotToken::from_raw_token(token);
+    copilot_token.github_token = Some(github_token.to_string());
+    Ok(copilot_token)
+}
+
+/// `seconds` as `m:ss`, for showing how long a device code stays valid.
+pub fn format_countdown(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
 pub fn load_copilot_token() -> Option<String> {
     let store = CopilotTokenStore::new().ok()?;
     store.get_valid_token()
 }
 
-pub fn ensure_copilot_token_in_env() -> bool {
-    if let Ok(store) = CopilotTokenStore::new() {
-        store.set_env_var().unwrap_or(false)
-    } else {
-        false
-    }
-}
-
 #[cfg(feature = "cli")]
 pub async fn run_copilot_auth_command(config_overrides: codex_common::CliConfigOverrides) -> anyhow::Result<()> {
     // Parse config overrides like exec does
@@ -339,7 +415,8 @@ pub async fn run_copilot_auth_command(config_overrides: codex_common::CliConfigO
     
     // Create a minimal config for copilot auth
     let config = Config::load_with_cli_overrides(cli_kv_overrides, Default::default())?;
-    let ctrl_c = Arc::new(Notify::new());
+    // Ctrl-C interrupts the session, which cancels the sign-in.
+    let ctrl_c = crate::util::notify_on_sigint();
     
     // Spawn codex instance
     let (codex, _init_id) = Codex::spawn(config, ctrl_c).await?;
@@ -356,7 +433,10 @@ pub async fn run_copilot_auth_command(config_overrides: codex_common::CliConfigO
                     println!("1. Visit: {}", auth_event.verification_uri);
                     println!("2. Enter this code: {}", auth_event.user_code);
                     println!("3. Authorize the application");
-                    println!("\nWaiting for authentication...");
+                    match auth_event.expires_in_secs {
+                        Some(secs) => println!("\nWaiting for authentication (the code expires in {})...", format_countdown(secs)),
+                        None => println!("\nWaiting for authentication..."),
+                    }
                 }
                 EventMsg::CopilotAuthComplete(complete_event) => {
                     if complete_event.success {
@@ -378,6 +458,70 @@ pub async fn run_copilot_auth_command(config_overrides: codex_common::CliConfigO
     Ok(())
 }
 
+/// Where `codex copilot auth --with-token` looks for the GitHub token when
+/// stdin is a terminal.
+#[cfg(feature = "cli")]
+const GITHUB_TOKEN_ENV_VAR: &str = "GITHUB_TOKEN";
+
+/// Sign in with a GitHub personal access token instead of the device flow,
+/// for environments where the device flow is blocked. The token comes from
+/// stdin, or from `GITHUB_TOKEN` when stdin is a terminal. It is exchanged
+/// for a Copilot token, which proves it can use Copilot, and saved with it so
+/// the Copilot token can be refreshed like one from the device flow.
+#[cfg(feature = "cli")]
+pub async fn run_copilot_token_auth_command(config_overrides: codex_common::CliConfigOverrides) -> anyhow::Result<()> {
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(|e| anyhow::anyhow!("Config override error: {}", e))?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, Default::default())?;
+    if config.offline {
+        anyhow::bail!("Copilot sign-in needs network access, which offline mode forbids");
+    }
+    let github_token = read_github_token()?;
+
+    let client = crate::http_client::create_client(&config)?;
+    let token = match refresh_copilot_token(&client, COPILOT_CHAT_AUTH_URL, &github_token).await {
+        Ok(token) => token,
+        Err(CodexErr::UnexpectedStatus(status, _)) if status == reqwest::StatusCode::UNAUTHORIZED => {
+            anyhow::bail!("GitHub rejected the token; check that it is valid and has not expired");
+        }
+        Err(CodexErr::UnexpectedStatus(status, _))
+            if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::NOT_FOUND =>
+        {
+            anyhow::bail!(
+                "the token cannot use Copilot ({}); it must belong to an account with a Copilot plan, and a fine-grained token needs the \"Copilot Requests\" permission",
+                status
+            );
+        }
+        Err(e) => return Err(e.into()),
+    };
+    CopilotTokenStore::new()?.save_token(&token)?;
+
+    println!("✓ Signed in to GitHub Copilot with the token");
+    if let Some(minutes) = token.expires_in_minutes() {
+        println!("   The Copilot token expires in {} minutes and is refreshed with the GitHub token.", minutes);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cli")]
+fn read_github_token() -> anyhow::Result<String> {
+    use std::io::IsTerminal;
+    use std::io::Read;
+
+    let mut token = String::new();
+    if std::io::stdin().is_terminal() {
+        token = std::env::var(GITHUB_TOKEN_ENV_VAR).unwrap_or_default();
+    } else {
+        std::io::stdin().read_to_string(&mut token)?;
+    }
+    let token = token.trim();
+    if token.is_empty() {
+        anyhow::bail!("no GitHub token: pipe one to stdin or set {}", GITHUB_TOKEN_ENV_VAR);
+    }
+    Ok(token.to_string())
+}
+
 #[cfg(feature = "cli")]
 pub async fn run_copilot_status_command() -> anyhow::Result<()> {
     println!("🔍 Checking Copilot token status...");
@@ -445,4 +589,25 @@ pub async fn run_copilot_status_command() -> anyhow::Result<()> {
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn slow_down_takes_the_interval_github_sends() {
+        assert_eq!(slowed_down_interval(&json!({"error": "slow_down", "interval": 12}), 5), 12);
+        assert_eq!(slowed_down_interval(&json!({"error": "slow_down"}), 5), 10);
+        assert_eq!(slowed_down_interval(&json!({"error": "slow_down", "interval": 5}), 10), 15);
+    }
+
+    #[test]
+    fn countdown_is_minutes_and_seconds() {
+        assert_eq!(format_countdown(900), "15:00");
+        assert_eq!(format_countdown(61), "1:01");
+        assert_eq!(format_countdown(0), "0:00");
+    }
+}
@@ -0,0 +1,140 @@
+//! GitHub Copilot plans, told apart by the `sku` of a Copilot token.
+//!
+//! Copilot Free serves only a few models and answers 403 for the others, so
+//! a session on the Free plan refuses those models up front, with a message
+//! that names the plan. The paid plans are not limited here: on Business and
+//! Enterprise the organization's policy decides, which the client cannot
+//! see.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CopilotPlan {
+    Free,
+    /// Copilot Pro and Pro+.
+    Individual,
+    Business,
+    Enterprise,
+}
+
+/// Models Copilot Free serves. Variants such as `gpt-4o-mini` or dated
+/// snapshots are included with their base model.
+const FREE_MODELS: &[&str] = &[
+    "gpt-4o",
+    "gpt-4.1",
+    "gpt-5-mini",
+    "o3-mini",
+    "claude-3.5-sonnet",
+    "claude-haiku-4.5",
+    "gemini-2.0-flash",
+];
+
+impl CopilotPlan {
+    /// The plan a token's `sku` stands for, e.g. `free_limited_copilot` or
+    /// `copilot_for_business_seat`; `None` for one it does not recognize.
+    pub fn from_sku(sku: &str) -> Option<Self> {
+        let sku = sku.to_ascii_lowercase();
+        if sku.contains("free") {
+            Some(Self::Free)
+        } else if sku.contains("enterprise") {
+            Some(Self::Enterprise)
+        } else if sku.contains("business") {
+            Some(Self::Business)
+        } else if ["subscriber", "individual", "pro"]
+            .iter()
+            .any(|word| sku.contains(word))
+        {
+            Some(Self::Individual)
+        } else {
+            None
+        }
+    }
+
+    /// The models the plan is limited to, or `None` when it is not.
+    pub fn models(self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Free => Some(FREE_MODELS),
+            Self::Individual | Self::Business | Self::Enterprise => None,
+        }
+    }
+
+    pub fn allows_model(self, model: &str) -> bool {
+        self.models().is_none_or(|models| {
+            models.iter().any(|base| {
+                model
+                    .strip_prefix(base)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+            })
+        })
+    }
+
+    /// Why `model` cannot be used on this plan, or `None` when it can.
+    pub fn check_model(self, model: &str) -> Option<String> {
+        let models = self.models().filter(|_| !self.allows_model(model))?;
+        Some(format!(
+            "the GitHub Copilot {self} plan does not include `{model}`; use one of {} (e.g. `/model {}`)",
+            models.join(", "),
+            models[0]
+        ))
+    }
+}
+
+impl fmt::Display for CopilotPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Free => "Free",
+            Self::Individual => "Pro",
+            Self::Business => "Business",
+            Self::Enterprise => "Enterprise",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn recognizes_the_plans_by_sku() {
+        assert_eq!(
+            CopilotPlan::from_sku("free_limited_copilot"),
+            Some(CopilotPlan::Free)
+        );
+        assert_eq!(
+            CopilotPlan::from_sku("monthly_subscriber_quota"),
+            Some(CopilotPlan::Individual)
+        );
+        assert_eq!(
+            CopilotPlan::from_sku("copilot_for_business_seat_quota"),
+            Some(CopilotPlan::Business)
+        );
+        assert_eq!(
+            CopilotPlan::from_sku("copilot_enterprise_seat_quota"),
+            Some(CopilotPlan::Enterprise)
+        );
+        assert_eq!(CopilotPlan::from_sku("something_new"), None);
+    }
+
+    #[test]
+    fn free_plan_refuses_premium_models() {
+        let free = CopilotPlan::Free;
+        assert!(free.allows_model("gpt-4o"));
+        assert!(free.allows_model("gpt-4o-mini"));
+        assert!(free.allows_model("claude-3.5-sonnet"));
+        assert!(!free.allows_model("gpt-4o1"));
+        assert!(!free.allows_model("o3"));
+        assert_eq!(
+            free.check_model("claude-opus-4").as_deref(),
+            Some(
+                "the GitHub Copilot Free plan does not include `claude-opus-4`; use one of gpt-4o, gpt-4.1, gpt-5-mini, o3-mini, claude-3.5-sonnet, claude-haiku-4.5, gemini-2.0-flash (e.g. `/model gpt-4o`)"
+            )
+        );
+        assert_eq!(CopilotPlan::Business.check_model("claude-opus-4"), None);
+    }
+}
@@ -1,8 +1,8 @@
-use std::fs;
-use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
-use anyhow::{Result, Context};
-use dirs::home_dir;
+use anyhow::Result;
+use crate::token_store::TokenFile;
+
+pub(crate) const COPILOT_TOKEN_FILE: &str = "copilot_token.json";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CopilotToken {
@@ -11,6 +11,10 @@ pub struct CopilotToken {
     pub sku: Option<String>,
     pub proxy_endpoint: Option<String>,
     pub tracking_id: Option<String>,
+    /// GitHub OAuth token the Copilot token was issued for, used to get a new
+    /// Copilot token once this one expires.
+    #[serde(default)]
+    pub github_token: Option<String>,
 }
 
 impl CopilotToken {
@@ -21,6 +25,7 @@ impl CopilotToken {
             sku: None,
             proxy_endpoint: None,
             tracking_id: None,
+            github_token: None,
         };
 
         // Parse token components if it's a structured Copilot token
@@ -63,6 +68,23 @@ impl CopilotToken {
         }
     }
 
+    /// Base URL of the proxy the token was issued for, from its `proxy-ep`.
+    /// Only a plain host name (with an optional port) is accepted, and it is
+    /// always reached over `https://`, so the token is only sent to a server
+    /// whose certificate is valid for that name.
+    pub fn proxy_base_url(&self) -> Option<String> {
+        let endpoint = self.proxy_endpoint.as_deref()?.trim();
+        let endpoint = endpoint.strip_prefix("https://").unwrap_or(endpoint);
+        let url = reqwest::Url::parse(&format!("https://{}", endpoint.trim_end_matches('/'))).ok()?;
+        let plain_host = url.domain().is_some()
+            && url.username().is_empty()
+            && url.password().is_none()
+            && url.path() == "/"
+            && url.query().is_none()
+            && url.fragment().is_none();
+        plain_host.then(|| url.as_str().trim_end_matches('/').to_string())
+    }
+
     pub fn expires_in_minutes(&self) -> Option<u64> {
         if let Some(expires_at) = self.expires_at {
             let current_time = std::time::SystemTime::now()
@@ -81,58 +103,24 @@ impl CopilotToken {
 }
 
 pub struct CopilotTokenStore {
-    token_file: PathBuf,
+    token_file: TokenFile,
 }
 
 impl CopilotTokenStore {
     pub fn new() -> Result<Self> {
-        let codex_home = std::env::var("CODEX_HOME")
-            .map(PathBuf::from)
-            .or_else(|_| {
-                home_dir()
-                    .map(|h| h.join(".codex"))
-                    .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))
-            })
-            .context("Could not determine Codex home directory")?;
-
-        // Ensure the directory exists
-        fs::create_dir_all(&codex_home)
-            .context("Failed to create Codex home directory")?;
-
-        let token_file = codex_home.join("copilot_token.json");
+        let token_file = TokenFile::in_codex_home(COPILOT_TOKEN_FILE)?;
 
         Ok(Self { token_file })
     }
 
     pub fn save_token(&self, token: &CopilotToken) -> Result<()> {
-        let json = serde_json::to_string_pretty(token)
-            .context("Failed to serialize token")?;
-
-        fs::write(&self.token_file, json)
-            .context("Failed to write token file")?;
-
-        // Set restrictive permissions (owner read/write only)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&self.token_file)?.permissions();
-            perms.set_mode(0o600);
-            fs::set_permissions(&self.token_file, perms)?;
-        }
-
-        Ok(())
+        self.token_file.save(token)
     }
 
     pub fn load_token(&self) -> Result<Option<CopilotToken>> {
-        if !self.token_file.exists() {
+        let Some(token) = self.token_file.load::<CopilotToken>()? else {
             return Ok(None);
-        }
-
-        let content = fs::read_to_string(&self.token_file)
-            .context("Failed to read token file")?;
-
-        let token: CopilotToken = serde_json::from_str(&content)
-            .context("Failed to parse token file")?;
+        };
 
         if token.is_expired() {
             // Remove expired token
@@ -144,11 +132,7 @@ impl CopilotTokenStore {
     }
 
     pub fn clear_token(&self) -> Result<()> {
-        if self.token_file.exists() {
-            fs::remove_file(&self.token_file)
-                .context("Failed to remove token file")?;
-        }
-        Ok(())
+        self.token_file.clear()
     }
 
     pub fn get_valid_token(&self) -> Option<String> {
@@ -161,17 +145,6 @@ impl CopilotTokenStore {
         // Fallback to environment variable
         std::env::var("COPILOT_TOKEN").ok()
     }
-
-    pub fn set_env_var(&self) -> Result<bool> {
-        if let Some(token) = self.get_valid_token() {
-            unsafe {
-                std::env::set_var("COPILOT_TOKEN", &token);
-            }
-            Ok(true)
-        } else {
-            Ok(false)
-        }
-    }
 }
 
 impl Default for CopilotTokenStore {
@@ -0,0 +1,186 @@
+//! Passphrase-encrypted copies of the saved credentials, behind
+//! `codex auth export` and `codex auth import`.
+//!
+//! A bundle holds the tokens of `codex copilot auth` and `codex login`, so
+//! that a container or CI runner can use them without running the
+//! interactive sign-in flows. The tokens are serialized as JSON and sealed
+//! with AES-256-GCM under a key derived from the passphrase with Argon2id;
+//! the file is a JSON envelope with the salt, nonce and ciphertext.
+
+use std::path::Path;
+
+use aes_gcm::Aes256Gcm;
+use aes_gcm::Key;
+use aes_gcm::Nonce;
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::KeyInit;
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::chatgpt_auth;
+use crate::chatgpt_auth::ChatGptTokens;
+use crate::copilot_token_store::COPILOT_TOKEN_FILE;
+use crate::copilot_token_store::CopilotToken;
+use crate::token_store::TokenFile;
+
+const VERSION: u32 = 1;
+
+/// The credentials in a bundle.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Credentials {
+    copilot: Option<CopilotToken>,
+    chatgpt: Option<ChatGptTokens>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kdf: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// What a bundle contained; for reporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleContents {
+    pub copilot: bool,
+    pub chatgpt: bool,
+}
+
+/// Encrypt the credentials saved in `codex_home` with `passphrase`. Fails
+/// when there are none to export.
+pub fn export(codex_home: &Path, passphrase: &str) -> Result<(Vec<u8>, BundleContents)> {
+    let credentials = Credentials {
+        copilot: TokenFile::new(codex_home, COPILOT_TOKEN_FILE).load()?,
+        chatgpt: TokenFile::new(codex_home, chatgpt_auth::AUTH_FILE).load()?,
+    };
+    let contents = contents(&credentials);
+    if !contents.copilot && !contents.chatgpt {
+        bail!("no saved credentials; sign in with `codex login` or `codex copilot auth` first");
+    }
+
+    let salt: [u8; 16] = rand::random();
+    let nonce: [u8; 12] = rand::random();
+    let plaintext = serde_json::to_vec(&credentials)?;
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| anyhow::anyhow!("could not encrypt the credentials"))?;
+    let envelope = Envelope {
+        version: VERSION,
+        kdf: "argon2id".to_string(),
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    };
+    Ok((serde_json::to_vec_pretty(&envelope)?, contents))
+}
+
+/// Decrypt `bundle` with `passphrase` and save its credentials in
+/// `codex_home`, replacing the ones saved there.
+pub fn import(codex_home: &Path, bundle: &[u8], passphrase: &str) -> Result<BundleContents> {
+    let envelope: Envelope = serde_json::from_slice(bundle)
+        .context("not a credentials file from `codex auth export`")?;
+    if envelope.version != VERSION || envelope.kdf != "argon2id" {
+        bail!(
+            "unsupported credentials file (version {}, {}); export it again with this version of Codex",
+            envelope.version,
+            envelope.kdf
+        );
+    }
+    let salt = STANDARD.decode(&envelope.salt)?;
+    let nonce = STANDARD.decode(&envelope.nonce)?;
+    let ciphertext = STANDARD.decode(&envelope.ciphertext)?;
+    if nonce.len() != 12 {
+        bail!("corrupted credentials file");
+    }
+    let plaintext = cipher(passphrase, &salt)?
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| anyhow::anyhow!("wrong passphrase, or the file was modified"))?;
+    let credentials: Credentials = serde_json::from_slice(&plaintext)?;
+
+    if let Some(token) = &credentials.copilot {
+        TokenFile::new(codex_home, COPILOT_TOKEN_FILE).save(token)?;
+    }
+    if let Some(tokens) = &credentials.chatgpt {
+        TokenFile::new(codex_home, chatgpt_auth::AUTH_FILE).save(tokens)?;
+    }
+    Ok(contents(&credentials))
+}
+
+fn contents(credentials: &Credentials) -> BundleContents {
+    BundleContents {
+        copilot: credentials.copilot.is_some(),
+        chatgpt: credentials.chatgpt.is_some(),
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm> {
+    if passphrase.is_empty() {
+        bail!("the passphrase must not be empty");
+    }
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("could not derive a key from the passphrase: {e}"))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn round_trips_the_saved_credentials() {
+        let source = TempDir::new().unwrap();
+        let tokens = ChatGptTokens {
+            id_token: "id".to_string(),
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            api_key: Some("sk-test".to_string()),
+            last_refresh: 1,
+        };
+        TokenFile::new(source.path(), chatgpt_auth::AUTH_FILE)
+            .save(&tokens)
+            .unwrap();
+
+        let (bundle, exported) = export(source.path(), "correct horse").unwrap();
+        assert_eq!(
+            exported,
+            BundleContents {
+                copilot: false,
+                chatgpt: true,
+            }
+        );
+        assert!(!String::from_utf8_lossy(&bundle).contains("sk-test"));
+
+        let target = TempDir::new().unwrap();
+        let err = import(target.path(), &bundle, "wrong horse").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"), "{err}");
+
+        assert_eq!(
+            import(target.path(), &bundle, "correct horse").unwrap(),
+            exported
+        );
+        let imported: ChatGptTokens = TokenFile::new(target.path(), chatgpt_auth::AUTH_FILE)
+            .load()
+            .unwrap()
+            .unwrap();
+        assert_eq!(imported, tokens);
+    }
+
+    #[test]
+    fn refuses_to_export_nothing() {
+        let codex_home = TempDir::new().unwrap();
+        assert!(export(codex_home.path(), "passphrase").is_err());
+    }
+}
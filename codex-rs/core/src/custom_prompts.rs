@@ -0,0 +1,147 @@
+//! User-defined prompt commands.
+//!
+//! Every `*.md` file in `$CODEX_HOME/prompts` becomes a named prompt: the file
+//! stem is the name (`review.md` → `review`) and the contents are the prompt
+//! template. Front-ends expose them as `/review` in the TUI and
+//! `codex run review` on the command line.
+//!
+//! Two placeholders are expanded before the prompt is sent to the model:
+//!
+//! - `$ARGUMENTS` — everything the user typed after the prompt name.
+//! - `$FILE` — the contents of the file named by the first argument, resolved
+//!   against the working directory.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Name of the directory under `CODEX_HOME` that holds prompt files.
+const PROMPTS_DIR_NAME: &str = "prompts";
+
+const ARGUMENTS_PLACEHOLDER: &str = "$ARGUMENTS";
+const FILE_PLACEHOLDER: &str = "$FILE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomPrompt {
+    /// Name used to invoke the prompt (the file stem).
+    pub name: String,
+    pub path: PathBuf,
+    /// Raw template, before placeholder expansion.
+    pub content: String,
+}
+
+impl CustomPrompt {
+    /// One-line summary for menus: the first non-empty line of the template
+    /// with any Markdown heading markers removed.
+    pub fn description(&self) -> String {
+        self.content
+            .lines()
+            .map(|l| l.trim_start_matches('#').trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("custom prompt")
+            .to_string()
+    }
+
+    /// Expand `$ARGUMENTS` and `$FILE` in the template. `$FILE` is only read
+    /// when the template actually uses it.
+    pub fn expand(&self, args: &str, cwd: &Path) -> std::io::Result<String> {
+        let args = args.trim();
+        let mut expanded = self.content.replace(ARGUMENTS_PLACEHOLDER, args);
+
+        if expanded.contains(FILE_PLACEHOLDER) {
+            let Some(first) = args.split_whitespace().next() else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("prompt `{}` expects a file argument", self.name),
+                ));
+            };
+            let path = cwd.join(first);
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                std::io::Error::new(e.kind(), format!("failed to read {}: {e}", path.display()))
+            })?;
+            expanded = expanded.replace(FILE_PLACEHOLDER, &contents);
+        }
+
+        Ok(expanded)
+    }
+}
+
+/// Directory that holds the user's prompt files.
+pub fn prompts_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join(PROMPTS_DIR_NAME)
+}
+
+/// Load every prompt in `dir`, sorted by name. A missing directory yields an
+/// empty list; unreadable files are skipped.
+pub fn discover_prompts(dir: &Path) -> Vec<CustomPrompt> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut prompts: Vec<CustomPrompt> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return None;
+            }
+            let content = std::fs::read_to_string(&path).ok()?;
+            Some(CustomPrompt {
+                name,
+                path,
+                content,
+            })
+        })
+        .collect();
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    prompts
+}
+
+/// Find the prompt called `name` in `dir`.
+pub fn find_prompt(dir: &Path, name: &str) -> Option<CustomPrompt> {
+    discover_prompts(dir).into_iter().find(|p| p.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn discovers_markdown_prompts_sorted_by_name() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("review.md"),
+            "# Review code\nReview $ARGUMENTS",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("explain.md"), "Explain").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let prompts = discover_prompts(dir.path());
+        let names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["explain", "review"]);
+        assert_eq!(prompts[1].description(), "Review code");
+        assert!(discover_prompts(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn expands_arguments_and_file() {
+        let cwd = TempDir::new().unwrap();
+        std::fs::write(cwd.path().join("main.rs"), "fn main() {}").unwrap();
+        let prompt = CustomPrompt {
+            name: "explain".to_string(),
+            path: PathBuf::from("explain.md"),
+            content: "Explain $ARGUMENTS:\n$FILE".to_string(),
+        };
+
+        assert_eq!(
+            prompt.expand(" main.rs ", cwd.path()).unwrap(),
+            "Explain main.rs:\nfn main() {}"
+        );
+        assert!(prompt.expand("", cwd.path()).is_err());
+        assert!(prompt.expand("missing.rs", cwd.path()).is_err());
+    }
+}
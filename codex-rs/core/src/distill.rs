@@ -0,0 +1,200 @@
+//! `codex distill`: turn what a finished session learned about its project
+//! into additions to the project's `AGENTS.md`.
+//!
+//! The session's transcript and the current `AGENTS.md` are sent to the
+//! configured model, which replies with the Markdown to append. The caller
+//! shows the result as a diff and writes it once the user accepts it.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use anyhow::Result;
+use similar::TextDiff;
+
+use crate::auth::AuthManager;
+use crate::client::ModelClient;
+use crate::client_common::Prompt;
+use crate::config::Config;
+use crate::http_client::create_client;
+use crate::http_client::is_local_url;
+use crate::models::ContentItem;
+use crate::models::ResponseItem;
+use crate::project_init::project_root;
+use crate::rollout::read_rollout;
+use crate::session_export::ExportFormat;
+use crate::session_export::Transcript;
+
+const DISTILL_PROMPT: &str = include_str!("../prompt_for_distill.md");
+
+/// Longer transcripts are cut from the start, since the commands that
+/// finally worked tend to come late in a session.
+const MAX_TRANSCRIPT_CHARS: usize = 200_000;
+
+/// What the model answers when the session taught nothing new.
+const NOTHING_TO_ADD: &str = "NONE";
+
+/// Proposed new contents of a project's `AGENTS.md`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Proposal {
+    pub path: PathBuf,
+    /// Empty when the file does not exist yet.
+    pub current: String,
+    pub proposed: String,
+}
+
+impl Proposal {
+    /// Unified diff from the current contents to the proposed ones.
+    pub fn diff(&self) -> String {
+        TextDiff::from_lines(&self.current, &self.proposed)
+            .unified_diff()
+            .header("AGENTS.md", "AGENTS.md")
+            .to_string()
+    }
+
+    pub fn apply(&self) -> std::io::Result<()> {
+        std::fs::write(&self.path, &self.proposed)
+    }
+}
+
+/// Ask the model what the session recorded in `rollout` adds to the
+/// `AGENTS.md` at the root of its project. Returns `None` when it has nothing
+/// to add.
+pub async fn propose(config: &Config, rollout: &Path) -> Result<Option<Proposal>> {
+    let (meta, _) = read_rollout(rollout)?;
+    let root = project_root(meta.cwd.as_deref().unwrap_or(&config.cwd));
+    let path = root.join("AGENTS.md");
+    let current = match std::fs::read_to_string(&path) {
+        Ok(current) => current,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let transcript = Transcript::load(rollout)?.render(ExportFormat::Markdown);
+
+    let provider = &config.model_provider;
+    if config.offline && !is_local_url(&provider.base_url) {
+        anyhow::bail!(
+            "offline mode only allows local model providers, but {} is at {}",
+            provider.name,
+            provider.base_url
+        );
+    }
+    let http_client = create_client(config)?;
+    let client = ModelClient::new(
+        &config.model,
+        provider.clone(),
+        config.model_reasoning_effort,
+        config.model_reasoning_summary,
+        config.sampling,
+        http_client.clone(),
+        Arc::new(AuthManager::new(&config.codex_home, http_client)),
+    );
+    let prompt = Prompt {
+        input: vec![ResponseItem::Message {
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: distill_request(&transcript, &current),
+            }],
+        }],
+        ..Default::default()
+    };
+    let reply = client.complete(&prompt).await?;
+
+    Ok(additions(&reply).map(|additions| Proposal {
+        path,
+        proposed: append(&current, &additions),
+        current,
+    }))
+}
+
+fn distill_request(transcript: &str, agents_md: &str) -> String {
+    let transcript = match transcript.char_indices().rev().nth(MAX_TRANSCRIPT_CHARS) {
+        Some((start, _)) => format!(
+            "(The start of the session is omitted.)\n\n{}",
+            &transcript[start..]
+        ),
+        None => transcript.to_string(),
+    };
+    let agents_md = if agents_md.trim().is_empty() {
+        "(AGENTS.md does not exist yet.)"
+    } else {
+        agents_md
+    };
+    format!(
+        "{DISTILL_PROMPT}\n<transcript>\n{transcript}\n</transcript>\n\n<agents_md>\n{agents_md}\n</agents_md>"
+    )
+}
+
+/// The Markdown to append from the model's `reply`, without a code fence
+/// around it.
+fn additions(reply: &str) -> Option<String> {
+    let mut text = reply.trim();
+    if let Some(fenced) = text.strip_prefix("```") {
+        // Drop the info string (e.g. `markdown`) along with the fence.
+        let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+        text = body.trim_end().strip_suffix("```").unwrap_or(body).trim();
+    }
+    (!text.is_empty() && text != NOTHING_TO_ADD).then(|| text.to_string())
+}
+
+fn append(current: &str, additions: &str) -> String {
+    let current = current.trim_end();
+    if current.is_empty() {
+        format!("{additions}\n")
+    } else {
+        format!("{current}\n\n{additions}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_additions_from_the_reply() {
+        assert_eq!(additions("NONE\n"), None);
+        assert_eq!(additions("  "), None);
+        assert_eq!(
+            additions("## Testing\n\n- Run `just test`.").as_deref(),
+            Some("## Testing\n\n- Run `just test`.")
+        );
+        assert_eq!(
+            additions("```markdown\n## Testing\n\n- Run `just test`.\n```\n").as_deref(),
+            Some("## Testing\n\n- Run `just test`.")
+        );
+    }
+
+    #[test]
+    fn appends_to_the_current_file() {
+        let proposal = Proposal {
+            path: PathBuf::from("AGENTS.md"),
+            current: "# Project\n\n- Use tabs.\n".to_string(),
+            proposed: append(
+                "# Project\n\n- Use tabs.\n",
+                "## Testing\n\n- Run `just test`.",
+            ),
+        };
+        assert_eq!(
+            proposal.proposed,
+            "# Project\n\n- Use tabs.\n\n## Testing\n\n- Run `just test`.\n"
+        );
+        assert_eq!(
+            proposal.diff(),
+            "--- AGENTS.md\n+++ AGENTS.md\n@@ -1,3 +1,7 @@\n # Project\n \n - Use tabs.\n+\n+## Testing\n+\n+- Run `just test`.\n"
+        );
+        assert_eq!(append("", "## Testing"), "## Testing\n");
+    }
+
+    #[test]
+    fn keeps_the_end_of_long_transcripts() {
+        let transcript = format!("{}the end", "x".repeat(MAX_TRANSCRIPT_CHARS));
+        let request = distill_request(&transcript, "");
+        assert!(request.contains("(The start of the session is omitted.)"));
+        assert!(request.contains("the end\n</transcript>"));
+        assert!(request.contains("(AGENTS.md does not exist yet.)"));
+        assert!(request.len() < MAX_TRANSCRIPT_CHARS + DISTILL_PROMPT.len() + 200);
+    }
+}
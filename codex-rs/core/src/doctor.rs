@@ -0,0 +1,486 @@
+//! Health checks behind `codex doctor`.
+//!
+//! Each check reports whether one thing Codex depends on works: the config,
+//! the model provider's API key and endpoint, the Copilot token, the sandbox
+//! backend and git. Failed checks carry a hint on how to fix them. Printing
+//! is left to the caller.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::ModelProviderInfo;
+use crate::auth::AuthManager;
+use crate::config::Config;
+use crate::copilot_token_store::CopilotTokenStore;
+use crate::error::CodexErr;
+use crate::http_client::create_client;
+use crate::util::is_inside_git_repo;
+
+/// How long to wait for a provider to answer before calling it unreachable.
+pub(crate) const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Provider name that the Copilot-specific checks apply to, as in
+/// `chat_completions`.
+const COPILOT_PROVIDER_NAME: &str = "GitHub Copilot";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// Works, but probably not the way the user wants it to.
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.status {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        write!(f, "[{label}] {}: {}", self.name, self.detail)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n       hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every check. `config` is the result of loading the config, so that a
+/// broken config is reported instead of stopping the run; the checks that
+/// need a config are skipped in that case.
+pub async fn run_checks(
+    config: std::io::Result<Config>,
+    codex_linux_sandbox_exe: Option<&Path>,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let config = match config {
+        Ok(config) => {
+            checks.push(Check::pass(
+                "config",
+                format!(
+                    "{} loaded (model `{}` from {})",
+                    config.codex_home.join("config.toml").display(),
+                    config.model,
+                    config.model_provider.name
+                ),
+            ));
+            Some(config)
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "config",
+                e.to_string(),
+                "fix config.toml or the `-c` overrides; the other provider checks are skipped",
+            ));
+            None
+        }
+    };
+
+    if let Some(config) = &config {
+        checks.extend(check_provider(config).await);
+    }
+    checks.push(check_sandbox(codex_linux_sandbox_exe));
+    checks.extend(check_git(config.as_ref()).await);
+    checks
+}
+
+async fn check_provider(config: &Config) -> Vec<Check> {
+    let provider = &config.model_provider;
+    let mut checks = Vec::new();
+    if provider.name == COPILOT_PROVIDER_NAME {
+        checks.extend(check_copilot_token(provider));
+    }
+
+    let client = create_client(config);
+    let auth = AuthManager::new(
+        &config.codex_home,
+        client.as_ref().cloned().unwrap_or_default(),
+    );
+    let mut base_url = provider.base_url.clone();
+    let api_key = match auth.get_credentials(provider).await {
+        Ok(Some(credentials)) => {
+            checks.push(Check::pass(
+                "api key",
+                format!("taken from {}", credentials.source),
+            ));
+            if let Some(url) = credentials.base_url {
+                base_url = url;
+            }
+            Some(credentials.token)
+        }
+        Ok(None) => {
+            checks.push(Check::pass(
+                "api key",
+                format!("{} does not need one", provider.name),
+            ));
+            None
+        }
+        Err(CodexErr::EnvVar(e)) => {
+            let hint = e.instructions.clone().unwrap_or_else(|| {
+                format!("export `{}` with your {} API key", e.var, provider.name)
+            });
+            checks.push(Check::fail(
+                "api key",
+                format!("`{}` is not set", e.var),
+                hint,
+            ));
+            None
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "api key",
+                e.to_string(),
+                "check the provider's `env_key`",
+            ));
+            None
+        }
+    };
+
+    match client {
+        Ok(client) => {
+            checks.push(probe(&client, "provider", &base_url, api_key.as_deref(), provider).await);
+        }
+        Err(e) => checks.push(Check::fail(
+            "provider",
+            format!("cannot build the HTTP client: {e}"),
+            "fix `http_proxy`, `https_proxy` or `extra_ca_certs` in config.toml",
+        )),
+    }
+    checks
+}
+
+/// Check the persisted Copilot token, and the proxy endpoint it was issued
+/// for, which chat requests are sent to.
+fn check_copilot_token(provider: &ModelProviderInfo) -> Vec<Check> {
+    const REAUTH: &str = "run `codex copilot auth`";
+    let token = match CopilotTokenStore::new().map(|store| store.load_token()) {
+        Ok(Ok(Some(token))) => token,
+        Ok(Ok(None)) if std::env::var("COPILOT_TOKEN").is_ok_and(|v| !v.trim().is_empty()) => {
+            return vec![Check::pass(
+                "copilot token",
+                "taken from `COPILOT_TOKEN`; no saved token to check",
+            )];
+        }
+        // `load_token` also deletes expired tokens, so these end up here.
+        Ok(Ok(None)) => {
+            return vec![Check::fail(
+                "copilot token",
+                "no saved token, or it has expired",
+                REAUTH,
+            )];
+        }
+        Ok(Err(e)) | Err(e) => {
+            return vec![Check::fail(
+                "copilot token",
+                format!("cannot read the saved token: {e}"),
+                REAUTH,
+            )];
+        }
+    };
+
+    let mut checks = vec![match token.expires_in_minutes() {
+        Some(minutes) => Check::pass(
+            "copilot token",
+            format!("valid for another {minutes} minutes"),
+        ),
+        None => Check::pass("copilot token", "valid, without an expiry"),
+    }];
+    if let Some(endpoint) = &token.proxy_endpoint {
+        checks.push(match token.proxy_base_url() {
+            Some(url) => Check::pass("copilot proxy", format!("chat requests go to {url}")),
+            None => Check::warn(
+                "copilot proxy",
+                format!(
+                    "ignoring the token's proxy endpoint `{endpoint}`, which is not a host name"
+                ),
+                format!(
+                    "requests go to {} instead; run `codex copilot auth` again if they fail",
+                    provider.base_url
+                ),
+            ),
+        });
+    }
+    checks
+}
+
+/// Send `GET {base_url}/models` to see whether the provider answers and
+/// accepts `api_key`.
+async fn probe(
+    client: &reqwest::Client,
+    name: &str,
+    base_url: &str,
+    api_key: Option<&str>,
+    provider: &ModelProviderInfo,
+) -> Check {
+    let url = models_url(base_url);
+    let request = models_request(client, base_url, api_key, provider);
+    match request.send().await {
+        Ok(response) => match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN if api_key.is_some() => {
+                let hint = if provider.name == COPILOT_PROVIDER_NAME {
+                    "the Copilot token was rejected; run `codex copilot auth` again".to_string()
+                } else {
+                    format!(
+                        "replace the key in `{}` with a valid one",
+                        provider.env_key.as_deref().unwrap_or_default()
+                    )
+                };
+                Check::fail(
+                    name,
+                    format!("{base_url} rejected the API key ({})", response.status()),
+                    hint,
+                )
+            }
+            status if status.is_success() => Check::pass(name, format!("{base_url} is reachable")),
+            // Not every provider lists its models; any answer shows that it
+            // can be reached.
+            status => Check::pass(name, format!("{base_url} is reachable ({status})")),
+        },
+        Err(e) => {
+            let hint = if crate::http_client::is_local_url(base_url) {
+                "is the local model server running?"
+            } else {
+                "check the network connection, `http_proxy`/`https_proxy` and `extra_ca_certs`"
+            };
+            Check::fail(name, format!("cannot reach {url}: {e}"), hint)
+        }
+    }
+}
+
+fn models_url(base_url: &str) -> String {
+    format!("{}/models", base_url.trim_end_matches('/'))
+}
+
+/// `GET {base_url}/models`, the cheapest request that still needs a valid
+/// key, with the headers `provider` expects.
+pub(crate) fn models_request(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: Option<&str>,
+    provider: &ModelProviderInfo,
+) -> reqwest::RequestBuilder {
+    let mut request = client.get(models_url(base_url)).timeout(PROBE_TIMEOUT);
+    if let Some(api_key) = api_key {
+        request = request.bearer_auth(api_key);
+    }
+    if provider.name == COPILOT_PROVIDER_NAME {
+        request = request
+            .header(
+                "Editor-Version",
+                format!("Codex/{}", env!("CARGO_PKG_VERSION")),
+            )
+            .header("Copilot-Integration-Id", "vscode-chat");
+    }
+    request
+}
+
+#[cfg(target_os = "macos")]
+fn check_sandbox(_codex_linux_sandbox_exe: Option<&Path>) -> Check {
+    let exe = crate::exec::MACOS_PATH_TO_SEATBELT_EXECUTABLE;
+    if Path::new(exe).exists() {
+        Check::pass("sandbox", format!("seatbelt ({exe})"))
+    } else {
+        Check::fail(
+            "sandbox",
+            format!("{exe} is missing, so commands cannot be sandboxed"),
+            "sandbox-exec ships with macOS; restore it or run with approvals for every command",
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check_sandbox(codex_linux_sandbox_exe: Option<&Path>) -> Check {
+    let Some(exe) = codex_linux_sandbox_exe else {
+        return Check::fail(
+            "sandbox",
+            "the Landlock helper is unavailable",
+            "run Codex through the `codex` executable, which contains the helper",
+        );
+    };
+    // The active Linux security modules, e.g. `lockdown,capability,landlock`.
+    match std::fs::read_to_string("/sys/kernel/security/lsm") {
+        Ok(lsms) if lsms.trim().split(',').any(|lsm| lsm == "landlock") => Check::pass(
+            "sandbox",
+            format!("landlock and seccomp ({})", exe.display()),
+        ),
+        Ok(lsms) => Check::fail(
+            "sandbox",
+            format!(
+                "Landlock is not enabled in this kernel (active: {})",
+                lsms.trim()
+            ),
+            "use Linux 5.13 or later and add `landlock` to the `lsm=` boot parameter",
+        ),
+        Err(e) => Check::warn(
+            "sandbox",
+            format!("cannot tell whether Landlock is enabled: {e}"),
+            "try `codex debug landlock -- true` to see whether sandboxed commands run",
+        ),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn check_sandbox(_codex_linux_sandbox_exe: Option<&Path>) -> Check {
+    Check::warn(
+        "sandbox",
+        "no sandbox backend on this platform, so commands run unsandboxed",
+        "keep an approval policy that asks before running commands",
+    )
+}
+
+async fn check_git(config: Option<&Config>) -> Vec<Check> {
+    let output = tokio::process::Command::new("git")
+        .arg("--version")
+        .output()
+        .await;
+    let mut checks = vec![match output {
+        Ok(output) if output.status.success() => Check::pass(
+            "git",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        Ok(output) => Check::fail(
+            "git",
+            format!("`git --version` exited with {}", output.status),
+            "reinstall git",
+        ),
+        Err(e) => Check::fail(
+            "git",
+            format!("cannot run git: {e}"),
+            "install git and make sure it is on PATH",
+        ),
+    }];
+    if let Some(config) = config.filter(|config| !is_inside_git_repo(config)) {
+        checks.push(Check::warn(
+            "git repo",
+            format!("{} is not inside a Git repository", config.cwd.display()),
+            "run Codex from a repository, or pass `--skip-git-repo-check` to `codex exec`",
+        ));
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::WireApi;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    fn provider(base_url: &str) -> ModelProviderInfo {
+        ModelProviderInfo {
+            name: "Test".into(),
+            base_url: base_url.into(),
+            env_key: Some("TEST_API_KEY".into()),
+            env_key_instructions: None,
+            wire_api: WireApi::Chat,
+            supports_images: true,
+            supports_structured_output: true,
+            rate_limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_tells_rejected_keys_from_unreachable_providers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", "Bearer good"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let base_url = format!("{}/v1", server.uri());
+        let provider = provider(&base_url);
+        let client = reqwest::Client::new();
+
+        let check = probe(&client, "provider", &base_url, Some("good"), &provider).await;
+        assert_eq!(check.status, Status::Pass);
+
+        let check = probe(&client, "provider", &base_url, Some("bad"), &provider).await;
+        assert_eq!(check.status, Status::Fail);
+        assert_eq!(
+            check.hint.as_deref(),
+            Some("replace the key in `TEST_API_KEY` with a valid one")
+        );
+
+        // Without a key there is nothing to reject.
+        let check = probe(&client, "provider", &base_url, None, &provider).await;
+        assert_eq!(check.status, Status::Pass);
+
+        // A port nothing listens on: wiremock keeps dropped servers running
+        // for reuse.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let base_url = format!("http://127.0.0.1:{port}/v1");
+        let check = probe(&client, "provider", &base_url, Some("good"), &provider).await;
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.detail.starts_with("cannot reach"), "{}", check.detail);
+    }
+
+    #[test]
+    fn failed_checks_print_their_hint() {
+        let check = Check::fail("git", "cannot run git", "install git");
+        assert_eq!(
+            check.to_string(),
+            "[FAIL] git: cannot run git\n       hint: install git"
+        );
+        assert_eq!(
+            Check::pass("git", "git version 2.45.0").to_string(),
+            "[pass] git: git version 2.45.0"
+        );
+    }
+}
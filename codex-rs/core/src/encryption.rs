@@ -0,0 +1,207 @@
+//! Encryption at rest of session rollouts and audit logs, with
+//! `encrypt_sessions`.
+//!
+//! Files are written as a sequence of records, one per write, each sealed
+//! with AES-256-GCM under a fresh random nonce: the length of the record as
+//! a little-endian `u32`, the 12-byte nonce, then the ciphertext. Sealing
+//! each write on its own keeps the files appendable and readable up to the
+//! last complete record. The key is generated on first use and kept in the
+//! OS keychain; `CODEX_SESSION_KEY` (32 bytes, base64) takes precedence, for
+//! machines without one.
+
+use std::io;
+use std::io::Read;
+use std::sync::OnceLock;
+
+use aes_gcm::Aes256Gcm;
+use aes_gcm::Key;
+use aes_gcm::Nonce;
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::KeyInit;
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+/// Appended to the name of encrypted files.
+pub(crate) const ENCRYPTED_SUFFIX: &str = ".enc";
+
+const KEY_ENV_VAR: &str = "CODEX_SESSION_KEY";
+
+/// Where the key is kept in the OS keychain.
+const KEYCHAIN_SERVICE: &str = "codex";
+const KEYCHAIN_ACCOUNT: &str = "session-encryption-key";
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone)]
+pub(crate) struct SessionKey {
+    cipher: Aes256Gcm,
+}
+
+impl SessionKey {
+    fn new(bytes: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(bytes)),
+        }
+    }
+
+    fn decode(encoded: &str) -> anyhow::Result<Self> {
+        let bytes: [u8; 32] = STANDARD
+            .decode(encoded.trim())?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("the key must be 32 bytes"))?;
+        Ok(Self::new(&bytes))
+    }
+
+    /// `plaintext` as a record to append to an encrypted file.
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| io::Error::other("failed to encrypt"))?;
+        let len = u32::try_from(NONCE_LEN + ciphertext.len())
+            .map_err(|_| io::Error::other("record too large to encrypt"))?;
+        let mut record = Vec::with_capacity(4 + len as usize);
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&nonce);
+        record.extend_from_slice(&ciphertext);
+        Ok(record)
+    }
+
+    /// The plaintext of a record, without its length.
+    fn open(&self, record: &[u8]) -> io::Result<Vec<u8>> {
+        let (nonce, ciphertext) = record.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "failed to decrypt: wrong key, or the file was modified",
+                )
+            })
+    }
+}
+
+/// The key to encrypt sessions with, read from the keychain once per
+/// process.
+pub(crate) fn session_key() -> io::Result<SessionKey> {
+    static KEY: OnceLock<Result<SessionKey, String>> = OnceLock::new();
+    KEY.get_or_init(|| load_or_create_key().map_err(|e| format!("{e:#}")))
+        .clone()
+        .map_err(|e| io::Error::other(format!("no session encryption key: {e}")))
+}
+
+fn load_or_create_key() -> anyhow::Result<SessionKey> {
+    if let Ok(encoded) = std::env::var(KEY_ENV_VAR) {
+        return SessionKey::decode(&encoded).with_context(|| format!("invalid {KEY_ENV_VAR}"));
+    }
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)?;
+    match entry.get_password() {
+        Ok(encoded) => SessionKey::decode(&encoded).context("invalid key in the keychain"),
+        Err(keyring::Error::NoEntry) => {
+            let bytes: [u8; 32] = rand::random();
+            entry
+                .set_password(&STANDARD.encode(bytes))
+                .context("failed to save the key in the keychain")?;
+            Ok(SessionKey::new(&bytes))
+        }
+        Err(e) => Err(e).context("failed to read the key from the keychain"),
+    }
+}
+
+/// Reads the plaintext of an encrypted file. A record cut short, as left by
+/// a process that died mid-write, is an `UnexpectedEof` error.
+pub(crate) struct DecryptingReader<R> {
+    inner: R,
+    key: SessionKey,
+    plaintext: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    pub(crate) fn new(inner: R, key: SessionKey) -> Self {
+        Self {
+            inner,
+            key,
+            plaintext: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plaintext.len() {
+            let mut len = [0; 4];
+            if self.inner.read(&mut len[..1])? == 0 {
+                return Ok(0);
+            }
+            self.inner.read_exact(&mut len[1..])?;
+            let len = u32::from_le_bytes(len) as usize;
+            if len < NONCE_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "corrupted encrypted record",
+                ));
+            }
+            let mut record = vec![0; len];
+            self.inner.read_exact(&mut record)?;
+            self.plaintext = self.key.open(&record)?;
+            self.pos = 0;
+        }
+        let n = (&self.plaintext[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn read_all(bytes: &[u8], key: &SessionKey) -> io::Result<String> {
+        let mut text = String::new();
+        DecryptingReader::new(bytes, key.clone()).read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let key = SessionKey::new(&[7; 32]);
+        let mut file = key.seal(b"{\"a\":1}\n").unwrap();
+        file.extend(key.seal(b"").unwrap());
+        file.extend(key.seal(b"{\"b\":2}\n").unwrap());
+        assert!(!file.windows(5).any(|w| w == b"\"a\":1"));
+        assert_eq!(read_all(&file, &key).unwrap(), "{\"a\":1}\n{\"b\":2}\n");
+
+        let other = SessionKey::new(&[8; 32]);
+        assert_eq!(
+            read_all(&file, &other).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn a_torn_record_ends_the_plaintext() {
+        let key = SessionKey::new(&[7; 32]);
+        let mut file = key.seal(b"complete\n").unwrap();
+        file.extend(&key.seal(b"torn\n").unwrap()[..10]);
+        let mut reader = DecryptingReader::new(file.as_slice(), key);
+        let mut complete = [0; 9];
+        reader.read_exact(&mut complete).unwrap();
+        assert_eq!(&complete, b"complete\n");
+        assert_eq!(
+            reader.read(&mut [0; 8]).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn keys_are_decoded_from_base64() {
+        assert!(SessionKey::decode(&STANDARD.encode([1; 32])).is_ok());
+        assert!(SessionKey::decode(&STANDARD.encode([1; 16])).is_err());
+    }
+}
@@ -22,6 +22,11 @@ pub enum SandboxErr {
     #[error("seccomp backend error")]
     SeccompBackend(#[from] seccompiler::BackendError),
 
+    /// Seccomp filters are not built for this architecture
+    #[cfg(target_os = "linux")]
+    #[error("seccomp filters are not supported on {0}")]
+    SeccompUnsupportedArch(&'static str),
+
     /// Command timed out
     #[error("command timed out")]
     Timeout,
@@ -74,6 +79,17 @@ pub enum CodexErr {
     #[error("sandbox error: {0}")]
     Sandbox(#[from] SandboxErr),
 
+    /// The user attached images but the configured provider cannot accept
+    /// them.
+    #[error(
+        "{0} does not support image inputs; remove the attached image(s) or switch to a vision-capable provider"
+    )]
+    ImageInputUnsupported(String),
+
+    /// `CODEX_REPLAY` has no recorded response left for a model request.
+    #[error("no recorded model responses left in {0}")]
+    ReplayExhausted(String),
+
     #[error("codex-linux-sandbox was required but not provided")]
     LandlockSandboxExecutableNotProvided,
 
@@ -0,0 +1,136 @@
+//! Event sink: headless runs POST every protocol [`Event`] to `event_sink`
+//! as JSON, one request per event and in the order they happened, so that a
+//! dashboard can follow long-running jobs as they go.
+//!
+//! Delivery is best effort. Events are posted in the background, a failed
+//! request is not retried, and the run goes on when the endpoint is down.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::Url;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::error;
+
+use crate::config::Config;
+use crate::http_client::create_client;
+use crate::http_client::is_local_url;
+use crate::protocol::Event;
+
+/// A request to the sink that takes longer is given up on.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long [`EventSink::flush`] waits for the events still queued.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Posts the events it is sent to an HTTP endpoint.
+pub struct EventSink {
+    tx: mpsc::UnboundedSender<Event>,
+    worker: JoinHandle<()>,
+}
+
+impl EventSink {
+    /// Start posting to `config.event_sink`, or `None` when it is not set.
+    /// Fails on an invalid URL, or one off this machine in offline mode.
+    pub fn start(config: &Config) -> anyhow::Result<Option<Self>> {
+        let Some(url) = &config.event_sink else {
+            return Ok(None);
+        };
+        let url = Url::parse(url).with_context(|| format!("invalid event sink URL `{url}`"))?;
+        if config.offline && !is_local_url(url.as_str()) {
+            anyhow::bail!("offline mode only allows an event sink on this machine, not {url}");
+        }
+        Ok(Some(Self::spawn(create_client(config)?, url)))
+    }
+
+    fn spawn(client: reqwest::Client, url: Url) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        let worker = tokio::spawn(async move {
+            let mut failing = false;
+            while let Some(event) = rx.recv().await {
+                let result = client
+                    .post(url.clone())
+                    .timeout(REQUEST_TIMEOUT)
+                    .json(&event)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                match result {
+                    Ok(_) => failing = false,
+                    // Once per outage rather than for every event.
+                    Err(e) if !failing => {
+                        failing = true;
+                        error!("failed to post events to the event sink {url}: {e}");
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+        Self { tx, worker }
+    }
+
+    pub fn send(&self, event: &Event) {
+        // The worker only stops once `tx` is dropped.
+        let _ = self.tx.send(event.clone());
+    }
+
+    /// Wait, for a while, until the events sent so far have been posted.
+    pub async fn flush(self) {
+        let Self { tx, worker } = self;
+        drop(tx);
+        if tokio::time::timeout(FLUSH_TIMEOUT, worker).await.is_err() {
+            error!("gave up posting the remaining events to the event sink");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::protocol::EventMsg;
+    use crate::protocol::TaskCompleteEvent;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    #[tokio::test]
+    async fn posts_each_event_in_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/events"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&format!("{}/events", server.uri())).unwrap();
+        let sink = EventSink::spawn(reqwest::Client::new(), url);
+        for id in ["1", "2"] {
+            sink.send(&Event {
+                id: id.to_string(),
+                msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                    last_agent_message: None,
+                    stats: None,
+                }),
+            });
+        }
+        sink.flush().await;
+
+        let ids: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| {
+                let event: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                event["id"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+}
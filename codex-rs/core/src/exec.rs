@@ -18,6 +18,8 @@ use tokio::process::Child;
 use tokio::process::Command;
 use tokio::sync::Notify;
 
+use crate::config_types::LinuxSandbox;
+use crate::config_types::MacosSeatbelt;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
@@ -42,7 +44,7 @@ const MACOS_SEATBELT_BASE_POLICY: &str = include_str!("seatbelt_base_policy.sbpl
 /// to defend against an attacker trying to inject a malicious version on the
 /// PATH. If /usr/bin/sandbox-exec has been tampered with, then the attacker
 /// already has root access.
-const MACOS_PATH_TO_SEATBELT_EXECUTABLE: &str = "/usr/bin/sandbox-exec";
+pub(crate) const MACOS_PATH_TO_SEATBELT_EXECUTABLE: &str = "/usr/bin/sandbox-exec";
 
 /// Experimental environment variable that will be set to some non-empty value
 /// if both of the following are true:
@@ -79,6 +81,8 @@ pub async fn process_exec_tool_call(
     ctrl_c: Arc<Notify>,
     sandbox_policy: &SandboxPolicy,
     codex_linux_sandbox_exe: &Option<PathBuf>,
+    linux_sandbox: &LinuxSandbox,
+    macos_seatbelt: &MacosSeatbelt,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
 
@@ -94,6 +98,7 @@ pub async fn process_exec_tool_call(
             let child = spawn_command_under_seatbelt(
                 command,
                 sandbox_policy,
+                macos_seatbelt,
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
@@ -116,6 +121,7 @@ pub async fn process_exec_tool_call(
                 codex_linux_sandbox_exe,
                 command,
                 sandbox_policy,
+                linux_sandbox,
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
@@ -169,11 +175,12 @@ pub async fn process_exec_tool_call(
 pub async fn spawn_command_under_seatbelt(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    macos_seatbelt: &MacosSeatbelt,
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
-    let args = create_seatbelt_command_args(command, sandbox_policy, &cwd);
+    let args = create_seatbelt_command_args(command, sandbox_policy, macos_seatbelt, &cwd);
     let arg0 = None;
     spawn_child_async(
         PathBuf::from(MACOS_PATH_TO_SEATBELT_EXECUTABLE),
@@ -198,6 +205,7 @@ pub async fn spawn_command_under_linux_sandbox<P>(
     codex_linux_sandbox_exe: P,
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    linux_sandbox: &LinuxSandbox,
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
@@ -205,7 +213,7 @@ pub async fn spawn_command_under_linux_sandbox<P>(
 where
     P: AsRef<Path>,
 {
-    let args = create_linux_sandbox_command_args(command, sandbox_policy, &cwd);
+    let args = create_linux_sandbox_command_args(command, sandbox_policy, linux_sandbox, &cwd);
     let arg0 = Some("codex-linux-sandbox");
     spawn_child_async(
         codex_linux_sandbox_exe.as_ref().to_path_buf(),
@@ -223,6 +231,7 @@ where
 fn create_linux_sandbox_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    linux_sandbox: &LinuxSandbox,
     cwd: &Path,
 ) -> Vec<String> {
     let mut linux_cmd: Vec<String> = vec![];
@@ -233,6 +242,12 @@ fn create_linux_sandbox_command_args(
     if sandbox_policy.has_full_disk_read_access() {
         linux_cmd.extend(["-s", "disk-full-read-access"].map(String::from));
     }
+    for root in sandbox_policy.get_readable_roots() {
+        linux_cmd.extend([
+            "-s".to_string(),
+            format!("disk-read-folder={}", root.to_string_lossy()),
+        ]);
+    }
 
     if sandbox_policy.has_full_disk_write_access() {
         linux_cmd.extend(["-s", "disk-full-write-access"].map(String::from));
@@ -257,6 +272,16 @@ fn create_linux_sandbox_command_args(
         linux_cmd.extend(["-s", "network-full-access"].map(String::from));
     }
 
+    for path in &linux_sandbox.read_only_paths {
+        linux_cmd.extend([
+            "--read-only-path".to_string(),
+            path.to_string_lossy().to_string(),
+        ]);
+    }
+    for class in &linux_sandbox.blocked_syscalls {
+        linux_cmd.extend(["--block-syscalls".to_string(), class.to_string()]);
+    }
+
     // Separator so that command arguments starting with `-` are not parsed as
     // options of the helper itself.
     linux_cmd.push("--".to_string());
@@ -270,6 +295,7 @@ fn create_linux_sandbox_command_args(
 fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    macos_seatbelt: &MacosSeatbelt,
     cwd: &Path,
 ) -> Vec<String> {
     let (file_write_policy, extra_cli_args) = {
@@ -316,8 +342,12 @@ fn create_seatbelt_command_args(
         ""
     };
 
+    // User rules go last so that they can narrow what the policy allows as
+    // well as widen it.
+    let extra_policy = macos_seatbelt.extra_policy.join("\n");
+
     let full_policy = format!(
-        "{MACOS_SEATBELT_BASE_POLICY}\n{file_read_policy}\n{file_write_policy}\n{network_policy}"
+        "{MACOS_SEATBELT_BASE_POLICY}\n{file_read_policy}\n{file_write_policy}\n{network_policy}\n{extra_policy}"
     );
     let mut seatbelt_args: Vec<String> = vec!["-p".to_string(), full_policy];
     seatbelt_args.extend(extra_cli_args);
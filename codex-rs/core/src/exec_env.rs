@@ -1,8 +1,20 @@
 use crate::config_types::EnvironmentVariablePattern;
+use crate::config_types::ShellEnvironment;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyInherit;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::OnceLock;
+
+/// Printed by the login shell right before its environment, so that whatever
+/// its startup files print is skipped.
+const LOGIN_ENV_MARKER: &str = "__codex_login_env__";
+
+/// Variables of the login shell that describe the shell itself rather than
+/// the user's setup.
+const LOGIN_SHELL_VARS: &[&str] = &["PWD", "OLDPWD", "SHLVL", "_"];
 
 /// Construct an environment map based on the rules in the specified policy. The
 /// resulting map can be passed directly to `Command::envs()` after calling
@@ -12,7 +24,65 @@ use std::collections::HashSet;
 /// The derivation follows the algorithm documented in the struct-level comment
 /// for [`ShellEnvironmentPolicy`].
 pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
-    populate_env(std::env::vars(), policy)
+    match policy.source {
+        ShellEnvironment::Process => populate_env(std::env::vars(), policy),
+        ShellEnvironment::Login => match login_shell_env() {
+            Some(vars) => populate_env(vars.iter().cloned(), policy),
+            None => populate_env(std::env::vars(), policy),
+        },
+    }
+}
+
+/// The environment of the user's login shell (`$SHELL -l -i`) after it has
+/// sourced its startup files. Captured once per process; `None`, after a
+/// warning, when it cannot be, in which case the process environment is
+/// used.
+fn login_shell_env() -> Option<&'static [(String, String)]> {
+    static LOGIN_ENV: OnceLock<Option<Vec<(String, String)>>> = OnceLock::new();
+    LOGIN_ENV
+        .get_or_init(|| {
+            let vars = capture_login_shell_env();
+            if let Err(e) = &vars {
+                tracing::warn!("using the process environment for commands: {e}");
+            }
+            vars.ok()
+        })
+        .as_deref()
+}
+
+fn capture_login_shell_env() -> Result<Vec<(String, String)>, String> {
+    if cfg!(windows) {
+        return Err("login shells are not supported on Windows".to_string());
+    }
+    let shell = std::env::var("SHELL").map_err(|_| "$SHELL is not set".to_string())?;
+    // Interactive, so that zsh sources `.zshrc` and bash, through the usual
+    // `.bash_profile`, `.bashrc`.
+    let output = Command::new(&shell)
+        .args(["-l", "-i", "-c"])
+        .arg(format!("printf '%s\\0' {LOGIN_ENV_MARKER}; env -0"))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run {shell}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("{shell} exited with {}", output.status));
+    }
+    parse_login_shell_env(&output.stdout)
+        .ok_or_else(|| format!("{shell} did not print its environment"))
+}
+
+/// The variables in the output of the login shell, after the marker.
+fn parse_login_shell_env(stdout: &[u8]) -> Option<Vec<(String, String)>> {
+    let stdout = String::from_utf8_lossy(stdout);
+    let marker = format!("{LOGIN_ENV_MARKER}\0");
+    let (_, env) = stdout.split_once(&marker)?;
+    Some(
+        env.split('\0')
+            .filter_map(|var| var.split_once('='))
+            .filter(|(name, _)| !LOGIN_SHELL_VARS.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    )
 }
 
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
@@ -174,6 +244,19 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn login_shell_env_skips_what_startup_files_print() {
+        let stdout = b"Welcome back!\n__codex_login_env__\0PATH=/home/me/.nvm/bin:/usr/bin\0SHLVL=2\0GREETING=a=b\0";
+        assert_eq!(
+            parse_login_shell_env(stdout),
+            Some(make_vars(&[
+                ("PATH", "/home/me/.nvm/bin:/usr/bin"),
+                ("GREETING", "a=b"),
+            ]))
+        );
+        assert_eq!(parse_login_shell_env(b"PATH=/usr/bin\0"), None);
+    }
+
     #[test]
     fn test_inherit_none() {
         let vars = make_vars(&[("PATH", "/usr/bin"), ("HOME", "/home")]);
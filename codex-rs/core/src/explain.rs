@@ -0,0 +1,160 @@
+//! `codex explain`: a one-shot explanation of a file or a symbol, in
+//! Markdown.
+//!
+//! A target that names a file under the working directory is attached within
+//! the `[attachments]` limits; anything else is looked up as the name of a
+//! top-level definition, found with the same patterns as the repository map.
+//! The request carries the project's instructions (`AGENTS.md`, the
+//! repository map), as the first request of a session would.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::attachments::AttachmentBudget;
+use crate::attachments::is_binary_file;
+use crate::auth::AuthManager;
+use crate::client::ModelClient;
+use crate::client_common::Prompt;
+use crate::config::Config;
+use crate::http_client::create_client;
+use crate::http_client::is_local_url;
+use crate::models::ContentItem;
+use crate::models::ResponseItem;
+use crate::project_doc::get_user_instructions;
+use crate::redaction::redact_secrets;
+use crate::repo_map::Definition;
+use crate::repo_map::find_definitions;
+
+const EXPLAIN_REQUEST: &str = "Explain the code below to a developer who is new to this project: what it is for, how it works, and how it fits into the rest of the repository. Point out anything surprising. Reply in Markdown, without asking follow-up questions.";
+
+/// Lines of a definition sent after its first line.
+const MAX_DEFINITION_LINES: usize = 150;
+
+/// Ask the model to explain `target`, a path relative to the working
+/// directory or the name of a top-level definition.
+pub async fn explain(config: &Config, target: &str) -> Result<String> {
+    let code = find_code(config, target)?;
+    let redacted = config
+        .redact_secrets
+        .then(|| redact_secrets(&code))
+        .flatten();
+    let code = match redacted {
+        Some(redacted) => redacted.text,
+        None => code,
+    };
+
+    let provider = &config.model_provider;
+    if config.offline && !is_local_url(&provider.base_url) {
+        anyhow::bail!(
+            "offline mode only allows local model providers, but {} is at {}",
+            provider.name,
+            provider.base_url
+        );
+    }
+    let http_client = create_client(config)?;
+    let client = ModelClient::new(
+        &config.model,
+        provider.clone(),
+        config.model_reasoning_effort,
+        config.model_reasoning_summary,
+        config.sampling,
+        http_client.clone(),
+        Arc::new(AuthManager::new(&config.codex_home, http_client)),
+    );
+    let prompt = Prompt {
+        input: vec![ResponseItem::Message {
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: format!("{EXPLAIN_REQUEST}\n\n{code}"),
+            }],
+        }],
+        user_instructions: get_user_instructions(config).await,
+        ..Default::default()
+    };
+    Ok(client.complete(&prompt).await?)
+}
+
+/// The code `target` refers to, as it is sent to the model.
+fn find_code(config: &Config, target: &str) -> Result<String> {
+    let path = config.cwd.join(target);
+    if path.is_file() {
+        let failed = || format!("failed to read {}", path.display());
+        if is_binary_file(&path).with_context(failed)? {
+            anyhow::bail!("{target} looks like a binary file");
+        }
+        let attachment = AttachmentBudget::new(&config.attachments)
+            .attach(&path, target)
+            .with_context(failed)?;
+        return Ok(attachment.text);
+    }
+
+    let definitions = find_definitions(&config.cwd, target);
+    let Some((first, others)) = definitions.split_first() else {
+        anyhow::bail!(
+            "{target} is neither a file nor a top-level definition under {}",
+            config.cwd.display()
+        );
+    };
+    let source = std::fs::read_to_string(config.cwd.join(&first.path))
+        .with_context(|| format!("failed to read {}", first.path))?;
+    let mut code = definition_excerpt(&source, first);
+    if !others.is_empty() {
+        let others: Vec<String> = others
+            .iter()
+            .map(|definition| format!("{}:{}", definition.path, definition.line))
+            .collect();
+        code.push_str(&format!(
+            "\n\n`{target}` is also defined at {}.",
+            others.join(", ")
+        ));
+    }
+    Ok(code)
+}
+
+/// The definition at `definition` in `source`, with the comments and
+/// attributes right above it, headed by where it is.
+fn definition_excerpt(source: &str, definition: &Definition) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut start = definition.line - 1;
+    while start > 0 && is_preamble(lines[start - 1]) {
+        start -= 1;
+    }
+    let end = lines.len().min(definition.line + MAX_DEFINITION_LINES);
+    format!(
+        "`{}`, lines {}-{end} of {}:\n```\n{}\n```",
+        definition.path,
+        start + 1,
+        lines.len(),
+        lines[start..end].join("\n")
+    )
+}
+
+/// Whether `line` documents or decorates the definition that follows it.
+fn is_preamble(line: &str) -> bool {
+    let line = line.trim_start();
+    ["//", "#", "@", "/*", "*"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn excerpts_start_at_the_doc_comment() {
+        let source = "use std::fmt;\n\n/// Runs it.\n#[inline]\npub fn run() {\n    todo!()\n}\n";
+        let definition = Definition {
+            path: "src/lib.rs".to_string(),
+            line: 5,
+        };
+        assert_eq!(
+            definition_excerpt(source, &definition),
+            "`src/lib.rs`, lines 3-7 of 7:\n```\n/// Runs it.\n#[inline]\npub fn run() {\n    todo!()\n}\n```"
+        );
+    }
+}
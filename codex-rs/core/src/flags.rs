@@ -22,4 +22,9 @@ env_flags! {
 
     /// Fixture path for offline tests (see client.rs).
     pub CODEX_RS_SSE_FIXTURE: Option<&str> = None;
+
+    /// Record model requests and responses to this file (see replay.rs).
+    pub CODEX_RECORD: Option<&str> = None;
+    /// Answer model requests from a file written with `CODEX_RECORD`.
+    pub CODEX_REPLAY: Option<&str> = None;
 }
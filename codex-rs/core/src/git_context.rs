@@ -0,0 +1,186 @@
+//! Recent Git changes sent ahead of the first prompt of a session, so that
+//! requests like "continue where I left off" need no explanation.
+//!
+//! The context lists the last commits, the files changed since
+//! `HEAD~commits` (including uncommitted and untracked ones), most recently
+//! modified first, and the diff against `HEAD~commits`, cut to
+//! `git_context.max_bytes`.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use tokio::process::Command;
+
+use crate::config_types::GitContext;
+
+/// Files listed beyond this are counted instead.
+const MAX_LISTED_FILES: usize = 50;
+
+/// Recent changes to the repository containing `cwd`, or `None` when it is
+/// not in a repository or nothing changed.
+pub(crate) async fn git_context(cwd: &Path, settings: &GitContext) -> Option<String> {
+    let root = git(cwd, &["rev-parse", "--show-toplevel"]).await?;
+    let root = Path::new(root.trim());
+    let commit_count: usize = git(cwd, &["rev-list", "--count", "HEAD"])
+        .await
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(0);
+    // Repositories with fewer commits are diffed against their first one.
+    let back = settings.commits.min(commit_count.saturating_sub(1));
+    let base = format!("HEAD~{back}");
+
+    let mut files = Vec::new();
+    if commit_count > 0 {
+        let changed = git(cwd, &["diff", "--name-status", &base])
+            .await
+            .unwrap_or_default();
+        files.extend(changed.lines().filter_map(|line| {
+            let (status, path) = line.split_once('\t')?;
+            // Renames list the old and the new path.
+            let path = path.rsplit('\t').next().unwrap_or(path);
+            Some((
+                status.chars().next().unwrap_or('M').to_string(),
+                path.to_string(),
+            ))
+        }));
+    }
+    let untracked = git(
+        cwd,
+        &["ls-files", "--others", "--exclude-standard", "--full-name"],
+    )
+    .await
+    .unwrap_or_default();
+    files.extend(
+        untracked
+            .lines()
+            .map(|path| ("?".to_string(), path.to_string())),
+    );
+    if files.is_empty() {
+        return None;
+    }
+    // Deleted files have no modification time and come last.
+    files.sort_by_cached_key(|(_, path)| {
+        std::cmp::Reverse(
+            std::fs::metadata(root.join(path))
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+        )
+    });
+
+    let mut text = String::from("<git_context>\n");
+    if commit_count > 0 {
+        let count = format!("-{}", back.max(1));
+        let log = git(cwd, &["log", "--oneline", "--no-decorate", &count])
+            .await
+            .unwrap_or_default();
+        text.push_str(&format!("Recent commits:\n{log}\n"));
+    }
+    text.push_str("Changed files, most recently modified first (? = untracked):\n");
+    for (status, path) in files.iter().take(MAX_LISTED_FILES) {
+        text.push_str(&format!("{status} {path}\n"));
+    }
+    if files.len() > MAX_LISTED_FILES {
+        text.push_str(&format!(
+            "({} more files)\n",
+            files.len() - MAX_LISTED_FILES
+        ));
+    }
+    if commit_count > 0 && settings.max_bytes > 0 {
+        let diff = git(cwd, &["diff", "--no-color", "--no-ext-diff", &base])
+            .await
+            .unwrap_or_default();
+        if !diff.is_empty() {
+            text.push_str(&format!("\nDiff against {base}:\n"));
+            text.push_str(&truncate(&diff, settings.max_bytes));
+        }
+    }
+    text.push_str("</git_context>");
+    Some(text)
+}
+
+/// `diff` cut at the last line that fits in `max_bytes`.
+fn truncate(diff: &str, max_bytes: usize) -> String {
+    if diff.len() <= max_bytes {
+        return diff.to_string();
+    }
+    let mut cut = max_bytes;
+    while !diff.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let end = diff[..cut].rfind('\n').map_or(0, |end| end + 1);
+    format!(
+        "{}[diff truncated: {} of {} bytes shown; run `git diff` for the rest]\n",
+        &diff[..end],
+        end,
+        diff.len()
+    )
+}
+
+async fn git(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[tokio::test]
+    async fn lists_commits_files_and_the_diff() {
+        let dir = TempDir::new().unwrap();
+        let settings = GitContext {
+            enabled: true,
+            commits: 1,
+            max_bytes: 4096,
+        };
+        run(dir.path(), &["init", "-q"]).await;
+        assert_eq!(git_context(dir.path(), &settings).await, None);
+
+        std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        run(dir.path(), &["add", "."]).await;
+        run(dir.path(), &["commit", "-qm", "first"]).await;
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        run(dir.path(), &["commit", "-qam", "second"]).await;
+        std::fs::write(dir.path().join("notes.md"), "todo\n").unwrap();
+
+        let context = git_context(dir.path(), &settings).await.unwrap();
+        assert!(context.contains(" second\n"), "{context}");
+        assert!(!context.contains(" first\n"), "{context}");
+        assert!(context.contains("M a.txt\n"), "{context}");
+        assert!(context.contains("? notes.md\n"), "{context}");
+        assert!(context.contains("Diff against HEAD~1:\n"), "{context}");
+        assert!(context.contains("\n+two\n"), "{context}");
+    }
+
+    #[test]
+    fn long_diffs_are_cut_at_a_line() {
+        let diff = "+aaa\n+bbb\n+ccc\n";
+        assert_eq!(truncate(diff, 100), diff);
+        assert_eq!(
+            truncate(diff, 7),
+            "+aaa\n[diff truncated: 5 of 15 bytes shown; run `git diff` for the rest]\n"
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! User hooks run around tool calls, configured in the `[hooks]` table.
+//!
+//! Each hook receives the call as a JSON object on stdin. A `pre_tool_call`
+//! hook blocks the call by exiting with a non-zero status (stderr is passed to
+//! the model as the reason) and can rewrite the call's arguments by printing
+//! `{"arguments": ...}` on stdout. `post_tool_call` hooks see the call's output
+//! as well; their result is only logged.
+//!
+//! Pre-call hooks fail closed: a hook that cannot be started, times out or
+//! prints something other than the expected JSON blocks the call.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use wildmatch::WildMatch;
+
+use crate::config_types::HookConfig;
+
+/// Applied to hooks without a `timeout_ms`.
+const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A tool call as described to hooks.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct HookToolCall<'a> {
+    /// `shell`, `apply_patch` (a `shell` call running `apply_patch`), a
+    /// built-in tool such as `update_plan`, or `<server>/<tool>` for MCP tools.
+    pub tool: &'a str,
+    pub call_id: &'a str,
+    pub cwd: &'a Path,
+    /// The arguments the model passed, as JSON.
+    pub arguments: Value,
+    /// Files a patch touches; empty for other tools.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub files: &'a [PathBuf],
+}
+
+#[derive(Serialize)]
+struct HookInput<'a> {
+    event: &'static str,
+    #[serde(flatten)]
+    call: &'a HookToolCall<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    success: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PreHookOutput {
+    arguments: Value,
+}
+
+/// Run the `pre_tool_call` hooks that apply to `call` in order, each seeing
+/// the arguments as rewritten by the ones before it. Returns the final
+/// arguments, or the reason the call was blocked.
+pub(crate) async fn run_pre_tool_call_hooks(
+    hooks: &[HookConfig],
+    mut call: HookToolCall<'_>,
+) -> Result<Value, String> {
+    for hook in hooks.iter().filter(|hook| applies_to(hook, call.tool)) {
+        let input = HookInput {
+            event: "pre_tool_call",
+            call: &call,
+            output: None,
+            success: None,
+        };
+        let name = hook_name(hook);
+        let result = run_hook(hook, &input)
+            .await
+            .map_err(|e| format!("blocked by hook `{name}`: {e}"))?;
+        if !result.status.success() {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            let reason = stderr.trim();
+            return Err(if reason.is_empty() {
+                format!("blocked by hook `{name}`")
+            } else {
+                format!("blocked by hook `{name}`: {reason}")
+            });
+        }
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        if stdout.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<PreHookOutput>(&stdout) {
+            Ok(PreHookOutput { arguments }) => call.arguments = arguments,
+            Err(e) => {
+                return Err(format!(
+                    "blocked by hook `{name}`: invalid output, expected {{\"arguments\": ...}}: {e}"
+                ));
+            }
+        }
+    }
+    Ok(call.arguments)
+}
+
+/// Run the `post_tool_call` hooks that apply to `call`. Failures are logged.
+pub(crate) async fn run_post_tool_call_hooks(
+    hooks: &[HookConfig],
+    call: &HookToolCall<'_>,
+    output: &str,
+    success: Option<bool>,
+) {
+    for hook in hooks.iter().filter(|hook| applies_to(hook, call.tool)) {
+        let input = HookInput {
+            event: "post_tool_call",
+            call,
+            output: Some(output),
+            success,
+        };
+        match run_hook(hook, &input).await {
+            Ok(result) if result.status.success() => {}
+            Ok(result) => tracing::warn!(
+                "post_tool_call hook `{}` exited with {}",
+                hook_name(hook),
+                result.status
+            ),
+            Err(e) => tracing::warn!("post_tool_call hook `{}` failed: {e}", hook_name(hook)),
+        }
+    }
+}
+
+fn applies_to(hook: &HookConfig, tool: &str) -> bool {
+    hook.tools.is_empty()
+        || hook
+            .tools
+            .iter()
+            .any(|pattern| WildMatch::new(pattern).matches(tool))
+}
+
+fn hook_name(hook: &HookConfig) -> String {
+    hook.command.join(" ")
+}
+
+async fn run_hook(
+    hook: &HookConfig,
+    input: &HookInput<'_>,
+) -> std::io::Result<std::process::Output> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "empty command",
+        ));
+    };
+    let payload = serde_json::to_vec(input)?;
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(input.call.cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that does not read its input closes the pipe early; that is
+        // not an error.
+        match stdin.write_all(&payload).await {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+    let timeout = hook
+        .timeout_ms
+        .map_or(DEFAULT_HOOK_TIMEOUT, Duration::from_millis);
+    tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("timed out after {}ms", timeout.as_millis()),
+            )
+        })?
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use serde_json::json;
+
+    fn hook(script: &str, tools: &[&str]) -> HookConfig {
+        HookConfig {
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            tools: tools.iter().map(|tool| tool.to_string()).collect(),
+            timeout_ms: None,
+        }
+    }
+
+    fn call(tool: &str) -> HookToolCall<'_> {
+        HookToolCall {
+            tool,
+            call_id: "call-1",
+            cwd: Path::new("/"),
+            arguments: json!({"command": ["ls"]}),
+            files: &[],
+        }
+    }
+
+    #[tokio::test]
+    async fn pre_hooks_block_rewrite_and_filter_by_tool() {
+        let block = hook("cat >/dev/null; echo 'no edits' >&2; exit 1", &["apply_*"]);
+        let rewrite = hook(
+            r#"cat >/dev/null; echo '{"arguments": {"command": ["ls", "-a"]}}'"#,
+            &["shell"],
+        );
+        let hooks = [block, rewrite];
+
+        assert_eq!(
+            run_pre_tool_call_hooks(&hooks, call("apply_patch")).await,
+            Err(
+                "blocked by hook `sh -c cat >/dev/null; echo 'no edits' >&2; exit 1`: no edits"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            run_pre_tool_call_hooks(&hooks, call("shell")).await,
+            Ok(json!({"command": ["ls", "-a"]}))
+        );
+        assert_eq!(
+            run_pre_tool_call_hooks(&hooks, call("update_plan")).await,
+            Ok(json!({"command": ["ls"]}))
+        );
+    }
+
+    #[tokio::test]
+    async fn pre_hooks_fail_closed() {
+        let garbage = hook("cat >/dev/null; echo not json", &[]);
+        assert!(
+            run_pre_tool_call_hooks(&[garbage], call("shell"))
+                .await
+                .is_err()
+        );
+
+        let slow = HookConfig {
+            timeout_ms: Some(50),
+            ..hook("sleep 5", &[])
+        };
+        let err = run_pre_tool_call_hooks(&[slow], call("shell"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("timed out"), "{err}");
+
+        let missing = HookConfig {
+            command: vec!["/definitely/not/a/hook".to_string()],
+            tools: Vec::new(),
+            timeout_ms: None,
+        };
+        assert!(
+            run_pre_tool_call_hooks(&[missing], call("shell"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn hooks_receive_the_call_as_json() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let out = dir.path().join("input.json");
+        let record = hook(&format!("cat > {}", out.display()), &[]);
+        run_post_tool_call_hooks(&[record], &call("shell"), "done", Some(true)).await;
+        let input: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(
+            input,
+            json!({
+                "event": "post_tool_call",
+                "tool": "shell",
+                "call_id": "call-1",
+                "cwd": "/",
+                "arguments": {"command": ["ls"]},
+                "output": "done",
+                "success": true,
+            })
+        );
+    }
+}
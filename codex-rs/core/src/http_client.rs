@@ -0,0 +1,159 @@
+//! The HTTP client behind every outbound request Codex makes: model calls,
+//! Copilot authentication and the GitHub API.
+//!
+//! `http_proxy` and `https_proxy` route plain and TLS traffic through a
+//! proxy, and `extra_ca_certs` adds PEM bundles to the trusted roots, e.g. the
+//! CA of a TLS-intercepting corporate proxy. Without a proxy in the config the
+//! usual `HTTP_PROXY`/`HTTPS_PROXY` environment variables apply.
+//!
+//! In offline mode only loopback hosts can be reached. Callers check the URLs
+//! they know about up front, with [`is_local_url`], so that they can fail with
+//! a clear error; the client itself refuses anything that slips through.
+
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+
+use reqwest::Certificate;
+use reqwest::NoProxy;
+use reqwest::Proxy;
+use reqwest::Url;
+
+use crate::config::Config;
+use crate::error::Result;
+
+/// Build the client configured by `config`. Fails on a malformed proxy URL
+/// or a certificate file that cannot be read or parsed.
+pub(crate) fn create_client(config: &Config) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if config.offline {
+        // Requests to other hosts are sent to a proxy on the discard port,
+        // which refuses the connection. Configured proxies are not used.
+        let blackhole = Url::parse(OFFLINE_BLACKHOLE).map_err(io::Error::other)?;
+        builder = builder.proxy(Proxy::custom(move |url| {
+            (!is_local(url)).then(|| blackhole.clone())
+        }));
+    } else {
+        // A configured proxy replaces the environment variables, so
+        // `NO_PROXY` is carried over explicitly.
+        if let Some(url) = &config.http_proxy {
+            let proxy = Proxy::http(url).map_err(|e| invalid_proxy("http_proxy", url, e))?;
+            builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+        }
+        if let Some(url) = &config.https_proxy {
+            let proxy = Proxy::https(url).map_err(|e| invalid_proxy("https_proxy", url, e))?;
+            builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+        }
+    }
+    for path in &config.extra_ca_certs {
+        for cert in load_certificates(path)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Discard port on the loopback interface, where normally nothing listens.
+const OFFLINE_BLACKHOLE: &str = "http://127.0.0.1:9";
+
+/// Whether `url` points at this machine, and can be used in offline mode.
+pub(crate) fn is_local_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| is_local(&url))
+}
+
+fn is_local(url: &Url) -> bool {
+    match url.host_str() {
+        Some(host) if host.eq_ignore_ascii_case("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    }
+}
+
+fn invalid_proxy(key: &str, url: &str, e: reqwest::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("invalid {key} `{url}`: {e}"),
+    )
+}
+
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let invalid = |message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("extra_ca_certs: {}: {message}", path.display()),
+        )
+    };
+    let pem = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+    let certs = Certificate::from_pem_bundle(&pem).map_err(|e| invalid(e.to_string()))?;
+    if certs.is_empty() {
+        return Err(invalid("no PEM certificates found".to_string()).into());
+    }
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use tempfile::TempDir;
+
+    fn config(toml: &str, codex_home: &Path) -> Config {
+        Config::load_from_base_config_with_overrides(
+            toml::from_str::<ConfigToml>(toml).unwrap(),
+            ConfigOverrides::default(),
+            codex_home.to_path_buf(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_bad_proxies_and_certificates() {
+        let codex_home = TempDir::new().unwrap();
+        let ok = config(
+            r#"https_proxy = "http://proxy.internal:3128""#,
+            codex_home.path(),
+        );
+        assert!(create_client(&ok).is_ok());
+
+        let bad_proxy = config(r#"http_proxy = "not a url""#, codex_home.path());
+        let err = create_client(&bad_proxy).unwrap_err().to_string();
+        assert!(err.starts_with("invalid http_proxy `not a url`"), "{err}");
+
+        std::fs::write(codex_home.path().join("empty.pem"), "").unwrap();
+        // Relative paths are resolved against CODEX_HOME.
+        let empty = config(r#"extra_ca_certs = ["empty.pem"]"#, codex_home.path());
+        let err = create_client(&empty).unwrap_err().to_string();
+        assert!(
+            err.ends_with("empty.pem: no PEM certificates found"),
+            "{err}"
+        );
+
+        let missing = config(r#"extra_ca_certs = ["/no/such/ca.pem"]"#, codex_home.path());
+        assert!(create_client(&missing).is_err());
+    }
+
+    #[test]
+    fn only_loopback_urls_are_local() {
+        for url in [
+            "http://localhost:11434/v1",
+            "http://127.0.0.1:8080",
+            "http://[::1]:1234/v1",
+        ] {
+            assert!(is_local_url(url), "{url}");
+        }
+        for url in [
+            "https://api.openai.com/v1",
+            "http://192.168.1.10:11434/v1",
+            "http://localhost.example.com",
+            "not a url",
+        ] {
+            assert!(!is_local_url(url), "{url}");
+        }
+    }
+}
@@ -9,14 +9,18 @@ pub fn is_known_safe_command(command: &[String]) -> bool {
 
     // TODO(mbolin): Also support safe commands that are piped together such
     // as `cat foo | wc -l`.
-    matches!(
-        command,
-        [bash, flag, script]
-            if bash == "bash"
-            && flag == "-lc"
-            && try_parse_bash(script).and_then(|tree|
-                try_parse_single_word_only_command(&tree, script)).is_some_and(|parsed_bash_command| is_safe_to_call_with_exec(&parsed_bash_command))
-    )
+    parse_bash_lc_single_command(command)
+        .is_some_and(|parsed_bash_command| is_safe_to_call_with_exec(&parsed_bash_command))
+}
+
+/// The words of `command` when it is `bash -lc` running a single command of
+/// plain words, without pipes, redirections or substitutions.
+pub(crate) fn parse_bash_lc_single_command(command: &[String]) -> Option<Vec<String>> {
+    match command {
+        [bash, flag, script] if bash == "bash" && flag == "-lc" => try_parse_bash(script)
+            .and_then(|tree| try_parse_single_word_only_command(&tree, script)),
+        _ => None,
+    }
 }
 
 fn is_safe_to_call_with_exec(command: &[String]) -> bool {
@@ -72,6 +76,66 @@ fn is_safe_to_call_with_exec(command: &[String]) -> bool {
     }
 }
 
+/// Every simple command of a `bash -lc` script as its words, in source
+/// order, wherever it is: in lists, pipelines, subshells, command
+/// substitutions or function bodies. Words that are not plain, such as
+/// `$REMOTE`, are kept as written. `None` when the script does not parse.
+pub(crate) fn parse_bash_script_commands(script: &str) -> Option<Vec<Vec<String>>> {
+    let tree = try_parse_bash(script)?;
+    let root = tree.root_node();
+    if root.has_error() {
+        return None;
+    }
+
+    let mut commands = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "command" {
+            let mut cursor = node.walk();
+            let words: Vec<String> = node
+                .named_children(&mut cursor)
+                .filter(|child| {
+                    !matches!(
+                        child.kind(),
+                        "variable_assignment" | "file_redirect" | "herestring_redirect"
+                    )
+                })
+                .filter_map(|child| command_word(child, script))
+                .collect();
+            if !words.is_empty() {
+                commands.push(words);
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.named_children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    Some(commands)
+}
+
+/// The word `node` of a command stands for, unquoted when it is a plain
+/// string.
+fn command_word(node: tree_sitter::Node, src: &str) -> Option<String> {
+    let node = match node.kind() {
+        "command_name" => node.named_child(0)?,
+        _ => node,
+    };
+    let text = node.utf8_text(src.as_bytes()).ok()?;
+    let word = match node.kind() {
+        "raw_string" => text
+            .strip_prefix('\'')
+            .and_then(|text| text.strip_suffix('\''))
+            .unwrap_or(text),
+        "string"
+            if node.named_child_count() == 1 && node.named_child(0)?.kind() == "string_content" =>
+        {
+            node.named_child(0)?.utf8_text(src.as_bytes()).ok()?
+        }
+        _ => text,
+    };
+    Some(word.to_string())
+}
+
 fn try_parse_bash(bash_lc_arg: &str) -> Option<Tree> {
     let lang = BASH.into();
     let mut parser = Parser::new();
@@ -5,12 +5,19 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod attachments;
+pub mod audit;
+pub mod auth;
+pub mod bench;
 mod chat_completions;
+pub mod chatgpt_auth;
+mod checkpoint;
 mod client;
 mod client_common;
 pub mod codex;
 pub use codex::Codex;
 pub mod codex_wrapper;
+mod command_risk;
 pub mod config;
 pub mod config_profile;
 pub mod config_types;
@@ -18,11 +25,22 @@ pub mod config_types;
 pub use codex_common::CliConfigOverrides;
 mod conversation_history;
 pub mod copilot;
+pub mod copilot_plan;
 mod copilot_token_store;
+pub mod credential_bundle;
+pub mod custom_prompts;
+pub mod distill;
+pub mod doctor;
+mod encryption;
 pub mod error;
+pub mod event_sink;
 pub mod exec;
 pub mod exec_env;
+pub mod explain;
 mod flags;
+mod git_context;
+mod hooks;
+mod http_client;
 mod is_safe_command;
 mod mcp_connection_manager;
 mod mcp_tool_call;
@@ -31,12 +49,45 @@ mod model_provider_info;
 pub use model_provider_info::ModelProviderInfo;
 pub use model_provider_info::WireApi;
 mod models;
+pub mod openai_model_info;
 mod openai_tools;
+pub mod otel;
+mod plan_tool;
+pub mod playback;
+pub mod policy;
 mod project_doc;
+pub mod project_init;
+pub mod project_memory;
+pub mod project_permissions;
 pub mod protocol;
-mod rollout;
+pub mod provider_status;
+pub mod rate_limit;
+mod read_cache;
+mod redaction;
+mod replay;
+mod repo_map;
+pub mod report;
+pub mod retention;
+pub mod rollout;
 mod safety;
+mod sandbox_escalation;
+mod scratchpad;
+mod seatbelt_policy;
+mod semantic_search;
+pub mod session_export;
+pub mod session_store;
+mod session_title;
+pub mod shell;
+mod structured_output;
+mod sub_agents;
+pub mod test_fix;
+mod token_store;
+mod tool_arguments;
+mod turn_review;
+mod untrusted_content;
 mod user_notification;
 pub mod util;
+mod working_dir;
+pub mod workspace_index;
 
 pub use client_common::model_supports_reasoning_summaries;
@@ -143,6 +143,11 @@ impl McpConnectionManager {
         self.tools.clone()
     }
 
+    /// The tool with the fully qualified `name`, if any server offers it.
+    pub fn tool(&self, name: &str) -> Option<&Tool> {
+        self.tools.get(name)
+    }
+
     /// Invoke the tool indicated by the (server, tool) pair.
     pub async fn call_tool(
         &self,
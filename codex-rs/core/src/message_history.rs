@@ -16,10 +16,14 @@
 
 use std::fs::File;
 use std::fs::OpenOptions;
+use std::io::Read;
 use std::io::Result;
+use std::io::Seek;
+use std::io::SeekFrom;
 use std::io::Write;
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use std::time::Duration;
@@ -28,6 +32,7 @@ use tokio::io::AsyncReadExt;
 use uuid::Uuid;
 
 use crate::config::Config;
+use crate::config_types::HistoryDedupe;
 use crate::config_types::HistoryPersistence;
 
 #[cfg(unix)]
@@ -38,10 +43,14 @@ use std::os::unix::fs::PermissionsExt;
 /// Filename that stores the message history inside `~/.codex`.
 const HISTORY_FILENAME: &str = "history.jsonl";
 
+/// How far from the end of the file to look for the last entry when checking
+/// for a duplicate. Longer entries are never treated as duplicates.
+const LAST_ENTRY_MAX_BYTES: u64 = 64 * 1024;
+
 const MAX_RETRIES: usize = 10;
 const RETRY_SLEEP: Duration = Duration::from_millis(100);
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 pub struct HistoryEntry {
     pub session_id: String,
     pub ts: u64,
@@ -59,7 +68,7 @@ fn history_filepath(config: &Config) -> PathBuf {
 /// which entails a small amount of blocking I/O internally.
 pub(crate) async fn append_entry(text: &str, session_id: &Uuid, config: &Config) -> Result<()> {
     match config.history.persistence {
-        HistoryPersistence::SaveAll => {
+        HistoryPersistence::Local => {
             // Save everything: proceed.
         }
         HistoryPersistence::None => {
@@ -111,7 +120,15 @@ pub(crate) async fn append_entry(text: &str, session_id: &Uuid, config: &Config)
     // We use sync I/O with spawn_blocking() because we are using a
     // [`std::fs::File`] instead of a [`tokio::fs::File`] to leverage an
     // advisory file locking API that is not available in the async API.
+    let dedupe = config.history.dedupe;
     tokio::task::spawn_blocking(move || -> Result<()> {
+        // Checked under the lock, so a concurrent session cannot append
+        // between the check and the write.
+        if dedupe == HistoryDedupe::Consecutive
+            && last_entry(&mut history_file)?.is_some_and(|last| last.text == entry.text)
+        {
+            return Ok(());
+        }
         history_file.write_all(line.as_bytes())?;
         history_file.flush()?;
         Ok(())
@@ -121,6 +138,25 @@ pub(crate) async fn append_entry(text: &str, session_id: &Uuid, config: &Config)
     Ok(())
 }
 
+/// The last entry of the history file, if it can be parsed from the final
+/// [`LAST_ENTRY_MAX_BYTES`] of the file.
+fn last_entry(file: &mut File) -> Result<Option<HistoryEntry>> {
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(LAST_ENTRY_MAX_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    file.take(len - start).read_to_end(&mut tail)?;
+
+    let tail = tail.strip_suffix(b"\n").unwrap_or(&tail);
+    let line = match tail.iter().rposition(|&b| b == b'\n') {
+        Some(idx) => &tail[idx + 1..],
+        // The tail starts mid-line unless it is the whole file.
+        None if start > 0 => return Ok(None),
+        None => tail,
+    };
+    Ok(serde_json::from_slice(line).ok())
+}
+
 /// Attempt to acquire an exclusive advisory lock on `file`, retrying up to 10
 /// times if the lock is currently held by another process. This prevents a
 /// potential indefinite wait while still giving other writers some time to
@@ -295,3 +331,58 @@ async fn ensure_owner_only_permissions(_file: &File) -> Result<()> {
     // For now, on non-Unix, simply succeed.
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use tempfile::TempDir;
+
+    fn config(codex_home: &TempDir, dedupe: HistoryDedupe) -> Config {
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap();
+        config.history.dedupe = dedupe;
+        config
+    }
+
+    fn recorded(config: &Config) -> Vec<String> {
+        std::fs::read_to_string(history_filepath(config))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<HistoryEntry>(line).unwrap().text)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn skips_consecutive_duplicates() {
+        let codex_home = TempDir::new().unwrap();
+        let config = config(&codex_home, HistoryDedupe::Consecutive);
+        let session_id = Uuid::new_v4();
+        for text in ["run the tests", "run the tests", "fix it", "run the tests"] {
+            append_entry(text, &session_id, &config).await.unwrap();
+        }
+        assert_eq!(
+            recorded(&config),
+            vec!["run the tests", "fix it", "run the tests"]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_every_prompt_without_dedupe() {
+        let codex_home = TempDir::new().unwrap();
+        let config = config(&codex_home, HistoryDedupe::None);
+        let session_id = Uuid::new_v4();
+        for _ in 0..2 {
+            append_entry("run the tests", &session_id, &config)
+                .await
+                .unwrap();
+        }
+        assert_eq!(recorded(&config), vec!["run the tests", "run the tests"]);
+    }
+}
@@ -5,12 +5,10 @@
 //!   2. User-defined entries inside `~/.codex/config.toml` under the `model_providers`
 //!      key. These override or extend the defaults at runtime.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
-use std::env::VarError;
-
-use crate::error::EnvVarError;
 
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
@@ -18,7 +16,7 @@ use crate::error::EnvVarError;
 /// *Responses* API. The two protocols use different request/response shapes
 /// and *cannot* be auto-detected at runtime, therefore each provider entry
 /// must declare which one it expects.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum WireApi {
     /// The experimental “Responses” API exposed by OpenAI at `/v1/responses`.
@@ -29,7 +27,7 @@ pub enum WireApi {
 }
 
 /// Serializable representation of a provider definition.
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 pub struct ModelProviderInfo {
     /// Friendly display name.
     pub name: String,
@@ -44,44 +42,33 @@ pub struct ModelProviderInfo {
 
     /// Which wire protocol this provider expects.
     pub wire_api: WireApi,
+
+    /// Whether the provider accepts image inputs. User turns that carry
+    /// images are rejected up front when this is `false`.
+    #[serde(default = "default_true")]
+    pub supports_images: bool,
+
+    /// Whether the provider accepts a JSON Schema for structured output. When
+    /// `false`, the schema is only described in the instructions.
+    #[serde(default = "default_true")]
+    pub supports_structured_output: bool,
+
+    /// Client-side limits on how fast requests are sent to this provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Per-minute request and token budgets for a provider. Requests over budget
+/// are queued until the sliding one-minute window has room for them.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct RateLimit {
+    pub requests_per_minute: Option<u32>,
+    /// Counted from an estimate of each request's input size.
+    pub tokens_per_minute: Option<u64>,
 }
 
-impl ModelProviderInfo {
-    /// If `env_key` is Some, returns the API key for this provider if present
-    /// (and non-empty) in the environment. If `env_key` is required but
-    /// cannot be found, returns an error.
-    pub fn api_key(&self) -> crate::error::Result<Option<String>> {
-        match &self.env_key {
-            Some(env_key) => {
-                // Special handling for Copilot tokens - try to load from persistent storage
-                if env_key == "COPILOT_TOKEN" && self.name == "GitHub Copilot" {
-                    if let Some(token) = crate::copilot::load_copilot_token() {
-                        // Also set it in the environment for this session
-                        unsafe {
-                            std::env::set_var("COPILOT_TOKEN", &token);
-                        }
-                        return Ok(Some(token));
-                    }
-                }
-                
-                std::env::var(env_key)
-                    .and_then(|v| {
-                        if v.trim().is_empty() {
-                            Err(VarError::NotPresent)
-                        } else {
-                            Ok(Some(v))
-                        }
-                    })
-                    .map_err(|_| {
-                        crate::error::CodexErr::EnvVar(EnvVarError {
-                            var: env_key.clone(),
-                            instructions: self.env_key_instructions.clone(),
-                        })
-                    })
-            }
-            None => Ok(None),
-        }
-    }
+fn default_true() -> bool {
+    true
 }
 
 /// Built-in default provider list.
@@ -97,6 +84,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("OPENAI_API_KEY".into()),
                 env_key_instructions: Some("Create an API key (https://platform.openai.com) and export it as an environment variable.".into()),
                 wire_api: WireApi::Responses,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -107,6 +97,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("OPENROUTER_API_KEY".into()),
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -117,6 +110,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("GEMINI_API_KEY".into()),
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -127,6 +123,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: None,
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -137,6 +136,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("MISTRAL_API_KEY".into()),
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -147,6 +149,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("DEEPSEEK_API_KEY".into()),
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: false,
+                supports_structured_output: false,
+                rate_limit: None,
             },
         ),
         (
@@ -157,6 +162,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("XAI_API_KEY".into()),
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -167,6 +175,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("GROQ_API_KEY".into()),
                 env_key_instructions: None,
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: true,
+                rate_limit: None,
             },
         ),
         (
@@ -177,6 +188,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 env_key: Some("COPILOT_TOKEN".into()),
                 env_key_instructions: Some("Authenticate with GitHub Copilot using 'codex copilot auth' or set COPILOT_TOKEN manually.".into()),
                 wire_api: WireApi::Chat,
+                supports_images: true,
+                supports_structured_output: false,
+                rate_limit: None,
             },
         ),
     ]
@@ -3,9 +3,12 @@ use std::collections::HashMap;
 use base64::Engine;
 use mcp_types::CallToolResult;
 use serde::Deserialize;
+use serde::Deserializer;
 use serde::Serialize;
 use serde::ser::Serializer;
 
+use crate::attachments::AttachmentBudget;
+use crate::config_types::Attachments;
 use crate::protocol::InputItem;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +133,9 @@ pub enum ReasoningItemReasoningSummary {
 
 impl From<Vec<InputItem>> for ResponseInputItem {
     fn from(items: Vec<InputItem>) -> Self {
+        // Mentioned files are normally attached, within the configured
+        // limits, before the input gets here.
+        let mut budget = AttachmentBudget::new(&Attachments::default());
         Self::Message {
             role: "user".to_string(),
             content: items
@@ -157,6 +163,21 @@ impl From<Vec<InputItem>> for ResponseInputItem {
                             None
                         }
                     },
+                    InputItem::LocalFile { path } => {
+                        match budget.attach(&path, &path.display().to_string()) {
+                            Ok(attachment) => Some(ContentItem::InputText {
+                                text: attachment.text,
+                            }),
+                            Err(err) => {
+                                tracing::warn!(
+                                    "Skipping file {} – could not read file: {}",
+                                    path.display(),
+                                    err
+                                );
+                                None
+                            }
+                        }
+                    }
                 })
                 .collect::<Vec<ContentItem>>(),
         }
@@ -177,10 +198,9 @@ pub struct ShellToolCallParams {
     pub timeout_ms: Option<u64>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct FunctionCallOutputPayload {
     pub content: String,
-    #[expect(dead_code)]
     pub success: Option<bool>,
 }
 
@@ -205,6 +225,33 @@ impl Serialize for FunctionCallOutputPayload {
     }
 }
 
+// Rollouts store the serialized (bare string) form, so accept it as well as the
+// object form when reading items back.
+impl<'de> Deserialize<'de> for FunctionCallOutputPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Wire {
+            Plain(String),
+            Object {
+                content: String,
+                success: Option<bool>,
+            },
+        }
+
+        Ok(match Wire::deserialize(deserializer)? {
+            Wire::Plain(content) => Self {
+                content,
+                success: None,
+            },
+            Wire::Object { content, success } => Self { content, success },
+        })
+    }
+}
+
 // Implement Display so callers can treat the payload like a plain string when logging or doing
 // trivial substring checks in tests (existing tests call `.contains()` on the output). Display
 // returns the raw `content` field.
@@ -0,0 +1,98 @@
+//! Static metadata about well-known OpenAI models: context window size and
+//! list prices, used by front-ends to show context fill and session cost.
+
+use crate::protocol::TokenUsage;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelInfo {
+    /// Maximum number of tokens (input + output) the model accepts.
+    pub context_window: u64,
+
+    /// Price in USD per million uncached input tokens.
+    pub input_price_per_million: f64,
+
+    /// Price in USD per million cached input tokens.
+    pub cached_input_price_per_million: f64,
+
+    /// Price in USD per million output tokens (reasoning tokens included).
+    pub output_price_per_million: f64,
+}
+
+impl ModelInfo {
+    /// Estimated cost in USD of `usage` at list prices.
+    pub fn estimate_cost(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage
+            .cached_input_tokens
+            .unwrap_or(0)
+            .min(usage.input_tokens);
+        let uncached = usage.input_tokens - cached;
+        (uncached as f64 * self.input_price_per_million
+            + cached as f64 * self.cached_input_price_per_million
+            + usage.output_tokens as f64 * self.output_price_per_million)
+            / 1_000_000.0
+    }
+}
+
+const fn info(context_window: u64, input: f64, cached_input: f64, output: f64) -> ModelInfo {
+    ModelInfo {
+        context_window,
+        input_price_per_million: input,
+        cached_input_price_per_million: cached_input,
+        output_price_per_million: output,
+    }
+}
+
+/// Known model families, most specific prefix first so that e.g.
+/// `gpt-4.1-mini` is not matched as `gpt-4.1`.
+const MODELS: &[(&str, ModelInfo)] = &[
+    ("codex-mini", info(200_000, 1.50, 0.375, 6.00)),
+    ("o4-mini", info(200_000, 1.10, 0.275, 4.40)),
+    ("o3-mini", info(200_000, 1.10, 0.55, 4.40)),
+    ("o3", info(200_000, 2.00, 0.50, 8.00)),
+    ("gpt-4.1-nano", info(1_047_576, 0.10, 0.025, 0.40)),
+    ("gpt-4.1-mini", info(1_047_576, 0.40, 0.10, 1.60)),
+    ("gpt-4.1", info(1_047_576, 2.00, 0.50, 8.00)),
+    ("gpt-4o-mini", info(128_000, 0.15, 0.075, 0.60)),
+    ("gpt-4o", info(128_000, 2.50, 1.25, 10.00)),
+];
+
+/// Look up `model`, allowing dated snapshots such as `o3-2025-04-16`.
+/// Returns `None` for models we know nothing about (e.g. local models).
+pub fn get_model_info(model: &str) -> Option<ModelInfo> {
+    MODELS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, info)| *info)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn matches_most_specific_prefix() {
+        assert_eq!(
+            get_model_info("gpt-4.1-mini-2025-04-14")
+                .unwrap()
+                .input_price_per_million,
+            0.40
+        );
+        assert_eq!(get_model_info("o3").unwrap().context_window, 200_000);
+        assert!(get_model_info("llama3").is_none());
+    }
+
+    #[test]
+    fn cost_discounts_cached_input() {
+        let info = get_model_info("o3").unwrap();
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: Some(500_000),
+            output_tokens: 100_000,
+            reasoning_output_tokens: None,
+            total_tokens: 1_100_000,
+        };
+        // 0.5M * $2 + 0.5M * $0.50 + 0.1M * $8
+        assert!((info.estimate_cost(&usage) - 2.05).abs() < 1e-9);
+    }
+}
@@ -4,6 +4,12 @@ use std::collections::BTreeMap;
 use std::sync::LazyLock;
 
 use crate::client_common::Prompt;
+use crate::plan_tool::UPDATE_PLAN_TOOL_NAME;
+use crate::project_memory::REMEMBER_TOOL_NAME;
+use crate::read_cache::READ_FILE_CHANGES_TOOL_NAME;
+use crate::scratchpad::SCRATCHPAD_TOOL_NAME;
+use crate::semantic_search::SEMANTIC_SEARCH_TOOL_NAME;
+use crate::sub_agents::SPAWN_AGENTS_TOOL_NAME;
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct ResponsesApiTool {
@@ -68,6 +74,160 @@ static DEFAULT_TOOLS: LazyLock<Vec<OpenAiTool>> = LazyLock::new(|| {
 static DEFAULT_CODEX_MODEL_TOOLS: LazyLock<Vec<OpenAiTool>> =
     LazyLock::new(|| vec![OpenAiTool::LocalShell {}]);
 
+static UPDATE_PLAN_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut step_properties = BTreeMap::new();
+    step_properties.insert("step".to_string(), JsonSchema::String);
+    step_properties.insert("status".to_string(), JsonSchema::String);
+
+    let mut properties = BTreeMap::new();
+    properties.insert("explanation".to_string(), JsonSchema::String);
+    properties.insert(
+        "plan".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::Object {
+                properties: step_properties,
+                required: &["step", "status"],
+                additional_properties: false,
+            }),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: UPDATE_PLAN_TOOL_NAME,
+        description: "Publishes your plan for a multi-step task so the user can follow your \
+                      progress. Send the complete list of steps every time; `status` is \
+                      `pending`, `in_progress` or `completed`, and at most one step is \
+                      `in_progress`. Update the plan as you finish steps or change course, with \
+                      an optional `explanation` of what changed.",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["plan"],
+            additional_properties: false,
+        },
+    })
+});
+
+static READ_FILE_CHANGES_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert("path".to_string(), JsonSchema::String);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_FILE_CHANGES_TOOL_NAME,
+        description: "Returns what changed in a file since you last read all of it with `cat` in \
+                      this session, as a unified diff, instead of its whole contents. Use it to \
+                      re-read a file you are editing. `path` is relative to the working \
+                      directory.",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["path"],
+            additional_properties: false,
+        },
+    })
+});
+
+static SCRATCHPAD_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert("action".to_string(), JsonSchema::String);
+    properties.insert("content".to_string(), JsonSchema::String);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SCRATCHPAD_TOOL_NAME,
+        description: "Your private notes for this session, kept outside the conversation: they \
+                      survive when the conversation is summarized and when the session is \
+                      resumed. `action` is `read`, `write` (replace the notes with `content`) \
+                      or `append` (add `content` as a new line). Use it on long tasks for what \
+                      you must not forget: decisions, findings, what is left to do. Holds at \
+                      most 8 KiB.",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["action"],
+            additional_properties: false,
+        },
+    })
+});
+
+/// Offered only when `[memory]` is enabled.
+static REMEMBER_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert("name".to_string(), JsonSchema::String);
+    properties.insert("content".to_string(), JsonSchema::String);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: REMEMBER_TOOL_NAME,
+        description: "Saves a fact about this project for future sessions, which get the \
+                      memories relevant to their task. Remember what took effort to find out \
+                      and will hold next time: how to build and test, where things live, \
+                      pitfalls. Not the details of the current task. `name` is a short slug \
+                      (lowercase letters, digits, `-`); saving under an existing name replaces \
+                      that memory. Keep `content` to a few lines of Markdown.",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["name", "content"],
+            additional_properties: false,
+        },
+    })
+});
+
+/// Offered only when the session may start sub-agents.
+static SPAWN_AGENTS_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut task_properties = BTreeMap::new();
+    task_properties.insert("prompt".to_string(), JsonSchema::String);
+    task_properties.insert("sandbox".to_string(), JsonSchema::String);
+
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "tasks".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::Object {
+                properties: task_properties,
+                required: &["prompt"],
+                additional_properties: false,
+            }),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SPAWN_AGENTS_TOOL_NAME,
+        description: "Runs independent subtasks concurrently, each in a separate agent, and returns \
+                      each agent's final report. Give every task a self-contained prompt. \
+                      `sandbox` is `read-only` (default) or `inherit` to allow the same writes \
+                      as this session. Sub-agents cannot ask the user for approval, so `inherit` \
+                      is refused unless this session never asks either.",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["tasks"],
+            additional_properties: false,
+        },
+    })
+});
+
+/// Offered only when `semantic_search` is enabled.
+static SEMANTIC_SEARCH_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert("query".to_string(), JsonSchema::String);
+    properties.insert("limit".to_string(), JsonSchema::Number);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SEMANTIC_SEARCH_TOOL_NAME,
+        description: "Finds the code and documentation in the workspace that best match a \
+                      natural-language `query`, such as \"where are sessions persisted\", and \
+                      returns the matching line ranges with their contents. Use it to find where \
+                      something is implemented when you do not know the names to grep for. \
+                      `limit` is the number of results (default 5).",
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: &["query"],
+            additional_properties: false,
+        },
+    })
+});
+
 /// Returns JSON values that are compatible with Function Calling in the
 /// Responses API:
 /// https://platform.openai.com/docs/guides/function-calling?api-mode=responses
@@ -81,10 +241,22 @@ pub(crate) fn create_tools_json_for_responses_api(
     } else {
         &DEFAULT_TOOLS
     };
-    let mut tools_json = Vec::with_capacity(default_tools.len() + prompt.extra_tools.len());
+    let mut tools_json = Vec::with_capacity(default_tools.len() + prompt.extra_tools.len() + 6);
     for t in default_tools.iter() {
         tools_json.push(serde_json::to_value(t)?);
     }
+    tools_json.push(serde_json::to_value(&*UPDATE_PLAN_TOOL)?);
+    tools_json.push(serde_json::to_value(&*READ_FILE_CHANGES_TOOL)?);
+    tools_json.push(serde_json::to_value(&*SCRATCHPAD_TOOL)?);
+    if prompt.allow_sub_agents {
+        tools_json.push(serde_json::to_value(&*SPAWN_AGENTS_TOOL)?);
+    }
+    if prompt.semantic_search {
+        tools_json.push(serde_json::to_value(&*SEMANTIC_SEARCH_TOOL)?);
+    }
+    if prompt.remember {
+        tools_json.push(serde_json::to_value(&*REMEMBER_TOOL)?);
+    }
     tools_json.extend(
         prompt
             .extra_tools
@@ -96,6 +268,26 @@ pub(crate) fn create_tools_json_for_responses_api(
     Ok(tools_json)
 }
 
+/// JSON Schema of the parameters of the built-in function tool `name`.
+pub(crate) fn builtin_tool_parameters(name: &str) -> Option<serde_json::Value> {
+    DEFAULT_TOOLS
+        .iter()
+        .chain([
+            &*UPDATE_PLAN_TOOL,
+            &*READ_FILE_CHANGES_TOOL,
+            &*SCRATCHPAD_TOOL,
+            &*SPAWN_AGENTS_TOOL,
+            &*SEMANTIC_SEARCH_TOOL,
+            &*REMEMBER_TOOL,
+        ])
+        .find_map(|tool| match tool {
+            OpenAiTool::Function(tool) if tool.name == name => {
+                serde_json::to_value(&tool.parameters).ok()
+            }
+            _ => None,
+        })
+}
+
 /// Returns JSON values that are compatible with Function Calling in the
 /// Chat Completions API:
 /// https://platform.openai.com/docs/guides/function-calling?api-mode=chat
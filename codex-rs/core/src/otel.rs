@@ -0,0 +1,120 @@
+//! OpenTelemetry export of the spans Codex records for tasks, turns, model
+//! requests and tool calls.
+//!
+//! Front-ends add the layer returned by [`otel_layer`] to their `tracing`
+//! subscriber. Exporting needs the `otel` cargo feature.
+
+use tracing::Subscriber;
+use tracing_subscriber::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::Config;
+
+/// Spans from this crate are exported whatever `RUST_LOG` says. Log events are
+/// not: they may quote prompts and tool output.
+#[cfg(feature = "otel")]
+const EXPORTED_TARGET: &str = "codex_core";
+
+/// Keeps the exporter running. Spans still buffered are flushed when it is
+/// dropped, so hold it until the process is about to exit.
+pub struct OtelGuard {
+    #[cfg(feature = "otel")]
+    provider: opentelemetry_sdk::trace::SdkTracerProvider,
+}
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!("failed to flush OpenTelemetry spans: {e}");
+        }
+    }
+}
+
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// Build the layer that exports spans to the endpoint in `config.otel`, or
+/// `None` when export is not configured. Fails if the exporter cannot be set
+/// up or this build does not include the `otel` feature.
+pub fn otel_layer<S>(config: &Config) -> anyhow::Result<Option<(BoxedLayer<S>, OtelGuard)>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+{
+    let Some(otel) = &config.otel else {
+        return Ok(None);
+    };
+    #[cfg(feature = "otel")]
+    {
+        use opentelemetry::trace::TracerProvider;
+        use opentelemetry_otlp::WithExportConfig;
+        use opentelemetry_otlp::WithHttpConfig;
+        use tracing_subscriber::filter::filter_fn;
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(otel.endpoint.clone())
+            .with_headers(otel.headers.clone())
+            .build()?;
+        let service_name = otel
+            .service_name
+            .clone()
+            .unwrap_or_else(|| "codex".to_string());
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                opentelemetry_sdk::Resource::builder()
+                    .with_service_name(service_name)
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer(EXPORTED_TARGET);
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(filter_fn(|metadata| {
+                metadata.is_span()
+                    && metadata.target().starts_with(EXPORTED_TARGET)
+                    && *metadata.level() <= tracing::Level::INFO
+            }));
+        Ok(Some((Box::new(layer), OtelGuard { provider })))
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = otel;
+        anyhow::bail!("[otel] is set but this build of Codex does not include the `otel` feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use crate::config_types::OtelConfig;
+    use tempfile::TempDir;
+    use tracing_subscriber::Registry;
+
+    #[test]
+    fn export_is_off_unless_configured() {
+        let codex_home = TempDir::new().unwrap();
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap();
+        assert!(otel_layer::<Registry>(&config).unwrap().is_none());
+
+        config.otel = Some(OtelConfig {
+            endpoint: "http://localhost:4318/v1/traces".to_string(),
+            headers: Default::default(),
+            service_name: None,
+        });
+        let result = otel_layer::<Registry>(&config);
+        if cfg!(feature = "otel") {
+            assert!(result.unwrap().is_some());
+        } else {
+            assert!(result.is_err());
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! The `update_plan` tool, through which the model publishes the steps of a
+//! multi-step task and keeps their statuses current so that front-ends can
+//! show progress.
+
+use crate::protocol::PlanUpdatedEvent;
+use crate::protocol::StepStatus;
+
+pub(crate) const UPDATE_PLAN_TOOL_NAME: &str = "update_plan";
+
+/// Arguments of an `update_plan` call. They have the shape of the event that
+/// is sent to front-ends, so the call is validated and forwarded as is.
+pub(crate) type UpdatePlanArgs = PlanUpdatedEvent;
+
+/// Parse and validate the arguments of an `update_plan` call.
+pub(crate) fn parse_update_plan(arguments: &str) -> Result<UpdatePlanArgs, String> {
+    let args: UpdatePlanArgs = serde_json::from_str(arguments)
+        .map_err(|e| format!("failed to parse function arguments: {e}"))?;
+    if args.plan.iter().any(|item| item.step.trim().is_empty()) {
+        return Err("every plan step needs a description".to_string());
+    }
+    let in_progress = args
+        .plan
+        .iter()
+        .filter(|item| item.status == StepStatus::InProgress)
+        .count();
+    if in_progress > 1 {
+        return Err(format!(
+            "at most one step can be in_progress at a time, got {in_progress}"
+        ));
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::protocol::PlanItem;
+
+    #[test]
+    fn parses_plan() {
+        let args = parse_update_plan(
+            r#"{"explanation":"found the bug","plan":[
+                {"step":"reproduce","status":"completed"},
+                {"step":"fix","status":"in_progress"},
+                {"step":"add a test","status":"pending"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(args.explanation.as_deref(), Some("found the bug"));
+        assert_eq!(
+            args.plan[1],
+            PlanItem {
+                step: "fix".to_string(),
+                status: StepStatus::InProgress,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_plans() {
+        let two_in_progress = r#"{"plan":[
+            {"step":"a","status":"in_progress"},
+            {"step":"b","status":"in_progress"}]}"#;
+        assert_eq!(
+            parse_update_plan(two_in_progress).unwrap_err(),
+            "at most one step can be in_progress at a time, got 2"
+        );
+        assert!(parse_update_plan(r#"{"plan":[{"step":" ","status":"pending"}]}"#).is_err());
+        assert!(parse_update_plan(r#"{"plan":[{"step":"a","status":"done"}]}"#).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Playback of recorded sessions for `codex replay`.
+//!
+//! A rollout records the items exchanged with the model rather than the
+//! events front-ends render, so [`Playback::load`] turns the items back into
+//! the events the live run emitted, in order, for a front-end to feed through
+//! the code that renders a live session. Two details cannot be recovered:
+//! patches show the hunks of the `apply_patch` call rather than a diff
+//! against the files as they were, and commands report their combined output
+//! as stdout.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::Hunk;
+use codex_apply_patch::parse_patch;
+use mcp_types::CallToolResult;
+use similar::ChangeTag;
+use similar::TextDiff;
+
+use crate::mcp_connection_manager::try_parse_fully_qualified_tool_name;
+use crate::models::LocalShellAction;
+use crate::models::ReasoningItemReasoningSummary;
+use crate::models::ResponseItem;
+use crate::models::ShellToolCallParams;
+use crate::plan_tool::UPDATE_PLAN_TOOL_NAME;
+use crate::plan_tool::UpdatePlanArgs;
+use crate::protocol::AgentMessageEvent;
+use crate::protocol::AgentReasoningEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::ExecCommandBeginEvent;
+use crate::protocol::ExecCommandEndEvent;
+use crate::protocol::FileChange;
+use crate::protocol::McpToolCallBeginEvent;
+use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::PatchApplyBeginEvent;
+use crate::protocol::PatchApplyEndEvent;
+use crate::protocol::TaskCompleteEvent;
+use crate::rollout::read_rollout;
+use crate::session_export::message_text;
+use crate::session_export::parse_exec_output;
+
+/// One step of a recorded session.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// A prompt the user submitted. Front-ends show prompts when they submit
+    /// them, so no event carries them.
+    UserMessage(String),
+    Event(Event),
+}
+
+#[derive(Debug, Clone)]
+pub struct Playback {
+    pub id: String,
+    pub timestamp: String,
+    pub steps: Vec<Step>,
+}
+
+impl Playback {
+    /// Load the session recorded in the rollout file at `path`.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let (meta, items) = read_rollout(path)?;
+        let cwd = meta.cwd.unwrap_or_default();
+        Ok(Self {
+            id: meta.id,
+            timestamp: meta.timestamp,
+            steps: steps_from_items(&items, &cwd),
+        })
+    }
+}
+
+/// What a call was shown as, to show its output the same way.
+enum Call {
+    Exec,
+    Patch,
+    McpTool,
+}
+
+fn steps_from_items(items: &[ResponseItem], cwd: &Path) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut calls: HashMap<&str, Call> = HashMap::new();
+    // Each prompt starts a task, whose events carry the id of the submission
+    // that started it.
+    let mut turn = 0;
+    let mut task_running = false;
+    let mut last_agent_message = None;
+
+    for item in items {
+        let msg = match item {
+            ResponseItem::Message { role, content } => {
+                let text = message_text(content);
+                if text.trim().is_empty() {
+                    continue;
+                }
+                if role != "user" {
+                    last_agent_message = Some(text.clone());
+                    steps.push(event(
+                        turn,
+                        EventMsg::AgentMessage(AgentMessageEvent { message: text }),
+                    ));
+                    continue;
+                }
+                if task_running {
+                    steps.push(event(
+                        turn,
+                        EventMsg::TaskComplete(TaskCompleteEvent {
+                            last_agent_message: last_agent_message.take(),
+                            stats: None,
+                        }),
+                    ));
+                }
+                turn += 1;
+                task_running = true;
+                steps.push(Step::UserMessage(text));
+                EventMsg::TaskStarted
+            }
+            ResponseItem::Reasoning { summary, .. } => {
+                for ReasoningItemReasoningSummary::SummaryText { text } in summary {
+                    steps.push(event(
+                        turn,
+                        EventMsg::AgentReasoning(AgentReasoningEvent { text: text.clone() }),
+                    ));
+                }
+                continue;
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action: LocalShellAction::Exec(action),
+                ..
+            } => {
+                let Some(call_id) = call_id.as_deref().or(id.as_deref()) else {
+                    continue;
+                };
+                let (call, msg) = shell_call(
+                    call_id,
+                    &action.command,
+                    action.working_directory.as_deref(),
+                    cwd,
+                );
+                calls.insert(call_id, call);
+                msg
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+            } => match name.as_str() {
+                "shell" | "container.exec" => {
+                    let Ok(params) = serde_json::from_str::<ShellToolCallParams>(arguments) else {
+                        continue;
+                    };
+                    let (call, msg) =
+                        shell_call(call_id, &params.command, params.workdir.as_deref(), cwd);
+                    calls.insert(call_id, call);
+                    msg
+                }
+                UPDATE_PLAN_TOOL_NAME => match serde_json::from_str::<UpdatePlanArgs>(arguments) {
+                    Ok(plan) => EventMsg::PlanUpdated(plan),
+                    Err(_) => continue,
+                },
+                _ => {
+                    let Some((server, tool)) = try_parse_fully_qualified_tool_name(name) else {
+                        continue;
+                    };
+                    calls.insert(call_id, Call::McpTool);
+                    EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
+                        call_id: call_id.clone(),
+                        server,
+                        tool,
+                        arguments: serde_json::from_str(arguments).ok(),
+                    })
+                }
+            },
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let Some(call) = calls.remove(call_id.as_str()) else {
+                    continue;
+                };
+                let call_id = call_id.clone();
+                match call {
+                    Call::Exec => {
+                        let (stdout, exit_code) = parse_exec_output(&output.content);
+                        EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                            call_id,
+                            stdout,
+                            stderr: String::new(),
+                            exit_code: exit_code.unwrap_or(-1),
+                        })
+                    }
+                    Call::Patch => {
+                        let (stdout, exit_code) = parse_exec_output(&output.content);
+                        EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+                            call_id,
+                            stdout,
+                            stderr: String::new(),
+                            success: exit_code == Some(0),
+                        })
+                    }
+                    Call::McpTool => EventMsg::McpToolCallEnd(McpToolCallEndEvent {
+                        call_id,
+                        result: serde_json::from_str::<CallToolResult>(&output.content)
+                            .map_err(|_| output.content.clone()),
+                    }),
+                }
+            }
+            ResponseItem::Other => continue,
+        };
+        steps.push(event(turn, msg));
+    }
+
+    if task_running {
+        steps.push(event(
+            turn,
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message,
+                stats: None,
+            }),
+        ));
+    }
+    steps
+}
+
+fn event(turn: u64, msg: EventMsg) -> Step {
+    Step::Event(Event {
+        id: turn.to_string(),
+        msg,
+    })
+}
+
+/// The begin event of a shell call, which is either a command or a patch.
+fn shell_call(
+    call_id: &str,
+    command: &[String],
+    workdir: Option<&str>,
+    cwd: &Path,
+) -> (Call, EventMsg) {
+    let call_id = call_id.to_string();
+    match command {
+        [program, patch] if program == "apply_patch" => (
+            Call::Patch,
+            EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+                call_id,
+                auto_approved: true,
+                changes: patch_changes(patch, cwd),
+            }),
+        ),
+        _ => (
+            Call::Exec,
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id,
+                command: command.to_vec(),
+                cwd: workdir.map_or_else(|| cwd.to_path_buf(), |workdir| cwd.join(workdir)),
+            }),
+        ),
+    }
+}
+
+/// The changes of an `apply_patch` call, with each update shown as the lines
+/// its hunks replace.
+fn patch_changes(patch: &str, cwd: &Path) -> HashMap<PathBuf, FileChange> {
+    let Ok(hunks) = parse_patch(patch) else {
+        return HashMap::new();
+    };
+    hunks
+        .into_iter()
+        .map(|hunk| {
+            let path = hunk.resolve_path(cwd);
+            let change = match hunk {
+                Hunk::AddFile { contents, .. } => FileChange::Add { content: contents },
+                Hunk::DeleteFile { .. } => FileChange::Delete,
+                Hunk::UpdateFile {
+                    move_path, chunks, ..
+                } => {
+                    let mut unified_diff = String::new();
+                    for chunk in &chunks {
+                        unified_diff.push_str("@@");
+                        if let Some(context) = &chunk.change_context {
+                            unified_diff.push(' ');
+                            unified_diff.push_str(context);
+                        }
+                        unified_diff.push('\n');
+                        let old = lines(&chunk.old_lines);
+                        let new = lines(&chunk.new_lines);
+                        for change in TextDiff::from_lines(&old, &new).iter_all_changes() {
+                            unified_diff.push(match change.tag() {
+                                ChangeTag::Equal => ' ',
+                                ChangeTag::Delete => '-',
+                                ChangeTag::Insert => '+',
+                            });
+                            unified_diff.push_str(change.value());
+                        }
+                    }
+                    FileChange::Update {
+                        unified_diff,
+                        move_path: move_path.map(|move_path| cwd.join(move_path)),
+                    }
+                }
+            };
+            (path, change)
+        })
+        .collect()
+}
+
+fn lines(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// The kinds of steps, with the text that identifies them.
+    fn describe(steps: &[Step]) -> Vec<String> {
+        steps
+            .iter()
+            .map(|step| match step {
+                Step::UserMessage(text) => format!("user: {text}"),
+                Step::Event(Event { id, msg }) => match msg {
+                    EventMsg::TaskStarted => format!("{id} task started"),
+                    EventMsg::TaskComplete(event) => {
+                        format!("{id} task complete: {:?}", event.last_agent_message)
+                    }
+                    EventMsg::AgentMessage(event) => format!("{id} agent: {}", event.message),
+                    EventMsg::ExecCommandBegin(event) => {
+                        format!("{id} exec {:?} in {}", event.command, event.cwd.display())
+                    }
+                    EventMsg::ExecCommandEnd(event) => {
+                        format!("{id} exit {}: {:?}", event.exit_code, event.stdout)
+                    }
+                    EventMsg::PatchApplyBegin(event) => {
+                        let change = &event.changes[Path::new("/repo/a.txt")];
+                        let FileChange::Update { unified_diff, .. } = change else {
+                            panic!("expected an update, got {change:?}");
+                        };
+                        format!("{id} patch: {unified_diff:?}")
+                    }
+                    EventMsg::PatchApplyEnd(event) => format!("{id} patched: {}", event.success),
+                    other => format!("{id} {other:?}"),
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn items_become_the_events_of_the_live_run() {
+        let items: Vec<ResponseItem> = [
+            r#"{"type":"message","role":"user","content":[{"type":"input_text","text":"list the files"}]}"#,
+            r#"{"type":"function_call","name":"shell","arguments":"{\"command\":[\"ls\"],\"workdir\":\"src\"}","call_id":"c1"}"#,
+            r#"{"type":"function_call_output","call_id":"c1","output":"{\"output\":\"a.txt\\n\",\"metadata\":{\"exit_code\":0,\"duration_seconds\":0.1}}"}"#,
+            r#"{"type":"message","role":"assistant","content":[{"type":"output_text","text":"One file."}]}"#,
+            r#"{"type":"message","role":"user","content":[{"type":"input_text","text":"fix it"}]}"#,
+            r#"{"type":"function_call","name":"shell","arguments":"{\"command\":[\"apply_patch\",\"*** Begin Patch\\n*** Update File: a.txt\\n@@\\n keep\\n-old\\n+new\\n*** End Patch\"]}","call_id":"c2"}"#,
+            r#"{"type":"function_call_output","call_id":"c2","output":"{\"output\":\"Done!\\n\",\"metadata\":{\"exit_code\":0,\"duration_seconds\":0.0}}"}"#,
+        ]
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+        assert_eq!(
+            describe(&steps_from_items(&items, Path::new("/repo"))),
+            vec![
+                "user: list the files",
+                "1 task started",
+                "1 exec [\"ls\"] in /repo/src",
+                "1 exit 0: \"a.txt\\n\"",
+                "1 agent: One file.",
+                "1 task complete: Some(\"One file.\")",
+                "user: fix it",
+                "2 task started",
+                "2 patch: \"@@\\n keep\\n-old\\n+new\\n\"",
+                "2 patched: true",
+                "2 task complete: None",
+            ]
+        );
+    }
+}
@@ -12,6 +12,7 @@
 //!     exists, the search stops – we do **not** walk past the Git root.
 
 use crate::config::Config;
+use crate::repo_map::repo_map;
 use std::path::Path;
 use tokio::io::AsyncReadExt;
 use tracing::error;
@@ -23,10 +24,16 @@ const CANDIDATE_FILENAMES: &[&str] = &["AGENTS.md"];
 /// be concatenated with the following separator.
 const PROJECT_DOC_SEPARATOR: &str = "\n\n--- project-doc ---\n\n";
 
-/// Combines `Config::instructions` and `AGENTS.md` (if present) into a single
-/// string of instructions.
+/// Precedes the map of the repository in the instructions.
+const REPO_MAP_SEPARATOR: &str = "\n\n--- repo-map ---\n\n";
+
+/// Precedes the list of `Config::checks` in the instructions.
+const PROJECT_CHECKS_SEPARATOR: &str = "\n\n--- project-checks ---\n\n";
+
+/// Combines `Config::instructions`, `AGENTS.md` (if present), a map of the
+/// repository and the project's checks into a single string of instructions.
 pub(crate) async fn get_user_instructions(config: &Config) -> Option<String> {
-    match find_project_doc(config).await {
+    let mut instructions = match find_project_doc(config).await {
         Ok(Some(project_doc)) => match &config.instructions {
             Some(original_instructions) => Some(format!(
                 "{original_instructions}{PROJECT_DOC_SEPARATOR}{project_doc}"
@@ -38,7 +45,37 @@ pub(crate) async fn get_user_instructions(config: &Config) -> Option<String> {
             error!("error trying to find project doc: {e:#}");
             config.instructions.clone()
         }
+    };
+
+    let (cwd, focus, max_tokens) = (
+        config.cwd.clone(),
+        config.focus.clone(),
+        config.repo_map_max_tokens,
+    );
+    match tokio::task::spawn_blocking(move || repo_map(&cwd, focus.as_deref(), max_tokens)).await {
+        Ok(Some(map)) => {
+            instructions = Some(match instructions {
+                Some(instructions) => format!("{instructions}{REPO_MAP_SEPARATOR}{map}"),
+                None => map,
+            });
+        }
+        Ok(None) => {}
+        Err(e) => error!("error building the repository map: {e:#}"),
+    }
+
+    let commands = config.checks.commands();
+    if commands.is_empty() {
+        return instructions;
+    }
+    let mut checks =
+        "Before you finish, verify your changes with the project's checks:\n".to_string();
+    for (kind, command) in commands {
+        checks.push_str(&format!("- {kind}: `{command}`\n"));
     }
+    Some(match instructions {
+        Some(instructions) => format!("{instructions}{PROJECT_CHECKS_SEPARATOR}{checks}"),
+        None => checks,
+    })
 }
 
 /// Attempt to locate and load the project documentation. Currently, the search
@@ -139,6 +176,7 @@ mod tests {
     use super::*;
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
+    use crate::config_types::ProjectChecks;
     use std::fs;
     use tempfile::TempDir;
 
@@ -158,6 +196,7 @@ mod tests {
 
         config.cwd = root.path().to_path_buf();
         config.project_doc_max_bytes = limit;
+        config.repo_map_max_tokens = 0;
 
         config.instructions = instructions.map(ToOwned::to_owned);
         config
@@ -268,6 +307,44 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    /// Configured checks are listed after the project doc.
+    #[tokio::test]
+    async fn appends_project_checks() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("AGENTS.md"), "proj doc").unwrap();
+        let mut config = make_config(&tmp, 4096, None);
+        config.checks = ProjectChecks {
+            test: Some("cargo test".to_string()),
+            format: Some("cargo fmt --check".to_string()),
+            ..Default::default()
+        };
+
+        let res = get_user_instructions(&config).await.expect("instructions");
+
+        assert_eq!(
+            res,
+            format!(
+                "proj doc{PROJECT_CHECKS_SEPARATOR}Before you finish, verify your changes with \
+                 the project's checks:\n- format: `cargo fmt --check`\n- test: `cargo test`\n"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn appends_repo_map() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("AGENTS.md"), "proj doc").unwrap();
+        fs::write(tmp.path().join("main.py"), "def main():\n    pass\n").unwrap();
+        let mut config = make_config(&tmp, 4096, None);
+        config.repo_map_max_tokens = 1000;
+
+        let res = get_user_instructions(&config).await.expect("instructions");
+
+        let (doc, map) = res.split_once(REPO_MAP_SEPARATOR).expect("repo map");
+        assert_eq!(doc, "proj doc");
+        assert!(map.ends_with("AGENTS.md\nmain.py: def main\n"), "{map}");
+    }
+
     /// If there are existing system instructions but the project doc is
     /// missing we expect the original instructions to be returned unchanged.
     #[tokio::test]
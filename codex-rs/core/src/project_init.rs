@@ -0,0 +1,386 @@
+//! Project detection behind `codex init`.
+//!
+//! [`detect`] looks at the marker files in a project root (`Cargo.toml`,
+//! `package.json`, `go.mod`, ...) to find the languages and build systems in
+//! use and the commands that build, test, lint and format-check the code.
+//! From that, [`agents_md`] and [`project_config`] render a starter
+//! `AGENTS.md` and `.codex/config.toml`.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::config_types::ProjectChecks;
+
+/// A language or build system found in the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    /// `None` for build systems that are not tied to a language, like Make.
+    pub language: Option<&'static str>,
+    pub build_system: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Detection {
+    pub stacks: Vec<Stack>,
+    pub checks: ProjectChecks,
+}
+
+/// The directory `codex init` writes to: the Git root above `cwd`, or `cwd`
+/// itself outside a repository.
+pub fn project_root(cwd: &Path) -> PathBuf {
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(cwd)
+        .to_path_buf()
+}
+
+/// Recognizes one stack in a project root.
+type Detector = fn(&Path) -> Option<(Stack, ProjectChecks)>;
+
+/// Detect the stacks used in `root` and their check commands. When several
+/// stacks provide the same kind of check, the first one detected wins; a
+/// `Makefile` only fills in what the others leave open.
+pub fn detect(root: &Path) -> Detection {
+    let mut detection = Detection::default();
+    let detectors: [Detector; 7] = [
+        detect_cargo,
+        detect_node,
+        detect_go,
+        detect_python,
+        detect_maven,
+        detect_gradle,
+        detect_make,
+    ];
+    for detector in detectors {
+        if let Some((stack, checks)) = detector(root) {
+            detection.stacks.push(stack);
+            let ours = &mut detection.checks;
+            ours.build = ours.build.take().or(checks.build);
+            ours.test = ours.test.take().or(checks.test);
+            ours.lint = ours.lint.take().or(checks.lint);
+            ours.format = ours.format.take().or(checks.format);
+        }
+    }
+    detection
+}
+
+fn read(root: &Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(root.join(name)).ok()
+}
+
+fn stack(language: Option<&'static str>, build_system: &str) -> Stack {
+    Stack {
+        language,
+        build_system: build_system.to_string(),
+    }
+}
+
+fn detect_cargo(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    let manifest = read(root, "Cargo.toml")?;
+    let workspace = if manifest.contains("[workspace]") {
+        " --workspace"
+    } else {
+        ""
+    };
+    Some((
+        stack(Some("Rust"), "Cargo"),
+        ProjectChecks {
+            build: Some(format!("cargo build{workspace}")),
+            test: Some(format!("cargo test{workspace}")),
+            lint: Some(format!(
+                "cargo clippy{workspace} --all-targets -- -D warnings"
+            )),
+            format: Some("cargo fmt --all -- --check".to_string()),
+        },
+    ))
+}
+
+fn detect_node(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    let manifest: serde_json::Value = serde_json::from_str(&read(root, "package.json")?).ok()?;
+    let manager = if root.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() {
+        "yarn"
+    } else if root.join("bun.lockb").exists() || root.join("bun.lock").exists() {
+        "bun"
+    } else {
+        "npm"
+    };
+    let language = if root.join("tsconfig.json").exists() {
+        "TypeScript"
+    } else {
+        "JavaScript"
+    };
+    let scripts = &manifest["scripts"];
+    let script = |names: &[&str]| {
+        names
+            .iter()
+            .find(|name| scripts.get(**name).is_some())
+            .map(|name| format!("{manager} run {name}"))
+    };
+    Some((
+        stack(Some(language), manager),
+        ProjectChecks {
+            build: script(&["build"]),
+            test: script(&["test"]),
+            lint: script(&["lint"]),
+            // Plain `format` scripts usually rewrite files instead of checking.
+            format: script(&["format:check", "fmt:check", "check-format"]),
+        },
+    ))
+}
+
+fn detect_go(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    read(root, "go.mod")?;
+    Some((
+        stack(Some("Go"), "Go modules"),
+        ProjectChecks {
+            build: Some("go build ./...".to_string()),
+            test: Some("go test ./...".to_string()),
+            lint: Some("go vet ./...".to_string()),
+            format: Some("test -z \"$(gofmt -l .)\"".to_string()),
+        },
+    ))
+}
+
+fn detect_python(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    let pyproject = read(root, "pyproject.toml");
+    if pyproject.is_none()
+        && !root.join("setup.py").exists()
+        && !root.join("requirements.txt").exists()
+    {
+        return None;
+    }
+    let pyproject = pyproject.unwrap_or_default();
+    let (build_system, run) = if root.join("uv.lock").exists() {
+        ("uv", "uv run ")
+    } else if root.join("poetry.lock").exists() {
+        ("Poetry", "poetry run ")
+    } else {
+        ("pip", "")
+    };
+    let uses_pytest = pyproject.contains("pytest")
+        || root.join("pytest.ini").exists()
+        || root.join("conftest.py").exists()
+        || root.join("tests").is_dir();
+    let uses_ruff = pyproject.contains("[tool.ruff") || root.join("ruff.toml").exists();
+    let format = if uses_ruff {
+        Some(format!("{run}ruff format --check ."))
+    } else if pyproject.contains("[tool.black") {
+        Some(format!("{run}black --check ."))
+    } else {
+        None
+    };
+    Some((
+        stack(Some("Python"), build_system),
+        ProjectChecks {
+            build: None,
+            test: uses_pytest.then(|| format!("{run}pytest")),
+            lint: uses_ruff.then(|| format!("{run}ruff check .")),
+            format,
+        },
+    ))
+}
+
+fn detect_maven(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    read(root, "pom.xml")?;
+    let mvn = if root.join("mvnw").exists() {
+        "./mvnw"
+    } else {
+        "mvn"
+    };
+    Some((
+        stack(Some("Java"), "Maven"),
+        ProjectChecks {
+            build: Some(format!("{mvn} -q compile")),
+            test: Some(format!("{mvn} -q test")),
+            lint: None,
+            format: None,
+        },
+    ))
+}
+
+fn detect_gradle(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    let language = if root.join("build.gradle.kts").exists() {
+        "Kotlin"
+    } else if root.join("build.gradle").exists() {
+        "Java"
+    } else {
+        return None;
+    };
+    let gradle = if root.join("gradlew").exists() {
+        "./gradlew"
+    } else {
+        "gradle"
+    };
+    Some((
+        stack(Some(language), "Gradle"),
+        ProjectChecks {
+            build: Some(format!("{gradle} assemble")),
+            test: Some(format!("{gradle} test")),
+            lint: None,
+            format: None,
+        },
+    ))
+}
+
+fn detect_make(root: &Path) -> Option<(Stack, ProjectChecks)> {
+    let makefile = read(root, "Makefile")?;
+    let target = |names: &[&str]| {
+        names
+            .iter()
+            .find(|name| {
+                makefile.lines().any(|line| {
+                    line.strip_prefix(**name)
+                        .is_some_and(|rest| rest.starts_with(':'))
+                })
+            })
+            .map(|name| format!("make {name}"))
+    };
+    Some((
+        stack(None, "Make"),
+        ProjectChecks {
+            build: target(&["build", "all"]),
+            test: target(&["test", "check"]),
+            lint: target(&["lint"]),
+            format: target(&["format-check", "fmt-check"]),
+        },
+    ))
+}
+
+/// A starter `AGENTS.md` for the project named `name`, with placeholders for
+/// what cannot be detected.
+pub fn agents_md(name: &str, detection: &Detection) -> String {
+    let mut doc = format!("# {name}\n\n## Project\n\n");
+    let languages = detection
+        .stacks
+        .iter()
+        .filter_map(|stack| {
+            let language = stack.language?;
+            Some(format!("{language} (built with {})", stack.build_system))
+        })
+        .collect::<Vec<_>>();
+    if !languages.is_empty() {
+        doc.push_str(&format!("Written in {}.\n\n", languages.join(", ")));
+    }
+    doc.push_str(
+        "<!-- Describe what the project does and how the code is organized. -->\n\n\
+         ## Checks\n\n",
+    );
+    let commands = detection.checks.commands();
+    if commands.is_empty() {
+        doc.push_str("<!-- List the commands that build, test and lint the project. -->\n\n");
+    } else {
+        doc.push_str("Run these before finishing a change:\n\n");
+        for (kind, command) in commands {
+            doc.push_str(&format!("- {kind}: `{command}`\n"));
+        }
+        doc.push('\n');
+    }
+    doc.push_str(
+        "## Conventions\n\n\
+         <!-- Code style, naming, test layout and commit message rules to follow. -->\n",
+    );
+    doc
+}
+
+/// The `.codex/config.toml` that records `checks`.
+pub fn project_config(checks: &ProjectChecks) -> String {
+    #[derive(Serialize)]
+    struct ProjectConfig<'a> {
+        checks: &'a ProjectChecks,
+    }
+    let table = toml::to_string(&ProjectConfig { checks }).unwrap_or_default();
+    format!(
+        "# Project settings for Codex, layered over ~/.codex/config.toml.\n\
+         # Codex asks the model to run these checks before it finishes.\n\n{table}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn project(files: &[(&str, &str)]) -> TempDir {
+        let root = TempDir::new().unwrap();
+        for (name, contents) in files {
+            std::fs::write(root.path().join(name), contents).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn detects_checks_from_marker_files() {
+        let root = project(&[
+            ("Cargo.toml", "[workspace]\nmembers = [\"core\"]\n"),
+            (
+                "package.json",
+                r#"{"scripts": {"build": "vite build", "lint": "eslint .", "format": "prettier -w ."}}"#,
+            ),
+            ("pnpm-lock.yaml", ""),
+            (
+                "Makefile",
+                "all:\n\tcargo build\nfmt-check:\n\tcargo fmt --check\n",
+            ),
+        ]);
+
+        let detection = detect(root.path());
+
+        assert_eq!(
+            detection.stacks,
+            vec![
+                stack(Some("Rust"), "Cargo"),
+                stack(Some("JavaScript"), "pnpm"),
+                stack(None, "Make"),
+            ]
+        );
+        // Cargo, detected first, provides every kind of check.
+        assert_eq!(
+            detection.checks.build.as_deref(),
+            Some("cargo build --workspace")
+        );
+        assert_eq!(
+            detection.checks.format.as_deref(),
+            Some("cargo fmt --all -- --check")
+        );
+
+        let root = project(&[
+            ("pyproject.toml", "[tool.ruff]\nline-length = 100\n"),
+            ("uv.lock", ""),
+            ("Makefile", "test:\n\tuv run pytest\n"),
+        ]);
+        assert_eq!(
+            detect(root.path()).checks,
+            ProjectChecks {
+                build: None,
+                test: Some("make test".to_string()),
+                lint: Some("uv run ruff check .".to_string()),
+                format: Some("uv run ruff format --check .".to_string()),
+            }
+        );
+
+        assert_eq!(detect(project(&[]).path()), Detection::default());
+    }
+
+    #[test]
+    fn renders_agents_md_and_config() {
+        let detection = detect(project(&[("go.mod", "module example.com/x\n")]).path());
+
+        let doc = agents_md("x", &detection);
+        assert!(
+            doc.contains("Written in Go (built with Go modules)."),
+            "{doc}"
+        );
+        assert!(doc.contains("- test: `go test ./...`\n"), "{doc}");
+
+        let config = project_config(&detection.checks);
+        let parsed: toml::Value = toml::from_str(&config).unwrap();
+        let checks: ProjectChecks = parsed["checks"].clone().try_into().unwrap();
+        assert_eq!(checks, detection.checks);
+    }
+}
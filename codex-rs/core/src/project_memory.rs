@@ -0,0 +1,267 @@
+//! Facts about a project remembered across sessions: key commands,
+//! architecture notes, pitfalls. Each memory is a Markdown file under
+//! `.codex/memory/` in the project root, written by the model through the
+//! `remember` tool or by the user with `codex memory edit`.
+//!
+//! When `[memory]` is enabled, the memories that share the most words with
+//! the first prompt of a session are sent ahead of it, as many as fit in
+//! `memory.max_bytes`.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::util::words;
+
+/// Where the memories live, relative to the project root.
+pub const MEMORY_DIR: &str = ".codex/memory";
+
+pub(crate) const REMEMBER_TOOL_NAME: &str = "remember";
+
+/// Words shorter than this say little about relevance ("a", "the", "and").
+const MIN_WORD_LEN: usize = 4;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RememberArgs {
+    pub name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryEntry {
+    pub name: String,
+    pub content: String,
+}
+
+/// The file holding memory `name` in the project at `root`. Names are made
+/// of lowercase letters, digits, `-` and `_`; a trailing `.md` is ignored.
+pub fn memory_path(root: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.strip_suffix(".md").unwrap_or(name);
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        anyhow::bail!("invalid memory name `{name}`: use lowercase letters, digits, `-` and `_`");
+    }
+    Ok(root.join(MEMORY_DIR).join(format!("{name}.md")))
+}
+
+/// The memories of the project at `root`, by name.
+pub fn list(root: &Path) -> Result<Vec<MemoryEntry>> {
+    let entries = match std::fs::read_dir(root.join(MEMORY_DIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut memories = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".md"))
+        else {
+            continue;
+        };
+        memories.push(MemoryEntry {
+            name: name.to_string(),
+            content: std::fs::read_to_string(&path)?,
+        });
+    }
+    memories.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(memories)
+}
+
+/// Write memory `name`, replacing it if it exists, and return its path.
+pub fn save(root: &Path, name: &str, content: &str) -> Result<PathBuf> {
+    let path = memory_path(root, name)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, memory_file(content))?;
+    Ok(path)
+}
+
+/// The `apply_patch` patch that does what [`save`] does, so that the model's
+/// memories are written the way its other edits are.
+pub(crate) fn save_patch(root: &Path, name: &str, content: &str) -> Result<String> {
+    let path = memory_path(root, name)?;
+    let added: String = memory_file(content)
+        .lines()
+        .map(|line| format!("+{line}\n"))
+        .collect();
+    let patch = match std::fs::read_to_string(&path) {
+        Ok(existing) => {
+            let removed: String = existing.lines().map(|line| format!("-{line}\n")).collect();
+            format!("*** Update File: {}\n@@\n{removed}{added}", path.display())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            format!("*** Add File: {}\n{added}", path.display())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(format!("*** Begin Patch\n{patch}*** End Patch"))
+}
+
+fn memory_file(content: &str) -> String {
+    let mut content = content.trim_end().to_string();
+    content.push('\n');
+    content
+}
+
+/// Delete memory `name`.
+pub fn forget(root: &Path, name: &str) -> Result<()> {
+    let path = memory_path(root, name)?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("no memory named `{name}` in {}", path.display())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The memories worth sending ahead of `prompt`: those sharing words with
+/// it, most shared words first, as many as fit in `max_bytes`.
+pub(crate) fn relevant<'a>(
+    memories: &'a [MemoryEntry],
+    prompt: &str,
+    max_bytes: usize,
+) -> Vec<&'a MemoryEntry> {
+    let prompt_words = words(prompt, MIN_WORD_LEN);
+    let mut scored: Vec<(usize, &MemoryEntry)> = memories
+        .iter()
+        .map(|memory| {
+            let memory_words = words(&format!("{} {}", memory.name, memory.content), MIN_WORD_LEN);
+            (prompt_words.intersection(&memory_words).count(), memory)
+        })
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut budget = max_bytes;
+    let mut selected = Vec::new();
+    for (_, memory) in scored {
+        if memory.content.len() <= budget {
+            budget -= memory.content.len();
+            selected.push(memory);
+        }
+    }
+    selected
+}
+
+/// The message that hands `memories` to the model.
+pub(crate) fn render(memories: &[&MemoryEntry]) -> String {
+    let mut text =
+        format!("Notes remembered from earlier sessions in this project ({MEMORY_DIR}/):");
+    for memory in memories {
+        text.push_str(&format!(
+            "\n\n## {}\n\n{}",
+            memory.name,
+            memory.content.trim_end()
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn memory(name: &str, content: &str) -> MemoryEntry {
+        MemoryEntry {
+            name: name.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn memories_are_saved_listed_and_forgotten() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        assert_eq!(list(root).unwrap(), vec![]);
+
+        save(root, "tests", "Run `just test`; it needs Docker.").unwrap();
+        save(
+            root,
+            "architecture.md",
+            "The parser lives in `src/parse`.\n\n",
+        )
+        .unwrap();
+        assert_eq!(
+            list(root).unwrap(),
+            vec![
+                memory("architecture", "The parser lives in `src/parse`.\n"),
+                memory("tests", "Run `just test`; it needs Docker.\n"),
+            ]
+        );
+
+        forget(root, "tests").unwrap();
+        assert_eq!(list(root).unwrap().len(), 1);
+        assert!(
+            forget(root, "tests")
+                .unwrap_err()
+                .to_string()
+                .starts_with("no memory named `tests`")
+        );
+        assert!(save(root, "../escape", "").is_err());
+    }
+
+    #[test]
+    fn save_patches_write_what_save_does() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let apply = |patch: String| {
+            codex_apply_patch::apply_patch(&patch, &mut Vec::new(), &mut Vec::new()).unwrap();
+        };
+
+        apply(save_patch(root, "tests", "Run `just test`.\n\nIt needs Docker.").unwrap());
+        apply(save_patch(root, "deploy", "Deploy with `make release`.").unwrap());
+        apply(save_patch(root, "deploy", "Deploy from a clean tree.\n\n").unwrap());
+        assert_eq!(
+            list(root).unwrap(),
+            vec![
+                memory("deploy", "Deploy from a clean tree.\n"),
+                memory("tests", "Run `just test`.\n\nIt needs Docker.\n"),
+            ]
+        );
+        assert!(save_patch(root, "../escape", "").is_err());
+    }
+
+    #[test]
+    fn the_most_relevant_memories_that_fit_are_selected() {
+        let memories = vec![
+            memory(
+                "deploy",
+                "Deploy with `make release`, never from a dirty tree.",
+            ),
+            memory(
+                "parser",
+                "The parser is generated; edit grammar.y, not parser.rs.",
+            ),
+            memory("tests", "Parser tests need the fixtures in tests/data."),
+        ];
+        let names = |selected: Vec<&MemoryEntry>| -> Vec<String> {
+            selected.into_iter().map(|m| m.name.clone()).collect()
+        };
+
+        assert_eq!(
+            names(relevant(&memories, "fix the failing parser tests", 1024)),
+            vec!["tests", "parser"]
+        );
+        assert_eq!(
+            names(relevant(&memories, "fix the failing parser tests", 50)),
+            vec!["tests"]
+        );
+        assert_eq!(
+            names(relevant(&memories, "rename a variable", 1024)),
+            Vec::<String>::new()
+        );
+    }
+}
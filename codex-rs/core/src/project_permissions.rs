@@ -0,0 +1,150 @@
+//! Sandbox grants remembered per workspace, so that access the user granted
+//! a denied command (say, writing to the pyenv shims folder) does not have to
+//! be granted again in every session.
+//!
+//! The grants live in `$CODEX_HOME/permissions.json`, keyed by the root of
+//! the workspace: the Git root containing the session's `cwd`, or the `cwd`
+//! itself outside a repository. They are kept out of the repository on
+//! purpose: a `.codex/` file checked into a cloned repo must not be able to
+//! widen the sandbox.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol::SandboxAccess;
+use crate::token_store::TokenFile;
+
+pub const PERMISSIONS_FILE: &str = "permissions.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PermissionsFile {
+    #[serde(default)]
+    workspaces: BTreeMap<PathBuf, Vec<SandboxAccess>>,
+}
+
+/// The workspace `cwd` belongs to: the closest ancestor with a `.git`, or
+/// `cwd` when there is none.
+pub fn workspace_root(cwd: &Path) -> PathBuf {
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(&cwd)
+        .to_path_buf()
+}
+
+/// The grants remembered for `workspace`, oldest first.
+pub fn load_grants(codex_home: &Path, workspace: &Path) -> Result<Vec<SandboxAccess>> {
+    let mut file = load(codex_home)?;
+    Ok(file.workspaces.remove(workspace).unwrap_or_default())
+}
+
+/// Remember `access` for `workspace`. Does nothing if it is remembered
+/// already.
+pub fn remember_grant(codex_home: &Path, workspace: &Path, access: SandboxAccess) -> Result<()> {
+    let mut file = load(codex_home)?;
+    let grants = file.workspaces.entry(workspace.to_path_buf()).or_default();
+    if grants.contains(&access) {
+        return Ok(());
+    }
+    grants.push(access);
+    TokenFile::new(codex_home, PERMISSIONS_FILE).save(&file)
+}
+
+/// Forget the grant at `index` in [`load_grants`] for `workspace`, returning
+/// it.
+pub fn revoke_grant(codex_home: &Path, workspace: &Path, index: usize) -> Result<SandboxAccess> {
+    let mut file = load(codex_home)?;
+    let grants = file.workspaces.entry(workspace.to_path_buf()).or_default();
+    if index >= grants.len() {
+        anyhow::bail!(
+            "no grant #{} for {} ({} remembered)",
+            index + 1,
+            workspace.display(),
+            grants.len()
+        );
+    }
+    let access = grants.remove(index);
+    if grants.is_empty() {
+        file.workspaces.remove(workspace);
+    }
+    TokenFile::new(codex_home, PERMISSIONS_FILE).save(&file)?;
+    Ok(access)
+}
+
+fn load(codex_home: &Path) -> Result<PermissionsFile> {
+    Ok(TokenFile::new(codex_home, PERMISSIONS_FILE)
+        .load()?
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn shims() -> SandboxAccess {
+        SandboxAccess::Write {
+            path: PathBuf::from("/home/me/.pyenv/shims"),
+        }
+    }
+
+    fn pypi() -> SandboxAccess {
+        SandboxAccess::Network {
+            host: "pypi.org".to_string(),
+        }
+    }
+
+    #[test]
+    fn grants_are_remembered_per_workspace() {
+        let codex_home = TempDir::new().unwrap();
+        let home = codex_home.path();
+        let repo = Path::new("/work/repo");
+        let other = Path::new("/work/other");
+
+        assert_eq!(load_grants(home, repo).unwrap(), vec![]);
+        remember_grant(home, repo, shims()).unwrap();
+        remember_grant(home, repo, pypi()).unwrap();
+        remember_grant(home, repo, shims()).unwrap();
+        remember_grant(home, other, pypi()).unwrap();
+
+        assert_eq!(load_grants(home, repo).unwrap(), vec![shims(), pypi()]);
+        assert_eq!(load_grants(home, other).unwrap(), vec![pypi()]);
+    }
+
+    #[test]
+    fn revoking_forgets_one_grant() {
+        let codex_home = TempDir::new().unwrap();
+        let home = codex_home.path();
+        let repo = Path::new("/work/repo");
+        remember_grant(home, repo, shims()).unwrap();
+        remember_grant(home, repo, pypi()).unwrap();
+
+        assert_eq!(revoke_grant(home, repo, 0).unwrap(), shims());
+        assert_eq!(load_grants(home, repo).unwrap(), vec![pypi()]);
+        assert_eq!(
+            revoke_grant(home, repo, 1).unwrap_err().to_string(),
+            "no grant #2 for /work/repo (1 remembered)"
+        );
+    }
+
+    #[test]
+    fn the_workspace_is_the_git_root() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("repo/.git")).unwrap();
+        std::fs::create_dir_all(root.join("repo/src/bin")).unwrap();
+
+        assert_eq!(
+            workspace_root(&root.join("repo/src/bin")),
+            root.join("repo")
+        );
+        assert_eq!(workspace_root(&root), root);
+    }
+}
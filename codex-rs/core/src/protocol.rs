@@ -4,22 +4,47 @@
 //! between user and agent.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
 
 
 use mcp_types::CallToolResult;
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaSettings;
 use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::SamplingParams;
+use crate::copilot_plan::CopilotPlan;
 use crate::message_history::HistoryEntry;
 use crate::model_provider_info::ModelProviderInfo;
 
+/// Version of the protocol, raised on changes that break existing clients.
+/// Clients check it with `Op::Handshake`.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// JSON Schema of the protocol: the `submission`s clients send and the
+/// `event`s they receive, for generating bindings in other languages.
+pub fn json_schema() -> serde_json::Result<serde_json::Value> {
+    let settings = SchemaSettings::draft2019_09();
+    let submission = settings
+        .clone()
+        .into_generator()
+        .into_root_schema_for::<Submission>();
+    let event = settings.into_generator().into_root_schema_for::<Event>();
+    Ok(serde_json::json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "submission": serde_json::to_value(submission)?,
+        "event": serde_json::to_value(event)?,
+    }))
+}
+
 /// Submission Queue Entry - requests from user
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Submission {
     /// Unique id for this Submission to correlate with Events
     pub id: String,
@@ -28,7 +53,7 @@ pub struct Submission {
 }
 
 /// Submission operation
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[allow(clippy::large_enum_variant)]
 #[non_exhaustive]
@@ -43,6 +68,8 @@ pub enum Op {
 
         model_reasoning_effort: ReasoningEffortConfig,
         model_reasoning_summary: ReasoningSummaryConfig,
+        #[serde(default)]
+        sampling: SamplingParams,
 
         /// Model instructions
         instructions: Option<String>,
@@ -69,22 +96,76 @@ pub enum Op {
         /// `ConfigureSession` operation so that the business-logic layer can
         /// operate deterministically.
         cwd: std::path::PathBuf,
+
+        /// What the agent may do in this session. `Ask` overrides the
+        /// approval and sandbox policies above.
+        #[serde(default)]
+        mode: SessionMode,
+
+        /// Show shell commands instead of running them, answering the model
+        /// with a "not executed" result.
+        #[serde(default)]
+        dry_run: bool,
     },
 
     /// Abort current task.
     /// This server sends no corresponding Event
-    Interrupt,
+    ///
+    /// Output the model produced in the interrupted turn is kept in the
+    /// conversation. When `replace_with` is set, a new task is started right
+    /// away with it as the user's instruction. A Copilot sign-in waiting for
+    /// the user is cancelled too.
+    Interrupt {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        replace_with: Option<String>,
+    },
+
+    /// Change the model, approval policy, mode or dry run of the session
+    /// from the next model request on, keeping the conversation and the MCP
+    /// servers. Unset fields keep their current value. Answered with
+    /// `TurnContextOverridden`, or with an `Error` when the model cannot take
+    /// over the conversation.
+    OverrideTurnContext {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        model: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        effort: Option<ReasoningEffortConfig>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        summary: Option<ReasoningSummaryConfig>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        approval_policy: Option<AskForApproval>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        mode: Option<SessionMode>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        dry_run: Option<bool>,
+    },
 
     /// Authenticate with GitHub Copilot using device flow
     CopilotAuth,
 
+    /// Sign in with a ChatGPT account in the browser
+    ChatGptAuth,
+
     /// Input from the user
     UserInput {
         /// User input items, see `InputItem`
         items: Vec<InputItem>,
+
+        /// Constrains the final agent message of the task this input starts.
+        /// Ignored when the input is added to a task that is already running.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        response_format: Option<ResponseFormat>,
     },
 
-    /// Approve a command execution
+    /// Approve a command execution, or answer a sandbox escalation request.
     ExecApproval {
         /// The id of the submission we are approving
         id: String,
@@ -100,6 +181,15 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Answer a `TurnDiffApprovalRequest`: keep the changes to `approved` and
+    /// restore every other file edited during the turn.
+    TurnDiffApproval {
+        /// The id of the submission whose changes are being reviewed
+        id: String,
+        /// Files whose changes are kept.
+        approved: Vec<PathBuf>,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -111,10 +201,41 @@ pub enum Op {
 
     /// Request a single history entry identified by `log_id` + `offset`.
     GetHistoryEntryRequest { offset: usize, log_id: u64 },
+
+    /// Ask the model to summarize the conversation so far and replace the
+    /// context carried into later turns with that summary. Runs as a regular
+    /// task, so it emits `TaskStarted`/`TaskComplete`.
+    Compact,
+
+    /// Take a checkpoint of the conversation and the workspace, on top of the
+    /// one taken at the start of every task. Answered with
+    /// `CheckpointCreated`.
+    Checkpoint,
+
+    /// Take the conversation, and the files the agent patched since, back to
+    /// checkpoint `checkpoint`; later checkpoints are dropped. Answered with
+    /// `Rewound`, or with an `Error` while a task is running.
+    Rewind { checkpoint: usize },
+
+    /// Deliver only the events of `kinds` from now on; an empty list restores
+    /// the full stream. `Error` events are always delivered. Takes effect
+    /// for events not yet received, without an answering event.
+    Subscribe { kinds: Vec<EventKind> },
+
+    /// Check that the client and Codex speak the same protocol version;
+    /// best sent first. Answered with `Handshake`, or with an `Error` when
+    /// the versions differ.
+    Handshake { protocol_version: u32 },
+
+    /// A submission from a newer client that this version does not know.
+    /// Never sent; answered with an `Error`.
+    #[serde(other)]
+    #[schemars(skip)]
+    Unknown,
 }
 
 /// Determines how liberally commands are auto‑approved by the system.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum AskForApproval {
     /// Under this policy, only “known safe” commands—as determined by
@@ -140,8 +261,23 @@ pub enum AskForApproval {
     Never,
 }
 
+/// What the agent is allowed to do in a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionMode {
+    /// The agent edits files and runs commands as the approval and sandbox
+    /// policies allow.
+    #[default]
+    Code,
+
+    /// Read-only codebase Q&A. Patches, MCP tools and every command that is
+    /// not known to only read files are refused, whatever the approval
+    /// policy, and the commands that do run are sandboxed read-only.
+    Ask,
+}
+
 /// Determines execution restrictions for model shell commands
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct SandboxPolicy {
     permissions: Vec<SandboxPermission>,
@@ -165,6 +301,16 @@ impl From<codex_common::ApprovalModeCliArg> for AskForApproval {
     }
 }
 
+#[cfg(feature = "cli")]
+impl From<codex_common::SessionModeCliArg> for SessionMode {
+    fn from(value: codex_common::SessionModeCliArg) -> Self {
+        match value {
+            codex_common::SessionModeCliArg::Code => SessionMode::Code,
+            codex_common::SessionModeCliArg::Ask => SessionMode::Ask,
+        }
+    }
+}
+
 #[cfg(feature = "cli")]
 impl From<codex_common::AskForApproval> for AskForApproval {
     fn from(value: codex_common::AskForApproval) -> Self {
@@ -183,6 +329,7 @@ impl From<codex_common::SandboxPermission> for SandboxPermission {
             codex_common::SandboxPermission::DiskFullReadAccess => SandboxPermission::DiskFullReadAccess,
             codex_common::SandboxPermission::DiskWritePlatformUserTempFolder => SandboxPermission::DiskWritePlatformUserTempFolder,
             codex_common::SandboxPermission::DiskWritePlatformGlobalTempFolder => SandboxPermission::DiskWritePlatformGlobalTempFolder,
+            codex_common::SandboxPermission::DiskReadFolder(path) => SandboxPermission::DiskReadFolder { folder: path },
             codex_common::SandboxPermission::DiskWriteCwd => SandboxPermission::DiskWriteCwd,
             codex_common::SandboxPermission::DiskWriteFolder(path) => SandboxPermission::DiskWriteFolder { folder: path },
             codex_common::SandboxPermission::DiskFullWriteAccess => SandboxPermission::DiskFullWriteAccess,
@@ -244,6 +391,54 @@ impl SandboxPolicy {
             .any(|perm| matches!(perm, SandboxPermission::NetworkFullAccess))
     }
 
+    /// The same policy with network access revoked, for offline mode.
+    pub fn without_network_access(mut self) -> Self {
+        self.permissions
+            .retain(|perm| !matches!(perm, SandboxPermission::NetworkFullAccess));
+        self
+    }
+
+    /// The same policy without the permissions `forbidden` matches, for the
+    /// managed config's `forbidden_sandbox_permissions`.
+    pub fn without_permissions(mut self, forbidden: impl Fn(&SandboxPermission) -> bool) -> Self {
+        self.permissions.retain(|perm| !forbidden(perm));
+        self
+    }
+
+    /// The same policy with reads limited to `folder` (and the system
+    /// directories programs need), for `--focus`. An unrestricted policy is
+    /// left as is.
+    pub fn with_reads_limited_to(mut self, folder: &Path) -> Self {
+        if self.is_unrestricted() {
+            return self;
+        }
+        self.permissions
+            .retain(|perm| !matches!(perm, SandboxPermission::DiskFullReadAccess));
+        self.with_permission(SandboxPermission::DiskReadFolder {
+            folder: folder.to_path_buf(),
+        })
+    }
+
+    /// Folders readable without `DiskFullReadAccess`.
+    pub fn get_readable_roots(&self) -> Vec<PathBuf> {
+        self.permissions
+            .iter()
+            .filter_map(|perm| match perm {
+                SandboxPermission::DiskReadFolder { folder } => Some(folder.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The same policy with `permission` added, e.g. a grant made during the
+    /// session.
+    pub fn with_permission(mut self, permission: SandboxPermission) -> Self {
+        if !self.permissions.contains(&permission) {
+            self.permissions.push(permission);
+        }
+        self
+    }
+
     pub fn get_writable_roots_with_cwd(&self, cwd: &Path) -> Vec<PathBuf> {
         let mut writable_roots = Vec::<PathBuf>::new();
         for perm in &self.permissions {
@@ -286,7 +481,7 @@ impl SandboxPolicy {
                 DiskWriteFolder { folder } => {
                     writable_roots.push(folder.clone());
                 }
-                DiskFullReadAccess | NetworkFullAccess => {}
+                DiskFullReadAccess | DiskReadFolder { .. } | NetworkFullAccess => {}
                 DiskFullWriteAccess => {
                     // Currently, we expect callers to only invoke this method
                     // after verifying has_full_disk_write_access() is false.
@@ -305,12 +500,16 @@ impl SandboxPolicy {
 
 /// Permissions that should be granted to the sandbox in which the agent
 /// operates.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum SandboxPermission {
     /// Is allowed to read all files on disk.
     DiskFullReadAccess,
 
+    /// Without `DiskFullReadAccess`, is allowed to read the specified folder
+    /// in place of the current working directory.
+    DiskReadFolder { folder: PathBuf },
+
     /// Is allowed to write to the operating system's temp dir that
     /// is restricted to the user the agent is running as. For
     /// example, on macOS, this is generally something under
@@ -339,7 +538,7 @@ pub enum SandboxPermission {
 
 /// User input
 #[non_exhaustive]
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum InputItem {
     Text {
@@ -355,10 +554,38 @@ pub enum InputItem {
     LocalImage {
         path: std::path::PathBuf,
     },
+
+    /// Local text file mentioned by the user (e.g. `@src/main.rs`). Its
+    /// contents are inlined as text, within the `[attachments]` size limits,
+    /// before they are sent.
+    LocalFile {
+        path: std::path::PathBuf,
+    },
+}
+
+/// Default for `attachments.max_file_bytes`: larger mentioned files are sent
+/// as their first and last lines.
+pub const MAX_FILE_MENTION_BYTES: usize = 64 * 1024;
+
+impl InputItem {
+    /// Returns `true` for the image variants.
+    pub fn is_image(&self) -> bool {
+        matches!(self, InputItem::Image { .. } | InputItem::LocalImage { .. })
+    }
+}
+
+/// Structured output: the agent's final message must be a JSON value that
+/// matches `schema`. The schema is passed to providers that support
+/// structured output, and the message is checked once the task ends; a
+/// message that does not match is sent back to the model to be fixed.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
+pub struct ResponseFormat {
+    /// JSON Schema of the final message.
+    pub schema: serde_json::Value,
 }
 
 /// Event Queue Entry - events from agent
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Event {
     /// Submission `id` that this event is correlated with.
     pub id: String,
@@ -367,7 +594,7 @@ pub struct Event {
 }
 
 /// Response event from the agent
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum EventMsg {
     /// Error while executing a submission
@@ -382,12 +609,27 @@ pub enum EventMsg {
     /// Agent text output message
     AgentMessage(AgentMessageEvent),
 
+    /// Incremental chunk of the agent message currently being streamed. The
+    /// complete text follows in an `AgentMessage` event.
+    AgentMessageDelta(AgentMessageDeltaEvent),
+
     /// Reasoning event from agent.
     AgentReasoning(AgentReasoningEvent),
 
+    /// Incremental chunk of the reasoning summary currently being streamed.
+    /// The complete text follows in one or more `AgentReasoning` events.
+    AgentReasoningDelta(AgentReasoningDeltaEvent),
+
     /// Ack the client's configure message.
     SessionConfigured(SessionConfiguredEvent),
 
+    /// Ack of `Op::OverrideTurnContext`.
+    TurnContextOverridden(TurnContextOverriddenEvent),
+
+    /// Sent after `SessionConfigured` when the session uses GitHub Copilot
+    /// and the token tells which plan it belongs to.
+    CopilotPlanDetected(CopilotPlanDetectedEvent),
+
     McpToolCallBegin(McpToolCallBeginEvent),
 
     McpToolCallEnd(McpToolCallEndEvent),
@@ -399,14 +641,32 @@ pub enum EventMsg {
 
     ExecApprovalRequest(ExecApprovalRequestEvent),
 
+    /// A sandboxed command failed because it was denied a specific path or
+    /// host; answered with `Op::ExecApproval`.
+    SandboxEscalationRequest(SandboxEscalationRequestEvent),
+
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// With `review_edits = "per-turn"`, the net changes of the task that just
+    /// finished, to be answered with `Op::TurnDiffApproval`.
+    TurnDiffApprovalRequest(TurnDiffApprovalRequestEvent),
+
     /// Copilot authentication started
     CopilotAuthStarted(CopilotAuthStartedEvent),
 
+    /// Sent before each poll while the Copilot device code waits for the
+    /// user, so a UI can show the time left.
+    CopilotAuthProgress(CopilotAuthProgressEvent),
+
     /// Copilot authentication completed
     CopilotAuthComplete(CopilotAuthCompleteEvent),
 
+    /// ChatGPT sign-in started; the user finishes it in the browser
+    ChatGptAuthStarted(ChatGptAuthStartedEvent),
+
+    /// ChatGPT sign-in completed
+    ChatGptAuthComplete(ChatGptAuthCompleteEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that the agent is about to apply a code patch. Mirrors
@@ -416,33 +676,312 @@ pub enum EventMsg {
     /// Notification that a patch application has finished.
     PatchApplyEnd(PatchApplyEndEvent),
 
+    /// With `apply_mode = "propose"`, a patch the agent wants applied. Codex
+    /// does not write it; the front-end is expected to.
+    PatchProposed(PatchProposedEvent),
+
     /// Response to GetHistoryEntryRequest.
     GetHistoryEntryResponse(GetHistoryEntryResponseEvent),
+
+    /// Token usage reported by the provider for the model request that just
+    /// finished.
+    TokenCount(TokenUsage),
+
+    /// Progress of a sub-agent started through the `spawn_agents` tool.
+    SubAgent(SubAgentEvent),
+
+    /// The agent published a new version of its plan through the
+    /// `update_plan` tool.
+    PlanUpdated(PlanUpdatedEvent),
+
+    /// Likely secrets were replaced with placeholders in content about to be
+    /// sent to the model.
+    SecretsRedacted(SecretsRedactedEvent),
+
+    /// The next model request is held back by the provider's rate limit.
+    RateLimited(RateLimitedEvent),
+
+    /// A tool call was not run because its arguments do not match the
+    /// tool's parameters; its output asks the model to correct them.
+    ToolCallRepair(ToolCallRepairEvent),
+
+    /// A shell command the agent proposed was not run because the session is
+    /// a dry run.
+    DryRunExec(DryRunExecEvent),
+
+    /// A checkpoint was taken that `Op::Rewind` can go back to.
+    CheckpointCreated(CheckpointCreatedEvent),
+
+    /// The conversation and the workspace were taken back to a checkpoint.
+    Rewound(RewoundEvent),
+
+    /// Ack of `Op::Handshake`.
+    Handshake(HandshakeEvent),
+
+    /// An event from a newer Codex that this version does not know, so that
+    /// clients built against this crate keep reading the stream. Never
+    /// sent.
+    #[serde(other)]
+    #[schemars(skip)]
+    Unknown,
+}
+
+/// Category of an [`EventMsg`], for `Op::Subscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Tasks starting and completing, and errors.
+    Task,
+    /// Complete agent messages.
+    Message,
+    /// Streamed chunks of agent messages.
+    MessageDelta,
+    /// Complete reasoning summaries.
+    Reasoning,
+    /// Streamed chunks of reasoning summaries.
+    ReasoningDelta,
+    /// Commands, patches, MCP tool calls, sub-agents and plan updates.
+    Tool,
+    /// Requests for the user's approval, which the task waits on.
+    Approval,
+    /// Acks of session-level submissions: configuration, history lookups,
+    /// checkpoints.
+    Session,
+    /// Sign-in progress.
+    Auth,
+    /// Token counts and rate limits.
+    Usage,
+    /// Background notices, such as redacted secrets.
+    Background,
+}
+
+impl EventMsg {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            EventMsg::Error(_) | EventMsg::TaskStarted | EventMsg::TaskComplete(_) => {
+                EventKind::Task
+            }
+            EventMsg::AgentMessage(_) => EventKind::Message,
+            EventMsg::AgentMessageDelta(_) => EventKind::MessageDelta,
+            EventMsg::AgentReasoning(_) => EventKind::Reasoning,
+            EventMsg::AgentReasoningDelta(_) => EventKind::ReasoningDelta,
+            EventMsg::McpToolCallBegin(_)
+            | EventMsg::McpToolCallEnd(_)
+            | EventMsg::ExecCommandBegin(_)
+            | EventMsg::ExecCommandEnd(_)
+            | EventMsg::PatchApplyBegin(_)
+            | EventMsg::PatchApplyEnd(_)
+            | EventMsg::PatchProposed(_)
+            | EventMsg::DryRunExec(_)
+            | EventMsg::ToolCallRepair(_)
+            | EventMsg::SubAgent(_)
+            | EventMsg::PlanUpdated(_) => EventKind::Tool,
+            EventMsg::ExecApprovalRequest(_)
+            | EventMsg::SandboxEscalationRequest(_)
+            | EventMsg::ApplyPatchApprovalRequest(_)
+            | EventMsg::TurnDiffApprovalRequest(_) => EventKind::Approval,
+            EventMsg::SessionConfigured(_)
+            | EventMsg::TurnContextOverridden(_)
+            | EventMsg::CopilotPlanDetected(_)
+            | EventMsg::GetHistoryEntryResponse(_)
+            | EventMsg::CheckpointCreated(_)
+            | EventMsg::Rewound(_)
+            | EventMsg::Handshake(_) => EventKind::Session,
+            EventMsg::CopilotAuthStarted(_)
+            | EventMsg::CopilotAuthProgress(_)
+            | EventMsg::CopilotAuthComplete(_)
+            | EventMsg::ChatGptAuthStarted(_)
+            | EventMsg::ChatGptAuthComplete(_) => EventKind::Auth,
+            EventMsg::TokenCount(_) | EventMsg::RateLimited(_) => EventKind::Usage,
+            EventMsg::BackgroundEvent(_) | EventMsg::SecretsRedacted(_) | EventMsg::Unknown => {
+                EventKind::Background
+            }
+        }
+    }
 }
 
 // Individual event payload types matching each `EventMsg` variant.
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ErrorEvent {
     pub message: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Token usage for a single model request. Providers that do not report the
+/// cached/reasoning breakdown leave those fields unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub cached_input_tokens: Option<u64>,
+    pub output_tokens: u64,
+    pub reasoning_output_tokens: Option<u64>,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Accumulate `other` into `self`, e.g. to keep a session total.
+    pub fn add_assign(&mut self, other: &TokenUsage) {
+        fn add_opt(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+            match (a, b) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+            }
+        }
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens = add_opt(self.cached_input_tokens, other.cached_input_tokens);
+        self.output_tokens += other.output_tokens;
+        self.reasoning_output_tokens =
+            add_opt(self.reasoning_output_tokens, other.reasoning_output_tokens);
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// An event emitted by a sub-agent, namespaced so front-ends can tell the
+/// sub-agents of one `spawn_agents` call apart from each other and from the
+/// main agent.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SubAgentEvent {
+    /// Identifies the sub-agent within the session, e.g. `2.1` for the first
+    /// sub-agent of the second `spawn_agents` call.
+    pub agent_id: String,
+    /// The subtask the sub-agent was given.
+    pub task: String,
+    pub msg: Box<EventMsg>,
+}
+
+impl SubAgentEvent {
+    /// One-line description of the event for front-ends that show sub-agent
+    /// progress as a log, or `None` if it is not worth showing.
+    pub fn summary(&self) -> Option<String> {
+        let id = &self.agent_id;
+        let text = match self.msg.as_ref() {
+            EventMsg::TaskStarted => format!("started: {}", self.task),
+            EventMsg::TaskComplete(_) => "finished".to_string(),
+            EventMsg::Error(ErrorEvent { message }) => format!("error: {message}"),
+            EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                let first_line = message.lines().next().unwrap_or_default();
+                format!("says: {first_line}")
+            }
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent { command, .. }) => {
+                format!("$ {}", command.join(" "))
+            }
+            EventMsg::PatchApplyBegin(PatchApplyBeginEvent { changes, .. }) => {
+                format!("editing {} file(s)", changes.len())
+            }
+            EventMsg::PatchProposed(PatchProposedEvent { changes, .. }) => {
+                format!("proposing edits to {} file(s)", changes.len())
+            }
+            EventMsg::McpToolCallBegin(McpToolCallBeginEvent { server, tool, .. }) => {
+                format!("tool {server}.{tool}")
+            }
+            EventMsg::PlanUpdated(PlanUpdatedEvent { plan, .. }) => {
+                let done = plan
+                    .iter()
+                    .filter(|item| item.status == StepStatus::Completed)
+                    .count();
+                format!("plan: {done}/{} steps done", plan.len())
+            }
+            EventMsg::SecretsRedacted(event) => event.summary(),
+            EventMsg::RateLimited(event) => event.summary(),
+            EventMsg::ToolCallRepair(event) => event.summary(),
+            EventMsg::DryRunExec(event) => event.summary(),
+            _ => return None,
+        };
+        Some(format!("[agent {id}] {text}"))
+    }
+}
+
+/// The agent's current plan. Every update carries the complete plan, so
+/// front-ends can replace whatever they showed before.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct PlanUpdatedEvent {
+    /// Why the plan changed, if the agent said.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    pub plan: Vec<PlanItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct PlanItem {
+    pub step: String,
+    pub status: StepStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    InProgress,
+    Completed,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct TaskCompleteEvent {
     pub last_agent_message: Option<String>,
+    /// What the task took. Unset for tasks not run by this session, e.g.
+    /// those of a replay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<TaskStats>,
+}
+
+/// Time, tokens and cost of a task.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct TaskStats {
+    /// From the task starting to it completing.
+    pub duration_ms: u64,
+    /// Part of `duration_ms` spent waiting on the model; the rest went to
+    /// tool calls and approvals.
+    pub model_ms: u64,
+    /// Model requests made.
+    pub requests: u32,
+    /// Summed over the requests; `cached_input_tokens` is the part of the
+    /// input served from the provider's prompt cache.
+    pub usage: TokenUsage,
+    /// Estimated cost in USD at list prices, of the requests to models whose
+    /// prices are known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+impl TaskStats {
+    /// Accumulate `other` into `self`, e.g. to total a session.
+    pub fn add_assign(&mut self, other: &TaskStats) {
+        self.duration_ms += other.duration_ms;
+        self.model_ms += other.model_ms;
+        self.requests += other.requests;
+        self.usage.add_assign(&other.usage);
+        self.add_cost(other.cost_usd);
+    }
+
+    pub fn add_cost(&mut self, cost_usd: Option<f64>) {
+        self.cost_usd = match (self.cost_usd, cost_usd) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+        };
+    }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct AgentMessageEvent {
     pub message: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct AgentMessageDeltaEvent {
+    pub delta: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct AgentReasoningEvent {
     pub text: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct AgentReasoningDeltaEvent {
+    pub delta: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct McpToolCallBeginEvent {
     /// Identifier so this can be paired with the McpToolCallEnd event.
     pub call_id: String,
@@ -454,11 +993,12 @@ pub struct McpToolCallBeginEvent {
     pub arguments: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct McpToolCallEndEvent {
     /// Identifier for the corresponding McpToolCallBegin that finished.
     pub call_id: String,
     /// Result of the tool call. Note this could be an error.
+    #[schemars(with = "Result<serde_json::Value, String>")]
     pub result: Result<CallToolResult, String>,
 }
 
@@ -471,7 +1011,7 @@ impl McpToolCallEndEvent {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ExecCommandBeginEvent {
     /// Identifier so this can be paired with the ExecCommandEnd event.
     pub call_id: String,
@@ -481,7 +1021,7 @@ pub struct ExecCommandBeginEvent {
     pub cwd: PathBuf,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ExecCommandEndEvent {
     /// Identifier for the ExecCommandBegin that finished.
     pub call_id: String,
@@ -493,7 +1033,7 @@ pub struct ExecCommandEndEvent {
     pub exit_code: i32,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ExecApprovalRequestEvent {
     /// The command to be executed.
     pub command: Vec<String>,
@@ -502,9 +1042,93 @@ pub struct ExecApprovalRequestEvent {
     /// Optional human-readable reason for the approval (e.g. retry without sandbox).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// What the command looks like it would do, when that is anything risky.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<CommandRisk>,
+}
+
+/// How much harm a command could do if it is not what the user expects.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    /// Writes files in the workspace.
+    Low,
+    /// Reaches the network, installs packages or deletes files in the
+    /// workspace.
+    Medium,
+    /// Runs with elevated privileges, runs a downloaded script, or deletes
+    /// or writes files outside the workspace.
+    High,
+}
+
+/// Result of statically classifying a command before it is approved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CommandRisk {
+    pub level: RiskLevel,
+    /// Everything risky that was found, most serious first, e.g. "deletes
+    /// files outside workspace; accesses the network".
+    pub explanation: String,
+}
+
+impl fmt::Display for CommandRisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            RiskLevel::Low => "LOW",
+            RiskLevel::Medium => "MEDIUM",
+            RiskLevel::High => "HIGH",
+        };
+        write!(f, "{level}: {}", self.explanation)
+    }
+}
+
+/// Access a sandboxed command was denied, which the user can grant for the
+/// rest of the session.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SandboxAccess {
+    /// Writes under `path`.
+    Write { path: PathBuf },
+    /// Network access, asked for because the command failed to reach
+    /// `host`. The sandbox cannot limit network access to one host, so
+    /// granting it lets sandboxed commands reach the whole network.
+    Network { host: String },
+}
+
+impl SandboxAccess {
+    /// The sandbox permission that grants this access.
+    pub fn permission(&self) -> SandboxPermission {
+        match self {
+            SandboxAccess::Write { path } => SandboxPermission::DiskWriteFolder {
+                folder: path.clone(),
+            },
+            SandboxAccess::Network { .. } => SandboxPermission::NetworkFullAccess,
+        }
+    }
+}
+
+impl fmt::Display for SandboxAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxAccess::Write { path } => write!(f, "write access to {}", path.display()),
+            SandboxAccess::Network { host } => write!(f, "full network access (to reach {host})"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SandboxEscalationRequestEvent {
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    /// What approving grants for the rest of the session. The command is then
+    /// retried in the sandbox. [`ReviewDecision::ApprovedForSession`] also
+    /// remembers it for the workspace, for later sessions.
+    pub access: SandboxAccess,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ApplyPatchApprovalRequestEvent {
     pub changes: HashMap<PathBuf, FileChange>,
     /// Optional explanatory reason (e.g. request for extra write access).
@@ -515,24 +1139,233 @@ pub struct ApplyPatchApprovalRequestEvent {
     pub grant_root: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TurnDiffApprovalRequestEvent {
+    /// Net change of every file edited during the turn.
+    pub changes: HashMap<PathBuf, FileChange>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SecretsRedactedEvent {
+    /// Where the secrets were found, e.g. `shell output` or a file path.
+    pub source: String,
+    pub secrets: Vec<RedactedSecret>,
+}
+
+impl SecretsRedactedEvent {
+    /// One-line description for the transcript, e.g.
+    /// `redacted 2 secret(s) from shell output: env-value ×2`.
+    pub fn summary(&self) -> String {
+        let total: usize = self.secrets.iter().map(|secret| secret.count).sum();
+        let kinds = self
+            .secrets
+            .iter()
+            .map(|secret| match secret.count {
+                1 => secret.kind.clone(),
+                count => format!("{} ×{count}", secret.kind),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("redacted {total} secret(s) from {}: {kinds}", self.source)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct DryRunExecEvent {
+    pub call_id: String,
+    /// The command that would have been run.
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+}
+
+impl DryRunExecEvent {
+    /// One-line description for the transcript, e.g. `dry-run, not
+    /// executed: rm -rf target`.
+    pub fn summary(&self) -> String {
+        let command = shlex::try_join(self.command.iter().map(String::as_str))
+            .unwrap_or_else(|_| self.command.join(" "));
+        format!("dry-run, not executed: {command}")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CheckpointCreatedEvent {
+    /// Numbered from 1 in the session.
+    pub number: usize,
+    /// Taken at the start of a task, before its prompt, rather than on
+    /// `Op::Checkpoint`.
+    pub automatic: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct HandshakeEvent {
+    /// Always [`PROTOCOL_VERSION`].
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RewoundEvent {
+    pub checkpoint: usize,
+    /// Files restored to their content at the checkpoint.
+    pub restored: Vec<PathBuf>,
+}
+
+impl RewoundEvent {
+    /// One-line description for the transcript, e.g. `rewound to checkpoint
+    /// 2, restored 3 file(s)`.
+    pub fn summary(&self) -> String {
+        format!(
+            "rewound to checkpoint {}, restored {} file(s)",
+            self.checkpoint,
+            self.restored.len()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ToolCallRepairEvent {
+    pub call_id: String,
+    /// Name of the tool as shown to users, e.g. `shell` or `server/tool`.
+    pub tool: String,
+    /// What is wrong with the arguments.
+    pub violation: String,
+    /// How many calls in a row the model has been asked to correct.
+    pub attempt: u32,
+    pub max_attempts: u32,
+}
+
+impl ToolCallRepairEvent {
+    /// One-line description for the transcript, e.g. `shell call with
+    /// invalid arguments ($ is missing the required property `command`);
+    /// asked the model to correct it (1/3)`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} call with invalid arguments ({}); asked the model to correct it ({}/{})",
+            self.tool, self.violation, self.attempt, self.max_attempts
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RateLimitedEvent {
+    /// Name of the provider, e.g. `OpenAI`.
+    pub provider: String,
+    /// How long the request waits before it is sent.
+    pub wait_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TurnContextOverriddenEvent {
+    /// The model used from the next model request on.
+    pub model: String,
+    pub effort: ReasoningEffortConfig,
+    pub summary: ReasoningSummaryConfig,
+    /// Context window of `model`, if it is a model Codex knows.
+    pub context_window: Option<u64>,
+    pub approval_policy: AskForApproval,
+    pub mode: SessionMode,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CopilotPlanDetectedEvent {
+    pub plan: CopilotPlan,
+    /// The token's `sku` the plan was told from.
+    pub sku: String,
+    /// The models the plan is limited to, so that front-ends can hide the
+    /// others; empty when it is not limited.
+    pub models: Vec<String>,
+}
+
+impl CopilotPlanDetectedEvent {
+    pub fn summary(&self) -> String {
+        if self.models.is_empty() {
+            format!("GitHub Copilot {} plan", self.plan)
+        } else {
+            format!(
+                "GitHub Copilot {} plan, with these models: {}",
+                self.plan,
+                self.models.join(", ")
+            )
+        }
+    }
+}
+
+impl TurnContextOverriddenEvent {
+    pub fn summary(&self) -> String {
+        match self.context_window {
+            Some(context_window) => format!(
+                "model set to {} (reasoning effort {}, {context_window} token context window)",
+                self.model, self.effort
+            ),
+            None => format!(
+                "model set to {} (reasoning effort {})",
+                self.model, self.effort
+            ),
+        }
+    }
+}
+
+impl RateLimitedEvent {
+    pub fn summary(&self) -> String {
+        format!(
+            "rate limited by {}; waiting {:.1}s",
+            self.provider,
+            self.wait_ms as f64 / 1000.0
+        )
+    }
+}
+
+/// How many secrets of one kind were redacted. The secrets themselves are
+/// never reported.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct RedactedSecret {
+    /// e.g. `aws-access-key-id` or `private-key`.
+    pub kind: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CopilotAuthStartedEvent {
     pub verification_uri: String,
     pub user_code: String,
+    /// How long the code stays valid, as GitHub reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CopilotAuthProgressEvent {
+    /// Seconds until the device code expires.
+    pub seconds_remaining: u64,
+    /// Seconds until the next poll; GitHub raises it with `slow_down`.
+    pub interval_secs: u64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct CopilotAuthCompleteEvent {
     pub success: bool,
     pub message: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ChatGptAuthStartedEvent {
+    /// Sign-in page, opened in the browser if possible.
+    pub auth_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ChatGptAuthCompleteEvent {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct BackgroundEventEvent {
     pub message: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct PatchApplyBeginEvent {
     /// Identifier so this can be paired with the PatchApplyEnd event.
     pub call_id: String,
@@ -542,7 +1375,15 @@ pub struct PatchApplyBeginEvent {
     pub changes: HashMap<PathBuf, FileChange>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct PatchProposedEvent {
+    /// The `apply_patch` call the patch comes from.
+    pub call_id: String,
+    /// The changes to apply, with paths made absolute.
+    pub changes: HashMap<PathBuf, FileChange>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct PatchApplyEndEvent {
     /// Identifier for the PatchApplyBegin that finished.
     pub call_id: String,
@@ -554,7 +1395,7 @@ pub struct PatchApplyEndEvent {
     pub success: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct GetHistoryEntryResponseEvent {
     pub offset: usize,
     pub log_id: u64,
@@ -563,9 +1404,10 @@ pub struct GetHistoryEntryResponseEvent {
     pub entry: Option<HistoryEntry>,
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct SessionConfiguredEvent {
     /// Unique id for this session.
+    #[schemars(with = "String")]
     pub session_id: Uuid,
 
     /// Tell the client what model is being queried.
@@ -579,7 +1421,7 @@ pub struct SessionConfiguredEvent {
 }
 
 /// User's decision in response to an ExecApprovalRequest.
-#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReviewDecision {
     /// User has approved this command and the agent should execute it.
@@ -600,7 +1442,7 @@ pub enum ReviewDecision {
     Abort,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum FileChange {
     Add {
@@ -613,7 +1455,7 @@ pub enum FileChange {
     },
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Chunk {
     /// 1-based line index of the first line in the original file
     pub orig_index: u32,
@@ -646,4 +1488,137 @@ mod tests {
             r#"{"id":"1234","msg":{"type":"session_configured","session_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","model":"codex-mini-latest","history_log_id":0,"history_entry_count":0}}"#
         );
     }
+    #[test]
+    fn schema_covers_submissions_and_events() {
+        let schema = json_schema().unwrap();
+        assert_eq!(schema["protocol_version"], PROTOCOL_VERSION);
+        let schema = schema.to_string();
+        for tag in ["\"user_input\"", "\"handshake\"", "\"session_configured\""] {
+            assert!(schema.contains(tag), "{tag} missing from {schema}");
+        }
+    }
+    /// The `type` tags of `T`'s variants, from its schema.
+    fn tags<T: JsonSchema>() -> Vec<String> {
+        let schema = serde_json::to_value(schemars::schema_for!(T)).unwrap();
+        schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|variant| variant["properties"]["type"]["enum"].as_array().unwrap())
+            .map(|tag| tag.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    /// SDKs in other languages match on these names: a variant may be added,
+    /// at the end, but never renamed or removed.
+    #[test]
+    fn tags_are_stable() {
+        assert_eq!(
+            tags::<Op>(),
+            [
+                "configure_session",
+                "interrupt",
+                "override_turn_context",
+                "copilot_auth",
+                "chat_gpt_auth",
+                "user_input",
+                "exec_approval",
+                "patch_approval",
+                "turn_diff_approval",
+                "add_to_history",
+                "get_history_entry_request",
+                "compact",
+                "checkpoint",
+                "rewind",
+                "subscribe",
+                "handshake",
+            ]
+        );
+        assert_eq!(
+            tags::<EventMsg>(),
+            [
+                "error",
+                "task_started",
+                "task_complete",
+                "agent_message",
+                "agent_message_delta",
+                "agent_reasoning",
+                "agent_reasoning_delta",
+                "session_configured",
+                "turn_context_overridden",
+                "copilot_plan_detected",
+                "mcp_tool_call_begin",
+                "mcp_tool_call_end",
+                "exec_command_begin",
+                "exec_command_end",
+                "exec_approval_request",
+                "sandbox_escalation_request",
+                "apply_patch_approval_request",
+                "turn_diff_approval_request",
+                "copilot_auth_started",
+                "copilot_auth_progress",
+                "copilot_auth_complete",
+                "chat_gpt_auth_started",
+                "chat_gpt_auth_complete",
+                "background_event",
+                "patch_apply_begin",
+                "patch_apply_end",
+                "patch_proposed",
+                "get_history_entry_response",
+                "token_count",
+                "sub_agent",
+                "plan_updated",
+                "secrets_redacted",
+                "rate_limited",
+                "tool_call_repair",
+                "dry_run_exec",
+                "checkpoint_created",
+                "rewound",
+                "handshake",
+            ]
+        );
+    }
+
+    #[test]
+    fn submissions_and_events_round_trip() {
+        let ops = [
+            Op::UserInput {
+                items: vec![InputItem::Text {
+                    text: "explain src/main.rs".to_string(),
+                }],
+                response_format: None,
+            },
+            Op::ExecApproval {
+                id: "1".to_string(),
+                decision: ReviewDecision::ApprovedForSession,
+            },
+            Op::Subscribe {
+                kinds: vec![EventKind::Task, EventKind::MessageDelta],
+            },
+            Op::Compact,
+        ];
+        for op in ops {
+            let json = serde_json::to_string(&op).unwrap();
+            assert_eq!(serde_json::from_str::<Op>(&json).unwrap(), op, "{json}");
+        }
+
+        for json in [
+            r#"{"type":"task_started"}"#,
+            r#"{"type":"agent_message_delta","delta":"Hel"}"#,
+            r#"{"type":"rewound","checkpoint":2,"restored":["src/lib.rs"]}"#,
+            r#"{"type":"sub_agent","agent_id":"2.1","task":"fix the lexer","msg":{"type":"error","message":"boom"}}"#,
+        ] {
+            let msg: EventMsg = serde_json::from_str(json).unwrap();
+            assert_eq!(serde_json::to_string(&msg).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn unknown_tags_deserialize_to_the_fallback() {
+        let op: Op = serde_json::from_str(r#"{"type":"from_the_future","level":3}"#).unwrap();
+        assert_eq!(op, Op::Unknown);
+        let msg: EventMsg =
+            serde_json::from_str(r#"{"type":"from_the_future","level":3}"#).unwrap();
+        assert!(matches!(msg, EventMsg::Unknown));
+    }
 }
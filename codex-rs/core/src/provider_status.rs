@@ -0,0 +1,438 @@
+//! Provider health checks behind `codex providers status`.
+//!
+//! Every configured provider is sent a few `GET {base_url}/models` requests
+//! to see whether it answers, whether it accepts the key, and how quickly.
+//! The requests go through the provider's rate limiter like model requests
+//! do, so the report also carries the headroom left under `rate_limit` and
+//! the limits the provider itself reported. Printing is left to the caller.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use reqwest::StatusCode;
+
+use crate::ModelProviderInfo;
+use crate::auth::AuthManager;
+use crate::config::Config;
+use crate::doctor::PROBE_TIMEOUT;
+use crate::doctor::models_request;
+use crate::error::CodexErr;
+use crate::http_client::create_client;
+use crate::http_client::is_local_url;
+use crate::rate_limit::Headroom;
+use crate::rate_limit::limiter_for;
+
+/// Requests sent to each provider by default.
+pub const DEFAULT_SAMPLES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Auth {
+    /// The provider accepted the key.
+    Valid,
+    /// The provider rejected the key, or asked for one it was not sent.
+    Rejected,
+    /// The provider's `env_key` is not set.
+    Missing,
+    /// The provider does not take a key, and did not ask for one.
+    NotNeeded,
+    /// The provider did not say, e.g. because it could not be reached.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderStatus {
+    /// Key of the provider in `model_providers`.
+    pub id: String,
+    pub provider: ModelProviderInfo,
+    /// Whether this is the provider the config uses.
+    pub current: bool,
+    /// Whether any request got an answer.
+    pub reachable: bool,
+    pub auth: Auth,
+    /// Median time to an answer.
+    pub p50: Option<Duration>,
+    /// What went wrong, if anything.
+    pub problem: Option<String>,
+    pub headroom: Headroom,
+}
+
+/// Check the providers named in `ids`, or all of them when it is empty,
+/// sorted by id. Fails on an id that is not in `model_providers`.
+pub async fn check_providers(
+    config: &Config,
+    ids: &[String],
+    samples: usize,
+) -> anyhow::Result<Vec<ProviderStatus>> {
+    let mut providers: Vec<(String, ModelProviderInfo)> = if ids.is_empty() {
+        config
+            .model_providers
+            .iter()
+            .map(|(id, provider)| (id.clone(), provider.clone()))
+            .collect()
+    } else {
+        let mut providers = Vec::new();
+        for id in ids {
+            let Some(provider) = config.model_providers.get(id) else {
+                let mut known: Vec<&str> =
+                    config.model_providers.keys().map(String::as_str).collect();
+                known.sort_unstable();
+                anyhow::bail!("no provider `{id}`; configured: {}", known.join(", "));
+            };
+            providers.push((id.clone(), provider.clone()));
+        }
+        providers
+    };
+    providers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let client = create_client(config)?;
+    let auth = AuthManager::new(&config.codex_home, client.clone());
+    let checks = providers.into_iter().map(|(id, provider)| {
+        let current = id == config.model_provider_id;
+        let skip = config.offline && !is_local_url(&provider.base_url);
+        let client = &client;
+        let auth = &auth;
+        async move {
+            let mut status = if skip {
+                ProviderStatus {
+                    id,
+                    provider,
+                    current: false,
+                    reachable: false,
+                    auth: Auth::Unknown,
+                    p50: None,
+                    problem: Some("skipped in offline mode".to_string()),
+                    headroom: Headroom::default(),
+                }
+            } else {
+                check_provider(client, auth, id, provider, samples).await
+            };
+            status.current = current;
+            status
+        }
+    });
+    Ok(futures::future::join_all(checks).await)
+}
+
+async fn check_provider(
+    client: &reqwest::Client,
+    auth: &AuthManager,
+    id: String,
+    provider: ModelProviderInfo,
+    samples: usize,
+) -> ProviderStatus {
+    let mut base_url = provider.base_url.clone();
+    let (api_key, mut auth_status, mut problem) = match auth.get_credentials(&provider).await {
+        Ok(Some(credentials)) => {
+            if let Some(url) = credentials.base_url {
+                base_url = url;
+            }
+            (Some(credentials.token), Auth::Unknown, None)
+        }
+        Ok(None) => (None, Auth::NotNeeded, None),
+        Err(CodexErr::EnvVar(e)) => (None, Auth::Missing, Some(format!("`{}` is not set", e.var))),
+        Err(e) => (None, Auth::Missing, Some(e.to_string())),
+    };
+
+    let limiter = limiter_for(&provider);
+    let mut latencies = Vec::new();
+    for _ in 0..samples {
+        let wait = limiter.reserve(0);
+        if wait > PROBE_TIMEOUT {
+            problem = Some(format!(
+                "held back by the rate limit for another {}s",
+                wait.as_secs()
+            ));
+            break;
+        }
+        tokio::time::sleep(wait).await;
+
+        let started = Instant::now();
+        let request = models_request(client, &base_url, api_key.as_deref(), &provider);
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                problem = Some(format!("cannot reach {base_url}: {e}"));
+                break;
+            }
+        };
+        latencies.push(started.elapsed());
+        limiter.observe(response.headers());
+        match response.status() {
+            status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                if auth_status != Auth::Missing {
+                    auth_status = Auth::Rejected;
+                    problem = Some(if api_key.is_some() {
+                        format!("the key was rejected ({status})")
+                    } else {
+                        format!("the provider asks for a key ({status})")
+                    });
+                }
+                break;
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+                if let Some(secs) = retry_after {
+                    limiter.pause(Duration::from_secs(secs));
+                }
+                if api_key.is_some() {
+                    auth_status = Auth::Valid;
+                }
+                problem = Some("rate limited (429 Too Many Requests)".to_string());
+                break;
+            }
+            // Not every provider lists its models; any other answer still
+            // shows that it can be reached.
+            status => {
+                if api_key.is_some() && status.is_success() {
+                    auth_status = Auth::Valid;
+                }
+            }
+        }
+    }
+
+    ProviderStatus {
+        id,
+        current: false,
+        reachable: !latencies.is_empty(),
+        auth: auth_status,
+        p50: median(latencies),
+        problem,
+        headroom: limiter.headroom(),
+        provider,
+    }
+}
+
+pub fn render_report(statuses: &[ProviderStatus]) -> String {
+    let mut report = format!(
+        "{:<16} {:<9} {:<10} {:>7}  {}\n",
+        "provider", "reachable", "auth", "p50", "headroom"
+    );
+    for status in statuses {
+        let id = if status.current {
+            format!("{}*", status.id)
+        } else {
+            status.id.clone()
+        };
+        let auth = match status.auth {
+            Auth::Valid => "valid",
+            Auth::Rejected => "rejected",
+            Auth::Missing => "missing",
+            Auth::NotNeeded => "not needed",
+            Auth::Unknown => "unknown",
+        };
+        let p50 = status
+            .p50
+            .map_or_else(|| "-".to_string(), |p50| format!("{}ms", p50.as_millis()));
+        report.push_str(&format!(
+            "{id:<16} {:<9} {auth:<10} {p50:>7}  {}\n",
+            if status.reachable { "yes" } else { "no" },
+            format_headroom(&status.headroom),
+        ));
+        if let Some(problem) = &status.problem {
+            report.push_str(&format!("  {problem}\n"));
+        }
+    }
+    if statuses.iter().any(|status| status.current) {
+        report.push_str("\n* the provider in use\n");
+    }
+    report
+}
+
+/// What the provider reported it has left, then what `rate_limit` leaves.
+fn format_headroom(headroom: &Headroom) -> String {
+    fn left(remaining: Option<u64>, limit: Option<u64>, what: &str) -> Option<String> {
+        match (remaining?, limit) {
+            (remaining, Some(limit)) => Some(format!("{remaining}/{limit} {what}")),
+            (remaining, None) => Some(format!("{remaining} {what}")),
+        }
+    }
+
+    let mut parts = Vec::new();
+    if let Some(reported) = headroom.reported {
+        parts.extend(left(
+            reported.requests_remaining,
+            reported.requests_limit,
+            "requests",
+        ));
+        parts.extend(left(
+            reported.tokens_remaining,
+            reported.tokens_limit,
+            "tokens",
+        ));
+    }
+    if let Some(requests) = headroom.requests {
+        parts.push(format!("{requests} requests/min under rate_limit"));
+    }
+    if let Some(tokens) = headroom.tokens {
+        parts.push(format!("{tokens} tokens/min under rate_limit"));
+    }
+    if let Some(paused_for) = headroom.paused_for {
+        parts.push(format!("paused for {}s", paused_for.as_secs()));
+    }
+    if parts.is_empty() {
+        "-".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// The lower of the two middle values for an even count.
+fn median(mut samples: Vec<Duration>) -> Option<Duration> {
+    samples.sort_unstable();
+    samples.get(samples.len().checked_sub(1)? / 2).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::model_provider_info::WireApi;
+    use crate::rate_limit::ReportedLimits;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    fn provider(base_url: String) -> ModelProviderInfo {
+        ModelProviderInfo {
+            name: "Local".to_string(),
+            base_url,
+            env_key: None,
+            env_key_instructions: None,
+            wire_api: WireApi::Chat,
+            supports_images: false,
+            supports_structured_output: false,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn median_takes_the_middle_sample() {
+        let ms = Duration::from_millis;
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![ms(30), ms(10), ms(20)]), Some(ms(20)));
+        assert_eq!(median(vec![ms(40), ms(10)]), Some(ms(10)));
+    }
+
+    #[test]
+    fn report_lists_each_provider_with_its_headroom() {
+        let status = |id: &str| ProviderStatus {
+            id: id.to_string(),
+            provider: provider("http://localhost:11434/v1".to_string()),
+            current: false,
+            reachable: true,
+            auth: Auth::Valid,
+            p50: Some(Duration::from_millis(212)),
+            problem: None,
+            headroom: Headroom::default(),
+        };
+        let openai = ProviderStatus {
+            current: true,
+            headroom: Headroom {
+                requests: Some(58),
+                reported: Some(ReportedLimits {
+                    requests_limit: Some(5000),
+                    requests_remaining: Some(4999),
+                    tokens_limit: None,
+                    tokens_remaining: Some(1_999_000),
+                }),
+                ..Default::default()
+            },
+            ..status("openai")
+        };
+        let ollama = ProviderStatus {
+            reachable: false,
+            auth: Auth::NotNeeded,
+            p50: None,
+            problem: Some("cannot reach http://localhost:11434/v1".to_string()),
+            ..status("ollama")
+        };
+
+        assert_eq!(
+            render_report(&[ollama, openai]),
+            "provider         reachable auth           p50  headroom
+ollama           no        not needed       -  -
+  cannot reach http://localhost:11434/v1
+openai*          yes       valid        212ms  4999/5000 requests, 1999000 tokens, 58 requests/min under rate_limit
+
+* the provider in use
+"
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_latency_and_the_limits_the_provider_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-limit-requests", "100")
+                    .insert_header("x-ratelimit-remaining-requests", "97"),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let codex_home = tempfile::tempdir().unwrap();
+        let client = reqwest::Client::new();
+        let auth = AuthManager::new(codex_home.path(), client.clone());
+        let status = check_provider(
+            &client,
+            &auth,
+            "local".to_string(),
+            provider(format!("{}/v1", server.uri())),
+            2,
+        )
+        .await;
+
+        assert!(status.reachable);
+        assert_eq!(status.auth, Auth::NotNeeded);
+        assert_eq!(status.problem, None);
+        assert!(status.p50.is_some());
+        assert_eq!(
+            status.headroom.reported,
+            Some(ReportedLimits {
+                requests_limit: Some(100),
+                requests_remaining: Some(97),
+                tokens_limit: None,
+                tokens_remaining: None,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn a_provider_that_asks_for_a_key_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let codex_home = tempfile::tempdir().unwrap();
+        let client = reqwest::Client::new();
+        let auth = AuthManager::new(codex_home.path(), client.clone());
+        let status = check_provider(
+            &client,
+            &auth,
+            "local".to_string(),
+            provider(server.uri()),
+            3,
+        )
+        .await;
+
+        assert!(status.reachable);
+        assert_eq!(status.auth, Auth::Rejected);
+        assert_eq!(
+            status.problem.as_deref(),
+            Some("the provider asks for a key (401 Unauthorized)")
+        );
+    }
+}
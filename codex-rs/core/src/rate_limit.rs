@@ -0,0 +1,294 @@
+//! Client-side rate limiting of model requests, per provider.
+//!
+//! Every session in the process, sub-agents included, shares one limiter per
+//! provider endpoint. A request reserves a slot in a sliding one-minute window
+//! sized by the provider's `rate_limit`, and requests wait for their slots in
+//! the order they asked. A 429 from the provider also holds back every other
+//! request to it until the `Retry-After` delay has passed, so parallel agents
+//! do not keep hammering a provider that is already pushing back.
+//!
+//! Each limiter also keeps the limits the provider last reported in its
+//! `x-ratelimit-*` headers, so that `codex providers status` can show the
+//! headroom left on both sides.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+
+use reqwest::header::HeaderMap;
+
+use crate::client_common::Prompt;
+use crate::model_provider_info::ModelProviderInfo;
+use crate::model_provider_info::RateLimit;
+
+/// Length of the window the per-minute limits apply to.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Rough number of bytes of serialized input per token, used to estimate the
+/// size of a request before it is sent.
+pub(crate) const BYTES_PER_TOKEN: usize = 4;
+
+static LIMITERS: LazyLock<Mutex<HashMap<String, Arc<RateLimiter>>>> =
+    LazyLock::new(Default::default);
+
+/// The limiter shared by all requests to `provider`'s endpoint.
+pub(crate) fn limiter_for(provider: &ModelProviderInfo) -> Arc<RateLimiter> {
+    let mut limiters = LIMITERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let limiter = limiters.entry(provider.base_url.clone()).or_default();
+    limiter.set_limits(provider.rate_limit.unwrap_or_default());
+    Arc::clone(limiter)
+}
+
+/// Estimated number of tokens `prompt` will consume.
+pub(crate) fn estimate_tokens(prompt: &Prompt) -> u64 {
+    let bytes = serde_json::to_string(&prompt.input).map_or(0, |input| input.len());
+    (bytes / BYTES_PER_TOKEN) as u64
+}
+
+/// Limits a provider reported in the `x-ratelimit-*` headers of a response,
+/// as sent by OpenAI and most OpenAI-compatible APIs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportedLimits {
+    pub requests_limit: Option<u64>,
+    pub requests_remaining: Option<u64>,
+    pub tokens_limit: Option<u64>,
+    pub tokens_remaining: Option<u64>,
+}
+
+impl ReportedLimits {
+    /// `None` when `headers` carry none of the limits.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let get = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse().ok();
+        let limits = Self {
+            requests_limit: get("x-ratelimit-limit-requests"),
+            requests_remaining: get("x-ratelimit-remaining-requests"),
+            tokens_limit: get("x-ratelimit-limit-tokens"),
+            tokens_remaining: get("x-ratelimit-remaining-tokens"),
+        };
+        (limits != Self::default()).then_some(limits)
+    }
+}
+
+/// How much more a limiter lets through right now.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Headroom {
+    /// Requests left in the current minute under the provider's
+    /// `rate_limit`, or `None` when it sets no request limit.
+    pub requests: Option<u64>,
+    /// Tokens left likewise.
+    pub tokens: Option<u64>,
+    /// How much longer every request is held back after a 429.
+    pub paused_for: Option<Duration>,
+    /// As of the last response from the provider that reported them.
+    pub reported: Option<ReportedLimits>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    window: Mutex<Window>,
+}
+
+impl RateLimiter {
+    fn set_limits(&self, limits: RateLimit) {
+        self.lock().limits = limits;
+    }
+
+    /// Reserve the earliest slot for a request of `tokens` tokens and return
+    /// how long the caller has to wait before sending it.
+    pub(crate) fn reserve(&self, tokens: u64) -> Duration {
+        let now = Instant::now();
+        let at = self.lock().reserve(now, tokens);
+        at.saturating_duration_since(now)
+    }
+
+    /// Hold back every request until `delay` from now, e.g. after the
+    /// provider answered 429.
+    pub(crate) fn pause(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut window = self.lock();
+        window.paused_until = Some(window.paused_until.map_or(until, |at| at.max(until)));
+    }
+
+    /// Remember the limits reported in the headers of a response.
+    pub(crate) fn observe(&self, headers: &HeaderMap) {
+        if let Some(reported) = ReportedLimits::from_headers(headers) {
+            self.lock().reported = Some(reported);
+        }
+    }
+
+    pub(crate) fn headroom(&self) -> Headroom {
+        self.lock().headroom(Instant::now())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Window> {
+        self.window.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Requests reserved during the last minute, plus those scheduled in the
+/// future, oldest first.
+#[derive(Debug, Default)]
+struct Window {
+    limits: RateLimit,
+    reserved: VecDeque<(Instant, u64)>,
+    paused_until: Option<Instant>,
+    reported: Option<ReportedLimits>,
+}
+
+impl Window {
+    fn reserve(&mut self, now: Instant, tokens: u64) -> Instant {
+        while self
+            .reserved
+            .front()
+            .is_some_and(|(at, _)| *at + WINDOW <= now)
+        {
+            self.reserved.pop_front();
+        }
+
+        // Slots are handed out in order: never earlier than the last one.
+        let mut at = [self.paused_until, self.reserved.back().map(|(at, _)| *at)]
+            .into_iter()
+            .flatten()
+            .fold(now, Instant::max);
+        loop {
+            let in_window = self
+                .reserved
+                .iter()
+                .filter(|(reserved_at, _)| *reserved_at + WINDOW > at);
+            let (count, used_tokens, oldest) = in_window
+                .fold((0u64, 0u64, None), |(count, sum, oldest), (t, n)| {
+                    (count + 1, sum + n, oldest.or(Some(*t)))
+                });
+            let over_requests = self
+                .limits
+                .requests_per_minute
+                .is_some_and(|limit| count >= u64::from(limit));
+            // A request larger than the whole budget is let through once the
+            // window is empty rather than blocked forever.
+            let over_tokens = self
+                .limits
+                .tokens_per_minute
+                .is_some_and(|limit| count > 0 && used_tokens + tokens > limit);
+            match oldest {
+                Some(oldest) if over_requests || over_tokens => at = oldest + WINDOW,
+                _ => break,
+            }
+        }
+        self.reserved.push_back((at, tokens));
+        at
+    }
+
+    /// Reservations scheduled for later still count against the budget.
+    fn headroom(&self, now: Instant) -> Headroom {
+        let (count, used_tokens) = self
+            .reserved
+            .iter()
+            .filter(|(at, _)| *at + WINDOW > now)
+            .fold((0u64, 0u64), |(count, sum), (_, n)| (count + 1, sum + n));
+        Headroom {
+            requests: self
+                .limits
+                .requests_per_minute
+                .map(|limit| u64::from(limit).saturating_sub(count)),
+            tokens: self
+                .limits
+                .tokens_per_minute
+                .map(|limit| limit.saturating_sub(used_tokens)),
+            paused_for: self
+                .paused_until
+                .filter(|until| *until > now)
+                .map(|until| until - now),
+            reported: self.reported,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn window(requests_per_minute: Option<u32>, tokens_per_minute: Option<u64>) -> Window {
+        Window {
+            limits: RateLimit {
+                requests_per_minute,
+                tokens_per_minute,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn queues_requests_over_the_request_limit() {
+        let now = Instant::now();
+        let mut window = window(Some(2), None);
+        assert_eq!(window.reserve(now, 0), now);
+        assert_eq!(
+            window.reserve(now + Duration::from_secs(10), 0),
+            now + Duration::from_secs(10)
+        );
+        // The third request waits for the first to leave the window, and the
+        // fourth for the second.
+        assert_eq!(
+            window.reserve(now + Duration::from_secs(20), 0),
+            now + WINDOW
+        );
+        assert_eq!(
+            window.reserve(now + Duration::from_secs(20), 0),
+            now + WINDOW + Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn queues_requests_over_the_token_limit() {
+        let now = Instant::now();
+        let mut window = window(None, Some(1_000));
+        assert_eq!(window.reserve(now, 600), now);
+        assert_eq!(window.reserve(now, 600), now + WINDOW);
+        // Larger than the whole budget: sent once the window is empty.
+        assert_eq!(window.reserve(now, 5_000), now + WINDOW * 2);
+    }
+
+    #[test]
+    fn pause_holds_back_unlimited_providers() {
+        let limiter = RateLimiter::default();
+        assert_eq!(limiter.reserve(0), Duration::ZERO);
+        limiter.pause(Duration::from_secs(30));
+        let wait = limiter.reserve(0);
+        assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn headroom_counts_reserved_requests_and_reported_limits() {
+        let now = Instant::now();
+        let mut window = window(Some(10), Some(1_000));
+        window.reserve(now, 300);
+        window.reserve(now, 200);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit-requests", "500".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests", "499".parse().unwrap());
+        window.reported = ReportedLimits::from_headers(&headers);
+
+        assert_eq!(
+            window.headroom(now + Duration::from_secs(1)),
+            Headroom {
+                requests: Some(8),
+                tokens: Some(500),
+                paused_for: None,
+                reported: Some(ReportedLimits {
+                    requests_limit: Some(500),
+                    requests_remaining: Some(499),
+                    tokens_limit: None,
+                    tokens_remaining: None,
+                }),
+            }
+        );
+        // Once the reservations leave the window, the whole budget is back.
+        assert_eq!(window.headroom(now + WINDOW).requests, Some(10),);
+    }
+}
@@ -0,0 +1,291 @@
+//! Per-session record of the files the model read through the shell, so that
+//! reading the same part of a file again, while it has not changed, is
+//! answered with a short "unchanged since last read" note instead of running
+//! the command and sending the same content again.
+//!
+//! Only plain reads of a single file are recognized: `cat FILE`,
+//! `sed -n 'M,Np' FILE`, `head`/`tail [-n N] FILE`. A file counts as
+//! unchanged while its modification time and size are those it had before the
+//! earlier read.
+//!
+//! The cache also keeps the content of each file the model last read in
+//! full, so that the `read_file_changes` tool can answer with a diff against
+//! it instead of the whole file.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use similar::TextDiff;
+
+use crate::is_safe_command::parse_bash_lc_single_command;
+
+pub(crate) const READ_FILE_CHANGES_TOOL_NAME: &str = "read_file_changes";
+
+/// Lines of context around each hunk of a `read_file_changes` diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Lines `head` and `tail` print without `-n`.
+const DEFAULT_HEAD_TAIL_LINES: u64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadFileChangesArgs {
+    pub path: String,
+}
+
+/// Which part of a file a command prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ReadRange {
+    Whole,
+    Lines(u64, u64),
+    Head(u64),
+    Tail(u64),
+}
+
+/// A command that prints (part of) one file and does nothing else.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct FileRead {
+    path: PathBuf,
+    range: ReadRange,
+}
+
+/// Modification time and size of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileRead {
+    /// The read `command`, run in `cwd`, performs, if it is only a read.
+    pub(crate) fn parse(command: &[String], cwd: &Path) -> Option<Self> {
+        let words = parse_bash_lc_single_command(command).unwrap_or_else(|| command.to_vec());
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let (range, file) = match words.as_slice() {
+            ["cat", file] if !file.starts_with('-') => (ReadRange::Whole, *file),
+            ["sed", "-n", script, file] => (parse_sed_range(script)?, *file),
+            [cmd @ ("head" | "tail"), args @ ..] => {
+                let (lines, file) = match args {
+                    [file] => (DEFAULT_HEAD_TAIL_LINES, *file),
+                    ["-n", lines, file] => (lines.parse().ok()?, *file),
+                    [lines, file] => (
+                        lines
+                            .strip_prefix("-n")
+                            .or_else(|| lines.strip_prefix('-'))?
+                            .parse()
+                            .ok()?,
+                        *file,
+                    ),
+                    _ => return None,
+                };
+                if file.starts_with('-') {
+                    return None;
+                }
+                let range = if *cmd == "head" {
+                    ReadRange::Head(lines)
+                } else {
+                    ReadRange::Tail(lines)
+                };
+                (range, file)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            path: cwd.join(file),
+            range,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The current stamp of the file, `None` if it cannot be read.
+    pub(crate) fn stamp(&self) -> Option<FileStamp> {
+        let metadata = std::fs::metadata(&self.path).ok()?;
+        Some(FileStamp {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+/// `N` or `M,N` followed by `p`.
+fn parse_sed_range(script: &str) -> Option<ReadRange> {
+    let lines = script.strip_suffix('p')?;
+    let (start, end) = lines.split_once(',').unwrap_or((lines, lines));
+    Some(ReadRange::Lines(start.parse().ok()?, end.parse().ok()?))
+}
+
+/// The reads served to the model in the session, with the stamp each file
+/// had and the call whose output holds the content.
+#[derive(Debug, Default)]
+pub(crate) struct ReadCache {
+    reads: HashMap<FileRead, (FileStamp, String)>,
+    /// Content of each file as the model last saw it in full.
+    contents: HashMap<PathBuf, String>,
+}
+
+impl ReadCache {
+    /// The call that read exactly `read` while the file had `stamp`, if any.
+    pub(crate) fn unchanged_since(&self, read: &FileRead, stamp: FileStamp) -> Option<&str> {
+        self.reads
+            .get(read)
+            .filter(|(recorded, _)| *recorded == stamp)
+            .map(|(_, call_id)| call_id.as_str())
+    }
+
+    /// Record that the output of `call_id`, `output`, is `read` of the file
+    /// as of `stamp`, taken before the command ran.
+    pub(crate) fn record(
+        &mut self,
+        read: FileRead,
+        stamp: FileStamp,
+        call_id: String,
+        output: &str,
+    ) {
+        if read.range == ReadRange::Whole {
+            self.contents.insert(read.path.clone(), output.to_string());
+        }
+        self.reads.insert(read, (stamp, call_id));
+    }
+
+    /// What changed in the file at `path` since the model last read it in
+    /// full, as a unified diff, which becomes what the model has seen.
+    pub(crate) fn changes_since_last_read(&mut self, path: &Path) -> Result<String, String> {
+        let Some(previous) = self.contents.get_mut(path) else {
+            return Err(format!(
+                "{} was not read in full in this session; read it with `cat` first",
+                path.display()
+            ));
+        };
+        let current = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        if *previous == current {
+            return Ok(format!("{} is unchanged since last read", path.display()));
+        }
+        let name = path.display().to_string();
+        let diff = TextDiff::from_lines(previous.as_str(), current.as_str())
+            .unified_diff()
+            .context_radius(DIFF_CONTEXT_LINES)
+            .header(&name, &name)
+            .to_string();
+        *previous = current;
+        Ok(diff)
+    }
+
+    /// Forget every read, when the outputs are no longer in the history the
+    /// model sees.
+    pub(crate) fn clear(&mut self) {
+        self.reads.clear();
+        self.contents.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn parse(command: &[&str]) -> Option<(PathBuf, ReadRange)> {
+        let command: Vec<String> = command.iter().map(|s| s.to_string()).collect();
+        FileRead::parse(&command, Path::new("/work/repo")).map(|read| (read.path, read.range))
+    }
+
+    fn in_repo(file: &str, range: ReadRange) -> Option<(PathBuf, ReadRange)> {
+        Some((Path::new("/work/repo").join(file), range))
+    }
+
+    #[test]
+    fn recognizes_plain_reads_of_one_file() {
+        assert_eq!(
+            parse(&["cat", "src/lib.rs"]),
+            in_repo("src/lib.rs", ReadRange::Whole)
+        );
+        assert_eq!(
+            parse(&["bash", "-lc", "sed -n '120,200p' src/lib.rs"]),
+            in_repo("src/lib.rs", ReadRange::Lines(120, 200))
+        );
+        assert_eq!(
+            parse(&["bash", "-lc", "sed -n 7p /etc/hosts"]),
+            Some((PathBuf::from("/etc/hosts"), ReadRange::Lines(7, 7)))
+        );
+        assert_eq!(
+            parse(&["head", "-n", "40", "README.md"]),
+            in_repo("README.md", ReadRange::Head(40))
+        );
+        assert_eq!(
+            parse(&["tail", "-5", "log.txt"]),
+            in_repo("log.txt", ReadRange::Tail(5))
+        );
+        assert_eq!(
+            parse(&["head", "README.md"]),
+            in_repo("README.md", ReadRange::Head(10))
+        );
+    }
+
+    #[test]
+    fn ignores_anything_but_a_plain_read() {
+        assert_eq!(parse(&["cat", "a.rs", "b.rs"]), None);
+        assert_eq!(parse(&["cat", "-n", "a.rs"]), None);
+        assert_eq!(parse(&["bash", "-lc", "cat a.rs | head"]), None);
+        assert_eq!(parse(&["bash", "-lc", "cat a.rs > b.rs"]), None);
+        assert_eq!(parse(&["sed", "-n", "/fn/p", "a.rs"]), None);
+        assert_eq!(parse(&["tail", "-f", "log.txt"]), None);
+    }
+
+    #[test]
+    fn a_read_is_unchanged_until_the_file_is() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "one\n").unwrap();
+        let command = vec!["cat".to_string(), "notes.txt".to_string()];
+        let read = FileRead::parse(&command, dir.path()).unwrap();
+        let stamp = read.stamp().unwrap();
+
+        let mut cache = ReadCache::default();
+        assert_eq!(cache.unchanged_since(&read, stamp), None);
+        cache.record(read.clone(), stamp, "call-1".to_string(), "one\n");
+        assert_eq!(cache.unchanged_since(&read, stamp), Some("call-1"));
+
+        std::fs::write(dir.path().join("notes.txt"), "one\ntwo\n").unwrap();
+        assert_eq!(cache.unchanged_since(&read, read.stamp().unwrap()), None);
+
+        cache.clear();
+        assert_eq!(cache.unchanged_since(&read, stamp), None);
+    }
+
+    #[test]
+    fn changes_are_a_diff_against_the_last_full_read() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let mut cache = ReadCache::default();
+        assert_eq!(
+            cache.changes_since_last_read(&path),
+            Err(format!(
+                "{} was not read in full in this session; read it with `cat` first",
+                path.display()
+            ))
+        );
+
+        let command = vec!["cat".to_string(), "notes.txt".to_string()];
+        let read = FileRead::parse(&command, dir.path()).unwrap();
+        let stamp = read.stamp().unwrap();
+        cache.record(read, stamp, "call-1".to_string(), "one\ntwo\n");
+        std::fs::write(&path, "one\n2\n").unwrap();
+
+        let name = path.display();
+        assert_eq!(
+            cache.changes_since_last_read(&path).unwrap(),
+            format!("--- {name}\n+++ {name}\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n")
+        );
+        assert_eq!(
+            cache.changes_since_last_read(&path).unwrap(),
+            format!("{name} is unchanged since last read")
+        );
+    }
+}
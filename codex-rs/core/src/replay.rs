@@ -0,0 +1,363 @@
+//! Record and replay of model responses.
+//!
+//! With `CODEX_RECORD=path` every model request the process makes is written
+//! to `path` as one JSON line holding the request input and the events the
+//! provider streamed back, errors included. With `CODEX_REPLAY=path` no
+//! provider is contacted: each request is answered with the next exchange
+//! from such a file, in order. Replays make runs of the agent loop
+//! deterministic, for integration tests and offline demo sessions. Requests
+//! are not matched against the recording, so sessions running sub-agents in
+//! parallel may not replay in the order they were recorded.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use futures::StreamExt;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::flags::CODEX_RECORD;
+use crate::flags::CODEX_REPLAY;
+use crate::models::ResponseItem;
+use crate::protocol::TokenUsage;
+
+static RECORDER: LazyLock<Option<io::Result<Arc<Recorder>>>> =
+    LazyLock::new(|| CODEX_RECORD.map(|path| Recorder::create(Path::new(path)).map(Arc::new)));
+
+static REPLAY: LazyLock<Option<io::Result<Arc<Replay>>>> =
+    LazyLock::new(|| CODEX_REPLAY.map(|path| Replay::load(Path::new(path)).map(Arc::new)));
+
+/// The recorder shared by the process when `CODEX_RECORD` is set.
+pub(crate) fn recorder() -> Result<Option<Arc<Recorder>>> {
+    shared(&RECORDER)
+}
+
+/// The replay shared by the process when `CODEX_REPLAY` is set.
+pub(crate) fn replay() -> Result<Option<Arc<Replay>>> {
+    shared(&REPLAY)
+}
+
+/// Whether model requests are answered from a recording.
+pub(crate) fn is_replaying() -> bool {
+    CODEX_REPLAY.is_some()
+}
+
+fn shared<T>(cell: &Option<io::Result<Arc<T>>>) -> Result<Option<Arc<T>>> {
+    match cell {
+        None => Ok(None),
+        Some(Ok(shared)) => Ok(Some(Arc::clone(shared))),
+        Some(Err(e)) => Err(io::Error::new(e.kind(), e.to_string()).into()),
+    }
+}
+
+/// One model request and everything the provider answered.
+#[derive(Debug, Serialize, Deserialize)]
+struct Exchange {
+    model: String,
+    input: Vec<ResponseItem>,
+    events: Vec<RecordedEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RecordedEvent {
+    OutputItemDone {
+        item: ResponseItem,
+    },
+    OutputTextDelta {
+        delta: String,
+    },
+    ReasoningSummaryDelta {
+        delta: String,
+    },
+    Completed {
+        response_id: String,
+        token_usage: Option<TokenUsage>,
+    },
+    /// The request failed or the stream broke off. Replayed as a stream
+    /// error, which the agent loop retries.
+    Error {
+        message: String,
+    },
+}
+
+impl From<&Result<ResponseEvent>> for RecordedEvent {
+    fn from(event: &Result<ResponseEvent>) -> Self {
+        match event {
+            Ok(ResponseEvent::OutputItemDone(item)) => {
+                RecordedEvent::OutputItemDone { item: item.clone() }
+            }
+            Ok(ResponseEvent::OutputTextDelta(delta)) => RecordedEvent::OutputTextDelta {
+                delta: delta.clone(),
+            },
+            Ok(ResponseEvent::ReasoningSummaryDelta(delta)) => {
+                RecordedEvent::ReasoningSummaryDelta {
+                    delta: delta.clone(),
+                }
+            }
+            Ok(ResponseEvent::Completed {
+                response_id,
+                token_usage,
+            }) => RecordedEvent::Completed {
+                response_id: response_id.clone(),
+                token_usage: token_usage.clone(),
+            },
+            Err(e) => RecordedEvent::Error {
+                message: error_message(e),
+            },
+        }
+    }
+}
+
+/// What is recorded of an error. Errors replay as `CodexErr::Stream`, so
+/// the message of one is recorded without the prefix that adds.
+fn error_message(e: &CodexErr) -> String {
+    match e {
+        CodexErr::Stream(message) => message.clone(),
+        e => e.to_string(),
+    }
+}
+
+impl From<RecordedEvent> for Result<ResponseEvent> {
+    fn from(event: RecordedEvent) -> Self {
+        match event {
+            RecordedEvent::OutputItemDone { item } => Ok(ResponseEvent::OutputItemDone(item)),
+            RecordedEvent::OutputTextDelta { delta } => Ok(ResponseEvent::OutputTextDelta(delta)),
+            RecordedEvent::ReasoningSummaryDelta { delta } => {
+                Ok(ResponseEvent::ReasoningSummaryDelta(delta))
+            }
+            RecordedEvent::Completed {
+                response_id,
+                token_usage,
+            } => Ok(ResponseEvent::Completed {
+                response_id,
+                token_usage,
+            }),
+            RecordedEvent::Error { message } => Err(CodexErr::Stream(message)),
+        }
+    }
+}
+
+/// Writes exchanges to a recording, one JSON line each.
+pub(crate) struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    /// Start a new recording at `path`, replacing any previous one.
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: Mutex::new(File::create(path)?),
+        })
+    }
+
+    /// Pass `response` through to the caller, recording it with the request
+    /// once the stream ends.
+    pub(crate) fn record(
+        self: Arc<Self>,
+        model: &str,
+        prompt: &Prompt,
+        response: Result<ResponseStream>,
+    ) -> Result<ResponseStream> {
+        let mut exchange = Exchange {
+            model: model.to_string(),
+            input: prompt.input.clone(),
+            events: Vec::new(),
+        };
+        let mut stream = match response {
+            Ok(stream) => stream,
+            Err(e) => {
+                exchange.events.push(RecordedEvent::Error {
+                    message: error_message(&e),
+                });
+                self.append(&exchange);
+                return Err(e);
+            }
+        };
+
+        let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(16);
+        tokio::spawn(async move {
+            while let Some(event) = stream.next().await {
+                exchange.events.push(RecordedEvent::from(&event));
+                if tx_event.send(event).await.is_err() {
+                    break;
+                }
+            }
+            self.append(&exchange);
+        });
+        Ok(ResponseStream { rx_event })
+    }
+
+    fn append(&self, exchange: &Exchange) {
+        let result = serde_json::to_string(exchange)
+            .map_err(io::Error::from)
+            .and_then(|line| {
+                let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+                writeln!(file, "{line}")
+            });
+        if let Err(e) = result {
+            warn!("failed to record model response: {e}");
+        }
+    }
+}
+
+/// Serves the exchanges of a recording in order.
+pub(crate) struct Replay {
+    path: PathBuf,
+    exchanges: Mutex<VecDeque<Exchange>>,
+}
+
+impl Replay {
+    pub(crate) fn load(path: &Path) -> io::Result<Self> {
+        let file = io::BufReader::new(File::open(path)?);
+        let mut exchanges = VecDeque::new();
+        for (index, line) in file.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: {e}", path.display(), index + 1),
+                )
+            })?;
+            exchanges.push_back(exchange);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    /// Answer the next request with the next recorded exchange.
+    pub(crate) fn next_response(&self) -> Result<ResponseStream> {
+        let exchange = self
+            .exchanges
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .ok_or_else(|| CodexErr::ReplayExhausted(self.path.display().to_string()))?;
+
+        let (tx_event, rx_event) = mpsc::channel(exchange.events.len().max(1));
+        for event in exchange.events {
+            // Cannot fail: the channel has room for every event and the
+            // receiver is still held here.
+            let _ = tx_event.try_send(event.into());
+        }
+        Ok(ResponseStream { rx_event })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::models::ContentItem;
+    use tempfile::TempDir;
+
+    fn message(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn provider_stream(events: Vec<Result<ResponseEvent>>) -> ResponseStream {
+        let (tx_event, rx_event) = mpsc::channel(events.len());
+        for event in events {
+            tx_event.try_send(event).unwrap();
+        }
+        ResponseStream { rx_event }
+    }
+
+    async fn collect(stream: ResponseStream) -> Vec<String> {
+        stream
+            .map(|event| match event {
+                Ok(ResponseEvent::OutputItemDone(item)) => format!("item {item:?}"),
+                Ok(ResponseEvent::OutputTextDelta(delta)) => format!("delta {delta}"),
+                Ok(ResponseEvent::ReasoningSummaryDelta(delta)) => format!("reasoning {delta}"),
+                Ok(ResponseEvent::Completed {
+                    response_id,
+                    token_usage,
+                }) => format!("completed {response_id} {token_usage:?}"),
+                Err(e) => format!("error {e}"),
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_exchanges_in_order() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let recorder = Arc::new(Recorder::create(&path).unwrap());
+        let prompt = Prompt {
+            input: vec![message("hello")],
+            ..Default::default()
+        };
+
+        let first = recorder.clone().record(
+            "o3",
+            &prompt,
+            Ok(provider_stream(vec![Err(CodexErr::Stream(
+                "connection reset".to_string(),
+            ))])),
+        );
+        let first = collect(first.unwrap()).await;
+        let second = recorder.clone().record(
+            "o3",
+            &prompt,
+            Ok(provider_stream(vec![
+                Ok(ResponseEvent::OutputTextDelta("hi".to_string())),
+                Ok(ResponseEvent::OutputItemDone(message("hi"))),
+                Ok(ResponseEvent::Completed {
+                    response_id: "resp_1".to_string(),
+                    token_usage: Some(TokenUsage {
+                        input_tokens: 5,
+                        output_tokens: 1,
+                        total_tokens: 6,
+                        ..Default::default()
+                    }),
+                }),
+            ])),
+        );
+        // Each exchange is written before its stream ends.
+        let second = collect(second.unwrap()).await;
+
+        let replay = Replay::load(&path).unwrap();
+        assert_eq!(collect(replay.next_response().unwrap()).await, first);
+        assert_eq!(collect(replay.next_response().unwrap()).await, second);
+        assert!(matches!(
+            replay.next_response(),
+            Err(CodexErr::ReplayExhausted(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_recordings() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, "\n{\"model\": \"o3\"}\n").unwrap();
+        let err = Replay::load(&path).err().unwrap().to_string();
+        assert!(err.contains("session.jsonl:2: missing field"), "{err}");
+    }
+}
@@ -0,0 +1,293 @@
+//! Compact map of the repository, included in the instructions so that the
+//! model knows its way around the project from the first turn.
+//!
+//! The map lists the files under the working directory, or under the
+//! `--focus` of the session, grouped by directory, shallowest first, and follows the name of each source file with its
+//! top-level definitions (`fn`, `struct`, `class`, exported functions, ...).
+//! Files, then definitions, are added until the map would exceed its token
+//! budget (`repo_map_max_tokens`); the rest is summarized as a count.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex_lite::Regex;
+
+use crate::rate_limit::BYTES_PER_TOKEN;
+use crate::workspace_index::WorkspaceIndex;
+
+/// Larger trees are mapped from their first files only.
+const MAX_INDEXED_FILES: usize = 20_000;
+
+/// Definitions are looked for in the first bytes of a file only.
+const MAX_SOURCE_BYTES: u64 = 256 * 1024;
+
+const MAX_SYMBOLS_PER_FILE: usize = 12;
+
+/// Room kept for the line counting the files left out.
+const OMITTED_LINE_BYTES: usize = 32;
+
+const HEADER: &str =
+    "Files in the working directory, with the top-level definitions of source files:\n";
+
+/// Top-level definitions per file extension. The first capture group is the
+/// kind of definition and the second its name.
+static SYMBOL_PATTERNS: LazyLock<Vec<(&[&str], Regex)>> = LazyLock::new(|| {
+    let patterns: [(&[&str], &str); 5] = [
+        (
+            &["rs"],
+            r"^(?:pub(?:\([^)]*\))?\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(fn|struct|enum|trait|type)\s+([A-Za-z_]\w*)",
+        ),
+        (&["py"], r"^(?:async\s+)?(def|class)\s+([A-Za-z_]\w*)"),
+        (
+            &["js", "jsx", "mjs", "ts", "tsx"],
+            r"^export\s+(?:default\s+)?(?:async\s+)?(function|class|interface|type|const|enum)\s+([A-Za-z_$][\w$]*)",
+        ),
+        (&["go"], r"^(func|type)\s+(?:\([^)]*\)\s*)?([A-Za-z_]\w*)"),
+        (
+            &["java", "kt"],
+            r"^(?:public\s+|abstract\s+|final\s+|data\s+|sealed\s+)*(class|interface|enum|record|object)\s+([A-Za-z_]\w*)",
+        ),
+    ];
+    patterns
+        .into_iter()
+        .map(|(extensions, pattern)| (extensions, symbol_pattern(pattern)))
+        .collect()
+});
+
+#[expect(clippy::expect_used)]
+fn symbol_pattern(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("invalid repo map pattern")
+}
+
+struct MappedFile<'a> {
+    path: &'a str,
+    dir: &'a str,
+    name: &'a str,
+    symbols: Vec<String>,
+}
+
+/// Map of the files under `root`, or only under `focus` when set, in about
+/// `max_tokens` tokens, or `None` when the map is disabled or there are no
+/// files.
+pub(crate) fn repo_map(root: &Path, focus: Option<&Path>, max_tokens: usize) -> Option<String> {
+    if max_tokens == 0 {
+        return None;
+    }
+    let index = WorkspaceIndex::build_within(root, focus.unwrap_or(root), MAX_INDEXED_FILES);
+    let mut paths: Vec<&str> = index.files().collect();
+    paths.sort_by_key(|path| (path.matches('/').count(), *path));
+    render(root, &paths, max_tokens * BYTES_PER_TOKEN)
+}
+
+fn render(root: &Path, paths: &[&str], budget: usize) -> Option<String> {
+    let mut used = HEADER.len() + OMITTED_LINE_BYTES;
+    let mut dirs = HashSet::new();
+    let mut files = Vec::new();
+    for path in paths {
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let mut cost = name.len() + 3;
+        if !dir.is_empty() && !dirs.contains(dir) {
+            cost += dir.len() + 2;
+        }
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        dirs.insert(dir);
+        files.push(MappedFile {
+            path,
+            dir,
+            name,
+            symbols: Vec::new(),
+        });
+    }
+    if files.is_empty() {
+        return None;
+    }
+
+    'files: for file in &mut files {
+        for symbol in symbols(root, file.path) {
+            let cost = symbol.len() + 2;
+            if used + cost > budget {
+                break 'files;
+            }
+            used += cost;
+            file.symbols.push(symbol);
+        }
+    }
+
+    let omitted = paths.len() - files.len();
+    let mut by_dir: BTreeMap<&str, Vec<&MappedFile>> = BTreeMap::new();
+    for file in &files {
+        by_dir.entry(file.dir).or_default().push(file);
+    }
+    let mut map = HEADER.to_string();
+    for (dir, mut files) in by_dir {
+        files.sort_by_key(|file| file.name);
+        let indent = if dir.is_empty() {
+            ""
+        } else {
+            map.push_str(&format!("{dir}/\n"));
+            "  "
+        };
+        for file in files {
+            map.push_str(indent);
+            map.push_str(file.name);
+            if !file.symbols.is_empty() {
+                map.push_str(": ");
+                map.push_str(&file.symbols.join(", "));
+            }
+            map.push('\n');
+        }
+    }
+    if omitted > 0 {
+        map.push_str(&format!("({omitted} more files not shown)\n"));
+    }
+    Some(map)
+}
+
+/// Top-level definitions in the file at `path`, e.g. `fn main`.
+fn symbols(root: &Path, path: &str) -> Vec<String> {
+    let Some((source, pattern)) = read_source(root, path) else {
+        return Vec::new();
+    };
+    source
+        .lines()
+        .filter_map(|line| pattern.captures(line))
+        .map(|captures| format!("{} {}", &captures[1], &captures[2]))
+        .take(MAX_SYMBOLS_PER_FILE)
+        .collect()
+}
+
+/// Where a top-level definition is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Definition {
+    /// Relative to the root, with `/` as the separator.
+    pub(crate) path: String,
+    /// 1-based.
+    pub(crate) line: usize,
+}
+
+/// The top-level definitions named `name` in the files under `root`, in the
+/// order of the map.
+pub(crate) fn find_definitions(root: &Path, name: &str) -> Vec<Definition> {
+    let index = WorkspaceIndex::build(root, MAX_INDEXED_FILES);
+    let mut paths: Vec<&str> = index.files().collect();
+    paths.sort_by_key(|path| (path.matches('/').count(), *path));
+    let mut definitions = Vec::new();
+    for path in paths {
+        let Some((source, pattern)) = read_source(root, path) else {
+            continue;
+        };
+        definitions.extend(
+            source
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| {
+                    pattern
+                        .captures(line)
+                        .is_some_and(|captures| &captures[2] == name)
+                })
+                .map(|(index, _)| Definition {
+                    path: path.to_string(),
+                    line: index + 1,
+                }),
+        );
+    }
+    definitions
+}
+
+/// The start of the source file at `path` with the pattern of its language,
+/// or `None` for other files.
+fn read_source(root: &Path, path: &str) -> Option<(String, &'static Regex)> {
+    let extension = path.rsplit_once('.').map_or("", |(_, extension)| extension);
+    let (_, pattern) = SYMBOL_PATTERNS
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&extension))?;
+    let mut source = Vec::new();
+    std::fs::File::open(root.join(path))
+        .and_then(|file| file.take(MAX_SOURCE_BYTES).read_to_end(&mut source))
+        .ok()?;
+    Some((String::from_utf8_lossy(&source).into_owned(), pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn project() -> TempDir {
+        let root = TempDir::new().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write("Cargo.toml", "[package]\nname = \"demo\"\n");
+        write(
+            "src/lib.rs",
+            "pub struct Config;\n\nimpl Config {\n    pub fn load() {}\n}\n\npub(crate) async fn run() {}\n",
+        );
+        write("src/main.rs", "fn main() {}\n");
+        write(
+            "web/app.ts",
+            "export default function App() {}\nconst hidden = 1;\n",
+        );
+        write(
+            "scripts/tool.py",
+            "class Tool:\n    def run(self):\n        pass\n",
+        );
+        root
+    }
+
+    #[test]
+    fn lists_files_with_their_definitions() {
+        let root = project();
+        assert_eq!(
+            repo_map(root.path(), None, 1000).unwrap(),
+            format!(
+                "{HEADER}Cargo.toml\nscripts/\n  tool.py: class Tool\nsrc/\n  lib.rs: struct Config, fn run\n  main.rs: fn main\nweb/\n  app.ts: function App\n"
+            )
+        );
+        assert_eq!(repo_map(root.path(), None, 0), None);
+    }
+
+    #[test]
+    fn a_focus_limits_the_map_to_its_files() {
+        let root = project();
+        assert_eq!(
+            repo_map(root.path(), Some(&root.path().join("src")), 1000).unwrap(),
+            format!("{HEADER}src/\n  lib.rs: struct Config, fn run\n  main.rs: fn main\n")
+        );
+    }
+
+    #[test]
+    fn finds_definitions_by_name() {
+        let root = project();
+        assert_eq!(
+            find_definitions(root.path(), "run"),
+            vec![Definition {
+                path: "src/lib.rs".to_string(),
+                line: 7,
+            }]
+        );
+        assert_eq!(find_definitions(root.path(), "load"), Vec::new());
+    }
+
+    #[test]
+    fn stays_within_the_budget() {
+        let root = project();
+        let budget = 40;
+        let map = repo_map(root.path(), None, budget).unwrap();
+        assert!(map.len() <= budget * BYTES_PER_TOKEN, "{map}");
+        // The shallowest files come first; the rest are counted.
+        assert!(map.starts_with(&format!("{HEADER}Cargo.toml\n")), "{map}");
+        assert!(map.ends_with("more files not shown)\n"), "{map}");
+    }
+}
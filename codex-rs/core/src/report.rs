@@ -0,0 +1,289 @@
+//! Reports of headless runs in the formats CI systems already read: JUnit
+//! XML for the outcome of tasks and test runs, and SARIF for the problems
+//! the agent found in the code.
+//!
+//! Findings come from the agent's final message, which `--report sarif`
+//! constrains with [`findings_format`].
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+
+use crate::protocol::ResponseFormat;
+
+/// Appended to the prompt of a run that reports findings.
+pub const FINDINGS_REQUEST: &str = "Once you are done, list the problems you found in the code as findings, each with the file and line it is about; an empty list is fine.";
+
+/// How serious a finding is, with SARIF's names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A problem the agent found.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Finding {
+    /// Relative to the working directory.
+    pub path: String,
+    /// 1-based.
+    pub line: u64,
+    pub severity: Severity,
+    /// Short kebab-case id of the kind of problem, e.g. `unchecked-error`.
+    pub rule: String,
+    pub message: String,
+}
+
+/// The final message of a run that reports findings.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Findings {
+    pub summary: String,
+    pub findings: Vec<Finding>,
+}
+
+/// The schema the final message of a run that reports findings must match.
+pub fn findings_format() -> ResponseFormat {
+    ResponseFormat {
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "summary": {
+                    "type": "string",
+                    "description": "What was done, in a few sentences."
+                },
+                "findings": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to the working directory."
+                            },
+                            "line": { "type": "integer", "description": "1-based line number." },
+                            "severity": { "enum": ["error", "warning", "note"] },
+                            "rule": {
+                                "type": "string",
+                                "description": "Short kebab-case id of the kind of problem."
+                            },
+                            "message": { "type": "string" }
+                        },
+                        "required": ["path", "line", "severity", "rule", "message"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["summary", "findings"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// The findings in a final message that matched [`findings_format`].
+pub fn parse_findings(message: &str) -> serde_json::Result<Findings> {
+    serde_json::from_str(message.trim())
+}
+
+/// A SARIF 2.1.0 log of `findings`; `succeeded` says whether the task ran to
+/// completion.
+pub fn sarif(findings: &[Finding], succeeded: bool) -> Value {
+    let rules: BTreeSet<&str> = findings
+        .iter()
+        .map(|finding| finding.rule.as_str())
+        .collect();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            json!({
+                "ruleId": finding.rule,
+                "level": finding.severity.as_str(),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": finding.path.replace('\\', "/"),
+                            "uriBaseId": "%SRCROOT%"
+                        },
+                        "region": { "startLine": finding.line.max(1) }
+                    }
+                }]
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "codex",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/openai/codex",
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>()
+                }
+            },
+            "invocations": [{ "executionSuccessful": succeeded }],
+            "results": results
+        }]
+    })
+}
+
+/// One `<testcase>` of a JUnit report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub duration: Duration,
+    /// Why it failed; `None` when it passed.
+    pub failure: Option<String>,
+    /// Shown as the case's output, e.g. the agent's last message.
+    pub output: Option<String>,
+}
+
+/// A JUnit XML report with `cases` in one suite named `suite`.
+pub fn junit(suite: &str, cases: &[TestCase]) -> String {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let time: f64 = cases.iter().map(|case| case.duration.as_secs_f64()).sum();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" errors=\"0\" time=\"{time:.3}\">\n",
+        escape_xml(suite),
+        cases.len()
+    );
+    for case in cases {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            escape_xml(suite),
+            escape_xml(&case.name),
+            case.duration.as_secs_f64()
+        ));
+        if case.failure.is_none() && case.output.is_none() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        if let Some(failure) = &case.failure {
+            let message = failure.lines().next().unwrap_or_default();
+            xml.push_str(&format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                escape_xml(message),
+                escape_xml(failure)
+            ));
+        }
+        if let Some(output) = &case.output {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                escape_xml(output)
+            ));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Escape `text` for XML, dropping the control characters XML 1.0 does not
+/// allow, such as the escape that starts a color code.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn findings_become_sarif_results() {
+        let findings = parse_findings(
+            r#"{"summary": "Reviewed.", "findings": [
+                {"path": "src\\lib.rs", "line": 12, "severity": "warning", "rule": "unchecked-error", "message": "The result is ignored."}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(findings.summary, "Reviewed.");
+
+        let log = sarif(&findings.findings, true);
+        let run = &log["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{ "id": "unchecked-error" }])
+        );
+        assert_eq!(run["invocations"][0]["executionSuccessful"], json!(true));
+        assert_eq!(
+            run["results"][0],
+            json!({
+                "ruleId": "unchecked-error",
+                "level": "warning",
+                "message": { "text": "The result is ignored." },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/lib.rs", "uriBaseId": "%SRCROOT%" },
+                        "region": { "startLine": 12 }
+                    }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn junit_reports_failures_and_output() {
+        let cases = vec![
+            TestCase {
+                name: "initial run".to_string(),
+                duration: Duration::from_millis(1500),
+                failure: Some(
+                    "test result: FAILED\n\u{1b}[31mpanicked\u{1b}[0m at <a>".to_string(),
+                ),
+                output: None,
+            },
+            TestCase {
+                name: "after fix 1".to_string(),
+                duration: Duration::from_millis(500),
+                failure: None,
+                output: None,
+            },
+        ];
+        assert_eq!(
+            junit("cargo test", &cases),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites>
+  <testsuite name=\"cargo test\" tests=\"2\" failures=\"1\" errors=\"0\" time=\"2.000\">
+    <testcase classname=\"cargo test\" name=\"initial run\" time=\"1.500\">
+      <failure message=\"test result: FAILED\">test result: FAILED
+[31mpanicked[0m at &lt;a&gt;</failure>
+    </testcase>
+    <testcase classname=\"cargo test\" name=\"after fix 1\" time=\"0.500\"/>
+  </testsuite>
+</testsuites>
+"
+        );
+    }
+}
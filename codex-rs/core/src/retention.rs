@@ -0,0 +1,153 @@
+//! Enforcement of the `[retention]` table: session rollouts and audit logs
+//! last written longer ago than it allows are deleted when a session starts
+//! and by `codex gc`. The summaries the session stores keep of deleted
+//! rollouts go with them.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::audit::audit_dir;
+use crate::config_types::Retention;
+use crate::rollout::prune_index;
+use crate::rollout::rollout_files;
+use crate::rollout::sessions_dir;
+use crate::session_store::forget_deleted_sessions;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// What a purge deleted, or would delete on a dry run.
+#[derive(Debug, Default)]
+pub struct PurgeReport {
+    pub sessions: Vec<PathBuf>,
+    pub audit_logs: Vec<PathBuf>,
+    /// Combined size of the files.
+    pub bytes: u64,
+}
+
+/// Delete the rollouts and audit logs in `codex_home` that `retention` no
+/// longer allows to keep, except `keep` (the rollout of a resumed session).
+/// With `dry_run`, only report them.
+pub fn purge(
+    codex_home: &Path,
+    retention: &Retention,
+    keep: Option<&Path>,
+    dry_run: bool,
+) -> io::Result<PurgeReport> {
+    let now = SystemTime::now();
+    let mut report = PurgeReport::default();
+
+    if let Some(cutoff) = cutoff(now, retention.sessions_days) {
+        let dir = sessions_dir(codex_home);
+        for rollout in rollout_files(codex_home)? {
+            let path = dir.join(&rollout.name);
+            if rollout.modified < cutoff && keep != Some(path.as_path()) {
+                report.bytes += rollout.len;
+                report.sessions.push(path);
+            }
+        }
+    }
+
+    if let Some(cutoff) = cutoff(now, retention.audit_days) {
+        match fs::read_dir(audit_dir(codex_home)) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry?;
+                    let metadata = entry.metadata()?;
+                    if metadata.is_file() && metadata.modified()? < cutoff {
+                        report.bytes += metadata.len();
+                        report.audit_logs.push(entry.path());
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+    for path in report.sessions.iter().chain(&report.audit_logs) {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if !report.sessions.is_empty() {
+        prune_index(codex_home)?;
+        forget_deleted_sessions(codex_home).map_err(io::Error::other)?;
+    }
+    Ok(report)
+}
+
+/// Files last written before this are too old to keep.
+fn cutoff(now: SystemTime, days: Option<u32>) -> Option<SystemTime> {
+    let age = Duration::from_secs(u64::from(days?) * SECONDS_PER_DAY);
+    Some(now.checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn write_old(path: &Path, days: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "{}\n").unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(days * SECONDS_PER_DAY);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn deletes_what_outlived_its_retention() {
+        let home = TempDir::new().unwrap();
+        let sessions = sessions_dir(home.path());
+        let old =
+            sessions.join("rollout-2025-01-01T00-00-00-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl");
+        let resumed =
+            sessions.join("rollout-2025-01-02T00-00-00-5973b6c1-0000-487b-a530-2aeb6098ae0e.jsonl");
+        let recent =
+            sessions.join("rollout-2025-03-01T00-00-00-5973b6c2-0000-487b-a530-2aeb6098ae0e.jsonl");
+        let old_audit = audit_dir(home.path()).join("5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl");
+        write_old(&old, 40);
+        write_old(&resumed, 40);
+        write_old(&recent, 5);
+        write_old(&old_audit, 40);
+
+        let retention = Retention {
+            sessions_days: Some(30),
+            audit_days: None,
+        };
+        let report = purge(home.path(), &retention, Some(&resumed), true).unwrap();
+        assert_eq!(report.sessions, vec![old.clone()]);
+        assert_eq!(report.bytes, 3);
+        assert!(old.exists());
+
+        purge(home.path(), &retention, Some(&resumed), false).unwrap();
+        assert!(!old.exists());
+        assert!(resumed.exists());
+        assert!(recent.exists());
+        assert!(old_audit.exists());
+
+        let retention = Retention {
+            sessions_days: None,
+            audit_days: Some(30),
+        };
+        let report = purge(home.path(), &retention, None, false).unwrap();
+        assert_eq!(report.audit_logs, vec![old_audit.clone()]);
+        assert!(!old_audit.exists());
+        assert!(resumed.exists());
+    }
+}
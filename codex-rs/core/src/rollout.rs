@@ -2,11 +2,29 @@
 //! [`ResponseItem`] objects exchanged during a session – to disk so that
 //! sessions can be replayed or inspected later (mirrors the behaviour of the
 //! upstream TypeScript implementation).
+//!
+//! With `compress_sessions`, each batch of lines is written as its own zstd
+//! frame to a `.jsonl.zst` file, so that the file stays readable after every
+//! write and a resumed session can keep appending to it. Listing sessions
+//! goes through an index of their summaries, and only reads the rollouts
+//! that changed since it was written.
+//!
+//! With `encrypt_sessions`, each batch is then sealed as described in
+//! [`crate::encryption`], in a `.jsonl.enc` or `.jsonl.zst.enc` file.
 
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs::File;
 use std::fs::{self};
+use std::io::BufRead;
+use std::io::BufReader;
 use std::io::Error as IoError;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
+use serde::Deserialize;
 use serde::Serialize;
 use time::OffsetDateTime;
 use time::format_description::FormatItem;
@@ -17,26 +35,122 @@ use tokio::sync::mpsc::{self};
 use uuid::Uuid;
 
 use crate::config::Config;
+use crate::encryption::DecryptingReader;
+use crate::encryption::ENCRYPTED_SUFFIX;
+use crate::encryption::SessionKey;
+use crate::encryption::session_key;
+use crate::models::ContentItem;
 use crate::models::ResponseItem;
+use crate::protocol::TaskStats;
 
 /// Folder inside `~/.codex` that holds saved rollouts.
 const SESSIONS_SUBDIR: &str = "sessions";
 
-#[derive(Serialize)]
-struct SessionMeta {
-    id: String,
-    timestamp: String,
+/// Appended to the `.jsonl` extension of compressed rollouts.
+const COMPRESSED_SUFFIX: &str = ".zst";
+
+/// File in the sessions folder caching the summary of each rollout.
+const INDEX_FILE: &str = "index.json";
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SessionMeta {
+    pub(crate) id: String,
+    pub(crate) timestamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    instructions: Option<String>,
+    pub(crate) instructions: Option<String>,
+    /// Working directory of the session. Missing in rollouts written before
+    /// it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cwd: Option<PathBuf>,
+    /// Short description of the session. It is generated once the session
+    /// is under way, so it is read from a later `session_title` line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    /// Totals of the tasks that completed, read from later `task_stats`
+    /// lines.
+    #[serde(skip)]
+    pub(crate) stats: TaskStats,
+    /// Content of the model's scratchpad, read from the last `scratchpad`
+    /// line.
+    #[serde(skip)]
+    pub(crate) scratchpad: Option<String>,
+}
+
+/// Line appended to the rollout once the session's title is known.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "session_title")]
+struct SessionTitleLine {
+    title: String,
+}
+
+/// Line appended to the rollout each time the model changes its scratchpad.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "scratchpad")]
+struct ScratchpadLine {
+    content: String,
+}
+
+/// Line appended to the rollout when a checkpoint is taken, ahead of the
+/// items recorded after it.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "checkpoint")]
+struct CheckpointLine {
+    number: usize,
+}
+
+/// Line appended to the rollout when the session rewinds to a checkpoint:
+/// the items recorded since that checkpoint are no longer part of the
+/// conversation.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "rewind")]
+struct RewindLine {
+    checkpoint: usize,
+}
+
+/// Line appended to the rollout when a task completes.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "task_stats")]
+struct TaskStatsLine {
+    /// Id of the submission that started the task.
+    turn_id: String,
+    #[serde(flatten)]
+    stats: TaskStats,
+}
+
+/// A recorded session, as listed by [`list_sessions`].
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub path: PathBuf,
+    pub id: String,
+    pub cwd: Option<PathBuf>,
+    /// Short description generated from the first prompt, if any.
+    pub title: Option<String>,
+    /// Text of the first user message.
+    pub first_prompt: Option<String>,
+    /// Number of user messages in the session.
+    pub turns: usize,
+    /// Time, tokens and cost of the session's completed tasks.
+    pub stats: TaskStats,
+    /// When the rollout was last written to.
+    pub modified: SystemTime,
+}
+
+/// Conversation read back from a rollout so that a session can continue it.
+pub(crate) struct ResumedSession {
+    pub(crate) path: PathBuf,
+    pub(crate) session_id: Uuid,
+    pub(crate) items: Vec<ResponseItem>,
+    pub(crate) scratchpad: Option<String>,
 }
 
 /// Records all [`ResponseItem`]s for a session and flushes them to disk after
 /// every update.
 ///
-/// Rollouts are recorded as JSONL and can be inspected with tools such as:
+/// Rollouts are recorded as JSONL, compressed unless `compress_sessions` is
+/// off, and can be inspected with tools such as:
 ///
 /// ```ignore
-/// $ jq -C . ~/.codex/sessions/rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl
+/// $ zstdcat ~/.codex/sessions/rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl.zst | jq -C .
 /// $ fx ~/.codex/sessions/rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl
 /// ```
 #[derive(Clone)]
@@ -55,6 +169,8 @@ impl RolloutRecorder {
     ) -> std::io::Result<Self> {
         let LogFileInfo {
             file,
+            compressed,
+            key,
             session_id,
             timestamp,
         } = create_log_file(config, uuid)?;
@@ -71,42 +187,42 @@ impl RolloutRecorder {
             timestamp,
             id: session_id.to_string(),
             instructions,
+            cwd: Some(config.cwd.clone()),
+            title: None,
+            stats: TaskStats::default(),
+            scratchpad: None,
         };
 
-        // A reasonably-sized bounded channel. If the buffer fills up the send
-        // future will yield, which is fine – we only need to ensure we do not
-        // perform *blocking* I/O on the caller’s thread.
-        let (tx, mut rx) = mpsc::channel::<String>(256);
-
-        // Spawn a Tokio task that owns the file handle and performs async
-        // writes. Using `tokio::fs::File` keeps everything on the async I/O
-        // driver instead of blocking the runtime.
-        tokio::task::spawn(async move {
-            let mut file = tokio::fs::File::from_std(file);
-
-            while let Some(line) = rx.recv().await {
-                // Write line + newline, then flush to disk.
-                if let Err(e) = file.write_all(line.as_bytes()).await {
-                    tracing::warn!("rollout writer: failed to write line: {e}");
-                    break;
-                }
-                if let Err(e) = file.write_all(b"\n").await {
-                    tracing::warn!("rollout writer: failed to write newline: {e}");
-                    break;
-                }
-                if let Err(e) = file.flush().await {
-                    tracing::warn!("rollout writer: failed to flush: {e}");
-                    break;
-                }
-            }
-        });
-
-        let recorder = Self { tx };
+        let recorder = Self {
+            tx: spawn_writer(file, compressed, key),
+        };
         // Ensure SessionMeta is the first item in the file.
         recorder.record_item(&meta).await?;
         Ok(recorder)
     }
 
+    /// Continue appending to the rollout of an earlier session.
+    pub fn resume(path: &Path) -> std::io::Result<Self> {
+        let key = is_encrypted(path).then(session_key).transpose()?;
+        let file = std::fs::OpenOptions::new().append(true).open(path)?;
+        Ok(Self {
+            tx: spawn_writer(file, is_compressed(path), key),
+        })
+    }
+
+    /// Read the rollout at `path` so that its conversation can be continued.
+    pub(crate) fn read_for_resume(path: &Path) -> std::io::Result<ResumedSession> {
+        let (meta, items) = read_rollout(path)?;
+        let session_id = Uuid::parse_str(&meta.id)
+            .map_err(|e| IoError::other(format!("invalid session id `{}`: {e}", meta.id)))?;
+        Ok(ResumedSession {
+            path: path.to_path_buf(),
+            session_id,
+            items,
+            scratchpad: meta.scratchpad,
+        })
+    }
+
     /// Append `items` to the rollout file.
     pub(crate) async fn record_items(&self, items: &[ResponseItem]) -> std::io::Result<()> {
         for item in items {
@@ -128,6 +244,45 @@ impl RolloutRecorder {
         Ok(())
     }
 
+    /// Record the title of the session.
+    pub(crate) async fn record_title(&self, title: &str) -> std::io::Result<()> {
+        self.record_item(&SessionTitleLine {
+            title: title.to_string(),
+        })
+        .await
+    }
+
+    /// Record the new content of the model's scratchpad.
+    pub(crate) async fn record_scratchpad(&self, content: &str) -> std::io::Result<()> {
+        self.record_item(&ScratchpadLine {
+            content: content.to_string(),
+        })
+        .await
+    }
+
+    /// Record that checkpoint `number` was taken.
+    pub(crate) async fn record_checkpoint(&self, number: usize) -> std::io::Result<()> {
+        self.record_item(&CheckpointLine { number }).await
+    }
+
+    /// Record that the session rewound to checkpoint `checkpoint`.
+    pub(crate) async fn record_rewind(&self, checkpoint: usize) -> std::io::Result<()> {
+        self.record_item(&RewindLine { checkpoint }).await
+    }
+
+    /// Record what the task started by submission `turn_id` took.
+    pub(crate) async fn record_task_stats(
+        &self,
+        turn_id: &str,
+        stats: &TaskStats,
+    ) -> std::io::Result<()> {
+        self.record_item(&TaskStatsLine {
+            turn_id: turn_id.to_string(),
+            stats: stats.clone(),
+        })
+        .await
+    }
+
     async fn record_item(&self, item: &impl Serialize) -> std::io::Result<()> {
         // Serialize the item to JSON first so that the writer thread only has
         // to perform the actual write.
@@ -141,10 +296,74 @@ impl RolloutRecorder {
     }
 }
 
+/// Spawn the task that appends queued lines to `file`, each batch as a zstd
+/// frame if `compressed`, then sealed with `key` if any.
+fn spawn_writer(file: File, compressed: bool, key: Option<SessionKey>) -> Sender<String> {
+    // A reasonably-sized bounded channel. If the buffer fills up the send
+    // future will yield, which is fine – we only need to ensure we do not
+    // perform *blocking* I/O on the caller’s thread.
+    let (tx, mut rx) = mpsc::channel::<String>(256);
+
+    // Spawn a Tokio task that owns the file handle and performs async
+    // writes. Using `tokio::fs::File` keeps everything on the async I/O
+    // driver instead of blocking the runtime.
+    tokio::task::spawn(async move {
+        let mut file = tokio::fs::File::from_std(file);
+
+        while let Some(line) = rx.recv().await {
+            // Take whatever else is queued so that a compressed batch is
+            // worth its frame.
+            let mut batch = line;
+            batch.push('\n');
+            while let Ok(line) = rx.try_recv() {
+                batch.push_str(&line);
+                batch.push('\n');
+            }
+            let bytes = if compressed {
+                match zstd::encode_all(batch.as_bytes(), 0) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        tracing::warn!("rollout writer: failed to compress lines: {e}");
+                        break;
+                    }
+                }
+            } else {
+                batch.into_bytes()
+            };
+            let bytes = match &key {
+                Some(key) => match key.seal(&bytes) {
+                    Ok(sealed) => sealed,
+                    Err(e) => {
+                        tracing::warn!("rollout writer: failed to encrypt lines: {e}");
+                        break;
+                    }
+                },
+                None => bytes,
+            };
+            // Write the batch, then flush to disk.
+            if let Err(e) = file.write_all(&bytes).await {
+                tracing::warn!("rollout writer: failed to write lines: {e}");
+                break;
+            }
+            if let Err(e) = file.flush().await {
+                tracing::warn!("rollout writer: failed to flush: {e}");
+                break;
+            }
+        }
+    });
+    tx
+}
+
 struct LogFileInfo {
     /// Opened file handle to the rollout file.
     file: File,
 
+    /// Whether the rollout is written zstd-compressed.
+    compressed: bool,
+
+    /// Key the rollout is encrypted with, if it is.
+    key: Option<SessionKey>,
+
     /// Session ID (also embedded in filename).
     session_id: Uuid,
 
@@ -154,8 +373,7 @@ struct LogFileInfo {
 
 fn create_log_file(config: &Config, session_id: Uuid) -> std::io::Result<LogFileInfo> {
     // Resolve ~/.codex/sessions and create it if missing.
-    let mut dir = config.codex_home.clone();
-    dir.push(SESSIONS_SUBDIR);
+    let dir = sessions_dir(&config.codex_home);
     fs::create_dir_all(&dir)?;
 
     let timestamp = OffsetDateTime::now_local()
@@ -169,7 +387,17 @@ fn create_log_file(config: &Config, session_id: Uuid) -> std::io::Result<LogFile
         .format(format)
         .map_err(|e| IoError::other(format!("failed to format timestamp: {e}")))?;
 
-    let filename = format!("rollout-{date_str}-{session_id}.jsonl");
+    // Get the key before creating the file, so that a missing key leaves no
+    // empty rollout behind.
+    let key = config.encrypt_sessions.then(session_key).transpose()?;
+    let compressed = config.compress_sessions;
+    let mut filename = format!("rollout-{date_str}-{session_id}.jsonl");
+    if compressed {
+        filename.push_str(COMPRESSED_SUFFIX);
+    }
+    if key.is_some() {
+        filename.push_str(ENCRYPTED_SUFFIX);
+    }
 
     let path = dir.join(filename);
     let file = std::fs::OpenOptions::new()
@@ -179,7 +407,529 @@ fn create_log_file(config: &Config, session_id: Uuid) -> std::io::Result<LogFile
 
     Ok(LogFileInfo {
         file,
+        compressed,
+        key,
         session_id,
         timestamp,
     })
 }
+
+/// Directory under `codex_home` that holds the rollout files.
+pub(crate) fn sessions_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join(SESSIONS_SUBDIR)
+}
+
+/// Locate the rollout for the session whose id is (or starts with) `id`.
+/// An ambiguous prefix is reported as an error rather than guessed.
+pub fn find_rollout(codex_home: &Path, id: &str) -> std::io::Result<PathBuf> {
+    let id = id.trim().to_ascii_lowercase();
+    let mut matches = Vec::new();
+    for entry in fs::read_dir(sessions_dir(codex_home))? {
+        let path = entry?.path();
+        let Some(session_id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(rollout_session_id)
+        else {
+            continue;
+        };
+        if session_id == id {
+            return Ok(path);
+        }
+        if !id.is_empty() && session_id.starts_with(&id) {
+            matches.push(path);
+        }
+    }
+    match matches.len() {
+        0 => Err(IoError::new(
+            std::io::ErrorKind::NotFound,
+            format!("no session with id `{id}`"),
+        )),
+        1 => Ok(matches.remove(0)),
+        n => Err(IoError::other(format!(
+            "session id `{id}` is ambiguous ({n} sessions match)"
+        ))),
+    }
+}
+
+/// The `limit` most recently active sessions, newest first. Rollouts that
+/// cannot be read are left out.
+pub fn list_sessions(codex_home: &Path, limit: usize) -> std::io::Result<Vec<SessionSummary>> {
+    let dir = sessions_dir(codex_home);
+    let rollouts = rollout_files(codex_home)?;
+    let index_path = dir.join(INDEX_FILE);
+    let mut index = read_index(&index_path);
+    let mut index_changed = false;
+    let mut summaries = Vec::new();
+    for rollout in &rollouts {
+        if summaries.len() == limit {
+            break;
+        }
+        let path = dir.join(&rollout.name);
+        let cached = index
+            .get(&rollout.name)
+            .filter(|entry| entry.len == rollout.len);
+        let summary = match cached {
+            Some(entry) => entry.to_summary(path, rollout.modified),
+            None => {
+                let Ok((meta, items)) =
+                    read_rollout_items(&path, |head| head.role.as_deref() == Some("user"))
+                else {
+                    continue;
+                };
+                let entry = IndexEntry::new(meta, &items, rollout.len);
+                let summary = entry.to_summary(path.clone(), rollout.modified);
+                // The index is not encrypted, so what encrypted rollouts say
+                // stays out of it.
+                if !is_encrypted(&path) {
+                    index.insert(rollout.name.clone(), entry);
+                    index_changed = true;
+                }
+                summary
+            }
+        };
+        // Sessions that never got a prompt are not worth resuming.
+        if summary.first_prompt.is_none() {
+            continue;
+        }
+        summaries.push(summary);
+    }
+
+    if index_changed {
+        let names: HashSet<&String> = rollouts.iter().map(|rollout| &rollout.name).collect();
+        index.retain(|name, _| names.contains(name));
+        if let Err(e) = write_index(&index_path, &index) {
+            tracing::warn!("failed to write {}: {e}", index_path.display());
+        }
+    }
+    Ok(summaries)
+}
+
+/// A rollout in the sessions folder.
+pub(crate) struct RolloutFile {
+    pub(crate) name: String,
+    pub(crate) len: u64,
+    pub(crate) modified: SystemTime,
+}
+
+/// The rollouts in the sessions folder, most recently written first.
+pub(crate) fn rollout_files(codex_home: &Path) -> std::io::Result<Vec<RolloutFile>> {
+    let entries = match fs::read_dir(sessions_dir(codex_home)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut rollouts = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if rollout_session_id(&name).is_none() {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        rollouts.push(RolloutFile {
+            name,
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        });
+    }
+    rollouts.sort_by_key(|rollout| std::cmp::Reverse(rollout.modified));
+    Ok(rollouts)
+}
+
+/// Summary of a rollout, as cached by the session stores.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct IndexEntry {
+    /// Length of the rollout when it was summarized. Rollouts only grow, so
+    /// an entry with another length is out of date.
+    pub(crate) len: u64,
+    pub(crate) id: String,
+    pub(crate) cwd: Option<PathBuf>,
+    pub(crate) title: Option<String>,
+    pub(crate) first_prompt: Option<String>,
+    pub(crate) turns: usize,
+    pub(crate) stats: TaskStats,
+}
+
+impl IndexEntry {
+    /// Summarize a rollout `len` bytes long from its metadata and items,
+    /// which must include its user messages.
+    pub(crate) fn new(meta: SessionMeta, items: &[ResponseItem], len: u64) -> Self {
+        let mut user_messages = items.iter().filter_map(|item| match item {
+            ResponseItem::Message { role, content } if role == "user" => Some(content),
+            _ => None,
+        });
+        let first_prompt = user_messages.next().map(|content| {
+            content
+                .iter()
+                .filter_map(|c| match c {
+                    ContentItem::InputText { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        let turns = usize::from(first_prompt.is_some()) + user_messages.count();
+        Self {
+            len,
+            id: meta.id,
+            cwd: meta.cwd,
+            title: meta.title,
+            first_prompt,
+            turns,
+            stats: meta.stats,
+        }
+    }
+
+    pub(crate) fn to_summary(&self, path: PathBuf, modified: SystemTime) -> SessionSummary {
+        SessionSummary {
+            path,
+            id: self.id.clone(),
+            cwd: self.cwd.clone(),
+            title: self.title.clone(),
+            first_prompt: self.first_prompt.clone(),
+            turns: self.turns,
+            stats: self.stats.clone(),
+            modified,
+        }
+    }
+}
+
+/// The sessions index, by rollout file name; empty if it is missing or
+/// unreadable.
+fn read_index(path: &Path) -> HashMap<String, IndexEntry> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Drop the entries of deleted rollouts from the sessions index.
+pub(crate) fn prune_index(codex_home: &Path) -> std::io::Result<()> {
+    let path = sessions_dir(codex_home).join(INDEX_FILE);
+    let mut index = read_index(&path);
+    let names: HashSet<String> = rollout_files(codex_home)?
+        .into_iter()
+        .map(|rollout| rollout.name)
+        .collect();
+    let len = index.len();
+    index.retain(|name, _| names.contains(name));
+    if index.len() == len {
+        return Ok(());
+    }
+    write_index(&path, &index)
+}
+
+fn write_index(path: &Path, index: &HashMap<String, IndexEntry>) -> std::io::Result<()> {
+    // Write a temporary file and rename it, so that a concurrent reader never
+    // sees half an index.
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(index)?)?;
+    fs::rename(&tmp, path)
+}
+
+/// Extract the session id from a `rollout-<timestamp>-<uuid>.jsonl` file
+/// name, compressed and encrypted or not.
+fn rollout_session_id(file_name: &str) -> Option<&str> {
+    let file_name = file_name
+        .strip_suffix(ENCRYPTED_SUFFIX)
+        .unwrap_or(file_name);
+    let file_name = file_name
+        .strip_suffix(COMPRESSED_SUFFIX)
+        .unwrap_or(file_name);
+    let stem = file_name.strip_prefix("rollout-")?.strip_suffix(".jsonl")?;
+    // The uuid is the last 36 characters; the timestamp precedes it.
+    let start = stem.len().checked_sub(36)?;
+    stem.get(start..)
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| {
+        path.strip_suffix(ENCRYPTED_SUFFIX)
+            .unwrap_or(path)
+            .ends_with(COMPRESSED_SUFFIX)
+    })
+}
+
+fn is_encrypted(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.ends_with(ENCRYPTED_SUFFIX))
+}
+
+/// The fields of a rollout line that tell what it holds, read without
+/// parsing the rest.
+#[derive(Deserialize)]
+pub(crate) struct LineHead {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    pub(crate) role: Option<String>,
+}
+
+/// Read a rollout file back into its metadata and recorded items. Lines that
+/// no longer parse (e.g. written by a newer version) are skipped.
+pub(crate) fn read_rollout(path: &Path) -> std::io::Result<(SessionMeta, Vec<ResponseItem>)> {
+    read_rollout_items(path, |_| true)
+}
+
+/// Like [`read_rollout`], keeping only the items whose line `keep` accepts.
+pub(crate) fn read_rollout_items(
+    path: &Path,
+    keep: impl Fn(&LineHead) -> bool,
+) -> std::io::Result<(SessionMeta, Vec<ResponseItem>)> {
+    let mut reader: Box<dyn Read> = Box::new(File::open(path)?);
+    if is_encrypted(path) {
+        reader = Box::new(DecryptingReader::new(reader, session_key()?));
+    }
+    if is_compressed(path) {
+        reader = Box::new(zstd::stream::read::Decoder::new(reader)?);
+    }
+    let mut lines = BufReader::new(reader).lines();
+    let meta_line = lines
+        .next()
+        .ok_or_else(|| IoError::other(format!("{} is empty", path.display())))??;
+    let mut meta: SessionMeta = serde_json::from_str(&meta_line)
+        .map_err(|e| IoError::other(format!("failed to parse session metadata: {e}")))?;
+
+    let mut items = Vec::new();
+    // Number of items recorded before each checkpoint still in effect.
+    let mut checkpoints: HashMap<usize, usize> = HashMap::new();
+    for line in lines {
+        // A session that died mid-write can leave a torn last frame.
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("{} ends in an unreadable record: {e}", path.display());
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let head: LineHead = match serde_json::from_str(&line) {
+            Ok(head) => head,
+            Err(e) => {
+                tracing::warn!("skipping unreadable rollout line: {e}");
+                continue;
+            }
+        };
+        match head.kind.as_deref() {
+            Some("session_title") => {
+                if let Ok(SessionTitleLine { title }) = serde_json::from_str(&line) {
+                    meta.title = Some(title);
+                }
+            }
+            Some("task_stats") => {
+                if let Ok(TaskStatsLine { stats, .. }) = serde_json::from_str(&line) {
+                    meta.stats.add_assign(&stats);
+                }
+            }
+            Some("scratchpad") => {
+                if let Ok(ScratchpadLine { content }) = serde_json::from_str(&line) {
+                    meta.scratchpad = Some(content);
+                }
+            }
+            Some("checkpoint") => {
+                if let Ok(CheckpointLine { number }) = serde_json::from_str(&line) {
+                    checkpoints.insert(number, items.len());
+                }
+            }
+            Some("rewind") => {
+                if let Ok(RewindLine { checkpoint }) = serde_json::from_str(&line) {
+                    if let Some(&len) = checkpoints.get(&checkpoint) {
+                        items.truncate(len);
+                        checkpoints.retain(|number, _| *number <= checkpoint);
+                    }
+                }
+            }
+            _ if !keep(&head) => {}
+            _ => match serde_json::from_str::<ResponseItem>(&line) {
+                Ok(ResponseItem::Other) => {}
+                Ok(item) => items.push(item),
+                Err(e) => tracing::warn!("skipping unreadable rollout line: {e}"),
+            },
+        }
+    }
+    Ok((meta, items))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn finds_rollout_by_id_prefix() {
+        let home = tempfile::TempDir::new().unwrap();
+        let dir = sessions_dir(home.path());
+        fs::create_dir_all(&dir).unwrap();
+        let first =
+            dir.join("rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl");
+        let second =
+            dir.join("rollout-2025-05-08T09-00-00-5973b6c1-0000-487b-a530-2aeb6098ae0e.jsonl");
+        fs::write(&first, "").unwrap();
+        fs::write(&second, "").unwrap();
+
+        assert_eq!(
+            find_rollout(home.path(), "5973B6C0-94b8-487b-a530-2aeb6098ae0e").unwrap(),
+            first
+        );
+        assert_eq!(find_rollout(home.path(), "5973b6c1").unwrap(), second);
+        assert!(find_rollout(home.path(), "5973b6c").is_err());
+        assert_eq!(
+            find_rollout(home.path(), "ffff").unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn lists_sessions_with_a_prompt() {
+        let home = tempfile::TempDir::new().unwrap();
+        assert!(list_sessions(home.path(), 10).unwrap().is_empty());
+
+        let dir = sessions_dir(home.path());
+        fs::create_dir_all(&dir).unwrap();
+        let user = |text: &str| {
+            format!(
+                r#"{{"type":"message","role":"user","content":[{{"type":"input_text","text":"{text}"}}]}}"#
+            )
+        };
+        fs::write(
+            dir.join("rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl"),
+            [
+                r#"{"id":"5973b6c0-94b8-487b-a530-2aeb6098ae0e","timestamp":"t","cwd":"/repo"}"#.to_string(),
+                user("fix the build"),
+                r#"{"type":"message","role":"assistant","content":[{"type":"output_text","text":"done"}]}"#.to_string(),
+                r#"{"type":"session_title","title":"Fix the build"}"#.to_string(),
+                r#"{"type":"task_stats","turn_id":"1","duration_ms":1500,"model_ms":1000,"requests":2,"usage":{"input_tokens":100,"cached_input_tokens":80,"output_tokens":10,"reasoning_output_tokens":null,"total_tokens":110},"cost_usd":0.5}"#.to_string(),
+                user("thanks"),
+                r#"{"type":"task_stats","turn_id":"2","duration_ms":500,"model_ms":400,"requests":1,"usage":{"input_tokens":50,"cached_input_tokens":null,"output_tokens":5,"reasoning_output_tokens":null,"total_tokens":55}}"#.to_string(),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("rollout-2025-05-08T09-00-00-5973b6c1-0000-487b-a530-2aeb6098ae0e.jsonl"),
+            r#"{"id":"5973b6c1-0000-487b-a530-2aeb6098ae0e","timestamp":"t"}"#,
+        )
+        .unwrap();
+
+        let sessions = list_sessions(home.path(), 10).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, "5973b6c0-94b8-487b-a530-2aeb6098ae0e");
+        assert_eq!(sessions[0].cwd, Some(PathBuf::from("/repo")));
+        assert_eq!(sessions[0].title.as_deref(), Some("Fix the build"));
+        assert_eq!(sessions[0].first_prompt.as_deref(), Some("fix the build"));
+        assert_eq!(sessions[0].turns, 2);
+        assert_eq!(
+            sessions[0].stats,
+            TaskStats {
+                duration_ms: 2000,
+                model_ms: 1400,
+                requests: 3,
+                usage: crate::protocol::TokenUsage {
+                    input_tokens: 150,
+                    cached_input_tokens: Some(80),
+                    output_tokens: 15,
+                    reasoning_output_tokens: None,
+                    total_tokens: 165,
+                },
+                cost_usd: Some(0.5),
+            }
+        );
+    }
+
+    #[test]
+    fn reads_compressed_rollouts_written_in_several_frames() {
+        let home = tempfile::TempDir::new().unwrap();
+        let dir = sessions_dir(home.path());
+        fs::create_dir_all(&dir).unwrap();
+        let path =
+            dir.join("rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl.zst");
+        let frame = |lines: &[&str]| zstd::encode_all(lines.join("\n").as_bytes(), 0).unwrap();
+        let meta = r#"{"id":"5973b6c0-94b8-487b-a530-2aeb6098ae0e","timestamp":"t"}"#;
+        let user = r#"{"type":"message","role":"user","content":[{"type":"input_text","text":"fix the build"}]}"#;
+        let assistant = r#"{"type":"message","role":"assistant","content":[{"type":"output_text","text":"done"}]}"#;
+        let mut bytes = frame(&[meta, user, ""]);
+        bytes.extend(frame(&[assistant, ""]));
+        fs::write(&path, &bytes).unwrap();
+
+        let (meta, items) = read_rollout(&path).unwrap();
+        assert_eq!(meta.id, "5973b6c0-94b8-487b-a530-2aeb6098ae0e");
+        assert_eq!(items.len(), 2);
+        assert_eq!(find_rollout(home.path(), "5973b6c0").unwrap(), path);
+        assert_eq!(list_sessions(home.path(), 10).unwrap().len(), 1);
+
+        // A frame cut short by a crash ends the rollout instead of failing it.
+        bytes.extend(&frame(&[user, ""])[..8]);
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(read_rollout(&path).unwrap().1.len(), 2);
+    }
+
+    #[test]
+    fn the_index_is_refreshed_when_a_rollout_grows() {
+        let home = tempfile::TempDir::new().unwrap();
+        let dir = sessions_dir(home.path());
+        fs::create_dir_all(&dir).unwrap();
+        let name = "rollout-2025-05-07T17-24-21-5973b6c0-94b8-487b-a530-2aeb6098ae0e.jsonl";
+        let mut rollout = [
+            r#"{"id":"5973b6c0-94b8-487b-a530-2aeb6098ae0e","timestamp":"t"}"#,
+            r#"{"type":"message","role":"user","content":[{"type":"input_text","text":"fix the build"}]}"#,
+        ]
+        .join("\n");
+        fs::write(dir.join(name), &rollout).unwrap();
+
+        assert_eq!(list_sessions(home.path(), 10).unwrap()[0].turns, 1);
+        let index = read_index(&dir.join(INDEX_FILE));
+        assert_eq!(index[name].first_prompt.as_deref(), Some("fix the build"));
+
+        rollout.push_str(
+            "\n{\"type\":\"message\",\"role\":\"user\",\"content\":[{\"type\":\"input_text\",\"text\":\"thanks\"}]}",
+        );
+        fs::write(dir.join(name), &rollout).unwrap();
+        assert_eq!(list_sessions(home.path(), 10).unwrap()[0].turns, 2);
+        assert_eq!(read_index(&dir.join(INDEX_FILE))[name].turns, 2);
+    }
+
+    #[test]
+    fn items_after_a_rewound_checkpoint_are_dropped() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rollout.jsonl");
+        let user = |text: &str| {
+            format!(
+                r#"{{"type":"message","role":"user","content":[{{"type":"input_text","text":"{text}"}}]}}"#
+            )
+        };
+        fs::write(
+            &path,
+            [
+                r#"{"id":"5973b6c0-94b8-487b-a530-2aeb6098ae0e","timestamp":"t"}"#.to_string(),
+                r#"{"type":"checkpoint","number":1}"#.to_string(),
+                user("first"),
+                r#"{"type":"checkpoint","number":2}"#.to_string(),
+                user("second"),
+                r#"{"type":"checkpoint","number":3}"#.to_string(),
+                user("third"),
+                r#"{"type":"rewind","checkpoint":2}"#.to_string(),
+                r#"{"type":"checkpoint","number":3}"#.to_string(),
+                user("again"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let (_, items) = read_rollout(&path).unwrap();
+        let texts: Vec<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                ResponseItem::Message { content, .. } => match content.first() {
+                    Some(crate::models::ContentItem::InputText { text }) => Some(text.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec!["first", "again"]);
+    }
+}
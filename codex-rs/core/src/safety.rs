@@ -103,6 +103,21 @@ pub fn assess_command_safety(
     }
 }
 
+/// Ask mode only runs commands that are known to just read files, whatever
+/// the approval policy and without asking the user. They still run in the
+/// platform sandbox, under the read-only policy of Ask mode.
+pub fn assess_command_safety_read_only(command: &[String]) -> SafetyCheck {
+    if is_known_safe_command(command) {
+        SafetyCheck::AutoApprove {
+            sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None),
+        }
+    } else {
+        SafetyCheck::Reject {
+            reason: "only commands that read files can run in ask mode".to_string(),
+        }
+    }
+}
+
 pub fn get_platform_sandbox() -> Option<SandboxType> {
     if cfg!(target_os = "macos") {
         Some(SandboxType::MacosSeatbelt)
@@ -113,7 +128,7 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
     }
 }
 
-fn is_write_patch_constrained_to_writable_paths(
+pub(crate) fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     writable_roots: &[PathBuf],
     cwd: &Path,
@@ -224,4 +239,28 @@ mod tests {
             &cwd,
         ))
     }
+
+    #[test]
+    fn ask_mode_only_runs_read_only_commands() {
+        let command = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(matches!(
+            assess_command_safety_read_only(&command(&["rg", "TODO"])),
+            SafetyCheck::AutoApprove { sandbox_type }
+                if sandbox_type == get_platform_sandbox().unwrap_or(SandboxType::None)
+        ));
+        assert!(matches!(
+            assess_command_safety_read_only(&command(&["bash", "-lc", "cat README.md"])),
+            SafetyCheck::AutoApprove { .. }
+        ));
+        for denied in [
+            command(&["rm", "-rf", "target"]),
+            command(&["bash", "-lc", "echo hi > notes.txt"]),
+            command(&["git", "commit", "-am", "wip"]),
+        ] {
+            assert!(matches!(
+                assess_command_safety_read_only(&denied),
+                SafetyCheck::Reject { .. }
+            ));
+        }
+    }
 }
@@ -0,0 +1,228 @@
+//! Working out, from what a sandboxed command printed, which access the
+//! sandbox denied it, so that the user can be asked to grant just that access
+//! for the rest of the session instead of re-running the command without a
+//! sandbox.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::protocol::SandboxAccess;
+
+/// How tools report a write that was refused.
+const WRITE_DENIALS: &[&str] = &[
+    "Permission denied",
+    "Read-only file system",
+    "Operation not permitted",
+];
+
+/// Text that is followed by the host a tool failed to reach.
+const HOST_MARKERS: &[&str] = &[
+    "Could not resolve host: ",
+    "Could not resolve hostname ",
+    "Failed to connect to ",
+    "ENOTFOUND ",
+    "EAI_AGAIN ",
+    "host='",
+    "unable to resolve host address ‘",
+];
+
+/// How tools report a network failure without naming the host next to it.
+const NETWORK_FAILURES: &[&str] = &[
+    "Temporary failure in name resolution",
+    "Name or service not known",
+    "Network is unreachable",
+    "nodename nor servname provided",
+];
+
+/// The access a command run in `cwd` was denied, judging by its `stderr`.
+/// Returns `None` when no single path or host can be named, or when the
+/// directory to grant is too broad, in which case the only way forward is to
+/// run the command without a sandbox.
+pub(crate) fn denied_access(stderr: &str, cwd: &Path) -> Option<SandboxAccess> {
+    stderr.lines().find_map(|line| {
+        if let Some(host) = denied_host(line) {
+            return Some(SandboxAccess::Network { host });
+        }
+        let path = writable_root(&cwd.join(denied_path(line)?));
+        (!too_broad(&path, cwd)).then_some(SandboxAccess::Write { path })
+    })
+}
+
+fn denied_host(line: &str) -> Option<String> {
+    let host = HOST_MARKERS
+        .iter()
+        .find_map(|marker| line.split_once(marker).map(|(_, rest)| leading_host(rest)))
+        .filter(|host| !host.is_empty());
+    if host.is_some() {
+        return host;
+    }
+    if !NETWORK_FAILURES
+        .iter()
+        .any(|failure| line.contains(failure))
+    {
+        return None;
+    }
+    line.split_once("://")
+        .map(|(_, rest)| leading_host(rest))
+        .filter(|host| !host.is_empty())
+}
+
+fn leading_host(text: &str) -> String {
+    text.chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .collect::<String>()
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn denied_path(line: &str) -> Option<String> {
+    let (before, after) = WRITE_DENIALS
+        .iter()
+        .find_map(|denial| line.split_once(denial))?;
+    // `Permission denied (publickey)` and the like are not about files.
+    let after = after.trim();
+    if !(after.is_empty() || after.starts_with(':')) {
+        return None;
+    }
+    let path = quoted(after).or_else(|| quoted(before)).unwrap_or_else(|| {
+        let before = before.trim_end().trim_end_matches(':');
+        before.rsplit(": ").next().unwrap_or(before).trim()
+    });
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// The last quoted text in `text`, in any of the quotes tools use.
+fn quoted(text: &str) -> Option<&str> {
+    [('\'', '\''), ('‘', '’'), ('"', '"'), ('`', '\'')]
+        .iter()
+        .find_map(|(open, close)| {
+            let end = text.rfind(*close)?;
+            let start = text[..end].rfind(*open)?;
+            Some(&text[start + open.len_utf8()..end])
+        })
+        .filter(|path| !path.is_empty())
+}
+
+/// A file cannot be made writable on its own, so a denied file grants the
+/// directory it is in.
+fn writable_root(path: &Path) -> PathBuf {
+    let mut root = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                root.pop();
+            }
+            Component::CurDir => {}
+            component => root.push(component),
+        }
+    }
+    if !root.is_dir() {
+        root.pop();
+    }
+    root
+}
+
+/// Whether granting writes under `dir` would cover far more than the denied
+/// path: the root, the home directory or a directory holding either of them
+/// or the workspace.
+fn too_broad(dir: &Path, cwd: &Path) -> bool {
+    dir.parent().is_none()
+        || (cwd.starts_with(dir) && dir != cwd)
+        || dirs::home_dir().is_some_and(|home| home.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn denied(stderr: &str) -> Option<SandboxAccess> {
+        denied_access(stderr, Path::new("/work/repo"))
+    }
+
+    fn write(path: &str) -> Option<SandboxAccess> {
+        Some(SandboxAccess::Write {
+            path: PathBuf::from(path),
+        })
+    }
+
+    fn network(host: &str) -> Option<SandboxAccess> {
+        Some(SandboxAccess::Network {
+            host: host.to_string(),
+        })
+    }
+
+    #[test]
+    fn names_the_directory_of_a_denied_write() {
+        assert_eq!(
+            denied("touch: cannot touch '/opt/tool/config.toml': Permission denied"),
+            write("/opt/tool")
+        );
+        assert_eq!(
+            denied("mkdir: cannot create directory ‘/opt/tool’: Read-only file system"),
+            write("/opt")
+        );
+        assert_eq!(
+            denied("PermissionError: [Errno 13] Permission denied: '/var/cache/app/x.db'"),
+            write("/var/cache/app")
+        );
+        assert_eq!(
+            denied("bash: ../sibling/out.txt: Operation not permitted"),
+            write("/work/sibling")
+        );
+    }
+
+    #[test]
+    fn names_the_host_of_a_denied_connection() {
+        assert_eq!(
+            denied("curl: (6) Could not resolve host: example.com"),
+            network("example.com")
+        );
+        assert_eq!(
+            denied(
+                "fatal: unable to access 'https://github.com/org/repo/': Could not resolve host: github.com"
+            ),
+            network("github.com")
+        );
+        assert_eq!(
+            denied("npm ERR! getaddrinfo ENOTFOUND registry.npmjs.org"),
+            network("registry.npmjs.org")
+        );
+        assert_eq!(
+            denied(
+                "error: failed to download from `https://index.crates.io/config.json`: Temporary failure in name resolution"
+            ),
+            network("index.crates.io")
+        );
+    }
+
+    #[test]
+    fn ignores_failures_that_name_nothing_to_grant() {
+        assert_eq!(
+            denied("git@github.com: Permission denied (publickey)."),
+            None
+        );
+        assert_eq!(denied("error: could not compile `foo`"), None);
+    }
+
+    #[test]
+    fn does_not_climb_to_broad_directories() {
+        assert_eq!(
+            denied("mkdir: cannot create directory ‘/tool’: Permission denied"),
+            None
+        );
+        assert_eq!(
+            denied("mkdir: cannot create directory ‘../tool’: Permission denied"),
+            None
+        );
+        if let Some(home) = dirs::home_dir() {
+            let stderr = format!(
+                "mkdir: cannot create directory ‘{}’: Permission denied",
+                home.join("tool").display()
+            );
+            assert_eq!(denied(&stderr), None);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! The `scratchpad` tool: notes the model keeps for itself during a long
+//! task. The scratchpad is not part of the conversation, so it survives
+//! compaction and costs nothing until the model reads it; each write is
+//! recorded in the rollout so that a resumed session gets it back.
+
+use serde::Deserialize;
+
+pub(crate) const SCRATCHPAD_TOOL_NAME: &str = "scratchpad";
+
+/// Most the scratchpad holds, so that reading it stays cheap.
+pub(crate) const MAX_SCRATCHPAD_BYTES: usize = 8 * 1024;
+
+/// Appended to the compacted summary when the scratchpad is not empty.
+pub(crate) const SCRATCHPAD_REMINDER: &str = "Your notes from before the summary are still in the scratchpad; read them with the `scratchpad` tool.";
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub(crate) enum ScratchpadArgs {
+    Read,
+    Write { content: String },
+    Append { content: String },
+}
+
+/// What a call did, so that the session knows whether to record the new
+/// content.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ScratchpadOutcome {
+    Read(String),
+    Changed(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scratchpad {
+    content: String,
+}
+
+impl Scratchpad {
+    pub(crate) fn new(content: String) -> Self {
+        Self { content }
+    }
+
+    pub(crate) fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Carry out `args`, refusing to grow the scratchpad past
+    /// [`MAX_SCRATCHPAD_BYTES`].
+    pub(crate) fn apply(&mut self, args: ScratchpadArgs) -> Result<ScratchpadOutcome, String> {
+        let content = match args {
+            ScratchpadArgs::Read if self.content.is_empty() => {
+                return Ok(ScratchpadOutcome::Read(
+                    "the scratchpad is empty".to_string(),
+                ));
+            }
+            ScratchpadArgs::Read => return Ok(ScratchpadOutcome::Read(self.content.clone())),
+            ScratchpadArgs::Write { content } => content,
+            ScratchpadArgs::Append { content } if self.content.is_empty() => content,
+            ScratchpadArgs::Append { content } => format!("{}\n{content}", self.content),
+        };
+        if content.len() > MAX_SCRATCHPAD_BYTES {
+            return Err(format!(
+                "the scratchpad holds at most {MAX_SCRATCHPAD_BYTES} bytes, this would make it {}; \
+                 rewrite it shorter with `write`",
+                content.len()
+            ));
+        }
+        self.content = content;
+        Ok(ScratchpadOutcome::Changed(format!(
+            "saved ({} of {MAX_SCRATCHPAD_BYTES} bytes used)",
+            self.content.len()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(json: &str) -> ScratchpadArgs {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn writes_appends_and_reads() {
+        let mut pad = Scratchpad::default();
+        assert_eq!(
+            pad.apply(args(r#"{"action":"read"}"#)),
+            Ok(ScratchpadOutcome::Read(
+                "the scratchpad is empty".to_string()
+            ))
+        );
+        pad.apply(args(r#"{"action":"write","content":"- parser done"}"#))
+            .unwrap();
+        assert_eq!(
+            pad.apply(args(r#"{"action":"append","content":"- lexer next"}"#)),
+            Ok(ScratchpadOutcome::Changed(format!(
+                "saved (26 of {MAX_SCRATCHPAD_BYTES} bytes used)"
+            )))
+        );
+        assert_eq!(pad.content(), "- parser done\n- lexer next");
+        pad.apply(args(r#"{"action":"write","content":"- all done"}"#))
+            .unwrap();
+        assert_eq!(
+            pad.apply(args(r#"{"action":"read"}"#)),
+            Ok(ScratchpadOutcome::Read("- all done".to_string()))
+        );
+    }
+
+    #[test]
+    fn refuses_to_grow_past_the_limit() {
+        let mut pad = Scratchpad::new("x".repeat(MAX_SCRATCHPAD_BYTES));
+        let err = pad
+            .apply(ScratchpadArgs::Append {
+                content: "y".to_string(),
+            })
+            .unwrap_err();
+        assert!(err.starts_with("the scratchpad holds at most 8192 bytes"));
+        assert_eq!(pad.content().len(), MAX_SCRATCHPAD_BYTES);
+    }
+}
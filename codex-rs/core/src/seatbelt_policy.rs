@@ -0,0 +1,128 @@
+//! Checks on the Seatbelt (sbpl) rules users append to the macOS sandbox
+//! profile with `[macos_seatbelt] extra_policy`. A snippet that does not
+//! parse would make `sandbox-exec` refuse every command, so it is rejected
+//! when the config is loaded instead.
+
+/// Check that `snippet` is a sequence of complete `allow`/`deny` rules.
+pub(crate) fn validate_snippet(snippet: &str) -> Result<(), String> {
+    let mut chars = snippet.chars().peekable();
+    let mut depth = 0usize;
+    let mut rules = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                // Comment to the end of the line.
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => skip_string(&mut chars)?,
+            '#' if chars.peek() == Some(&'"') => {
+                chars.next();
+                skip_string(&mut chars)?;
+            }
+            '(' => {
+                if depth == 0 {
+                    let head = read_symbol(&mut chars);
+                    match head.as_str() {
+                        "allow" | "deny" => {}
+                        "" => return Err("a rule must start with `allow` or `deny`".to_string()),
+                        head => {
+                            return Err(format!(
+                                "only `allow` and `deny` rules can be added, not `{head}`"
+                            ));
+                        }
+                    }
+                    if head == "allow" && read_symbol(&mut chars) == "default" {
+                        return Err("`(allow default)` would turn the sandbox off".to_string());
+                    }
+                    rules += 1;
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| "unbalanced `)`".to_string())?;
+            }
+            c if c.is_whitespace() => {}
+            c if depth == 0 => return Err(format!("unexpected `{c}` outside a rule")),
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return Err("missing `)`".to_string());
+    }
+    if rules == 0 {
+        return Err("no rules".to_string());
+    }
+    Ok(())
+}
+
+fn skip_string(chars: &mut impl Iterator<Item = char>) -> Result<(), String> {
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Ok(()),
+            _ => {}
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+/// Skip whitespace, then read the symbol that follows, if any.
+fn read_symbol(chars: &mut std::iter::Peekable<impl Iterator<Item = char>>) -> String {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    let mut symbol = String::new();
+    while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !matches!(c, '(' | ')' | '"')) {
+        symbol.push(c);
+    }
+    symbol
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn accepts_allow_and_deny_rules() {
+        assert_eq!(
+            validate_snippet(
+                r#"; toolchain
+(allow file-write* (subpath "/opt/toolchain"))
+(deny file-read* (regex #"^/Users/[^/]+/\.ssh"))"#
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_complete_rule() {
+        assert_eq!(
+            validate_snippet(r#"(allow file-write* (subpath "/opt")"#),
+            Err("missing `)`".to_string())
+        );
+        assert_eq!(
+            validate_snippet(r#"(allow file-write* (subpath "/opt))"#),
+            Err("unterminated string".to_string())
+        );
+        assert_eq!(
+            validate_snippet("(version 1)"),
+            Err("only `allow` and `deny` rules can be added, not `version`".to_string())
+        );
+        assert_eq!(
+            validate_snippet("(allow default)"),
+            Err("`(allow default)` would turn the sandbox off".to_string())
+        );
+        assert_eq!(
+            validate_snippet("allow file-write*"),
+            Err("unexpected `a` outside a rule".to_string())
+        );
+        assert_eq!(validate_snippet("; nothing"), Err("no rules".to_string()));
+    }
+}
@@ -0,0 +1,780 @@
+//! The `semantic_search` tool, which finds the parts of the workspace that
+//! are closest in meaning to a natural-language query.
+//!
+//! Workspace files are cut into chunks of lines, and each chunk is embedded
+//! with `semantic_search.model`. The vectors are kept under `.codex/index/`
+//! at the project root, with a hash of each file's contents.
+//!
+//! The index is brought up to date before each search. The first search of a
+//! session compares every file against the store; after that, a file watcher
+//! tells which files to look at, so a refresh costs as much as the edits made
+//! since the last search rather than the size of the tree. Files whose hash
+//! did not change are not embedded again, and neither are the chunks of a
+//! changed file whose text did not change.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use ignore::Match;
+use ignore::gitignore::Gitignore;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tracing::warn;
+
+use crate::client::ModelClient;
+use crate::http_client::is_local_url;
+use crate::project_init::project_root;
+use crate::replay::is_replaying;
+use crate::workspace_index::CODEXIGNORE_FILE;
+use crate::workspace_index::DEFAULT_MAX_INDEXED_FILES;
+use crate::workspace_index::WorkspaceIndex;
+
+pub(crate) const SEMANTIC_SEARCH_TOOL_NAME: &str = "semantic_search";
+
+/// Where the embeddings are stored, relative to the project root.
+const STORE_FILE: &str = ".codex/index/semantic.json";
+
+const CHUNK_LINES: usize = 40;
+const MAX_CHUNK_BYTES: usize = 4 * 1024;
+
+/// Larger files are most likely generated or data, and are not embedded.
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Number of chunks sent in one embeddings request.
+const EMBEDDING_BATCH_SIZE: usize = 64;
+
+const DEFAULT_RESULTS: usize = 5;
+const MAX_RESULTS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SemanticSearchArgs {
+    query: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// The embeddings of a project, as stored in [`STORE_FILE`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    /// Embeddings of different models cannot be compared, so the store is
+    /// rebuilt when the model changes.
+    model: String,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedFile {
+    stamp: Stamp,
+    /// SHA-256 of the contents, in hex. Empty in stores written before
+    /// hashes were recorded.
+    #[serde(default)]
+    hash: String,
+    /// Empty for files that are not text.
+    chunks: Vec<Chunk>,
+}
+
+/// Size and modification time of a file, which spare reading the files that
+/// were not touched when every file is compared against the store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    len: u64,
+    modified_ms: u64,
+}
+
+impl Stamp {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+        Self {
+            len: metadata.len(),
+            modified_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Chunk {
+    start_line: usize,
+    end_line: usize,
+    text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    embedding: Vec<f32>,
+}
+
+/// How a file differs from the store.
+#[derive(Debug, PartialEq)]
+enum FileUpdate {
+    /// Deleted, or no longer indexed.
+    Removed(String),
+    /// Touched, but with the same contents.
+    Restamped { path: String, stamp: Stamp },
+    /// New, or with new contents.
+    Changed {
+        path: String,
+        stamp: Stamp,
+        hash: String,
+        chunks: Vec<Chunk>,
+    },
+}
+
+/// Files changed since the last refresh, as reported by the watcher.
+#[derive(Debug, Default)]
+struct PendingChanges {
+    /// Set until the first refresh, and whenever the watcher may have missed
+    /// events.
+    rescan: bool,
+    paths: HashSet<PathBuf>,
+}
+
+/// The embeddings of the project containing a session's working directory.
+pub(crate) struct SemanticIndex {
+    root: PathBuf,
+    client: ModelClient,
+    offline: bool,
+    /// Read from disk on the first search.
+    store: tokio::sync::Mutex<Option<Store>>,
+    pending: Arc<Mutex<PendingChanges>>,
+    /// Without a watcher, every refresh compares every file.
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl SemanticIndex {
+    /// `client` queries the embedding model.
+    pub(crate) fn new(cwd: &Path, client: ModelClient, offline: bool) -> Self {
+        let root = project_root(cwd);
+        let pending = Arc::new(Mutex::new(PendingChanges {
+            rescan: true,
+            paths: HashSet::new(),
+        }));
+        let watcher = watch(&root, Arc::clone(&pending))
+            .inspect_err(|e| warn!("failed to watch {} for changes: {e}", root.display()))
+            .ok();
+        Self {
+            root,
+            client,
+            offline,
+            store: tokio::sync::Mutex::new(None),
+            pending,
+            watcher,
+        }
+    }
+
+    /// Bring the index up to date and return the chunks closest to the
+    /// query, formatted for the model.
+    pub(crate) async fn search(&self, args: SemanticSearchArgs) -> Result<String, String> {
+        // A replayed session never contacts the provider.
+        if is_replaying() {
+            return Err(format!(
+                "{SEMANTIC_SEARCH_TOOL_NAME} is not available when replaying a session"
+            ));
+        }
+        let provider = self.client.provider();
+        if self.offline && !is_local_url(&provider.base_url) {
+            return Err(format!(
+                "offline mode only allows local model providers, but {} is at {}",
+                provider.name, provider.base_url
+            ));
+        }
+
+        let mut store = self.store.lock().await;
+        let store = store.get_or_insert_with(|| self.load());
+        self.refresh(store)
+            .await
+            .map_err(|e| format!("failed to update the index: {e:#}"))?;
+        let query = self
+            .client
+            .embed(std::slice::from_ref(&args.query))
+            .await
+            .map_err(|e| format!("failed to embed the query: {e}"))?
+            .pop()
+            .ok_or_else(|| "the provider returned no embedding for the query".to_string())?;
+        let limit = args.limit.unwrap_or(DEFAULT_RESULTS).clamp(1, MAX_RESULTS);
+        Ok(render(&rank(store, &query, limit)))
+    }
+
+    fn load(&self) -> Store {
+        let stored = std::fs::read(self.root.join(STORE_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Store>(&bytes).ok());
+        match stored {
+            Some(store) if store.model == self.client.model() => store,
+            _ => Store {
+                model: self.client.model().to_string(),
+                files: BTreeMap::new(),
+            },
+        }
+    }
+
+    /// Embed the files that changed since `store` was last updated, drop
+    /// the deleted ones and save the result.
+    async fn refresh(&self, store: &mut Store) -> anyhow::Result<()> {
+        let pending = {
+            let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+            std::mem::replace(
+                &mut *pending,
+                PendingChanges {
+                    rescan: self.watcher.is_none(),
+                    paths: HashSet::new(),
+                },
+            )
+        };
+        let root = self.root.clone();
+        let known: HashMap<String, (Stamp, String)> = store
+            .files
+            .iter()
+            .map(|(path, file)| (path.clone(), (file.stamp.clone(), file.hash.clone())))
+            .collect();
+        // Which files a `.gitignore` covers is only known by walking the tree.
+        let rescan = pending.rescan
+            || pending
+                .paths
+                .iter()
+                .any(|path| path.file_name().is_some_and(|name| name == ".gitignore"));
+        let scanned = tokio::task::spawn_blocking(move || {
+            if rescan {
+                scan_all(&root, &known)
+            } else {
+                scan_paths(&root, &known, pending.paths)
+            }
+        })
+        .await;
+        let updates = match scanned {
+            Ok(updates) => updates,
+            Err(e) => {
+                self.pending
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .rescan = true;
+                return Err(e.into());
+            }
+        };
+        if updates.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = self.apply(store, updates).await {
+            // The changes that were not embedded are looked for again next
+            // time.
+            self.pending
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .rescan = true;
+            return Err(e);
+        }
+        save(&self.root.join(STORE_FILE), store).await
+    }
+
+    async fn apply(&self, store: &mut Store, mut updates: Vec<FileUpdate>) -> anyhow::Result<()> {
+        for update in &mut updates {
+            if let FileUpdate::Changed { path, chunks, .. } = update {
+                let old = store
+                    .files
+                    .get(path.as_str())
+                    .map_or(&[][..], |file| &file.chunks);
+                reuse_embeddings(old, chunks);
+            }
+        }
+        let inputs: Vec<String> = updates
+            .iter()
+            .filter_map(|update| match update {
+                FileUpdate::Changed { path, chunks, .. } => Some((path, chunks)),
+                _ => None,
+            })
+            .flat_map(|(path, chunks)| {
+                chunks
+                    .iter()
+                    .filter(|chunk| chunk.embedding.is_empty())
+                    .map(move |chunk| format!("{path}\n{}", chunk.text))
+            })
+            .collect();
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(EMBEDDING_BATCH_SIZE) {
+            let batch_embeddings = self.client.embed(batch).await?;
+            if batch_embeddings.len() != batch.len() {
+                anyhow::bail!(
+                    "the provider returned {} embeddings for {} inputs",
+                    batch_embeddings.len(),
+                    batch.len()
+                );
+            }
+            embeddings.extend(batch_embeddings);
+        }
+
+        let mut embeddings = embeddings.into_iter();
+        for update in updates {
+            match update {
+                FileUpdate::Removed(path) => {
+                    store.files.remove(&path);
+                }
+                FileUpdate::Restamped { path, stamp } => {
+                    if let Some(file) = store.files.get_mut(&path) {
+                        file.stamp = stamp;
+                    }
+                }
+                FileUpdate::Changed {
+                    path,
+                    stamp,
+                    hash,
+                    mut chunks,
+                } => {
+                    for chunk in chunks.iter_mut().filter(|chunk| chunk.embedding.is_empty()) {
+                        chunk.embedding = embeddings.next().unwrap_or_default();
+                    }
+                    store.files.insert(
+                        path,
+                        IndexedFile {
+                            stamp,
+                            hash,
+                            chunks,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Watch `root` and record the paths that change in `pending`.
+fn watch(root: &Path, pending: Arc<Mutex<PendingChanges>>) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+        match event {
+            Ok(event) if !event.need_rescan() => pending.paths.extend(event.paths),
+            _ => pending.rescan = true,
+        }
+    })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// Compare every indexed file under `root` with `known`. Only the files whose
+/// stamp differs are read.
+fn scan_all(root: &Path, known: &HashMap<String, (Stamp, String)>) -> Vec<FileUpdate> {
+    let index = WorkspaceIndex::build(root, DEFAULT_MAX_INDEXED_FILES);
+    let current: HashSet<&str> = index.files().collect();
+    let mut updates: Vec<FileUpdate> = known
+        .keys()
+        .filter(|path| !current.contains(path.as_str()))
+        .map(|path| FileUpdate::Removed(path.clone()))
+        .collect();
+    for path in index.files() {
+        let Ok(metadata) = std::fs::metadata(root.join(path)) else {
+            continue;
+        };
+        let stamp = Stamp::of(&metadata);
+        let known = known.get(path);
+        if known.is_some_and(|(known_stamp, _)| *known_stamp == stamp) {
+            continue;
+        }
+        updates.extend(compare(
+            root,
+            path,
+            stamp,
+            known.map(|(_, hash)| hash.as_str()),
+        ));
+    }
+    updates
+}
+
+/// Compare the files at `paths`, as reported by the watcher, with `known`.
+fn scan_paths(
+    root: &Path,
+    known: &HashMap<String, (Stamp, String)>,
+    paths: HashSet<PathBuf>,
+) -> Vec<FileUpdate> {
+    let mut updates = Vec::new();
+    let mut seen = HashSet::new();
+    for path in paths {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if !seen.insert(relative.clone()) {
+            continue;
+        }
+        // Files that are now ignored are dropped like deleted ones.
+        let metadata = if is_ignored(root, &relative) {
+            None
+        } else {
+            std::fs::metadata(&path).ok()
+        };
+        match metadata {
+            Some(metadata) if metadata.is_file() => {
+                let known = known.get(&relative).map(|(_, hash)| hash.as_str());
+                updates.extend(compare(root, &relative, Stamp::of(&metadata), known));
+            }
+            Some(metadata) if metadata.is_dir() => {
+                // A directory that was created or moved here.
+                let index = WorkspaceIndex::build(&path, DEFAULT_MAX_INDEXED_FILES);
+                for file in index.files() {
+                    let file = format!("{relative}/{file}");
+                    let Ok(metadata) = std::fs::metadata(root.join(&file)) else {
+                        continue;
+                    };
+                    let stamp = Stamp::of(&metadata);
+                    let known = known.get(&file);
+                    if known.is_some_and(|(known_stamp, _)| *known_stamp == stamp) {
+                        continue;
+                    }
+                    let known = known.map(|(_, hash)| hash.as_str());
+                    updates.extend(compare(root, &file, stamp, known));
+                }
+            }
+            _ => {
+                // A deleted file, or a deleted or renamed directory.
+                let prefix = format!("{relative}/");
+                updates.extend(
+                    known
+                        .keys()
+                        .filter(|known| **known == relative || known.starts_with(&prefix))
+                        .map(|known| FileUpdate::Removed(known.clone())),
+                );
+            }
+        }
+    }
+    updates
+}
+
+/// Read the file at `path` and compare its hash with `known_hash`.
+fn compare(root: &Path, path: &str, stamp: Stamp, known_hash: Option<&str>) -> Option<FileUpdate> {
+    let bytes = if stamp.len > MAX_FILE_BYTES {
+        Vec::new()
+    } else {
+        std::fs::read(root.join(path)).ok()?
+    };
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    if known_hash == Some(hash.as_str()) {
+        return Some(FileUpdate::Restamped {
+            path: path.to_string(),
+            stamp,
+        });
+    }
+    let chunks = String::from_utf8(bytes)
+        .ok()
+        .filter(|text| !text.contains('\0'))
+        .map(|text| chunk_lines(&text))
+        .unwrap_or_default();
+    Some(FileUpdate::Changed {
+        path: path.to_string(),
+        stamp,
+        hash,
+        chunks,
+    })
+}
+
+/// Whether the workspace index leaves out `path`: it is hidden, or matched
+/// by a `.codexignore` or `.gitignore` between `root` and the file.
+fn is_ignored(root: &Path, path: &str) -> bool {
+    if path.split('/').any(|component| component.starts_with('.')) {
+        return true;
+    }
+    let mut dirs = vec![root.to_path_buf()];
+    let (parents, _) = path.rsplit_once('/').unwrap_or(("", path));
+    for component in parents.split('/').filter(|component| !component.is_empty()) {
+        let dir = dirs[dirs.len() - 1].join(component);
+        dirs.push(dir);
+    }
+    // The ignore file closest to the file has the last word; in one
+    // directory, `.codexignore` goes before `.gitignore`.
+    let path = root.join(path);
+    for dir in dirs.iter().rev() {
+        for name in [CODEXIGNORE_FILE, ".gitignore"] {
+            let ignore_file = dir.join(name);
+            if !ignore_file.is_file() {
+                continue;
+            }
+            match Gitignore::new(ignore_file)
+                .0
+                .matched_path_or_any_parents(&path, false)
+            {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+    }
+    false
+}
+
+/// Give the chunks in `chunks` whose text is unchanged the embedding they had
+/// in `old`.
+fn reuse_embeddings(old: &[Chunk], chunks: &mut [Chunk]) {
+    let old: HashMap<&str, &Vec<f32>> = old
+        .iter()
+        .map(|chunk| (chunk.text.as_str(), &chunk.embedding))
+        .collect();
+    for chunk in chunks {
+        if let Some(embedding) = old.get(chunk.text.as_str()) {
+            chunk.embedding = (*embedding).clone();
+        }
+    }
+}
+
+/// Cut `text` into chunks of at most [`CHUNK_LINES`] lines and about
+/// [`MAX_CHUNK_BYTES`] bytes, leaving out the blank ones.
+fn chunk_lines(text: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut start_line = 1;
+    let mut lines = 0;
+    for (index, line) in text.lines().enumerate() {
+        if lines == 0 {
+            start_line = index + 1;
+        }
+        // Very long lines, e.g. in minified files, are cut short.
+        let line = match line.char_indices().nth(MAX_CHUNK_BYTES) {
+            Some((end, _)) => &line[..end],
+            None => line,
+        };
+        current.push_str(line);
+        current.push('\n');
+        lines += 1;
+        if lines == CHUNK_LINES || current.len() >= MAX_CHUNK_BYTES {
+            push_chunk(&mut chunks, &mut current, start_line, index + 1);
+            lines = 0;
+        }
+    }
+    if lines > 0 {
+        push_chunk(
+            &mut chunks,
+            &mut current,
+            start_line,
+            start_line + lines - 1,
+        );
+    }
+    chunks
+}
+
+fn push_chunk(chunks: &mut Vec<Chunk>, text: &mut String, start_line: usize, end_line: usize) {
+    let text = std::mem::take(text);
+    if !text.trim().is_empty() {
+        chunks.push(Chunk {
+            start_line,
+            end_line,
+            text,
+            embedding: Vec::new(),
+        });
+    }
+}
+
+async fn save(path: &Path, store: &Store) -> anyhow::Result<()> {
+    let json = serde_json::to_vec(store)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    // Write a temporary file and rename it so that a concurrent search, e.g.
+    // by a sub-agent, never reads a half-written store.
+    let tmp_path = path.with_extension(format!("json.tmp.{}", std::process::id()));
+    tokio::fs::write(&tmp_path, json).await?;
+    tokio::fs::rename(&tmp_path, path)
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// The `limit` chunks of `store` most similar to `query`, best first.
+fn rank<'a>(store: &'a Store, query: &[f32], limit: usize) -> Vec<(f32, &'a str, &'a Chunk)> {
+    let mut scored: Vec<(f32, &str, &Chunk)> = store
+        .files
+        .iter()
+        .flat_map(|(path, file)| file.chunks.iter().map(move |chunk| (path.as_str(), chunk)))
+        .map(|(path, chunk)| (cosine_similarity(query, &chunk.embedding), path, chunk))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(limit);
+    scored
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+fn render(hits: &[(f32, &str, &Chunk)]) -> String {
+    if hits.is_empty() {
+        return "No indexed files.".to_string();
+    }
+    hits.iter()
+        .map(|(score, path, chunk)| {
+            format!(
+                "{path}:{}-{} (similarity {score:.2})\n{}",
+                chunk.start_line, chunk.end_line, chunk.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn chunks_skip_blank_runs() {
+        let mut text = String::new();
+        for line in 1..=CHUNK_LINES + 2 {
+            text.push_str(&format!("line {line}\n"));
+        }
+        text.push_str(&"\n".repeat(CHUNK_LINES));
+        let chunks = chunk_lines(&text);
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (chunk.start_line, chunk.end_line))
+                .collect::<Vec<_>>(),
+            vec![(1, CHUNK_LINES), (CHUNK_LINES + 1, 2 * CHUNK_LINES)]
+        );
+        assert!(
+            chunks[1]
+                .text
+                .starts_with(&format!("line {}\n", CHUNK_LINES + 1))
+        );
+    }
+
+    #[test]
+    fn ranks_chunks_by_similarity() {
+        let chunk = |text: &str, embedding: Vec<f32>| Chunk {
+            start_line: 1,
+            end_line: 1,
+            text: text.to_string(),
+            embedding,
+        };
+        let mut store = Store::default();
+        for (path, embedding) in [
+            ("auth.rs", vec![1.0, 0.0]),
+            ("parse.rs", vec![0.0, 1.0]),
+            ("both.rs", vec![1.0, 1.0]),
+        ] {
+            store.files.insert(
+                path.to_string(),
+                IndexedFile {
+                    stamp: Stamp {
+                        len: 0,
+                        modified_ms: 0,
+                    },
+                    hash: String::new(),
+                    chunks: vec![chunk(path, embedding)],
+                },
+            );
+        }
+        let hits = rank(&store, &[1.0, 0.1], 2);
+        assert_eq!(
+            hits.iter().map(|(_, path, _)| *path).collect::<Vec<_>>(),
+            vec!["auth.rs", "both.rs"]
+        );
+        assert_eq!(render(&hits[..1]), "auth.rs:1-1 (similarity 1.00)\nauth.rs");
+        assert_eq!(render(&[]), "No indexed files.");
+    }
+
+    #[test]
+    fn rereads_only_the_reported_files() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(".gitignore", "target/\n");
+        write(".codexignore", "fixtures/\n");
+        write("touched.rs", "fn touched() {}\n");
+        write("edited.rs", "fn edited() {}\n");
+        write("new.rs", "fn new() {}\n");
+        write("target/debug/build.rs", "fn generated() {}\n");
+        write("untouched.rs", "fn untouched() {}\n");
+        write("fixtures/sample.rs", "fn sample() {}\n");
+
+        let stale = Stamp {
+            len: 0,
+            modified_ms: 0,
+        };
+        let hash = |contents: &str| format!("{:x}", Sha256::digest(contents));
+        let known: HashMap<String, (Stamp, String)> = [
+            ("touched.rs", hash("fn touched() {}\n")),
+            ("edited.rs", hash("fn old() {}\n")),
+            ("deleted.rs", hash("fn deleted() {}\n")),
+            ("untouched.rs", hash("fn untouched() {}\n")),
+            ("fixtures/sample.rs", hash("fn sample() {}\n")),
+        ]
+        .into_iter()
+        .map(|(path, hash)| (path.to_string(), (stale.clone(), hash)))
+        .collect();
+        let reported = [
+            "touched.rs",
+            "edited.rs",
+            "deleted.rs",
+            "new.rs",
+            "target/debug/build.rs",
+            "fixtures/sample.rs",
+        ]
+        .into_iter()
+        .map(|path| root.join(path))
+        .collect();
+
+        let mut updates: Vec<String> = scan_paths(root, &known, reported)
+            .into_iter()
+            .map(|update| match update {
+                FileUpdate::Removed(path) => format!("removed {path}"),
+                FileUpdate::Restamped { path, .. } => format!("restamped {path}"),
+                FileUpdate::Changed { path, chunks, .. } => {
+                    format!("changed {path}: {}", chunks[0].text.trim_end())
+                }
+            })
+            .collect();
+        updates.sort();
+        assert_eq!(
+            updates,
+            vec![
+                "changed edited.rs: fn edited() {}",
+                "changed new.rs: fn new() {}",
+                "removed deleted.rs",
+                "removed fixtures/sample.rs",
+                "restamped touched.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_embeddings_of_unchanged_chunks() {
+        let chunk = |text: &str, embedding: Vec<f32>| Chunk {
+            start_line: 1,
+            end_line: 1,
+            text: text.to_string(),
+            embedding,
+        };
+        let old = vec![
+            chunk("fn a() {}\n", vec![1.0]),
+            chunk("fn b() {}\n", vec![2.0]),
+        ];
+        let mut chunks = vec![
+            chunk("fn a() {}\n", Vec::new()),
+            chunk("fn c() {}\n", Vec::new()),
+        ];
+        reuse_embeddings(&old, &mut chunks);
+        assert_eq!(chunks[0].embedding, vec![1.0]);
+        assert!(chunks[1].embedding.is_empty());
+    }
+}
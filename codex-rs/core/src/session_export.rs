@@ -0,0 +1,711 @@
+//! Export a recorded session as a document that can be attached to a PR or
+//! shared with teammates.
+//!
+//! The rollout written by [`crate::rollout`] is the source of truth: each
+//! recorded item is turned into a transcript [`Entry`] (prompt, agent message,
+//! command with its output, patch, or other tool call) which is then rendered
+//! as Markdown, standalone HTML, or JSON.
+//!
+//! A transcript can also be shared as a [`Bundle`]: a single file with likely
+//! secrets redacted, which `codex sessions view` opens read-only.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::models::ContentItem;
+use crate::models::LocalShellAction;
+use crate::models::ResponseItem;
+use crate::models::ShellToolCallParams;
+use crate::protocol::RedactedSecret;
+use crate::redaction::redact_secrets;
+use crate::rollout::read_rollout;
+
+/// Extension of the bundles written by `codex sessions share`.
+pub const BUNDLE_EXTENSION: &str = "codex";
+
+const BUNDLE_FORMAT: &str = "codex-session-bundle";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    /// Format implied by the extension of `path`, if it is a known one.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!(
+                "unknown export format `{other}` (expected md, html or json)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    User {
+        text: String,
+    },
+    Agent {
+        text: String,
+    },
+    Command {
+        command: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+    Patch {
+        patch: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+    ToolCall {
+        name: String,
+        arguments: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub id: String,
+    pub timestamp: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Transcript {
+    /// Load the transcript recorded in the rollout file at `path`.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let (meta, items) = read_rollout(path)?;
+        Ok(Self {
+            id: meta.id,
+            timestamp: meta.timestamp,
+            entries: entries_from_items(&items),
+        })
+    }
+
+    /// A copy with likely secrets redacted from every entry, and how many of
+    /// each kind were.
+    pub fn redacted(&self) -> (Self, Vec<RedactedSecret>) {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut redact = |text: &mut String| {
+            if let Some(redacted) = redact_secrets(text) {
+                *text = redacted.text;
+                for secret in redacted.secrets {
+                    *counts.entry(secret.kind).or_default() += secret.count;
+                }
+            }
+        };
+        let mut transcript = self.clone();
+        for entry in &mut transcript.entries {
+            let (text, output) = match entry {
+                Entry::User { text } | Entry::Agent { text } => (text, None),
+                Entry::Command {
+                    command, output, ..
+                } => (command, output.as_mut()),
+                Entry::Patch { patch, output } => (patch, output.as_mut()),
+                Entry::ToolCall {
+                    arguments, output, ..
+                } => (arguments, output.as_mut()),
+            };
+            redact(text);
+            if let Some(output) = output {
+                redact(output);
+            }
+        }
+        let secrets = counts
+            .into_iter()
+            .map(|(kind, count)| RedactedSecret { kind, count })
+            .collect();
+        (transcript, secrets)
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Html => self.to_html(),
+            #[expect(clippy::expect_used)]
+            ExportFormat::Json => serde_json::to_string_pretty(self).expect("serialize transcript"),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# Codex session {}\n\nStarted {}\n",
+            self.id, self.timestamp
+        );
+        for entry in &self.entries {
+            out.push('\n');
+            match entry {
+                Entry::User { text } => {
+                    let _ = writeln!(out, "## User\n\n{text}");
+                }
+                Entry::Agent { text } => {
+                    let _ = writeln!(out, "## Codex\n\n{text}");
+                }
+                Entry::Command {
+                    command,
+                    exit_code,
+                    output,
+                } => {
+                    out.push_str("**Command**");
+                    if let Some(code) = exit_code {
+                        let _ = write!(out, " (exit code {code})");
+                    }
+                    out.push_str("\n\n");
+                    out.push_str(&code_fence(&format!("$ {command}"), "sh"));
+                    if let Some(output) = output.as_deref().filter(|o| !o.is_empty()) {
+                        out.push('\n');
+                        out.push_str(&code_fence(output, "text"));
+                    }
+                }
+                Entry::Patch { patch, output } => {
+                    out.push_str("**Patch**\n\n");
+                    out.push_str(&code_fence(patch, "diff"));
+                    if let Some(output) = output.as_deref().filter(|o| !o.is_empty()) {
+                        out.push('\n');
+                        out.push_str(&code_fence(output, "text"));
+                    }
+                }
+                Entry::ToolCall {
+                    name,
+                    arguments,
+                    output,
+                } => {
+                    let _ = writeln!(out, "**Tool call** `{name}`\n");
+                    out.push_str(&code_fence(arguments, "json"));
+                    if let Some(output) = output.as_deref().filter(|o| !o.is_empty()) {
+                        out.push('\n');
+                        out.push_str(&code_fence(output, "text"));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let mut body = String::new();
+        for entry in &self.entries {
+            match entry {
+                Entry::User { text } => {
+                    let _ = writeln!(
+                        body,
+                        "<section class=\"user\"><h2>User</h2>{}</section>",
+                        markdown_to_html(text)
+                    );
+                }
+                Entry::Agent { text } => {
+                    let _ = writeln!(
+                        body,
+                        "<section class=\"agent\"><h2>Codex</h2>{}</section>",
+                        markdown_to_html(text)
+                    );
+                }
+                Entry::Command {
+                    command,
+                    exit_code,
+                    output,
+                } => {
+                    let status = exit_code
+                        .map(|code| format!(" <span class=\"status\">exit code {code}</span>"))
+                        .unwrap_or_default();
+                    let _ = writeln!(
+                        body,
+                        "<section class=\"tool\"><h3>Command{status}</h3><pre><code>$ {}</code></pre>{}</section>",
+                        escape_html(command),
+                        html_output(output.as_deref())
+                    );
+                }
+                Entry::Patch { patch, output } => {
+                    let _ = writeln!(
+                        body,
+                        "<section class=\"tool\"><h3>Patch</h3><pre><code>{}</code></pre>{}</section>",
+                        html_patch(patch),
+                        html_output(output.as_deref())
+                    );
+                }
+                Entry::ToolCall {
+                    name,
+                    arguments,
+                    output,
+                } => {
+                    let _ = writeln!(
+                        body,
+                        "<section class=\"tool\"><h3>Tool call <code>{}</code></h3><pre><code>{}</code></pre>{}</section>",
+                        escape_html(name),
+                        escape_html(arguments),
+                        html_output(output.as_deref())
+                    );
+                }
+            }
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Codex session {id}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>Codex session {id}</h1>\n<p class=\"meta\">Started {timestamp}</p>\n{body}</body>\n</html>\n",
+            id = escape_html(&self.id),
+            timestamp = escape_html(&self.timestamp),
+        )
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:960px;margin:2em auto;padding:0 1em;line-height:1.5}\
+section{border-left:4px solid #ccc;padding:0 1em;margin:1em 0}\
+section.user{border-color:#0969da}section.agent{border-color:#8250df}\
+pre{background:#f6f8fa;padding:.75em;overflow-x:auto}\
+.meta,.status,summary{color:#57606a}\
+.add{color:#1a7f37}.del{color:#cf222e}";
+
+/// Turn recorded items into transcript entries, attaching each call output to
+/// the call that produced it.
+fn entries_from_items(items: &[ResponseItem]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    // call_id -> index into `entries`.
+    let mut calls: HashMap<&str, usize> = HashMap::new();
+
+    for item in items {
+        match item {
+            ResponseItem::Message { role, content } => {
+                let text = message_text(content);
+                if text.trim().is_empty() {
+                    continue;
+                }
+                entries.push(if role == "user" {
+                    Entry::User { text }
+                } else {
+                    Entry::Agent { text }
+                });
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action: LocalShellAction::Exec(action),
+                ..
+            } => {
+                if let Some(call_id) = call_id.as_deref().or(id.as_deref()) {
+                    calls.insert(call_id, entries.len());
+                }
+                entries.push(command_entry(&action.command));
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+            } => {
+                calls.insert(call_id, entries.len());
+                let shell_params = match name.as_str() {
+                    "shell" | "container.exec" => {
+                        serde_json::from_str::<ShellToolCallParams>(arguments).ok()
+                    }
+                    _ => None,
+                };
+                entries.push(match shell_params {
+                    Some(params) => command_entry(&params.command),
+                    None => Entry::ToolCall {
+                        name: name.clone(),
+                        arguments: pretty_json(arguments),
+                        output: None,
+                    },
+                });
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let Some(entry) = calls
+                    .get(call_id.as_str())
+                    .and_then(|idx| entries.get_mut(*idx))
+                else {
+                    continue;
+                };
+                match entry {
+                    Entry::Command {
+                        exit_code,
+                        output: slot,
+                        ..
+                    } => {
+                        let (text, code) = parse_exec_output(&output.content);
+                        *exit_code = code;
+                        *slot = Some(text);
+                    }
+                    Entry::Patch { output: slot, .. } => {
+                        *slot = Some(parse_exec_output(&output.content).0);
+                    }
+                    Entry::ToolCall { output: slot, .. } => {
+                        *slot = Some(output.content.clone());
+                    }
+                    Entry::User { .. } | Entry::Agent { .. } => {}
+                }
+            }
+            ResponseItem::Reasoning { .. } | ResponseItem::Other => {}
+        }
+    }
+    entries
+}
+
+/// A session shared as a single file, with likely secrets redacted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    format: String,
+    version: u32,
+    /// What was redacted from the transcript, by kind.
+    pub redacted: Vec<RedactedSecret>,
+    pub transcript: Transcript,
+}
+
+impl Bundle {
+    pub fn new(transcript: &Transcript) -> Self {
+        let (transcript, redacted) = transcript.redacted();
+        Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            redacted,
+            transcript,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Read the bundle at `path`, refusing files that are not bundles or
+    /// come from a newer version of Codex.
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let bundle: Self = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| invalid(format!("{} is not a session bundle: {e}", path.display())))?;
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(invalid(format!(
+                "{} is not a session bundle",
+                path.display()
+            )));
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(invalid(format!(
+                "{} was written by a newer version of Codex (bundle version {})",
+                path.display(),
+                bundle.version
+            )));
+        }
+        Ok(bundle)
+    }
+}
+
+pub(crate) fn message_text(content: &[ContentItem]) -> String {
+    content
+        .iter()
+        .map(|item| match item {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => text.as_str(),
+            ContentItem::InputImage { .. } => "[image]",
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn command_entry(command: &[String]) -> Entry {
+    match command {
+        [program, patch] if program == "apply_patch" => Entry::Patch {
+            patch: patch.clone(),
+            output: None,
+        },
+        _ => Entry::Command {
+            command: display_command(command),
+            exit_code: None,
+            output: None,
+        },
+    }
+}
+
+/// Show `bash -lc <script>` as just the script, and quote everything else.
+fn display_command(command: &[String]) -> String {
+    match command {
+        [bash, flag, script] if bash == "bash" && flag == "-lc" => script.clone(),
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    }
+}
+
+/// Shell outputs are recorded as `{"output": ..., "metadata": {"exit_code": ...}}`;
+/// anything else (e.g. an error message) is returned verbatim.
+pub(crate) fn parse_exec_output(content: &str) -> (String, Option<i32>) {
+    #[derive(Deserialize)]
+    struct ExecMetadata {
+        exit_code: i32,
+    }
+
+    #[derive(Deserialize)]
+    struct ExecOutput {
+        output: String,
+        metadata: ExecMetadata,
+    }
+
+    match serde_json::from_str::<ExecOutput>(content) {
+        Ok(parsed) => (parsed.output, Some(parsed.metadata.exit_code)),
+        Err(_) => (content.to_string(), None),
+    }
+}
+
+fn pretty_json(arguments: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(arguments)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| arguments.to_string())
+}
+
+/// Wrap `content` in a fenced code block whose fence is longer than any run
+/// of backticks inside it.
+fn code_fence(content: &str, lang: &str) -> String {
+    let longest_run = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let newline = if content.ends_with('\n') { "" } else { "\n" };
+    format!("{fence}{lang}\n{content}{newline}{fence}\n")
+}
+
+fn markdown_to_html(text: &str) -> String {
+    let mut html = String::new();
+    let parser = pulldown_cmark::Parser::new_ext(text, pulldown_cmark::Options::all());
+    // Raw HTML in a message is shown as text rather than injected into the
+    // page, and links and images cannot point at script.
+    let parser = parser.map(|event| match event {
+        pulldown_cmark::Event::Html(raw) | pulldown_cmark::Event::InlineHtml(raw) => {
+            pulldown_cmark::Event::Text(raw)
+        }
+        pulldown_cmark::Event::Start(pulldown_cmark::Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => pulldown_cmark::Event::Start(pulldown_cmark::Tag::Link {
+            link_type,
+            dest_url: "#".into(),
+            title,
+            id,
+        }),
+        pulldown_cmark::Event::Start(pulldown_cmark::Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => pulldown_cmark::Event::Start(pulldown_cmark::Tag::Image {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+/// Whether a link or image of an exported message may point at `url`: web
+/// and mail URLs and relative ones, not `javascript:` or `data:` URLs that
+/// would run in the page. Browsers ignore whitespace and control characters
+/// in the scheme, so they are dropped before it is read.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => matches!(
+            url[..end].to_ascii_lowercase().as_str(),
+            "http" | "https" | "mailto"
+        ),
+        _ => true,
+    }
+}
+
+fn html_output(output: Option<&str>) -> String {
+    match output.filter(|o| !o.is_empty()) {
+        Some(output) => format!(
+            "<details><summary>Output</summary><pre><code>{}</code></pre></details>",
+            escape_html(output)
+        ),
+        None => String::new(),
+    }
+}
+
+fn html_patch(patch: &str) -> String {
+    patch
+        .lines()
+        .map(|line| {
+            let escaped = escape_html(line);
+            if line.starts_with('+') && !line.starts_with("+++") {
+                format!("<span class=\"add\">{escaped}</span>")
+            } else if line.starts_with('-') && !line.starts_with("---") {
+                format!("<span class=\"del\">{escaped}</span>")
+            } else {
+                escaped
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn transcript() -> Transcript {
+        let items: Vec<ResponseItem> = [
+            r#"{"type":"message","role":"user","content":[{"type":"input_text","text":"list the files"}]}"#,
+            r#"{"type":"function_call","name":"shell","arguments":"{\"command\":[\"bash\",\"-lc\",\"ls\"]}","call_id":"c1"}"#,
+            r#"{"type":"function_call_output","call_id":"c1","output":"{\"output\":\"a.txt\\n\",\"metadata\":{\"exit_code\":0,\"duration_seconds\":0.1}}"}"#,
+            r#"{"type":"function_call","name":"shell","arguments":"{\"command\":[\"apply_patch\",\"*** Begin Patch\\n+<b>\\n*** End Patch\"]}","call_id":"c2"}"#,
+            r#"{"type":"message","role":"assistant","content":[{"type":"output_text","text":"Done: `a.txt`"}]}"#,
+        ]
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+        Transcript {
+            id: "abc".to_string(),
+            timestamp: "2025-05-07T17:24:21.000Z".to_string(),
+            entries: entries_from_items(&items),
+        }
+    }
+
+    #[test]
+    fn items_become_entries_with_outputs_attached() {
+        let entries = transcript().entries;
+        assert_eq!(
+            entries,
+            vec![
+                Entry::User {
+                    text: "list the files".to_string()
+                },
+                Entry::Command {
+                    command: "ls".to_string(),
+                    exit_code: Some(0),
+                    output: Some("a.txt\n".to_string()),
+                },
+                Entry::Patch {
+                    patch: "*** Begin Patch\n+<b>\n*** End Patch".to_string(),
+                    output: None,
+                },
+                Entry::Agent {
+                    text: "Done: `a.txt`".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_markdown_and_escaped_html() {
+        let transcript = transcript();
+
+        let markdown = transcript.render(ExportFormat::Markdown);
+        assert!(markdown.starts_with("# Codex session abc\n"));
+        assert!(
+            markdown
+                .contains("**Command** (exit code 0)\n\n```sh\n$ ls\n```\n\n```text\na.txt\n```\n")
+        );
+        assert!(markdown.contains("```diff\n*** Begin Patch\n+<b>\n*** End Patch\n```\n"));
+
+        let html = transcript.render(ExportFormat::Html);
+        assert!(html.contains("<span class=\"add\">+&lt;b&gt;</span>"));
+        assert!(html.contains("<code>a.txt</code>"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&transcript.render(ExportFormat::Json)).unwrap();
+        assert_eq!(json["entries"][1]["type"], "command");
+    }
+
+    #[test]
+    fn bundle_redacts_secrets_and_round_trips() {
+        let mut transcript = transcript();
+        transcript.entries.push(Entry::Command {
+            command: "cat .env".to_string(),
+            exit_code: Some(0),
+            output: Some("OPENAI_API_KEY=sk-abcdefghijklmnopqrstuvwxyz\n".to_string()),
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("abc.{BUNDLE_EXTENSION}"));
+        Bundle::new(&transcript).write(&path).unwrap();
+        let bundle = Bundle::read(&path).unwrap();
+
+        assert_eq!(
+            bundle.redacted,
+            vec![RedactedSecret {
+                kind: "api-key".to_string(),
+                count: 1
+            }]
+        );
+        assert_eq!(
+            bundle.transcript.entries.last(),
+            Some(&Entry::Command {
+                command: "cat .env".to_string(),
+                exit_code: Some(0),
+                output: Some("OPENAI_API_KEY=[REDACTED:api-key]\n".to_string()),
+            })
+        );
+        assert_eq!(bundle.transcript.entries[..4], transcript.entries[..4]);
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(Bundle::read(&path).is_err());
+    }
+
+    #[test]
+    fn links_only_keep_safe_urls() {
+        let html = markdown_to_html(
+            "[docs](https://example.com/a) [mail](mailto:a@example.com) [file](src/lib.rs) \
+             [x](javascript:alert(1)) [y](JavaScript&#58;alert(1)) [z](java%0Ascript:alert(1)) \
+             ![img](data:image/svg+xml,<svg>)",
+        );
+        assert!(html.contains("href=\"https://example.com/a\""), "{html}");
+        assert!(html.contains("href=\"mailto:a@example.com\""), "{html}");
+        assert!(html.contains("href=\"src/lib.rs\""), "{html}");
+        assert!(!html.to_ascii_lowercase().contains("javascript"), "{html}");
+        assert!(!html.contains("data:"), "{html}");
+        assert!(html.contains(">x</a>"), "{html}");
+    }
+
+    #[test]
+    fn code_fence_outgrows_backticks_in_content() {
+        assert_eq!(code_fence("a ```` b", ""), "`````\na ```` b\n`````\n");
+        assert_eq!("HTML".parse::<ExportFormat>(), Ok(ExportFormat::Html));
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.md")),
+            Some(ExportFormat::Markdown)
+        );
+    }
+}
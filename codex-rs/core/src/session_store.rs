@@ -0,0 +1,493 @@
+//! Queries over the recorded sessions, for `codex sessions list` and
+//! `codex sessions search`.
+//!
+//! Sessions are always recorded as rollouts in `~/.codex/sessions`; a
+//! [`SessionStore`] is how they are looked up. The JSONL store reads the
+//! rollouts, through the index of their summaries kept by
+//! [`list_sessions`], and has to read whole transcripts to search them. The
+//! SQLite store keeps the summaries and the text of the messages in
+//! `sessions.sqlite`, with a full-text index, and only reads the rollouts
+//! that changed since the last query.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use anyhow::Result;
+use rusqlite::Connection;
+use rusqlite::params;
+use time::Date;
+use time::macros::format_description;
+
+use crate::config::Config;
+use crate::config_types::SessionStoreKind;
+use crate::models::ContentItem;
+use crate::models::ResponseItem;
+use crate::rollout::IndexEntry;
+use crate::rollout::SessionSummary;
+use crate::rollout::list_sessions;
+use crate::rollout::read_rollout_items;
+use crate::rollout::rollout_files;
+use crate::rollout::sessions_dir;
+use crate::util::words;
+
+/// Database of the SQLite store, in the sessions folder.
+const DATABASE_FILE: &str = "sessions.sqlite";
+
+const SCHEMA: &str = "
+PRAGMA secure_delete = ON;
+CREATE TABLE IF NOT EXISTS sessions (
+    file TEXT PRIMARY KEY,
+    len INTEGER NOT NULL,
+    modified INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    cwd TEXT,
+    title TEXT,
+    first_prompt TEXT,
+    turns INTEGER NOT NULL,
+    stats TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_modified ON sessions (modified);
+CREATE INDEX IF NOT EXISTS sessions_cwd ON sessions (cwd);
+CREATE VIRTUAL TABLE IF NOT EXISTS messages USING fts5 (text);
+";
+
+/// Which sessions to return.
+#[derive(Debug, Clone, Default)]
+pub struct SessionQuery {
+    /// Only sessions run in this directory or below it.
+    pub cwd: Option<PathBuf>,
+    /// Only sessions active since then.
+    pub since: Option<SystemTime>,
+    /// Only sessions whose messages contain all these words, ignoring case.
+    pub text: Option<String>,
+    /// Most sessions to return.
+    pub limit: usize,
+}
+
+impl SessionQuery {
+    fn is_filtered(&self) -> bool {
+        self.cwd.is_some() || self.since.is_some() || self.text.is_some()
+    }
+}
+
+pub trait SessionStore {
+    /// The sessions with a prompt that match `query`, most recently active
+    /// first.
+    fn query(&self, query: &SessionQuery) -> Result<Vec<SessionSummary>>;
+}
+
+/// The store `config` asks for.
+pub fn open(config: &Config) -> Result<Box<dyn SessionStore>> {
+    if config.encrypt_sessions && config.session_store == SessionStoreKind::Sqlite {
+        anyhow::bail!(
+            "session_store = \"sqlite\" keeps the text of the messages unencrypted; it cannot be used with encrypt_sessions"
+        );
+    }
+    Ok(match config.session_store {
+        SessionStoreKind::Jsonl => Box::new(JsonlSessionStore::new(config.codex_home.clone())),
+        SessionStoreKind::Sqlite => Box::new(SqliteSessionStore::open(&config.codex_home)?),
+    })
+}
+
+/// Drop the sessions whose rollouts were deleted from the SQLite store, if
+/// there is one, so that their messages do not outlive them.
+pub(crate) fn forget_deleted_sessions(codex_home: &Path) -> Result<()> {
+    if !sessions_dir(codex_home).join(DATABASE_FILE).exists() {
+        return Ok(());
+    }
+    SqliteSessionStore::open(codex_home)?.refresh()
+}
+
+/// The first moment of `date`, given as `YYYY-MM-DD`, in UTC.
+pub fn parse_date(date: &str) -> Result<SystemTime, String> {
+    let date = Date::parse(date, format_description!("[year]-[month]-[day]"))
+        .map_err(|e| format!("invalid date `{date}`, expected YYYY-MM-DD: {e}"))?;
+    Ok(date.midnight().assume_utc().into())
+}
+
+pub struct JsonlSessionStore {
+    codex_home: PathBuf,
+}
+
+impl JsonlSessionStore {
+    pub fn new(codex_home: PathBuf) -> Self {
+        Self { codex_home }
+    }
+}
+
+impl SessionStore for JsonlSessionStore {
+    fn query(&self, query: &SessionQuery) -> Result<Vec<SessionSummary>> {
+        // Without filters, the first sessions listed are the answer.
+        let limit = if query.is_filtered() {
+            usize::MAX
+        } else {
+            query.limit
+        };
+        let text = query.text.as_deref().map(|text| words(text, 1));
+        let mut sessions = Vec::new();
+        for session in list_sessions(&self.codex_home, limit)? {
+            if sessions.len() == query.limit {
+                break;
+            }
+            if query.since.is_some_and(|since| session.modified < since) {
+                break;
+            }
+            let in_cwd = match (&query.cwd, &session.cwd) {
+                (None, _) => true,
+                (Some(cwd), Some(session_cwd)) => session_cwd.starts_with(cwd),
+                (Some(_), None) => false,
+            };
+            if !in_cwd {
+                continue;
+            }
+            if let Some(text) = &text {
+                let Ok((_, items)) = read_rollout_items(&session.path, |head| head.role.is_some())
+                else {
+                    continue;
+                };
+                if !text.is_subset(&words(&messages_text(&items), 1)) {
+                    continue;
+                }
+            }
+            sessions.push(session);
+        }
+        Ok(sessions)
+    }
+}
+
+pub struct SqliteSessionStore {
+    codex_home: PathBuf,
+    connection: Connection,
+}
+
+impl SqliteSessionStore {
+    pub fn open(codex_home: &Path) -> Result<Self> {
+        let dir = sessions_dir(codex_home);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(DATABASE_FILE);
+        let connection = Connection::open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            codex_home: codex_home.to_path_buf(),
+            connection,
+        })
+    }
+
+    /// Bring the database in line with the rollouts: add the new ones, read
+    /// again those that grew and drop those that were deleted.
+    fn refresh(&self) -> Result<()> {
+        let dir = sessions_dir(&self.codex_home);
+        let transaction = self.connection.unchecked_transaction()?;
+        let mut indexed: HashMap<String, i64> = transaction
+            .prepare("SELECT file, len FROM sessions")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        for rollout in rollout_files(&self.codex_home)? {
+            let len = rollout.len as i64;
+            if indexed.remove(&rollout.name) == Some(len) {
+                continue;
+            }
+            let Ok((meta, items)) =
+                read_rollout_items(&dir.join(&rollout.name), |head| head.role.is_some())
+            else {
+                continue;
+            };
+            let text = messages_text(&items);
+            let entry = IndexEntry::new(meta, &items, rollout.len);
+            let rowid: i64 = transaction.query_row(
+                "INSERT INTO sessions (file, len, modified, id, cwd, title, first_prompt, turns, stats)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT (file) DO UPDATE SET
+                     len = excluded.len,
+                     modified = excluded.modified,
+                     id = excluded.id,
+                     cwd = excluded.cwd,
+                     title = excluded.title,
+                     first_prompt = excluded.first_prompt,
+                     turns = excluded.turns,
+                     stats = excluded.stats
+                 RETURNING rowid",
+                params![
+                    rollout.name,
+                    len,
+                    unix_millis(rollout.modified),
+                    entry.id,
+                    entry
+                        .cwd
+                        .as_ref()
+                        .map(|cwd| cwd.to_string_lossy().into_owned()),
+                    entry.title,
+                    entry.first_prompt,
+                    entry.turns as i64,
+                    serde_json::to_string(&entry.stats)?,
+                ],
+                |row| row.get(0),
+            )?;
+            transaction.execute("DELETE FROM messages WHERE rowid = ?1", [rowid])?;
+            transaction.execute(
+                "INSERT INTO messages (rowid, text) VALUES (?1, ?2)",
+                params![rowid, text],
+            )?;
+        }
+
+        // What is left was deleted.
+        for file in indexed.keys() {
+            transaction.execute(
+                "DELETE FROM messages WHERE rowid IN (SELECT rowid FROM sessions WHERE file = ?1)",
+                [file],
+            )?;
+            transaction.execute("DELETE FROM sessions WHERE file = ?1", [file])?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+impl SessionStore for SqliteSessionStore {
+    fn query(&self, query: &SessionQuery) -> Result<Vec<SessionSummary>> {
+        self.refresh()?;
+        let dir = sessions_dir(&self.codex_home);
+        let cwd = query
+            .cwd
+            .as_ref()
+            .map(|cwd| cwd.to_string_lossy().into_owned());
+        let since = query.since.map(unix_millis);
+        // Quote the words so that FTS5 takes none of them for an operator.
+        let text = query.text.as_deref().map(|text| {
+            words(text, 1)
+                .iter()
+                .map(|word| format!("\"{word}\""))
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        let limit = i64::try_from(query.limit).unwrap_or(i64::MAX);
+
+        let mut statement = self.connection.prepare(
+            "SELECT file, modified, id, cwd, title, first_prompt, turns, stats FROM sessions
+             WHERE first_prompt IS NOT NULL
+               AND (?1 IS NULL OR cwd = ?1 OR substr(cwd, 1, length(?1) + 1) = ?1 || '/')
+               AND (?2 IS NULL OR modified >= ?2)
+               AND (?3 IS NULL OR ?3 = '' OR rowid IN (SELECT rowid FROM messages WHERE messages MATCH ?3))
+             ORDER BY modified DESC
+             LIMIT ?4",
+        )?;
+        let sessions = statement
+            .query_map(params![cwd, since, text, limit], |row| {
+                let file: String = row.get(0)?;
+                let modified: i64 = row.get(1)?;
+                let cwd: Option<String> = row.get(3)?;
+                let turns: i64 = row.get(6)?;
+                let stats: String = row.get(7)?;
+                Ok(SessionSummary {
+                    path: dir.join(file),
+                    id: row.get(2)?,
+                    cwd: cwd.map(PathBuf::from),
+                    title: row.get(4)?,
+                    first_prompt: row.get(5)?,
+                    turns: turns as usize,
+                    stats: serde_json::from_str(&stats).unwrap_or_default(),
+                    modified: UNIX_EPOCH + Duration::from_millis(modified as u64),
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(sessions)
+    }
+}
+
+/// The text of the user and assistant messages among `items`.
+fn messages_text(items: &[ResponseItem]) -> String {
+    let mut text = String::new();
+    for item in items {
+        let ResponseItem::Message { content, .. } = item else {
+            continue;
+        };
+        for content in content {
+            if let ContentItem::InputText { text: t } | ContentItem::OutputText { text: t } =
+                content
+            {
+                text.push_str(t);
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    const FIRST: &str = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+    const SECOND: &str = "5973b6c1-0000-487b-a530-2aeb6098ae0e";
+
+    fn rollout_name(id: &str) -> String {
+        format!("rollout-2025-05-07T17-24-21-{id}.jsonl")
+    }
+
+    fn write_rollout(codex_home: &Path, id: &str, cwd: &str, messages: &[(&str, &str)]) {
+        let dir = sessions_dir(codex_home);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut lines = vec![format!(r#"{{"id":"{id}","timestamp":"t","cwd":"{cwd}"}}"#)];
+        for (role, text) in messages {
+            let kind = if *role == "user" {
+                "input_text"
+            } else {
+                "output_text"
+            };
+            lines.push(format!(
+                r#"{{"type":"message","role":"{role}","content":[{{"type":"{kind}","text":"{text}"}}]}}"#
+            ));
+        }
+        std::fs::write(dir.join(rollout_name(id)), lines.join("\n")).unwrap();
+    }
+
+    fn ids(store: &dyn SessionStore, query: SessionQuery) -> Vec<String> {
+        let mut ids: Vec<String> = store
+            .query(&SessionQuery { limit: 10, ..query })
+            .unwrap()
+            .into_iter()
+            .map(|session| session.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn both_stores_answer_queries_alike() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            FIRST,
+            "/work/api",
+            &[
+                ("user", "fix the flaky login test"),
+                ("assistant", "The Retry loop was racing."),
+            ],
+        );
+        write_rollout(
+            home.path(),
+            SECOND,
+            "/work/web",
+            &[("user", "add a dark theme")],
+        );
+        let jsonl = JsonlSessionStore::new(home.path().to_path_buf());
+        let sqlite = SqliteSessionStore::open(home.path()).unwrap();
+
+        for store in [&jsonl as &dyn SessionStore, &sqlite] {
+            assert_eq!(ids(store, SessionQuery::default()), vec![FIRST, SECOND]);
+            assert_eq!(
+                ids(
+                    store,
+                    SessionQuery {
+                        cwd: Some(PathBuf::from("/work/web")),
+                        ..Default::default()
+                    }
+                ),
+                vec![SECOND]
+            );
+            assert_eq!(
+                ids(
+                    store,
+                    SessionQuery {
+                        cwd: Some(PathBuf::from("/work/we")),
+                        ..Default::default()
+                    }
+                ),
+                Vec::<String>::new()
+            );
+            assert_eq!(
+                ids(
+                    store,
+                    SessionQuery {
+                        text: Some("retry LOGIN".to_string()),
+                        ..Default::default()
+                    }
+                ),
+                vec![FIRST]
+            );
+            assert_eq!(
+                ids(
+                    store,
+                    SessionQuery {
+                        text: Some("retry theme".to_string()),
+                        ..Default::default()
+                    }
+                ),
+                Vec::<String>::new()
+            );
+            assert_eq!(
+                ids(
+                    store,
+                    SessionQuery {
+                        since: Some(SystemTime::now() + Duration::from_secs(3600)),
+                        ..Default::default()
+                    }
+                ),
+                Vec::<String>::new()
+            );
+        }
+    }
+
+    #[test]
+    fn the_sqlite_store_follows_the_rollouts() {
+        let home = TempDir::new().unwrap();
+        write_rollout(home.path(), FIRST, "/work", &[("user", "fix the build")]);
+        write_rollout(home.path(), SECOND, "/work", &[("user", "add a theme")]);
+        let store = SqliteSessionStore::open(home.path()).unwrap();
+        let search = |text: &str| {
+            ids(
+                &store,
+                SessionQuery {
+                    text: Some(text.to_string()),
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(search("build"), vec![FIRST]);
+
+        write_rollout(
+            home.path(),
+            FIRST,
+            "/work",
+            &[("user", "fix the build"), ("user", "now the linter")],
+        );
+        std::fs::remove_file(sessions_dir(home.path()).join(rollout_name(SECOND))).unwrap();
+        assert_eq!(search("linter"), vec![FIRST]);
+        assert_eq!(ids(&store, SessionQuery::default()), vec![FIRST]);
+        assert_eq!(
+            store
+                .query(&SessionQuery {
+                    limit: 1,
+                    ..Default::default()
+                })
+                .unwrap()[0]
+                .turns,
+            2
+        );
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(
+            parse_date("1970-01-02").unwrap(),
+            UNIX_EPOCH + Duration::from_secs(86_400)
+        );
+        assert!(parse_date("02/01/1970").is_err());
+    }
+}
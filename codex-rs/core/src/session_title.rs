@@ -0,0 +1,112 @@
+//! Short titles for sessions, shown by `codex sessions list` and the resume
+//! picker. A title is generated from the first prompt by `router.small_model`
+//! when one is configured, and derived from the prompt itself otherwise or
+//! when the request fails.
+
+use tracing::warn;
+
+use crate::client::ModelClient;
+use crate::client_common::Prompt;
+use crate::error::Result;
+use crate::models::ContentItem;
+use crate::models::ResponseItem;
+use crate::replay;
+
+/// Titles longer than this are cut at a word boundary.
+const MAX_TITLE_CHARS: usize = 60;
+
+const TITLE_REQUEST: &str = "Write a title of at most six words for a coding session that starts with the request below. Reply with the title only.";
+
+/// Title for a session whose first prompt is `prompt`, or `None` if the
+/// prompt has no text.
+pub(crate) async fn generate_title(client: Option<&ModelClient>, prompt: &str) -> Option<String> {
+    // A recorded or replayed session must contain only the conversation's
+    // own requests.
+    let recording = !matches!(replay::recorder(), Ok(None));
+    if let Some(client) = client.filter(|_| !replay::is_replaying() && !recording) {
+        match request_title(client, prompt).await {
+            Ok(Some(title)) => return Some(title),
+            Ok(None) => {}
+            Err(e) => warn!("failed to generate a session title: {e}"),
+        }
+    }
+    heuristic_title(prompt)
+}
+
+async fn request_title(client: &ModelClient, prompt: &str) -> Result<Option<String>> {
+    let prompt = Prompt {
+        input: vec![ResponseItem::Message {
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: format!("{TITLE_REQUEST}\n\n{prompt}"),
+            }],
+        }],
+        ..Default::default()
+    };
+    let reply = client.complete(&prompt).await?;
+    let title = reply
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || "\"'`*#.".contains(c)))
+        .find(|line| !line.is_empty());
+    Ok(title.map(truncate))
+}
+
+/// The first sentence of the first line of `prompt`.
+fn heuristic_title(prompt: &str) -> Option<String> {
+    let line = prompt
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    let sentence = line
+        .find(". ")
+        .or_else(|| line.find("? "))
+        .map_or(line.as_str(), |end| &line[..=end]);
+    Some(truncate(sentence.trim_end_matches('.')))
+}
+
+fn truncate(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let cut: String = title.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > MAX_TITLE_CHARS / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([' ', ',', ';', ':']))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn heuristic_title_takes_the_first_sentence() {
+        assert_eq!(heuristic_title("  \n"), None);
+        assert_eq!(
+            heuristic_title("Fix the flaky   login test. It fails on CI.\nDetails below")
+                .as_deref(),
+            Some("Fix the flaky login test")
+        );
+        assert_eq!(
+            heuristic_title("why does cargo build fail? see log").as_deref(),
+            Some("why does cargo build fail?")
+        );
+    }
+
+    #[test]
+    fn long_titles_are_cut_at_a_word() {
+        let title = heuristic_title(
+            "refactor the session configuration so that every front-end shares one code path for overrides",
+        )
+        .unwrap();
+        assert_eq!(
+            title,
+            "refactor the session configuration so that every front-end…"
+        );
+        assert!(title.chars().count() <= MAX_TITLE_CHARS + 1);
+    }
+}
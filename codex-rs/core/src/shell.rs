@@ -0,0 +1,745 @@
+//! The shell the model's commands run in. Models write commands for bash; on
+//! Windows, where the user's shell is usually PowerShell or cmd, the common
+//! Unix commands among them (`ls -la`, `rm -rf`, `head -n 20`, ...) are
+//! translated, arguments are quoted the way that shell expects, backslashes
+//! in paths the command prints are turned into forward slashes, and the model
+//! is told which shell it is talking to.
+//!
+//! Translation is best effort: a script that uses anything beyond simple
+//! commands joined with `&&`, `||`, `;` and `|`, with `>`/`>>` redirections,
+//! is handed to the shell as the model wrote it.
+
+use std::borrow::Cow;
+
+use serde::Deserialize;
+
+/// Told to the model when commands run in Git Bash.
+const GIT_BASH_INSTRUCTIONS: &str = "Shell commands run in Git Bash on Windows. Write them for bash; \
+paths in command output are shown with forward slashes, e.g. `C:/Users/me/project`.";
+
+/// Told to the model when commands run in PowerShell.
+const POWERSHELL_INSTRUCTIONS: &str = "Shell commands run in Windows PowerShell, not bash. \
+`ls`, `cat`, `head`, `tail`, `rm`, `mkdir`, `cp`, `mv`, `pwd` and `which` with their common flags, \
+joined with `&&`, `||`, `;` or `|`, are translated to PowerShell; write anything else in PowerShell. \
+Paths in command output are shown with forward slashes.";
+
+/// Told to the model when commands run in cmd.
+const CMD_INSTRUCTIONS: &str = "Shell commands run in the Windows command prompt (cmd.exe), not bash. \
+`ls`, `cat`, `rm`, `mkdir`, `cp`, `mv`, `pwd` and `which` with their common flags, joined with \
+`&&`, `||` or `|`, are translated to cmd; write anything else for cmd. \
+Paths in command output are shown with forward slashes.";
+
+/// Lines `head` and `tail` print without `-n`.
+const DEFAULT_HEAD_TAIL_LINES: &str = "10";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Shell {
+    /// A POSIX shell: commands run as the model writes them.
+    #[serde(skip)]
+    Posix,
+    /// Git Bash (MSYS2) on Windows.
+    #[serde(rename = "git-bash")]
+    GitBash,
+    #[serde(rename = "powershell")]
+    PowerShell,
+    #[serde(rename = "cmd")]
+    Cmd,
+}
+
+/// A piece of a `bash -lc` script.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// `&&`, `||`, `;` or `|`.
+    Separator(&'static str),
+    /// `>`, `>>`, `2>`, `2>&1`, ...; the target, if any, is the next word.
+    Redirect(String),
+}
+
+impl Shell {
+    /// The shell Codex runs in: on Windows, Git Bash when started from it,
+    /// PowerShell when started from it and cmd otherwise.
+    pub fn detect() -> Self {
+        if cfg!(windows) {
+            detect_windows(|name| std::env::var(name).ok())
+        } else {
+            Self::Posix
+        }
+    }
+
+    /// What the model is told about the shell, if anything.
+    pub(crate) fn instructions(self) -> Option<&'static str> {
+        match self {
+            Self::Posix => None,
+            Self::GitBash => Some(GIT_BASH_INSTRUCTIONS),
+            Self::PowerShell => Some(POWERSHELL_INSTRUCTIONS),
+            Self::Cmd => Some(CMD_INSTRUCTIONS),
+        }
+    }
+
+    /// `arg` quoted so that this shell passes it on as one argument.
+    pub fn quote(self, arg: &str) -> Cow<'_, str> {
+        let plain = |special: &str| !arg.is_empty() && !arg.contains(|c| special.contains(c));
+        match self {
+            // Only a NUL byte cannot be quoted, and no argument can hold one.
+            Self::Posix | Self::GitBash => shlex::try_quote(arg).unwrap_or(Cow::Borrowed(arg)),
+            Self::PowerShell if plain(" \t\"'`$&|;,(){}[]<>@#") => Cow::Borrowed(arg),
+            Self::PowerShell => Cow::Owned(format!("'{}'", arg.replace('\'', "''"))),
+            Self::Cmd if plain(" \t\"&|<>^%()") => Cow::Borrowed(arg),
+            Self::Cmd => Cow::Owned(format!("\"{}\"", arg.replace('"', "\"\""))),
+        }
+    }
+
+    /// The argv that runs `command`, as the model wrote it, in this shell.
+    pub fn translate(self, command: &[String]) -> Vec<String> {
+        let (translate_command, null_device): (TranslateCommand, &str) = match self {
+            Self::Posix | Self::GitBash => return command.to_vec(),
+            Self::PowerShell => (powershell_command, "$null"),
+            Self::Cmd => (cmd_command, "NUL"),
+        };
+        let tokens = match command {
+            [bash, flag, script] if bash == "bash" && flag == "-lc" => match tokenize(script) {
+                Some(tokens) => tokens,
+                None => return self.verbatim(command),
+            },
+            _ => command.iter().cloned().map(Token::Word).collect(),
+        };
+        let Some(pieces) = translate_tokens(&tokens, translate_command, null_device) else {
+            return self.verbatim(command);
+        };
+        let argv = self.argv().iter().map(|arg| arg.to_string());
+        if self == Self::Cmd {
+            // cmd parses its own command line, so the pieces are passed as
+            // they are; those with spaces get quoted when the process is
+            // spawned.
+            argv.chain(pieces.into_iter().map(Piece::into_string))
+                .collect()
+        } else {
+            let script = pieces
+                .iter()
+                .map(|piece| piece.script(self))
+                .collect::<Vec<_>>()
+                .join(" ");
+            argv.chain(std::iter::once(script)).collect()
+        }
+    }
+
+    /// The argv that runs a script in this shell, without the script.
+    fn argv(self) -> &'static [&'static str] {
+        match self {
+            Self::Posix | Self::GitBash => &["bash", "-lc"],
+            Self::PowerShell => &[
+                "powershell.exe",
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+            ],
+            Self::Cmd => &["cmd.exe", "/d", "/c"],
+        }
+    }
+
+    /// `command` handed to the shell untranslated.
+    fn verbatim(self, command: &[String]) -> Vec<String> {
+        let script = match command {
+            [bash, flag, script] if bash == "bash" && flag == "-lc" => script.clone(),
+            _ => command
+                .iter()
+                .map(|arg| self.quote(arg))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        self.argv()
+            .iter()
+            .map(|arg| arg.to_string())
+            .chain(std::iter::once(script))
+            .collect()
+    }
+
+    /// `output` with the Windows paths in it written with forward slashes.
+    /// Only words that look like paths are changed: `C:\...`, `\\server\...`,
+    /// `.\...`, `..\...` and relative paths ending in a file name with an
+    /// extension, such as `src\main.rs`.
+    pub fn normalize_output(self, output: &str) -> Cow<'_, str> {
+        if self == Self::Posix || !output.contains('\\') {
+            return Cow::Borrowed(output);
+        }
+        let mut normalized = String::with_capacity(output.len());
+        for piece in output.split_inclusive(char::is_whitespace) {
+            let word = piece.trim_end_matches(char::is_whitespace);
+            if is_windows_path(word) {
+                normalized.push_str(&word.replace('\\', "/"));
+                normalized.push_str(&piece[word.len()..]);
+            } else {
+                normalized.push_str(piece);
+            }
+        }
+        Cow::Owned(normalized)
+    }
+}
+
+fn detect_windows(var: impl Fn(&str) -> Option<String>) -> Shell {
+    if var("MSYSTEM").is_some() {
+        return Shell::GitBash;
+    }
+    // PowerShell adds the user's and its own module folders to the one
+    // Windows sets for every process.
+    let module_paths = var("PSModulePath").map_or(0, |paths| {
+        paths.split(';').filter(|path| !path.is_empty()).count()
+    });
+    if module_paths >= 3 {
+        Shell::PowerShell
+    } else {
+        Shell::Cmd
+    }
+}
+
+/// Split `script` into tokens, or `None` when it uses more of bash than
+/// can be translated.
+fn tokenize(script: &str) -> Option<Vec<Token>> {
+    // Variables, substitutions and heredocs mean something else, or nothing,
+    // in the Windows shells.
+    if script.contains(['`', '$', '\n']) || script.contains("<<") {
+        return None;
+    }
+    let mut tokens = Vec::new();
+    for word in shlex::split(script)? {
+        let (word, separator) = match word.strip_suffix(';') {
+            Some(word) if !word.is_empty() => (word.to_string(), Some(";")),
+            _ => (word, None),
+        };
+        match word.as_str() {
+            "&&" => tokens.push(Token::Separator("&&")),
+            "||" => tokens.push(Token::Separator("||")),
+            ";" => tokens.push(Token::Separator(";")),
+            "|" => tokens.push(Token::Separator("|")),
+            _ => match split_redirect(&word) {
+                Some((redirect, target)) => {
+                    tokens.push(Token::Redirect(redirect.to_string()));
+                    tokens.extend(target.map(|target| Token::Word(target.to_string())));
+                }
+                None if word.contains(['<', '>', '&', '*', '?']) => return None,
+                None => tokens.push(Token::Word(word)),
+            },
+        }
+        tokens.extend(separator.map(Token::Separator));
+    }
+    Some(tokens)
+}
+
+/// `>out.txt` is `>` and `out.txt`, `2>&1` is all redirection.
+fn split_redirect(word: &str) -> Option<(&str, Option<&str>)> {
+    let stream_len = usize::from(word.starts_with(['1', '2']));
+    let rest = word[stream_len..].strip_prefix('>')?;
+    let op_len = stream_len + 1 + usize::from(rest.starts_with('>'));
+    let (op, target) = word.split_at(op_len);
+    if target.starts_with('&') {
+        return Some((word, None));
+    }
+    Some((op, (!target.is_empty()).then_some(target)))
+}
+
+/// A piece of the translated script.
+#[derive(Clone)]
+enum Piece {
+    /// Written as it is: a command name, flag or operator.
+    Raw(String),
+    /// An argument, quoted for the shell.
+    Arg(String),
+}
+
+/// Translates the words of one simple command, or `None` when it cannot.
+type TranslateCommand = fn(&[String]) -> Option<Vec<Piece>>;
+
+impl Piece {
+    fn script(&self, shell: Shell) -> Cow<'_, str> {
+        match self {
+            Self::Raw(raw) => Cow::Borrowed(raw),
+            Self::Arg(arg) => shell.quote(arg),
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Self::Raw(s) | Self::Arg(s) => s,
+        }
+    }
+}
+
+/// Translate each simple command in `tokens` with `translate_command`,
+/// which returns `None` for a command it passes through. `/dev/null` becomes
+/// `null_device`.
+fn translate_tokens(
+    tokens: &[Token],
+    translate_command: fn(&[String]) -> Option<Vec<Piece>>,
+    null_device: &str,
+) -> Option<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let flush = |words: &mut Vec<String>, pieces: &mut Vec<Piece>| -> Option<()> {
+        if words.is_empty() {
+            return Some(());
+        }
+        // `FOO=bar cmd` has no equivalent.
+        if words[0].contains('=') {
+            return None;
+        }
+        match translate_command(words) {
+            Some(translated) => pieces.extend(translated),
+            None => {
+                pieces.push(Piece::Raw(words[0].clone()));
+                pieces.extend(words[1..].iter().cloned().map(Piece::Arg));
+            }
+        }
+        words.clear();
+        Some(())
+    };
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => words.push(word.clone()),
+            Token::Separator(separator) => {
+                flush(&mut words, &mut pieces)?;
+                pieces.push(Piece::Raw(separator.to_string()));
+            }
+            Token::Redirect(redirect) => {
+                flush(&mut words, &mut pieces)?;
+                pieces.push(Piece::Raw(redirect.clone()));
+                if redirect.contains('&') {
+                    continue;
+                }
+                if let Some(Token::Word(target)) = tokens.next() {
+                    pieces.push(if target == "/dev/null" {
+                        Piece::Raw(null_device.to_string())
+                    } else {
+                        Piece::Arg(target.clone())
+                    });
+                }
+            }
+        }
+    }
+    flush(&mut words, &mut pieces)?;
+    Some(pieces)
+}
+
+/// The single-letter flags and the operands of `args`, or `None` if there is
+/// a flag outside `known` or a long option.
+fn flags_and_operands<'a>(args: &'a [String], known: &str) -> Option<(String, Vec<&'a str>)> {
+    let mut flags = String::new();
+    let mut operands = Vec::new();
+    for arg in args {
+        match arg.strip_prefix('-') {
+            Some(letters) if !letters.is_empty() => {
+                if !letters.chars().all(|c| known.contains(c)) {
+                    return None;
+                }
+                flags.push_str(letters);
+            }
+            _ => operands.push(arg.as_str()),
+        }
+    }
+    Some((flags, operands))
+}
+
+/// `-n N`, `-nN`, `-N` or nothing, followed by at most one file.
+fn line_count_and_file(args: &[String]) -> Option<(&str, Option<&str>)> {
+    let (lines, rest) = match args {
+        [flag, lines, rest @ ..] if flag == "-n" => (lines.as_str(), rest),
+        [flag, rest @ ..] if flag.starts_with('-') => (
+            flag.strip_prefix("-n")
+                .filter(|lines| !lines.is_empty())
+                .unwrap_or(&flag[1..]),
+            rest,
+        ),
+        rest => (DEFAULT_HEAD_TAIL_LINES, rest),
+    };
+    if lines.is_empty() || !lines.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match rest {
+        [] => Some((lines, None)),
+        [file] => Some((lines, Some(file.as_str()))),
+        _ => None,
+    }
+}
+
+fn raw(pieces: &[&str]) -> Vec<Piece> {
+    pieces
+        .iter()
+        .map(|piece| Piece::Raw(piece.to_string()))
+        .collect()
+}
+
+/// `operands` as the value of a PowerShell `-Path`: `'a', 'b'`.
+fn powershell_paths(pieces: &mut Vec<Piece>, operands: &[&str]) {
+    for (i, operand) in operands.iter().enumerate() {
+        if i > 0 {
+            pieces.push(Piece::Raw(",".to_string()));
+        }
+        pieces.push(Piece::Arg(operand.to_string()));
+    }
+}
+
+fn powershell_command(words: &[String]) -> Option<Vec<Piece>> {
+    let (cmd, args) = words.split_first()?;
+    let pieces = match cmd.as_str() {
+        "ls" => {
+            let (flags, operands) = flags_and_operands(args, "laAhR1tr")?;
+            let mut pieces = raw(&["Get-ChildItem"]);
+            if flags.contains(['a', 'A']) {
+                pieces.extend(raw(&["-Force"]));
+            }
+            if flags.contains('R') {
+                pieces.extend(raw(&["-Recurse"]));
+            }
+            if !operands.is_empty() {
+                pieces.extend(raw(&["-Path"]));
+                powershell_paths(&mut pieces, &operands);
+            }
+            pieces
+        }
+        "cat" => {
+            let (_, operands) = flags_and_operands(args, "")?;
+            if operands.is_empty() {
+                return None;
+            }
+            let mut pieces = raw(&["Get-Content", "-Path"]);
+            powershell_paths(&mut pieces, &operands);
+            pieces
+        }
+        "head" | "tail" => {
+            let (lines, file) = line_count_and_file(args)?;
+            let count = if cmd == "head" {
+                "-TotalCount"
+            } else {
+                "-Tail"
+            };
+            match file {
+                Some(file) => {
+                    let mut pieces = raw(&["Get-Content", "-Path"]);
+                    pieces.push(Piece::Arg(file.to_string()));
+                    pieces.extend(raw(&[count, lines]));
+                    pieces
+                }
+                None if cmd == "head" => raw(&["Select-Object", "-First", lines]),
+                None => raw(&["Select-Object", "-Last", lines]),
+            }
+        }
+        "rm" => {
+            let (flags, operands) = flags_and_operands(args, "rRfv")?;
+            if operands.is_empty() {
+                return None;
+            }
+            let mut pieces = raw(&["Remove-Item"]);
+            if flags.contains(['r', 'R']) {
+                pieces.extend(raw(&["-Recurse"]));
+            }
+            if flags.contains('f') {
+                // `rm -f` is quiet about missing files.
+                pieces.extend(raw(&["-Force", "-ErrorAction", "SilentlyContinue"]));
+            }
+            pieces.extend(raw(&["-Path"]));
+            powershell_paths(&mut pieces, &operands);
+            pieces
+        }
+        "mkdir" => {
+            let (flags, operands) = flags_and_operands(args, "pv")?;
+            if operands.is_empty() {
+                return None;
+            }
+            let mut pieces = raw(&["New-Item", "-ItemType", "Directory"]);
+            if flags.contains('p') {
+                pieces.extend(raw(&["-Force"]));
+            }
+            pieces.extend(raw(&["-Path"]));
+            powershell_paths(&mut pieces, &operands);
+            pieces
+        }
+        "cp" | "mv" => {
+            let (flags, operands) = flags_and_operands(args, "rRfv")?;
+            let [source, destination] = operands.as_slice() else {
+                return None;
+            };
+            let mut pieces = raw(&[if cmd == "cp" {
+                "Copy-Item"
+            } else {
+                "Move-Item"
+            }]);
+            if cmd == "cp" && flags.contains(['r', 'R']) {
+                pieces.extend(raw(&["-Recurse"]));
+            }
+            if flags.contains('f') {
+                pieces.extend(raw(&["-Force"]));
+            }
+            pieces.extend(raw(&["-Path"]));
+            pieces.push(Piece::Arg(source.to_string()));
+            pieces.extend(raw(&["-Destination"]));
+            pieces.push(Piece::Arg(destination.to_string()));
+            pieces
+        }
+        "pwd" if args.is_empty() => raw(&["Get-Location"]),
+        "which" => {
+            let [program] = args else {
+                return None;
+            };
+            let mut pieces = raw(&["Get-Command"]);
+            pieces.push(Piece::Arg(program.clone()));
+            pieces
+        }
+        _ => return None,
+    };
+    Some(pieces)
+}
+
+fn cmd_command(words: &[String]) -> Option<Vec<Piece>> {
+    let (cmd, args) = words.split_first()?;
+    // cmd's own commands take `/` for options, so paths need backslashes.
+    let paths = |operands: &[&str]| -> Vec<Piece> {
+        operands
+            .iter()
+            .map(|operand| Piece::Arg(operand.replace('/', "\\")))
+            .collect()
+    };
+    let pieces = match cmd.as_str() {
+        "ls" => {
+            let (flags, operands) = flags_and_operands(args, "laAhR1tr")?;
+            let mut pieces = raw(&["dir"]);
+            if flags.contains(['a', 'A']) {
+                pieces.extend(raw(&["/a"]));
+            }
+            if flags.contains('R') {
+                pieces.extend(raw(&["/s"]));
+            }
+            pieces.extend(paths(&operands));
+            pieces
+        }
+        "cat" => {
+            let (_, operands) = flags_and_operands(args, "")?;
+            if operands.is_empty() {
+                return None;
+            }
+            [raw(&["type"]), paths(&operands)].concat()
+        }
+        "rm" => {
+            let (flags, operands) = flags_and_operands(args, "rRfv")?;
+            if operands.is_empty() {
+                return None;
+            }
+            let command = if flags.contains(['r', 'R']) {
+                raw(&["rmdir", "/s", "/q"])
+            } else if flags.contains('f') {
+                raw(&["del", "/f", "/q"])
+            } else {
+                raw(&["del"])
+            };
+            [command, paths(&operands)].concat()
+        }
+        "mkdir" => {
+            // cmd's `mkdir` creates missing parents, like `mkdir -p`.
+            let (_, operands) = flags_and_operands(args, "pv")?;
+            if operands.is_empty() {
+                return None;
+            }
+            [raw(&["mkdir"]), paths(&operands)].concat()
+        }
+        "cp" | "mv" => {
+            let (flags, operands) = flags_and_operands(args, "rRfv")?;
+            if operands.len() != 2 {
+                return None;
+            }
+            let command = match cmd.as_str() {
+                "cp" if flags.contains(['r', 'R']) => raw(&["xcopy", "/e", "/i", "/y"]),
+                "cp" => raw(&["copy", "/y"]),
+                _ => raw(&["move", "/y"]),
+            };
+            [command, paths(&operands)].concat()
+        }
+        "pwd" if args.is_empty() => raw(&["cd"]),
+        "which" => {
+            let [program] = args else {
+                return None;
+            };
+            [raw(&["where"]), vec![Piece::Arg(program.clone())]].concat()
+        }
+        _ => return None,
+    };
+    Some(pieces)
+}
+
+fn is_windows_path(word: &str) -> bool {
+    let path = word
+        .trim_start_matches(['\'', '"', '(', '['])
+        .trim_end_matches(['\'', '"', ')', ']', ',', ';', ':']);
+    if !path.contains('\\')
+        || !path
+            .chars()
+            .all(|c| c.is_alphanumeric() || "\\/.:_-+@~$".contains(c))
+    {
+        return false;
+    }
+    let bytes = path.as_bytes();
+    let absolute = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && &path[1..3] == ":\\";
+    let unc = path.starts_with("\\\\");
+    let dotted = path.starts_with(".\\") || path.starts_with("..\\");
+    let relative = !path.starts_with('\\')
+        && path.split('\\').all(|component| !component.is_empty())
+        && path
+            .rsplit('\\')
+            .next()
+            .is_some_and(|name| name.contains('.') && !name.starts_with('.'));
+    absolute || unc || dotted || relative
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bash(script: &str) -> Vec<String> {
+        vec!["bash".to_string(), "-lc".to_string(), script.to_string()]
+    }
+
+    fn powershell(script: &str) -> Vec<String> {
+        let argv = [
+            "powershell.exe",
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+        ];
+        argv.iter()
+            .map(|arg| arg.to_string())
+            .chain(std::iter::once(script.to_string()))
+            .collect()
+    }
+
+    fn cmd(args: &[&str]) -> Vec<String> {
+        ["cmd.exe", "/d", "/c"]
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn detects_the_windows_shell_from_the_environment() {
+        assert_eq!(
+            detect_windows(env(&[("MSYSTEM", "MINGW64")])),
+            Shell::GitBash
+        );
+        assert_eq!(
+            detect_windows(env(&[(
+                "PSModulePath",
+                r"C:\Users\me\Documents\WindowsPowerShell\Modules;C:\Program Files\WindowsPowerShell\Modules;C:\Windows\system32\WindowsPowerShell\v1.0\Modules"
+            )])),
+            Shell::PowerShell
+        );
+        assert_eq!(
+            detect_windows(env(&[(
+                "PSModulePath",
+                r"C:\Program Files\WindowsPowerShell\Modules;C:\Windows\system32\WindowsPowerShell\v1.0\Modules"
+            )])),
+            Shell::Cmd
+        );
+    }
+
+    #[test]
+    fn quotes_arguments_for_each_shell() {
+        assert_eq!(
+            shlex::split(&Shell::Posix.quote("it's here")),
+            Some(vec!["it's here".to_string()])
+        );
+        assert_eq!(Shell::PowerShell.quote("src/lib.rs"), "src/lib.rs");
+        assert_eq!(Shell::PowerShell.quote("it's here"), "'it''s here'");
+        assert_eq!(Shell::PowerShell.quote("$HOME"), "'$HOME'");
+        assert_eq!(Shell::Cmd.quote("a&b"), "\"a&b\"");
+        assert_eq!(Shell::Cmd.quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn posix_commands_run_as_written() {
+        assert_eq!(Shell::Posix.translate(&bash("ls -la")), bash("ls -la"));
+        assert_eq!(Shell::GitBash.translate(&bash("ls -la")), bash("ls -la"));
+    }
+
+    #[test]
+    fn translates_unix_commands_to_powershell() {
+        let shell = Shell::PowerShell;
+        assert_eq!(
+            shell.translate(&bash("ls -la src")),
+            powershell("Get-ChildItem -Force -Path src")
+        );
+        assert_eq!(
+            shell.translate(&bash("rm -rf target 'my dir'")),
+            powershell(
+                "Remove-Item -Recurse -Force -ErrorAction SilentlyContinue -Path target , 'my dir'"
+            )
+        );
+        assert_eq!(
+            shell.translate(&bash("cat Cargo.toml | head -n 5")),
+            powershell("Get-Content -Path Cargo.toml | Select-Object -First 5")
+        );
+        assert_eq!(
+            shell.translate(&bash("mkdir -p out && cargo build 2>/dev/null")),
+            powershell("New-Item -ItemType Directory -Force -Path out && cargo build 2> $null")
+        );
+        assert_eq!(
+            shell.translate(&["tail".to_string(), "-20".to_string(), "log.txt".to_string()]),
+            powershell("Get-Content -Path log.txt -Tail 20")
+        );
+    }
+
+    #[test]
+    fn hands_untranslatable_scripts_to_powershell_as_written() {
+        assert_eq!(
+            Shell::PowerShell.translate(&bash("for f in *.rs; do wc -l $f; done")),
+            powershell("for f in *.rs; do wc -l $f; done")
+        );
+        assert_eq!(
+            Shell::PowerShell.translate(&bash("RUST_LOG=debug cargo run")),
+            powershell("RUST_LOG=debug cargo run")
+        );
+    }
+
+    #[test]
+    fn translates_unix_commands_to_cmd() {
+        let shell = Shell::Cmd;
+        assert_eq!(
+            shell.translate(&bash("ls -a src/bin")),
+            cmd(&["dir", "/a", "src\\bin"])
+        );
+        assert_eq!(
+            shell.translate(&bash("rm -rf target && cp -r assets out > NUL")),
+            cmd(&[
+                "rmdir", "/s", "/q", "target", "&&", "xcopy", "/e", "/i", "/y", "assets", "out",
+                ">", "NUL"
+            ])
+        );
+        assert_eq!(shell.translate(&bash("which git")), cmd(&["where", "git"]));
+    }
+
+    #[test]
+    fn normalizes_windows_paths_in_output() {
+        assert_eq!(
+            Shell::PowerShell.normalize_output(
+                "error in C:\\work\\repo\\src\\main.rs: see .\\notes and src\\lib.rs\n"
+            ),
+            "error in C:/work/repo/src/main.rs: see ./notes and src/lib.rs\n"
+        );
+        assert_eq!(
+            Shell::Cmd.normalize_output("printf(\"a\\n\"); \\d+ 50\\%"),
+            "printf(\"a\\n\"); \\d+ 50\\%"
+        );
+        assert_eq!(
+            Shell::Posix.normalize_output("C:\\work"),
+            Cow::Borrowed("C:\\work")
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Structured output: checking the agent's final message against the JSON
+//! Schema of a [`ResponseFormat`], and telling the model what to fix when it
+//! does not match.
+//!
+//! Only the keywords that describe the shape of a value are checked: `type`,
+//! `enum`, `const`, `anyOf`, `properties`, `required`,
+//! `additionalProperties` and `items`. Other keywords are ignored here, but
+//! still reach providers that enforce the schema themselves.
+
+use serde_json::Value;
+
+use crate::protocol::ResponseFormat;
+
+/// Name of the schema in requests to providers.
+pub(crate) const SCHEMA_NAME: &str = "codex_output";
+
+/// How many times the model is asked to fix a final message that does not
+/// match the schema before the task fails.
+pub(crate) const MAX_REASKS: u32 = 2;
+
+/// Appended to the instructions, for providers that do not enforce the
+/// schema.
+pub(crate) fn instructions(format: &ResponseFormat) -> String {
+    format!(
+        "When you are done, your final message must be a single JSON value that matches the JSON Schema below, with no code fences or other text around it.\n{}",
+        format.schema
+    )
+}
+
+/// Sent to the model when its final message does not match the schema.
+pub(crate) fn reask_message(violation: &str) -> String {
+    format!(
+        "Your final message does not match the required JSON Schema: {violation}. Reply again with only a JSON value that matches the schema."
+    )
+}
+
+/// Check the agent's final message against `format`, describing the first
+/// mismatch found.
+pub(crate) fn check_final_message(
+    format: &ResponseFormat,
+    message: Option<&str>,
+) -> Result<(), String> {
+    let message = message.ok_or("there is no final message")?;
+    let value: Value = serde_json::from_str(message.trim())
+        .map_err(|e| format!("the final message is not valid JSON: {e}"))?;
+    validate(&format.schema, &value, "$")
+}
+
+/// Check `value` against `schema`, describing the first mismatch found with
+/// `path` naming where `value` sits, e.g. `$`.
+pub(crate) fn validate(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        // `true` accepts anything and `false` nothing.
+        return match schema {
+            Value::Bool(false) => Err(format!("{path} is not allowed")),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(ty) => has_type(value, ty),
+            Value::Array(types) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|ty| has_type(value, ty)),
+            _ => true,
+        };
+        if !matches {
+            let expected = match expected {
+                Value::Array(types) => types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                other => other.as_str().unwrap_or_default().to_string(),
+            };
+            return Err(format!(
+                "{path} should be of type {expected}, not {}",
+                type_name(value)
+            ));
+        }
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed = allowed
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(format!("{path} should be one of {allowed}"));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            return Err(format!("{path} should be {expected}"));
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("anyOf") {
+        if !options
+            .iter()
+            .any(|option| validate(option, value, path).is_ok())
+        {
+            return Err(format!("{path} matches none of the schemas in anyOf"));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                if let Some(key) = required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .find(|key| !object.contains_key(*key))
+                {
+                    return Err(format!("{path} is missing the required property `{key}`"));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in object {
+                let item_schema = properties
+                    .and_then(|properties| properties.get(key))
+                    .or_else(|| schema.get("additionalProperties"));
+                if let Some(item_schema) = item_schema {
+                    validate(item_schema, item, &format!("{path}.{key}"))?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &format!("{path}[{index}]"))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use serde_json::json;
+
+    fn format() -> ResponseFormat {
+        ResponseFormat {
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "status": { "enum": ["pass", "fail"] },
+                    "failures": { "type": "integer" },
+                    "files": { "type": "array", "items": { "type": "string" } },
+                    "note": { "type": ["string", "null"] },
+                },
+                "required": ["status", "failures"],
+                "additionalProperties": false,
+            }),
+        }
+    }
+
+    fn check(message: &str) -> Result<(), String> {
+        check_final_message(&format(), Some(message))
+    }
+
+    #[test]
+    fn accepts_matching_messages() {
+        assert_eq!(check(r#"{"status":"pass","failures":0}"#), Ok(()));
+        assert_eq!(
+            check(r#" {"status":"fail","failures":2.0,"files":["a.rs"],"note":null} "#),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn describes_the_first_mismatch() {
+        assert_eq!(
+            check_final_message(&format(), None),
+            Err("there is no final message".to_string())
+        );
+        assert!(
+            check("Here you go: {}")
+                .unwrap_err()
+                .starts_with("the final message is not valid JSON")
+        );
+        assert_eq!(
+            check(r#"["pass"]"#),
+            Err("$ should be of type object, not array".to_string())
+        );
+        assert_eq!(
+            check(r#"{"status":"pass"}"#),
+            Err("$ is missing the required property `failures`".to_string())
+        );
+        assert_eq!(
+            check(r#"{"status":"ok","failures":0}"#),
+            Err(r#"$.status should be one of "pass", "fail""#.to_string())
+        );
+        assert_eq!(
+            check(r#"{"status":"fail","failures":1.5}"#),
+            Err("$.failures should be of type integer, not number".to_string())
+        );
+        assert_eq!(
+            check(r#"{"status":"fail","failures":1,"files":["a.rs",3]}"#),
+            Err("$.files[1] should be of type string, not number".to_string())
+        );
+        assert_eq!(
+            check(r#"{"status":"fail","failures":1,"note":1}"#),
+            Err("$.note should be of type string or null, not number".to_string())
+        );
+        assert_eq!(
+            check(r#"{"status":"pass","failures":0,"extra":true}"#),
+            Err("$.extra is not allowed".to_string())
+        );
+    }
+}
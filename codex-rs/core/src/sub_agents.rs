@@ -0,0 +1,308 @@
+//! Sub-agents the model can hand independent subtasks to through the
+//! `spawn_agents` tool.
+//!
+//! Each sub-agent is its own [`Codex`] with a scoped prompt and sandbox. The
+//! sub-agents of one call run concurrently, their events are forwarded to the
+//! front-end as [`EventMsg::SubAgent`], and their final reports are merged
+//! into a single tool output for the main agent.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::future::join_all;
+use serde::Deserialize;
+use tokio::sync::Notify;
+
+use crate::Codex;
+use crate::config::Config;
+use crate::protocol::AskForApproval;
+use crate::protocol::ErrorEvent;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::SessionMode;
+use crate::protocol::SubAgentEvent;
+use crate::protocol::TaskCompleteEvent;
+
+pub(crate) const SPAWN_AGENTS_TOOL_NAME: &str = "spawn_agents";
+
+/// Sent ahead of every subtask so the sub-agent reports back instead of
+/// waiting for a user who is not there.
+const SUB_AGENT_PREAMBLE: &str = "You are a sub-agent working on one part of a larger task. \
+Work only on the subtask below and do not ask questions: nobody will answer them. When you are \
+done, reply with a concise report of what you found or changed.";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpawnAgentsArgs {
+    pub(crate) tasks: Vec<SubAgentTask>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SubAgentTask {
+    prompt: String,
+    #[serde(default)]
+    sandbox: SubAgentSandbox,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SubAgentSandbox {
+    #[default]
+    ReadOnly,
+    /// Same sandbox as the session that spawned the sub-agent. Only for
+    /// sessions that never ask for approval either, since sub-agents cannot.
+    Inherit,
+}
+
+/// What a sub-agent inherits from the session that spawns it.
+pub(crate) struct ParentSession<'a> {
+    pub(crate) config: &'a Config,
+    pub(crate) model: &'a str,
+    pub(crate) cwd: &'a Path,
+    pub(crate) sandbox_policy: &'a SandboxPolicy,
+    pub(crate) approval_policy: AskForApproval,
+    pub(crate) mode: SessionMode,
+}
+
+impl ParentSession<'_> {
+    /// Sub-agents never ask for approval, never widen the sandbox, cannot
+    /// start sub-agents of their own, do not start MCP servers and are not
+    /// listed for `codex resume`. The sub-agents of a session in ask mode are
+    /// in ask mode too.
+    fn sub_agent_config(&self, sandbox: SubAgentSandbox) -> Config {
+        let mut config = self.config.clone();
+        config.model = self.model.to_string();
+        config.cwd = self.cwd.to_path_buf();
+        config.approval_policy = AskForApproval::Never;
+        config.sandbox_policy = match sandbox {
+            SubAgentSandbox::ReadOnly => SandboxPolicy::new_read_only_policy(),
+            SubAgentSandbox::Inherit => self.sandbox_policy.clone(),
+        };
+        config.mode = self.mode;
+        config.max_sub_agents = 0;
+        config.mcp_servers.clear();
+        config.notify = None;
+        config.resume_from = None;
+        config.record_rollout = false;
+        config
+    }
+}
+
+/// Run the sub-agents requested by one `spawn_agents` call and return the
+/// merged report. `call_seq` numbers the call within the session so that
+/// agent ids stay unique; `forward` receives every namespaced event.
+pub(crate) async fn run_sub_agents(
+    parent: ParentSession<'_>,
+    call_seq: u64,
+    args: SpawnAgentsArgs,
+    forward: impl Fn(SubAgentEvent) + Sync,
+) -> Result<String, String> {
+    if args.tasks.is_empty() {
+        return Err("spawn_agents needs at least one task".to_string());
+    }
+    let max = parent.config.max_sub_agents;
+    if args.tasks.len() > max {
+        return Err(format!(
+            "spawn_agents accepts at most {max} tasks per call, got {}; split the work into \
+             fewer tasks or several calls",
+            args.tasks.len()
+        ));
+    }
+    // A sub-agent runs what its parent would have asked the user about.
+    if parent.approval_policy != AskForApproval::Never
+        && args
+            .tasks
+            .iter()
+            .any(|task| task.sandbox == SubAgentSandbox::Inherit)
+    {
+        return Err(
+            "sub-agents cannot ask for approval, so they can only inherit the sandbox of a \
+             session whose approval policy is `never`; use the `read-only` sandbox"
+                .to_string(),
+        );
+    }
+
+    let runs = args.tasks.iter().enumerate().map(|(idx, task)| {
+        let agent_id = format!("{call_seq}.{}", idx + 1);
+        let config = parent.sub_agent_config(task.sandbox);
+        let forward = &forward;
+        async move {
+            let on_event = |msg: EventMsg| {
+                forward(SubAgentEvent {
+                    agent_id: agent_id.clone(),
+                    task: task.prompt.clone(),
+                    msg: Box::new(msg),
+                })
+            };
+            run_sub_agent(config, &task.prompt, on_event).await
+        }
+    });
+    let results = join_all(runs).await;
+
+    Ok(merge_reports(&args.tasks, results))
+}
+
+fn merge_reports(tasks: &[SubAgentTask], results: Vec<Result<Option<String>, String>>) -> String {
+    tasks
+        .iter()
+        .zip(results)
+        .enumerate()
+        .map(|(idx, (task, result))| {
+            let report = match result {
+                Ok(Some(message)) => message,
+                Ok(None) => "(finished without a report)".to_string(),
+                Err(e) => format!("(failed: {e})"),
+            };
+            format!("## Sub-agent {}: {}\n\n{report}", idx + 1, task.prompt)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Drive a single sub-agent until its task completes. Returns its last
+/// message.
+async fn run_sub_agent(
+    config: Config,
+    prompt: &str,
+    on_event: impl Fn(EventMsg),
+) -> Result<Option<String>, String> {
+    let (codex, _init_id) = Codex::spawn(config, Arc::new(Notify::new()))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut handle = SubAgentHandle {
+        codex: Arc::new(codex),
+        finished: false,
+    };
+    handle
+        .codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: format!("{SUB_AGENT_PREAMBLE}\n\nSubtask: {prompt}"),
+            }],
+            response_format: None,
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        let event = handle.codex.next_event().await.map_err(|e| e.to_string())?;
+        match event.msg {
+            EventMsg::SessionConfigured(_)
+            | EventMsg::AgentMessageDelta(_)
+            | EventMsg::AgentReasoning(_)
+            | EventMsg::AgentReasoningDelta(_) => {}
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message,
+                stats,
+            }) => {
+                handle.finished = true;
+                on_event(EventMsg::TaskComplete(TaskCompleteEvent {
+                    last_agent_message: last_agent_message.clone(),
+                    stats,
+                }));
+                return Ok(last_agent_message);
+            }
+            EventMsg::Error(ErrorEvent { message }) => {
+                // A failed turn ends the task without a `TaskComplete`.
+                handle.finished = true;
+                on_event(EventMsg::Error(ErrorEvent {
+                    message: message.clone(),
+                }));
+                return Err(message);
+            }
+            msg => on_event(msg),
+        }
+    }
+}
+
+/// Interrupts the sub-agent if its driver is dropped before the task
+/// finished, e.g. because the main agent's turn was interrupted.
+struct SubAgentHandle {
+    codex: Arc<Codex>,
+    finished: bool,
+}
+
+impl Drop for SubAgentHandle {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let codex = Arc::clone(&self.codex);
+        tokio::spawn(async move {
+            codex
+                .submit(Op::Interrupt { replace_with: None })
+                .await
+                .ok();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_tasks_with_default_sandbox() {
+        let args: SpawnAgentsArgs = serde_json::from_str(
+            r#"{"tasks":[{"prompt":"read the docs"},{"prompt":"fix tests","sandbox":"inherit"}]}"#,
+        )
+        .unwrap();
+        let sandboxes: Vec<_> = args.tasks.iter().map(|t| t.sandbox).collect();
+        assert_eq!(
+            sandboxes,
+            vec![SubAgentSandbox::ReadOnly, SubAgentSandbox::Inherit]
+        );
+    }
+
+    #[tokio::test]
+    async fn inherit_needs_a_parent_that_never_asks() {
+        let codex_home = TempDir::new().unwrap();
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap();
+        let args: SpawnAgentsArgs =
+            serde_json::from_str(r#"{"tasks":[{"prompt":"fix tests","sandbox":"inherit"}]}"#)
+                .unwrap();
+        let parent = ParentSession {
+            config: &config,
+            model: "o3",
+            cwd: codex_home.path(),
+            sandbox_policy: &config.sandbox_policy,
+            approval_policy: AskForApproval::OnFailure,
+            mode: SessionMode::Code,
+        };
+
+        let err = run_sub_agents(parent, 1, args, |_| {}).await.unwrap_err();
+        assert!(err.contains("approval policy is `never`"), "{err}");
+    }
+
+    #[test]
+    fn merges_reports_in_task_order() {
+        let tasks = vec![
+            SubAgentTask {
+                prompt: "a".to_string(),
+                sandbox: SubAgentSandbox::ReadOnly,
+            },
+            SubAgentTask {
+                prompt: "b".to_string(),
+                sandbox: SubAgentSandbox::ReadOnly,
+            },
+        ];
+        let merged = merge_reports(
+            &tasks,
+            vec![Ok(Some("done".to_string())), Err("boom".to_string())],
+        );
+        assert_eq!(
+            merged,
+            "## Sub-agent 1: a\n\ndone\n\n## Sub-agent 2: b\n\n(failed: boom)"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! `codex test-fix`: run the project's test command and, while it fails,
+//! give the failure to the agent to fix, until the tests pass or the
+//! iteration budget runs out.
+//!
+//! The front-end drives the loop; this module runs the tests, words the
+//! request sent after each failing run and tallies the report.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use tokio::sync::Notify;
+
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::SandboxErr;
+use crate::exec::ExecParams;
+use crate::exec::SandboxType;
+use crate::exec::process_exec_tool_call;
+use crate::exec_env::create_env;
+use crate::safety::get_platform_sandbox;
+
+/// The end of the output of a failing run is sent; it usually holds the
+/// failures and the summary.
+const MAX_FAILURE_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Test suites take much longer than the commands the model runs.
+const TEST_TIMEOUT_MS: u64 = 60 * 60 * 1000;
+
+/// One run of the test command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestRun {
+    pub passed: bool,
+    /// Combined stdout and stderr.
+    pub output: String,
+    pub duration: Duration,
+}
+
+/// Run `command` with the shell in the session's directory, under the
+/// session's sandbox like the commands the agent runs.
+pub async fn run_tests(command: &str, config: &Config) -> Result<TestRun> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let params = ExecParams {
+        command: vec![shell.to_string(), flag.to_string(), command.to_string()],
+        cwd: config.cwd.clone(),
+        timeout_ms: Some(TEST_TIMEOUT_MS),
+        env: create_env(&config.shell_environment_policy),
+    };
+    let sandbox_type = if config.sandbox_policy.is_unrestricted() {
+        SandboxType::None
+    } else {
+        get_platform_sandbox().unwrap_or(SandboxType::None)
+    };
+    let start = Instant::now();
+    let (passed, stdout, stderr) = match process_exec_tool_call(
+        params,
+        sandbox_type,
+        Arc::new(Notify::new()),
+        &config.sandbox_policy,
+        &config.codex_linux_sandbox_exe,
+        &config.linux_sandbox,
+        &config.macos_seatbelt,
+    )
+    .await
+    {
+        Ok(output) => (output.exit_code == 0, output.stdout, output.stderr),
+        // Under a sandbox, a failing command is reported as denied.
+        Err(CodexErr::Sandbox(SandboxErr::Denied(_, stdout, stderr))) => (false, stdout, stderr),
+        Err(e) => return Err(e),
+    };
+    Ok(TestRun {
+        passed,
+        output: stdout + &stderr,
+        duration: start.elapsed(),
+    })
+}
+
+/// What the agent is asked after `run` of `command` failed. `instructions`,
+/// if not empty, are the user's own and come first.
+pub fn fix_request(command: &str, run: &TestRun, instructions: &str) -> String {
+    let mut output = run.output.trim_end();
+    if output.len() > MAX_FAILURE_OUTPUT_BYTES {
+        let mut start = output.len() - MAX_FAILURE_OUTPUT_BYTES;
+        while !output.is_char_boundary(start) {
+            start += 1;
+        }
+        output = &output[start..];
+    }
+    let mut request = String::new();
+    if !instructions.trim().is_empty() {
+        request.push_str(instructions.trim());
+        request.push_str("\n\n");
+    }
+    request.push_str(&format!(
+        "The test command `{command}` fails. Fix the code so that it passes, without weakening or skipping tests. It is run again once you finish.\n\nEnd of its output:\n```\n{output}\n```"
+    ));
+    request
+}
+
+/// Outcome of a `codex test-fix` run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestFixReport {
+    /// Every test run, the first one before any fix.
+    pub runs: Vec<TestRun>,
+    /// Times the agent was asked for a fix.
+    pub attempts: usize,
+}
+
+impl TestFixReport {
+    pub fn passed(&self) -> bool {
+        self.runs.last().is_some_and(|run| run.passed)
+    }
+
+    /// One line per run and a verdict.
+    pub fn render(&self) -> String {
+        let mut report = String::new();
+        for (index, run) in self.runs.iter().enumerate() {
+            let status = if run.passed { "passed" } else { "failed" };
+            report.push_str(&format!(
+                "{}: {status} in {:.1}s\n",
+                run_label(index),
+                run.duration.as_secs_f64()
+            ));
+        }
+        report.push_str(&match (self.passed(), self.attempts) {
+            (true, 0) => "The tests already pass.".to_string(),
+            (true, attempts) => format!("The tests pass after {attempts} fix attempt(s)."),
+            (false, attempts) => format!("The tests still fail after {attempts} fix attempt(s)."),
+        });
+        report
+    }
+}
+
+/// Names the run at `index` of [`TestFixReport::runs`].
+pub fn run_label(index: usize) -> String {
+    if index == 0 {
+        "initial run".to_string()
+    } else {
+        format!("after fix {index}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use crate::protocol::SandboxPermission;
+    use crate::protocol::SandboxPolicy;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn run(passed: bool) -> TestRun {
+        TestRun {
+            passed,
+            output: String::new(),
+            duration: Duration::from_millis(1500),
+        }
+    }
+
+    fn config(dir: &TempDir, sandbox_policy: SandboxPolicy) -> Config {
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides {
+                cwd: Some(dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            dir.path().to_path_buf(),
+        )
+        .unwrap();
+        config.sandbox_policy = sandbox_policy;
+        config
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_the_command_with_the_shell() {
+        let dir = TempDir::new().unwrap();
+        let config = config(
+            &dir,
+            SandboxPolicy::from(vec![
+                SandboxPermission::DiskFullReadAccess,
+                SandboxPermission::DiskFullWriteAccess,
+                SandboxPermission::NetworkFullAccess,
+            ]),
+        );
+        let passing = run_tests("echo ok", &config).await.unwrap();
+        assert!(passing.passed);
+        assert_eq!(passing.output, "ok\n");
+        let failing = run_tests("echo broken >&2; exit 3", &config).await.unwrap();
+        assert!(!failing.passed);
+        assert_eq!(failing.output, "broken\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn runs_the_command_in_the_sandbox() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, SandboxPolicy::new_read_only_policy());
+        // No sandbox executable is configured, so the command cannot start.
+        assert!(matches!(
+            run_tests("echo ok", &config).await,
+            Err(CodexErr::LandlockSandboxExecutableNotProvided)
+        ));
+    }
+
+    #[test]
+    fn the_request_keeps_the_end_of_the_output() {
+        let failing = TestRun {
+            output: format!(
+                "{}test result: FAILED\n",
+                "x".repeat(MAX_FAILURE_OUTPUT_BYTES)
+            ),
+            ..run(false)
+        };
+        let request = fix_request("cargo test", &failing, "Only touch src/.");
+        assert!(request.starts_with("Only touch src/.\n\nThe test command `cargo test` fails."));
+        assert!(request.ends_with("test result: FAILED\n```"), "{request}");
+        assert!(request.len() < MAX_FAILURE_OUTPUT_BYTES + 400);
+    }
+
+    #[test]
+    fn reports_each_run() {
+        let report = TestFixReport {
+            runs: vec![run(false), run(false), run(true)],
+            attempts: 2,
+        };
+        assert_eq!(
+            report.render(),
+            "initial run: failed in 1.5s\nafter fix 1: failed in 1.5s\nafter fix 2: passed in 1.5s\nThe tests pass after 2 fix attempt(s)."
+        );
+        // The agent's turn failed, so the tests were not run again.
+        let report = TestFixReport {
+            runs: vec![run(false)],
+            attempts: 1,
+        };
+        assert!(!report.passed());
+        assert!(
+            report
+                .render()
+                .ends_with("still fail after 1 fix attempt(s).")
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Credentials persisted as JSON files in the Codex home directory, readable
+//! and writable by the current user only. Each sign-in flow keeps its tokens
+//! in its own file: `copilot_token.json` for the Copilot device flow and
+//! `auth.json` for the ChatGPT login.
+//!
+//! Several Codex processes may refresh the same token at once, so access goes
+//! through an advisory lock on a `.lock` file next to the token file, and
+//! writes replace the file atomically: the tokens are written to a temporary
+//! file that is then renamed over the old one. A refresh holds the exclusive
+//! lock from reading the old tokens to saving the new ones, so only one
+//! process sends the refresh request; the others wait for its result. Async
+//! callers take that lock with [`TokenFile::lock_for_refresh`], which waits
+//! without blocking the runtime.
+
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use dirs::home_dir;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// How often to try for the lock, or to re-read a token file that does not
+/// parse, before giving up.
+const MAX_RETRIES: usize = 20;
+const RETRY_SLEEP: Duration = Duration::from_millis(50);
+
+/// How often [`TokenFile::lock_for_refresh`] tries for the lock: long enough
+/// for another process to finish a refresh request.
+const REFRESH_LOCK_RETRIES: usize = 600;
+
+/// Outcome of one attempt at reading a token file.
+enum ReadAttempt<T> {
+    Done(Option<T>),
+    /// The file is half-written; read it again shortly.
+    Retry,
+}
+
+pub(crate) struct TokenFile {
+    path: PathBuf,
+}
+
+impl TokenFile {
+    pub(crate) fn new(codex_home: &Path, name: &str) -> Self {
+        Self {
+            path: codex_home.join(name),
+        }
+    }
+
+    /// The file `name` in `CODEX_HOME` (default `~/.codex`), which is created
+    /// if needed.
+    pub(crate) fn in_codex_home(name: &str) -> Result<Self> {
+        let codex_home = std::env::var("CODEX_HOME")
+            .ok()
+            .filter(|val| !val.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".codex")))
+            .context("Could not determine Codex home directory")?;
+        fs::create_dir_all(&codex_home).context("Failed to create Codex home directory")?;
+        Ok(Self::new(&codex_home, name))
+    }
+
+    pub(crate) fn load<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let _lock = self.lock(false)?;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.read_attempt(attempt)? {
+                ReadAttempt::Done(value) => return Ok(value),
+                ReadAttempt::Retry => std::thread::sleep(RETRY_SLEEP),
+            }
+        }
+    }
+
+    /// Read the file once. Writers from before the lock was introduced write
+    /// in place, so the file may be caught half-written: it is read again
+    /// until it parses, up to [`MAX_RETRIES`] times.
+    fn read_attempt<T: DeserializeOwned>(&self, attempt: usize) -> Result<ReadAttempt<T>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(ReadAttempt::Done(None));
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()));
+            }
+        };
+        match serde_json::from_str(&content) {
+            Ok(value) => Ok(ReadAttempt::Done(Some(value))),
+            Err(e) if e.is_eof() && attempt < MAX_RETRIES => Ok(ReadAttempt::Retry),
+            Err(e) => Err(e).with_context(|| format!("Failed to parse {}", self.path.display())),
+        }
+    }
+
+    pub(crate) fn save<T: Serialize>(&self, value: &T) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = self.lock(true)?;
+        self.write(value)
+    }
+
+    /// Replace the file with `value`. The caller holds the exclusive lock.
+    fn write<T: Serialize>(&self, value: &T) -> Result<()> {
+        let json = serde_json::to_string_pretty(value).context("Failed to serialize token")?;
+        let tmp_path = self.sibling(&format!("tmp.{}", std::process::id()));
+        let write = || -> std::io::Result<()> {
+            let mut file = owner_only_options()
+                .write(true)
+                .truncate(true)
+                .open(&tmp_path)?;
+            file.write_all(json.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &self.path)
+        };
+        write()
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&tmp_path);
+            })
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    pub(crate) fn clear(&self) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let _lock = self.lock(true)?;
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", self.path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// `<name>.<suffix>` next to the token file.
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(suffix);
+        self.path.with_file_name(name)
+    }
+
+    /// Take the exclusive lock for a refresh: the returned guard reads the
+    /// tokens and saves their replacement while other processes wait.
+    /// `Ok(None)` when there is no token file.
+    pub(crate) async fn lock_for_refresh(&self) -> Result<Option<LockedTokenFile<'_>>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let lock = self.open_lock_file()?;
+        for _ in 0..REFRESH_LOCK_RETRIES {
+            if self.try_lock(&lock, true)? {
+                return Ok(Some(LockedTokenFile {
+                    token_file: self,
+                    _lock: lock,
+                }));
+            }
+            tokio::time::sleep(RETRY_SLEEP).await;
+        }
+        anyhow::bail!("{} is locked by another Codex process", self.path.display())
+    }
+
+    /// Take the advisory lock, shared for reading or exclusive for writing.
+    /// It is released when the returned file is dropped.
+    fn lock(&self, exclusive: bool) -> Result<File> {
+        let lock = self.open_lock_file()?;
+        for _ in 0..MAX_RETRIES {
+            if self.try_lock(&lock, exclusive)? {
+                return Ok(lock);
+            }
+            std::thread::sleep(RETRY_SLEEP);
+        }
+        anyhow::bail!("{} is locked by another Codex process", self.path.display())
+    }
+
+    fn open_lock_file(&self) -> Result<File> {
+        let lock_path = self.sibling("lock");
+        owner_only_options()
+            .write(true)
+            .truncate(false)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))
+    }
+
+    /// Try once for the lock on `lock`; `false` if another process holds it.
+    fn try_lock(&self, lock: &File, exclusive: bool) -> Result<bool> {
+        let locked = if exclusive {
+            fs2::FileExt::try_lock_exclusive(lock)
+        } else {
+            fs2::FileExt::try_lock_shared(lock)
+        };
+        match locked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("Failed to lock {}", self.path.display())),
+        }
+    }
+}
+
+/// A token file under the exclusive lock, taken with
+/// [`TokenFile::lock_for_refresh`]. The lock is released on drop.
+pub(crate) struct LockedTokenFile<'a> {
+    token_file: &'a TokenFile,
+    _lock: File,
+}
+
+impl LockedTokenFile<'_> {
+    pub(crate) async fn load<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.token_file.read_attempt(attempt)? {
+                ReadAttempt::Done(value) => return Ok(value),
+                ReadAttempt::Retry => tokio::time::sleep(RETRY_SLEEP).await,
+            }
+        }
+    }
+
+    pub(crate) fn save<T: Serialize>(&self, value: &T) -> Result<()> {
+        self.token_file.write(value)
+    }
+}
+
+/// Options that create a file readable and writable by its owner only.
+fn owner_only_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn save_waits_for_the_lock_and_replaces_the_file() {
+        let codex_home = TempDir::new().unwrap();
+        let token_file = TokenFile::new(codex_home.path(), "token.json");
+        token_file.save(&"first").unwrap();
+
+        let lock = token_file.lock(true).unwrap();
+        let writer = std::thread::spawn({
+            let codex_home = codex_home.path().to_path_buf();
+            move || TokenFile::new(&codex_home, "token.json").save(&"second")
+        });
+        std::thread::sleep(RETRY_SLEEP * 2);
+        assert_eq!(
+            fs::read_to_string(codex_home.path().join("token.json")).unwrap(),
+            "\"first\""
+        );
+        drop(lock);
+        writer.join().unwrap().unwrap();
+
+        assert_eq!(
+            token_file.load::<String>().unwrap().as_deref(),
+            Some("second")
+        );
+        // Only the token file and its lock file remain.
+        let mut names: Vec<_> = fs::read_dir(codex_home.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["token.json", "token.json.lock"]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(codex_home.path().join("token.json"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_the_lock_from_load_to_save() {
+        let codex_home = TempDir::new().unwrap();
+        let token_file = TokenFile::new(codex_home.path(), "token.json");
+        token_file.save(&"old").unwrap();
+
+        let locked = token_file.lock_for_refresh().await.unwrap().unwrap();
+        assert_eq!(
+            locked.load::<String>().await.unwrap().as_deref(),
+            Some("old")
+        );
+        // Another process can neither read nor write until the refreshed
+        // token is saved.
+        let other = TokenFile::new(codex_home.path(), "token.json");
+        let other_lock = other.open_lock_file().unwrap();
+        assert!(!other.try_lock(&other_lock, false).unwrap());
+        locked.save(&"new").unwrap();
+        drop(locked);
+
+        assert!(other.try_lock(&other_lock, false).unwrap());
+        drop(other_lock);
+        assert_eq!(other.load::<String>().unwrap().as_deref(), Some("new"));
+        assert!(
+            TokenFile::new(codex_home.path(), "missing.json")
+                .lock_for_refresh()
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn load_rereads_a_half_written_file() {
+        let codex_home = TempDir::new().unwrap();
+        let path = codex_home.path().join("token.json");
+        fs::write(&path, r#"{"token": "ab"#).unwrap();
+
+        let writer = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                std::thread::sleep(RETRY_SLEEP * 2);
+                fs::write(path, r#"{"token": "abc"}"#).unwrap();
+            }
+        });
+        let value: serde_json::Value = TokenFile::new(codex_home.path(), "token.json")
+            .load()
+            .unwrap()
+            .unwrap();
+        writer.join().unwrap();
+        assert_eq!(value["token"], "abc");
+    }
+}
@@ -0,0 +1,68 @@
+//! Checking the arguments of a tool call against the JSON Schema of the
+//! tool's parameters before running it. A call that does not match is not
+//! run; its output tells the model what to fix, so it can call the tool
+//! again instead of the tool failing on arguments it cannot make sense of.
+//!
+//! The checks are those of [`crate::structured_output`].
+
+use serde_json::Value;
+
+use crate::structured_output::validate;
+
+/// How many calls in a row the model is asked to correct before calls with
+/// arguments that do not match are run anyway, leaving the tool to report
+/// the problem.
+pub(crate) const MAX_TOOL_CALL_REPAIRS: u32 = 3;
+
+/// Check the raw `arguments` of a call against the tool's `parameters`,
+/// describing the first mismatch found.
+pub(crate) fn check_arguments(parameters: &Value, arguments: &str) -> Result<(), String> {
+    let value: Value = serde_json::from_str(arguments)
+        .map_err(|e| format!("the arguments are not valid JSON: {e}"))?;
+    validate(parameters, &value, "$")
+}
+
+/// Output of a call that was not run because its arguments do not match.
+pub(crate) fn repair_message(tool: &str, violation: &str) -> String {
+    format!(
+        "The call was not run: its arguments do not match the parameters of `{tool}`: {violation}. Call `{tool}` again with corrected arguments."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn describes_what_to_fix() {
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "array", "items": { "type": "string" } },
+                "timeout": { "type": "number" },
+            },
+            "required": ["command"],
+            "additionalProperties": false,
+        });
+        assert_eq!(
+            check_arguments(&parameters, r#"{"command":["ls","-l"],"timeout":1000}"#),
+            Ok(())
+        );
+        assert_eq!(
+            check_arguments(&parameters, r#"{"command":"ls -l"}"#),
+            Err("$.command should be of type array, not string".to_string())
+        );
+        assert_eq!(
+            check_arguments(&parameters, r#"{"cmd":["ls"]}"#),
+            Err("$ is missing the required property `command`".to_string())
+        );
+        assert!(
+            check_arguments(&parameters, r#"{"command":["ls"]"#)
+                .unwrap_err()
+                .starts_with("the arguments are not valid JSON")
+        );
+    }
+}
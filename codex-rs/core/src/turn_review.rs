@@ -0,0 +1,199 @@
+//! Turn-level review of file edits (`review_edits = "per-turn"`).
+//!
+//! Patches are still written to disk as they come, so the agent can build and
+//! test its work, but the content every file had before its first edit is kept
+//! in a staging area. When the task ends, the net changes are presented as one
+//! diff and the files the user rejects are restored.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::ApplyPatchFileChange;
+use similar::TextDiff;
+
+use crate::protocol::FileChange;
+
+/// Lines of context around each hunk of the review diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Content of each file edited since the last review, as it was before the
+/// first edit. `None` marks a file that did not exist.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct StagedEdits {
+    originals: BTreeMap<PathBuf, Option<Vec<u8>>>,
+}
+
+impl StagedEdits {
+    /// Remember the current content of every file `action` is about to touch.
+    /// Call before the patch is applied.
+    pub(crate) fn stage(&mut self, action: &ApplyPatchAction) {
+        for (path, change) in action.changes() {
+            self.record(path);
+            if let ApplyPatchFileChange::Update {
+                move_path: Some(dest),
+                ..
+            } = change
+            {
+                self.record(dest);
+            }
+        }
+    }
+
+    fn record(&mut self, path: &Path) {
+        if self.originals.contains_key(path) {
+            return;
+        }
+        let original = match std::fs::read(path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            // A file we cannot read cannot be restored either, so it is left
+            // out of the review.
+            Err(e) => {
+                tracing::warn!("not staging {}: {e}", path.display());
+                return;
+            }
+        };
+        self.originals.insert(path.to_path_buf(), original);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    /// Net change of every staged file compared with its content on disk now.
+    /// Files that ended up unchanged are left out.
+    pub(crate) fn changes(&self) -> HashMap<PathBuf, FileChange> {
+        self.originals
+            .iter()
+            .filter_map(|(path, original)| {
+                let current = std::fs::read(path).ok();
+                let change = match (original, current) {
+                    (None, None) => return None,
+                    (Some(old), Some(new)) if *old == new => return None,
+                    (None, Some(content)) => FileChange::Add {
+                        content: String::from_utf8_lossy(&content).into_owned(),
+                    },
+                    (Some(_), None) => FileChange::Delete,
+                    (Some(old), Some(new)) => FileChange::Update {
+                        unified_diff: unified_diff(old, &new),
+                        move_path: None,
+                    },
+                };
+                Some((path.clone(), change))
+            })
+            .collect()
+    }
+
+    /// Restore every changed file that is not in `approved` and return the
+    /// restored paths.
+    pub(crate) fn revert_rejected(self, approved: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+        let mut reverted = Vec::new();
+        for (path, original) in self.originals {
+            if approved.contains(&path) {
+                continue;
+            }
+            let current = std::fs::read(&path).ok();
+            if current == original {
+                continue;
+            }
+            match original {
+                Some(content) => {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&path, content)?;
+                }
+                None => std::fs::remove_file(&path)?,
+            }
+            reverted.push(path);
+        }
+        Ok(reverted)
+    }
+}
+
+fn unified_diff(old: &[u8], new: &[u8]) -> String {
+    let old = String::from_utf8_lossy(old);
+    let new = String::from_utf8_lossy(new);
+    TextDiff::from_lines(old.as_ref(), new.as_ref())
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .to_string()
+}
+
+/// Message that tells the model which of its changes the user rejected.
+pub(crate) fn rejection_note(cwd: &Path, reverted: &[PathBuf]) -> String {
+    let files = reverted
+        .iter()
+        .map(|path| {
+            let path = path.strip_prefix(cwd).unwrap_or(path);
+            format!("- {}", path.display())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "The user reviewed your changes and rejected the ones to these files, which have been \
+         restored to their previous content:\n{files}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn reviews_net_changes_and_reverts_rejected_files() {
+        let dir = TempDir::new().unwrap();
+        let kept = dir.path().join("kept.txt");
+        let edited = dir.path().join("edited.txt");
+        let added = dir.path().join("new/added.txt");
+        let untouched = dir.path().join("untouched.txt");
+        std::fs::write(&kept, "one\n").unwrap();
+        std::fs::write(&edited, "two\n").unwrap();
+        std::fs::write(&untouched, "same\n").unwrap();
+
+        let mut staged = StagedEdits::default();
+        for path in [&kept, &edited, &added, &untouched] {
+            staged.record(path);
+        }
+        // A second edit of the same file keeps the first original.
+        std::fs::write(&edited, "intermediate\n").unwrap();
+        staged.record(&edited);
+        std::fs::write(&kept, "one!\n").unwrap();
+        std::fs::write(&edited, "two!\n").unwrap();
+        std::fs::create_dir_all(added.parent().unwrap()).unwrap();
+        std::fs::write(&added, "hello\n").unwrap();
+
+        let changes = staged.changes();
+        assert_eq!(changes.len(), 3);
+        match &changes[&edited] {
+            FileChange::Update { unified_diff, .. } => {
+                assert!(unified_diff.contains("-two\n+two!\n"), "{unified_diff}");
+            }
+            other => panic!("unexpected change {other:?}"),
+        }
+        assert!(matches!(changes[&added], FileChange::Add { .. }));
+        assert!(!changes.contains_key(&untouched));
+
+        let mut reverted = staged.revert_rejected(std::slice::from_ref(&kept)).unwrap();
+        reverted.sort();
+        assert_eq!(reverted, vec![edited.clone(), added.clone()]);
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "one!\n");
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "two\n");
+        assert!(!added.exists());
+    }
+
+    #[test]
+    fn rejection_note_lists_paths_relative_to_cwd() {
+        let note = rejection_note(
+            Path::new("/repo"),
+            &[PathBuf::from("/repo/src/lib.rs"), PathBuf::from("/tmp/x")],
+        );
+        assert!(note.ends_with(":\n- src/lib.rs\n- /tmp/x"));
+    }
+}
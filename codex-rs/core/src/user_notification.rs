@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use serde::Serialize;
 
 /// User can configure a program that will receive notifications. Each
@@ -16,6 +18,27 @@ pub(crate) enum UserNotification {
         /// The last message sent by the assistant in the turn.
         last_assistant_message: Option<String>,
     },
+
+    /// The agent is blocked until the user approves a command or a patch.
+    #[serde(rename_all = "kebab-case")]
+    ApprovalRequested {
+        turn_id: String,
+
+        /// Command awaiting approval, for command executions.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        command: Option<Vec<String>>,
+
+        /// Files the patch would modify, for patch applications.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        changed_files: Option<Vec<PathBuf>>,
+
+        /// Why the agent is asking, when it says.
+        reason: Option<String>,
+    },
+
+    /// The turn stopped because of an error.
+    #[serde(rename_all = "kebab-case")]
+    Error { turn_id: String, message: String },
 }
 
 #[cfg(test)]
@@ -38,4 +61,31 @@ mod tests {
             r#"{"type":"agent-turn-complete","turn-id":"12345","input-messages":["Rename `foo` to `bar` and update the callsites."],"last-assistant-message":"Rename complete and verified `cargo build` succeeds."}"#
         );
     }
+
+    #[test]
+    fn test_approval_and_error_notifications() {
+        let approval = UserNotification::ApprovalRequested {
+            turn_id: "7".to_string(),
+            command: Some(vec![
+                "rm".to_string(),
+                "-rf".to_string(),
+                "build".to_string(),
+            ]),
+            changed_files: None,
+            reason: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&approval).unwrap(),
+            r#"{"type":"approval-requested","turn-id":"7","command":["rm","-rf","build"],"reason":null}"#
+        );
+
+        let error = UserNotification::Error {
+            turn_id: "7".to_string(),
+            message: "stream disconnected".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"type":"error","turn-id":"7","message":"stream disconnected"}"#
+        );
+    }
 }
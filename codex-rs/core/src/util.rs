@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -64,3 +65,13 @@ pub fn is_inside_git_repo(config: &Config) -> bool {
 
     false
 }
+
+/// The distinct lowercase words of `text` at least `min_len` bytes long. A
+/// word is a run of alphanumeric characters, as SQLite's full-text index
+/// splits them.
+pub(crate) fn words(text: &str, min_len: usize) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && word.len() >= min_len)
+        .map(str::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,129 @@
+//! The directory the model's shell commands run in. Each command runs in a
+//! fresh shell, so a `cd` at the start of one would not carry over to the
+//! next; the session remembers where it led instead, so that
+//! `cd frontend && npm test` followed by a bare `npm run build` runs both in
+//! `frontend`, as in an interactive shell.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::protocol::SandboxPolicy;
+
+/// The directory the leading `cd`s of `command`, run in `cwd`, move to, if
+/// it starts with any. Only `cd DIR` followed by `&&`, `;` or nothing counts:
+/// a `cd` in a pipeline or before `||` may not apply to what follows.
+pub(crate) fn cd_target(command: &[String], cwd: &Path) -> Option<PathBuf> {
+    let words = match command {
+        [bash, flag, script] if bash == "bash" && flag == "-lc" => shlex::split(script)?,
+        _ => command.to_vec(),
+    };
+    let mut words = words.iter().map(String::as_str).peekable();
+    let mut dir = None;
+    while words.next_if_eq(&"cd").is_some() {
+        let Some(target) = words.next() else {
+            return dir;
+        };
+        let (target, separator) = match target.strip_suffix(';') {
+            Some(target) => (target, Some(";")),
+            None => (target, words.next()),
+        };
+        if target.is_empty() || target.starts_with(['-', '~', '$']) {
+            return dir;
+        }
+        if !matches!(separator, None | Some("&&" | ";")) {
+            return dir;
+        }
+        dir = Some(normalize(&dir.as_deref().unwrap_or(cwd).join(target)));
+    }
+    dir
+}
+
+/// Whether commands may be started in `dir`: it is a directory the sandbox
+/// lets them read.
+pub(crate) fn can_run_in(dir: &Path, policy: &SandboxPolicy, cwd: &Path) -> bool {
+    let Ok(dir) = dir.canonicalize() else {
+        return false;
+    };
+    if !dir.is_dir() {
+        return false;
+    }
+    if policy.has_full_disk_read_access() {
+        return true;
+    }
+    let mut readable_roots = policy.get_readable_roots();
+    if readable_roots.is_empty() {
+        readable_roots.push(cwd.to_path_buf());
+    }
+    readable_roots.extend(policy.get_writable_roots_with_cwd(cwd));
+    readable_roots.iter().any(|root| {
+        dir.starts_with(root) || root.canonicalize().is_ok_and(|root| dir.starts_with(root))
+    })
+}
+
+/// `path` without `.` and `..` components.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn target(command: &[&str]) -> Option<PathBuf> {
+        let command: Vec<String> = command.iter().map(|s| s.to_string()).collect();
+        cd_target(&command, Path::new("/work/repo"))
+    }
+
+    #[test]
+    fn follows_leading_cds() {
+        assert_eq!(
+            target(&["bash", "-lc", "cd frontend && npm test"]),
+            Some(PathBuf::from("/work/repo/frontend"))
+        );
+        assert_eq!(
+            target(&["bash", "-lc", "cd frontend; cd ../backend/src"]),
+            Some(PathBuf::from("/work/repo/backend/src"))
+        );
+        assert_eq!(target(&["cd", "/tmp"]), Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn ignores_cds_that_may_not_carry_over() {
+        assert_eq!(target(&["bash", "-lc", "npm test"]), None);
+        assert_eq!(target(&["bash", "-lc", "cd frontend || exit 1"]), None);
+        assert_eq!(target(&["bash", "-lc", "ls && cd frontend"]), None);
+        assert_eq!(target(&["bash", "-lc", "cd -"]), None);
+        assert_eq!(
+            target(&["bash", "-lc", "cd web && cd $DIR"]),
+            Some(PathBuf::from("/work/repo/web"))
+        );
+    }
+
+    #[test]
+    fn only_readable_directories_are_allowed() {
+        let dir = TempDir::new().unwrap();
+        let cwd = dir.path();
+        std::fs::create_dir(cwd.join("frontend")).unwrap();
+        let read_only = SandboxPolicy::new_read_only_policy();
+        let limited = SandboxPolicy::new_read_only_policy().with_reads_limited_to(cwd);
+
+        assert!(can_run_in(&cwd.join("frontend"), &limited, cwd));
+        assert!(!can_run_in(&cwd.join("missing"), &read_only, cwd));
+        assert!(!can_run_in(Path::new("/"), &limited, cwd));
+        assert!(can_run_in(Path::new("/"), &read_only, cwd));
+    }
+}
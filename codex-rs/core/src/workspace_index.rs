@@ -0,0 +1,209 @@
+//! In-memory index of the files in the workspace.
+//!
+//! The index is a flat list of paths relative to the workspace root, built by
+//! walking the tree while honoring `.gitignore` and friends. Front-ends use it
+//! to complete `@path` mentions, and the repository map, `codex explain` and
+//! semantic search list the workspace with it; it is cheap enough to rebuild
+//! on demand.
+//!
+//! `.codexignore` files, in `.gitignore` syntax, leave out files that are
+//! tracked but should never reach the model, such as generated code,
+//! fixtures and vendored dependencies.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use ignore::WalkBuilder;
+
+/// Ignore file, in `.gitignore` syntax, for files only Codex should skip.
+pub const CODEXIGNORE_FILE: &str = ".codexignore";
+
+/// Upper bound on the number of files indexed so that very large trees do not
+/// stall the UI.
+pub const DEFAULT_MAX_INDEXED_FILES: usize = 50_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    /// Paths relative to `root`, using `/` as the separator, sorted.
+    files: Vec<String>,
+}
+
+impl WorkspaceIndex {
+    /// Walk `root` and record every regular file that is not ignored.
+    pub fn build(root: &Path, max_files: usize) -> Self {
+        Self::build_within(root, root, max_files)
+    }
+
+    /// Like [`WorkspaceIndex::build`], but only walk `dir`, a directory under
+    /// `root` such as the `--focus` of the session. Paths stay relative to
+    /// `root`.
+    pub fn build_within(root: &Path, dir: &Path, max_files: usize) -> Self {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let prefix = canonical(dir)
+            .strip_prefix(canonical(root))
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut files = Vec::new();
+        let walker = WalkBuilder::new(dir)
+            .hidden(true)
+            .git_ignore(true)
+            .require_git(false)
+            .add_custom_ignore_filename(CODEXIGNORE_FILE)
+            .build();
+        for entry in walker.filter_map(Result::ok) {
+            if files.len() >= max_files {
+                break;
+            }
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            if let Ok(relative) = entry.path().strip_prefix(dir) {
+                files.push(prefix.join(relative).to_string_lossy().replace('\\', "/"));
+            }
+        }
+        files.sort();
+        Self {
+            root: root.to_path_buf(),
+            files,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The indexed paths, relative to the root and sorted.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(String::as_str)
+    }
+
+    /// Return up to `limit` indexed paths matching `query`, best match first.
+    ///
+    /// A path matches when the characters of `query` appear in it in order
+    /// (case-insensitive). Contiguous matches rank above scattered ones,
+    /// matches in the file name above matches in the directory, and shorter
+    /// paths above longer ones.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&str> {
+        let query = query.to_lowercase();
+        let mut scored: Vec<(i64, &str)> = self
+            .files
+            .iter()
+            .filter_map(|path| fuzzy_score(path, &query).map(|s| (s, path.as_str())))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored.into_iter().take(limit).map(|(_, p)| p).collect()
+    }
+}
+
+/// Score how well `path` matches the lowercase `query`, or `None` if the
+/// characters of `query` do not appear in it in order. Higher is better.
+pub fn fuzzy_score(path: &str, query: &str) -> Option<i64> {
+    let lower = path.to_lowercase();
+    let file_name_start = lower.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let length_penalty = lower.len() as i64;
+
+    if query.is_empty() {
+        return Some(-length_penalty);
+    }
+
+    if let Some(pos) = lower.rfind(query) {
+        let in_file_name = if pos >= file_name_start { 1_000 } else { 0 };
+        return Some(10_000 + in_file_name - length_penalty);
+    }
+
+    // Fall back to an in-order subsequence match.
+    let mut chars = lower.char_indices();
+    let mut gaps = 0i64;
+    let mut last: Option<usize> = None;
+    for qc in query.chars() {
+        let (idx, _) = chars.find(|(_, c)| *c == qc)?;
+        if let Some(last) = last {
+            gaps += (idx - last - 1) as i64;
+        }
+        last = Some(idx);
+    }
+    Some(-gaps * 10 - length_penalty)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    fn index_with(files: &[&str]) -> WorkspaceIndex {
+        WorkspaceIndex {
+            root: PathBuf::from("/ws"),
+            files: files.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn ranks_substring_matches_in_file_name_first() {
+        let index = index_with(&[
+            "docs/config.md",
+            "core/src/config.rs",
+            "config/mod.rs",
+            "core/src/codex.rs",
+        ]);
+        assert_eq!(
+            index.search("config", 10),
+            vec!["docs/config.md", "core/src/config.rs", "config/mod.rs"]
+        );
+        assert_eq!(index.search("cdx", 10), vec!["core/src/codex.rs"]);
+        assert!(index.search("zzz", 10).is_empty());
+    }
+
+    #[test]
+    fn build_respects_gitignore() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        std::fs::write(dir.path().join("target/out.rs"), "").unwrap();
+
+        let index = WorkspaceIndex::build(dir.path(), DEFAULT_MAX_INDEXED_FILES);
+        assert_eq!(index.search("", 10), vec!["src/lib.rs"]);
+    }
+
+    #[test]
+    fn build_respects_codexignore() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(CODEXIGNORE_FILE), "vendor/\n*.snap\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("vendor")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        std::fs::write(dir.path().join("src/lib.snap"), "").unwrap();
+        std::fs::write(dir.path().join("vendor/dep.rs"), "").unwrap();
+
+        let index = WorkspaceIndex::build(dir.path(), DEFAULT_MAX_INDEXED_FILES);
+        assert_eq!(index.search("", 10), vec!["src/lib.rs"]);
+    }
+
+    #[test]
+    fn build_within_keeps_paths_relative_to_the_root() {
+        let dir = TempDir::new().unwrap();
+        for path in ["services/api/main.rs", "services/web/app.ts", "README.md"] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let index = WorkspaceIndex::build_within(
+            dir.path(),
+            &dir.path().join("services/api"),
+            DEFAULT_MAX_INDEXED_FILES,
+        );
+        assert_eq!(index.search("", 10), vec!["services/api/main.rs"]);
+    }
+}
@@ -0,0 +1,567 @@
+//! End-to-end harness for the agent loop.
+//!
+//! A [`Harness`] runs a real `Codex` session in a temporary workspace against
+//! a fake model provider that answers each request with the next scripted
+//! response. Tests then compare the events the session emitted, rendered one
+//! line per event, and the files left in the workspace with expected
+//! snapshots.
+
+#![allow(clippy::expect_used, clippy::unwrap_used, dead_code)]
+
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use codex_core::Codex;
+use codex_core::ModelProviderInfo;
+use codex_core::WireApi;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::ConfigToml;
+use codex_core::config_types::ApplyMode;
+use codex_core::policy::Policy;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventKind;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::FileChange;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ResponseFormat;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SandboxPermission;
+use codex_core::protocol::SandboxPolicy;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::Request;
+use wiremock::Respond;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// How long to wait for the session's next event before failing the test.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An assistant message.
+pub fn message(text: &str) -> Value {
+    json!({
+        "type": "message",
+        "role": "assistant",
+        "content": [{ "type": "output_text", "text": text }],
+    })
+}
+
+/// A `shell` tool call running `command`.
+pub fn shell(call_id: &str, command: &[&str]) -> Value {
+    json!({
+        "type": "function_call",
+        "name": "shell",
+        "call_id": call_id,
+        "arguments": json!({ "command": command }).to_string(),
+    })
+}
+
+/// A `shell` tool call applying `patch` with `apply_patch`.
+pub fn apply_patch(call_id: &str, patch: &str) -> Value {
+    shell(call_id, &["apply_patch", patch])
+}
+
+/// Responses waiting to be served, and the bodies of the requests received so
+/// far.
+#[derive(Default)]
+struct Script {
+    responses: Mutex<VecDeque<ScriptedResponse>>,
+    requests: Mutex<Vec<Value>>,
+    /// Every input of the embeddings requests received so far.
+    embedded: Mutex<Vec<String>>,
+}
+
+/// The output items of one response, and whether the stream completes
+/// after them or breaks off.
+struct ScriptedResponse {
+    items: Vec<Value>,
+    completes: bool,
+}
+
+struct ScriptedProvider(Arc<Script>);
+
+impl Respond for ScriptedProvider {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let script = &self.0;
+        let body: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+        // Session titles are requested in the background, so they are
+        // answered outside the script.
+        let text = &body["input"][0]["content"][0]["text"];
+        if text
+            .as_str()
+            .is_some_and(|text| text.starts_with("Write a title"))
+        {
+            return sse(vec![message("Scripted session")], Some("resp_title"));
+        }
+        let mut requests = script.requests.lock().unwrap();
+        requests.push(body);
+        let Some(response) = script.responses.lock().unwrap().pop_front() else {
+            return ResponseTemplate::new(400).set_body_string("no scripted response left");
+        };
+
+        let response_id = format!("resp_{}", requests.len());
+        sse(
+            response.items,
+            response.completes.then_some(response_id.as_str()),
+        )
+    }
+}
+
+/// Embeds each input as a bag of its words, so that texts sharing words are
+/// similar.
+struct FakeEmbeddings(Arc<Script>);
+
+impl Respond for FakeEmbeddings {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+        let inputs: Vec<String> = body["input"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|input| input.as_str().map(str::to_string))
+            .collect();
+        let data: Vec<Value> = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let mut embedding = vec![0.0f32; 256];
+                for word in input
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                {
+                    let hash = word.to_lowercase().bytes().fold(7usize, |hash, b| {
+                        hash.wrapping_mul(31).wrapping_add(b as usize)
+                    });
+                    embedding[hash % 256] += 1.0;
+                }
+                json!({ "object": "embedding", "index": index, "embedding": embedding })
+            })
+            .collect();
+        self.0.embedded.lock().unwrap().extend(inputs);
+        ResponseTemplate::new(200).set_body_json(json!({ "object": "list", "data": data }))
+    }
+}
+
+/// A streamed response with `items` as its output.
+/// A stream of `items`, completed as `response_id` or, without one, broken
+/// off after the last item.
+fn sse(items: Vec<Value>, response_id: Option<&str>) -> ResponseTemplate {
+    let mut body = String::new();
+    for item in items {
+        let event = json!({ "type": "response.output_item.done", "item": item });
+        body.push_str(&format!(
+            "event: response.output_item.done\ndata: {event}\n\n"
+        ));
+    }
+    if let Some(response_id) = response_id {
+        let completed = json!({
+            "type": "response.completed",
+            "response": { "id": response_id, "output": [] },
+        });
+        body.push_str(&format!("event: response.completed\ndata: {completed}\n\n"));
+    }
+    ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(body, "text/event-stream")
+}
+
+pub struct HarnessBuilder {
+    files: Vec<(String, String)>,
+    responses: Vec<ScriptedResponse>,
+    approval_policy: AskForApproval,
+    small_model: Option<String>,
+    semantic_search: bool,
+    memory: bool,
+    dry_run: bool,
+    policy: Policy,
+    untrusted_commands: Vec<String>,
+    forbidden: Vec<SandboxPermission>,
+    locked: Vec<String>,
+    apply_mode: ApplyMode,
+}
+
+impl HarnessBuilder {
+    /// Create `path` in the workspace before the session starts.
+    pub fn file(mut self, path: &str, contents: &str) -> Self {
+        self.files.push((path.to_string(), contents.to_string()));
+        self
+    }
+
+    /// Defaults to `UnlessAllowListed`, so every patch needs approval.
+    /// Commands run unsandboxed and are approved under any policy.
+    pub fn approval_policy(mut self, approval_policy: AskForApproval) -> Self {
+        self.approval_policy = approval_policy;
+        self
+    }
+
+    /// Set `router.small_model`.
+    pub fn small_model(mut self, model: &str) -> Self {
+        self.small_model = Some(model.to_string());
+        self
+    }
+
+    /// Enable `semantic_search`, with embeddings from the fake provider.
+    pub fn semantic_search(mut self) -> Self {
+        self.semantic_search = true;
+        self
+    }
+
+    /// Enable `[memory]`.
+    pub fn memory(mut self) -> Self {
+        self.memory = true;
+        self
+    }
+
+    /// Show shell commands instead of running them.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Check tool calls against `policy`, in the format of the policy file.
+    pub fn policy(mut self, policy: &str) -> Self {
+        self.policy = toml::from_str(policy).unwrap();
+        self
+    }
+
+    /// Treat the output of `commands` as untrusted (`[untrusted_content]`).
+    pub fn untrusted_commands(mut self, commands: &[&str]) -> Self {
+        self.untrusted_commands = commands.iter().map(|command| command.to_string()).collect();
+        self
+    }
+
+    /// Forbid `permission` as the managed config does.
+    pub fn forbid(mut self, permission: SandboxPermission) -> Self {
+        self.forbidden.push(permission);
+        self
+    }
+
+    /// Lock the top-level `key` as the managed config does.
+    pub fn lock(mut self, key: &str) -> Self {
+        self.locked.push(key.to_string());
+        self
+    }
+
+    pub fn apply_mode(mut self, apply_mode: ApplyMode) -> Self {
+        self.apply_mode = apply_mode;
+        self
+    }
+
+    /// Answer the next model request with `items`.
+    pub fn respond(mut self, items: Vec<Value>) -> Self {
+        self.responses.push(ScriptedResponse {
+            items,
+            completes: true,
+        });
+        self
+    }
+
+    /// Answer the next model request with `items`, then break the stream off
+    /// before the response completes.
+    pub fn respond_and_disconnect(mut self, items: Vec<Value>) -> Self {
+        self.responses.push(ScriptedResponse {
+            items,
+            completes: false,
+        });
+        self
+    }
+
+    pub async fn start(self) -> Harness {
+        // Fail the task on the first error instead of retrying it. Every
+        // test sets the same values, so concurrent tests do not race.
+        unsafe {
+            std::env::set_var("OPENAI_REQUEST_MAX_RETRIES", "0");
+            std::env::set_var("OPENAI_STREAM_MAX_RETRIES", "0");
+        }
+
+        let script = Arc::new(Script {
+            responses: Mutex::new(self.responses.into()),
+            ..Default::default()
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(ScriptedProvider(Arc::clone(&script)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .respond_with(FakeEmbeddings(Arc::clone(&script)))
+            .mount(&server)
+            .await;
+
+        let workspace = TempDir::new().unwrap();
+        let cwd = workspace.path().canonicalize().unwrap();
+        for (path, contents) in &self.files {
+            let path = cwd.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let codex_home = TempDir::new().unwrap();
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides {
+                cwd: Some(cwd.clone()),
+                approval_policy: Some(self.approval_policy),
+                sandbox_policy: Some(SandboxPolicy::from(vec![
+                    SandboxPermission::DiskFullReadAccess,
+                    SandboxPermission::DiskFullWriteAccess,
+                    SandboxPermission::NetworkFullAccess,
+                ])),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )
+        .expect("defaults for test should always succeed");
+        config.router.small_model = self.small_model;
+        config.semantic_search.enabled = self.semantic_search;
+        config.memory.enabled = self.memory;
+        config.dry_run = self.dry_run;
+        config.policy = self.policy;
+        config.untrusted_content.commands = self.untrusted_commands;
+        config.sandbox_policy = config
+            .sandbox_policy
+            .without_permissions(|permission| self.forbidden.contains(permission));
+        config.forbidden_sandbox_permissions = self.forbidden;
+        config.locked = self.locked;
+        config.apply_mode = self.apply_mode;
+        config.model_provider = ModelProviderInfo {
+            name: "scripted".into(),
+            base_url: format!("{}/v1", server.uri()),
+            // Any variable that is set will do: the fake provider ignores the key.
+            env_key: Some("PATH".into()),
+            env_key_instructions: None,
+            wire_api: WireApi::Responses,
+            supports_images: true,
+            supports_structured_output: true,
+            rate_limit: None,
+        };
+
+        let ctrl_c = Arc::new(tokio::sync::Notify::new());
+        let (codex, _init_id) = Codex::spawn(config, ctrl_c).await.unwrap();
+        // Tests see the events that follow the session's configuration.
+        loop {
+            let event = timeout(EVENT_TIMEOUT, codex.next_event())
+                .await
+                .expect("timed out waiting for the session")
+                .unwrap();
+            if matches!(event.msg, EventMsg::SessionConfigured(_)) {
+                break;
+            }
+        }
+        Harness {
+            codex,
+            script,
+            cwd,
+            _server: server,
+            _workspace: workspace,
+            _codex_home: codex_home,
+        }
+    }
+}
+
+pub struct Harness {
+    codex: Codex,
+    script: Arc<Script>,
+    cwd: PathBuf,
+    _server: MockServer,
+    _workspace: TempDir,
+    _codex_home: TempDir,
+}
+
+impl Harness {
+    pub fn builder() -> HarnessBuilder {
+        HarnessBuilder {
+            files: Vec::new(),
+            responses: Vec::new(),
+            approval_policy: AskForApproval::UnlessAllowListed,
+            small_model: None,
+            semantic_search: false,
+            memory: false,
+            dry_run: false,
+            policy: Policy::default(),
+            untrusted_commands: Vec::new(),
+            forbidden: Vec::new(),
+            locked: Vec::new(),
+            apply_mode: ApplyMode::default(),
+        }
+    }
+
+    /// Run a task for `prompt` until it completes or fails, answering
+    /// approval requests with `decisions` in order. Returns one line per
+    /// event a test can assert on.
+    pub async fn run(&self, prompt: &str, decisions: &[ReviewDecision]) -> Vec<String> {
+        self.run_task(prompt, None, decisions).await
+    }
+
+    /// Like [`Self::run`], for a task whose final message must match
+    /// `schema`.
+    pub async fn run_structured(&self, prompt: &str, schema: Value) -> Vec<String> {
+        self.run_task(prompt, Some(ResponseFormat { schema }), &[])
+            .await
+    }
+
+    /// Like [`Self::run`], for `/compact`.
+    pub async fn compact(&self) -> Vec<String> {
+        self.run_op(Op::Compact, &[]).await
+    }
+
+    async fn run_task(
+        &self,
+        prompt: &str,
+        response_format: Option<ResponseFormat>,
+        decisions: &[ReviewDecision],
+    ) -> Vec<String> {
+        let op = Op::UserInput {
+            items: vec![InputItem::Text {
+                text: prompt.to_string(),
+            }],
+            response_format,
+        };
+        self.run_op(op, decisions).await
+    }
+
+    async fn run_op(&self, op: Op, decisions: &[ReviewDecision]) -> Vec<String> {
+        self.codex.submit(op).await.unwrap();
+
+        let mut decisions = decisions.iter().copied();
+        let mut transcript = Vec::new();
+        loop {
+            let event = timeout(EVENT_TIMEOUT, self.codex.next_event())
+                .await
+                .expect("timed out waiting for an event")
+                .unwrap();
+            if let Some(line) = describe(&event.msg, &self.cwd) {
+                transcript.push(line);
+            }
+            let op = match event.msg {
+                EventMsg::ExecApprovalRequest(_) => Op::ExecApproval {
+                    id: event.id,
+                    decision: decisions.next().expect("unexpected approval request"),
+                },
+                EventMsg::ApplyPatchApprovalRequest(_) => Op::PatchApproval {
+                    id: event.id,
+                    decision: decisions.next().expect("unexpected approval request"),
+                },
+                EventMsg::TaskComplete(_) | EventMsg::Error(_) => break,
+                _ => continue,
+            };
+            self.codex.submit(op).await.unwrap();
+        }
+        assert_eq!(decisions.next(), None, "approval decisions left unused");
+        transcript
+    }
+
+    /// Submit `op`, which must not start a task, and return the event that
+    /// answers it.
+    pub async fn submit(&self, op: Op) -> EventMsg {
+        self.codex.submit(op).await.unwrap();
+        timeout(EVENT_TIMEOUT, self.codex.next_event())
+            .await
+            .expect("timed out waiting for an event")
+            .unwrap()
+            .msg
+    }
+
+    /// Submit `Op::Subscribe`, which no event answers.
+    pub async fn subscribe(&self, kinds: Vec<EventKind>) {
+        self.codex.submit(Op::Subscribe { kinds }).await.unwrap();
+    }
+
+    /// Every file in the workspace, by path relative to it.
+    pub fn files(&self) -> BTreeMap<String, String> {
+        let mut files = BTreeMap::new();
+        collect_files(&self.cwd, &self.cwd, &mut files);
+        files
+    }
+
+    /// The output the model was sent for tool call `call_id`.
+    pub fn tool_output(&self, call_id: &str) -> Option<String> {
+        let requests = self.script.requests.lock().unwrap();
+        requests
+            .iter()
+            .filter_map(|request| request["input"].as_array())
+            .flatten()
+            .find(|item| item["type"] == "function_call_output" && item["call_id"] == call_id)
+            .and_then(|item| item["output"].as_str().map(str::to_string))
+    }
+
+    /// Every text the fake provider was asked to embed, in order.
+    pub fn embedded(&self) -> Vec<String> {
+        self.script.embedded.lock().unwrap().clone()
+    }
+
+    /// Number of requests the fake provider has received.
+    pub fn request_count(&self) -> usize {
+        self.script.requests.lock().unwrap().len()
+    }
+
+    /// Body of the `index`th request the fake provider received.
+    pub fn request(&self, index: usize) -> Value {
+        self.script.requests.lock().unwrap()[index].clone()
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else {
+            let relative = path.strip_prefix(root).unwrap().display().to_string();
+            files.insert(relative, std::fs::read_to_string(&path).unwrap());
+        }
+    }
+}
+
+/// Render `msg` as a transcript line, or `None` for events that are not
+/// deterministic or not interesting to the tests.
+fn describe(msg: &EventMsg, cwd: &Path) -> Option<String> {
+    let paths = |changes: &std::collections::HashMap<PathBuf, FileChange>| {
+        let mut paths = changes
+            .keys()
+            .map(|path| path.strip_prefix(cwd).unwrap_or(path).display().to_string())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.join(", ")
+    };
+    let line = match msg {
+        EventMsg::TaskStarted => "task started".to_string(),
+        EventMsg::TaskComplete(_) => "task complete".to_string(),
+        EventMsg::Error(event) => format!("error: {}", event.message),
+        EventMsg::AgentMessage(event) => format!("agent: {}", event.message),
+        EventMsg::ExecApprovalRequest(event) => {
+            format!("approve exec? {}", event.command.join(" "))
+        }
+        EventMsg::ExecCommandBegin(event) => format!("exec: {}", event.command.join(" ")),
+        EventMsg::DryRunExec(event) => format!("dry run: {}", event.command.join(" ")),
+        EventMsg::ExecCommandEnd(event) => {
+            format!("exit {}: {}", event.exit_code, event.stdout.trim_end())
+        }
+        EventMsg::ApplyPatchApprovalRequest(event) => {
+            format!("approve patch? {}", paths(&event.changes))
+        }
+        EventMsg::PatchApplyBegin(event) if event.auto_approved => {
+            format!("patch (auto-approved): {}", paths(&event.changes))
+        }
+        EventMsg::PatchApplyBegin(event) => format!("patch: {}", paths(&event.changes)),
+        EventMsg::PatchProposed(event) => format!("patch proposed: {}", paths(&event.changes)),
+        EventMsg::PatchApplyEnd(event) if event.success => "patch applied".to_string(),
+        EventMsg::PatchApplyEnd(event) => format!("patch failed: {}", event.stderr.trim_end()),
+        _ => return None,
+    };
+    Some(line)
+}
@@ -85,6 +85,7 @@ async fn live_streaming_and_prev_id_reset() {
             items: vec![InputItem::Text {
                 text: "Say the words 'stream test'".into(),
             }],
+            response_format: None,
         })
         .await
         .unwrap();
@@ -119,6 +120,7 @@ async fn live_streaming_and_prev_id_reset() {
             items: vec![InputItem::Text {
                 text: "Respond with exactly: second turn succeeded".into(),
             }],
+            response_format: None,
         })
         .await
         .unwrap();
@@ -172,6 +174,7 @@ async fn live_shell_function_call() {
                     "Use the shell function to run the command `echo {MARKER}` and no other commands."
                 ),
             }],
+            response_format: None,
         })
         .await
         .unwrap();
@@ -107,6 +107,9 @@ async fn keeps_previous_response_id_between_tasks() {
         env_key: Some("PATH".into()),
         env_key_instructions: None,
         wire_api: codex_core::WireApi::Responses,
+        supports_images: true,
+        supports_structured_output: true,
+        rate_limit: None,
     };
 
     // Init session
@@ -122,6 +125,7 @@ async fn keeps_previous_response_id_between_tasks() {
             items: vec![InputItem::Text {
                 text: "hello".into(),
             }],
+            response_format: None,
         })
         .await
         .unwrap();
@@ -143,6 +147,7 @@ async fn keeps_previous_response_id_between_tasks() {
             items: vec![InputItem::Text {
                 text: "again".into(),
             }],
+            response_format: None,
         })
         .await
         .unwrap();
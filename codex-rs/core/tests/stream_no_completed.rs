@@ -96,6 +96,9 @@ async fn retries_on_early_close() {
         env_key: Some("PATH".into()),
         env_key_instructions: None,
         wire_api: codex_core::WireApi::Responses,
+        supports_images: true,
+        supports_structured_output: true,
+        rate_limit: None,
     };
 
     let ctrl_c = std::sync::Arc::new(tokio::sync::Notify::new());
@@ -109,6 +112,7 @@ async fn retries_on_early_close() {
             items: vec![InputItem::Text {
                 text: "hello".into(),
             }],
+            response_format: None,
         })
         .await
         .unwrap();
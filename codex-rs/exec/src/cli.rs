@@ -2,6 +2,8 @@ use clap::Parser;
 use clap::ValueEnum;
 use codex_common::CliConfigOverrides;
 use codex_common::SandboxPermissionOption;
+use codex_common::SessionModeCliArg;
+use codex_core::config_types::ApplyMode;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -19,6 +21,15 @@ pub struct Cli {
     #[arg(long = "profile", short = 'p')]
     pub config_profile: Option<String>,
 
+    /// Sampling temperature, between 0 and 2. Lower values make runs more
+    /// deterministic.
+    #[arg(long = "temperature")]
+    pub temperature: Option<f32>,
+
+    /// Seed for repeatable sampling, for providers that support it.
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+
     /// Convenience alias for low-friction sandboxed automatic execution (network-disabled sandbox that can write to cwd and TMPDIR)
     #[arg(long = "full-auto", default_value_t = false)]
     pub full_auto: bool,
@@ -26,10 +37,36 @@ pub struct Cli {
     #[clap(flatten)]
     pub sandbox: SandboxPermissionOption,
 
+    /// `ask` keeps the agent read-only: it can read and search files but not
+    /// edit them or run other commands.
+    #[arg(long = "mode")]
+    pub mode: Option<SessionModeCliArg>,
+
+    /// `propose` sends the agent's patches out as `patch_proposed` events
+    /// instead of writing them, for an editor plugin to apply.
+    #[arg(long = "apply-mode", value_enum)]
+    pub apply_mode: Option<ApplyModeCliArg>,
+
+    /// Print the shell commands the agent proposes instead of running them;
+    /// the agent is told they were not executed.
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+
     /// Tell the agent to use the specified directory as its working root.
     #[clap(long = "cd", short = 'C', value_name = "DIR")]
     pub cwd: Option<PathBuf>,
 
+    /// Subdirectory of the working root to concentrate on in a large
+    /// repository: file completion and the repository map only cover it and,
+    /// on Linux, sandboxed commands can only read it.
+    #[arg(long = "focus", value_name = "DIR")]
+    pub focus: Option<PathBuf>,
+
+    /// Forbid all network access except to providers on this machine, e.g.
+    /// Ollama. Commands run by the agent lose network access as well.
+    #[arg(long = "offline", default_value_t = false)]
+    pub offline: bool,
+
     /// Allow running Codex outside a Git repository.
     #[arg(long = "skip-git-repo-check", default_value_t = false)]
     pub skip_git_repo_check: bool,
@@ -45,10 +82,78 @@ pub struct Cli {
     #[arg(long = "output-last-message")]
     pub last_message_file: Option<PathBuf>,
 
+    /// JSON Schema file the agent's last message must match. The model is
+    /// asked to fix a message that does not, and Codex exits with an error if
+    /// it still does not match.
+    #[arg(long = "output-schema", value_name = "FILE")]
+    pub output_schema: Option<PathBuf>,
+
+    /// Also write a report for CI: `junit` for the outcome of the task (or
+    /// of each test run of `codex test-fix`), `sarif` for the problems the
+    /// agent found in the code.
+    #[arg(long = "report", value_enum)]
+    pub report: Option<ReportFormat>,
+
+    /// Where to write the report. Defaults to `codex-report.xml` or
+    /// `codex-report.sarif` in the working directory.
+    #[arg(long = "report-file", value_name = "FILE", requires = "report")]
+    pub report_file: Option<PathBuf>,
+
+    /// POST every event to this URL as JSON while the task runs, e.g. for a
+    /// dashboard. Overrides `event_sink` in the config.
+    #[arg(long = "event-sink", value_name = "URL")]
+    pub event_sink: Option<String>,
+
     /// Initial instructions for the agent. If not provided as an argument (or
     /// if `-` is used), instructions are read from stdin.
     #[arg(value_name = "PROMPT")]
     pub prompt: Option<String>,
+
+    /// Set by `codex test-fix`: fix the failing tests instead of running a
+    /// single prompt, which then holds extra instructions.
+    #[clap(skip)]
+    pub test_fix: Option<TestFix>,
+}
+
+/// Options of `codex test-fix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFix {
+    /// Defaults to `checks.test` from the config.
+    pub command: Option<String>,
+    /// Fix attempts before giving up.
+    pub max_iterations: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum ApplyModeCliArg {
+    Write,
+    Propose,
+}
+
+impl From<ApplyModeCliArg> for ApplyMode {
+    fn from(value: ApplyModeCliArg) -> Self {
+        match value {
+            ApplyModeCliArg::Write => ApplyMode::Write,
+            ApplyModeCliArg::Propose => ApplyMode::Propose,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum ReportFormat {
+    Junit,
+    Sarif,
+}
+
+impl ReportFormat {
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            ReportFormat::Junit => "codex-report.xml",
+            ReportFormat::Sarif => "codex-report.sarif",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
@@ -1,9 +1,12 @@
+use codex_common::elapsed::format_duration;
 use codex_common::elapsed::format_elapsed;
 use codex_core::WireApi;
 use codex_core::config::Config;
+use codex_core::event_sink::EventSink;
 use codex_core::model_supports_reasoning_summaries;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::CheckpointCreatedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -14,11 +17,21 @@ use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::PatchProposedEvent;
+use codex_core::protocol::PlanItem;
+use codex_core::protocol::PlanUpdatedEvent;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_core::protocol::SessionMode;
+use codex_core::protocol::StepStatus;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_core::test_fix::TestFixReport;
+use codex_core::test_fix::TestRun;
 use owo_colors::OwoColorize;
 use owo_colors::Style;
 use shlex::try_join;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 use std::time::Instant;
 
 /// This should be configurable. When used in CI, users may not want to impose
@@ -48,6 +61,9 @@ pub(crate) struct EventProcessor {
 
     /// Whether to include `AgentReasoning` events in the output.
     show_agent_reasoning: bool,
+
+    /// Where every event is posted as well, when `event_sink` is set.
+    event_sink: Option<EventSink>,
 }
 
 impl EventProcessor {
@@ -69,6 +85,7 @@ impl EventProcessor {
                 cyan: Style::new().cyan(),
                 call_id_to_tool_call,
                 show_agent_reasoning,
+                event_sink: None,
             }
         } else {
             Self {
@@ -83,6 +100,7 @@ impl EventProcessor {
                 cyan: Style::new(),
                 call_id_to_tool_call,
                 show_agent_reasoning,
+                event_sink: None,
             }
         }
     }
@@ -136,6 +154,12 @@ impl EventProcessor {
             ("approval", format!("{:?}", config.approval_policy)),
             ("sandbox", format!("{:?}", config.sandbox_policy)),
         ];
+        if config.mode == SessionMode::Ask {
+            entries.push(("mode", "ask (read-only)".to_string()));
+        }
+        if config.dry_run {
+            entries.push(("dry run", "commands are shown, not executed".to_string()));
+        }
         if config.model_provider.wire_api == WireApi::Responses
             && model_supports_reasoning_summaries(&config.model)
         {
@@ -148,6 +172,15 @@ impl EventProcessor {
                 config.model_reasoning_summary.to_string(),
             ));
         }
+        if let Some(temperature) = config.sampling.temperature {
+            entries.push(("temperature", temperature.to_string()));
+        }
+        if let Some(top_p) = config.sampling.top_p {
+            entries.push(("top_p", top_p.to_string()));
+        }
+        if let Some(seed) = config.sampling.seed {
+            entries.push(("seed", seed.to_string()));
+        }
 
         for (key, value) in entries {
             println!("{} {}", format!("{key}:").style(self.bold), value);
@@ -166,7 +199,121 @@ impl EventProcessor {
         );
     }
 
+    /// Announce a run of the test command of `codex test-fix`, made after
+    /// `attempts` fixes.
+    pub(crate) fn print_test_run_begin(&mut self, command: &str, attempts: usize) {
+        let when = if attempts == 0 {
+            "before any fix".to_string()
+        } else {
+            format!("after fix {attempts}")
+        };
+        ts_println!(
+            self,
+            "{} {} ({when})",
+            "running tests".style(self.magenta),
+            command.style(self.bold)
+        );
+    }
+
+    pub(crate) fn print_test_run_end(&mut self, run: &TestRun) {
+        let duration = format_duration(run.duration);
+        if run.passed {
+            ts_println!(
+                self,
+                "{}",
+                format!("tests passed in {duration}").style(self.green)
+            );
+        } else {
+            ts_println!(
+                self,
+                "{}",
+                format!("tests failed in {duration}:").style(self.red)
+            );
+            // The end of the output holds the failures and the summary.
+            let lines: Vec<&str> = run.output.lines().collect();
+            let tail = &lines[lines
+                .len()
+                .saturating_sub(MAX_OUTPUT_LINES_FOR_EXEC_TOOL_CALL)..];
+            println!("{}", tail.join("\n").style(self.dimmed));
+        }
+    }
+
+    pub(crate) fn print_test_fix_report(&mut self, report: &TestFixReport) {
+        let style = if report.passed() {
+            self.green
+        } else {
+            self.red
+        };
+        ts_println!(self, "{}", "test-fix report:".style(self.bold));
+        println!("{}", report.render().style(style));
+    }
+
+    /// Pretty-print the changes of a patch with colored diff markers so
+    /// they are easy to scan in the terminal output.
+    fn print_file_changes(&self, changes: &HashMap<PathBuf, FileChange>) {
+        for (path, change) in changes.iter() {
+            match change {
+                FileChange::Add { content } => {
+                    let header =
+                        format!("{} {}", format_file_change(change), path.to_string_lossy());
+                    println!("{}", header.style(self.magenta));
+                    for line in content.lines() {
+                        println!("{}", line.style(self.green));
+                    }
+                }
+                FileChange::Delete => {
+                    let header =
+                        format!("{} {}", format_file_change(change), path.to_string_lossy());
+                    println!("{}", header.style(self.magenta));
+                }
+                FileChange::Update {
+                    unified_diff,
+                    move_path,
+                } => {
+                    let header = if let Some(dest) = move_path {
+                        format!(
+                            "{} {} -> {}",
+                            format_file_change(change),
+                            path.to_string_lossy(),
+                            dest.to_string_lossy()
+                        )
+                    } else {
+                        format!("{} {}", format_file_change(change), path.to_string_lossy())
+                    };
+                    println!("{}", header.style(self.magenta));
+
+                    // Colorize diff lines. We keep file header lines
+                    // (--- / +++) without extra coloring so they are
+                    // still readable.
+                    for diff_line in unified_diff.lines() {
+                        if diff_line.starts_with('+') && !diff_line.starts_with("+++") {
+                            println!("{}", diff_line.style(self.green));
+                        } else if diff_line.starts_with('-') && !diff_line.starts_with("---") {
+                            println!("{}", diff_line.style(self.red));
+                        } else {
+                            println!("{diff_line}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_event_sink(&mut self, event_sink: Option<EventSink>) {
+        self.event_sink = event_sink;
+    }
+
+    /// Wait for the events still queued for the event sink to be posted.
+    pub(crate) async fn flush_event_sink(&mut self) {
+        if let Some(event_sink) = self.event_sink.take() {
+            event_sink.flush().await;
+        }
+    }
+
     pub(crate) fn process_event(&mut self, event: Event) {
+        if let Some(event_sink) = &self.event_sink {
+            event_sink.send(&event);
+        }
         let Event { id: _, msg } = event;
         match msg {
             EventMsg::Error(ErrorEvent { message }) => {
@@ -176,9 +323,27 @@ impl EventProcessor {
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 ts_println!(self, "{}", message.style(self.dimmed));
             }
-            EventMsg::TaskStarted | EventMsg::TaskComplete(_) => {
+            EventMsg::TaskStarted
+            | EventMsg::TaskComplete(TaskCompleteEvent { stats: None, .. }) => {
                 // Ignore.
             }
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                stats: Some(stats), ..
+            }) => {
+                let mut summary = format!(
+                    "task took {} ({} requests, {} tokens)",
+                    format_duration(Duration::from_millis(stats.duration_ms)),
+                    stats.requests,
+                    stats.usage.total_tokens
+                );
+                if let Some(cost) = stats.cost_usd {
+                    summary.push_str(&format!(", ~${cost:.2}"));
+                }
+                ts_println!(self, "{}", summary.style(self.dimmed));
+            }
+            EventMsg::AgentMessageDelta(_) | EventMsg::AgentReasoningDelta(_) => {
+                // The complete text is printed once it arrives.
+            }
             EventMsg::AgentMessage(AgentMessageEvent { message }) => {
                 ts_println!(
                     self,
@@ -337,62 +502,18 @@ impl EventProcessor {
                     auto_approved,
                 );
 
-                // Pretty-print the patch summary with colored diff markers so
-                // it’s easy to scan in the terminal output.
-                for (path, change) in changes.iter() {
-                    match change {
-                        FileChange::Add { content } => {
-                            let header = format!(
-                                "{} {}",
-                                format_file_change(change),
-                                path.to_string_lossy()
-                            );
-                            println!("{}", header.style(self.magenta));
-                            for line in content.lines() {
-                                println!("{}", line.style(self.green));
-                            }
-                        }
-                        FileChange::Delete => {
-                            let header = format!(
-                                "{} {}",
-                                format_file_change(change),
-                                path.to_string_lossy()
-                            );
-                            println!("{}", header.style(self.magenta));
-                        }
-                        FileChange::Update {
-                            unified_diff,
-                            move_path,
-                        } => {
-                            let header = if let Some(dest) = move_path {
-                                format!(
-                                    "{} {} -> {}",
-                                    format_file_change(change),
-                                    path.to_string_lossy(),
-                                    dest.to_string_lossy()
-                                )
-                            } else {
-                                format!("{} {}", format_file_change(change), path.to_string_lossy())
-                            };
-                            println!("{}", header.style(self.magenta));
-
-                            // Colorize diff lines. We keep file header lines
-                            // (--- / +++) without extra coloring so they are
-                            // still readable.
-                            for diff_line in unified_diff.lines() {
-                                if diff_line.starts_with('+') && !diff_line.starts_with("+++") {
-                                    println!("{}", diff_line.style(self.green));
-                                } else if diff_line.starts_with('-')
-                                    && !diff_line.starts_with("---")
-                                {
-                                    println!("{}", diff_line.style(self.red));
-                                } else {
-                                    println!("{diff_line}");
-                                }
-                            }
-                        }
-                    }
-                }
+                self.print_file_changes(&changes);
+            }
+            EventMsg::PatchProposed(PatchProposedEvent {
+                call_id: _,
+                changes,
+            }) => {
+                ts_println!(
+                    self,
+                    "{} (not written; the editor applies it):",
+                    "proposed patch".style(self.magenta),
+                );
+                self.print_file_changes(&changes);
             }
             EventMsg::PatchApplyEnd(PatchApplyEndEvent {
                 call_id,
@@ -428,12 +549,16 @@ impl EventProcessor {
                     println!("{}", line.style(self.dimmed));
                 }
             }
-            EventMsg::ExecApprovalRequest(_) => {
+            EventMsg::ExecApprovalRequest(_) | EventMsg::SandboxEscalationRequest(_) => {
                 // Should we exit?
             }
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::TurnDiffApprovalRequest(_) => {
+                // exec never asks for approval, so turn edits are accepted
+                // without a review.
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     ts_println!(
@@ -462,15 +587,73 @@ impl EventProcessor {
                 ts_println!(self, "model: {}", model);
                 println!();
             }
-            EventMsg::GetHistoryEntryResponse(_) => {
+            EventMsg::GetHistoryEntryResponse(_) | EventMsg::Handshake(_) | EventMsg::Unknown => {
                 // Currently ignored in exec output.
             }
-            EventMsg::CopilotAuthStarted(_) => {
+            EventMsg::CopilotAuthStarted(_) | EventMsg::CopilotAuthProgress(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::TurnContextOverridden(event) => {
+                ts_println!(self, "{}", event.summary().style(self.dimmed));
+            }
+            EventMsg::CopilotPlanDetected(event) => {
+                ts_println!(self, "{}", event.summary().style(self.dimmed));
+            }
             EventMsg::CopilotAuthComplete(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::ChatGptAuthStarted(_) | EventMsg::ChatGptAuthComplete(_) => {
+                // Currently ignored in exec output.
+            }
+            EventMsg::TokenCount(_) => {
+                // Currently ignored in exec output.
+            }
+            EventMsg::SecretsRedacted(event) => {
+                ts_println!(self, "{}", event.summary().style(self.dimmed));
+            }
+            EventMsg::RateLimited(event) => {
+                ts_println!(self, "{}", event.summary().style(self.dimmed));
+            }
+            EventMsg::ToolCallRepair(event) => {
+                ts_println!(self, "{}", event.summary().style(self.dimmed));
+            }
+            EventMsg::CheckpointCreated(CheckpointCreatedEvent { number, automatic }) => {
+                if !automatic {
+                    ts_println!(
+                        self,
+                        "{}",
+                        format!("checkpoint {number}").style(self.dimmed)
+                    );
+                }
+            }
+            EventMsg::Rewound(event) => {
+                ts_println!(self, "{}", event.summary().style(self.dimmed));
+            }
+            EventMsg::DryRunExec(event) => {
+                ts_println!(self, "{}", event.summary().style(self.magenta));
+            }
+            EventMsg::SubAgent(sub_agent_event) => {
+                if let Some(summary) = sub_agent_event.summary() {
+                    ts_println!(self, "{}", summary.style(self.dimmed));
+                }
+            }
+            EventMsg::PlanUpdated(PlanUpdatedEvent { explanation, plan }) => {
+                ts_println!(self, "{}", "plan".style(self.bold).style(self.magenta));
+                if let Some(explanation) = explanation {
+                    println!("{}", explanation.style(self.italic));
+                }
+                for PlanItem { step, status } in plan {
+                    match status {
+                        StepStatus::Completed => {
+                            println!("{} {}", "✔".style(self.green), step.style(self.dimmed));
+                        }
+                        StepStatus::InProgress => {
+                            println!("{} {}", "▶".style(self.cyan), step.style(self.bold));
+                        }
+                        StepStatus::Pending => println!("□ {step}"),
+                    }
+                }
+            }
         }
     }
 }
@@ -6,43 +6,100 @@ use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
+use anyhow::Context;
 pub use cli::Cli;
+pub use cli::ReportFormat;
+pub use cli::TestFix;
+use codex_core::Codex;
 use codex_core::codex_wrapper;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config::PROJECT_CONFIG_FILE;
+use codex_core::event_sink::EventSink;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::ResponseFormat;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::report::FINDINGS_REQUEST;
+use codex_core::report::TestCase;
+use codex_core::report::findings_format;
+use codex_core::report::junit;
+use codex_core::report::parse_findings;
+use codex_core::report::sarif;
+use codex_core::test_fix::TestFixReport;
+use codex_core::test_fix::fix_request;
+use codex_core::test_fix::run_label;
+use codex_core::test_fix::run_tests;
 use codex_core::util::is_inside_git_repo;
 use event_processor::EventProcessor;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
 
 pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
     let Cli {
         images,
         model,
         config_profile,
+        temperature,
+        seed,
         full_auto,
         sandbox,
+        mode,
+        apply_mode,
+        dry_run,
         cwd,
+        focus,
+        offline,
         skip_git_repo_check,
         color,
         last_message_file,
+        output_schema,
+        report,
+        report_file,
+        event_sink,
         prompt,
+        test_fix,
         config_overrides,
     } = cli;
 
+    let response_format = match output_schema.as_deref().map(load_output_schema) {
+        Some(Ok(response_format)) => Some(response_format),
+        Some(Err(e)) => {
+            eprintln!("Error reading --output-schema: {e}");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    // Findings reach the report through the agent's final message, which
+    // then has to match their schema.
+    let response_format = match report {
+        Some(ReportFormat::Sarif) if output_schema.is_some() => {
+            eprintln!("--report sarif cannot be combined with --output-schema.");
+            std::process::exit(1);
+        }
+        Some(ReportFormat::Sarif) if test_fix.is_some() => {
+            eprintln!("codex test-fix only supports --report junit.");
+            std::process::exit(1);
+        }
+        Some(ReportFormat::Sarif) => Some(findings_format()),
+        _ => response_format,
+    };
+
     // Determine the prompt based on CLI arg and/or stdin.
     let prompt = match prompt {
         Some(p) if p != "-" => p,
+        // `codex test-fix` needs no instructions.
+        None if test_fix.is_some() => String::new(),
         // Either `-` was passed or no positional arg.
         maybe_dash => {
             // When no arg (None) **and** stdin is a TTY, bail out early – unless the
@@ -101,6 +158,14 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         cwd: cwd.map(|p| p.canonicalize().unwrap_or(p)),
         model_provider: None,
         codex_linux_sandbox_exe,
+        mode: mode.map(Into::into),
+        dry_run: dry_run.then_some(true),
+        focus,
+        offline: offline.then_some(true),
+        temperature,
+        seed,
+        event_sink,
+        apply_mode: apply_mode.map(Into::into),
     };
     // Parse `-c` overrides.
     let cli_kv_overrides = match config_overrides.parse_overrides() {
@@ -123,23 +188,74 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         std::process::exit(1);
     }
 
+    let test_fix = test_fix.map(|test_fix| {
+        let command = match test_fix.command {
+            Some(command) => command,
+            None => {
+                let Some(command) = config.checks.test.clone() else {
+                    eprintln!(
+                        "No test command: pass --test-command or set `test` in the [checks] table."
+                    );
+                    std::process::exit(1);
+                };
+                if config.test_command_from_project && !confirm_project_test_command(&command) {
+                    eprintln!(
+                        "Not running `{command}` from {PROJECT_CONFIG_FILE}: pass it with --test-command to run it."
+                    );
+                    std::process::exit(1);
+                }
+                command
+            }
+        };
+        (command, test_fix.max_iterations, config.clone())
+    });
+    let cwd = config.cwd.clone();
+    let report = report.map(|format| {
+        let path = report_file.unwrap_or_else(|| cwd.join(format.default_file_name()));
+        (format, path)
+    });
+
+    let (otel_layer, _otel_guard) = match codex_core::otel::otel_layer(&config) {
+        Ok(Some((layer, guard))) => (Some(layer), Some(guard)),
+        Ok(None) => (None, None),
+        Err(e) => {
+            eprintln!("Error in [otel]: {e}");
+            std::process::exit(1);
+        }
+    };
+
     // TODO(mbolin): Take a more thoughtful approach to logging.
     let default_level = "error";
-    let _ = tracing_subscriber::fmt()
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(stderr_with_ansi)
+        .with_writer(std::io::stderr)
         // Fallback to the `default_level` log filter if the environment
         // variable is not set _or_ contains an invalid value
-        .with_env_filter(
+        .with_filter(
             EnvFilter::try_from_default_env()
                 .or_else(|_| EnvFilter::try_new(default_level))
                 .unwrap_or_else(|_| EnvFilter::new(default_level)),
-        )
-        .with_ansi(stderr_with_ansi)
-        .with_writer(std::io::stderr)
+        );
+    let _ = tracing_subscriber::registry()
+        .with(otel_layer)
+        .with(fmt_layer)
         .try_init();
 
+    let event_sink = match EventSink::start(&config) {
+        Ok(event_sink) => event_sink,
+        Err(e) => {
+            eprintln!("Error in event_sink: {e:#}");
+            std::process::exit(1);
+        }
+    };
+
     let (codex_wrapper, event, ctrl_c) = codex_wrapper::init_codex(config).await?;
     let codex = Arc::new(codex_wrapper);
     info!("Codex initialized with event: {event:?}");
+    if let Some(event_sink) = &event_sink {
+        event_sink.send(&event);
+    }
+    event_processor.set_event_sink(event_sink);
 
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
     {
@@ -152,7 +268,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                         // Forward an interrupt to the codex so it can abort any in‑flight task.
                         let _ = codex
                             .submit(
-                                Op::Interrupt,
+                                Op::Interrupt { replace_with: None },
                             )
                             .await;
 
@@ -184,43 +300,241 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
             .into_iter()
             .map(|path| InputItem::LocalImage { path })
             .collect();
-        let initial_images_event_id = codex.submit(Op::UserInput { items }).await?;
+        let initial_images_event_id = codex
+            .submit(Op::UserInput {
+                items,
+                response_format: None,
+            })
+            .await?;
         info!("Sent images with event ID: {initial_images_event_id}");
         while let Ok(event) = codex.next_event().await {
             if event.id == initial_images_event_id
-                && matches!(
-                    event.msg,
-                    EventMsg::TaskComplete(TaskCompleteEvent {
-                        last_agent_message: _,
-                    })
-                )
+                && matches!(event.msg, EventMsg::TaskComplete(TaskCompleteEvent { .. }))
             {
                 break;
             }
         }
     }
 
+    if let Some((command, max_iterations, config)) = test_fix {
+        let test_fix_report = run_test_fix(
+            &codex,
+            &mut rx,
+            &mut event_processor,
+            &config,
+            &command,
+            max_iterations,
+            &prompt,
+        )
+        .await?;
+        event_processor.flush_event_sink().await;
+        event_processor.print_test_fix_report(&test_fix_report);
+        if let Some((_, path)) = &report {
+            write_report(path, &test_fix_junit(&command, &test_fix_report))?;
+        }
+        if !test_fix_report.passed() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Send the prompt.
-    let items: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
-    let initial_prompt_task_id = codex.submit(Op::UserInput { items }).await?;
-    info!("Sent prompt with event ID: {initial_prompt_task_id}");
+    let name = task_name(&prompt);
+    let text = match report {
+        Some((ReportFormat::Sarif, _)) => format!("{prompt}\n\n{FINDINGS_REQUEST}"),
+        _ => prompt,
+    };
+    let items: Vec<InputItem> = vec![InputItem::Text { text }];
+    let start = Instant::now();
+    let outcome = run_task(
+        &codex,
+        &mut rx,
+        &mut event_processor,
+        items,
+        response_format,
+    )
+    .await?;
+    event_processor.flush_event_sink().await;
+    match &report {
+        Some((ReportFormat::Junit, path)) => {
+            write_report(path, &task_junit(name, start.elapsed(), &outcome))?;
+        }
+        Some((ReportFormat::Sarif, path)) => write_report(path, &task_sarif(&outcome))?,
+        None => {}
+    }
+    match outcome {
+        // The task failed, e.g. because its last message never matched
+        // `--output-schema`.
+        TaskOutcome::Failed => std::process::exit(1),
+        TaskOutcome::Completed { last_agent_message } => {
+            handle_last_message(last_agent_message, last_message_file.as_deref())?;
+        }
+        TaskOutcome::Interrupted => {}
+    }
+
+    Ok(())
+}
+
+enum TaskOutcome {
+    Completed {
+        last_agent_message: Option<String>,
+    },
+    Failed,
+    /// Ctrl-C stopped the task, and with it the stream of events.
+    Interrupted,
+}
+
+/// Submit `items` and print the events of the task they start until it ends.
+async fn run_task(
+    codex: &Codex,
+    rx: &mut UnboundedReceiver<Event>,
+    event_processor: &mut EventProcessor,
+    items: Vec<InputItem>,
+    response_format: Option<ResponseFormat>,
+) -> anyhow::Result<TaskOutcome> {
+    let task_id = codex
+        .submit(Op::UserInput {
+            items,
+            response_format,
+        })
+        .await?;
+    info!("Sent prompt with event ID: {task_id}");
 
     // Run the loop until the task is complete.
     while let Some(event) = rx.recv().await {
-        let (is_last_event, last_assistant_message) = match &event.msg {
-            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
-                (true, last_agent_message.clone())
-            }
-            _ => (false, None),
+        let outcome = match &event.msg {
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message, ..
+            }) => Some(TaskOutcome::Completed {
+                last_agent_message: last_agent_message.clone(),
+            }),
+            EventMsg::Error(_) if event.id == task_id => Some(TaskOutcome::Failed),
+            _ => None,
         };
         event_processor.process_event(event);
-        if is_last_event {
-            handle_last_message(last_assistant_message, last_message_file.as_deref())?;
-            break;
+        if let Some(outcome) = outcome {
+            return Ok(outcome);
         }
     }
+    Ok(TaskOutcome::Interrupted)
+}
 
-    Ok(())
+/// Ask on the terminal whether to run `command`, which the project config
+/// chose. Without a terminal to ask on, it is not run.
+fn confirm_project_test_command(command: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    eprint!("Run the test command `{command}` from {PROJECT_CONFIG_FILE}? [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Run `command` and have the agent fix what fails, up to `max_iterations`
+/// times, until it passes.
+async fn run_test_fix(
+    codex: &Codex,
+    rx: &mut UnboundedReceiver<Event>,
+    event_processor: &mut EventProcessor,
+    config: &Config,
+    command: &str,
+    max_iterations: usize,
+    instructions: &str,
+) -> anyhow::Result<TestFixReport> {
+    let mut report = TestFixReport::default();
+    loop {
+        event_processor.print_test_run_begin(command, report.attempts);
+        let run = run_tests(command, config)
+            .await
+            .with_context(|| format!("failed to run `{command}`"))?;
+        event_processor.print_test_run_end(&run);
+        let request = (!run.passed).then(|| fix_request(command, &run, instructions));
+        report.runs.push(run);
+        let Some(request) = request.filter(|_| report.attempts < max_iterations) else {
+            return Ok(report);
+        };
+
+        report.attempts += 1;
+        let items = vec![InputItem::Text { text: request }];
+        match run_task(codex, rx, event_processor, items, None).await? {
+            TaskOutcome::Completed { .. } => {}
+            TaskOutcome::Failed | TaskOutcome::Interrupted => return Ok(report),
+        }
+    }
+}
+
+/// The first line of `prompt`, shortened, to name the task in a report.
+fn task_name(prompt: &str) -> String {
+    const MAX_CHARS: usize = 80;
+    let line = prompt.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= MAX_CHARS {
+        return line.to_string();
+    }
+    let mut name: String = line.chars().take(MAX_CHARS - 3).collect();
+    name.push_str("...");
+    name
+}
+
+fn task_junit(name: String, duration: std::time::Duration, outcome: &TaskOutcome) -> String {
+    let (failure, output) = match outcome {
+        TaskOutcome::Completed { last_agent_message } => (None, last_agent_message.clone()),
+        TaskOutcome::Failed => (Some("the task failed".to_string()), None),
+        TaskOutcome::Interrupted => (Some("the task was interrupted".to_string()), None),
+    };
+    junit(
+        "codex exec",
+        &[TestCase {
+            name,
+            duration,
+            failure,
+            output,
+        }],
+    )
+}
+
+/// The findings of the final message; a run that did not complete reports
+/// none and is marked unsuccessful.
+fn task_sarif(outcome: &TaskOutcome) -> String {
+    let findings = match outcome {
+        TaskOutcome::Completed {
+            last_agent_message: Some(message),
+        } => parse_findings(message).ok(),
+        _ => None,
+    };
+    let log = match findings {
+        Some(findings) => sarif(&findings.findings, true),
+        None => sarif(&[], false),
+    };
+    format!("{log:#}\n")
+}
+
+/// One test case per run of the test command.
+fn test_fix_junit(command: &str, report: &TestFixReport) -> String {
+    let cases: Vec<TestCase> = report
+        .runs
+        .iter()
+        .enumerate()
+        .map(|(index, run)| TestCase {
+            name: run_label(index),
+            duration: run.duration,
+            failure: (!run.passed).then(|| format!("`{command}` failed\n\n{}", run.output)),
+            output: None,
+        })
+        .collect();
+    junit(command, &cases)
+}
+
+fn write_report(path: &Path, contents: &str) -> anyhow::Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn load_output_schema(path: &Path) -> anyhow::Result<ResponseFormat> {
+    let schema: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    if !schema.is_object() {
+        anyhow::bail!("{} does not contain a JSON object", path.display());
+    }
+    Ok(ResponseFormat { schema })
 }
 
 fn handle_last_message(
@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::config_types::SyscallClass;
 use codex_core::error::CodexErr;
 use codex_core::error::Result;
 use codex_core::error::SandboxErr;
@@ -25,48 +26,100 @@ use seccompiler::SeccompRule;
 use seccompiler::TargetArch;
 use seccompiler::apply_filter;
 
+/// Directories that stay readable without `disk-full-read-access`, so that
+/// programs can still be found, loaded and configured.
+const SYSTEM_READ_ROOTS: &[&str] = &[
+    "/bin", "/sbin", "/usr", "/lib", "/lib32", "/lib64", "/etc", "/dev", "/proc",
+];
+
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    read_only_paths: &[PathBuf],
+    blocked_syscalls: &[SyscallClass],
 ) -> Result<()> {
     if !sandbox_policy.has_full_network_access() {
         install_network_seccomp_filter_on_current_thread()?;
     }
 
-    if !sandbox_policy.has_full_disk_write_access() {
-        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+    if !blocked_syscalls.is_empty() {
+        install_syscall_seccomp_filter_on_current_thread(blocked_syscalls)?;
     }
 
-    // TODO(ragona): Add appropriate restrictions if
-    // `sandbox_policy.has_full_disk_read_access()` is `false`.
+    let full_read = sandbox_policy.has_full_disk_read_access();
+    let full_write = sandbox_policy.has_full_disk_write_access();
+    if !full_read || !full_write {
+        let writable_roots = if full_write {
+            vec![PathBuf::from("/")]
+        } else {
+            sandbox_policy.get_writable_roots_with_cwd(cwd)
+        };
+        let readable_roots = if full_read {
+            vec![PathBuf::from("/")]
+        } else {
+            // Readable folders granted by the policy (`--focus`) replace the
+            // working directory.
+            let mut granted = sandbox_policy.get_readable_roots();
+            if granted.is_empty() {
+                granted.push(cwd.to_path_buf());
+            }
+            // Writable folders can be read too, but full write access leaves
+            // reads restricted.
+            let writable_folders = if full_write {
+                Vec::new()
+            } else {
+                writable_roots.clone()
+            };
+            SYSTEM_READ_ROOTS
+                .iter()
+                .map(PathBuf::from)
+                .chain(granted)
+                .chain(read_only_paths.iter().cloned())
+                .chain(writable_folders)
+                .collect()
+        };
+        install_filesystem_landlock_rules_on_current_thread(readable_roots, writable_roots)?;
+    }
 
     Ok(())
 }
 
 /// Installs Landlock file-system rules on the current thread allowing read
-/// access to the entire file-system while restricting write access to
-/// `/dev/null` and the provided list of `writable_roots`.
+/// access to `readable_roots` while restricting write access to `/dev/null`
+/// and the provided list of `writable_roots`, which are not made readable.
+/// Roots that do not exist are skipped.
 ///
 /// # Errors
 /// Returns [`CodexErr::Sandbox`] variants when the ruleset fails to apply.
-fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathBuf>) -> Result<()> {
+fn install_filesystem_landlock_rules_on_current_thread(
+    readable_roots: Vec<PathBuf>,
+    writable_roots: Vec<PathBuf>,
+) -> Result<()> {
     let abi = ABI::V5;
     let access_rw = AccessFs::from_all(abi);
     let access_ro = AccessFs::from_read(abi);
+    let access_wo = AccessFs::from_write(abi);
+    let readable_roots: Vec<PathBuf> = readable_roots
+        .into_iter()
+        .filter(|root| root.exists())
+        .collect();
+    let writable_roots: Vec<PathBuf> = writable_roots
+        .into_iter()
+        .filter(|root| root.exists())
+        .collect();
 
     let mut ruleset = Ruleset::default()
         .set_compatibility(CompatLevel::BestEffort)
         .handle_access(access_rw)?
         .create()?
-        .add_rules(landlock::path_beneath_rules(&["/"], access_ro))?
+        .add_rules(landlock::path_beneath_rules(&readable_roots, access_ro))?
         .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
         .set_no_new_privs(true);
 
     if !writable_roots.is_empty() {
-        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_rw))?;
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_wo))?;
     }
 
     let status = ruleset.restrict_self()?;
@@ -78,6 +131,57 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
+/// Installs a seccomp filter that makes the system calls of
+/// `blocked_syscalls` fail with `EPERM`.
+fn install_syscall_seccomp_filter_on_current_thread(
+    blocked_syscalls: &[SyscallClass],
+) -> std::result::Result<(), SandboxErr> {
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
+    for class in blocked_syscalls {
+        let syscalls: &[i64] = match class {
+            SyscallClass::Mount => &[libc::SYS_mount, libc::SYS_umount2, libc::SYS_pivot_root],
+            SyscallClass::KernelModules => &[
+                libc::SYS_init_module,
+                libc::SYS_finit_module,
+                libc::SYS_delete_module,
+            ],
+            SyscallClass::Keyring => &[libc::SYS_add_key, libc::SYS_keyctl, libc::SYS_request_key],
+            SyscallClass::Namespaces => &[libc::SYS_unshare, libc::SYS_setns],
+            SyscallClass::Bpf => &[libc::SYS_bpf, libc::SYS_perf_event_open],
+            SyscallClass::Reboot => &[
+                libc::SYS_reboot,
+                libc::SYS_kexec_load,
+                libc::SYS_swapon,
+                libc::SYS_swapoff,
+            ],
+        };
+        for nr in syscalls {
+            rules.insert(*nr, vec![]); // empty rule vec = unconditional match
+        }
+    }
+
+    let filter = SeccompFilter::new(
+        rules,
+        SeccompAction::Allow,                     // default – allow
+        SeccompAction::Errno(libc::EPERM as u32), // when rule matches – return EPERM
+        seccomp_target_arch()?,
+    )?;
+    let prog: BpfProgram = filter.try_into()?;
+    apply_filter(&prog)?;
+
+    Ok(())
+}
+
+fn seccomp_target_arch() -> std::result::Result<TargetArch, SandboxErr> {
+    if cfg!(target_arch = "x86_64") {
+        Ok(TargetArch::x86_64)
+    } else if cfg!(target_arch = "aarch64") {
+        Ok(TargetArch::aarch64)
+    } else {
+        Err(SandboxErr::SeccompUnsupportedArch(std::env::consts::ARCH))
+    }
+}
+
 /// Installs a seccomp filter that blocks outbound network access except for
 /// AF_UNIX domain sockets.
 fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
@@ -122,13 +226,7 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         rules,
         SeccompAction::Allow,                     // default – allow
         SeccompAction::Errno(libc::EPERM as u32), // when rule matches – return EPERM
-        if cfg!(target_arch = "x86_64") {
-            TargetArch::x86_64
-        } else if cfg!(target_arch = "aarch64") {
-            TargetArch::aarch64
-        } else {
-            unimplemented!("unsupported architecture for seccomp filter");
-        },
+        seccomp_target_arch()?,
     )?;
 
     let prog: BpfProgram = filter.try_into()?;
@@ -1,6 +1,8 @@
 use clap::Parser;
 use codex_common::SandboxPermissionOption;
+use codex_core::config_types::SyscallClass;
 use std::ffi::CString;
+use std::path::PathBuf;
 
 use crate::landlock::apply_sandbox_policy_to_current_thread;
 
@@ -9,13 +11,28 @@ pub struct LandlockCommand {
     #[clap(flatten)]
     pub sandbox: SandboxPermissionOption,
 
+    /// Path the command may read when `disk-full-read-access` is not
+    /// granted. Can be given more than once.
+    #[arg(long = "read-only-path")]
+    pub read_only_paths: Vec<PathBuf>,
+
+    /// Class of system calls to block, e.g. `mount`. Can be given more than
+    /// once.
+    #[arg(long = "block-syscalls")]
+    pub blocked_syscalls: Vec<SyscallClass>,
+
     /// Full command args to run under landlock.
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
 }
 
 pub fn run_main() -> ! {
-    let LandlockCommand { sandbox, command } = LandlockCommand::parse();
+    let LandlockCommand {
+        sandbox,
+        read_only_paths,
+        blocked_syscalls,
+        command,
+    } = LandlockCommand::parse();
 
     let sandbox_policy = match sandbox.permissions.map(Into::into) {
         Some(sandbox_policy) => sandbox_policy,
@@ -29,7 +46,12 @@ pub fn run_main() -> ! {
         }
     };
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &cwd) {
+    if let Err(e) = apply_sandbox_policy_to_current_thread(
+        &sandbox_policy,
+        &cwd,
+        &read_only_paths,
+        &blocked_syscalls,
+    ) {
         panic!("error running landlock: {e:?}");
     }
 
@@ -1,6 +1,8 @@
 #![cfg(target_os = "linux")]
 #![expect(clippy::unwrap_used, clippy::expect_used)]
 
+use codex_core::config_types::LinuxSandbox;
+use codex_core::config_types::MacosSeatbelt;
 use codex_core::config_types::ShellEnvironmentPolicy;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
@@ -8,6 +10,7 @@ use codex_core::exec::ExecParams;
 use codex_core::exec::SandboxType;
 use codex_core::exec::process_exec_tool_call;
 use codex_core::exec_env::create_env;
+use codex_core::protocol::SandboxPermission;
 use codex_core::protocol::SandboxPolicy;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -39,6 +42,8 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
         ctrl_c,
         &sandbox_policy,
         &codex_linux_sandbox_exe,
+        &LinuxSandbox::default(),
+        &MacosSeatbelt::default(),
     )
     .await
     .unwrap();
@@ -104,6 +109,106 @@ async fn test_timeout() {
     run_cmd(&["sleep", "2"], &[], 50).await;
 }
 
+/// Exit code of `cmd` in a sandbox without `disk-full-read-access`. The
+/// policy grants something, as the sandbox falls back to the read-only policy
+/// when it is given no permissions at all.
+async fn exit_code_without_full_read(cmd: &[&str], linux_sandbox: &LinuxSandbox) -> i32 {
+    let sandbox_policy = SandboxPolicy::from(vec![SandboxPermission::DiskWriteCwd]);
+    exit_code_under_policy(cmd, &sandbox_policy, linux_sandbox).await
+}
+
+async fn exit_code_under_policy(
+    cmd: &[&str],
+    sandbox_policy: &SandboxPolicy,
+    linux_sandbox: &LinuxSandbox,
+) -> i32 {
+    let params = ExecParams {
+        command: cmd.iter().map(|elm| elm.to_string()).collect(),
+        cwd: std::env::current_dir().expect("cwd should exist"),
+        timeout_ms: Some(1_000),
+        env: create_env_from_core_vars(),
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        Arc::new(Notify::new()),
+        sandbox_policy,
+        &codex_linux_sandbox_exe,
+        linux_sandbox,
+        &MacosSeatbelt::default(),
+    )
+    .await;
+    match result {
+        Ok(output) => output.exit_code,
+        Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, _, _))) => exit_code,
+        Err(e) => panic!("unexpected error: {e:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_read_only_paths() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("notes.txt");
+    std::fs::write(&file_path, "hello").unwrap();
+    let cat = ["cat", file_path.to_str().unwrap()];
+
+    assert_ne!(
+        exit_code_without_full_read(&cat, &LinuxSandbox::default()).await,
+        0
+    );
+    let linux_sandbox = LinuxSandbox {
+        read_only_paths: vec![tmpdir.path().to_path_buf()],
+        ..Default::default()
+    };
+    assert_eq!(exit_code_without_full_read(&cat, &linux_sandbox).await, 0);
+}
+
+#[tokio::test]
+async fn test_focused_reads() {
+    let focus = tempfile::tempdir().unwrap();
+    let file_path = focus.path().join("notes.txt");
+    std::fs::write(&file_path, "hello").unwrap();
+    let sandbox_policy = SandboxPolicy::new_read_only_policy().with_reads_limited_to(focus.path());
+
+    let cat = ["cat", file_path.to_str().unwrap()];
+    assert_eq!(
+        exit_code_under_policy(&cat, &sandbox_policy, &LinuxSandbox::default()).await,
+        0
+    );
+    // The working directory, outside the focus, is no longer readable.
+    assert_ne!(
+        exit_code_under_policy(
+            &["cat", "Cargo.toml"],
+            &sandbox_policy,
+            &LinuxSandbox::default()
+        )
+        .await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_full_write_keeps_reads_restricted() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("notes.txt");
+    std::fs::write(&file_path, "hello").unwrap();
+    let path = file_path.to_str().unwrap();
+    let sandbox_policy = SandboxPolicy::from(vec![SandboxPermission::DiskFullWriteAccess]);
+
+    let write = ["bash", "-c", &format!("echo goodbye > {path}")];
+    assert_eq!(
+        exit_code_under_policy(&write, &sandbox_policy, &LinuxSandbox::default()).await,
+        0
+    );
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "goodbye\n");
+    assert_ne!(
+        exit_code_under_policy(&["cat", path], &sandbox_policy, &LinuxSandbox::default()).await,
+        0
+    );
+}
+
 /// Helper that runs `cmd` under the Linux sandbox and asserts that the command
 /// does NOT succeed (i.e. returns a non‑zero exit code) **unless** the binary
 /// is missing in which case we silently treat it as an accepted skip so the
@@ -129,6 +234,8 @@ async fn assert_network_blocked(cmd: &[&str]) {
         ctrl_c,
         &sandbox_policy,
         &codex_linux_sandbox_exe,
+        &LinuxSandbox::default(),
+        &MacosSeatbelt::default(),
     )
     .await;
 
@@ -169,6 +169,14 @@ impl CodexToolCallParam {
             sandbox_policy,
             model_provider: None,
             codex_linux_sandbox_exe,
+            mode: None,
+            dry_run: None,
+            focus: None,
+            offline: None,
+            temperature: None,
+            seed: None,
+            event_sink: None,
+            apply_mode: None,
         };
 
         let cli_overrides = cli_overrides
@@ -71,6 +71,7 @@ pub async fn run_codex_tool_session(
             items: vec![InputItem::Text {
                 text: initial_prompt.clone(),
             }],
+            response_format: None,
         })
         .await
     {
@@ -90,7 +91,7 @@ pub async fn run_codex_tool_session(
                     EventMsg::AgentMessage(AgentMessageEvent { message }) => {
                         last_agent_message = Some(message.clone());
                     }
-                    EventMsg::ExecApprovalRequest(_) => {
+                    EventMsg::ExecApprovalRequest(_) | EventMsg::SandboxEscalationRequest(_) => {
                         let result = CallToolResult {
                             content: vec![CallToolResultContent::TextContent(TextContent {
                                 r#type: "text".to_string(),
@@ -126,9 +127,25 @@ pub async fn run_codex_tool_session(
                             .await;
                         break;
                     }
-                    EventMsg::TaskComplete(TaskCompleteEvent {
-                        last_agent_message: _,
-                    }) => {
+                    EventMsg::TurnDiffApprovalRequest(_) => {
+                        let result = CallToolResult {
+                            content: vec![CallToolResultContent::TextContent(TextContent {
+                                r#type: "text".to_string(),
+                                text: "TURN_DIFF_APPROVAL_REQUIRED".to_string(),
+                                annotations: None,
+                            })],
+                            is_error: None,
+                        };
+                        let _ = outgoing
+                            .send(JSONRPCMessage::Response(JSONRPCResponse {
+                                jsonrpc: JSONRPC_VERSION.into(),
+                                id: id.clone(),
+                                result: result.into(),
+                            }))
+                            .await;
+                        break;
+                    }
+                    EventMsg::TaskComplete(TaskCompleteEvent { .. }) => {
                         let result = if let Some(msg) = last_agent_message {
                             CallToolResult {
                                 content: vec![CallToolResultContent::TextContent(TextContent {
@@ -163,6 +180,8 @@ pub async fn run_codex_tool_session(
                     EventMsg::Error(_)
                     | EventMsg::TaskStarted
                     | EventMsg::AgentReasoning(_)
+                    | EventMsg::AgentReasoningDelta(_)
+                    | EventMsg::AgentMessageDelta(_)
                     | EventMsg::McpToolCallBegin(_)
                     | EventMsg::McpToolCallEnd(_)
                     | EventMsg::ExecCommandBegin(_)
@@ -170,9 +189,26 @@ pub async fn run_codex_tool_session(
                     | EventMsg::BackgroundEvent(_)
                     | EventMsg::PatchApplyBegin(_)
                     | EventMsg::PatchApplyEnd(_)
+                    | EventMsg::PatchProposed(_)
                     | EventMsg::GetHistoryEntryResponse(_)
+                    | EventMsg::Handshake(_)
+                    | EventMsg::Unknown
                     | EventMsg::CopilotAuthStarted(_)
-                    | EventMsg::CopilotAuthComplete(_) => {
+                    | EventMsg::CopilotAuthProgress(_)
+                    | EventMsg::CopilotAuthComplete(_)
+                    | EventMsg::TurnContextOverridden(_)
+                    | EventMsg::CopilotPlanDetected(_)
+                    | EventMsg::ChatGptAuthStarted(_)
+                    | EventMsg::ChatGptAuthComplete(_)
+                    | EventMsg::TokenCount(_)
+                    | EventMsg::SubAgent(_)
+                    | EventMsg::PlanUpdated(_)
+                    | EventMsg::SecretsRedacted(_)
+                    | EventMsg::ToolCallRepair(_)
+                    | EventMsg::CheckpointCreated(_)
+                    | EventMsg::Rewound(_)
+                    | EventMsg::DryRunExec(_)
+                    | EventMsg::RateLimited(_) => {
                         // For now, we do not do anything extra for these
                         // events. Note that
                         // send(codex_event_to_notification(&event)) above has
@@ -1,11 +1,14 @@
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::chatwidget::ChatWidget;
+use crate::external_editor;
 use crate::git_warning_screen::GitWarningOutcome;
 use crate::git_warning_screen::GitWarningScreen;
+use crate::keybindings::KeyBindings;
 use crate::mouse_capture::MouseCapture;
+use crate::replay::Player;
+use crate::replay::Replay;
 use crate::scroll_event_helper::ScrollEventHelper;
-use crate::slash_command::SlashCommand;
 use crate::tui;
 // used by ChatWidgetArgs
 use codex_core::config::Config;
@@ -17,8 +20,12 @@ use crossterm::event::KeyEvent;
 use crossterm::event::MouseEvent;
 use crossterm::event::MouseEventKind;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::channel;
+use std::time::Duration;
 
 /// Top-level application state: which full-screen view is currently active.
 #[allow(clippy::large_enum_variant)]
@@ -41,6 +48,15 @@ pub(crate) struct App<'a> {
     /// Stored parameters needed to instantiate the ChatWidget later, e.g.,
     /// after dismissing the Git-repo warning.
     chat_args: Option<ChatWidgetArgs>,
+
+    keybindings: KeyBindings,
+
+    /// Set while another program (e.g. `$EDITOR`) owns the terminal so the
+    /// input thread leaves its keystrokes alone.
+    input_paused: Arc<AtomicBool>,
+
+    /// Playback of `codex replay`, which takes all key events.
+    player: Option<Player>,
 }
 
 /// Aggregate parameters needed to create a `ChatWidget`, as creation may be
@@ -48,6 +64,7 @@ pub(crate) struct App<'a> {
 #[derive(Clone)]
 struct ChatWidgetArgs {
     config: Config,
+    keybindings: KeyBindings,
     initial_prompt: Option<String>,
     initial_images: Vec<PathBuf>,
 }
@@ -55,9 +72,11 @@ struct ChatWidgetArgs {
 impl<'a> App<'a> {
     pub(crate) fn new(
         config: Config,
+        keybindings: KeyBindings,
         initial_prompt: Option<String>,
         show_git_warning: bool,
         initial_images: Vec<std::path::PathBuf>,
+        replay: Option<Replay>,
     ) -> Self {
         let (app_event_tx, app_event_rx) = channel();
         let app_event_tx = AppEventSender::new(app_event_tx);
@@ -65,10 +84,26 @@ impl<'a> App<'a> {
 
         // Spawn a dedicated thread for reading the crossterm event loop and
         // re-publishing the events as AppEvents, as appropriate.
+        let input_paused = Arc::new(AtomicBool::new(false));
         {
             let app_event_tx = app_event_tx.clone();
+            let input_paused = Arc::clone(&input_paused);
             std::thread::spawn(move || {
-                while let Ok(event) = crossterm::event::read() {
+                loop {
+                    // Poll rather than block in `read()` so that a pause takes
+                    // effect before the next event is consumed.
+                    match crossterm::event::poll(Duration::from_millis(100)) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(_) => break,
+                    }
+                    if input_paused.load(Ordering::Relaxed) {
+                        std::thread::sleep(Duration::from_millis(50));
+                        continue;
+                    }
+                    let Ok(event) = crossterm::event::read() else {
+                        break;
+                    };
                     match event {
                         crossterm::event::Event::Key(key_event) => {
                             app_event_tx.send(AppEvent::KeyEvent(key_event));
@@ -89,21 +124,13 @@ impl<'a> App<'a> {
                             scroll_event_helper.scroll_down();
                         }
                         crossterm::event::Event::Paste(pasted) => {
-                            use crossterm::event::KeyModifiers;
-
-                            for ch in pasted.chars() {
-                                let key_event = match ch {
-                                    '\n' | '\r' => {
-                                        // Represent newline as <Shift+Enter> so that the bottom
-                                        // pane treats it as a literal newline instead of a submit
-                                        // action (submission is only triggered on Enter *without*
-                                        // any modifiers).
-                                        KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT)
-                                    }
-                                    _ => KeyEvent::new(KeyCode::Char(ch), KeyModifiers::empty()),
-                                };
-                                app_event_tx.send(AppEvent::KeyEvent(key_event));
-                            }
+                            app_event_tx.send(AppEvent::Paste(pasted));
+                        }
+                        crossterm::event::Event::FocusGained => {
+                            app_event_tx.send(AppEvent::FocusChanged(true));
+                        }
+                        crossterm::event::Event::FocusLost => {
+                            app_event_tx.send(AppEvent::FocusChanged(false));
                         }
                         _ => {
                             // Ignore any other events.
@@ -113,20 +140,34 @@ impl<'a> App<'a> {
             });
         }
 
-        let (app_state, chat_args) = if show_git_warning {
+        let (app_state, chat_args) = if replay.is_some() {
+            let widget = ChatWidget::new_replay(config, keybindings, app_event_tx.clone());
+            (
+                AppState::Chat {
+                    widget: Box::new(widget),
+                },
+                None,
+            )
+        } else if show_git_warning {
             (
                 AppState::GitWarning {
                     screen: GitWarningScreen::new(),
                 },
                 Some(ChatWidgetArgs {
                     config,
+                    keybindings,
                     initial_prompt,
                     initial_images,
                 }),
             )
         } else {
-            let chat_widget =
-                ChatWidget::new(config, app_event_tx.clone(), initial_prompt, initial_images);
+            let chat_widget = ChatWidget::new(
+                config,
+                keybindings,
+                app_event_tx.clone(),
+                initial_prompt,
+                initial_images,
+            );
             (
                 AppState::Chat {
                     widget: Box::new(chat_widget),
@@ -134,12 +175,16 @@ impl<'a> App<'a> {
                 None,
             )
         };
+        let player = replay.map(|replay| Player::spawn(replay, app_event_tx.clone()));
 
         Self {
             app_event_tx,
             app_event_rx,
             app_state,
             chat_args,
+            keybindings,
+            input_paused,
+            player,
         }
     }
 
@@ -164,16 +209,16 @@ impl<'a> App<'a> {
                     self.draw_next_frame(terminal)?;
                 }
                 AppEvent::KeyEvent(key_event) => {
+                    if let Some(player) = &self.player {
+                        player.handle_key_event(key_event);
+                        continue;
+                    }
                     match key_event {
-                        KeyEvent {
-                            code: KeyCode::Char('c'),
-                            modifiers: crossterm::event::KeyModifiers::CONTROL,
-                            ..
-                        } => {
+                        key_event if self.keybindings.interrupt.matches(&key_event) => {
                             // Forward interrupt to ChatWidget when active.
                             match &mut self.app_state {
                                 AppState::Chat { widget } => {
-                                    widget.submit_op(Op::Interrupt);
+                                    widget.submit_op(Op::Interrupt { replace_with: None });
                                 }
                                 AppState::GitWarning { .. } => {
                                     // No-op.
@@ -192,6 +237,14 @@ impl<'a> App<'a> {
                         }
                     };
                 }
+                AppEvent::Paste(pasted) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.handle_paste(pasted),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::FocusChanged(focused) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_terminal_focused(focused),
+                    AppState::GitWarning { .. } => {}
+                },
                 AppEvent::Scroll(scroll_delta) => {
                     self.dispatch_scroll_event(scroll_delta);
                 }
@@ -209,19 +262,76 @@ impl<'a> App<'a> {
                     AppState::Chat { widget } => widget.update_latest_log(line),
                     AppState::GitWarning { .. } => {}
                 },
-                AppEvent::DispatchCommand(command) => match command {
-                    SlashCommand::Clear => match &mut self.app_state {
-                        AppState::Chat { widget } => widget.clear_conversation_history(),
-                        AppState::GitWarning { .. } => {}
-                    },
-                    SlashCommand::ToggleMouseMode => {
-                        if let Err(e) = mouse_capture.toggle() {
-                            tracing::error!("Failed to toggle mouse mode: {e}");
-                        }
+                AppEvent::DispatchCommand { command, args } => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.dispatch_command(command.as_ref(), &args),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::SubmitPrompt(text) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.submit_prompt(text),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::ClearConversation => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.clear_conversation_history(),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::ToggleMouseMode => {
+                    if let Err(e) = mouse_capture.toggle() {
+                        tracing::error!("Failed to toggle mouse mode: {e}");
                     }
-                    SlashCommand::Quit => {
-                        break;
+                }
+                AppEvent::ShowAttachImageView => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.show_attach_image_view(),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::OpenExternalEditor(draft) => {
+                    let edited = self.run_external_editor(terminal, mouse_capture, &draft);
+                    if let AppState::Chat { widget } = &mut self.app_state {
+                        match edited {
+                            Ok(text) => widget.set_composer_text(&text),
+                            Err(e) => widget.add_info_message(format!("Editor failed: {e}")),
+                        }
                     }
+                    self.app_event_tx.send(AppEvent::Redraw);
+                }
+                AppEvent::InfoMessage(message) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.add_info_message(message),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::SetModel(model) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_model(model),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::SetApprovalPolicy(policy) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_approval_policy(policy),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::SetMode(mode) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_mode(mode),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::SetDryRun(dry_run) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_dry_run(dry_run),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::ShowDiff => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.show_diff(),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::DiffReady(files) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.on_diff_ready(files),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::WorkspaceIndexReady(index) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_workspace_index(index),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::ReplayUserMessage(text) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.add_replayed_user_message(text),
+                    AppState::GitWarning { .. } => {}
+                },
+                AppEvent::ReplayStatus(status) => match &mut self.app_state {
+                    AppState::Chat { widget } => widget.set_replay_status(status),
+                    AppState::GitWarning { .. } => {}
                 },
             }
         }
@@ -230,6 +340,28 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Suspend the TUI, let the user edit `draft` in `$EDITOR`, then take the
+    /// terminal back.
+    fn run_external_editor(
+        &self,
+        terminal: &mut tui::Tui,
+        mouse_capture: &mut MouseCapture,
+        draft: &str,
+    ) -> std::io::Result<String> {
+        let mouse_was_active = mouse_capture.is_active();
+        self.input_paused.store(true, Ordering::Relaxed);
+        let edited = mouse_capture
+            .disable()
+            .and_then(|()| tui::suspend())
+            .and_then(|()| external_editor::edit_text(draft));
+
+        let resumed =
+            tui::resume(terminal).and_then(|()| mouse_capture.set_active(mouse_was_active));
+        self.input_paused.store(false, Ordering::Relaxed);
+        resumed?;
+        edited
+    }
+
     fn draw_next_frame(&mut self, terminal: &mut tui::Tui) -> Result<()> {
         match &mut self.app_state {
             AppState::Chat { widget } => {
@@ -259,6 +391,7 @@ impl<'a> App<'a> {
 
                     let widget = Box::new(ChatWidget::new(
                         args.config,
+                        args.keybindings,
                         self.app_event_tx.clone(),
                         args.initial_prompt,
                         args.initial_images,
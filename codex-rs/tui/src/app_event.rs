@@ -1,6 +1,12 @@
+use std::sync::Arc;
+
+use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
+use codex_core::protocol::SessionMode;
+use codex_core::workspace_index::WorkspaceIndex;
 use crossterm::event::KeyEvent;
 
+use crate::diff_view::FileDiff;
 use crate::slash_command::SlashCommand;
 
 #[allow(clippy::large_enum_variant)]
@@ -11,6 +17,12 @@ pub(crate) enum AppEvent {
 
     KeyEvent(KeyEvent),
 
+    /// Text delivered via bracketed paste.
+    Paste(String),
+
+    /// The terminal window gained (`true`) or lost (`false`) focus.
+    FocusChanged(bool),
+
     /// Scroll event with a value representing the "scroll delta" as the net
     /// scroll up/down events within a short time window.
     Scroll(i32),
@@ -27,5 +39,58 @@ pub(crate) enum AppEvent {
 
     /// Dispatch a recognized slash command from the UI (composer) to the app
     /// layer so it can be handled centrally.
-    DispatchCommand(SlashCommand),
+    DispatchCommand {
+        command: Arc<dyn SlashCommand>,
+        args: String,
+    },
+
+    /// Submit text as if the user had typed it into the composer (used by
+    /// custom prompt commands).
+    SubmitPrompt(String),
+
+    /// Clear the conversation history shown in the UI.
+    ClearConversation,
+
+    /// Toggle mouse capture (scrolling vs. text selection).
+    ToggleMouseMode,
+
+    /// Open the prompt for attaching an image to the next message.
+    ShowAttachImageView,
+
+    /// Open the draft in `$VISUAL` / `$EDITOR`; the edited text replaces the
+    /// composer contents.
+    OpenExternalEditor(String),
+
+    /// Show an informational message in the conversation history.
+    InfoMessage(String),
+
+    /// Switch the model used for subsequent turns.
+    SetModel(String),
+
+    /// Change when the agent must ask before running commands.
+    SetApprovalPolicy(AskForApproval),
+
+    /// Switch between ask (read-only) and code mode.
+    SetMode(SessionMode),
+
+    /// Turn dry run, where shell commands are shown but not executed, on or
+    /// off.
+    SetDryRun(bool),
+
+    /// Show the git diff of the current working directory.
+    ShowDiff,
+
+    /// The git diff computed in the background for the diff viewer, split
+    /// per file; `None` outside a Git repository.
+    DiffReady(Result<Option<Vec<FileDiff>>, String>),
+
+    /// The background scan of the working directory used for `@mention`
+    /// completion has finished.
+    WorkspaceIndexReady(Arc<WorkspaceIndex>),
+
+    /// A prompt of the session being replayed, shown as if just submitted.
+    ReplayUserMessage(String),
+
+    /// Progress and controls of the replay, shown in place of the composer.
+    ReplayStatus(String),
 }
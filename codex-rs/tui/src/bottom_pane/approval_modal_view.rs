@@ -4,6 +4,7 @@ use ratatui::layout::Rect;
 use ratatui::widgets::WidgetRef;
 
 use crate::app_event_sender::AppEventSender;
+use crate::keybindings::KeyBindings;
 use crate::user_approval_widget::ApprovalRequest;
 use crate::user_approval_widget::UserApprovalWidget;
 
@@ -15,14 +16,20 @@ pub(crate) struct ApprovalModalView<'a> {
     current: UserApprovalWidget<'a>,
     queue: Vec<ApprovalRequest>,
     app_event_tx: AppEventSender,
+    keybindings: KeyBindings,
 }
 
 impl ApprovalModalView<'_> {
-    pub fn new(request: ApprovalRequest, app_event_tx: AppEventSender) -> Self {
+    pub fn new(
+        request: ApprovalRequest,
+        app_event_tx: AppEventSender,
+        keybindings: KeyBindings,
+    ) -> Self {
         Self {
-            current: UserApprovalWidget::new(request, app_event_tx.clone()),
+            current: UserApprovalWidget::new(request, app_event_tx.clone(), keybindings),
             queue: Vec::new(),
             app_event_tx,
+            keybindings,
         }
     }
 
@@ -34,7 +41,8 @@ impl ApprovalModalView<'_> {
     fn maybe_advance(&mut self) {
         if self.current.is_complete() {
             if let Some(req) = self.queue.pop() {
-                self.current = UserApprovalWidget::new(req, self.app_event_tx.clone());
+                self.current =
+                    UserApprovalWidget::new(req, self.app_event_tx.clone(), self.keybindings);
             }
         }
     }
@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use tui_input::Input;
+use tui_input::backend::crossterm::EventHandler;
+
+use super::BottomPane;
+use super::BottomPaneView;
+
+use crate::theme::theme;
+
+/// Rows consumed by the border.
+const BORDER_LINES: u16 = 2;
+
+/// Single-line prompt that asks for the path of an image to attach to the
+/// next message. Invalid paths keep the prompt open and show the reason.
+pub(crate) struct AttachImageView {
+    input: Input,
+    error: Option<String>,
+    done: bool,
+}
+
+impl AttachImageView {
+    pub fn new() -> Self {
+        Self {
+            input: Input::default(),
+            error: None,
+            done: false,
+        }
+    }
+}
+
+/// Validate that `raw` points at a readable file in an image format we know
+/// how to encode. Returns the path on success or a user-facing error.
+pub(crate) fn validate_image_path(raw: &str) -> Result<PathBuf, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err("enter a path to an image file".to_string());
+    }
+    let path = PathBuf::from(raw);
+    if !path.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    if image::ImageFormat::from_path(&path).is_err() {
+        return Err(format!(
+            "{} is not a supported image format",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+impl<'a> BottomPaneView<'a> for AttachImageView {
+    fn handle_key_event(&mut self, pane: &mut BottomPane<'a>, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Esc => {
+                self.done = true;
+            }
+            KeyCode::Enter => match validate_image_path(self.input.value()) {
+                Ok(path) => {
+                    pane.attach_image(path);
+                    self.done = true;
+                }
+                Err(err) => {
+                    self.error = Some(err);
+                }
+            },
+            _ => {
+                self.error = None;
+                self.input
+                    .handle_event(&crossterm::event::Event::Key(key_event));
+            }
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.done
+    }
+
+    fn calculate_required_height(&self, _area: &Rect) -> u16 {
+        let error_rows = u16::from(self.error.is_some());
+        1 + error_rows + BORDER_LINES
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let mut lines = vec![Line::from(self.input.value().to_string())];
+        if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone().fg(theme().error)));
+        }
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Attach image (Enter to attach, Esc to cancel)")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn rejects_missing_and_non_image_files() {
+        assert!(validate_image_path("").is_err());
+        assert!(validate_image_path("/definitely/not/here.png").is_err());
+
+        let dir = TempDir::new().unwrap();
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, "hello").unwrap();
+        assert!(validate_image_path(text.to_str().unwrap()).is_err());
+
+        let png = dir.path().join("shot.png");
+        std::fs::write(&png, b"not really a png").unwrap();
+        assert_eq!(validate_image_path(png.to_str().unwrap()), Ok(png));
+    }
+}
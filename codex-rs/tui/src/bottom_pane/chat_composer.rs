@@ -1,3 +1,8 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_core::workspace_index::WorkspaceIndex;
+
 use crossterm::event::KeyEvent;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Alignment;
@@ -9,36 +14,103 @@ use ratatui::widgets::BorderType;
 use ratatui::widgets::Borders;
 use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
+use tempfile::TempPath;
+use tui_textarea::CursorMove;
 use tui_textarea::Input;
 use tui_textarea::Key;
 use tui_textarea::TextArea;
 
 use super::chat_composer_history::ChatComposerHistory;
 use super::command_popup::CommandPopup;
+use super::file_search_popup::FileSearchPopup;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::clipboard_paste::paste_image_to_temp_png;
+use crate::clipboard_paste::pasted_image_path;
+use crate::keybindings::KeyBindings;
+use crate::keybindings::KeyChord;
+use crate::slash_command::SlashCommandRegistry;
+use crate::slash_command::parse_slash_command;
+use crate::theme::theme;
 
 /// Minimum number of visible text rows inside the textarea.
 const MIN_TEXTAREA_ROWS: usize = 1;
 /// Rows consumed by the border.
 const BORDER_LINES: u16 = 2;
+/// Pastes longer than this many characters are shown as a one-line
+/// placeholder and expanded when the message is submitted.
+const LARGE_PASTE_CHAR_THRESHOLD: usize = 1000;
+
+/// An `@mention` being typed: the word spans `start..end` (in chars) on line
+/// `row`, and `query` is the text after the `@`.
+struct MentionToken {
+    row: usize,
+    start: usize,
+    end: usize,
+    query: String,
+}
 
 /// Result returned when the user interacts with the text area.
 pub enum InputResult {
-    Submitted(String),
+    Submitted {
+        text: String,
+        image_paths: Vec<PathBuf>,
+    },
+    /// Esc while a task is running: interrupt it and continue with the
+    /// composer contents, if any.
+    Steer {
+        text: String,
+        image_paths: Vec<PathBuf>,
+    },
     None,
 }
 
+/// An image attached to the pending message. The placeholder is inserted
+/// into the textarea; deleting it drops the attachment.
+struct AttachedImage {
+    placeholder: String,
+    path: PathBuf,
+    /// The temporary file of an image pasted from the clipboard, deleted
+    /// along with the attachment.
+    temp_file: Option<TempPath>,
+}
+
+/// A large paste collapsed into a placeholder in the textarea.
+struct PendingPaste {
+    placeholder: String,
+    text: String,
+}
+
 pub(crate) struct ChatComposer<'a> {
     textarea: TextArea<'a>,
     command_popup: Option<CommandPopup>,
+    /// Completion popup for the `@mention` under the cursor.
+    file_popup: Option<FileSearchPopup>,
+    /// Files available for `@mention` completion; `None` until the background
+    /// index build finishes.
+    workspace_index: Option<Arc<WorkspaceIndex>>,
+    slash_commands: SlashCommandRegistry,
     app_event_tx: AppEventSender,
     history: ChatComposerHistory,
+    /// Images attached to the message currently being composed.
+    attached_images: Vec<AttachedImage>,
+    /// Pasted images of sent messages, which the agent reads once it gets to
+    /// them; deleted when the session ends.
+    sent_temp_images: Vec<TempPath>,
+    /// Large pastes in the message currently being composed.
+    pending_pastes: Vec<PendingPaste>,
+    submit_key: KeyChord,
+    has_input_focus: bool,
 }
 
 impl ChatComposer<'_> {
-    pub fn new(has_input_focus: bool, app_event_tx: AppEventSender) -> Self {
+    pub fn new(
+        has_input_focus: bool,
+        app_event_tx: AppEventSender,
+        slash_commands: SlashCommandRegistry,
+        keybindings: KeyBindings,
+    ) -> Self {
         let mut textarea = TextArea::default();
         textarea.set_placeholder_text("send a message");
         textarea.set_cursor_line_style(ratatui::style::Style::default());
@@ -46,8 +118,16 @@ impl ChatComposer<'_> {
         let mut this = Self {
             textarea,
             command_popup: None,
+            file_popup: None,
+            workspace_index: None,
+            slash_commands,
             app_event_tx,
             history: ChatComposerHistory::new(),
+            attached_images: Vec::new(),
+            sent_temp_images: Vec::new(),
+            pending_pastes: Vec::new(),
+            submit_key: keybindings.submit,
+            has_input_focus,
         };
         this.update_border(has_input_focus);
         this
@@ -72,23 +152,238 @@ impl ChatComposer<'_> {
             .on_entry_response(log_id, offset, entry, &mut self.textarea)
     }
 
+    /// Provide the index used to complete `@mentions`.
+    pub(crate) fn set_workspace_index(&mut self, index: Arc<WorkspaceIndex>) {
+        self.workspace_index = Some(index);
+        self.sync_file_popup();
+    }
+
     pub fn set_input_focus(&mut self, has_focus: bool) {
+        self.has_input_focus = has_focus;
         self.update_border(has_focus);
     }
 
+    /// Attach an image to the message currently being composed and insert
+    /// an inline placeholder for it at the cursor.
+    pub(crate) fn attach_image(&mut self, path: PathBuf, label: Option<String>) {
+        let label = label.unwrap_or_else(|| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string())
+        });
+        self.attach(path, label, None);
+    }
+
+    fn attach(&mut self, path: PathBuf, label: String, temp_file: Option<TempPath>) {
+        let placeholder = format!("[image: {label}]");
+        self.textarea.insert_str(format!("{placeholder} "));
+        self.attached_images.push(AttachedImage {
+            placeholder,
+            path,
+            temp_file,
+        });
+        self.update_border(self.has_input_focus);
+    }
+
+    /// Handle a bracketed paste. Pasting the path of an image file (which is
+    /// what terminals send when a file is dragged in) attaches the image;
+    /// large pastes are collapsed into a placeholder; anything else is
+    /// inserted as text.
+    pub(crate) fn handle_paste(&mut self, pasted: String) -> bool {
+        let pasted = pasted.replace("\r\n", "\n").replace('\r', "\n");
+        if let Some(path) = pasted_image_path(&pasted) {
+            self.attach_image(path, None);
+        } else if pasted.chars().count() > LARGE_PASTE_CHAR_THRESHOLD {
+            let placeholder = format!("[pasted {} lines]", pasted.lines().count());
+            self.textarea.insert_str(&placeholder);
+            self.pending_pastes.push(PendingPaste {
+                placeholder,
+                text: pasted,
+            });
+        } else {
+            self.textarea.insert_str(pasted);
+        }
+        self.sync_command_popup();
+        self.sync_file_popup();
+        true
+    }
+
+    /// Try to attach an image from the system clipboard. Returns `false`
+    /// when the clipboard holds no image so the key can be handled normally.
+    fn try_paste_clipboard_image(&mut self) -> bool {
+        match paste_image_to_temp_png() {
+            Ok((temp_file, info)) => {
+                let label = format!("{}x{} PNG", info.width, info.height);
+                self.attach(temp_file.to_path_buf(), label, Some(temp_file));
+                true
+            }
+            Err(err) => {
+                tracing::debug!("clipboard image paste failed: {err}");
+                false
+            }
+        }
+    }
+
+    /// Split the submitted text into the text to send and the attachments
+    /// whose placeholders survived editing. Placeholders are removed from the
+    /// text because the images travel as separate input items.
+    fn take_submission(&mut self, text: String) -> (String, Vec<PathBuf>) {
+        let mut text = self.expand_pastes(text);
+        let mut image_paths = Vec::new();
+        for image in std::mem::take(&mut self.attached_images) {
+            if text.contains(&image.placeholder) {
+                text = text.replacen(&image.placeholder, "", 1);
+                image_paths.push(image.path);
+                self.sent_temp_images.extend(image.temp_file);
+            }
+        }
+        self.update_border(self.has_input_focus);
+        let text = if image_paths.is_empty() {
+            text
+        } else {
+            text.trim().to_string()
+        };
+        (text, image_paths)
+    }
+
+    /// Replace collapsed pastes with their contents.
+    fn expand_pastes(&mut self, mut text: String) -> String {
+        for paste in std::mem::take(&mut self.pending_pastes) {
+            text = text.replacen(&paste.placeholder, &paste.text, 1);
+        }
+        text
+    }
+
+    /// Replace the draft, e.g. with the result of editing it in `$EDITOR`.
+    /// Attached images whose placeholders survive stay attached.
+    pub(crate) fn set_text(&mut self, text: &str) {
+        self.textarea.select_all();
+        self.textarea.cut();
+        self.textarea.insert_str(text);
+        self.pending_pastes.clear();
+        self.attached_images
+            .retain(|image| text.contains(&image.placeholder));
+        self.update_border(self.has_input_focus);
+        self.sync_command_popup();
+        self.sync_file_popup();
+    }
+
+    /// Ask the app to open the draft in an external editor; collapsed pastes
+    /// are expanded so they can be edited too.
+    fn open_external_editor(&self) {
+        let draft = self.textarea.lines().join("\n");
+        let draft = self.pending_pastes.iter().fold(draft, |draft, paste| {
+            draft.replacen(&paste.placeholder, &paste.text, 1)
+        });
+        self.app_event_tx.send(AppEvent::OpenExternalEditor(draft));
+    }
+
+    /// Clear the composer and return its text and attached images, recording
+    /// the text in the local history.
+    pub(crate) fn take_message(&mut self) -> (String, Vec<PathBuf>) {
+        let text = self.textarea.lines().join("\n");
+        self.textarea.select_all();
+        self.textarea.cut();
+
+        let (text, image_paths) = self.take_submission(text);
+        if !text.is_empty() {
+            self.history.record_local_submission(&text);
+        }
+        (text, image_paths)
+    }
+
     /// Handle a key event coming from the main UI.
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
-        let result = match self.command_popup {
-            Some(_) => self.handle_key_event_with_popup(key_event),
-            None => self.handle_key_event_without_popup(key_event),
+        let result = match (&self.command_popup, &self.file_popup) {
+            (Some(_), _) => self.handle_key_event_with_popup(key_event),
+            (None, Some(_)) => self.handle_key_event_with_file_popup(key_event),
+            (None, None) => self.handle_key_event_without_popup(key_event),
         };
 
         // Update (or hide/show) popup after processing the key.
         self.sync_command_popup();
+        self.sync_file_popup();
 
         result
     }
 
+    /// Handle key event when the `@mention` popup is visible.
+    fn handle_key_event_with_file_popup(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
+        let Some(popup) = self.file_popup.as_mut() else {
+            tracing::error!("handle_key_event_with_file_popup called without an active popup");
+            return (InputResult::None, false);
+        };
+
+        match key_event.into() {
+            Input { key: Key::Up, .. } => {
+                popup.move_up();
+                (InputResult::None, true)
+            }
+            Input { key: Key::Down, .. } => {
+                popup.move_down();
+                (InputResult::None, true)
+            }
+            Input { key: Key::Esc, .. } => {
+                self.file_popup = None;
+                (InputResult::None, true)
+            }
+            Input { key: Key::Tab, .. }
+            | Input {
+                key: Key::Enter,
+                shift: false,
+                alt: false,
+                ctrl: false,
+            } => {
+                match popup.selected_match().map(str::to_string) {
+                    Some(path) => {
+                        self.complete_file_mention(&path);
+                        (InputResult::None, true)
+                    }
+                    // Nothing to complete: let the key behave as usual.
+                    None => self.handle_key_event_without_popup(key_event),
+                }
+            }
+            input => self.handle_input_basic(input),
+        }
+    }
+
+    /// Replace the `@mention` under the cursor with `@path `.
+    fn complete_file_mention(&mut self, path: &str) {
+        let Some(token) = self.current_mention() else {
+            return;
+        };
+        self.textarea
+            .move_cursor(CursorMove::Jump(token.row as u16, token.start as u16));
+        self.textarea.delete_str(token.end - token.start);
+        self.textarea.insert_str(format!("@{path} "));
+        self.file_popup = None;
+    }
+
+    /// The whitespace-delimited word under the cursor if it starts with `@`.
+    fn current_mention(&self) -> Option<MentionToken> {
+        let (row, col) = self.textarea.cursor();
+        let line: Vec<char> = self.textarea.lines().get(row)?.chars().collect();
+        let start = line[..col.min(line.len())]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let end = line[col.min(line.len())..]
+            .iter()
+            .position(|c| c.is_whitespace())
+            .map(|i| col + i)
+            .unwrap_or(line.len());
+        if line.get(start) != Some(&'@') {
+            return None;
+        }
+        Some(MentionToken {
+            row,
+            start,
+            end,
+            query: line[start + 1..end].iter().collect(),
+        })
+    }
+
     /// Handle key event when the slash-command popup is visible.
     fn handle_key_event_with_popup(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
         let Some(popup) = self.command_popup.as_mut() else {
@@ -116,12 +411,12 @@ impl ChatComposer<'_> {
 
                     let starts_with_cmd = first_line
                         .trim_start()
-                        .starts_with(&format!("/{}", cmd.command()));
+                        .starts_with(&format!("/{}", cmd.name()));
 
                     if !starts_with_cmd {
                         self.textarea.select_all();
                         self.textarea.cut();
-                        let _ = self.textarea.insert_str(format!("/{} ", cmd.command()));
+                        let _ = self.textarea.insert_str(format!("/{} ", cmd.name()));
                     }
                 }
                 (InputResult::None, true)
@@ -132,9 +427,22 @@ impl ChatComposer<'_> {
                 alt: false,
                 ctrl: false,
             } => {
-                if let Some(cmd) = popup.selected_command() {
+                let parsed = self
+                    .textarea
+                    .lines()
+                    .first()
+                    .and_then(|line| parse_slash_command(line))
+                    .map(|(name, args)| (name.to_string(), args.to_string()));
+                let (name, args) = parsed.unwrap_or_default();
+                // An exactly typed command wins over the highlighted entry.
+                let selected = self
+                    .slash_commands
+                    .get(&name)
+                    .or_else(|| popup.selected_command());
+                if let Some(command) = selected {
                     // Send command to the app layer.
-                    self.app_event_tx.send(AppEvent::DispatchCommand(*cmd));
+                    self.app_event_tx
+                        .send(AppEvent::DispatchCommand { command, args });
 
                     // Clear textarea so no residual text remains.
                     self.textarea.select_all();
@@ -153,6 +461,15 @@ impl ChatComposer<'_> {
 
     /// Handle key event when no popup is visible.
     fn handle_key_event_without_popup(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
+        if self.submit_key.matches(&key_event) {
+            let (text, image_paths) = self.take_message();
+            return if text.is_empty() && image_paths.is_empty() {
+                (InputResult::None, true)
+            } else {
+                (InputResult::Submitted { text, image_paths }, true)
+            };
+        }
+
         let input: Input = key_event.into();
         match input {
             // -------------------------------------------------------------
@@ -183,21 +500,19 @@ impl ChatComposer<'_> {
                 self.handle_input_basic(input)
             }
             Input {
-                key: Key::Enter,
+                key: Key::Char('v'),
+                ctrl: true,
+                alt: false,
                 shift: false,
+            } if self.try_paste_clipboard_image() => (InputResult::None, true),
+            Input {
+                key: Key::Char('e'),
+                ctrl: true,
                 alt: false,
-                ctrl: false,
+                shift: false,
             } => {
-                let text = self.textarea.lines().join("\n");
-                self.textarea.select_all();
-                self.textarea.cut();
-
-                if text.is_empty() {
-                    (InputResult::None, true)
-                } else {
-                    self.history.record_local_submission(&text);
-                    (InputResult::Submitted(text), true)
-                }
+                self.open_external_editor();
+                (InputResult::None, false)
             }
             Input {
                 key: Key::Enter, ..
@@ -237,7 +552,10 @@ impl ChatComposer<'_> {
 
         if first_line.starts_with('/') {
             // Create popup lazily when the user starts a slash command.
-            let popup = self.command_popup.get_or_insert_with(CommandPopup::new);
+            let slash_commands = &self.slash_commands;
+            let popup = self
+                .command_popup
+                .get_or_insert_with(|| CommandPopup::new(slash_commands.clone()));
 
             // Forward *only* the first line since `CommandPopup` only needs
             // the command token.
@@ -248,10 +566,29 @@ impl ChatComposer<'_> {
         }
     }
 
+    /// Show, update or hide the `@mention` popup to match the word under the
+    /// cursor. The slash-command popup takes precedence.
+    fn sync_file_popup(&mut self) {
+        let query = match (&self.workspace_index, self.command_popup.is_some()) {
+            (Some(_), false) => self.current_mention().map(|t| t.query),
+            _ => None,
+        };
+        match (query, &self.workspace_index) {
+            (Some(query), Some(index)) => {
+                self.file_popup
+                    .get_or_insert_with(FileSearchPopup::new)
+                    .set_query(&query, index);
+            }
+            _ => self.file_popup = None,
+        }
+    }
+
     pub fn calculate_required_height(&self, area: &Rect) -> u16 {
         let rows = self.textarea.lines().len().max(MIN_TEXTAREA_ROWS);
         let num_popup_rows = if let Some(popup) = &self.command_popup {
             popup.calculate_required_height(area)
+        } else if let Some(popup) = &self.file_popup {
+            popup.calculate_required_height(area)
         } else {
             0
         };
@@ -267,7 +604,10 @@ impl ChatComposer<'_> {
 
         let bs = if has_focus {
             BlockState {
-                right_title: Line::from("Enter to send | Ctrl+D to quit | Ctrl+J for newline")
+                right_title: Line::from(format!(
+                    "{} to send | Shift+Enter or Ctrl+J for newline | Ctrl+E to edit in $EDITOR | Ctrl+D to quit",
+                    self.submit_key
+                ))
                     .alignment(Alignment::Right),
                 border_style: Style::default(),
             }
@@ -278,8 +618,15 @@ impl ChatComposer<'_> {
             }
         };
 
+        let mut block = ratatui::widgets::Block::default();
+        if !self.attached_images.is_empty() {
+            let count = self.attached_images.len();
+            let noun = if count == 1 { "image" } else { "images" };
+            block = block.title(Line::from(format!("{count} {noun} attached")).fg(theme().info));
+        }
+
         self.textarea.set_block(
-            ratatui::widgets::Block::default()
+            block
                 .title_bottom(bs.right_title)
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
@@ -287,16 +634,22 @@ impl ChatComposer<'_> {
         );
     }
 
-    pub(crate) fn is_command_popup_visible(&self) -> bool {
-        self.command_popup.is_some()
+    pub(crate) fn is_popup_visible(&self) -> bool {
+        self.command_popup.is_some() || self.file_popup.is_some()
     }
 }
 
 impl WidgetRef for &ChatComposer<'_> {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        if let Some(popup) = &self.command_popup {
-            let popup_height = popup.calculate_required_height(&area);
+        let popup: Option<(&dyn WidgetRef, u16)> = if let Some(popup) = &self.command_popup {
+            Some((popup, popup.calculate_required_height(&area)))
+        } else if let Some(popup) = &self.file_popup {
+            Some((popup, popup.calculate_required_height(&area)))
+        } else {
+            None
+        };
 
+        if let Some((popup, popup_height)) = popup {
             // Split the provided rect so that the popup is rendered at the
             // *top* and the textarea occupies the remaining space below.
             let popup_rect = Rect {
@@ -313,10 +666,96 @@ impl WidgetRef for &ChatComposer<'_> {
                 height: area.height.saturating_sub(popup_rect.height),
             };
 
-            popup.render(popup_rect, buf);
+            popup.render_ref(popup_rect, buf);
             self.textarea.render(textarea_rect, buf);
         } else {
             self.textarea.render(area, buf);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::NamedTempFile;
+    use tempfile::TempDir;
+
+    fn composer() -> ChatComposer<'static> {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        ChatComposer::new(
+            true,
+            AppEventSender::new(tx),
+            SlashCommandRegistry::with_built_ins(),
+            KeyBindings::default(),
+        )
+    }
+
+    #[test]
+    fn deleted_placeholders_drop_their_attachments() {
+        let mut composer = composer();
+        composer.attach_image(PathBuf::from("/tmp/a.png"), None);
+        composer.attach_image(PathBuf::from("/tmp/b.png"), None);
+
+        let (text, image_paths) =
+            composer.take_submission("look at [image: b.png] please".to_string());
+        assert_eq!(text, "look at  please");
+        assert_eq!(image_paths, vec![PathBuf::from("/tmp/b.png")]);
+        assert!(composer.attached_images.is_empty());
+    }
+
+    #[test]
+    fn pasted_images_are_deleted_unless_sent() {
+        let mut composer = composer();
+        let sent = NamedTempFile::new().unwrap().into_temp_path();
+        let sent_path = sent.to_path_buf();
+        let dropped = NamedTempFile::new().unwrap().into_temp_path();
+        let dropped_path = dropped.to_path_buf();
+        composer.attach(sent_path.clone(), "sent".to_string(), Some(sent));
+        composer.attach(dropped_path.clone(), "dropped".to_string(), Some(dropped));
+
+        let (_, image_paths) = composer.take_submission("[image: sent]".to_string());
+        assert_eq!(image_paths, vec![sent_path.clone()]);
+        assert!(sent_path.exists());
+        assert!(!dropped_path.exists());
+
+        drop(composer);
+        assert!(!sent_path.exists());
+    }
+
+    #[test]
+    fn plain_text_paste_is_inserted() {
+        let mut composer = composer();
+        composer.handle_paste("hello\nworld".to_string());
+        assert_eq!(composer.textarea.lines(), ["hello", "world"]);
+        assert!(composer.attached_images.is_empty());
+    }
+
+    #[test]
+    fn large_paste_is_collapsed_and_expanded_on_submit() {
+        let mut composer = composer();
+        let pasted = "line\r\n".repeat(300);
+        composer.handle_paste(pasted);
+        assert_eq!(composer.textarea.lines(), ["[pasted 300 lines]"]);
+
+        let (text, _) = composer.take_message();
+        assert_eq!(text, "line\n".repeat(300));
+        assert!(composer.pending_pastes.is_empty());
+    }
+
+    #[test]
+    fn tab_completes_file_mention() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+
+        let mut composer = composer();
+        composer.set_workspace_index(Arc::new(WorkspaceIndex::build(dir.path(), 100)));
+        composer.handle_paste("see @mai".to_string());
+        assert!(composer.is_popup_visible());
+
+        composer.handle_key_event(KeyEvent::from(crossterm::event::KeyCode::Tab));
+        assert_eq!(composer.textarea.lines(), ["see @src/main.rs "]);
+        assert!(!composer.is_popup_visible());
+    }
+}
@@ -1,8 +1,7 @@
-use std::collections::HashMap;
+use std::sync::Arc;
 
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Style;
 use ratatui::style::Stylize;
 use ratatui::widgets::Block;
@@ -15,7 +14,8 @@ use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
 
 use crate::slash_command::SlashCommand;
-use crate::slash_command::built_in_slash_commands;
+use crate::slash_command::SlashCommandRegistry;
+use crate::theme::theme;
 
 const MAX_POPUP_ROWS: usize = 5;
 /// Ideally this is enough to show the longest command name.
@@ -25,15 +25,15 @@ use ratatui::style::Modifier;
 
 pub(crate) struct CommandPopup {
     command_filter: String,
-    all_commands: HashMap<&'static str, SlashCommand>,
+    commands: SlashCommandRegistry,
     selected_idx: Option<usize>,
 }
 
 impl CommandPopup {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(commands: SlashCommandRegistry) -> Self {
         Self {
             command_filter: String::new(),
-            all_commands: built_in_slash_commands(),
+            commands,
             selected_idx: None,
         }
     }
@@ -82,25 +82,10 @@ impl CommandPopup {
     }
 
     /// Return the list of commands that match the current filter. Matching is
-    /// performed using a *prefix* comparison on the command name.
-    fn filtered_commands(&self) -> Vec<&SlashCommand> {
-        let mut cmds: Vec<&SlashCommand> = self
-            .all_commands
-            .values()
-            .filter(|cmd| {
-                if self.command_filter.is_empty() {
-                    true
-                } else {
-                    cmd.command()
-                        .starts_with(&self.command_filter.to_ascii_lowercase())
-                }
-            })
-            .collect();
-
-        // Sort the commands alphabetically so the order is stable and
-        // predictable.
-        cmds.sort_by(|a, b| a.command().cmp(b.command()));
-        cmds
+    /// performed using a *prefix* comparison on the command name; the result
+    /// is sorted alphabetically so the order is stable and predictable.
+    fn filtered_commands(&self) -> Vec<Arc<dyn SlashCommand>> {
+        self.commands.matching(&self.command_filter)
     }
 
     /// Move the selection cursor one step up.
@@ -140,9 +125,9 @@ impl CommandPopup {
     }
 
     /// Return currently selected command, if any.
-    pub(crate) fn selected_command(&self) -> Option<&SlashCommand> {
+    pub(crate) fn selected_command(&self) -> Option<Arc<dyn SlashCommand>> {
         let matches = self.filtered_commands();
-        self.selected_idx.and_then(|idx| matches.get(idx).copied())
+        self.selected_idx.and_then(|idx| matches.get(idx).cloned())
     }
 }
 
@@ -151,7 +136,7 @@ impl WidgetRef for CommandPopup {
         let matches = self.filtered_commands();
 
         let mut rows: Vec<Row> = Vec::new();
-        let visible_matches: Vec<&SlashCommand> =
+        let visible_matches: Vec<Arc<dyn SlashCommand>> =
             matches.into_iter().take(MAX_POPUP_ROWS).collect();
 
         if visible_matches.is_empty() {
@@ -161,19 +146,19 @@ impl WidgetRef for CommandPopup {
             ]));
         } else {
             let default_style = Style::default();
-            let command_style = Style::default().fg(Color::LightBlue);
+            let command_style = Style::default().fg(theme().accent);
             for (idx, cmd) in visible_matches.iter().enumerate() {
                 let (cmd_style, desc_style) = if Some(idx) == self.selected_idx {
                     (
-                        command_style.bg(Color::DarkGray),
-                        default_style.bg(Color::DarkGray),
+                        command_style.bg(theme().selection),
+                        default_style.bg(theme().selection),
                     )
                 } else {
                     (command_style, default_style)
                 };
 
                 rows.push(Row::new(vec![
-                    Cell::from(format!("/{}", cmd.command())).style(cmd_style),
+                    Cell::from(format!("/{}", cmd.name())).style(cmd_style),
                     Cell::from(cmd.description().to_string()).style(desc_style),
                 ]));
             }
@@ -0,0 +1,114 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Constraint;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Cell;
+use ratatui::widgets::Row;
+use ratatui::widgets::Table;
+use ratatui::widgets::Widget;
+use ratatui::widgets::WidgetRef;
+
+use codex_core::workspace_index::WorkspaceIndex;
+
+use crate::theme::theme;
+
+/// Maximum number of matches shown (and kept) at once.
+pub(crate) const MAX_POPUP_ROWS: usize = 8;
+
+/// Popup listing workspace files that match the `@mention` under the cursor.
+pub(crate) struct FileSearchPopup {
+    query: String,
+    matches: Vec<String>,
+    selected_idx: Option<usize>,
+}
+
+impl FileSearchPopup {
+    pub(crate) fn new() -> Self {
+        Self {
+            query: String::new(),
+            matches: Vec::new(),
+            selected_idx: None,
+        }
+    }
+
+    /// Re-run the search when the text after `@` changes.
+    pub(crate) fn set_query(&mut self, query: &str, index: &WorkspaceIndex) {
+        if query == self.query && !self.matches.is_empty() {
+            return;
+        }
+        self.query = query.to_string();
+        self.matches = index
+            .search(query, MAX_POPUP_ROWS)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        self.selected_idx = if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    pub(crate) fn move_up(&mut self) {
+        if let Some(idx) = self.selected_idx {
+            self.selected_idx = Some(idx.saturating_sub(1));
+        }
+    }
+
+    pub(crate) fn move_down(&mut self) {
+        if let Some(idx) = self.selected_idx {
+            if idx + 1 < self.matches.len() {
+                self.selected_idx = Some(idx + 1);
+            }
+        }
+    }
+
+    pub(crate) fn selected_match(&self) -> Option<&str> {
+        self.selected_idx
+            .and_then(|idx| self.matches.get(idx))
+            .map(String::as_str)
+    }
+
+    pub(crate) fn calculate_required_height(&self, _area: &Rect) -> u16 {
+        // One row per match (at least one for the "no matches" row) plus the
+        // border.
+        self.matches.len().clamp(1, MAX_POPUP_ROWS) as u16 + 2
+    }
+}
+
+impl WidgetRef for FileSearchPopup {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let rows: Vec<Row> = if self.matches.is_empty() {
+            vec![Row::new(vec![
+                Cell::from("No matching files").add_modifier(Modifier::ITALIC),
+            ])]
+        } else {
+            let path_style = Style::default().fg(theme().accent);
+            self.matches
+                .iter()
+                .enumerate()
+                .map(|(idx, path)| {
+                    let style = if Some(idx) == self.selected_idx {
+                        path_style.bg(theme().selection)
+                    } else {
+                        path_style
+                    };
+                    Row::new(vec![Cell::from(format!("@{path}")).style(style)])
+                })
+                .collect()
+        };
+
+        Table::new(rows, [Constraint::Min(10)])
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .render(area, buf);
+    }
+}
@@ -1,28 +1,48 @@
 //! Bottom pane: shows the ChatComposer or a BottomPaneView, if one is active.
 
 use bottom_pane_view::BottomPaneView;
-use bottom_pane_view::ConditionalUpdate;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_core::protocol::PlanItem;
+use codex_core::workspace_index::WorkspaceIndex;
+use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
 use ratatui::buffer::Buffer;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
 use ratatui::layout::Rect;
 use ratatui::widgets::WidgetRef;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::keybindings::KeyBindings;
+use crate::plan_widget::PlanWidget;
+use crate::slash_command::SlashCommandRegistry;
+use crate::status_indicator_widget::StatusIndicatorWidget;
 use crate::user_approval_widget::ApprovalRequest;
 
 mod approval_modal_view;
+mod attach_image_view;
 mod bottom_pane_view;
 mod chat_composer;
 mod chat_composer_history;
 mod command_popup;
-mod status_indicator_view;
+mod file_search_popup;
+mod turn_review_view;
 
 pub(crate) use chat_composer::ChatComposer;
 pub(crate) use chat_composer::InputResult;
 
 use approval_modal_view::ApprovalModalView;
-use status_indicator_view::StatusIndicatorView;
+use attach_image_view::AttachImageView;
+use turn_review_view::TurnReviewView;
+
+/// Rows taken by the status indicator shown above the composer while a task
+/// is running.
+const STATUS_INDICATOR_HEIGHT: u16 = 3;
 
 /// Pane displayed in the lower half of the chat UI.
 pub(crate) struct BottomPane<'a> {
@@ -33,24 +53,41 @@ pub(crate) struct BottomPane<'a> {
     /// If present, this is displayed instead of the `composer`.
     active_view: Option<Box<dyn BottomPaneView<'a> + 'a>>,
 
+    /// Shown above the composer while a task is running. The composer stays
+    /// usable so further messages can be queued.
+    status_indicator: Option<StatusIndicatorWidget>,
+
+    /// The agent's plan, shown above the status indicator until every step
+    /// is completed and the task has finished.
+    plan: Option<PlanWidget>,
+
     app_event_tx: AppEventSender,
     has_input_focus: bool,
-    is_task_running: bool,
+    keybindings: KeyBindings,
 }
 
 pub(crate) struct BottomPaneParams {
     pub(crate) app_event_tx: AppEventSender,
     pub(crate) has_input_focus: bool,
+    pub(crate) slash_commands: SlashCommandRegistry,
+    pub(crate) keybindings: KeyBindings,
 }
 
 impl BottomPane<'_> {
     pub fn new(params: BottomPaneParams) -> Self {
         Self {
-            composer: ChatComposer::new(params.has_input_focus, params.app_event_tx.clone()),
+            composer: ChatComposer::new(
+                params.has_input_focus,
+                params.app_event_tx.clone(),
+                params.slash_commands,
+                params.keybindings,
+            ),
             active_view: None,
+            status_indicator: None,
+            plan: None,
             app_event_tx: params.app_event_tx,
             has_input_focus: params.has_input_focus,
-            is_task_running: false,
+            keybindings: params.keybindings,
         }
     }
 
@@ -60,15 +97,16 @@ impl BottomPane<'_> {
             view.handle_key_event(self, key_event);
             if !view.is_complete() {
                 self.active_view = Some(view);
-            } else if self.is_task_running {
-                let height = self.composer.calculate_required_height(&Rect::default());
-                self.active_view = Some(Box::new(StatusIndicatorView::new(
-                    self.app_event_tx.clone(),
-                    height,
-                )));
             }
             self.request_redraw();
             InputResult::None
+        } else if self.status_indicator.is_some()
+            && key_event.code == KeyCode::Esc
+            && !self.composer.is_popup_visible()
+        {
+            let (text, image_paths) = self.composer.take_message();
+            self.request_redraw();
+            InputResult::Steer { text, image_paths }
         } else {
             let (input_result, needs_redraw) = self.composer.handle_key_event(key_event);
             if needs_redraw {
@@ -78,18 +116,26 @@ impl BottomPane<'_> {
         }
     }
 
-    /// Update the status indicator text (only when the `StatusIndicatorView` is
-    /// active).
+    /// Replace the composer draft.
+    pub(crate) fn set_composer_text(&mut self, text: &str) {
+        self.composer.set_text(text);
+        self.request_redraw();
+    }
+
+    /// Update the status indicator text (only while a task is running).
     pub(crate) fn update_status_text(&mut self, text: String) {
-        if let Some(view) = &mut self.active_view {
-            match view.update_status_text(text) {
-                ConditionalUpdate::NeedsRedraw => {
-                    self.request_redraw();
-                }
-                ConditionalUpdate::NoRedraw => {
-                    // No redraw needed.
-                }
-            }
+        if let Some(indicator) = &mut self.status_indicator {
+            indicator.update_text(text);
+            self.request_redraw();
+        }
+    }
+
+    /// Number of messages submitted during the current task that the agent
+    /// has not answered yet.
+    pub(crate) fn set_queued_message_count(&mut self, count: usize) {
+        if let Some(indicator) = &mut self.status_indicator {
+            indicator.set_queued_message_count(count);
+            self.request_redraw();
         }
     }
 
@@ -100,28 +146,20 @@ impl BottomPane<'_> {
     }
 
     pub fn set_task_running(&mut self, running: bool) {
-        self.is_task_running = running;
-
-        match (running, self.active_view.is_some()) {
+        match (running, self.status_indicator.is_some()) {
             (true, false) => {
-                // Show status indicator overlay.
-                let height = self.composer.calculate_required_height(&Rect::default());
-                self.active_view = Some(Box::new(StatusIndicatorView::new(
+                self.status_indicator = Some(StatusIndicatorWidget::new(
                     self.app_event_tx.clone(),
-                    height,
-                )));
+                    STATUS_INDICATOR_HEIGHT,
+                ));
                 self.request_redraw();
             }
             (false, true) => {
-                if let Some(mut view) = self.active_view.take() {
-                    if view.should_hide_when_task_is_done() {
-                        // Leave self.active_view as None.
-                        self.request_redraw();
-                    } else {
-                        // Preserve the view.
-                        self.active_view = Some(view);
-                    }
+                self.status_indicator = None;
+                if self.plan.as_ref().is_some_and(PlanWidget::is_done) {
+                    self.plan = None;
                 }
+                self.request_redraw();
             }
             _ => {
                 // No change.
@@ -129,6 +167,12 @@ impl BottomPane<'_> {
         }
     }
 
+    /// Show the agent's latest plan. An empty plan hides the checklist.
+    pub(crate) fn set_plan(&mut self, plan: Vec<PlanItem>) {
+        self.plan = (!plan.is_empty()).then(|| PlanWidget::new(plan));
+        self.request_redraw();
+    }
+
     /// Called when the agent requests user approval.
     pub fn push_approval_request(&mut self, request: ApprovalRequest) {
         let request = if let Some(view) = self.active_view.as_mut() {
@@ -144,17 +188,62 @@ impl BottomPane<'_> {
         };
 
         // Otherwise create a new approval modal overlay.
-        let modal = ApprovalModalView::new(request, self.app_event_tx.clone());
+        let modal = ApprovalModalView::new(request, self.app_event_tx.clone(), self.keybindings);
         self.active_view = Some(Box::new(modal));
         self.request_redraw()
     }
 
+    /// Ask the user which of the files edited during the task to keep.
+    pub(crate) fn show_turn_review(&mut self, id: String, cwd: PathBuf, files: Vec<PathBuf>) {
+        self.active_view = Some(Box::new(TurnReviewView::new(
+            id,
+            cwd,
+            files,
+            self.app_event_tx.clone(),
+        )));
+        self.request_redraw();
+    }
+
+    /// Show the prompt for attaching an image file to the next message.
+    pub(crate) fn show_attach_image_view(&mut self) {
+        if self.active_view.is_some() {
+            return;
+        }
+        self.active_view = Some(Box::new(AttachImageView::new()));
+        self.request_redraw();
+    }
+
+    pub(crate) fn attach_image(&mut self, path: PathBuf) {
+        self.composer.attach_image(path, None);
+    }
+
+    /// Forward a bracketed paste to the composer. While a view is active the
+    /// paste is replayed as key presses so views keep their plain-text input.
+    pub(crate) fn handle_paste(&mut self, pasted: String) {
+        if self.active_view.is_some() {
+            for ch in pasted.chars() {
+                let key_event = match ch {
+                    '\n' | '\r' => KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT),
+                    _ => KeyEvent::new(KeyCode::Char(ch), KeyModifiers::empty()),
+                };
+                self.handle_key_event(key_event);
+            }
+        } else if self.composer.handle_paste(pasted) {
+            self.request_redraw();
+        }
+    }
+
     /// Height (terminal rows) required by the current bottom pane.
     pub fn calculate_required_height(&self, area: &Rect) -> u16 {
         if let Some(view) = &self.active_view {
             view.calculate_required_height(area)
         } else {
-            self.composer.calculate_required_height(area)
+            let plan_height = self.plan.as_ref().map_or(0, PlanWidget::get_height);
+            let indicator_height = self
+                .status_indicator
+                .as_ref()
+                .map_or(0, StatusIndicatorWidget::get_height);
+            plan_height + indicator_height + self.composer.calculate_required_height(area)
         }
     }
 
@@ -162,9 +251,21 @@ impl BottomPane<'_> {
         self.app_event_tx.send(AppEvent::Redraw)
     }
 
+    /// Whether a view such as an approval prompt is shown instead of the
+    /// composer.
+    pub(crate) fn has_active_view(&self) -> bool {
+        self.active_view.is_some()
+    }
+
     /// Returns true when the slash-command popup inside the composer is visible.
-    pub(crate) fn is_command_popup_visible(&self) -> bool {
-        self.active_view.is_none() && self.composer.is_command_popup_visible()
+    /// Whether the composer is showing a completion popup (slash commands or
+    /// `@mentions`), which wants Tab for completion.
+    pub(crate) fn is_popup_visible(&self) -> bool {
+        self.active_view.is_none() && self.composer.is_popup_visible()
+    }
+
+    pub(crate) fn set_workspace_index(&mut self, index: Arc<WorkspaceIndex>) {
+        self.composer.set_workspace_index(index);
     }
 
     // --- History helpers ---
@@ -195,7 +296,25 @@ impl WidgetRef for &BottomPane<'_> {
         if let Some(ov) = &self.active_view {
             ov.render(area, buf);
         } else {
-            (&self.composer).render_ref(area, buf);
+            let [plan_area, indicator_area, composer_area] = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(self.plan.as_ref().map_or(0, PlanWidget::get_height)),
+                    Constraint::Length(
+                        self.status_indicator
+                            .as_ref()
+                            .map_or(0, StatusIndicatorWidget::get_height),
+                    ),
+                    Constraint::Min(0),
+                ])
+                .areas(area);
+            if let Some(plan) = &self.plan {
+                plan.render_ref(plan_area, buf);
+            }
+            if let Some(indicator) = &self.status_indicator {
+                indicator.render_ref(indicator_area, buf);
+            }
+            (&self.composer).render_ref(composer_area, buf);
         }
     }
 }
@@ -0,0 +1,194 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::Op;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+
+use super::BottomPane;
+use super::BottomPaneView;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+use crate::theme::theme;
+
+/// Rows consumed by the border and the key hint.
+const CHROME_LINES: u16 = 3;
+
+/// Files listed at once. Longer lists scroll with the selection.
+const MAX_VISIBLE_FILES: usize = 10;
+
+/// Review of the changes a task made (`review_edits = "per-turn"`): every
+/// edited file starts out accepted and can be toggled before submitting.
+pub(crate) struct TurnReviewView {
+    id: String,
+    cwd: PathBuf,
+    files: Vec<(PathBuf, bool)>,
+    selected: usize,
+    app_event_tx: AppEventSender,
+    done: bool,
+}
+
+impl TurnReviewView {
+    pub fn new(
+        id: String,
+        cwd: PathBuf,
+        mut files: Vec<PathBuf>,
+        app_event_tx: AppEventSender,
+    ) -> Self {
+        files.sort();
+        Self {
+            id,
+            cwd,
+            files: files.into_iter().map(|path| (path, true)).collect(),
+            selected: 0,
+            app_event_tx,
+            done: false,
+        }
+    }
+
+    fn set_all(&mut self, accepted: bool) {
+        for (_, file_accepted) in &mut self.files {
+            *file_accepted = accepted;
+        }
+    }
+
+    fn approved(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, accepted)| *accepted)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    fn submit(&mut self) {
+        self.app_event_tx
+            .send(AppEvent::CodexOp(Op::TurnDiffApproval {
+                id: self.id.clone(),
+                approved: self.approved(),
+            }));
+        self.done = true;
+    }
+
+    fn first_visible(&self) -> usize {
+        let max_first = self.files.len().saturating_sub(MAX_VISIBLE_FILES);
+        self.selected
+            .saturating_sub(MAX_VISIBLE_FILES - 1)
+            .min(max_first)
+    }
+
+    fn display_path<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.cwd).unwrap_or(path)
+    }
+}
+
+impl<'a> BottomPaneView<'a> for TurnReviewView {
+    fn handle_key_event(&mut self, _pane: &mut BottomPane<'a>, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.files.len().saturating_sub(1));
+            }
+            KeyCode::Char(' ') => {
+                if let Some((_, accepted)) = self.files.get_mut(self.selected) {
+                    *accepted = !*accepted;
+                }
+            }
+            KeyCode::Char('a') => {
+                self.set_all(true);
+                self.submit();
+            }
+            KeyCode::Char('r') => {
+                self.set_all(false);
+                self.submit();
+            }
+            KeyCode::Enter => self.submit(),
+            _ => {}
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.done
+    }
+
+    fn calculate_required_height(&self, _area: &Rect) -> u16 {
+        self.files.len().min(MAX_VISIBLE_FILES) as u16 + CHROME_LINES
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let mut lines: Vec<Line> = self
+            .files
+            .iter()
+            .enumerate()
+            .skip(self.first_visible())
+            .take(MAX_VISIBLE_FILES)
+            .map(|(idx, (path, accepted))| {
+                let marker = if *accepted {
+                    "[x] ".fg(theme().success)
+                } else {
+                    "[ ] ".fg(theme().error)
+                };
+                let path = self.display_path(path).display().to_string();
+                let path = if idx == self.selected {
+                    path.fg(theme().accent).bold()
+                } else {
+                    path.fg(theme().text)
+                };
+                Line::from(vec![marker, path])
+            })
+            .collect();
+        lines.push(Line::from(
+            "↑/↓ move · space toggle · a accept all · r reject all · enter submit"
+                .fg(theme().muted),
+        ));
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Review changes made this turn ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn submits_the_files_left_accepted() {
+        let (tx, rx) = channel();
+        let mut view = TurnReviewView::new(
+            "sub-1".to_string(),
+            PathBuf::from("/repo"),
+            vec![PathBuf::from("/repo/b.rs"), PathBuf::from("/repo/a.rs")],
+            AppEventSender::new(tx),
+        );
+        // Reject the second file (b.rs, after sorting).
+        view.selected = 1;
+        if let Some((_, accepted)) = view.files.get_mut(view.selected) {
+            *accepted = !*accepted;
+        }
+        view.submit();
+        assert!(view.done);
+        match rx.recv().unwrap() {
+            AppEvent::CodexOp(Op::TurnDiffApproval { id, approved }) => {
+                assert_eq!(id, "sub-1");
+                assert_eq!(approved, vec![PathBuf::from("/repo/a.rs")]);
+            }
+            _ => panic!("expected a TurnDiffApproval op"),
+        }
+    }
+}
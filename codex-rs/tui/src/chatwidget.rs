@@ -3,9 +3,15 @@ use std::sync::Arc;
 
 use codex_core::codex_wrapper::init_codex;
 use codex_core::config::Config;
+use codex_core::custom_prompts::discover_prompts;
+use codex_core::custom_prompts::prompts_dir;
+use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
+use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::CheckpointCreatedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -17,17 +23,30 @@ use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
+use codex_core::protocol::PatchProposedEvent;
+use codex_core::protocol::PlanUpdatedEvent;
+use codex_core::protocol::SandboxEscalationRequestEvent;
+use codex_core::protocol::SessionMode;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TokenUsage;
+use codex_core::protocol::TurnDiffApprovalRequestEvent;
+use codex_core::session_export::Entry;
+use codex_core::session_export::Transcript;
+use codex_core::workspace_index::DEFAULT_MAX_INDEXED_FILES;
+use codex_core::workspace_index::WorkspaceIndex;
 use crossterm::event::KeyEvent;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Direction;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::widgets::Paragraph;
 use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::mpsc::unbounded_channel;
+use uuid::Uuid;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
@@ -35,7 +54,20 @@ use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::InputResult;
 use crate::conversation_history_widget::ConversationHistoryWidget;
+use crate::desktop_notifier::DesktopNotifier;
+use crate::diff_view::DiffView;
+use crate::diff_view::DiffViewAction;
+use crate::diff_view::FileDiff;
+use crate::diff_view::parse_diff;
+use crate::exec_command::strip_bash_lc_and_escape;
+use crate::file_mention::find_file_mentions;
+use crate::get_git_diff::get_git_diff;
 use crate::history_cell::PatchEventType;
+use crate::keybindings::KeyBindings;
+use crate::slash_command::SlashCommand;
+use crate::slash_command::SlashCommandContext;
+use crate::slash_command::SlashCommandRegistry;
+use crate::status_line::StatusLine;
 use crate::user_approval_widget::ApprovalRequest;
 
 pub(crate) struct ChatWidget<'a> {
@@ -46,6 +78,30 @@ pub(crate) struct ChatWidget<'a> {
     input_focus: InputFocus,
     config: Config,
     initial_user_message: Option<UserMessage>,
+    /// Tokens consumed by the session so far, as reported by the agent.
+    token_usage: TokenUsage,
+    /// Usage of the most recent model request, for the context-window fill
+    /// shown in the status line.
+    last_token_usage: Option<TokenUsage>,
+    /// Diff viewer opened with `/diff`; replaces the conversation pane while
+    /// open.
+    diff_view: Option<DiffView>,
+    /// `/diff` was used and the viewer opens once the diff is computed.
+    diff_requested: bool,
+    notifier: DesktopNotifier,
+    keybindings: KeyBindings,
+    task_running: bool,
+    /// Messages submitted while the current task was running. The agent picks
+    /// them up at its next turn boundary.
+    queued_messages: usize,
+    /// Id of the running session, known once the agent has configured it.
+    session_id: Option<Uuid>,
+    /// Set when replaying a recorded session rather than running one; shown
+    /// in place of the bottom pane.
+    replay_status: Option<String>,
+    /// Length of the history at each checkpoint of the session, by checkpoint
+    /// number minus one, for cutting it back on a rewind.
+    checkpoints: Vec<usize>,
 }
 
 #[derive(Clone, Copy, Eq, PartialEq)]
@@ -59,15 +115,6 @@ struct UserMessage {
     image_paths: Vec<PathBuf>,
 }
 
-impl From<String> for UserMessage {
-    fn from(text: String) -> Self {
-        Self {
-            text,
-            image_paths: Vec::new(),
-        }
-    }
-}
-
 fn create_initial_user_message(text: String, image_paths: Vec<PathBuf>) -> Option<UserMessage> {
     if text.is_empty() && image_paths.is_empty() {
         None
@@ -79,6 +126,7 @@ fn create_initial_user_message(text: String, image_paths: Vec<PathBuf>) -> Optio
 impl ChatWidget<'_> {
     pub(crate) fn new(
         config: Config,
+        keybindings: KeyBindings,
         app_event_tx: AppEventSender,
         initial_prompt: Option<String>,
         initial_images: Vec<PathBuf>,
@@ -117,6 +165,52 @@ impl ChatWidget<'_> {
             }
         });
 
+        // Index the working directory in the background for `@mention`
+        // completion.
+        {
+            let app_event_tx = app_event_tx.clone();
+            let cwd = config.cwd.clone();
+            let focus = config.focus.clone().unwrap_or_else(|| cwd.clone());
+            tokio::task::spawn_blocking(move || {
+                let index = WorkspaceIndex::build_within(&cwd, &focus, DEFAULT_MAX_INDEXED_FILES);
+                app_event_tx.send(AppEvent::WorkspaceIndexReady(Arc::new(index)));
+            });
+        }
+
+        Self::build(
+            config,
+            keybindings,
+            app_event_tx,
+            codex_op_tx,
+            create_initial_user_message(initial_prompt.unwrap_or_default(), initial_images),
+        )
+    }
+
+    /// A widget without an agent, for `codex replay` to feed the events of
+    /// a recorded session to.
+    pub(crate) fn new_replay(
+        mut config: Config,
+        keybindings: KeyBindings,
+        app_event_tx: AppEventSender,
+    ) -> Self {
+        // Tasks finishing in a replay are not news.
+        config.notifications.enabled = false;
+        let (codex_op_tx, _codex_op_rx) = unbounded_channel::<Op>();
+        let mut widget = Self::build(config, keybindings, app_event_tx, codex_op_tx, None);
+        widget.replay_status = Some(String::new());
+        widget
+    }
+
+    fn build(
+        config: Config,
+        keybindings: KeyBindings,
+        app_event_tx: AppEventSender,
+        codex_op_tx: UnboundedSender<Op>,
+        initial_user_message: Option<UserMessage>,
+    ) -> Self {
+        let mut slash_commands = SlashCommandRegistry::with_built_ins();
+        slash_commands.register_custom_prompts(discover_prompts(&prompts_dir(&config.codex_home)));
+
         Self {
             app_event_tx: app_event_tx.clone(),
             codex_op_tx,
@@ -124,22 +218,62 @@ impl ChatWidget<'_> {
             bottom_pane: BottomPane::new(BottomPaneParams {
                 app_event_tx,
                 has_input_focus: true,
+                slash_commands,
+                keybindings,
             }),
             input_focus: InputFocus::BottomPane,
+            initial_user_message,
+            token_usage: TokenUsage::default(),
+            last_token_usage: None,
+            diff_view: None,
+            diff_requested: false,
+            notifier: DesktopNotifier::new(config.notifications.clone()),
+            keybindings,
+            task_running: false,
+            queued_messages: 0,
+            session_id: None,
+            replay_status: None,
+            checkpoints: Vec::new(),
             config,
-            initial_user_message: create_initial_user_message(
-                initial_prompt.unwrap_or_default(),
-                initial_images,
-            ),
         }
     }
 
     pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if self
+            .keybindings
+            .toggle_diff
+            .is_some_and(|chord| chord.matches(&key_event))
+        {
+            self.show_diff();
+            return;
+        }
+
+        // Approval prompts and completion popups keep their keys while the
+        // diff viewer is open; keys the viewer does not use reach the
+        // composer.
+        let bottom_pane_busy =
+            self.bottom_pane.has_active_view() || self.bottom_pane.is_popup_visible();
+        if let Some(diff_view) = self.diff_view.as_mut().filter(|_| !bottom_pane_busy) {
+            match diff_view.handle_key_event(key_event) {
+                DiffViewAction::Close => {
+                    self.diff_view = None;
+                    self.request_redraw();
+                    return;
+                }
+                DiffViewAction::Redraw => {
+                    self.request_redraw();
+                    return;
+                }
+                DiffViewAction::None => {}
+            }
+        }
+
         // Special-case <Tab>: normally toggles focus between history and bottom panes.
-        // However, when the slash-command popup is visible we forward the key
-        // to the bottom pane so it can handle auto-completion.
+        // However, when a completion popup is visible we forward the key to
+        // the bottom pane so it can handle auto-completion.
         if matches!(key_event.code, crossterm::event::KeyCode::Tab)
-            && !self.bottom_pane.is_command_popup_visible()
+            && !self.bottom_pane.is_popup_visible()
+            && !self.conversation_history.is_editing_search()
         {
             self.input_focus = match self.input_focus {
                 InputFocus::HistoryPane => InputFocus::BottomPane,
@@ -153,6 +287,21 @@ impl ChatWidget<'_> {
             return;
         }
 
+        // The scroll bindings (Page Up / Page Down by default) scroll the
+        // transcript even while typing.
+        if !self.conversation_history.is_editing_search() {
+            if self.keybindings.scroll_up.matches(&key_event) {
+                self.conversation_history.scroll_page_up();
+                self.request_redraw();
+                return;
+            }
+            if self.keybindings.scroll_down.matches(&key_event) {
+                self.conversation_history.scroll_page_down();
+                self.request_redraw();
+                return;
+            }
+        }
+
         match self.input_focus {
             InputFocus::HistoryPane => {
                 let needs_redraw = self.conversation_history.handle_key_event(key_event);
@@ -161,14 +310,61 @@ impl ChatWidget<'_> {
                 }
             }
             InputFocus::BottomPane => match self.bottom_pane.handle_key_event(key_event) {
-                InputResult::Submitted(text) => {
-                    self.submit_user_message(text.into());
+                InputResult::Submitted { text, image_paths } => {
+                    self.submit_user_message(UserMessage { text, image_paths });
+                }
+                InputResult::Steer { text, image_paths } => {
+                    self.steer(UserMessage { text, image_paths });
                 }
                 InputResult::None => {}
             },
         }
     }
 
+    fn set_task_finished(&mut self) {
+        self.task_running = false;
+        self.queued_messages = 0;
+        self.bottom_pane.set_task_running(false);
+        // An interrupted message stays as far as it got.
+        self.conversation_history.finish_streams(&self.config);
+    }
+
+    /// Directories besides the working directory that `@path` mentions may
+    /// point into: the sandbox's writable roots.
+    fn mention_roots(&self) -> Vec<PathBuf> {
+        self.config
+            .sandbox_policy
+            .get_writable_roots_with_cwd(&self.config.cwd)
+    }
+
+    /// Interrupt the running turn and continue with `user_message` as the new
+    /// instruction; an empty message just interrupts.
+    fn steer(&mut self, user_message: UserMessage) {
+        let text_only = user_message.image_paths.is_empty()
+            && find_file_mentions(&user_message.text, &self.config.cwd, &self.mention_roots())
+                .is_empty();
+        if user_message.text.is_empty() && user_message.image_paths.is_empty() {
+            self.submit_op(Op::Interrupt { replace_with: None });
+        } else if text_only {
+            let text = user_message.text;
+            self.submit_op(Op::Interrupt {
+                replace_with: Some(text.clone()),
+            });
+            self.submit_op(Op::AddToHistory { text: text.clone() });
+            self.conversation_history.add_user_message(text);
+            self.conversation_history.scroll_to_bottom();
+        } else {
+            // Attachments need a full `UserInput`, which starts a new task
+            // once the interrupt has been processed.
+            self.submit_op(Op::Interrupt { replace_with: None });
+            self.task_running = false;
+            self.submit_user_message(user_message);
+        }
+        self.queued_messages = 0;
+        self.bottom_pane.set_queued_message_count(0);
+        self.request_redraw();
+    }
+
     fn submit_user_message(&mut self, user_message: UserMessage) {
         let UserMessage { text, image_paths } = user_message;
         let mut items: Vec<InputItem> = Vec::new();
@@ -177,8 +373,15 @@ impl ChatWidget<'_> {
             items.push(InputItem::Text { text: text.clone() });
         }
 
-        for path in image_paths {
-            items.push(InputItem::LocalImage { path });
+        for path in &image_paths {
+            items.push(InputItem::LocalImage { path: path.clone() });
+        }
+
+        let file_mentions = find_file_mentions(&text, &self.config.cwd, &self.mention_roots());
+        for mention in &file_mentions {
+            items.push(InputItem::LocalFile {
+                path: mention.path.clone(),
+            });
         }
 
         if items.is_empty() {
@@ -186,11 +389,20 @@ impl ChatWidget<'_> {
         }
 
         self.codex_op_tx
-            .send(Op::UserInput { items })
+            .send(Op::UserInput {
+                items,
+                response_format: None,
+            })
             .unwrap_or_else(|e| {
                 tracing::error!("failed to send message: {e}");
             });
 
+        if self.task_running {
+            self.queued_messages += 1;
+            self.bottom_pane
+                .set_queued_message_count(self.queued_messages);
+        }
+
         // Persist the text to cross-session message history.
         if !text.is_empty() {
             self.codex_op_tx
@@ -200,25 +412,265 @@ impl ChatWidget<'_> {
                 });
         }
 
-        // Only show text portion in conversation history for now.
-        if !text.is_empty() {
-            self.conversation_history.add_user_message(text);
+        // Images are listed by path beneath the text portion.
+        let mut display = text;
+        for path in &image_paths {
+            if !display.is_empty() {
+                display.push('\n');
+            }
+            display.push_str(&format!("[image: {}]", path.display()));
+        }
+        for mention in &file_mentions {
+            display.push('\n');
+            display.push_str(&mention.chip(self.config.attachments.max_file_bytes));
+        }
+        if !display.is_empty() {
+            self.conversation_history.add_user_message(display);
         }
         self.conversation_history.scroll_to_bottom();
     }
 
+    /// Handle a bracketed paste from the terminal.
+    pub(crate) fn handle_paste(&mut self, pasted: String) {
+        if self.input_focus == InputFocus::BottomPane {
+            self.bottom_pane.handle_paste(pasted);
+        }
+    }
+
+    pub(crate) fn show_attach_image_view(&mut self) {
+        self.bottom_pane.show_attach_image_view();
+    }
+
     pub(crate) fn clear_conversation_history(&mut self) {
         self.conversation_history.clear();
         self.request_redraw();
     }
 
+    pub(crate) fn set_workspace_index(&mut self, index: Arc<WorkspaceIndex>) {
+        self.bottom_pane.set_workspace_index(index);
+    }
+
+    /// Submit the expanded text of a custom prompt command.
+    pub(crate) fn submit_prompt(&mut self, text: String) {
+        self.submit_user_message(UserMessage {
+            text,
+            image_paths: Vec::new(),
+        });
+    }
+
+    /// Run a slash command selected in the composer.
+    pub(crate) fn dispatch_command(&mut self, command: &dyn SlashCommand, args: &str) {
+        let ctx = SlashCommandContext {
+            config: &self.config,
+            token_usage: &self.token_usage,
+            session_id: self.session_id,
+            app_event_tx: &self.app_event_tx,
+        };
+        command.execute(args, &ctx);
+    }
+
+    /// Replay the messages of a resumed session so the conversation picks up
+    /// where it left off.
+    fn show_resumed_conversation(&mut self) {
+        let Some(path) = self.config.resume_from.as_deref() else {
+            return;
+        };
+        let transcript = match Transcript::load(path) {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                self.conversation_history
+                    .add_error(format!("failed to read resumed session: {e}"));
+                return;
+            }
+        };
+        for entry in transcript.entries {
+            match entry {
+                Entry::User { text } => self.conversation_history.add_user_message(text),
+                Entry::Agent { text } => self
+                    .conversation_history
+                    .add_agent_message(&self.config, text),
+                Entry::Command { .. } | Entry::Patch { .. } | Entry::ToolCall { .. } => {}
+            }
+        }
+        self.conversation_history.scroll_to_bottom();
+    }
+
+    pub(crate) fn add_replayed_user_message(&mut self, text: String) {
+        self.conversation_history.add_user_message(text);
+        self.conversation_history.scroll_to_bottom();
+        self.request_redraw();
+    }
+
+    pub(crate) fn set_replay_status(&mut self, status: String) {
+        self.replay_status = Some(status);
+        self.request_redraw();
+    }
+
+    pub(crate) fn add_info_message(&mut self, message: String) {
+        self.conversation_history.add_background_event(message);
+        self.conversation_history.scroll_to_bottom();
+        self.request_redraw();
+    }
+
+    /// Whether the managed config locks `key`, which the agent would refuse
+    /// to change, reporting it if so.
+    fn refuse_locked(&mut self, key: &str) -> bool {
+        if !self.config.locks(key) {
+            return false;
+        }
+        self.conversation_history
+            .add_error(format!("{key} is locked by the managed configuration"));
+        self.conversation_history.scroll_to_bottom();
+        self.request_redraw();
+        true
+    }
+
+    /// Ask the agent to switch to `model`, keeping the conversation. The
+    /// config is updated once the agent confirms the switch.
+    pub(crate) fn set_model(&mut self, model: String) {
+        if self.refuse_locked("model") {
+            return;
+        }
+        self.submit_op(Op::OverrideTurnContext {
+            model: Some(model),
+            effort: None,
+            summary: None,
+            approval_policy: None,
+            mode: None,
+            dry_run: None,
+        });
+    }
+
+    pub(crate) fn set_approval_policy(&mut self, policy: AskForApproval) {
+        if self.refuse_locked("approval_policy") {
+            return;
+        }
+        self.config.approval_policy = policy;
+        self.submit_op(Op::OverrideTurnContext {
+            model: None,
+            effort: None,
+            summary: None,
+            approval_policy: Some(policy),
+            mode: None,
+            dry_run: None,
+        });
+        self.add_info_message(format!("approval policy set to {policy:?}"));
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: SessionMode) {
+        if self.refuse_locked("mode") {
+            return;
+        }
+        self.config.mode = mode;
+        self.submit_op(Op::OverrideTurnContext {
+            model: None,
+            effort: None,
+            summary: None,
+            approval_policy: None,
+            mode: Some(mode),
+            dry_run: None,
+        });
+        self.add_info_message(match mode {
+            SessionMode::Ask => {
+                "ask mode: the agent can read and search files but not change anything".to_string()
+            }
+            SessionMode::Code => "code mode: the agent can edit files and run commands".to_string(),
+        });
+    }
+
+    pub(crate) fn set_dry_run(&mut self, dry_run: bool) {
+        if self.refuse_locked("dry_run") {
+            return;
+        }
+        self.config.dry_run = dry_run;
+        self.submit_op(Op::OverrideTurnContext {
+            model: None,
+            effort: None,
+            summary: None,
+            approval_policy: None,
+            mode: None,
+            dry_run: Some(dry_run),
+        });
+        self.add_info_message(if dry_run {
+            "dry run: shell commands are shown but not executed".to_string()
+        } else {
+            "dry run off: shell commands are executed again".to_string()
+        });
+    }
+
+    /// Toggle the diff viewer. It opens once the diff has been computed in
+    /// the background.
+    pub(crate) fn show_diff(&mut self) {
+        if self.diff_view.take().is_some() {
+            self.diff_requested = false;
+            self.request_redraw();
+        } else if !self.diff_requested {
+            self.diff_requested = true;
+            self.compute_diff();
+        }
+    }
+
+    /// Recompute the diff shown in the viewer, if it is open, so it reflects
+    /// patches applied since it was opened.
+    fn refresh_diff_view(&mut self) {
+        if self.diff_view.is_some() {
+            self.compute_diff();
+        }
+    }
+
+    /// Run `git diff` and highlight its output off the UI thread; the result
+    /// comes back as [`AppEvent::DiffReady`].
+    fn compute_diff(&self) {
+        let config = self.config.clone();
+        let app_event_tx = self.app_event_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let files = get_git_diff(&config)
+                .map(|diff| diff.map(|d| parse_diff(&d, config.tui.code_theme.as_deref())))
+                .map_err(|e| e.to_string());
+            app_event_tx.send(AppEvent::DiffReady(files));
+        });
+    }
+
+    /// Show a diff computed by [`Self::compute_diff`]: refresh the open
+    /// viewer, or open it if `/diff` asked for it.
+    pub(crate) fn on_diff_ready(&mut self, files: Result<Option<Vec<FileDiff>>, String>) {
+        if let Some(diff_view) = self.diff_view.as_mut() {
+            match files {
+                Ok(Some(files)) => diff_view.update(files),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("failed to refresh diff: {e}"),
+            }
+        } else if std::mem::take(&mut self.diff_requested) {
+            match files {
+                Ok(Some(files)) => self.diff_view = Some(DiffView::new(files)),
+                Ok(None) => {
+                    self.conversation_history
+                        .add_error("`/diff` requires a Git repository.".to_string());
+                    self.conversation_history.scroll_to_bottom();
+                }
+                Err(e) => {
+                    self.conversation_history
+                        .add_error(format!("failed to compute diff: {e}"));
+                    self.conversation_history.scroll_to_bottom();
+                }
+            }
+        } else {
+            return;
+        }
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_codex_event(&mut self, event: Event) {
         let Event { id, msg } = event;
         match msg {
             EventMsg::SessionConfigured(event) => {
+                let first_configure = self.session_id.replace(event.session_id).is_none();
                 // Record session information at the top of the conversation.
                 self.conversation_history
                     .add_session_info(&self.config, event.clone());
+                if first_configure {
+                    self.show_resumed_conversation();
+                }
 
                 // Forward history metadata to the bottom pane so the chat
                 // composer can navigate through past messages.
@@ -238,6 +690,11 @@ impl ChatWidget<'_> {
                     .add_agent_message(&self.config, message);
                 self.request_redraw();
             }
+            EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
+                self.conversation_history
+                    .append_agent_message_delta(&self.config, &delta);
+                self.request_redraw();
+            }
             EventMsg::AgentReasoning(AgentReasoningEvent { text }) => {
                 if !self.config.hide_agent_reasoning {
                     self.conversation_history
@@ -245,30 +702,68 @@ impl ChatWidget<'_> {
                     self.request_redraw();
                 }
             }
+            EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }) => {
+                if !self.config.hide_agent_reasoning {
+                    self.conversation_history
+                        .append_agent_reasoning_delta(&self.config, &delta);
+                    self.request_redraw();
+                }
+            }
             EventMsg::TaskStarted => {
+                self.notifier.on_task_started();
+                self.task_running = true;
                 self.bottom_pane.set_task_running(true);
                 self.request_redraw();
             }
             EventMsg::TaskComplete(TaskCompleteEvent {
-                last_agent_message: _,
+                last_agent_message,
+                stats,
             }) => {
-                self.bottom_pane.set_task_running(false);
+                if let Some(stats) = stats {
+                    self.conversation_history.add_task_stats(&stats);
+                }
+                self.notifier
+                    .on_task_complete(last_agent_message.as_deref());
+                self.set_task_finished();
                 self.request_redraw();
             }
             EventMsg::Error(ErrorEvent { message }) => {
                 self.conversation_history.add_error(message);
-                self.bottom_pane.set_task_running(false);
+                self.set_task_finished();
             }
             EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
                 command,
                 cwd,
                 reason,
+                risk,
             }) => {
+                let summary = format!("run `{}`", strip_bash_lc_and_escape(&command));
+                self.notifier.on_approval_request(&summary);
                 let request = ApprovalRequest::Exec {
                     id,
                     command,
                     cwd,
                     reason,
+                    risk,
+                };
+                self.bottom_pane.push_approval_request(request);
+            }
+            EventMsg::SandboxEscalationRequest(SandboxEscalationRequestEvent {
+                command,
+                cwd,
+                access,
+                ..
+            }) => {
+                self.notifier
+                    .on_approval_request(&format!("grant {access} to the sandbox"));
+                let request = ApprovalRequest::Exec {
+                    id,
+                    command,
+                    cwd,
+                    reason: Some(format!(
+                        "The sandbox denied this command {access}. Grant it to sandboxed commands for the rest of this session and retry in the sandbox? Press `a` to also remember it for this workspace."
+                    )),
+                    risk: None,
                 };
                 self.bottom_pane.push_approval_request(request);
             }
@@ -288,6 +783,8 @@ impl ChatWidget<'_> {
                 // prompt before they have seen *what* is being requested.
                 // ------------------------------------------------------------------
 
+                let summary = format!("apply changes to {} file(s)", changes.len());
+                self.notifier.on_approval_request(&summary);
                 self.conversation_history
                     .add_patch_event(PatchEventType::ApprovalRequest, changes);
 
@@ -302,6 +799,17 @@ impl ChatWidget<'_> {
                 self.bottom_pane.push_approval_request(request);
                 self.request_redraw();
             }
+            EventMsg::TurnDiffApprovalRequest(TurnDiffApprovalRequestEvent { changes }) => {
+                let summary = format!("review changes to {} file(s)", changes.len());
+                self.notifier.on_approval_request(&summary);
+                let files = changes.keys().cloned().collect();
+                self.conversation_history
+                    .add_patch_event(PatchEventType::TurnReview, changes);
+                self.conversation_history.scroll_to_bottom();
+                self.bottom_pane
+                    .show_turn_review(id, self.config.cwd.clone(), files);
+                self.request_redraw();
+            }
             EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
                 call_id,
                 command,
@@ -325,6 +833,15 @@ impl ChatWidget<'_> {
                 }
                 self.request_redraw();
             }
+            EventMsg::PatchProposed(PatchProposedEvent {
+                call_id: _,
+                changes,
+            }) => {
+                // Nothing is written: applying the patch is up to the editor.
+                self.conversation_history
+                    .add_patch_event(PatchEventType::ApprovalRequest, changes);
+                self.request_redraw();
+            }
             EventMsg::ExecCommandEnd(ExecCommandEndEvent {
                 call_id,
                 exit_code,
@@ -352,6 +869,97 @@ impl ChatWidget<'_> {
                     .record_completed_mcp_tool_call(call_id, success, result);
                 self.request_redraw();
             }
+            EventMsg::TokenCount(usage) => {
+                self.token_usage.add_assign(&usage);
+                self.last_token_usage = Some(usage);
+                self.request_redraw();
+            }
+            EventMsg::SecretsRedacted(event) => {
+                self.conversation_history
+                    .add_background_event(event.summary());
+                self.request_redraw();
+            }
+            EventMsg::ToolCallRepair(event) => {
+                self.conversation_history
+                    .add_background_event(event.summary());
+                self.request_redraw();
+            }
+            EventMsg::CheckpointCreated(CheckpointCreatedEvent { number, automatic }) => {
+                // An automatic checkpoint is taken before the prompt that
+                // started the task, which is already in the history.
+                let position = if automatic {
+                    self.conversation_history
+                        .last_user_message()
+                        .unwrap_or_else(|| self.conversation_history.entry_count())
+                } else {
+                    self.conversation_history.entry_count()
+                };
+                self.checkpoints.truncate(number.saturating_sub(1));
+                self.checkpoints.push(position);
+                if !automatic {
+                    self.conversation_history
+                        .add_background_event(format!("checkpoint {number}"));
+                    self.request_redraw();
+                }
+            }
+            EventMsg::Rewound(event) => {
+                if let Some(&position) = self.checkpoints.get(event.checkpoint.saturating_sub(1)) {
+                    self.conversation_history.truncate(position);
+                }
+                self.checkpoints.truncate(event.checkpoint);
+                self.conversation_history
+                    .add_background_event(event.summary());
+                self.request_redraw();
+            }
+            EventMsg::DryRunExec(event) => {
+                self.conversation_history
+                    .add_background_event(event.summary());
+                self.request_redraw();
+            }
+            EventMsg::TurnContextOverridden(event) => {
+                // Switches of the approval policy, mode or dry run are
+                // reported when the user makes them.
+                if event.model != self.config.model
+                    || event.effort != self.config.model_reasoning_effort
+                {
+                    self.conversation_history
+                        .add_background_event(event.summary());
+                    self.conversation_history.scroll_to_bottom();
+                }
+                self.config.model = event.model;
+                self.config.model_reasoning_effort = event.effort;
+                self.config.model_reasoning_summary = event.summary;
+                self.request_redraw();
+            }
+            EventMsg::CopilotPlanDetected(event) => {
+                self.conversation_history
+                    .add_background_event(event.summary());
+                self.request_redraw();
+            }
+            EventMsg::RateLimited(event) => {
+                self.conversation_history
+                    .add_background_event(event.summary());
+                self.request_redraw();
+            }
+            EventMsg::SubAgent(event) => {
+                // Sub-agents spend tokens too, but their requests say nothing
+                // about how full the main agent's context window is.
+                if let EventMsg::TokenCount(usage) = event.msg.as_ref() {
+                    self.token_usage.add_assign(usage);
+                }
+                if let Some(summary) = event.summary() {
+                    self.conversation_history.add_background_event(summary);
+                }
+                self.request_redraw();
+            }
+            EventMsg::PlanUpdated(PlanUpdatedEvent { explanation, plan }) => {
+                if let Some(explanation) = explanation {
+                    self.conversation_history
+                        .add_background_event(format!("plan: {explanation}"));
+                }
+                self.bottom_pane.set_plan(plan);
+                self.request_redraw();
+            }
             EventMsg::GetHistoryEntryResponse(event) => {
                 let codex_core::protocol::GetHistoryEntryResponseEvent {
                     offset,
@@ -364,6 +972,9 @@ impl ChatWidget<'_> {
                     .on_history_entry_response(log_id, offset, entry.map(|e| e.text));
             }
             event => {
+                if matches!(event, EventMsg::PatchApplyEnd(_)) {
+                    self.refresh_diff_view();
+                }
                 self.conversation_history
                     .add_background_event(format!("{event:?}"));
                 self.request_redraw();
@@ -371,6 +982,10 @@ impl ChatWidget<'_> {
         }
     }
 
+    pub(crate) fn set_terminal_focused(&mut self, focused: bool) {
+        self.notifier.set_focused(focused);
+    }
+
     /// Update the live log preview while a task is running.
     pub(crate) fn update_latest_log(&mut self, line: String) {
         // Forward only if we are currently showing the status indicator.
@@ -395,6 +1010,10 @@ impl ChatWidget<'_> {
     }
 
     /// Forward an `Op` directly to codex.
+    pub(crate) fn set_composer_text(&mut self, text: &str) {
+        self.bottom_pane.set_composer_text(text);
+    }
+
     pub(crate) fn submit_op(&self, op: Op) {
         if let Err(e) = self.codex_op_tx.send(op) {
             tracing::error!("failed to submit op: {e}");
@@ -404,14 +1023,33 @@ impl ChatWidget<'_> {
 
 impl WidgetRef for &ChatWidget<'_> {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let bottom_height = self.bottom_pane.calculate_required_height(&area);
+        let bottom_height = match self.replay_status {
+            Some(_) => 1,
+            None => self.bottom_pane.calculate_required_height(&area),
+        };
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(0), Constraint::Length(bottom_height)])
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(bottom_height),
+            ])
             .split(area);
 
-        self.conversation_history.render(chunks[0], buf);
-        (&self.bottom_pane).render(chunks[1], buf);
+        StatusLine {
+            config: &self.config,
+            total_usage: &self.token_usage,
+            last_usage: self.last_token_usage.as_ref(),
+        }
+        .render(chunks[0], buf);
+        match &self.diff_view {
+            Some(diff_view) => diff_view.render_ref(chunks[1], buf),
+            None => self.conversation_history.render(chunks[1], buf),
+        }
+        match &self.replay_status {
+            Some(status) => Paragraph::new(status.as_str().dim()).render(chunks[2], buf),
+            None => (&self.bottom_pane).render(chunks[2], buf),
+        }
     }
 }
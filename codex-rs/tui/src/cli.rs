@@ -2,6 +2,7 @@ use clap::Parser;
 use codex_common::ApprovalModeCliArg;
 use codex_common::CliConfigOverrides;
 use codex_common::SandboxPermissionOption;
+use codex_common::SessionModeCliArg;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -22,6 +23,15 @@ pub struct Cli {
     #[arg(long = "profile", short = 'p')]
     pub config_profile: Option<String>,
 
+    /// Sampling temperature, between 0 and 2. Lower values make runs more
+    /// deterministic.
+    #[arg(long = "temperature")]
+    pub temperature: Option<f32>,
+
+    /// Seed for repeatable sampling, for providers that support it.
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+
     /// Configure when the model requires human approval before executing a command.
     #[arg(long = "ask-for-approval", short = 'a')]
     pub approval_policy: Option<ApprovalModeCliArg>,
@@ -33,14 +43,61 @@ pub struct Cli {
     #[clap(flatten)]
     pub sandbox: SandboxPermissionOption,
 
+    /// `ask` keeps the agent read-only: it can read and search files but not
+    /// edit them or run other commands. Toggle it in the session with `/mode`.
+    #[arg(long = "mode")]
+    pub mode: Option<SessionModeCliArg>,
+
+    /// Show the shell commands the agent proposes instead of running them;
+    /// the agent is told they were not executed. Toggle it in the session
+    /// with `/dry-run`.
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+
     /// Tell the agent to use the specified directory as its working root.
     #[clap(long = "cd", short = 'C', value_name = "DIR")]
     pub cwd: Option<PathBuf>,
 
+    /// Subdirectory of the working root to concentrate on in a large
+    /// repository: file completion and the repository map only cover it and,
+    /// on Linux, sandboxed commands can only read it.
+    #[arg(long = "focus", value_name = "DIR")]
+    pub focus: Option<PathBuf>,
+
+    /// Forbid all network access except to providers on this machine, e.g.
+    /// Ollama. Commands run by the agent lose network access as well.
+    #[arg(long = "offline", default_value_t = false)]
+    pub offline: bool,
+
     /// Allow running Codex outside a Git repository.
     #[arg(long = "skip-git-repo-check", default_value_t = false)]
     pub skip_git_repo_check: bool,
 
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
+
+    /// Earlier session to continue; set by `codex resume`.
+    #[clap(skip)]
+    pub resume: Option<ResumeTarget>,
+
+    /// Recorded session to play back instead of running one; set by
+    /// `codex replay`.
+    #[clap(skip)]
+    pub replay: Option<ReplayTarget>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ResumeTarget {
+    /// Let the user choose from a list of recent sessions.
+    Picker,
+    /// The session with this id (or unique id prefix).
+    Session(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayTarget {
+    /// Session id (or unique id prefix).
+    pub id: String,
+    /// Playback speed, as a multiple of the default pace.
+    pub speed: f64,
 }
@@ -0,0 +1,130 @@
+//! Helpers for attaching pasted images to the composer.
+//!
+//! Terminals deliver pastes in two ways: the bracketed-paste text (which is
+//! what kitty/iTerm2 send when a file is dragged in, i.e. its path) and the
+//! system clipboard, which we can only reach by reading it directly. Both
+//! end up as a local image path that is attached to the pending message.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use image::ImageFormat;
+use tempfile::TempPath;
+
+#[derive(Debug)]
+pub(crate) enum PasteImageError {
+    ClipboardUnavailable(String),
+    NoImage(String),
+    EncodeFailed(String),
+    IoError(String),
+}
+
+impl std::fmt::Display for PasteImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasteImageError::ClipboardUnavailable(msg) => {
+                write!(f, "clipboard unavailable: {msg}")
+            }
+            PasteImageError::NoImage(msg) => write!(f, "no image on clipboard: {msg}"),
+            PasteImageError::EncodeFailed(msg) => write!(f, "could not encode image: {msg}"),
+            PasteImageError::IoError(msg) => write!(f, "io error: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for PasteImageError {}
+
+/// Dimensions of an image taken from the clipboard, used for the inline
+/// placeholder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PastedImageInfo {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Read an image from the system clipboard, encode it as PNG and write it to
+/// a temporary file so it can be attached like any other local image. The
+/// file is deleted when the returned path is dropped.
+pub(crate) fn paste_image_to_temp_png() -> Result<(TempPath, PastedImageInfo), PasteImageError> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| PasteImageError::ClipboardUnavailable(e.to_string()))?;
+    let img = clipboard
+        .get_image()
+        .map_err(|e| PasteImageError::NoImage(e.to_string()))?;
+
+    let width = img.width as u32;
+    let height = img.height as u32;
+    let Some(rgba) = image::RgbaImage::from_raw(width, height, img.bytes.into_owned()) else {
+        return Err(PasteImageError::EncodeFailed(
+            "clipboard returned an invalid RGBA buffer".to_string(),
+        ));
+    };
+
+    let mut file = tempfile::Builder::new()
+        .prefix("codex-clipboard-")
+        .suffix(".png")
+        .tempfile()
+        .map_err(|e| PasteImageError::IoError(e.to_string()))?;
+    rgba.write_to(&mut file, ImageFormat::Png)
+        .map_err(|e| PasteImageError::EncodeFailed(e.to_string()))?;
+
+    Ok((file.into_temp_path(), PastedImageInfo { width, height }))
+}
+
+/// If `pasted` is the path of an existing image file (optionally quoted or
+/// given as a `file://` URL, as terminals do for drag-and-drop), return it.
+pub(crate) fn pasted_image_path(pasted: &str) -> Option<PathBuf> {
+    let trimmed = pasted.trim();
+    if trimmed.is_empty() || trimmed.contains('\n') {
+        return None;
+    }
+
+    let unquoted = trimmed
+        .strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .or_else(|| trimmed.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+        .unwrap_or(trimmed);
+    let raw = unquoted.strip_prefix("file://").unwrap_or(unquoted);
+    // Shells escape spaces when a file is dropped onto the terminal.
+    let unescaped = raw.replace("\\ ", " ");
+
+    let path = Path::new(&unescaped);
+    if path.is_file() && ImageFormat::from_path(path).is_ok() {
+        Some(path.to_path_buf())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn recognizes_dropped_image_paths() {
+        let dir = TempDir::new().unwrap();
+        let dir = dir.path();
+        let png = dir.join("my shot.png");
+        std::fs::write(&png, b"png").unwrap();
+        let txt = dir.join("notes.txt");
+        std::fs::write(&txt, b"txt").unwrap();
+
+        let plain = png.to_str().unwrap().to_string();
+        assert_eq!(pasted_image_path(&plain), Some(png.clone()));
+        assert_eq!(pasted_image_path(&format!("'{plain}'")), Some(png.clone()));
+        assert_eq!(
+            pasted_image_path(&format!("file://{plain}")),
+            Some(png.clone())
+        );
+        assert_eq!(
+            pasted_image_path(&plain.replace(' ', "\\ ")),
+            Some(png.clone())
+        );
+
+        assert_eq!(pasted_image_path(txt.to_str().unwrap()), None);
+        assert_eq!(pasted_image_path("just some text"), None);
+        assert_eq!(pasted_image_path(&format!("{plain}\n{plain}")), None);
+    }
+}
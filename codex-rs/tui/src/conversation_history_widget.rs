@@ -2,9 +2,11 @@ use crate::cell_widget::CellWidget;
 use crate::history_cell::CommandOutput;
 use crate::history_cell::HistoryCell;
 use crate::history_cell::PatchEventType;
+use crate::theme::theme;
 use codex_core::config::Config;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_core::protocol::TaskStats;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use ratatui::prelude::*;
@@ -33,6 +35,25 @@ pub struct ConversationHistoryWidget {
     /// The height of the viewport last time render_ref() was called
     last_viewport_height: StdCell<usize>,
     has_input_focus: bool,
+    /// Active transcript search, if any.
+    search: Option<TranscriptSearch>,
+    /// Whether completed reasoning summaries are shown in full or collapsed
+    /// to their header; toggled with `t`.
+    reasoning_expanded: bool,
+    /// Index of the entry of the agent message being streamed, so that each
+    /// delta goes straight to it.
+    streaming_message: Option<usize>,
+    /// Likewise for the reasoning summary being streamed.
+    streaming_reasoning: Option<usize>,
+}
+
+/// State of the `/` search in the transcript.
+struct TranscriptSearch {
+    query: String,
+    /// True while the query is being typed.
+    editing: bool,
+    /// Index of the entry the last `n`/`N` jump landed on.
+    current_entry: Option<usize>,
 }
 
 impl ConversationHistoryWidget {
@@ -44,6 +65,10 @@ impl ConversationHistoryWidget {
             num_rendered_lines: StdCell::new(0),
             last_viewport_height: StdCell::new(0),
             has_input_focus: false,
+            search: None,
+            reasoning_expanded: true,
+            streaming_message: None,
+            streaming_reasoning: None,
         }
     }
 
@@ -53,6 +78,26 @@ impl ConversationHistoryWidget {
 
     /// Returns true if it needs a redraw.
     pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        if let Some(search) = self.search.as_mut().filter(|s| s.editing) {
+            match key_event.code {
+                KeyCode::Esc => self.search = None,
+                KeyCode::Enter => {
+                    search.editing = false;
+                    if search.query.is_empty() {
+                        self.search = None;
+                    } else {
+                        self.jump_to_match(SearchDirection::Forward);
+                    }
+                }
+                KeyCode::Backspace => {
+                    search.query.pop();
+                }
+                KeyCode::Char(c) => search.query.push(c),
+                _ => return false,
+            }
+            return true;
+        }
+
         match key_event.code {
             KeyCode::Up | KeyCode::Char('k') => {
                 self.scroll_up(1);
@@ -70,10 +115,148 @@ impl ConversationHistoryWidget {
                 self.scroll_page_down();
                 true
             }
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.scroll_position = 0;
+                true
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                self.scroll_to_bottom();
+                true
+            }
+            KeyCode::Char('t') => {
+                self.toggle_reasoning();
+                true
+            }
+            KeyCode::Char('/') => {
+                self.search = Some(TranscriptSearch {
+                    query: String::new(),
+                    editing: true,
+                    current_entry: None,
+                });
+                true
+            }
+            KeyCode::Char('n') if self.search.is_some() => {
+                self.jump_to_match(SearchDirection::Forward);
+                true
+            }
+            KeyCode::Char('N') if self.search.is_some() => {
+                self.jump_to_match(SearchDirection::Backward);
+                true
+            }
+            KeyCode::Esc if self.search.is_some() => {
+                self.search = None;
+                true
+            }
             _ => false,
         }
     }
 
+    /// Whether the search query is being typed, in which case every printable
+    /// key belongs to the search box.
+    pub(crate) fn is_editing_search(&self) -> bool {
+        self.search.as_ref().is_some_and(|s| s.editing)
+    }
+
+    /// Indices of the entries whose text contains `query` (case-insensitive).
+    fn matching_entries(&self, query: &str) -> Vec<usize> {
+        let query = query.to_lowercase();
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.cell.plain_text().to_lowercase().contains(&query))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Index of the first entry that is at least partly visible.
+    fn first_visible_entry(&self) -> usize {
+        let max_scroll = self
+            .num_rendered_lines
+            .get()
+            .saturating_sub(self.last_viewport_height.get());
+        let mut remaining = self.scroll_position.min(max_scroll);
+        for (idx, entry) in self.entries.iter().enumerate() {
+            let height = entry.line_count.get();
+            if remaining < height {
+                return idx;
+            }
+            remaining -= height;
+        }
+        self.entries.len().saturating_sub(1)
+    }
+
+    /// Scroll so the next (or previous) matching entry is at the top of the
+    /// viewport, wrapping around at either end of the transcript.
+    fn jump_to_match(&mut self, direction: SearchDirection) {
+        let Some(query) = self.search.as_ref().map(|s| s.query.clone()) else {
+            return;
+        };
+        let matches = self.matching_entries(&query);
+        if matches.is_empty() {
+            if let Some(search) = self.search.as_mut() {
+                search.current_entry = None;
+            }
+            return;
+        }
+
+        // Continue from the last match; on the first jump, start from the top
+        // of the viewport so the result is at or below what is on screen.
+        let current = self.search.as_ref().and_then(|s| s.current_entry);
+        let target = match (direction, current) {
+            (SearchDirection::Forward, Some(cur)) => matches.iter().find(|&&idx| idx > cur),
+            (SearchDirection::Forward, None) => {
+                let first = self.first_visible_entry();
+                matches.iter().find(|&&idx| idx >= first)
+            }
+            (SearchDirection::Backward, cur) => {
+                let before = cur.unwrap_or_else(|| self.first_visible_entry());
+                matches.iter().rev().find(|&&idx| idx < before)
+            }
+        };
+        let target = match (target, direction) {
+            (Some(&idx), _) => idx,
+            (None, SearchDirection::Forward) => matches[0],
+            (None, SearchDirection::Backward) => matches[matches.len() - 1],
+        };
+
+        if let Some(search) = self.search.as_mut() {
+            search.current_entry = Some(target);
+        }
+        self.scroll_position = self.entries[..target]
+            .iter()
+            .map(|e| e.line_count.get())
+            .sum();
+    }
+
+    /// Title shown on the border: the key help, or the search status while a
+    /// search is active.
+    fn title(&self) -> String {
+        match &self.search {
+            Some(TranscriptSearch {
+                query,
+                editing: true,
+                ..
+            }) => format!("Search: {query}▌ (Enter to search, Esc to cancel)"),
+            Some(TranscriptSearch {
+                query,
+                current_entry,
+                ..
+            }) => {
+                let matches = self.matching_entries(query);
+                let position = current_entry
+                    .and_then(|cur| matches.iter().position(|&idx| idx == cur))
+                    .map(|pos| format!("{}/{}", pos + 1, matches.len()))
+                    .unwrap_or_else(|| format!("0/{}", matches.len()));
+                format!("Search \"{query}\" {position} (n/N = next/prev, Esc = clear)")
+            }
+            None if self.has_input_focus => {
+                "Messages (↑/↓ or j/k = line,  b/space = page,  g/G = top/bottom,  / = search,  t = thinking)"
+                    .to_string()
+            }
+            None => "Messages (tab to focus)".to_string(),
+        }
+    }
+
     /// Negative delta scrolls up; positive delta scrolls down.
     pub(crate) fn scroll(&mut self, delta: i32) {
         match delta.cmp(&0) {
@@ -123,7 +306,7 @@ impl ConversationHistoryWidget {
     }
 
     /// Scroll up by one full viewport height (Page Up).
-    fn scroll_page_up(&mut self) {
+    pub(crate) fn scroll_page_up(&mut self) {
         let viewport_height = self.last_viewport_height.get().max(1);
 
         // If we are currently in the "stick to bottom" mode, first convert the
@@ -142,7 +325,7 @@ impl ConversationHistoryWidget {
     }
 
     /// Scroll down by one full viewport height (Page Down).
-    fn scroll_page_down(&mut self) {
+    pub(crate) fn scroll_page_down(&mut self) {
         // Nothing to do if we're already stuck to the bottom.
         if self.scroll_position == usize::MAX {
             return;
@@ -194,12 +377,114 @@ impl ConversationHistoryWidget {
         self.add_to_history(HistoryCell::new_user_prompt(message));
     }
 
+    /// Add the complete agent message, replacing the streaming preview of it
+    /// if there is one.
     pub fn add_agent_message(&mut self, config: &Config, message: String) {
-        self.add_to_history(HistoryCell::new_agent_message(config, message));
+        let cell = HistoryCell::new_agent_message(config, message);
+        let width = self.cached_width.get();
+        match self.streaming_message.take() {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.cell = cell;
+                if width > 0 {
+                    entry.line_count.set(entry.cell.height(width));
+                }
+            }
+            None => self.add_to_history(cell),
+        }
+    }
+
+    /// Append a delta to the agent message being streamed, starting a new
+    /// entry for the first delta of a message.
+    pub fn append_agent_message_delta(&mut self, config: &Config, delta: &str) {
+        if self.streaming_message.is_none() {
+            self.streaming_message = Some(self.entries.len());
+            self.add_to_history(HistoryCell::new_streaming_agent_message());
+        }
+        let width = self.cached_width.get();
+        if let Some(entry) = self.streaming_agent_message() {
+            entry.cell.push_agent_message_delta(config, delta);
+            if width > 0 {
+                entry.line_count.set(entry.cell.height(width));
+            }
+        }
+    }
+
+    /// Render a message or reasoning summary that stopped streaming (e.g.
+    /// because the turn was interrupted) as a regular one.
+    pub fn finish_streams(&mut self, config: &Config) {
+        if let Some(Entry {
+            cell: HistoryCell::StreamingAgentReasoning { stream, .. },
+            ..
+        }) = self.streaming_agent_reasoning()
+        {
+            let source = stream.source().to_string();
+            self.add_agent_reasoning(config, source);
+        }
+        if let Some(Entry {
+            cell: HistoryCell::StreamingAgentMessage { stream, .. },
+            ..
+        }) = self.streaming_agent_message()
+        {
+            let source = stream.source().to_string();
+            self.add_agent_message(config, source);
+        }
     }
 
+    fn streaming_agent_message(&mut self) -> Option<&mut Entry> {
+        self.entries.get_mut(self.streaming_message?)
+    }
+
+    /// Add a complete reasoning summary, replacing the streaming preview of
+    /// it if there is one.
     pub fn add_agent_reasoning(&mut self, config: &Config, text: String) {
-        self.add_to_history(HistoryCell::new_agent_reasoning(config, text));
+        let cell = HistoryCell::new_agent_reasoning(config, text, self.reasoning_expanded);
+        let width = self.cached_width.get();
+        match self.streaming_reasoning.take() {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.cell = cell;
+                if width > 0 {
+                    entry.line_count.set(entry.cell.height(width));
+                }
+            }
+            None => self.add_to_history(cell),
+        }
+    }
+
+    /// Append a delta to the reasoning summary being streamed, starting a new
+    /// entry for the first delta.
+    pub fn append_agent_reasoning_delta(&mut self, config: &Config, delta: &str) {
+        if self.streaming_reasoning.is_none() {
+            self.streaming_reasoning = Some(self.entries.len());
+            self.add_to_history(HistoryCell::new_streaming_agent_reasoning());
+        }
+        let width = self.cached_width.get();
+        if let Some(entry) = self.streaming_agent_reasoning() {
+            entry.cell.push_agent_reasoning_delta(config, delta);
+            if width > 0 {
+                entry.line_count.set(entry.cell.height(width));
+            }
+        }
+    }
+
+    fn streaming_agent_reasoning(&mut self) -> Option<&mut Entry> {
+        self.entries.get_mut(self.streaming_reasoning?)
+    }
+
+    /// Collapse every completed reasoning summary to its header, or expand
+    /// them all again.
+    fn toggle_reasoning(&mut self) {
+        self.reasoning_expanded = !self.reasoning_expanded;
+        let width = self.cached_width.get();
+        for entry in &mut self.entries {
+            if let HistoryCell::AgentReasoning { .. } = entry.cell {
+                entry.cell.set_reasoning_expanded(self.reasoning_expanded);
+                if width > 0 {
+                    entry.line_count.set(entry.cell.height(width));
+                }
+            }
+        }
     }
 
     pub fn add_background_event(&mut self, message: String) {
@@ -210,6 +495,10 @@ impl ConversationHistoryWidget {
         self.add_to_history(HistoryCell::new_error_event(message));
     }
 
+    pub fn add_task_stats(&mut self, stats: &TaskStats) {
+        self.add_to_history(HistoryCell::new_task_stats(stats));
+    }
+
     /// Add a pending patch entry (before user approval).
     pub fn add_patch_event(
         &mut self,
@@ -245,10 +534,35 @@ impl ConversationHistoryWidget {
         });
     }
 
+    /// Number of entries in the history.
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Index of the most recent user prompt, if any.
+    pub fn last_user_message(&self) -> Option<usize> {
+        self.entries
+            .iter()
+            .rposition(|entry| matches!(entry.cell, HistoryCell::UserPrompt { .. }))
+    }
+
+    /// Drop the entries from index `len` on, as when the conversation is
+    /// rewound.
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+        self.streaming_message = self.streaming_message.filter(|&index| index < len);
+        self.streaming_reasoning = self.streaming_reasoning.filter(|&index| index < len);
+        self.scroll_position = usize::MAX;
+        self.search = None;
+    }
+
     /// Remove all history entries and reset scrolling.
     pub fn clear(&mut self) {
         self.entries.clear();
+        self.streaming_message = None;
+        self.streaming_reasoning = None;
         self.scroll_position = usize::MAX;
+        self.search = None;
     }
 
     pub fn record_completed_exec_command(
@@ -328,13 +642,11 @@ impl ConversationHistoryWidget {
 
 impl WidgetRef for ConversationHistoryWidget {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let (title, border_style) = if self.has_input_focus {
-            (
-                "Messages (↑/↓ or j/k = line,  b/space = page)",
-                Style::default().fg(Color::LightYellow),
-            )
+        let title = self.title();
+        let border_style = if self.has_input_focus {
+            Style::default().fg(theme().focus)
         } else {
-            ("Messages (tab to focus)", Style::default().dim())
+            Style::default().dim()
         };
 
         let block = Block::default()
@@ -445,6 +757,14 @@ impl WidgetRef for ConversationHistoryWidget {
             }
         }
 
+        if let Some(search) = self.search.as_ref().filter(|s| !s.query.is_empty()) {
+            let content_area = Rect {
+                width: effective_width,
+                ..inner
+            };
+            highlight_matches(&search.query, content_area, buf);
+        }
+
         // Always render a scrollbar *track* so the reserved column is filled.
         let overflow = num_lines.saturating_sub(viewport_height);
 
@@ -461,9 +781,9 @@ impl WidgetRef for ConversationHistoryWidget {
             // a low-contrast thumb so the scrollbar fades into the background without becoming
             // invisible.
             let thumb_style = if self.has_input_focus {
-                Style::reset().fg(Color::LightYellow)
+                Style::reset().fg(theme().focus)
             } else {
-                Style::reset().fg(Color::Gray)
+                Style::reset().fg(theme().muted)
             };
 
             // By default the Scrollbar widget inherits any style that was
@@ -476,12 +796,12 @@ impl WidgetRef for ConversationHistoryWidget {
                 Scrollbar::new(ScrollbarOrientation::VerticalRight)
                     .begin_symbol(Some("↑"))
                     .end_symbol(Some("↓"))
-                    .begin_style(Style::reset().fg(Color::DarkGray))
-                    .end_style(Style::reset().fg(Color::DarkGray))
+                    .begin_style(Style::reset().fg(theme().muted).dim())
+                    .end_style(Style::reset().fg(theme().muted).dim())
                     .thumb_symbol("█")
                     .thumb_style(thumb_style)
                     .track_symbol(Some("│"))
-                    .track_style(Style::reset().fg(Color::DarkGray)),
+                    .track_style(Style::reset().fg(theme().muted).dim()),
                 inner,
                 buf,
                 &mut scroll_state,
@@ -494,9 +814,195 @@ impl WidgetRef for ConversationHistoryWidget {
     }
 }
 
+#[derive(Clone, Copy)]
+enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+/// Restyle every on-screen occurrence of `query` (case-insensitive) within
+/// `area`. Works on the rendered buffer so it follows line wrapping.
+fn highlight_matches(query: &str, area: Rect, buf: &mut Buffer) {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let style = Style::default().bg(theme().search_match).fg(Color::Black);
+    for y in area.top()..area.bottom() {
+        // Map each character on the row back to its column; wide characters
+        // occupy two cells, the second of which has an empty symbol.
+        let mut row: Vec<(char, u16)> = Vec::new();
+        for x in area.left()..area.right() {
+            if let Some(c) = buf[(x, y)].symbol().chars().next() {
+                row.extend(c.to_lowercase().map(|lc| (lc, x)));
+            }
+        }
+        let mut start = 0;
+        while start + query.len() <= row.len() {
+            if row[start..start + query.len()]
+                .iter()
+                .map(|(c, _)| *c)
+                .eq(query.iter().copied())
+            {
+                for (_, x) in &row[start..start + query.len()] {
+                    buf[(*x, y)].set_style(style);
+                }
+                start += query.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+}
+
 /// Common [`Wrap`] configuration used for both measurement and rendering so
 /// they stay in sync.
 #[inline]
 pub(crate) const fn wrap_cfg() -> ratatui::widgets::Wrap {
     ratatui::widgets::Wrap { trim: false }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(widget: &mut ConversationHistoryWidget, code: KeyCode) {
+        widget.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn render(widget: &ConversationHistoryWidget, area: Rect) -> Buffer {
+        let mut buf = Buffer::empty(area);
+        widget.render_ref(area, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn search_jumps_between_matches_and_highlights() {
+        let mut widget = ConversationHistoryWidget::new();
+        widget.add_user_message("find the needle".to_string());
+        for i in 0..20 {
+            widget.add_background_event(format!("filler {i}"));
+        }
+        widget.add_user_message("another needle".to_string());
+        let area = Rect::new(0, 0, 40, 10);
+        render(&widget, area);
+
+        press(&mut widget, KeyCode::Char('/'));
+        for c in "NEEDLE".chars() {
+            press(&mut widget, KeyCode::Char(c));
+        }
+        assert!(widget.is_editing_search());
+        press(&mut widget, KeyCode::Enter);
+        assert!(!widget.is_editing_search());
+
+        // The view is pinned to the bottom, so the first jump wraps to the
+        // last match; `n` then wraps around to the first one.
+        let last_entry_offset: usize = widget.entries[..21]
+            .iter()
+            .map(|e| e.line_count.get())
+            .sum();
+        assert_eq!(widget.scroll_position, last_entry_offset);
+        press(&mut widget, KeyCode::Char('n'));
+        assert_eq!(widget.scroll_position, 0);
+
+        let buf = render(&widget, area);
+        let highlighted: Vec<String> = (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .filter(|&x| buf[(x, y)].bg == theme().search_match)
+                    .map(|x| buf[(x, y)].symbol().to_string())
+                    .collect::<String>()
+            })
+            .filter(|row| !row.is_empty())
+            .collect();
+        assert_eq!(highlighted, vec!["needle"]);
+
+        press(&mut widget, KeyCode::Esc);
+        assert!(widget.search.is_none());
+    }
+
+    #[test]
+    fn message_deltas_go_to_the_streaming_entry() {
+        let config = Config::load_from_base_config_with_overrides(
+            codex_core::config::ConfigToml::default(),
+            codex_core::config::ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .unwrap();
+        let mut widget = ConversationHistoryWidget::new();
+
+        widget.append_agent_message_delta(&config, "Hello");
+        widget.add_background_event("checking".to_string());
+        widget.append_agent_message_delta(&config, ", world");
+        assert_eq!(widget.entries.len(), 2);
+        assert!(
+            widget.entries[0]
+                .cell
+                .plain_text()
+                .ends_with("Hello, world")
+        );
+
+        widget.add_agent_message(&config, "Hello, world.".to_string());
+        assert_eq!(widget.entries.len(), 2);
+        assert!(matches!(
+            widget.entries[0].cell,
+            HistoryCell::AgentMessage { .. }
+        ));
+
+        // A rewind drops the streaming entry with the rest.
+        widget.append_agent_message_delta(&config, "Next");
+        widget.truncate(2);
+        widget.append_agent_message_delta(&config, "Again");
+        assert_eq!(widget.entries.len(), 3);
+        assert!(widget.entries[2].cell.plain_text().ends_with("Again"));
+    }
+
+    #[test]
+    fn reasoning_streams_in_and_collapses() {
+        let config = Config::load_from_base_config_with_overrides(
+            codex_core::config::ConfigToml::default(),
+            codex_core::config::ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .unwrap();
+        let mut widget = ConversationHistoryWidget::new();
+        let area = Rect::new(0, 0, 40, 10);
+        render(&widget, area);
+
+        widget.append_agent_reasoning_delta(&config, "Reading the ");
+        widget.append_agent_reasoning_delta(&config, "tests.\nThen");
+        assert_eq!(widget.entries.len(), 1);
+        assert_eq!(
+            widget.entries[0].cell.plain_text(),
+            "thinking\nReading the tests.\nThen"
+        );
+
+        widget.add_agent_reasoning(&config, "Reading the tests.\n\nThen the code.".to_string());
+        assert_eq!(widget.entries.len(), 1);
+        let expanded = widget.entries[0].line_count.get();
+
+        press(&mut widget, KeyCode::Char('t'));
+        assert_eq!(widget.entries[0].line_count.get(), 2);
+        let buf = render(&widget, area);
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert!(
+            rows.iter()
+                .any(|row| row.contains("thinking (") && row.contains("lines hidden)")),
+            "{rows:#?}"
+        );
+
+        // The full text stays searchable, and new summaries follow the toggle.
+        assert!(
+            widget.entries[0]
+                .cell
+                .plain_text()
+                .contains("Then the code.")
+        );
+        widget.add_agent_reasoning(&config, "Done.".to_string());
+        assert_eq!(widget.entries[1].line_count.get(), 2);
+
+        press(&mut widget, KeyCode::Char('t'));
+        assert_eq!(widget.entries[0].line_count.get(), expanded);
+    }
+}
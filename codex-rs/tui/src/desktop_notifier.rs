@@ -0,0 +1,165 @@
+//! OS notifications for events that need the user's attention while the
+//! terminal is in the background.
+//!
+//! Focus is tracked through the terminal's focus reporting. Terminals that do
+//! not report focus are assumed to stay focused, so they never notify.
+
+use std::process::Command;
+use std::process::Stdio;
+use std::time::Instant;
+
+use codex_core::config_types::Notifications;
+
+const NOTIFICATION_TITLE: &str = "Codex";
+
+/// Notification bodies are cut to this many characters.
+const MAX_BODY_CHARS: usize = 200;
+
+pub(crate) struct DesktopNotifier {
+    settings: Notifications,
+    focused: bool,
+    task_started: Option<Instant>,
+}
+
+impl DesktopNotifier {
+    pub(crate) fn new(settings: Notifications) -> Self {
+        Self {
+            settings,
+            focused: true,
+            task_started: None,
+        }
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub(crate) fn on_task_started(&mut self) {
+        self.task_started = Some(Instant::now());
+    }
+
+    pub(crate) fn on_task_complete(&mut self, last_agent_message: Option<&str>) {
+        let Some(started) = self.task_started.take() else {
+            return;
+        };
+        if !self.settings.task_complete
+            || started.elapsed().as_secs() < self.settings.min_task_duration_secs
+        {
+            return;
+        }
+        self.send(last_agent_message.unwrap_or("Task complete"));
+    }
+
+    pub(crate) fn on_approval_request(&self, summary: &str) {
+        if self.settings.approval_requests {
+            self.send(&format!("Approval needed: {summary}"));
+        }
+    }
+
+    fn send(&self, body: &str) {
+        if !self.settings.enabled || self.focused {
+            return;
+        }
+        let Some((program, args)) = notification_command(NOTIFICATION_TITLE, &truncate(body))
+        else {
+            return;
+        };
+        // Wait on a separate thread so the child is reaped without blocking
+        // the UI.
+        std::thread::spawn(move || {
+            let status = Command::new(&program)
+                .args(&args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            if let Err(e) = status {
+                tracing::warn!("failed to run {program} for desktop notification: {e}");
+            }
+        });
+    }
+}
+
+fn truncate(body: &str) -> String {
+    let body = body.trim();
+    if body.chars().count() <= MAX_BODY_CHARS {
+        return body.to_string();
+    }
+    let mut truncated: String = body.chars().take(MAX_BODY_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Program and arguments that show a notification on this platform.
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Option<(String, Vec<String>)> {
+    let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    Some((
+        "osascript".to_string(),
+        vec![
+            "-e".to_string(),
+            format!(
+                "display notification \"{}\" with title \"{}\"",
+                quote(body),
+                quote(title)
+            ),
+        ],
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn notification_command(title: &str, body: &str) -> Option<(String, Vec<String>)> {
+    let quote = |s: &str| s.replace('\'', "''");
+    let script = format!(
+        "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+         $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+         $text = $xml.GetElementsByTagName('text'); \
+         $text.Item(0).AppendChild($xml.CreateTextNode('{}')) > $null; \
+         $text.Item(1).AppendChild($xml.CreateTextNode('{}')) > $null; \
+         [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{}').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+        quote(title),
+        quote(body),
+        quote(title),
+    );
+    Some((
+        "powershell".to_string(),
+        vec![
+            "-NoProfile".to_string(),
+            "-NonInteractive".to_string(),
+            "-Command".to_string(),
+            script,
+        ],
+    ))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notification_command(title: &str, body: &str) -> Option<(String, Vec<String>)> {
+    Some((
+        "notify-send".to_string(),
+        vec![
+            "--app-name".to_string(),
+            title.to_string(),
+            title.to_string(),
+            body.to_string(),
+        ],
+    ))
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn notification_command(_title: &str, _body: &str) -> Option<(String, Vec<String>)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_bodies_are_truncated() {
+        let body = "x".repeat(MAX_BODY_CHARS + 10);
+        let truncated = truncate(&body);
+        assert_eq!(truncated.chars().count(), MAX_BODY_CHARS);
+        assert!(truncated.ends_with('…'));
+        assert_eq!(truncate("  short  "), "short");
+    }
+}
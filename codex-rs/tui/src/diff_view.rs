@@ -0,0 +1,392 @@
+//! Full-screen viewer for the cumulative git diff of the session.
+//!
+//! Opened with `/diff`, it replaces the conversation pane with a file list on
+//! the left and the selected file's diff on the right, the code highlighted
+//! for the file's language. The composer and approval prompts below stay
+//! usable: the viewer only takes the navigation keys it needs. The chat
+//! widget refreshes it after every patch application so it always reflects
+//! the working tree.
+
+use std::cell::Cell;
+use std::path::Path;
+
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Clear;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::ListState;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::StatefulWidget;
+use ratatui::widgets::Widget;
+use ratatui::widgets::WidgetRef;
+
+use crate::syntax_highlight::try_highlight_code;
+use crate::theme::theme;
+
+/// Outcome of a key press in the viewer.
+pub(crate) enum DiffViewAction {
+    /// The key is not one of the viewer's; it should go to the composer.
+    None,
+    Redraw,
+    Close,
+}
+
+/// The part of a unified diff that belongs to one file.
+#[derive(Debug)]
+pub(crate) struct FileDiff {
+    path: String,
+    added: usize,
+    removed: usize,
+    lines: Vec<String>,
+    /// `lines` styled for display.
+    styled: Vec<Line<'static>>,
+}
+
+pub(crate) struct DiffView {
+    files: Vec<FileDiff>,
+    selected: usize,
+    scroll: usize,
+    /// Height of the diff pane at the last render, for paging.
+    last_height: Cell<usize>,
+}
+
+impl DiffView {
+    pub(crate) fn new(files: Vec<FileDiff>) -> Self {
+        Self {
+            files,
+            selected: 0,
+            scroll: 0,
+            last_height: Cell::new(0),
+        }
+    }
+
+    /// Replace the contents with a freshly computed diff, keeping the same
+    /// file selected when it is still present.
+    pub(crate) fn update(&mut self, files: Vec<FileDiff>) {
+        let selected_path = self.files.get(self.selected).map(|f| f.path.clone());
+        self.files = files;
+        match selected_path.and_then(|p| self.files.iter().position(|f| f.path == p)) {
+            Some(idx) => self.selected = idx,
+            None => {
+                self.selected = 0;
+                self.scroll = 0;
+            }
+        }
+    }
+
+    /// Handle the viewer's navigation keys. Text editing keys are left to
+    /// the composer, so a reply can be typed while reviewing the diff.
+    pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) -> DiffViewAction {
+        let page = self.last_height.get().max(1);
+        match key_event.code {
+            KeyCode::Esc => return DiffViewAction::Close,
+            KeyCode::Tab => self.select_file(self.selected + 1),
+            KeyCode::BackTab => self.select_file(
+                self.selected
+                    .checked_sub(1)
+                    .unwrap_or(self.files.len().saturating_sub(1)),
+            ),
+            KeyCode::Down => self.scroll_by(1),
+            KeyCode::Up => self.scroll_by(-1),
+            KeyCode::PageDown => self.scroll_by(page as isize),
+            KeyCode::PageUp => self.scroll_by(-(page as isize)),
+            _ => return DiffViewAction::None,
+        }
+        DiffViewAction::Redraw
+    }
+
+    fn select_file(&mut self, idx: usize) {
+        if self.files.is_empty() {
+            return;
+        }
+        self.selected = idx % self.files.len();
+        self.scroll = 0;
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta)
+            .min(self.max_scroll());
+    }
+
+    fn max_scroll(&self) -> usize {
+        let lines = self.files.get(self.selected).map_or(0, |f| f.lines.len());
+        lines.saturating_sub(self.last_height.get())
+    }
+}
+
+impl WidgetRef for DiffView {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+
+        let title = "Diff (Tab = file, ↑/↓ = scroll, PgUp/PgDn = page, Esc = close)";
+        if self.files.is_empty() {
+            Paragraph::new(Line::from("No changes detected.".italic()))
+                .block(border(title))
+                .render(area, buf);
+            return;
+        }
+
+        let list_width = self
+            .files
+            .iter()
+            .map(|f| f.path.len() + 12)
+            .max()
+            .unwrap_or(0)
+            .min(area.width as usize / 3) as u16;
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(list_width), Constraint::Min(0)])
+            .split(area);
+
+        let items: Vec<ListItem> = self
+            .files
+            .iter()
+            .map(|f| {
+                ListItem::new(Line::from(vec![
+                    f.path.clone().into(),
+                    format!(" +{}", f.added).fg(theme().success),
+                    format!(" -{}", f.removed).fg(theme().error),
+                ]))
+            })
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(
+            List::new(items)
+                .block(border(&format!("Files ({})", self.files.len())))
+                .highlight_style(Style::default().bg(theme().selection)),
+            chunks[0],
+            buf,
+            &mut state,
+        );
+
+        let file = &self.files[self.selected];
+        let block = border(title);
+        let inner_height = block.inner(chunks[1]).height as usize;
+        self.last_height.set(inner_height);
+        let lines: Vec<Line<'static>> = file
+            .styled
+            .iter()
+            .skip(self.scroll)
+            .take(inner_height)
+            .cloned()
+            .collect();
+        Paragraph::new(lines).block(block).render(chunks[1], buf);
+    }
+}
+
+fn border(title: &str) -> Block<'static> {
+    Block::default()
+        .title(title.to_string())
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+}
+
+/// Color a single line of unified diff output.
+fn styled_diff_line(line: &str) -> Line<'static> {
+    let owned = line.to_string();
+    if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") {
+        Line::from(owned).bold()
+    } else if line.starts_with('+') {
+        Line::from(owned).fg(theme().success)
+    } else if line.starts_with('-') {
+        Line::from(owned).fg(theme().error)
+    } else if line.starts_with("@@") {
+        Line::from(owned).fg(theme().info)
+    } else {
+        Line::from(owned)
+    }
+}
+
+/// Split `git diff` output into per-file sections and style them, the code
+/// highlighted with `code_theme` (see `tui.code_theme`). This can take a
+/// while for large diffs, so it is meant to run off the UI thread.
+pub(crate) fn parse_diff(diff: &str, code_theme: Option<&str>) -> Vec<FileDiff> {
+    let mut files = split_by_file(diff);
+    for file in &mut files {
+        file.styled = style_file(file, code_theme);
+    }
+    files
+}
+
+/// Style the lines of one file. Lines inside hunks are highlighted as the
+/// file's language, with the `+`/`-` marker colored; headers, and all lines
+/// when the language is unknown, get the plain diff colors.
+fn style_file(file: &FileDiff, code_theme: Option<&str>) -> Vec<Line<'static>> {
+    let plain = || file.lines.iter().map(|l| styled_diff_line(l)).collect();
+
+    let first_hunk = file
+        .lines
+        .iter()
+        .position(|l| l.starts_with("@@"))
+        .unwrap_or(file.lines.len());
+    let is_code = |line: &str| line.starts_with([' ', '+', '-']);
+    let mut code = String::new();
+    for line in file.lines[first_hunk..].iter().filter(|l| is_code(l)) {
+        code.push_str(&line[1..]);
+        code.push('\n');
+    }
+    let path = Path::new(&file.path);
+    let lang = path
+        .extension()
+        .or_else(|| path.file_name())
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let Some(highlighted) = try_highlight_code(&code, &lang, code_theme) else {
+        return plain();
+    };
+
+    let mut highlighted = highlighted.into_iter();
+    let mut styled = Vec::with_capacity(file.lines.len());
+    for (idx, line) in file.lines.iter().enumerate() {
+        if idx < first_hunk || !is_code(line) {
+            styled.push(styled_diff_line(line));
+            continue;
+        }
+        let marker = match &line[..1] {
+            "+" => "+".fg(theme().success),
+            "-" => "-".fg(theme().error),
+            _ => " ".into(),
+        };
+        let mut spans = vec![marker];
+        spans.extend(highlighted.next().map(|l| l.spans).unwrap_or_default());
+        styled.push(Line::from(spans));
+    }
+    styled
+}
+
+/// Split `git diff` output into per-file sections keyed by the new path.
+fn split_by_file(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            // `a/<old> b/<new>`; for untracked files diffed against
+            // /dev/null the paths are the same on both sides.
+            let path = header
+                .rsplit_once(" b/")
+                .map(|(_, new)| new.to_string())
+                .unwrap_or_else(|| header.to_string());
+            files.push(FileDiff {
+                path,
+                added: 0,
+                removed: 0,
+                lines: Vec::new(),
+                styled: Vec::new(),
+            });
+            in_hunk = false;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        // Before the first hunk, `---`/`+++` are the file headers; inside
+        // one they are removed or added lines that start with `--`/`++`.
+        in_hunk |= line.starts_with("@@");
+        if in_hunk && line.starts_with('+') {
+            file.added += 1;
+        } else if in_hunk && line.starts_with('-') {
+            file.removed += 1;
+        }
+        file.lines.push(line.to_string());
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use ratatui::style::Color;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1 +1,2 @@
+-old
++new
++more
+diff --git a/README.md b/README.md
+--- a/README.md
++++ b/README.md
+@@ -1 +1 @@
+-a
++b
+";
+
+    #[test]
+    fn splits_diff_per_file_with_counts() {
+        let files = split_by_file(DIFF);
+        let summary: Vec<(&str, usize, usize)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.added, f.removed))
+            .collect();
+        assert_eq!(summary, vec![("src/lib.rs", 2, 1), ("README.md", 1, 1)]);
+        assert_eq!(files[1].lines[0], "diff --git a/README.md b/README.md");
+    }
+
+    #[test]
+    fn highlights_code_and_colors_markers() {
+        let files = parse_diff(DIFF, None);
+        let styled = &files[0].styled;
+        let text: Vec<String> = styled
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(text, files[0].lines);
+
+        // `+new` in src/lib.rs: the marker in the diff color, the code
+        // highlighted as Rust.
+        let added = &styled[5];
+        assert_eq!(added.spans[0].style.fg, Some(theme().success));
+        assert!(matches!(added.spans[1].style.fg, Some(Color::Rgb(..))));
+
+        // Without highlighting, whole lines get the diff colors.
+        let plain = parse_diff(DIFF, Some("none"));
+        assert_eq!(plain[0].styled[5].style.fg, Some(theme().success));
+    }
+
+    #[test]
+    fn leaves_typing_to_the_composer() {
+        let mut view = DiffView::new(parse_diff(DIFF, None));
+        for key in [KeyCode::Char('q'), KeyCode::Char('j'), KeyCode::Enter] {
+            assert!(matches!(
+                view.handle_key_event(KeyEvent::from(key)),
+                DiffViewAction::None
+            ));
+        }
+        assert!(matches!(
+            view.handle_key_event(KeyEvent::from(KeyCode::Esc)),
+            DiffViewAction::Close
+        ));
+    }
+
+    #[test]
+    fn update_keeps_selected_file() {
+        let mut view = DiffView::new(parse_diff(DIFF, None));
+        view.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        assert_eq!(view.files[view.selected].path, "README.md");
+
+        // README.md is now first; the selection should follow it.
+        let reordered = DIFF.split_once("diff --git a/README.md").unwrap();
+        view.update(parse_diff(
+            &format!("diff --git a/README.md{}{}", reordered.1, reordered.0),
+            None,
+        ));
+        assert_eq!(view.selected, 0);
+        assert_eq!(view.files[0].path, "README.md");
+    }
+}
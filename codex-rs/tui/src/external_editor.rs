@@ -0,0 +1,77 @@
+//! Editing the composer draft, and files such as the project's memories, in
+//! the user's `$VISUAL` / `$EDITOR`.
+//!
+//! The caller is responsible for handing the terminal to the editor (leaving
+//! raw mode and the alternate screen) and for taking it back afterwards.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Open `draft` in the external editor and return the edited text, without
+/// the trailing newline most editors add.
+pub(crate) fn edit_text(draft: &str) -> io::Result<String> {
+    let path = std::env::temp_dir().join(format!("codex-prompt-{}.md", uuid::Uuid::new_v4()));
+    std::fs::write(&path, draft)?;
+    let status = edit_file(&path);
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    status?;
+    let mut edited = edited?;
+    while edited.ends_with('\n') || edited.ends_with('\r') {
+        edited.pop();
+    }
+    Ok(edited)
+}
+
+/// Open `path` in the external editor and wait for it to exit.
+pub fn edit_file(path: &Path) -> io::Result<()> {
+    let command = editor_command(std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok());
+    let Some((program, args)) = command.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not parse the editor command",
+        ));
+    };
+    let status = Command::new(program).args(args).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{program} exited with {status}")));
+    }
+    Ok(())
+}
+
+/// `$VISUAL` wins over `$EDITOR`; both may carry arguments (`code --wait`).
+fn editor_command(visual: Option<String>, editor: Option<String>) -> Vec<String> {
+    let configured = [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|value| !value.trim().is_empty());
+    match configured {
+        Some(value) => shlex::split(&value).unwrap_or_default(),
+        None => vec![DEFAULT_EDITOR.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visual_takes_precedence_and_arguments_are_split() {
+        assert_eq!(
+            editor_command(Some("code --wait".to_string()), Some("vim".to_string())),
+            vec!["code", "--wait"]
+        );
+        assert_eq!(
+            editor_command(Some(String::new()), Some("nano".to_string())),
+            vec!["nano"]
+        );
+        assert_eq!(editor_command(None, None), vec![DEFAULT_EDITOR]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Resolution of `@path` mentions in submitted messages.
+//!
+//! Every whitespace-delimited word that starts with `@` and names a file
+//! under the working directory or a writable root is attached to the turn as
+//! an `InputItem::LocalFile`; core reads it and enforces the size limits.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Cap on the number of files attached from a single message.
+const MAX_FILE_MENTIONS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileMention {
+    /// Path as written by the user (without the `@`).
+    pub label: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl FileMention {
+    /// Short description shown in the transcript, e.g. `[file: src/main.rs (2 KB)]`.
+    /// Files over `max_file_bytes` are sent as their first and last lines.
+    pub(crate) fn chip(&self, max_file_bytes: usize) -> String {
+        let truncated = if self.size > max_file_bytes as u64 {
+            ", first and last lines only"
+        } else {
+            ""
+        };
+        format!(
+            "[file: {} ({}{truncated})]",
+            self.label,
+            format_size(self.size)
+        )
+    }
+}
+
+/// Find the files mentioned in `text`, resolved against `cwd`. Mentions that
+/// do not name an existing file are left alone (they may be e.g. handles), and
+/// so are files outside `cwd` and `roots`, whichever way the mention or a
+/// symlink leads there.
+pub(crate) fn find_file_mentions(text: &str, cwd: &Path, roots: &[PathBuf]) -> Vec<FileMention> {
+    let roots: Vec<PathBuf> = std::iter::once(cwd)
+        .chain(roots.iter().map(PathBuf::as_path))
+        .filter_map(|root| root.canonicalize().ok())
+        .collect();
+    let mut mentions: Vec<FileMention> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(raw) = word.strip_prefix('@') else {
+            continue;
+        };
+        // Allow trailing punctuation such as "see @src/lib.rs, then ...".
+        let candidates = [
+            raw,
+            raw.trim_end_matches([',', '.', ';', ':', ')', '?', '!']),
+        ];
+        let Some((label, path, size)) = candidates.iter().find_map(|label| {
+            if label.is_empty() {
+                return None;
+            }
+            let path = cwd.join(label).canonicalize().ok()?;
+            if !roots.iter().any(|root| path.starts_with(root)) {
+                return None;
+            }
+            let metadata = std::fs::metadata(&path).ok()?;
+            metadata
+                .is_file()
+                .then(|| (label.to_string(), path, metadata.len()))
+        }) else {
+            continue;
+        };
+        if mentions.iter().any(|m| m.path == path) {
+            continue;
+        }
+        mentions.push(FileMention { label, path, size });
+        if mentions.len() == MAX_FILE_MENTIONS {
+            break;
+        }
+    }
+    mentions
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else {
+        format!("{} KB", bytes.div_ceil(1024))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn resolves_existing_files_only() {
+        let dir = TempDir::new().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn lib() {}").unwrap();
+
+        let mentions = find_file_mentions(
+            "look at @src/lib.rs, not @missing.rs or @someone (again: @src/lib.rs)",
+            &dir,
+            &[],
+        );
+        assert_eq!(
+            mentions,
+            vec![FileMention {
+                label: "src/lib.rs".to_string(),
+                path: dir.join("src/lib.rs"),
+                size: 11,
+            }]
+        );
+        assert_eq!(mentions[0].chip(1024), "[file: src/lib.rs (11 B)]");
+        assert_eq!(
+            mentions[0].chip(10),
+            "[file: src/lib.rs (11 B, first and last lines only)]"
+        );
+    }
+
+    #[test]
+    fn ignores_files_outside_the_workspace_and_roots() {
+        let outside = TempDir::new().unwrap();
+        let outside = outside.path().canonicalize().unwrap();
+        std::fs::write(outside.join("secret.txt"), "key").unwrap();
+        let workspace = outside.join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+
+        let text = format!("@../secret.txt @{}", outside.join("secret.txt").display());
+        assert_eq!(find_file_mentions(&text, &workspace, &[]), Vec::new());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.join("secret.txt"), workspace.join("link")).unwrap();
+            assert_eq!(find_file_mentions("@link", &workspace, &[]), Vec::new());
+        }
+
+        let mentions = find_file_mentions(&text, &workspace, std::slice::from_ref(&outside));
+        assert_eq!(mentions.len(), 1);
+        assert_eq!(mentions[0].path, outside.join("secret.txt"));
+    }
+}
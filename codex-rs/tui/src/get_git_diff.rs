@@ -0,0 +1,47 @@
+//! Utility to compute the current Git diff for the working directory.
+//!
+//! The output includes tracked changes (`git diff`) as well as untracked
+//! files, which are rendered as additions against `/dev/null`.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use codex_core::config::Config;
+use codex_core::util::is_inside_git_repo;
+
+/// Return the unstaged changes and untracked files of the session's working
+/// directory, or `None` if it is not inside a Git repository.
+pub(crate) fn get_git_diff(config: &Config) -> io::Result<Option<String>> {
+    if !is_inside_git_repo(config) {
+        return Ok(None);
+    }
+    let cwd = config.cwd.as_path();
+
+    let mut diff = run_git(cwd, &["diff", "--no-color"])?;
+
+    let untracked = run_git(cwd, &["ls-files", "--others", "--exclude-standard"])?;
+    for file in untracked.lines().filter(|l| !l.is_empty()) {
+        // `git diff --no-index` exits with status 1 when the inputs differ,
+        // so the exit code is deliberately ignored here.
+        let output = Command::new("git")
+            .args(["diff", "--no-color", "--no-index", "--", "/dev/null", file])
+            .current_dir(cwd)
+            .output()?;
+        diff.push_str(&String::from_utf8_lossy(&output.stdout));
+    }
+
+    Ok(Some(diff))
+}
+
+fn run_git(cwd: &Path, args: &[&str]) -> io::Result<String> {
+    let output = Command::new("git").args(args).current_dir(cwd).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -11,7 +11,6 @@ use ratatui::layout::Constraint;
 use ratatui::layout::Direction;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::text::Span;
@@ -23,6 +22,8 @@ use ratatui::widgets::Widget;
 use ratatui::widgets::WidgetRef;
 use ratatui::widgets::Wrap;
 
+use crate::theme::theme;
+
 const NO_GIT_ERROR: &str = "We recommend running codex inside a git repository. \
 This helps ensure that changes can be tracked and easily rolled back if necessary. \
 Do you wish to proceed?";
@@ -85,7 +86,9 @@ impl WidgetRef for &GitWarningScreen {
             .border_type(BorderType::Plain)
             .title(Span::styled(
                 "Warning: Not a Git repository", // bold warning title
-                Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(theme().error),
             ));
 
         // Obtain the inner area before rendering (render consumes the block).
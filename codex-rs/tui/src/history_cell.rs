@@ -1,7 +1,9 @@
 use crate::cell_widget::CellWidget;
 use crate::exec_command::escape_command;
 use crate::markdown::append_markdown;
+use crate::markdown_stream::MarkdownStream;
 use crate::text_block::TextBlock;
+use crate::theme::theme;
 use base64::Engine;
 use codex_ansi_escape::ansi_escape_line;
 use codex_common::elapsed::format_duration;
@@ -10,6 +12,7 @@ use codex_core::config::Config;
 use codex_core::model_supports_reasoning_summaries;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_core::protocol::TaskStats;
 use image::DynamicImage;
 use image::GenericImageView;
 use image::ImageReader;
@@ -39,7 +42,11 @@ pub(crate) struct CommandOutput {
 
 pub(crate) enum PatchEventType {
     ApprovalRequest,
-    ApplyBegin { auto_approved: bool },
+    ApplyBegin {
+        auto_approved: bool,
+    },
+    /// Net changes of a task, reviewed when it ends.
+    TurnReview,
 }
 
 /// Represents an event to display in the conversation history. Returns its
@@ -55,8 +62,27 @@ pub(crate) enum HistoryCell {
     /// Message from the agent.
     AgentMessage { view: TextBlock },
 
-    /// Reasoning event from the agent.
-    AgentReasoning { view: TextBlock },
+    /// Agent message that is still streaming in; replaced by `AgentMessage`
+    /// once the complete text arrives.
+    StreamingAgentMessage {
+        view: TextBlock,
+        stream: MarkdownStream,
+    },
+
+    /// Reasoning event from the agent. When collapsed, only `collapsed_view`,
+    /// a one-line header, is shown.
+    AgentReasoning {
+        view: TextBlock,
+        collapsed_view: TextBlock,
+        expanded: bool,
+    },
+
+    /// Reasoning summary that is still streaming in; replaced by
+    /// `AgentReasoning` once the complete text arrives.
+    StreamingAgentReasoning {
+        view: TextBlock,
+        stream: MarkdownStream,
+    },
 
     /// An exec tool call that has not finished yet.
     ActiveExecCommand {
@@ -108,6 +134,9 @@ pub(crate) enum HistoryCell {
     /// Error event from the backend.
     ErrorEvent { view: TextBlock },
 
+    /// Time, tokens and cost of the task that just completed.
+    TaskStats { view: TextBlock },
+
     /// Info describing the newly-initialized session.
     SessionInfo { view: TextBlock },
 
@@ -143,7 +172,7 @@ impl HistoryCell {
                 ]),
                 Line::from(""),
                 Line::from(vec![
-                    "codex session".magenta().bold(),
+                    "codex session".fg(theme().agent).bold(),
                     " ".into(),
                     session_id.to_string().dim(),
                 ]),
@@ -181,7 +210,7 @@ impl HistoryCell {
             }
         } else {
             let lines = vec![
-                Line::from("model changed:".magenta().bold()),
+                Line::from("model changed:".fg(theme().agent).bold()),
                 Line::from(format!("requested: {}", config.model)),
                 Line::from(format!("used: {}", model)),
                 Line::from(""),
@@ -194,7 +223,7 @@ impl HistoryCell {
 
     pub(crate) fn new_user_prompt(message: String) -> Self {
         let mut lines: Vec<Line<'static>> = Vec::new();
-        lines.push(Line::from("user".cyan().bold()));
+        lines.push(Line::from("user".fg(theme().user).bold()));
         lines.extend(message.lines().map(|l| Line::from(l.to_string())));
         lines.push(Line::from(""));
 
@@ -205,7 +234,7 @@ impl HistoryCell {
 
     pub(crate) fn new_agent_message(config: &Config, message: String) -> Self {
         let mut lines: Vec<Line<'static>> = Vec::new();
-        lines.push(Line::from("codex".magenta().bold()));
+        lines.push(Line::from("codex".fg(theme().agent).bold()));
         append_markdown(&message, &mut lines, config);
         lines.push(Line::from(""));
 
@@ -214,14 +243,67 @@ impl HistoryCell {
         }
     }
 
-    pub(crate) fn new_agent_reasoning(config: &Config, text: String) -> Self {
+    pub(crate) fn new_streaming_agent_message() -> Self {
+        HistoryCell::StreamingAgentMessage {
+            view: TextBlock::new(vec![Line::from("codex".fg(theme().agent).bold())]),
+            stream: MarkdownStream::default(),
+        }
+    }
+
+    /// Feed a delta to a `StreamingAgentMessage`; other cells are unchanged.
+    pub(crate) fn push_agent_message_delta(&mut self, config: &Config, delta: &str) {
+        if let HistoryCell::StreamingAgentMessage { view, stream } = self {
+            stream.push_delta(delta, config);
+            let mut lines = vec![Line::from("codex".fg(theme().agent).bold())];
+            lines.extend(stream.lines());
+            view.lines = lines;
+        }
+    }
+
+    pub(crate) fn new_agent_reasoning(config: &Config, text: String, expanded: bool) -> Self {
         let mut lines: Vec<Line<'static>> = Vec::new();
-        lines.push(Line::from("thinking".magenta().italic()));
+        lines.push(Line::from("thinking".fg(theme().agent).italic()));
         append_markdown(&text, &mut lines, config);
         lines.push(Line::from(""));
 
+        let hidden = lines.len() - 2;
+        let collapsed_lines = vec![
+            Line::from(vec![
+                "thinking".fg(theme().agent).italic(),
+                format!(" ({hidden} lines hidden)").dim(),
+            ]),
+            Line::from(""),
+        ];
+
         HistoryCell::AgentReasoning {
             view: TextBlock::new(lines),
+            collapsed_view: TextBlock::new(collapsed_lines),
+            expanded,
+        }
+    }
+
+    pub(crate) fn new_streaming_agent_reasoning() -> Self {
+        HistoryCell::StreamingAgentReasoning {
+            view: TextBlock::new(vec![Line::from("thinking".fg(theme().agent).italic())]),
+            stream: MarkdownStream::default(),
+        }
+    }
+
+    /// Feed a delta to a `StreamingAgentReasoning`; other cells are
+    /// unchanged.
+    pub(crate) fn push_agent_reasoning_delta(&mut self, config: &Config, delta: &str) {
+        if let HistoryCell::StreamingAgentReasoning { view, stream } = self {
+            stream.push_delta(delta, config);
+            let mut lines = vec![Line::from("thinking".fg(theme().agent).italic())];
+            lines.extend(stream.lines());
+            view.lines = lines;
+        }
+    }
+
+    /// Collapse or expand an `AgentReasoning`; other cells are unchanged.
+    pub(crate) fn set_reasoning_expanded(&mut self, expand: bool) {
+        if let HistoryCell::AgentReasoning { expanded, .. } = self {
+            *expanded = expand;
         }
     }
 
@@ -230,7 +312,7 @@ impl HistoryCell {
         let start = Instant::now();
 
         let lines: Vec<Line<'static>> = vec![
-            Line::from(vec!["command".magenta(), " running...".dim()]),
+            Line::from(vec!["command".fg(theme().agent), " running...".dim()]),
             Line::from(format!("$ {command_escaped}")),
             Line::from(""),
         ];
@@ -255,7 +337,7 @@ impl HistoryCell {
 
         // Title depends on whether we have output yet.
         let title_line = Line::from(vec![
-            "command".magenta(),
+            "command".fg(theme().agent),
             format!(
                 " (code: {}, duration: {})",
                 exit_code,
@@ -309,7 +391,7 @@ impl HistoryCell {
         };
 
         let start = Instant::now();
-        let title_line = Line::from(vec!["tool".magenta(), " running...".dim()]);
+        let title_line = Line::from(vec!["tool".fg(theme().agent), " running...".dim()]);
         let lines: Vec<Line<'static>> = vec![
             title_line,
             Line::from(format!("$ {invocation}")),
@@ -383,7 +465,7 @@ impl HistoryCell {
         let duration = format_duration(start.elapsed());
         let status_str = if success { "success" } else { "failed" };
         let title_line = Line::from(vec![
-            "tool".magenta(),
+            "tool".fg(theme().agent),
             format!(" {fq_tool_name} ({status_str}, duration: {})", duration).dim(),
         ]);
 
@@ -430,7 +512,7 @@ impl HistoryCell {
 
     pub(crate) fn new_error_event(message: String) -> Self {
         let lines: Vec<Line<'static>> = vec![
-            vec!["ERROR: ".red().bold(), message.into()].into(),
+            vec!["ERROR: ".fg(theme().error).bold(), message.into()].into(),
             "".into(),
         ];
         HistoryCell::ErrorEvent {
@@ -438,6 +520,41 @@ impl HistoryCell {
         }
     }
 
+    /// A dim footer like "12.34s (model 9.80s) · 3 requests · 15200 in
+    /// (12000 cached) · 1100 out · $0.04".
+    pub(crate) fn new_task_stats(stats: &TaskStats) -> Self {
+        let TaskStats {
+            duration_ms,
+            model_ms,
+            requests,
+            usage,
+            cost_usd,
+        } = stats;
+        let mut parts = vec![
+            format!(
+                "{} (model {})",
+                format_duration(Duration::from_millis(*duration_ms)),
+                format_duration(Duration::from_millis(*model_ms))
+            ),
+            match requests {
+                1 => "1 request".to_string(),
+                n => format!("{n} requests"),
+            },
+        ];
+        parts.push(match usage.cached_input_tokens {
+            Some(cached) => format!("{} in ({cached} cached)", usage.input_tokens),
+            None => format!("{} in", usage.input_tokens),
+        });
+        parts.push(format!("{} out", usage.output_tokens));
+        if let Some(cost) = cost_usd {
+            parts.push(format!("${cost:.2}"));
+        }
+        let lines: Vec<Line<'static>> = vec![Line::from(parts.join(" · ")).dim(), "".into()];
+        HistoryCell::TaskStats {
+            view: TextBlock::new(lines),
+        }
+    }
+
     /// Create a new `PendingPatch` cell that lists the file‑level summary of
     /// a proposed patch. The summary lines should already be formatted (e.g.
     /// "A path/to/file.rs").
@@ -447,13 +564,14 @@ impl HistoryCell {
     ) -> Self {
         let title = match event_type {
             PatchEventType::ApprovalRequest => "proposed patch",
+            PatchEventType::TurnReview => "changes made this turn",
             PatchEventType::ApplyBegin {
                 auto_approved: true,
             } => "applying patch",
             PatchEventType::ApplyBegin {
                 auto_approved: false,
             } => {
-                let lines = vec![Line::from("patch applied".magenta().bold())];
+                let lines = vec![Line::from("patch applied".fg(theme().agent).bold())];
                 return Self::PendingPatch {
                     view: TextBlock::new(lines),
                 };
@@ -466,13 +584,13 @@ impl HistoryCell {
 
         // Header similar to the command formatter so patches are visually
         // distinct while still fitting the overall colour scheme.
-        lines.push(Line::from(title.magenta().bold()));
+        lines.push(Line::from(title.fg(theme().agent).bold()));
 
         for line in summary_lines {
             if line.starts_with('+') {
-                lines.push(line.green().into());
+                lines.push(line.fg(theme().success).into());
             } else if line.starts_with('-') {
-                lines.push(line.red().into());
+                lines.push(line.fg(theme().error).into());
             } else if let Some(space_idx) = line.find(' ') {
                 let kind_owned = line[..space_idx].to_string();
                 let rest_owned = line[space_idx + 1..].to_string();
@@ -480,10 +598,10 @@ impl HistoryCell {
                 let style_for = |fg: Color| Style::default().fg(fg).add_modifier(Modifier::BOLD);
 
                 let styled_kind = match kind_owned.as_str() {
-                    "A" => RtSpan::styled(kind_owned.clone(), style_for(Color::Green)),
-                    "D" => RtSpan::styled(kind_owned.clone(), style_for(Color::Red)),
-                    "M" => RtSpan::styled(kind_owned.clone(), style_for(Color::Yellow)),
-                    "R" | "C" => RtSpan::styled(kind_owned.clone(), style_for(Color::Cyan)),
+                    "A" => RtSpan::styled(kind_owned.clone(), style_for(theme().success)),
+                    "D" => RtSpan::styled(kind_owned.clone(), style_for(theme().error)),
+                    "M" => RtSpan::styled(kind_owned.clone(), style_for(theme().warning)),
+                    "R" | "C" => RtSpan::styled(kind_owned.clone(), style_for(theme().info)),
                     _ => RtSpan::raw(kind_owned.clone()),
                 };
 
@@ -509,15 +627,52 @@ impl HistoryCell {
 // the match arms.
 // ---------------------------------------------------------------------------
 
+impl HistoryCell {
+    /// Unstyled text of the cell, used for transcript search. Image output
+    /// has no text.
+    pub(crate) fn plain_text(&self) -> String {
+        match self {
+            HistoryCell::WelcomeMessage { view }
+            | HistoryCell::UserPrompt { view }
+            | HistoryCell::AgentMessage { view }
+            | HistoryCell::StreamingAgentMessage { view, .. }
+            | HistoryCell::AgentReasoning { view, .. }
+            | HistoryCell::StreamingAgentReasoning { view, .. }
+            | HistoryCell::BackgroundEvent { view }
+            | HistoryCell::ErrorEvent { view }
+            | HistoryCell::TaskStats { view }
+            | HistoryCell::SessionInfo { view }
+            | HistoryCell::CompletedExecCommand { view }
+            | HistoryCell::CompletedMcpToolCallWithTextOutput { view }
+            | HistoryCell::PendingPatch { view }
+            | HistoryCell::ActiveExecCommand { view, .. }
+            | HistoryCell::ActiveMcpToolCall { view, .. } => view.plain_text(),
+            HistoryCell::CompletedMcpToolCallWithImageOutput { .. } => String::new(),
+        }
+    }
+}
+
 impl CellWidget for HistoryCell {
     fn height(&self, width: u16) -> usize {
         match self {
             HistoryCell::WelcomeMessage { view }
             | HistoryCell::UserPrompt { view }
             | HistoryCell::AgentMessage { view }
-            | HistoryCell::AgentReasoning { view }
+            | HistoryCell::StreamingAgentMessage { view, .. }
+            | HistoryCell::AgentReasoning {
+                view,
+                expanded: true,
+                ..
+            }
+            | HistoryCell::AgentReasoning {
+                collapsed_view: view,
+                expanded: false,
+                ..
+            }
+            | HistoryCell::StreamingAgentReasoning { view, .. }
             | HistoryCell::BackgroundEvent { view }
             | HistoryCell::ErrorEvent { view }
+            | HistoryCell::TaskStats { view }
             | HistoryCell::SessionInfo { view }
             | HistoryCell::CompletedExecCommand { view }
             | HistoryCell::CompletedMcpToolCallWithTextOutput { view }
@@ -536,9 +691,21 @@ impl CellWidget for HistoryCell {
             HistoryCell::WelcomeMessage { view }
             | HistoryCell::UserPrompt { view }
             | HistoryCell::AgentMessage { view }
-            | HistoryCell::AgentReasoning { view }
+            | HistoryCell::StreamingAgentMessage { view, .. }
+            | HistoryCell::AgentReasoning {
+                view,
+                expanded: true,
+                ..
+            }
+            | HistoryCell::AgentReasoning {
+                collapsed_view: view,
+                expanded: false,
+                ..
+            }
+            | HistoryCell::StreamingAgentReasoning { view, .. }
             | HistoryCell::BackgroundEvent { view }
             | HistoryCell::ErrorEvent { view }
+            | HistoryCell::TaskStats { view }
             | HistoryCell::SessionInfo { view }
             | HistoryCell::CompletedExecCommand { view }
             | HistoryCell::CompletedMcpToolCallWithTextOutput { view }
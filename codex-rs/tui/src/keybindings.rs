@@ -0,0 +1,224 @@
+//! Key chords for TUI actions, configured through `[tui.keybindings]`.
+
+use std::fmt;
+
+use codex_core::config_types::Keybindings as KeybindingsConfig;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+
+/// A key together with the modifiers that must be held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Parse chords such as `ctrl+c`, `shift+enter`, `alt+pageup` or `y`.
+    pub(crate) fn parse(chord: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+        let Some((key, modifier_names)) = parts.split_last() else {
+            return Err(format!("empty key chord {chord:?}"));
+        };
+        // `ctrl++` binds the plus key.
+        let (key, modifier_names) = if key.is_empty() && chord.ends_with("++") {
+            ("+", &modifier_names[..modifier_names.len() - 1])
+        } else {
+            (*key, modifier_names)
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {name:?} in key chord {chord:?}")),
+            };
+        }
+
+        let code = match key.to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            lower => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                        Some(n @ 1..=12) => KeyCode::F(n),
+                        _ => return Err(format!("unknown key {key:?} in key chord {chord:?}")),
+                    },
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers).normalized())
+    }
+
+    /// Shifted characters are matched by the character itself, since
+    /// terminals differ in whether they also report the Shift modifier.
+    fn normalized(self) -> Self {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::SHIFT) => Self::new(
+                KeyCode::Char(c.to_ascii_uppercase()),
+                self.modifiers - KeyModifiers::SHIFT,
+            ),
+            _ => self,
+        }
+    }
+
+    pub(crate) fn matches(&self, key_event: &KeyEvent) -> bool {
+        *self == Self::new(key_event.code, key_event.modifiers).normalized()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.code {
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// The parsed `[tui.keybindings]` table, with defaults filled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyBindings {
+    pub(crate) submit: KeyChord,
+    pub(crate) interrupt: KeyChord,
+    pub(crate) scroll_up: KeyChord,
+    pub(crate) scroll_down: KeyChord,
+    pub(crate) toggle_diff: Option<KeyChord>,
+    pub(crate) approve: KeyChord,
+    pub(crate) reject: KeyChord,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            submit: KeyChord::new(KeyCode::Enter, KeyModifiers::NONE),
+            interrupt: KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            scroll_up: KeyChord::new(KeyCode::PageUp, KeyModifiers::NONE),
+            scroll_down: KeyChord::new(KeyCode::PageDown, KeyModifiers::NONE),
+            toggle_diff: None,
+            approve: KeyChord::new(KeyCode::Char('y'), KeyModifiers::NONE),
+            reject: KeyChord::new(KeyCode::Char('n'), KeyModifiers::NONE),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub(crate) fn from_config(config: &KeybindingsConfig) -> Result<Self, String> {
+        let parse = |chord: &Option<String>, default: KeyChord| {
+            chord.as_deref().map_or(Ok(default), KeyChord::parse)
+        };
+        let defaults = Self::default();
+        Ok(Self {
+            submit: parse(&config.submit, defaults.submit)?,
+            interrupt: parse(&config.interrupt, defaults.interrupt)?,
+            scroll_up: parse(&config.scroll_up, defaults.scroll_up)?,
+            scroll_down: parse(&config.scroll_down, defaults.scroll_down)?,
+            toggle_diff: config
+                .toggle_diff
+                .as_deref()
+                .map(KeyChord::parse)
+                .transpose()?,
+            approve: parse(&config.approve, defaults.approve)?,
+            reject: parse(&config.reject, defaults.reject)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn parses_chords() {
+        assert_eq!(
+            KeyChord::parse("ctrl+c").unwrap(),
+            KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyChord::parse("Shift+Enter").unwrap(),
+            KeyChord::new(KeyCode::Enter, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            KeyChord::parse("f5").unwrap(),
+            KeyChord::new(KeyCode::F(5), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl++").unwrap(),
+            KeyChord::new(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyChord::parse("alt+PageUp").unwrap().to_string(),
+            "alt+pageup"
+        );
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn shifted_characters_match_with_or_without_shift_reported() {
+        let chord = KeyChord::parse("shift+g").unwrap();
+        assert!(chord.matches(&KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+        assert!(chord.matches(&KeyEvent::new(KeyCode::Char('G'), KeyModifiers::NONE)));
+        assert!(!chord.matches(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn unset_actions_keep_defaults() {
+        let config = KeybindingsConfig {
+            submit: Some("ctrl+enter".to_string()),
+            ..Default::default()
+        };
+        let bindings = KeyBindings::from_config(&config).unwrap();
+        assert_eq!(
+            bindings.submit,
+            KeyChord::new(KeyCode::Enter, KeyModifiers::CONTROL)
+        );
+        assert_eq!(bindings.interrupt, KeyBindings::default().interrupt);
+    }
+}
@@ -8,7 +8,9 @@ use codex_core::config::ConfigOverrides;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::util::is_inside_git_repo;
+use keybindings::KeyBindings;
 use log_layer::TuiLogLayer;
+use replay::Replay;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
 use tracing_appender::non_blocking;
@@ -23,21 +25,39 @@ mod cell_widget;
 mod chatwidget;
 mod citation_regex;
 mod cli;
+mod clipboard_paste;
 mod conversation_history_widget;
+mod desktop_notifier;
+mod diff_view;
 mod exec_command;
+mod external_editor;
+mod file_mention;
+mod get_git_diff;
 mod git_warning_screen;
 mod history_cell;
+mod keybindings;
 mod log_layer;
 mod markdown;
+mod markdown_stream;
 mod mouse_capture;
+mod onboarding;
+mod plan_widget;
+mod replay;
+mod resume_picker;
 mod scroll_event_helper;
 mod slash_command;
 mod status_indicator_widget;
+mod status_line;
+mod syntax_highlight;
 mod text_block;
+mod theme;
 mod tui;
 mod user_approval_widget;
 
 pub use cli::Cli;
+pub use cli::ReplayTarget;
+pub use cli::ResumeTarget;
+pub use external_editor::edit_file;
 
 pub fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> std::io::Result<()> {
     let (sandbox_policy, approval_policy) = if cli.full_auto {
@@ -50,35 +70,106 @@ pub fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> std::io::
         (sandbox_policy, cli.approval_policy.map(Into::into))
     };
 
-    let config = {
-        // Load configuration and support CLI overrides.
-        let overrides = ConfigOverrides {
-            model: cli.model.clone(),
-            approval_policy,
-            sandbox_policy,
-            cwd: cli.cwd.clone().map(|p| p.canonicalize().unwrap_or(p)),
-            model_provider: None,
-            config_profile: cli.config_profile.clone(),
-            codex_linux_sandbox_exe,
-        };
-        // Parse `-c` overrides from the CLI.
-        let cli_kv_overrides = match cli.config_overrides.parse_overrides() {
-            Ok(v) => v,
-            #[allow(clippy::print_stderr)]
-            Err(e) => {
-                eprintln!("Error parsing -c overrides: {e}");
-                std::process::exit(1);
-            }
-        };
+    // Load configuration and support CLI overrides.
+    let overrides = ConfigOverrides {
+        model: cli.model.clone(),
+        approval_policy,
+        sandbox_policy,
+        cwd: cli.cwd.clone().map(|p| p.canonicalize().unwrap_or(p)),
+        model_provider: None,
+        config_profile: cli.config_profile.clone(),
+        codex_linux_sandbox_exe,
+        mode: cli.mode.map(Into::into),
+        dry_run: cli.dry_run.then_some(true),
+        focus: cli.focus.clone(),
+        offline: cli.offline.then_some(true),
+        temperature: cli.temperature,
+        seed: cli.seed,
+        event_sink: None,
+        apply_mode: None,
+    };
+    // Parse `-c` overrides from the CLI.
+    let cli_kv_overrides = match cli.config_overrides.parse_overrides() {
+        Ok(v) => v,
+        #[allow(clippy::print_stderr)]
+        Err(e) => {
+            eprintln!("Error parsing -c overrides: {e}");
+            std::process::exit(1);
+        }
+    };
+    // Loads the configuration again once the first-run setup has written
+    // config.toml.
+    let reload_config: ReloadConfig = Box::new(move || {
+        Config::load_with_cli_overrides(cli_kv_overrides.clone(), overrides.clone())
+    });
+
+    #[allow(clippy::print_stderr)]
+    let mut config = match reload_config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error loading configuration: {err}");
+            std::process::exit(1);
+        }
+    };
 
+    if let Some(ResumeTarget::Session(id)) = &cli.resume {
         #[allow(clippy::print_stderr)]
-        match Config::load_with_cli_overrides(cli_kv_overrides, overrides) {
-            Ok(config) => config,
+        match codex_core::rollout::find_rollout(&config.codex_home, id) {
+            Ok(path) => config.resume_from = Some(path),
             Err(err) => {
-                eprintln!("Error loading configuration: {err}");
+                eprintln!("Error resuming session: {err}");
                 std::process::exit(1);
             }
         }
+    }
+
+    #[allow(clippy::print_stderr)]
+    let replay = match &cli.replay {
+        Some(ReplayTarget { id, speed }) => {
+            let playback = codex_core::rollout::find_rollout(&config.codex_home, id)
+                .and_then(|path| codex_core::playback::Playback::load(&path));
+            match playback {
+                Ok(playback) => Some(Replay {
+                    playback,
+                    speed: *speed,
+                }),
+                Err(err) => {
+                    eprintln!("Error loading session to replay: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    #[allow(clippy::print_stderr)]
+    let keybindings = match KeyBindings::from_config(&config.tui.keybindings) {
+        Ok(keybindings) => keybindings,
+        Err(err) => {
+            eprintln!("Error in [tui.keybindings]: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let background_is_light =
+        theme::background_is_light(std::env::var("COLORFGBG").ok().as_deref());
+    #[allow(clippy::print_stderr)]
+    match theme::Theme::from_config(&config.tui, background_is_light) {
+        Ok(theme) => theme::init(theme),
+        Err(err) => {
+            eprintln!("Error in [tui] theme: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    #[allow(clippy::print_stderr)]
+    let (otel_layer, _otel_guard) = match codex_core::otel::otel_layer(&config) {
+        Ok(Some((layer, guard))) => (Some(layer), Some(guard)),
+        Ok(None) => (None, None),
+        Err(err) => {
+            eprintln!("Error in [otel]: {err}");
+            std::process::exit(1);
+        }
     };
 
     let log_dir = codex_core::config::log_dir(&config)?;
@@ -119,6 +210,7 @@ pub fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> std::io::
     let tui_layer = TuiLogLayer::new(log_tx.clone(), 120).with_filter(env_filter());
 
     let _ = tracing_subscriber::registry()
+        .with(otel_layer)
         .with(file_layer)
         .with(tui_layer)
         .try_init();
@@ -127,12 +219,23 @@ pub fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> std::io::
     // modal. The flag is shown when the current working directory is *not*
     // inside a Git repository **and** the user did *not* pass the
     // `--allow-no-git-exec` flag.
-    let show_git_warning = !cli.skip_git_repo_check && !is_inside_git_repo(&config);
+    let show_git_warning =
+        !cli.skip_git_repo_check && replay.is_none() && !is_inside_git_repo(&config);
 
-    try_run_ratatui_app(cli, config, show_git_warning, log_rx);
+    try_run_ratatui_app(
+        cli,
+        config,
+        reload_config,
+        keybindings,
+        show_git_warning,
+        replay,
+        log_rx,
+    );
     Ok(())
 }
 
+type ReloadConfig = Box<dyn Fn() -> std::io::Result<Config>>;
+
 #[expect(
     clippy::print_stderr,
     reason = "Resort to stderr in exceptional situations."
@@ -140,18 +243,32 @@ pub fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> std::io::
 fn try_run_ratatui_app(
     cli: Cli,
     config: Config,
+    reload_config: ReloadConfig,
+    keybindings: KeyBindings,
     show_git_warning: bool,
+    replay: Option<Replay>,
     log_rx: tokio::sync::mpsc::UnboundedReceiver<String>,
 ) {
-    if let Err(report) = run_ratatui_app(cli, config, show_git_warning, log_rx) {
+    if let Err(report) = run_ratatui_app(
+        cli,
+        config,
+        reload_config,
+        keybindings,
+        show_git_warning,
+        replay,
+        log_rx,
+    ) {
         eprintln!("Error: {report:?}");
     }
 }
 
 fn run_ratatui_app(
     cli: Cli,
-    config: Config,
+    mut config: Config,
+    reload_config: ReloadConfig,
+    keybindings: KeyBindings,
     show_git_warning: bool,
+    replay: Option<Replay>,
     mut log_rx: tokio::sync::mpsc::UnboundedReceiver<String>,
 ) -> color_eyre::Result<()> {
     color_eyre::install()?;
@@ -165,8 +282,44 @@ fn run_ratatui_app(
     let (mut terminal, mut mouse_capture) = tui::init(&config)?;
     terminal.clear()?;
 
+    if onboarding::should_run(&config.codex_home) {
+        match onboarding::run(&mut terminal, &config)? {
+            onboarding::OnboardingOutcome::Configured => {
+                let resume_from = config.resume_from.take();
+                config = reload_config()?;
+                config.resume_from = resume_from;
+            }
+            onboarding::OnboardingOutcome::Skipped => {}
+            onboarding::OnboardingOutcome::Quit => {
+                restore();
+                return Ok(());
+            }
+        }
+        terminal.clear()?;
+    }
+
+    if matches!(cli.resume, Some(ResumeTarget::Picker)) {
+        let sessions =
+            codex_core::rollout::list_sessions(&config.codex_home, resume_picker::MAX_SESSIONS)?;
+        match resume_picker::run(&mut terminal, sessions)? {
+            Some(path) => config.resume_from = Some(path),
+            None => {
+                restore();
+                return Ok(());
+            }
+        }
+        terminal.clear()?;
+    }
+
     let Cli { prompt, images, .. } = cli;
-    let mut app = App::new(config.clone(), prompt, show_git_warning, images);
+    let mut app = App::new(
+        config.clone(),
+        keybindings,
+        prompt,
+        show_git_warning,
+        images,
+        replay,
+    );
 
     // Bridge log receiver into the AppEvent channel so latest log lines update the UI.
     {
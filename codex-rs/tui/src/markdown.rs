@@ -6,13 +6,20 @@ use std::borrow::Cow;
 use std::path::Path;
 
 use crate::citation_regex::CITATION_REGEX;
+use crate::syntax_highlight::highlight_code;
 
 pub(crate) fn append_markdown(
     markdown_source: &str,
     lines: &mut Vec<Line<'static>>,
     config: &Config,
 ) {
-    append_markdown_with_opener_and_cwd(markdown_source, lines, config.file_opener, &config.cwd);
+    append_markdown_with_opener_and_cwd(
+        markdown_source,
+        lines,
+        config.file_opener,
+        &config.cwd,
+        config.tui.code_theme.as_deref(),
+    );
 }
 
 fn append_markdown_with_opener_and_cwd(
@@ -20,13 +27,31 @@ fn append_markdown_with_opener_and_cwd(
     lines: &mut Vec<Line<'static>>,
     file_opener: UriBasedFileOpener,
     cwd: &Path,
+    code_theme: Option<&str>,
 ) {
     // Perform citation rewrite *before* feeding the string to the markdown
     // renderer. When `file_opener` is absent we bypass the transformation to
     // avoid unnecessary allocations.
     let processed_markdown = rewrite_file_citations(markdown_source, file_opener, cwd);
 
-    let markdown = tui_markdown::from_str(&processed_markdown);
+    // Fenced code blocks are highlighted by us rather than `tui_markdown` so
+    // that the theme follows the user's configuration.
+    for segment in split_code_fences(&processed_markdown) {
+        match segment {
+            MarkdownSegment::Prose(text) => append_prose(text, lines),
+            MarkdownSegment::Code { lang, code } => {
+                if lines.last().is_some_and(|l| l.width() > 0) {
+                    lines.push(Line::from(""));
+                }
+                lines.extend(highlight_code(code, lang, code_theme));
+                lines.push(Line::from(""));
+            }
+        }
+    }
+}
+
+fn append_prose(markdown_source: &str, lines: &mut Vec<Line<'static>>) {
+    let markdown = tui_markdown::from_str(markdown_source);
 
     // `tui_markdown` returns a `ratatui::text::Text` where every `Line` borrows
     // from the input `message` string. Since the `HistoryCell` stores its lines
@@ -53,6 +78,78 @@ fn append_markdown_with_opener_and_cwd(
     }
 }
 
+#[derive(Debug, PartialEq)]
+enum MarkdownSegment<'a> {
+    Prose(&'a str),
+    Code { lang: &'a str, code: &'a str },
+}
+
+/// Split `src` into prose and fenced code blocks (```` ``` ```` or `~~~`).
+/// An unterminated fence runs to the end of the input, which keeps partially
+/// streamed code blocks highlighted.
+fn split_code_fences(src: &str) -> Vec<MarkdownSegment<'_>> {
+    let mut segments = Vec::new();
+    let mut prose_start = 0;
+    // (fence marker, lang, offset where the code starts)
+    let mut open: Option<(&str, &str, usize)> = None;
+    let mut offset = 0;
+
+    for line in src.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        // Up to three spaces of indentation are allowed before a fence.
+        let unindented = trimmed.trim_start_matches(' ');
+        if trimmed.len() - unindented.len() > 3 {
+            continue;
+        }
+
+        match open {
+            None => {
+                let Some(marker) = ["```", "~~~"]
+                    .into_iter()
+                    .find(|m| unindented.starts_with(m))
+                else {
+                    continue;
+                };
+                if line_start > prose_start {
+                    segments.push(MarkdownSegment::Prose(&src[prose_start..line_start]));
+                }
+                let info = unindented.trim_start_matches(&marker[..1]);
+                let lang = info.split_whitespace().next().unwrap_or("");
+                open = Some((marker, lang, offset));
+            }
+            Some((marker, lang, code_start)) => {
+                if unindented.starts_with(marker)
+                    && unindented
+                        .trim_start_matches(&marker[..1])
+                        .trim()
+                        .is_empty()
+                {
+                    segments.push(MarkdownSegment::Code {
+                        lang,
+                        code: &src[code_start..line_start],
+                    });
+                    open = None;
+                    prose_start = offset;
+                }
+            }
+        }
+    }
+
+    match open {
+        Some((_, lang, code_start)) => segments.push(MarkdownSegment::Code {
+            lang,
+            code: &src[code_start..],
+        }),
+        None if prose_start < src.len() => {
+            segments.push(MarkdownSegment::Prose(&src[prose_start..]))
+        }
+        None => {}
+    }
+    segments
+}
+
 /// Rewrites file citations in `src` into markdown hyperlinks using the
 /// provided `scheme` (`vscode`, `cursor`, etc.). The resulting URI follows the
 /// format expected by VS Code-compatible file openers:
@@ -148,7 +245,13 @@ mod tests {
         // The helper itself always rewrites – this test validates behaviour of
         // append_markdown when `file_opener` is None.
         let mut out = Vec::new();
-        append_markdown_with_opener_and_cwd(markdown, &mut out, UriBasedFileOpener::None, cwd);
+        append_markdown_with_opener_and_cwd(
+            markdown,
+            &mut out,
+            UriBasedFileOpener::None,
+            cwd,
+            None,
+        );
         // Convert lines back to string for comparison.
         let rendered: String = out
             .iter()
@@ -160,4 +263,24 @@ mod tests {
         // Ensure helper rewrites.
         assert_ne!(markdown, unchanged);
     }
+
+    #[test]
+    fn code_fences_are_split_from_prose() {
+        let src = "Intro\n\n```rust title\nfn a() {}\n```\nOutro\n~~~\nplain\n";
+        assert_eq!(
+            split_code_fences(src),
+            vec![
+                MarkdownSegment::Prose("Intro\n\n"),
+                MarkdownSegment::Code {
+                    lang: "rust",
+                    code: "fn a() {}\n",
+                },
+                MarkdownSegment::Prose("Outro\n"),
+                MarkdownSegment::Code {
+                    lang: "",
+                    code: "plain\n",
+                },
+            ]
+        );
+    }
 }
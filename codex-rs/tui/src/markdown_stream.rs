@@ -0,0 +1,93 @@
+//! Incremental rendering of an agent message while it streams in.
+//!
+//! Re-rendering the whole message on every delta would make half-written
+//! constructs (an unclosed `**`, a list marker without its text) flash with
+//! the wrong style. Instead, only complete lines are rendered as markdown, and
+//! only when a delta finishes a line; the trailing partial line is shown as
+//! plain text until its newline arrives.
+
+use codex_core::config::Config;
+use ratatui::text::Line;
+
+use crate::markdown::append_markdown;
+
+#[derive(Default)]
+pub(crate) struct MarkdownStream {
+    source: String,
+    /// Byte length of the prefix of `source` that `rendered` reflects; always
+    /// just past a newline.
+    rendered_len: usize,
+    rendered: Vec<Line<'static>>,
+}
+
+impl MarkdownStream {
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Append `delta` and re-render the complete lines if it finished any.
+    pub(crate) fn push_delta(&mut self, delta: &str, config: &Config) {
+        self.source.push_str(delta);
+        let complete_len = self.source.rfind('\n').map_or(0, |idx| idx + 1);
+        if complete_len > self.rendered_len {
+            self.rendered.clear();
+            append_markdown(&self.source[..complete_len], &mut self.rendered, config);
+            // Keep the layout stable while streaming: the blank line that
+            // closes a block is added back once the next block starts.
+            while self.rendered.last().is_some_and(|l| l.width() == 0) {
+                self.rendered.pop();
+            }
+            self.rendered_len = complete_len;
+        }
+    }
+
+    /// Lines to display right now: rendered markdown followed by the partial
+    /// last line as plain text.
+    pub(crate) fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = self.rendered.clone();
+        let tail = &self.source[self.rendered_len..];
+        if !tail.is_empty() {
+            lines.push(Line::from(tail.to_string()));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+
+    fn text_of(lines: &[Line<'_>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn renders_complete_lines_and_keeps_partial_tail_plain() {
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .unwrap();
+
+        let mut stream = MarkdownStream::default();
+        stream.push_delta("# Title\nsome **bo", &config);
+        let lines = stream.lines();
+        assert_eq!(
+            text_of(&lines).last().map(String::as_str),
+            Some("some **bo")
+        );
+
+        stream.push_delta("ld**\n", &config);
+        // Once the line is complete it is rendered as markdown.
+        let text = text_of(&stream.lines());
+        assert_eq!(text.last().map(String::as_str), Some("some bold"));
+        assert_eq!(stream.source(), "# Title\nsome **bold**\n");
+    }
+}
@@ -0,0 +1,663 @@
+//! First-run setup, shown when `CODEX_HOME/config.toml` does not exist yet:
+//! pick a model provider, sign in to it, choose how much Codex may do
+//! without asking, and write the choices to `config.toml`.
+
+use std::io::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_core::ModelProviderInfo;
+use codex_core::config::Config;
+use codex_core::copilot::format_countdown;
+use codex_core::protocol::Event;
+use codex_core::protocol::EventMsg;
+use crossterm::event::Event as TerminalEvent;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use ratatui::Frame;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Wrap;
+use tokio::sync::Notify;
+
+use crate::theme::theme;
+use crate::tui;
+
+/// Provider name that signs in with the Copilot device flow.
+const COPILOT_PROVIDER_NAME: &str = "GitHub Copilot";
+
+/// Sandbox of the two modes that run commands without asking: the same as
+/// `--full-auto`.
+const AUTO_SANDBOX_PERMISSIONS: &str =
+    r#"["disk-full-read-access", "disk-write-cwd", "disk-write-platform-user-temp-folder"]"#;
+
+/// How much Codex may do without asking, in the order offered.
+const APPROVAL_MODES: [ApprovalMode; 3] = [
+    ApprovalMode {
+        title: "Ask me first",
+        policy: "unless-allow-listed",
+        sandboxed: false,
+        description: "Codex reads files and runs read-only commands such as `ls` and `cat` on its \
+                      own. It asks before it edits a file or runs anything else.",
+    },
+    ApprovalMode {
+        title: "Work on its own in this folder",
+        policy: "on-failure",
+        sandboxed: true,
+        description: "Codex edits files and runs commands without asking, inside a sandbox that \
+                      can only write to this folder and cannot reach the network. When a command \
+                      fails in the sandbox, Codex asks before running it outside.",
+    },
+    ApprovalMode {
+        title: "Never ask",
+        policy: "never",
+        sandboxed: true,
+        description: "Like the previous mode, but Codex never asks: a command that fails in the \
+                      sandbox is reported back to the model. Meant for unattended runs.",
+    },
+];
+
+struct ApprovalMode {
+    title: &'static str,
+    /// Value of `approval_policy`.
+    policy: &'static str,
+    sandboxed: bool,
+    description: &'static str,
+}
+
+/// How the setup ended.
+pub(crate) enum OnboardingOutcome {
+    /// `config.toml` was written; the config should be loaded again.
+    Configured,
+    /// Dismissed without writing anything; asked again on the next launch.
+    Skipped,
+    Quit,
+}
+
+/// Whether this is the first launch, i.e. there is no `config.toml` yet.
+pub(crate) fn should_run(codex_home: &Path) -> bool {
+    !codex_home.join("config.toml").exists()
+}
+
+pub(crate) fn run(terminal: &mut tui::Tui, config: &Config) -> Result<OnboardingOutcome> {
+    let mut onboarding = Onboarding::new(config);
+    let (tx_event, rx_event) = async_channel::unbounded();
+    let mut cancel_sign_in: Option<Arc<Notify>> = None;
+    loop {
+        terminal.draw(|frame| onboarding.render(frame))?;
+        // Poll, so that progress of the Copilot sign-in shows up while the
+        // user waits.
+        while let Ok(event) = rx_event.try_recv() {
+            onboarding.handle_copilot_event(event);
+        }
+        if !crossterm::event::poll(Duration::from_millis(200))? {
+            continue;
+        }
+        let TerminalEvent::Key(key_event) = crossterm::event::read()? else {
+            continue;
+        };
+        match onboarding.handle_key_event(key_event) {
+            Some(Action::StartCopilotSignIn) => {
+                let config = config.clone();
+                let tx_event = tx_event.clone();
+                let cancel = Arc::new(Notify::new());
+                cancel_sign_in = Some(Arc::clone(&cancel));
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        codex_core::copilot::run_device_flow(&config, tx_event.clone(), cancel)
+                            .await
+                    {
+                        let _ = tx_event
+                            .send(complete_event(false, format!("Sign-in failed: {e}")))
+                            .await;
+                    }
+                });
+            }
+            Some(Action::CancelCopilotSignIn) => {
+                if let Some(cancel) = cancel_sign_in.take() {
+                    cancel.notify_one();
+                }
+            }
+            Some(Action::Write) => {
+                std::fs::create_dir_all(&config.codex_home)?;
+                match std::fs::write(
+                    config.codex_home.join("config.toml"),
+                    onboarding.config_toml(),
+                ) {
+                    Ok(()) => return Ok(OnboardingOutcome::Configured),
+                    Err(e) => onboarding.error = Some(format!("Could not write config.toml: {e}")),
+                }
+            }
+            Some(Action::Skip) => return Ok(OnboardingOutcome::Skipped),
+            Some(Action::Quit) => return Ok(OnboardingOutcome::Quit),
+            None => {}
+        }
+    }
+}
+
+fn complete_event(success: bool, message: String) -> Event {
+    Event {
+        id: String::new(),
+        msg: EventMsg::CopilotAuthComplete(codex_core::protocol::CopilotAuthCompleteEvent {
+            success,
+            message,
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Provider,
+    SignIn,
+    Approval,
+    Confirm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CopilotSignIn {
+    NotStarted,
+    /// Requesting a device code.
+    Starting,
+    Waiting {
+        verification_uri: String,
+        user_code: String,
+        /// Seconds until the code expires, when GitHub said.
+        seconds_remaining: Option<u64>,
+    },
+    Done {
+        success: bool,
+        message: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    StartCopilotSignIn,
+    /// Back out of the sign-in waiting for the user.
+    CancelCopilotSignIn,
+    Write,
+    Skip,
+    Quit,
+}
+
+struct Onboarding {
+    /// Built-in providers by id, OpenAI first and the rest by name.
+    providers: Vec<(String, ModelProviderInfo)>,
+    provider: usize,
+    approval: usize,
+    step: Step,
+    copilot: CopilotSignIn,
+    /// Whether a Copilot token was saved before the setup started.
+    copilot_signed_in: bool,
+    error: Option<String>,
+}
+
+impl Onboarding {
+    fn new(config: &Config) -> Self {
+        let mut providers: Vec<(String, ModelProviderInfo)> = config
+            .model_providers
+            .iter()
+            .map(|(id, info)| (id.clone(), info.clone()))
+            .collect();
+        providers.sort_by(|(a_id, a), (b_id, b)| {
+            (a_id != "openai", &a.name).cmp(&(b_id != "openai", &b.name))
+        });
+        Self {
+            providers,
+            provider: 0,
+            approval: 0,
+            step: Step::Provider,
+            copilot: CopilotSignIn::NotStarted,
+            copilot_signed_in: codex_core::copilot::load_copilot_token().is_some(),
+            error: None,
+        }
+    }
+
+    fn selected_provider(&self) -> &ModelProviderInfo {
+        &self.providers[self.provider].1
+    }
+
+    fn is_copilot(&self) -> bool {
+        self.selected_provider().name == COPILOT_PROVIDER_NAME
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<Action> {
+        if key_event.kind == KeyEventKind::Release {
+            return None;
+        }
+        if key_event.code == KeyCode::Char('c')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return Some(Action::Quit);
+        }
+        self.error = None;
+        match (self.step, key_event.code) {
+            (Step::Provider, KeyCode::Esc) => return Some(Action::Skip),
+            (Step::SignIn, KeyCode::Esc)
+                if matches!(
+                    self.copilot,
+                    CopilotSignIn::Starting | CopilotSignIn::Waiting { .. }
+                ) =>
+            {
+                self.back();
+                return Some(Action::CancelCopilotSignIn);
+            }
+            (_, KeyCode::Esc) => self.back(),
+            (Step::Provider, KeyCode::Up) => self.provider = self.provider.saturating_sub(1),
+            (Step::Provider, KeyCode::Down) => {
+                self.provider = (self.provider + 1).min(self.providers.len().saturating_sub(1));
+            }
+            (Step::Provider, KeyCode::Enter) => self.step = Step::SignIn,
+            (Step::SignIn, KeyCode::Enter) => {
+                if !self.is_copilot() || self.copilot_signed_in {
+                    self.step = Step::Approval;
+                    return None;
+                }
+                match &self.copilot {
+                    CopilotSignIn::NotStarted | CopilotSignIn::Done { success: false, .. } => {
+                        self.copilot = CopilotSignIn::Starting;
+                        return Some(Action::StartCopilotSignIn);
+                    }
+                    CopilotSignIn::Done { success: true, .. } => self.step = Step::Approval,
+                    CopilotSignIn::Starting | CopilotSignIn::Waiting { .. } => {}
+                }
+            }
+            (Step::Approval, KeyCode::Up) => self.approval = self.approval.saturating_sub(1),
+            (Step::Approval, KeyCode::Down) => {
+                self.approval = (self.approval + 1).min(APPROVAL_MODES.len() - 1);
+            }
+            (Step::Approval, KeyCode::Enter) => self.step = Step::Confirm,
+            (Step::Confirm, KeyCode::Enter) => return Some(Action::Write),
+            _ => {}
+        }
+        None
+    }
+
+    fn back(&mut self) {
+        self.step = match self.step {
+            Step::Provider | Step::SignIn => Step::Provider,
+            Step::Approval => Step::SignIn,
+            Step::Confirm => Step::Approval,
+        };
+    }
+
+    fn handle_copilot_event(&mut self, event: Event) {
+        match event.msg {
+            EventMsg::CopilotAuthStarted(started) => {
+                self.copilot = CopilotSignIn::Waiting {
+                    verification_uri: started.verification_uri,
+                    user_code: started.user_code,
+                    seconds_remaining: started.expires_in_secs,
+                };
+            }
+            EventMsg::CopilotAuthProgress(progress) => {
+                if let CopilotSignIn::Waiting {
+                    seconds_remaining, ..
+                } = &mut self.copilot
+                {
+                    *seconds_remaining = Some(progress.seconds_remaining);
+                }
+            }
+            EventMsg::CopilotAuthComplete(complete) => {
+                self.copilot_signed_in = complete.success;
+                self.copilot = CopilotSignIn::Done {
+                    success: complete.success,
+                    message: complete.message,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// The `config.toml` recording the choices.
+    fn config_toml(&self) -> String {
+        let mode = &APPROVAL_MODES[self.approval];
+        let mut toml = format!(
+            "# Written by the Codex first-run setup. See config.md for every option.\n\
+             model_provider = \"{}\"\n\
+             approval_policy = \"{}\"\n",
+            self.providers[self.provider].0, mode.policy
+        );
+        if mode.sandboxed {
+            toml.push_str(&format!(
+                "sandbox_permissions = {AUTO_SANDBOX_PERMISSIONS}\n"
+            ));
+        }
+        toml
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [title_area, body_area, hint_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+
+        let step = match self.step {
+            Step::Provider => "1/4 Model provider",
+            Step::SignIn => "2/4 Sign in",
+            Step::Approval => "3/4 Approvals",
+            Step::Confirm => "4/4 Save",
+        };
+        frame.render_widget(
+            Line::from(vec![
+                "Welcome to Codex".bold(),
+                Span::raw("  "),
+                step.fg(theme().muted),
+            ]),
+            title_area,
+        );
+
+        let mut lines = match self.step {
+            Step::Provider => self.provider_lines(),
+            Step::SignIn => self.sign_in_lines(),
+            Step::Approval => self.approval_lines(),
+            Step::Confirm => {
+                let mut lines = vec![
+                    Line::from("Press enter to save these settings to config.toml:"),
+                    Line::from(""),
+                ];
+                lines.extend(
+                    self.config_toml()
+                        .lines()
+                        .map(|line| Line::from(line.to_string().fg(theme().accent))),
+                );
+                lines
+            }
+        };
+        if let Some(error) = &self.error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(error.clone().fg(theme().error)));
+        }
+        frame.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            ),
+            body_area,
+        );
+
+        let hint = match self.step {
+            Step::Provider => "↑/↓ select · enter continue · esc skip setup",
+            Step::Approval => "↑/↓ select · enter continue · esc back",
+            Step::SignIn | Step::Confirm => "enter continue · esc back",
+        };
+        frame.render_widget(Line::from(hint.fg(theme().muted)), hint_area);
+    }
+
+    fn provider_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from("Which model provider should Codex use?"),
+            Line::from(""),
+        ];
+        for (idx, (_, provider)) in self.providers.iter().enumerate() {
+            let marker = if idx == self.provider { "› " } else { "  " };
+            let name = if idx == self.provider {
+                provider.name.clone().bold().fg(theme().accent)
+            } else {
+                Span::raw(provider.name.clone())
+            };
+            let status = match &provider.env_key {
+                _ if provider.name == COPILOT_PROVIDER_NAME => "signs in with GitHub".to_string(),
+                Some(env_key) if env_key_is_set(env_key) => format!("{env_key} is set"),
+                Some(env_key) => format!("needs {env_key}"),
+                None => "no API key needed".to_string(),
+            };
+            lines.push(Line::from(vec![
+                Span::raw(marker),
+                name,
+                format!("  {status}").fg(theme().muted),
+            ]));
+        }
+        lines
+    }
+
+    fn sign_in_lines(&self) -> Vec<Line<'static>> {
+        let provider = self.selected_provider();
+        if self.is_copilot() {
+            return match &self.copilot {
+                _ if self.copilot_signed_in && self.copilot == CopilotSignIn::NotStarted => {
+                    vec![Line::from(
+                        "You are already signed in to GitHub Copilot.".fg(theme().success),
+                    )]
+                }
+                CopilotSignIn::NotStarted => vec![Line::from(
+                    "Press enter to sign in to GitHub Copilot in your browser.",
+                )],
+                CopilotSignIn::Starting => vec![Line::from("Contacting GitHub…".fg(theme().muted))],
+                CopilotSignIn::Waiting {
+                    verification_uri,
+                    user_code,
+                    seconds_remaining,
+                } => vec![
+                    Line::from(vec![
+                        Span::raw("Open "),
+                        verification_uri.clone().fg(theme().accent),
+                        Span::raw(" and enter the code "),
+                        user_code.clone().bold(),
+                    ]),
+                    Line::from(""),
+                    Line::from(
+                        match seconds_remaining {
+                            Some(secs) => format!(
+                                "Waiting for you to authorize Codex… (the code expires in {})",
+                                format_countdown(*secs)
+                            ),
+                            None => "Waiting for you to authorize Codex…".to_string(),
+                        }
+                        .fg(theme().muted),
+                    ),
+                    Line::from("Press esc to cancel.".fg(theme().muted)),
+                ],
+                CopilotSignIn::Done {
+                    success: true,
+                    message,
+                } => vec![Line::from(message.clone().fg(theme().success))],
+                CopilotSignIn::Done {
+                    success: false,
+                    message,
+                } => vec![
+                    Line::from(message.clone().fg(theme().error)),
+                    Line::from(""),
+                    Line::from("Press enter to try again."),
+                ],
+            };
+        }
+        match &provider.env_key {
+            Some(env_key) if env_key_is_set(env_key) => vec![Line::from(
+                format!("Found your {} API key in `{env_key}`.", provider.name).fg(theme().success),
+            )],
+            Some(env_key) => {
+                let mut lines = vec![
+                    Line::from(format!(
+                        "Codex reads the {} API key from the `{env_key}` environment variable, \
+                         which is not set. Add this to your shell profile and restart Codex:",
+                        provider.name
+                    )),
+                    Line::from(""),
+                    Line::from(format!("  export {env_key}=<your key>").fg(theme().accent)),
+                ];
+                if let Some(instructions) = &provider.env_key_instructions {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(instructions.clone().fg(theme().muted)));
+                }
+                lines
+            }
+            None => vec![Line::from(format!(
+                "{} needs no API key. Make sure it is running at {}.",
+                provider.name, provider.base_url
+            ))],
+        }
+    }
+
+    fn approval_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from("How much may Codex do without asking you?"),
+            Line::from(""),
+        ];
+        for (idx, mode) in APPROVAL_MODES.iter().enumerate() {
+            if idx == self.approval {
+                lines.push(Line::from(vec![
+                    Span::raw("› "),
+                    mode.title.bold().fg(theme().accent),
+                ]));
+                lines.push(Line::from(
+                    format!("  {}", mode.description).fg(theme().muted),
+                ));
+            } else {
+                lines.push(Line::from(format!("  {}", mode.title)));
+            }
+        }
+        lines
+    }
+}
+
+fn env_key_is_set(env_key: &str) -> bool {
+    std::env::var(env_key).is_ok_and(|value| !value.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+    use codex_core::protocol::CopilotAuthProgressEvent;
+    use codex_core::protocol::CopilotAuthStartedEvent;
+    use pretty_assertions::assert_eq;
+
+    fn onboarding() -> Onboarding {
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .unwrap();
+        let mut onboarding = Onboarding::new(&config);
+        onboarding.copilot_signed_in = false;
+        onboarding
+    }
+
+    fn press(onboarding: &mut Onboarding, code: KeyCode) -> Option<Action> {
+        onboarding.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn writes_the_chosen_provider_and_approval_mode() {
+        let mut onboarding = onboarding();
+        assert_eq!(onboarding.providers[0].0, "openai");
+
+        press(&mut onboarding, KeyCode::Enter);
+        assert_eq!(onboarding.step, Step::SignIn);
+        press(&mut onboarding, KeyCode::Enter);
+        press(&mut onboarding, KeyCode::Down);
+        press(&mut onboarding, KeyCode::Enter);
+        assert_eq!(onboarding.step, Step::Confirm);
+        assert_eq!(press(&mut onboarding, KeyCode::Enter), Some(Action::Write));
+
+        assert_eq!(
+            onboarding.config_toml(),
+            "# Written by the Codex first-run setup. See config.md for every option.\n\
+             model_provider = \"openai\"\n\
+             approval_policy = \"on-failure\"\n\
+             sandbox_permissions = [\"disk-full-read-access\", \"disk-write-cwd\", \
+             \"disk-write-platform-user-temp-folder\"]\n"
+        );
+
+        // Esc walks back, and skips the setup from the first step.
+        press(&mut onboarding, KeyCode::Esc);
+        press(&mut onboarding, KeyCode::Esc);
+        press(&mut onboarding, KeyCode::Esc);
+        assert_eq!(onboarding.step, Step::Provider);
+        assert_eq!(press(&mut onboarding, KeyCode::Esc), Some(Action::Skip));
+    }
+
+    #[test]
+    fn copilot_needs_a_sign_in_before_continuing() {
+        let mut onboarding = onboarding();
+        onboarding.provider = onboarding
+            .providers
+            .iter()
+            .position(|(_, provider)| provider.name == COPILOT_PROVIDER_NAME)
+            .unwrap();
+        press(&mut onboarding, KeyCode::Enter);
+
+        assert_eq!(
+            press(&mut onboarding, KeyCode::Enter),
+            Some(Action::StartCopilotSignIn)
+        );
+        onboarding.handle_copilot_event(Event {
+            id: String::new(),
+            msg: EventMsg::CopilotAuthStarted(CopilotAuthStartedEvent {
+                verification_uri: "https://github.com/login/device".to_string(),
+                user_code: "ABCD-1234".to_string(),
+                expires_in_secs: Some(900),
+            }),
+        });
+        onboarding.handle_copilot_event(Event {
+            id: String::new(),
+            msg: EventMsg::CopilotAuthProgress(CopilotAuthProgressEvent {
+                seconds_remaining: 895,
+                interval_secs: 5,
+            }),
+        });
+        assert!(matches!(
+            onboarding.copilot,
+            CopilotSignIn::Waiting {
+                seconds_remaining: Some(895),
+                ..
+            }
+        ));
+        // Waiting for the browser: enter does nothing.
+        assert_eq!(press(&mut onboarding, KeyCode::Enter), None);
+        assert_eq!(onboarding.step, Step::SignIn);
+
+        onboarding.handle_copilot_event(complete_event(false, "User denied".to_string()));
+        assert_eq!(
+            press(&mut onboarding, KeyCode::Enter),
+            Some(Action::StartCopilotSignIn)
+        );
+        onboarding.handle_copilot_event(complete_event(true, "Signed in".to_string()));
+        press(&mut onboarding, KeyCode::Enter);
+        assert_eq!(onboarding.step, Step::Approval);
+    }
+
+    #[test]
+    fn esc_cancels_a_pending_copilot_sign_in() {
+        let mut onboarding = onboarding();
+        onboarding.provider = onboarding
+            .providers
+            .iter()
+            .position(|(_, provider)| provider.name == COPILOT_PROVIDER_NAME)
+            .unwrap();
+        press(&mut onboarding, KeyCode::Enter);
+        press(&mut onboarding, KeyCode::Enter);
+
+        assert_eq!(
+            press(&mut onboarding, KeyCode::Esc),
+            Some(Action::CancelCopilotSignIn)
+        );
+        assert_eq!(onboarding.step, Step::Provider);
+        onboarding.handle_copilot_event(complete_event(false, "cancelled".to_string()));
+        press(&mut onboarding, KeyCode::Enter);
+        assert_eq!(
+            press(&mut onboarding, KeyCode::Enter),
+            Some(Action::StartCopilotSignIn)
+        );
+    }
+}
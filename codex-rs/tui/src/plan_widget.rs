@@ -0,0 +1,138 @@
+//! Live checklist of the plan the agent published through `update_plan`,
+//! shown above the composer.
+
+use codex_core::protocol::PlanItem;
+use codex_core::protocol::StepStatus;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Padding;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::WidgetRef;
+
+use crate::theme::theme;
+
+/// Steps shown at once. Longer plans scroll to keep the current step in view.
+const MAX_VISIBLE_STEPS: usize = 8;
+
+pub(crate) struct PlanWidget {
+    plan: Vec<PlanItem>,
+}
+
+impl PlanWidget {
+    pub(crate) fn new(plan: Vec<PlanItem>) -> Self {
+        Self { plan }
+    }
+
+    /// Whether every step has been completed.
+    pub(crate) fn is_done(&self) -> bool {
+        self.plan
+            .iter()
+            .all(|item| item.status == StepStatus::Completed)
+    }
+
+    /// Preferred height in terminal rows, including the border.
+    pub(crate) fn get_height(&self) -> u16 {
+        self.plan.len().min(MAX_VISIBLE_STEPS) as u16 + 2
+    }
+
+    /// Index of the first visible step: the window is centred on the step in
+    /// progress, or on the first pending one.
+    fn first_visible(&self) -> usize {
+        let current = self
+            .plan
+            .iter()
+            .position(|item| item.status == StepStatus::InProgress)
+            .or_else(|| {
+                self.plan
+                    .iter()
+                    .position(|item| item.status == StepStatus::Pending)
+            })
+            .unwrap_or(self.plan.len().saturating_sub(1));
+        let max_first = self.plan.len().saturating_sub(MAX_VISIBLE_STEPS);
+        current.saturating_sub(MAX_VISIBLE_STEPS / 2).min(max_first)
+    }
+
+    fn step_line(item: &PlanItem) -> Line<'static> {
+        match item.status {
+            StepStatus::Completed => Line::from(vec![
+                "✔ ".fg(theme().success),
+                item.step.clone().fg(theme().muted).crossed_out(),
+            ]),
+            StepStatus::InProgress => Line::from(vec![
+                "▶ ".fg(theme().accent),
+                item.step.clone().fg(theme().text).bold(),
+            ]),
+            StepStatus::Pending => Line::from(vec![
+                "□ ".fg(theme().muted),
+                item.step.clone().fg(theme().text),
+            ]),
+        }
+    }
+}
+
+impl WidgetRef for PlanWidget {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let done = self
+            .plan
+            .iter()
+            .filter(|item| item.status == StepStatus::Completed)
+            .count();
+        let block = Block::default()
+            .title(format!(" Plan {done}/{} ", self.plan.len()))
+            .padding(Padding::new(1, 0, 0, 0))
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(theme().muted);
+        let lines: Vec<Line> = self
+            .plan
+            .iter()
+            .skip(self.first_visible())
+            .take(MAX_VISIBLE_STEPS)
+            .map(Self::step_line)
+            .collect();
+        Paragraph::new(lines).block(block).render_ref(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn plan(statuses: &[StepStatus]) -> PlanWidget {
+        PlanWidget::new(
+            statuses
+                .iter()
+                .enumerate()
+                .map(|(idx, status)| PlanItem {
+                    step: format!("step {idx}"),
+                    status: *status,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn long_plans_keep_the_current_step_in_view() {
+        use StepStatus::*;
+        let mut statuses = vec![Completed; 12];
+        statuses.extend([InProgress, Pending, Pending]);
+        let widget = plan(&statuses);
+        assert_eq!(widget.get_height(), MAX_VISIBLE_STEPS as u16 + 2);
+        assert_eq!(widget.first_visible(), 7);
+        assert!(!widget.is_done());
+
+        let widget = plan(&[InProgress, Pending]);
+        assert_eq!(widget.first_visible(), 0);
+        assert_eq!(widget.get_height(), 4);
+
+        let widget = plan(&[Completed; 10]);
+        assert_eq!(widget.first_visible(), 2);
+        assert!(widget.is_done());
+    }
+}
@@ -0,0 +1,196 @@
+//! Playback of a recorded session for `codex replay`.
+//!
+//! The steps of the session are fed to the chat widget as if the agent were
+//! emitting them, so they render exactly as they did live, one at a time on
+//! a timer the user can pause, step through and speed up.
+
+use std::time::Duration;
+
+use codex_core::playback::Playback;
+use codex_core::playback::Step;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+
+/// Time between steps at 1x.
+const STEP_DELAY: Duration = Duration::from_millis(800);
+
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 16.0;
+
+/// Lines scrolled by a page key.
+const PAGE_LINES: i32 = 10;
+
+/// A recorded session to play back.
+pub(crate) struct Replay {
+    pub(crate) playback: Playback,
+    /// Multiple of [`STEP_DELAY`] to start at.
+    pub(crate) speed: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Control {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+}
+
+/// Handle on a running playback, which takes the keys the composer would in
+/// a live session.
+pub(crate) struct Player {
+    control_tx: UnboundedSender<Control>,
+    app_event_tx: AppEventSender,
+}
+
+impl Player {
+    pub(crate) fn spawn(replay: Replay, app_event_tx: AppEventSender) -> Self {
+        let (control_tx, control_rx) = unbounded_channel();
+        let Replay { playback, speed } = replay;
+        app_event_tx.send(AppEvent::InfoMessage(format!(
+            "replaying session {} from {}",
+            playback.id, playback.timestamp
+        )));
+        tokio::spawn(play(
+            playback.steps,
+            speed.clamp(MIN_SPEED, MAX_SPEED),
+            control_rx,
+            app_event_tx.clone(),
+        ));
+        Self {
+            control_tx,
+            app_event_tx,
+        }
+    }
+
+    pub(crate) fn handle_key_event(&self, key_event: KeyEvent) {
+        let control = match key_event.code {
+            KeyCode::Char(' ') => Control::TogglePause,
+            KeyCode::Right | KeyCode::Char('n') => Control::Step,
+            KeyCode::Char('+') | KeyCode::Char('=') => Control::Faster,
+            KeyCode::Char('-') => Control::Slower,
+            _ => {
+                let event = match key_event.code {
+                    KeyCode::Up => AppEvent::Scroll(-1),
+                    KeyCode::Down => AppEvent::Scroll(1),
+                    KeyCode::PageUp => AppEvent::Scroll(-PAGE_LINES),
+                    KeyCode::PageDown => AppEvent::Scroll(PAGE_LINES),
+                    KeyCode::Char('q') | KeyCode::Esc => AppEvent::ExitRequest,
+                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        AppEvent::ExitRequest
+                    }
+                    _ => return,
+                };
+                self.app_event_tx.send(event);
+                return;
+            }
+        };
+        // The playback only stops once this handle is dropped.
+        let _ = self.control_tx.send(control);
+    }
+}
+
+async fn play(
+    steps: Vec<Step>,
+    mut speed: f64,
+    mut control_rx: UnboundedReceiver<Control>,
+    app_event_tx: AppEventSender,
+) {
+    let total = steps.len();
+    let mut steps = steps.into_iter();
+    let mut played = 0;
+    let mut paused = false;
+    while played < total {
+        app_event_tx.send(AppEvent::ReplayStatus(status(played, total, speed, paused)));
+        let control = if paused {
+            control_rx.recv().await
+        } else {
+            tokio::select! {
+                _ = tokio::time::sleep(STEP_DELAY.div_f64(speed)) => Some(Control::Step),
+                control = control_rx.recv() => control,
+            }
+        };
+        match control {
+            Some(Control::Step) => {
+                let Some(step) = steps.next() else {
+                    break;
+                };
+                played += 1;
+                app_event_tx.send(match step {
+                    Step::UserMessage(text) => AppEvent::ReplayUserMessage(text),
+                    Step::Event(event) => AppEvent::CodexEvent(event),
+                });
+            }
+            Some(Control::TogglePause) => paused = !paused,
+            Some(Control::Faster) => speed = (speed * 2.0).min(MAX_SPEED),
+            Some(Control::Slower) => speed = (speed / 2.0).max(MIN_SPEED),
+            None => return,
+        }
+    }
+    app_event_tx.send(AppEvent::ReplayStatus(format!(
+        "■ end of session ({total} steps) · ↑/↓ scroll · q quit"
+    )));
+}
+
+fn status(played: usize, total: usize, speed: f64, paused: bool) -> String {
+    let state = if paused { "⏸ paused" } else { "▶ playing" };
+    format!(
+        "{state} {played}/{total} at {speed}x · space pause · → step · +/- speed · ↑/↓ scroll · q quit"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use codex_core::protocol::Event;
+    use codex_core::protocol::EventMsg;
+    use pretty_assertions::assert_eq;
+    use std::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn steps_play_in_order_while_paused() {
+        let (tx, rx) = channel();
+        let (control_tx, control_rx) = unbounded_channel();
+        let steps = vec![
+            Step::UserMessage("hi".to_string()),
+            Step::Event(Event {
+                id: "1".to_string(),
+                msg: EventMsg::TaskStarted,
+            }),
+        ];
+        // Paused first, so only the explicit steps advance the playback.
+        for control in [Control::TogglePause, Control::Step, Control::Step] {
+            control_tx.send(control).unwrap();
+        }
+        play(steps, 1.0, control_rx, AppEventSender::new(tx)).await;
+
+        let played: Vec<String> = rx
+            .try_iter()
+            .map(|event| match event {
+                AppEvent::ReplayStatus(status) => status,
+                AppEvent::ReplayUserMessage(text) => format!("user: {text}"),
+                AppEvent::CodexEvent(event) => format!("event {}", event.id),
+                _ => "other".to_string(),
+            })
+            .map(|line| line.split(" · ").next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            played,
+            vec![
+                "▶ playing 0/2 at 1x",
+                "⏸ paused 0/2 at 1x",
+                "user: hi",
+                "⏸ paused 1/2 at 1x",
+                "event 1",
+                "■ end of session (2 steps)",
+            ]
+        );
+    }
+}
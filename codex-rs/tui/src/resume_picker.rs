@@ -0,0 +1,382 @@
+//! Full-screen list of recent sessions shown by `codex resume`, with a
+//! preview of the highlighted session's transcript.
+
+use std::collections::HashMap;
+use std::io::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use codex_core::rollout::SessionSummary;
+use codex_core::session_export::Entry;
+use codex_core::session_export::Transcript;
+use codex_core::workspace_index::fuzzy_score;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use ratatui::Frame;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::ListState;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Wrap;
+
+use crate::exec_command::relativize_to_home;
+use crate::theme::theme;
+use crate::tui;
+
+/// Number of sessions offered by the picker.
+pub(crate) const MAX_SESSIONS: usize = 200;
+
+enum PickerAction {
+    Resume(PathBuf),
+    Cancel,
+}
+
+struct ResumePicker {
+    sessions: Vec<SessionSummary>,
+    now: SystemTime,
+    query: String,
+    /// Indices into `sessions` that match `query`, best match first.
+    filtered: Vec<usize>,
+    list_state: ListState,
+    /// Rendered transcripts, keyed by index into `sessions`.
+    previews: HashMap<usize, Vec<Line<'static>>>,
+}
+
+/// Let the user pick one of `sessions`. Returns the rollout to resume, or
+/// `None` if the picker was dismissed.
+pub(crate) fn run(
+    terminal: &mut tui::Tui,
+    sessions: Vec<SessionSummary>,
+) -> Result<Option<PathBuf>> {
+    let mut picker = ResumePicker::new(sessions, SystemTime::now());
+    loop {
+        terminal.draw(|frame| picker.render(frame))?;
+        if let Event::Key(key_event) = crossterm::event::read()? {
+            match picker.handle_key_event(key_event) {
+                Some(PickerAction::Resume(path)) => return Ok(Some(path)),
+                Some(PickerAction::Cancel) => return Ok(None),
+                None => {}
+            }
+        }
+    }
+}
+
+impl ResumePicker {
+    fn new(sessions: Vec<SessionSummary>, now: SystemTime) -> Self {
+        let mut picker = Self {
+            sessions,
+            now,
+            query: String::new(),
+            filtered: Vec::new(),
+            list_state: ListState::default(),
+            previews: HashMap::new(),
+        };
+        picker.apply_filter();
+        picker
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<PickerAction> {
+        if key_event.kind == KeyEventKind::Release {
+            return None;
+        }
+        match key_event.code {
+            KeyCode::Esc => return Some(PickerAction::Cancel),
+            KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(PickerAction::Cancel);
+            }
+            KeyCode::Enter => {
+                return self
+                    .selected()
+                    .map(|idx| PickerAction::Resume(self.sessions[idx].path.clone()));
+            }
+            KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Down => {
+                if let Some(pos) = self.list_state.selected() {
+                    if pos + 1 < self.filtered.len() {
+                        self.list_state.select(Some(pos + 1));
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.apply_filter();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.apply_filter();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|pos| self.filtered.get(pos).copied())
+    }
+
+    /// Recompute the matching sessions. Without a query the sessions keep
+    /// their most-recent-first order.
+    fn apply_filter(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut scored: Vec<(i64, usize)> = self
+            .sessions
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, session)| {
+                fuzzy_score(&search_text(session), &query).map(|score| (score, idx))
+            })
+            .collect();
+        if !query.is_empty() {
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        }
+        self.filtered = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.list_state
+            .select((!self.filtered.is_empty()).then_some(0));
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [prompt_area, body_area, hint_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+        let [list_area, preview_area] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .areas(body_area);
+
+        frame.render_widget(
+            Line::from(vec![
+                "Resume a session: ".bold(),
+                Span::raw(self.query.clone()),
+                "█".fg(theme().muted),
+            ]),
+            prompt_area,
+        );
+
+        let items: Vec<ListItem> = self
+            .filtered
+            .iter()
+            .map(|&idx| ListItem::new(self.session_line(&self.sessions[idx])))
+            .collect();
+        let title = format!(
+            " {} of {} sessions ",
+            self.filtered.len(),
+            self.sessions.len()
+        );
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .highlight_style(Style::default().bg(theme().selection));
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let preview = match self.selected() {
+            Some(idx) => self.preview(idx),
+            None => vec![Line::from("No matching sessions".fg(theme().muted))],
+        };
+        frame.render_widget(
+            Paragraph::new(preview).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .title(" Preview ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            ),
+            preview_area,
+        );
+
+        frame.render_widget(
+            Line::from("type to filter · ↑/↓ select · enter resume · esc cancel".fg(theme().muted)),
+            hint_area,
+        );
+    }
+
+    fn session_line(&self, session: &SessionSummary) -> Line<'static> {
+        let age = self
+            .now
+            .duration_since(session.modified)
+            .unwrap_or_default();
+        let turns = match session.turns {
+            1 => "1 turn".to_string(),
+            n => format!("{n} turns"),
+        };
+        Line::from(vec![
+            format!("{:>8}  ", format_age(age)).fg(theme().muted),
+            format!("{turns:<9}  ").fg(theme().muted),
+            format!("{}  ", display_cwd(session)).fg(theme().accent),
+            Span::raw(single_line(description(session))),
+        ])
+    }
+
+    fn preview(&mut self, idx: usize) -> Vec<Line<'static>> {
+        let path = &self.sessions[idx].path;
+        self.previews
+            .entry(idx)
+            .or_insert_with(|| match Transcript::load(path) {
+                Ok(transcript) => preview_lines(&transcript),
+                Err(e) => vec![Line::from(
+                    format!("failed to read session: {e}").fg(theme().error),
+                )],
+            })
+            .clone()
+    }
+}
+
+fn search_text(session: &SessionSummary) -> String {
+    format!(
+        "{} {} {}",
+        display_cwd(session),
+        session.title.as_deref().unwrap_or_default(),
+        session.first_prompt.as_deref().unwrap_or_default()
+    )
+}
+
+/// The session's title, or its first prompt for sessions without one.
+fn description(session: &SessionSummary) -> &str {
+    session
+        .title
+        .as_deref()
+        .or(session.first_prompt.as_deref())
+        .unwrap_or_default()
+}
+
+fn display_cwd(session: &SessionSummary) -> String {
+    match &session.cwd {
+        Some(cwd) => match relativize_to_home(cwd) {
+            Some(rel) => format!("~/{}", rel.display()),
+            None => cwd.display().to_string(),
+        },
+        None => "?".to_string(),
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{}m ago", secs / 60),
+        3_600..86_400 => format!("{}h ago", secs / 3_600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+fn preview_lines(transcript: &Transcript) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for entry in &transcript.entries {
+        match entry {
+            Entry::User { text } => {
+                lines.push(Line::from("user".fg(theme().user).bold()));
+                lines.extend(text.lines().map(|l| Line::from(l.to_string())));
+            }
+            Entry::Agent { text } => {
+                lines.push(Line::from("codex".fg(theme().agent).bold()));
+                lines.extend(text.lines().map(|l| Line::from(l.to_string())));
+            }
+            Entry::Command { command, .. } => {
+                lines.push(Line::from(format!("$ {command}").fg(theme().muted)));
+            }
+            Entry::Patch { patch, .. } => {
+                let files = patch
+                    .lines()
+                    .filter_map(|l| l.strip_prefix("*** ").filter(|l| l.contains(" File: ")))
+                    .count();
+                lines.push(Line::from(
+                    format!("applied patch ({files} file(s))").fg(theme().muted),
+                ));
+            }
+            Entry::ToolCall { name, .. } => {
+                lines.push(Line::from(format!("tool {name}").fg(theme().muted)));
+            }
+        }
+        lines.push(Line::from(""));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn session(id: &str, cwd: &str, prompt: &str) -> SessionSummary {
+        SessionSummary {
+            path: PathBuf::from(format!("rollout-{id}.jsonl")),
+            id: id.to_string(),
+            cwd: Some(PathBuf::from(cwd)),
+            title: None,
+            first_prompt: Some(prompt.to_string()),
+            turns: 1,
+            stats: Default::default(),
+            modified: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn typing_filters_and_enter_resumes_the_best_match() {
+        let mut picker = ResumePicker::new(
+            vec![
+                session("a", "/src/web", "fix the login page"),
+                session("b", "/src/codex", "add a resume command"),
+                session("c", "/src/codex", "bump dependencies"),
+            ],
+            SystemTime::UNIX_EPOCH,
+        );
+        assert_eq!(picker.filtered, vec![0, 1, 2]);
+
+        for c in "resume".chars() {
+            assert!(picker.handle_key_event(key(KeyCode::Char(c))).is_none());
+        }
+        assert_eq!(picker.filtered, vec![1]);
+
+        match picker.handle_key_event(key(KeyCode::Enter)) {
+            Some(PickerAction::Resume(path)) => assert_eq!(path, PathBuf::from("rollout-b.jsonl")),
+            _ => panic!("expected the filtered session to be resumed"),
+        }
+
+        picker.handle_key_event(key(KeyCode::Char('z')));
+        assert!(picker.filtered.is_empty());
+        assert!(picker.handle_key_event(key(KeyCode::Enter)).is_none());
+        assert!(matches!(
+            picker.handle_key_event(key(KeyCode::Esc)),
+            Some(PickerAction::Cancel)
+        ));
+    }
+
+    #[test]
+    fn formats_age() {
+        assert_eq!(format_age(Duration::from_secs(5)), "just now");
+        assert_eq!(format_age(Duration::from_secs(5 * 60)), "5m ago");
+        assert_eq!(format_age(Duration::from_secs(3 * 3_600)), "3h ago");
+        assert_eq!(format_age(Duration::from_secs(2 * 86_400)), "2d ago");
+    }
+}
@@ -1,42 +1,550 @@
-use std::collections::HashMap;
-
-use strum::IntoEnumIterator;
-use strum_macros::AsRefStr; // derive macro
-use strum_macros::EnumIter;
-use strum_macros::EnumString;
-use strum_macros::IntoStaticStr;
-
-/// Commands that can be invoked by starting a message with a leading slash.
-#[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, EnumIter, AsRefStr, IntoStaticStr,
-)]
-#[strum(serialize_all = "kebab-case")]
-pub enum SlashCommand {
-    Clear,
-    ToggleMouseMode,
-    Quit,
-}
-
-impl SlashCommand {
+//! Slash commands that can be invoked by starting a message with a leading
+//! slash.
+//!
+//! Commands implement [`SlashCommand`] and are looked up through a
+//! [`SlashCommandRegistry`]. The built-in commands are registered by
+//! [`SlashCommandRegistry::with_built_ins`]; other modules can add their own
+//! with [`SlashCommandRegistry::register`] before the registry is handed to
+//! the composer.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use codex_common::ApprovalModeCliArg;
+use codex_common::SessionModeCliArg;
+use codex_core::config::Config;
+use codex_core::custom_prompts::CustomPrompt;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::Op;
+use codex_core::protocol::SessionMode;
+use codex_core::protocol::TokenUsage;
+use codex_core::rollout::find_rollout;
+use codex_core::session_export::ExportFormat;
+use codex_core::session_export::Transcript;
+use uuid::Uuid;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+
+/// State a command may read while it runs. Commands act by sending
+/// [`AppEvent`]s rather than by mutating the UI directly.
+pub(crate) struct SlashCommandContext<'a> {
+    pub config: &'a Config,
+    pub token_usage: &'a TokenUsage,
+    /// `None` until the agent has configured the session.
+    pub session_id: Option<Uuid>,
+    pub app_event_tx: &'a AppEventSender,
+}
+
+pub(crate) trait SlashCommand: Send + Sync {
+    /// Command string without the leading '/'.
+    fn name(&self) -> &str;
+
     /// User-visible description shown in the popup.
-    pub fn description(self) -> &'static str {
-        match self {
-            SlashCommand::Clear => "Clear the chat history.",
-            SlashCommand::ToggleMouseMode => {
-                "Toggle mouse mode (enable for scrolling, disable for text selection)"
+    fn description(&self) -> &str;
+
+    /// Run the command. `args` is the (trimmed) text that followed the
+    /// command name on the first line of the composer.
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>);
+}
+
+/// Set of commands available in the composer, keyed by name.
+#[derive(Clone, Default)]
+pub(crate) struct SlashCommandRegistry {
+    commands: BTreeMap<String, Arc<dyn SlashCommand>>,
+}
+
+impl SlashCommandRegistry {
+    /// Registry containing every built-in command.
+    pub(crate) fn with_built_ins() -> Self {
+        let mut registry = Self::default();
+        registry.register(Arc::new(AttachCommand));
+        registry.register(Arc::new(ApprovalsCommand));
+        registry.register(Arc::new(CheckpointCommand));
+        registry.register(Arc::new(ClearCommand));
+        registry.register(Arc::new(CompactCommand));
+        registry.register(Arc::new(DiffCommand));
+        registry.register(Arc::new(DryRunCommand));
+        registry.register(Arc::new(ExportCommand));
+        registry.register(Arc::new(ModeCommand));
+        registry.register(Arc::new(ModelCommand));
+        registry.register(Arc::new(QuitCommand));
+        registry.register(Arc::new(RewindCommand));
+        registry.register(Arc::new(ToggleMouseModeCommand));
+        registry.register(Arc::new(UsageCommand));
+        registry
+    }
+
+    /// Add `command`, replacing any existing command with the same name.
+    pub(crate) fn register(&mut self, command: Arc<dyn SlashCommand>) {
+        self.commands.insert(command.name().to_string(), command);
+    }
+
+    /// Expose each of the user's custom prompts as a command. Prompts never
+    /// shadow a command that is already registered.
+    pub(crate) fn register_custom_prompts(&mut self, prompts: Vec<CustomPrompt>) {
+        for prompt in prompts {
+            if !self.commands.contains_key(&prompt.name) {
+                self.register(Arc::new(PromptCommand::new(prompt)));
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<dyn SlashCommand>> {
+        self.commands.get(name).cloned()
+    }
+
+    /// Commands whose name starts with `prefix`, sorted by name.
+    pub(crate) fn matching(&self, prefix: &str) -> Vec<Arc<dyn SlashCommand>> {
+        let prefix = prefix.to_ascii_lowercase();
+        self.commands
+            .values()
+            .filter(|cmd| cmd.name().to_ascii_lowercase().starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Split the first line of the composer into the command token (without the
+/// leading '/') and its arguments. Returns `None` if the line is not a slash
+/// command.
+pub(crate) fn parse_slash_command(first_line: &str) -> Option<(&str, &str)> {
+    let stripped = first_line.strip_prefix('/')?;
+    let stripped = stripped.trim_start();
+    let (name, args) = match stripped.find(char::is_whitespace) {
+        Some(idx) => (&stripped[..idx], stripped[idx..].trim()),
+        None => (stripped, ""),
+    };
+    Some((name, args))
+}
+
+struct AttachCommand;
+
+impl SlashCommand for AttachCommand {
+    fn name(&self) -> &str {
+        "attach"
+    }
+
+    fn description(&self) -> &str {
+        "Attach an image file to the next message."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::ShowAttachImageView);
+    }
+}
+
+struct ApprovalsCommand;
+
+impl SlashCommand for ApprovalsCommand {
+    fn name(&self) -> &str {
+        "approvals"
+    }
+
+    fn description(&self) -> &str {
+        "Show or change when commands need approval."
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        if args.is_empty() {
+            let options: Vec<String> = ApprovalModeCliArg::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            ctx.app_event_tx.send(AppEvent::InfoMessage(format!(
+                "approval policy: {:?}\nusage: /approvals <{}>",
+                ctx.config.approval_policy,
+                options.join("|")
+            )));
+            return;
+        }
+
+        match ApprovalModeCliArg::from_str(args, true) {
+            Ok(mode) => {
+                let policy: AskForApproval = mode.into();
+                ctx.app_event_tx.send(AppEvent::SetApprovalPolicy(policy));
+            }
+            Err(_) => ctx.app_event_tx.send(AppEvent::InfoMessage(format!(
+                "unknown approval policy `{args}`"
+            ))),
+        }
+    }
+}
+
+struct CheckpointCommand;
+
+impl SlashCommand for CheckpointCommand {
+    fn name(&self) -> &str {
+        "checkpoint"
+    }
+
+    fn description(&self) -> &str {
+        "Mark a point in the conversation to /rewind to."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::CodexOp(Op::Checkpoint));
+    }
+}
+
+struct ClearCommand;
+
+impl SlashCommand for ClearCommand {
+    fn name(&self) -> &str {
+        "clear"
+    }
+
+    fn description(&self) -> &str {
+        "Clear the chat history."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::ClearConversation);
+    }
+}
+
+struct CompactCommand;
+
+impl SlashCommand for CompactCommand {
+    fn name(&self) -> &str {
+        "compact"
+    }
+
+    fn description(&self) -> &str {
+        "Summarize the conversation to free up context."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::CodexOp(Op::Compact));
+    }
+}
+
+struct DiffCommand;
+
+impl SlashCommand for DiffCommand {
+    fn name(&self) -> &str {
+        "diff"
+    }
+
+    fn description(&self) -> &str {
+        "Toggle the diff viewer for the working directory."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::ShowDiff);
+    }
+}
+
+struct DryRunCommand;
+
+impl SlashCommand for DryRunCommand {
+    fn name(&self) -> &str {
+        "dry-run"
+    }
+
+    fn description(&self) -> &str {
+        "Toggle dry run, where shell commands are shown but not executed."
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        let dry_run = match args {
+            "" => !ctx.config.dry_run,
+            "on" => true,
+            "off" => false,
+            _ => {
+                ctx.app_event_tx.send(AppEvent::InfoMessage(format!(
+                    "unknown argument `{args}`\nusage: /dry-run [on|off]"
+                )));
+                return;
+            }
+        };
+        ctx.app_event_tx.send(AppEvent::SetDryRun(dry_run));
+    }
+}
+
+struct ExportCommand;
+
+impl SlashCommand for ExportCommand {
+    fn name(&self) -> &str {
+        "export"
+    }
+
+    fn description(&self) -> &str {
+        "Export this session as Markdown, HTML or JSON (/export [file])."
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        let Some(session_id) = ctx.session_id else {
+            ctx.app_event_tx.send(AppEvent::InfoMessage(
+                "/export: the session has not started yet".to_string(),
+            ));
+            return;
+        };
+        let message = match export_session(session_id, args, ctx.config) {
+            Ok(path) => format!("exported session to {}", path.display()),
+            Err(e) => format!("/export: {e}"),
+        };
+        ctx.app_event_tx.send(AppEvent::InfoMessage(message));
+    }
+}
+
+/// Write the transcript to `args` (format chosen by its extension), or to
+/// `codex-session-<id>.md` in the working directory when no file is given.
+fn export_session(session_id: Uuid, args: &str, config: &Config) -> std::io::Result<PathBuf> {
+    let path = if args.is_empty() {
+        config.cwd.join(format!("codex-session-{session_id}.md"))
+    } else {
+        config.cwd.join(args)
+    };
+    let format = ExportFormat::from_path(&path).unwrap_or(ExportFormat::Markdown);
+    let rollout = find_rollout(&config.codex_home, &session_id.to_string())?;
+    let document = Transcript::load(&rollout)?.render(format);
+    std::fs::write(&path, document)?;
+    Ok(path)
+}
+
+struct ModeCommand;
+
+impl SlashCommand for ModeCommand {
+    fn name(&self) -> &str {
+        "mode"
+    }
+
+    fn description(&self) -> &str {
+        "Toggle ask mode, where the agent cannot change anything."
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        let mode = if args.is_empty() {
+            match ctx.config.mode {
+                SessionMode::Code => SessionMode::Ask,
+                SessionMode::Ask => SessionMode::Code,
+            }
+        } else {
+            match SessionModeCliArg::from_str(args, true) {
+                Ok(mode) => mode.into(),
+                Err(_) => {
+                    ctx.app_event_tx.send(AppEvent::InfoMessage(format!(
+                        "unknown mode `{args}`\nusage: /mode [code|ask]"
+                    )));
+                    return;
+                }
             }
-            SlashCommand::Quit => "Exit the application.",
+        };
+        ctx.app_event_tx.send(AppEvent::SetMode(mode));
+    }
+}
+
+struct ModelCommand;
+
+impl SlashCommand for ModelCommand {
+    fn name(&self) -> &str {
+        "model"
+    }
+
+    fn description(&self) -> &str {
+        "Show or switch the model used for the next turns."
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        if args.is_empty() {
+            ctx.app_event_tx.send(AppEvent::InfoMessage(format!(
+                "model: {} (provider: {})\nusage: /model <name>",
+                ctx.config.model, ctx.config.model_provider_id
+            )));
+        } else {
+            ctx.app_event_tx.send(AppEvent::SetModel(args.to_string()));
         }
     }
+}
+
+struct QuitCommand;
+
+impl SlashCommand for QuitCommand {
+    fn name(&self) -> &str {
+        "quit"
+    }
+
+    fn description(&self) -> &str {
+        "Exit the application."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::ExitRequest);
+    }
+}
+
+struct RewindCommand;
+
+impl SlashCommand for RewindCommand {
+    fn name(&self) -> &str {
+        "rewind"
+    }
 
-    /// Command string without the leading '/'. Provided for compatibility with
-    /// existing code that expects a method named `command()`.
-    pub fn command(self) -> &'static str {
-        self.into()
+    fn description(&self) -> &str {
+        "Restore the conversation and patched files to a checkpoint."
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        match args.parse::<usize>() {
+            Ok(checkpoint) => ctx
+                .app_event_tx
+                .send(AppEvent::CodexOp(Op::Rewind { checkpoint })),
+            Err(_) => ctx.app_event_tx.send(AppEvent::InfoMessage(
+                "usage: /rewind <checkpoint>\nA checkpoint is taken before every prompt and on /checkpoint.".to_string(),
+            )),
+        }
     }
 }
 
-/// Return all built-in commands in a HashMap keyed by their command string.
-pub fn built_in_slash_commands() -> HashMap<&'static str, SlashCommand> {
-    SlashCommand::iter().map(|c| (c.command(), c)).collect()
+struct ToggleMouseModeCommand;
+
+impl SlashCommand for ToggleMouseModeCommand {
+    fn name(&self) -> &str {
+        "toggle-mouse-mode"
+    }
+
+    fn description(&self) -> &str {
+        "Toggle mouse mode (enable for scrolling, disable for text selection)"
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx.send(AppEvent::ToggleMouseMode);
+    }
+}
+
+struct UsageCommand;
+
+impl SlashCommand for UsageCommand {
+    fn name(&self) -> &str {
+        "usage"
+    }
+
+    fn description(&self) -> &str {
+        "Show token usage for this session."
+    }
+
+    fn execute(&self, _args: &str, ctx: &SlashCommandContext<'_>) {
+        ctx.app_event_tx
+            .send(AppEvent::InfoMessage(format_token_usage(ctx.token_usage)));
+    }
+}
+
+/// A user-defined prompt from `$CODEX_HOME/prompts`. Running it expands the
+/// template and submits the result as a user message.
+struct PromptCommand {
+    prompt: CustomPrompt,
+    description: String,
+}
+
+impl PromptCommand {
+    fn new(prompt: CustomPrompt) -> Self {
+        let description = prompt.description();
+        Self {
+            prompt,
+            description,
+        }
+    }
+}
+
+impl SlashCommand for PromptCommand {
+    fn name(&self) -> &str {
+        &self.prompt.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn execute(&self, args: &str, ctx: &SlashCommandContext<'_>) {
+        match self.prompt.expand(args, &ctx.config.cwd) {
+            Ok(text) => ctx.app_event_tx.send(AppEvent::SubmitPrompt(text)),
+            Err(e) => ctx
+                .app_event_tx
+                .send(AppEvent::InfoMessage(format!("/{}: {e}", self.prompt.name))),
+        }
+    }
+}
+
+pub(crate) fn format_token_usage(usage: &TokenUsage) -> String {
+    let mut lines = vec![
+        format!("input tokens: {}", usage.input_tokens),
+        format!("output tokens: {}", usage.output_tokens),
+        format!("total tokens: {}", usage.total_tokens),
+    ];
+    if let Some(cached) = usage.cached_input_tokens {
+        lines.insert(1, format!("cached input tokens: {cached}"));
+    }
+    if let Some(reasoning) = usage.reasoning_output_tokens {
+        lines.insert(lines.len() - 1, format!("reasoning tokens: {reasoning}"));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn parses_command_and_args() {
+        assert_eq!(parse_slash_command("/model o3"), Some(("model", "o3")));
+        assert_eq!(parse_slash_command("/quit"), Some(("quit", "")));
+        assert_eq!(
+            parse_slash_command("/approvals   never  "),
+            Some(("approvals", "never"))
+        );
+        assert_eq!(parse_slash_command("hello"), None);
+    }
+
+    #[test]
+    fn registry_matches_by_prefix_and_allows_extension() {
+        struct Custom;
+        impl SlashCommand for Custom {
+            fn name(&self) -> &str {
+                "custom"
+            }
+            fn description(&self) -> &str {
+                "custom"
+            }
+            fn execute(&self, _args: &str, _ctx: &SlashCommandContext<'_>) {}
+        }
+
+        let mut registry = SlashCommandRegistry::with_built_ins();
+        let names: Vec<String> = registry
+            .matching("c")
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["checkpoint", "clear", "compact"]);
+
+        registry.register(Arc::new(Custom));
+        let names: Vec<String> = registry
+            .matching("c")
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["checkpoint", "clear", "compact", "custom"]);
+        assert!(registry.get("custom").is_some());
+    }
+
+    #[test]
+    fn custom_prompts_do_not_shadow_existing_commands() {
+        let prompt = |name: &str| CustomPrompt {
+            name: name.to_string(),
+            path: std::path::PathBuf::from(format!("{name}.md")),
+            content: format!("# {name} prompt"),
+        };
+
+        let mut registry = SlashCommandRegistry::with_built_ins();
+        registry.register_custom_prompts(vec![prompt("quit"), prompt("review")]);
+
+        let quit = registry.get("quit").unwrap();
+        assert_eq!(quit.description(), "Exit the application.");
+        let review = registry.get("review").unwrap();
+        assert_eq!(review.description(), "review prompt");
+    }
 }
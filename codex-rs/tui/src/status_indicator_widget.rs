@@ -11,7 +11,6 @@ use std::time::Duration;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Alignment;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::style::Stylize;
@@ -26,6 +25,7 @@ use ratatui::widgets::WidgetRef;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::theme::theme;
 
 use codex_ansi_escape::ansi_escape_line;
 
@@ -34,11 +34,12 @@ pub(crate) struct StatusIndicatorWidget {
     /// time).
     text: String,
 
-    /// Height in terminal rows – matches the height of the textarea at the
-    /// moment the task started so the UI does not jump when we toggle between
-    /// input mode and loading mode.
+    /// Height in terminal rows.
     height: u16,
 
+    /// Messages the user submitted while the task was running.
+    queued_messages: usize,
+
     frame_idx: Arc<AtomicUsize>,
     running: Arc<AtomicBool>,
     // Keep one sender alive to prevent the channel from closing while the
@@ -73,6 +74,7 @@ impl StatusIndicatorWidget {
         Self {
             text: String::from("waiting for logs…"),
             height: height.max(3),
+            queued_messages: 0,
             frame_idx,
             running,
             _app_event_tx: app_event_tx,
@@ -84,6 +86,10 @@ impl StatusIndicatorWidget {
         self.height
     }
 
+    pub(crate) fn set_queued_message_count(&mut self, count: usize) {
+        self.queued_messages = count;
+    }
+
     /// Update the line that is displayed in the widget.
     pub(crate) fn update_text(&mut self, text: String) {
         self.text = text.replace(['\n', '\r'], " ");
@@ -121,21 +127,21 @@ impl WidgetRef for StatusIndicatorWidget {
         header_spans.push(Span::styled(
             "Working ",
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD),
         ));
 
         header_spans.push(Span::styled(
             "[",
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD),
         ));
 
         for i in 0..DOT_COUNT {
             let style = if i == active {
                 Style::default()
-                    .fg(Color::White)
+                    .fg(theme().text)
                     .add_modifier(Modifier::BOLD)
             } else {
                 Style::default().dim()
@@ -146,10 +152,19 @@ impl WidgetRef for StatusIndicatorWidget {
         header_spans.push(Span::styled(
             "] ",
             Style::default()
-                .fg(Color::White)
+                .fg(theme().text)
                 .add_modifier(Modifier::BOLD),
         ));
 
+        header_spans.push(Span::styled("esc to interrupt ", Style::default().dim()));
+
+        if self.queued_messages > 0 {
+            header_spans.push(Span::styled(
+                format!("({} queued) ", self.queued_messages),
+                Style::default().fg(theme().warning),
+            ));
+        }
+
         // Ensure we do not overflow width.
         let inner_width = block.inner(area).width as usize;
 
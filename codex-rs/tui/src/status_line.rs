@@ -0,0 +1,160 @@
+//! One-line header above the conversation summarizing the session: model and
+//! provider, approval policy and sandbox (or ask mode), context-window fill and
+//! estimated cost.
+
+use codex_core::config::Config;
+use codex_core::openai_model_info::get_model_info;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SessionMode;
+use codex_core::protocol::TokenUsage;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+
+use crate::theme::theme;
+
+/// Context fill (in percent) from which the indicator turns red.
+const CONTEXT_WARNING_PERCENT: u64 = 80;
+
+pub(crate) struct StatusLine<'a> {
+    pub(crate) config: &'a Config,
+    /// Usage accumulated over the session, for the cost estimate.
+    pub(crate) total_usage: &'a TokenUsage,
+    /// Usage of the most recent request; its input size is the current
+    /// context size.
+    pub(crate) last_usage: Option<&'a TokenUsage>,
+}
+
+impl StatusLine<'_> {
+    fn line(&self) -> Line<'static> {
+        let separator = || " │ ".dim();
+        let mut spans: Vec<Span<'static>> = vec![
+            self.config.model.clone().bold(),
+            format!(" ({})", self.config.model_provider_id).dim(),
+            separator(),
+        ];
+        match self.config.mode {
+            // Ask mode overrides both policies, so showing them would mislead.
+            SessionMode::Ask => spans.push("mode: ask (read-only)".fg(theme().warning)),
+            SessionMode::Code => spans.extend([
+                format!("approval: {}", approval_label(self.config.approval_policy)).into(),
+                separator(),
+                format!(
+                    "sandbox: {}",
+                    sandbox_label(&self.config.sandbox_policy, self.config)
+                )
+                .into(),
+            ]),
+        }
+
+        let model_info = get_model_info(&self.config.model);
+        if let (Some(info), Some(last)) = (model_info, self.last_usage) {
+            let percent = (last.input_tokens + last.output_tokens) * 100 / info.context_window;
+            let text = format!("context: {percent}%");
+            spans.push(separator());
+            spans.push(if percent >= CONTEXT_WARNING_PERCENT {
+                text.fg(theme().error)
+            } else {
+                text.into()
+            });
+        }
+
+        spans.push(separator());
+        spans.push(format!("tokens: {}", self.total_usage.total_tokens).into());
+        if let Some(info) = model_info {
+            spans.push(separator());
+            spans.push(format!("${:.2}", info.estimate_cost(self.total_usage)).into());
+        }
+
+        Line::from(spans)
+    }
+}
+
+impl Widget for StatusLine<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(self.line()).render(area, buf);
+    }
+}
+
+fn approval_label(policy: AskForApproval) -> &'static str {
+    match policy {
+        AskForApproval::UnlessAllowListed => "unless-allow-listed",
+        AskForApproval::AutoEdit => "auto-edit",
+        AskForApproval::OnFailure => "on-failure",
+        AskForApproval::Never => "never",
+    }
+}
+
+fn sandbox_label(policy: &SandboxPolicy, config: &Config) -> String {
+    let mut label = if policy.has_full_disk_write_access() {
+        "full-access"
+    } else if !policy.get_writable_roots_with_cwd(&config.cwd).is_empty() {
+        "workspace-write"
+    } else {
+        "read-only"
+    }
+    .to_string();
+    if policy.has_full_network_access() {
+        label.push_str(" +network");
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+
+    #[test]
+    fn shows_model_policies_context_and_cost() {
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .unwrap();
+        config.model = "o3".to_string();
+        config.model_provider_id = "openai".to_string();
+        config.approval_policy = AskForApproval::OnFailure;
+        config.sandbox_policy = SandboxPolicy::new_read_only_policy();
+
+        let usage = TokenUsage {
+            input_tokens: 100_000,
+            cached_input_tokens: None,
+            output_tokens: 0,
+            reasoning_output_tokens: None,
+            total_tokens: 100_000,
+        };
+        let line = StatusLine {
+            config: &config,
+            total_usage: &usage,
+            last_usage: Some(&usage),
+        }
+        .line();
+        let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(
+            text,
+            "o3 (openai) │ approval: on-failure │ sandbox: read-only │ context: 50% │ tokens: 100000 │ $0.20"
+        );
+
+        config.mode = SessionMode::Ask;
+        let line = StatusLine {
+            config: &config,
+            total_usage: &usage,
+            last_usage: None,
+        }
+        .line();
+        let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(
+            text,
+            "o3 (openai) │ mode: ask (read-only) │ tokens: 100000 │ $0.20"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Syntax highlighting for fenced code blocks, backed by `syntect`'s bundled
+//! syntaxes and themes.
+
+use std::sync::LazyLock;
+
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::FontStyle;
+use syntect::highlighting::Theme;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::theme::theme;
+
+/// Theme used when `tui.code_theme` names an unknown theme.
+pub(crate) const DEFAULT_CODE_THEME: &str = "base16-ocean.dark";
+
+/// Value of `tui.code_theme` that turns highlighting off.
+const NO_THEME: &str = "none";
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// Resolve the configured theme name, defaulting to the one that goes with
+/// the UI theme. Returns `None` when highlighting is disabled.
+fn resolve_theme(name: Option<&str>) -> Option<&'static Theme> {
+    let name = name.unwrap_or(theme().code_theme);
+    if name.eq_ignore_ascii_case(NO_THEME) {
+        return None;
+    }
+    THEME_SET.themes.get(name).or_else(|| {
+        tracing::warn!("unknown code theme `{name}`, using `{DEFAULT_CODE_THEME}`");
+        THEME_SET.themes.get(DEFAULT_CODE_THEME)
+    })
+}
+
+/// Render `code` as one `Line` per source line, highlighted as `lang` (the
+/// first word of the fence info string: a language name such as `rust` or a
+/// file extension such as `py`). Unknown languages and a disabled theme
+/// produce unstyled lines.
+pub(crate) fn highlight_code(code: &str, lang: &str, theme: Option<&str>) -> Vec<Line<'static>> {
+    try_highlight_code(code, lang, theme).unwrap_or_else(|| {
+        code.lines()
+            .map(|l| Line::from(l.to_string()))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Like [`highlight_code`], but returns `None` instead of unstyled lines
+/// when the language is unknown or highlighting is disabled, so callers can
+/// fall back to their own styling.
+pub(crate) fn try_highlight_code(
+    code: &str,
+    lang: &str,
+    theme: Option<&str>,
+) -> Option<Vec<Line<'static>>> {
+    let theme = resolve_theme(theme)?;
+    let syntax = find_syntax(lang)?;
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut lines = Vec::new();
+    for source_line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(source_line, &SYNTAX_SET).ok()?;
+        let spans: Vec<Span<'static>> = regions
+            .into_iter()
+            .map(|(style, text)| {
+                Span::styled(
+                    text.trim_end_matches(['\n', '\r']).to_string(),
+                    convert_style(style),
+                )
+            })
+            .filter(|span| !span.content.is_empty())
+            .collect();
+        lines.push(Line::from(spans));
+    }
+    Some(lines)
+}
+
+fn find_syntax(lang: &str) -> Option<&'static syntect::parsing::SyntaxReference> {
+    let lang = lang.trim();
+    if lang.is_empty() {
+        return None;
+    }
+    SYNTAX_SET
+        .find_syntax_by_token(lang)
+        .or_else(|| SYNTAX_SET.find_syntax_by_extension(&lang.to_lowercase()))
+}
+
+/// Map a syntect style onto the terminal. The theme background is dropped so
+/// code blocks blend with the rest of the transcript.
+fn convert_style(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut out = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        out = out.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        out = out.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        out = out.add_modifier(Modifier::UNDERLINED);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_of(lines: &[Line<'_>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn highlights_known_language_and_preserves_text() {
+        let code = "fn main() {\n    println!(\"hi\");\n}\n";
+        let lines = highlight_code(code, "rust", None);
+        assert_eq!(
+            text_of(&lines),
+            vec!["fn main() {", "    println!(\"hi\");", "}"]
+        );
+        assert!(lines[0].spans.len() > 1);
+        assert!(
+            lines[0]
+                .spans
+                .iter()
+                .all(|s| matches!(s.style.fg, Some(Color::Rgb(..))))
+        );
+    }
+
+    #[test]
+    fn unknown_language_or_disabled_theme_is_plain() {
+        let plain = highlight_code("x = 1\n", "not-a-language", None);
+        assert_eq!(plain[0].spans[0].style, Style::default());
+
+        let disabled = highlight_code("x = 1\n", "py", Some("none"));
+        assert_eq!(disabled[0].spans[0].style, Style::default());
+    }
+}
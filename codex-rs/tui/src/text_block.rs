@@ -12,6 +12,20 @@ impl TextBlock {
     pub(crate) fn new(lines: Vec<Line<'static>>) -> Self {
         Self { lines }
     }
+
+    /// The text of the block without styling, one line per `Line`.
+    pub(crate) fn plain_text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 impl CellWidget for TextBlock {
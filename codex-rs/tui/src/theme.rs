@@ -0,0 +1,212 @@
+//! Color theme shared by all widgets.
+//!
+//! The theme is resolved once at startup from `tui.theme` / `tui.themes` and
+//! read through [`theme()`] wherever a widget picks a color.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use codex_core::config_types::ThemeColors;
+use codex_core::config_types::Tui;
+use ratatui::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Theme {
+    pub(crate) agent: Color,
+    pub(crate) user: Color,
+    pub(crate) success: Color,
+    pub(crate) error: Color,
+    pub(crate) warning: Color,
+    pub(crate) info: Color,
+    pub(crate) accent: Color,
+    pub(crate) selection: Color,
+    pub(crate) focus: Color,
+    pub(crate) muted: Color,
+    pub(crate) text: Color,
+    pub(crate) search_match: Color,
+    /// Syntax highlighting theme used when `tui.code_theme` is not set.
+    pub(crate) code_theme: &'static str,
+}
+
+impl Theme {
+    pub(crate) const DARK: Theme = Theme {
+        agent: Color::Magenta,
+        user: Color::Cyan,
+        success: Color::Green,
+        error: Color::Red,
+        warning: Color::Yellow,
+        info: Color::Cyan,
+        accent: Color::LightBlue,
+        selection: Color::DarkGray,
+        focus: Color::LightYellow,
+        muted: Color::DarkGray,
+        text: Color::White,
+        search_match: Color::Yellow,
+        code_theme: "base16-ocean.dark",
+    };
+
+    pub(crate) const LIGHT: Theme = Theme {
+        agent: Color::Magenta,
+        user: Color::Blue,
+        success: Color::Green,
+        error: Color::Red,
+        warning: Color::Rgb(0xaf, 0x5f, 0x00),
+        info: Color::Blue,
+        accent: Color::Blue,
+        selection: Color::Gray,
+        focus: Color::Blue,
+        muted: Color::Gray,
+        text: Color::Black,
+        search_match: Color::LightYellow,
+        code_theme: "InspiredGitHub",
+    };
+
+    pub(crate) const HIGH_CONTRAST: Theme = Theme {
+        agent: Color::LightMagenta,
+        user: Color::LightCyan,
+        success: Color::LightGreen,
+        error: Color::LightRed,
+        warning: Color::LightYellow,
+        info: Color::LightCyan,
+        accent: Color::White,
+        selection: Color::Blue,
+        focus: Color::White,
+        muted: Color::Gray,
+        text: Color::White,
+        search_match: Color::LightYellow,
+        code_theme: "base16-eighties.dark",
+    };
+
+    fn built_in(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Self::DARK),
+            "light" => Some(Self::LIGHT),
+            "high-contrast" => Some(Self::HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    /// Resolve `tui.theme` against the built-in and user-defined themes.
+    /// `background_is_light` is only consulted for `auto`.
+    pub(crate) fn from_config(tui: &Tui, background_is_light: bool) -> Result<Theme, String> {
+        let name = tui.theme.as_deref().unwrap_or("auto");
+        if name == "auto" {
+            return Ok(if background_is_light {
+                Self::LIGHT
+            } else {
+                Self::DARK
+            });
+        }
+        if let Some(theme) = Self::built_in(name) {
+            return Ok(theme);
+        }
+        match tui.themes.get(name) {
+            Some(colors) => Self::custom(name, colors),
+            None => Err(format!(
+                "unknown theme {name:?}; expected auto, dark, light, high-contrast{}",
+                custom_theme_names(&tui.themes)
+            )),
+        }
+    }
+
+    fn custom(name: &str, colors: &ThemeColors) -> Result<Theme, String> {
+        let base = colors.base.as_deref().unwrap_or("dark");
+        let mut theme = Self::built_in(base)
+            .ok_or_else(|| format!("theme {name:?}: unknown base theme {base:?}"))?;
+        for (slot, value) in [
+            (&mut theme.agent, &colors.agent),
+            (&mut theme.user, &colors.user),
+            (&mut theme.success, &colors.success),
+            (&mut theme.error, &colors.error),
+            (&mut theme.warning, &colors.warning),
+            (&mut theme.info, &colors.info),
+            (&mut theme.accent, &colors.accent),
+            (&mut theme.selection, &colors.selection),
+            (&mut theme.focus, &colors.focus),
+            (&mut theme.muted, &colors.muted),
+            (&mut theme.text, &colors.text),
+            (&mut theme.search_match, &colors.search_match),
+        ] {
+            if let Some(value) = value {
+                *slot = Color::from_str(value)
+                    .map_err(|_| format!("theme {name:?}: invalid color {value:?}"))?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+fn custom_theme_names(themes: &HashMap<String, ThemeColors>) -> String {
+    let mut names: Vec<&str> = themes.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    names.iter().map(|name| format!(", {name}")).collect()
+}
+
+/// Best-effort detection of a light terminal background from `COLORFGBG`
+/// (`"<fg>;<bg>"`, set by rxvt, Konsole, iTerm2 and others). Terminals that
+/// do not set it are assumed to be dark.
+pub(crate) fn background_is_light(colorfgbg: Option<&str>) -> bool {
+    colorfgbg
+        .and_then(|value| value.rsplit(';').next())
+        .and_then(|bg| bg.trim().parse::<u8>().ok())
+        .is_some_and(|bg| bg == 7 || bg >= 9)
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Install the theme for this process. Only the first call has an effect.
+pub(crate) fn init(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The active theme; the dark theme until [`init`] has been called.
+pub(crate) fn theme() -> &'static Theme {
+    THEME.get().unwrap_or(&Theme::DARK)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn auto_follows_terminal_background() {
+        assert!(background_is_light(Some("0;15")));
+        assert!(background_is_light(Some("0;default;7")));
+        assert!(!background_is_light(Some("15;0")));
+        assert!(!background_is_light(None));
+
+        let tui = Tui::default();
+        assert_eq!(Theme::from_config(&tui, true).unwrap(), Theme::LIGHT);
+        assert_eq!(Theme::from_config(&tui, false).unwrap(), Theme::DARK);
+    }
+
+    #[test]
+    fn custom_theme_overrides_its_base() {
+        let mut tui = Tui {
+            theme: Some("mine".to_string()),
+            ..Default::default()
+        };
+        tui.themes.insert(
+            "mine".to_string(),
+            ThemeColors {
+                base: Some("light".to_string()),
+                agent: Some("#b58900".to_string()),
+                ..Default::default()
+            },
+        );
+        let theme = Theme::from_config(&tui, false).unwrap();
+        assert_eq!(theme.agent, Color::Rgb(0xb5, 0x89, 0x00));
+        assert_eq!(theme.user, Theme::LIGHT.user);
+
+        tui.themes.get_mut("mine").unwrap().user = Some("not-a-color".to_string());
+        assert!(Theme::from_config(&tui, false).is_err());
+        tui.theme = Some("missing".to_string());
+        assert!(
+            Theme::from_config(&tui, false)
+                .unwrap_err()
+                .contains("mine")
+        );
+    }
+}
@@ -4,8 +4,14 @@ use std::io::stdout;
 
 use codex_core::config::Config;
 use crossterm::event::DisableBracketedPaste;
+use crossterm::event::DisableFocusChange;
 use crossterm::event::DisableMouseCapture;
 use crossterm::event::EnableBracketedPaste;
+use crossterm::event::EnableFocusChange;
+use crossterm::event::KeyboardEnhancementFlags;
+use crossterm::event::PopKeyboardEnhancementFlags;
+use crossterm::event::PushKeyboardEnhancementFlags;
+use crossterm::terminal::supports_keyboard_enhancement;
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::crossterm::execute;
@@ -21,11 +27,8 @@ pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
 /// Initialize the terminal
 pub fn init(config: &Config) -> Result<(Tui, MouseCapture)> {
-    execute!(stdout(), EnterAlternateScreen)?;
-    execute!(stdout(), EnableBracketedPaste)?;
+    enter()?;
     let mouse_capture = MouseCapture::new_with_capture(!config.tui.disable_mouse_capture)?;
-
-    enable_raw_mode()?;
     set_panic_hook();
     let tui = Terminal::new(CrosstermBackend::new(stdout()))?;
     Ok((tui, mouse_capture))
@@ -39,6 +42,44 @@ fn set_panic_hook() {
     }));
 }
 
+fn enter() -> Result<()> {
+    execute!(stdout(), EnterAlternateScreen)?;
+    execute!(stdout(), EnableBracketedPaste)?;
+    // Focus reports drive desktop notifications.
+    execute!(stdout(), EnableFocusChange)?;
+    // Lets terminals that support it report Shift+Enter distinctly from
+    // Enter.
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(
+            stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+    }
+    enable_raw_mode()
+}
+
+fn leave() -> Result<()> {
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    execute!(stdout(), DisableBracketedPaste)?;
+    execute!(stdout(), DisableFocusChange)?;
+    execute!(stdout(), LeaveAlternateScreen)?;
+    disable_raw_mode()
+}
+
+/// Hand the terminal to another program (e.g. `$EDITOR`). Mouse capture is
+/// left to the caller's `MouseCapture`.
+pub fn suspend() -> Result<()> {
+    leave()
+}
+
+/// Take the terminal back after [`suspend`].
+pub fn resume(terminal: &mut Tui) -> Result<()> {
+    enter()?;
+    terminal.clear()
+}
+
 /// Restore the terminal to its original state
 pub fn restore() -> Result<()> {
     // We are shutting down, and we cannot reference the `MouseCapture`, so we
@@ -47,8 +88,5 @@ pub fn restore() -> Result<()> {
         // It is possible that `DisableMouseCapture` is written more than once
         // on shutdown, so ignore the error in this case.
     }
-    execute!(stdout(), DisableBracketedPaste)?;
-    execute!(stdout(), LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    Ok(())
+    leave()
 }
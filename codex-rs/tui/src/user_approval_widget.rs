@@ -8,8 +8,10 @@
 
 use std::path::PathBuf;
 
+use codex_core::protocol::CommandRisk;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::RiskLevel;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use ratatui::buffer::Buffer;
@@ -31,6 +33,8 @@ use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::exec_command::relativize_to_home;
 use crate::exec_command::strip_bash_lc_and_escape;
+use crate::keybindings::KeyBindings;
+use crate::theme::theme;
 
 /// Request coming from the agent that needs user approval.
 pub(crate) enum ApprovalRequest {
@@ -39,6 +43,7 @@ pub(crate) enum ApprovalRequest {
         command: Vec<String>,
         cwd: PathBuf,
         reason: Option<String>,
+        risk: Option<CommandRisk>,
     },
     ApplyPatch {
         id: String,
@@ -58,7 +63,7 @@ struct SelectOption {
 // keep in same order as in the TS implementation
 const SELECT_OPTIONS: &[SelectOption] = &[
     SelectOption {
-        label: "Yes (y)",
+        label: "Yes",
         decision: Some(ReviewDecision::Approved),
 
         enters_input_mode: false,
@@ -76,7 +81,7 @@ const SELECT_OPTIONS: &[SelectOption] = &[
         enters_input_mode: true,
     },
     SelectOption {
-        label: "No, and keep going (n)",
+        label: "No, and keep going",
         decision: Some(ReviewDecision::Denied),
 
         enters_input_mode: false,
@@ -100,6 +105,7 @@ enum Mode {
 pub(crate) struct UserApprovalWidget<'a> {
     approval_request: ApprovalRequest,
     app_event_tx: AppEventSender,
+    keybindings: KeyBindings,
     confirmation_prompt: Paragraph<'a>,
 
     /// Currently selected index in *select* mode.
@@ -121,13 +127,18 @@ pub(crate) struct UserApprovalWidget<'a> {
 const BORDER_LINES: u16 = 2;
 
 impl UserApprovalWidget<'_> {
-    pub(crate) fn new(approval_request: ApprovalRequest, app_event_tx: AppEventSender) -> Self {
+    pub(crate) fn new(
+        approval_request: ApprovalRequest,
+        app_event_tx: AppEventSender,
+        keybindings: KeyBindings,
+    ) -> Self {
         let input = Input::default();
         let confirmation_prompt = match &approval_request {
             ApprovalRequest::Exec {
                 command,
                 cwd,
                 reason,
+                risk,
                 ..
             } => {
                 let cmd = strip_bash_lc_and_escape(command);
@@ -146,6 +157,15 @@ impl UserApprovalWidget<'_> {
                     ]),
                     Line::from(""),
                 ];
+                if let Some(risk) = risk {
+                    let color = match risk.level {
+                        RiskLevel::High => theme().error,
+                        RiskLevel::Medium => theme().warning,
+                        RiskLevel::Low => theme().muted,
+                    };
+                    contents.push(Line::from(risk.to_string().fg(color).bold()));
+                    contents.push(Line::from(""));
+                }
                 if let Some(reason) = reason {
                     contents.push(Line::from(reason.clone().italic()));
                     contents.push(Line::from(""));
@@ -182,6 +202,7 @@ impl UserApprovalWidget<'_> {
         Self {
             approval_request,
             app_event_tx,
+            keybindings,
             confirmation_prompt,
             selected_option: 0,
             input,
@@ -230,6 +251,14 @@ impl UserApprovalWidget<'_> {
     }
 
     fn handle_select_key(&mut self, key_event: KeyEvent) {
+        if self.keybindings.approve.matches(&key_event) {
+            self.send_decision(ReviewDecision::Approved);
+            return;
+        }
+        if self.keybindings.reject.matches(&key_event) {
+            self.send_decision(ReviewDecision::Denied);
+            return;
+        }
         match key_event.code {
             KeyCode::Up => {
                 if self.selected_option == 0 {
@@ -241,15 +270,9 @@ impl UserApprovalWidget<'_> {
             KeyCode::Down => {
                 self.selected_option = (self.selected_option + 1) % SELECT_OPTIONS.len();
             }
-            KeyCode::Char('y') => {
-                self.send_decision(ReviewDecision::Approved);
-            }
             KeyCode::Char('a') => {
                 self.send_decision(ReviewDecision::ApprovedForSession);
             }
-            KeyCode::Char('n') => {
-                self.send_decision(ReviewDecision::Denied);
-            }
             KeyCode::Char('e') => {
                 self.mode = Mode::Input;
             }
@@ -321,7 +344,6 @@ impl UserApprovalWidget<'_> {
 }
 
 const PLAIN: Style = Style::new();
-const BLUE_FG: Style = Style::new().fg(Color::Blue);
 
 impl WidgetRef for &UserApprovalWidget<'_> {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
@@ -350,11 +372,21 @@ impl WidgetRef for &UserApprovalWidget<'_> {
                 .enumerate()
                 .map(|(idx, opt)| {
                     let (prefix, style) = if idx == self.selected_option {
-                        ("▶", BLUE_FG)
+                        ("▶", Style::new().fg(theme().accent))
                     } else {
                         (" ", PLAIN)
                     };
-                    Line::styled(format!("  {prefix} {}", opt.label), style)
+                    // The shortcuts for these two are configurable.
+                    let label = match opt.decision {
+                        Some(ReviewDecision::Approved) => {
+                            format!("{} ({})", opt.label, self.keybindings.approve)
+                        }
+                        Some(ReviewDecision::Denied) => {
+                            format!("{} ({})", opt.label, self.keybindings.reject)
+                        }
+                        _ => opt.label.to_string(),
+                    };
+                    Line::styled(format!("  {prefix} {label}"), style)
                 })
                 .collect(),
             Mode::Input => {